@@ -176,6 +176,24 @@ impl AllocKind {
             Unknown => "_",
         }
     }
+
+    /// True if allocations of this kind are created directly on the major heap.
+    ///
+    /// ```rust
+    /// # alloc_data::prelude! {}
+    /// assert!(!AllocKind::Minor.is_major());
+    /// assert!(AllocKind::Major.is_major());
+    /// assert!(AllocKind::MajorPostponed.is_major());
+    /// assert!(AllocKind::Serialized.is_major());
+    /// assert!(!AllocKind::Unknown.is_major());
+    /// ```
+    pub fn is_major(&self) -> bool {
+        use AllocKind::*;
+        match self {
+            Major | MajorPostponed | Serialized => true,
+            Minor | Unknown => false,
+        }
+    }
 }
 
 /// An allocation builder.
@@ -197,6 +215,8 @@ pub struct Builder {
     pub toc: time::SinceStart,
     /// Time of death.
     pub tod: Option<time::SinceStart>,
+    /// True if some of the locations of the callstack could not be resolved.
+    pub unresolved_locs: bool,
 }
 impl Builder {
     /// Constructor.
@@ -218,6 +238,7 @@ impl Builder {
             labels,
             toc,
             tod,
+            unresolved_locs: false,
         }
     }
 
@@ -227,6 +248,12 @@ impl Builder {
         self
     }
 
+    /// Sets the unresolved-locations flag.
+    pub fn unresolved_locs(mut self, unresolved_locs: bool) -> Self {
+        self.unresolved_locs = unresolved_locs;
+        self
+    }
+
     /// Builds an `Alloc`.
     pub fn build(self, sample_rate: &SampleRate, uid: uid::Alloc) -> Res<Alloc> {
         let Self {
@@ -238,6 +265,7 @@ impl Builder {
             labels,
             toc,
             tod,
+            unresolved_locs,
         } = self;
         let real_size = sample_rate.real_size_of(nsamples);
         match uid_hint {
@@ -259,6 +287,8 @@ impl Builder {
             labels,
             toc,
             tod,
+            promoted_at: None,
+            unresolved_locs,
         })
     }
 }
@@ -284,6 +314,14 @@ pub struct Alloc {
     pub toc: time::SinceStart,
     /// Time of death.
     pub tod: Option<time::SinceStart>,
+    /// Time of promotion to the major heap, for minor allocations that were promoted.
+    #[serde(default)]
+    pub promoted_at: Option<time::SinceStart>,
+    /// True if some of the locations of the callstack could not be resolved.
+    ///
+    /// Such locations appear in the callstack as placeholders mentioning the raw location code.
+    #[serde(default)]
+    pub unresolved_locs: bool,
 }
 
 impl Alloc {
@@ -311,6 +349,8 @@ impl Alloc {
             labels,
             toc,
             tod,
+            promoted_at: None,
+            unresolved_locs: false,
         }
     }
 
@@ -320,6 +360,11 @@ impl Alloc {
         self
     }
 
+    /// True if some of the locations of the callstack could not be resolved.
+    pub fn has_unresolved_locs(&self) -> bool {
+        self.unresolved_locs
+    }
+
     /// Sets the time of death.
     ///
     /// Bails if a time of death is already registered.
@@ -336,6 +381,32 @@ impl Alloc {
         }
     }
 
+    /// Sets the time of promotion to the major heap.
+    ///
+    /// Bails if a time of promotion is already registered.
+    pub fn set_promoted_at(&mut self, promoted_at: time::SinceStart) -> Result<(), String> {
+        if self.promoted_at.is_some() {
+            Err("\
+                 trying to set the time of promotion, \
+                 but a promotion is already registered for this allocation\
+                 "
+            .into())
+        } else {
+            self.promoted_at = Some(promoted_at);
+            Ok(())
+        }
+    }
+
+    /// True if the allocation was created on, or promoted to, the major heap strictly before some
+    /// point in time.
+    pub fn is_major_at(&self, time: time::SinceStart) -> bool {
+        self.kind.is_major()
+            || self
+                .promoted_at
+                .map(|promoted_at| promoted_at < time)
+                .unwrap_or(false)
+    }
+
     /// Sets the time of creation.
     pub fn set_toc(&mut self, toc: time::SinceStart) {
         self.toc = toc
@@ -368,6 +439,20 @@ impl Alloc {
     pub fn labels(&self) -> Arc<Vec<Str>> {
         self.labels.get()
     }
+    /// Shared trace accessor, cheaper than [`trace`](#method.trace) when comparing traces.
+    pub fn shared_trace(&self) -> Trace {
+        self.trace
+    }
+    /// Shared labels accessor, cheaper than [`labels`](#method.labels) when comparing labels.
+    pub fn shared_labels(&self) -> Labels {
+        self.labels
+    }
+    /// Overwrites the labels.
+    ///
+    /// Labels can change after the allocation is built when users tag it.
+    pub fn set_labels(&mut self, labels: Labels) {
+        self.labels = labels
+    }
     /// Time of creation accessor.
     pub fn toc(&self) -> time::SinceStart {
         self.toc
@@ -415,6 +500,12 @@ pub struct Init {
     pub callstack_is_rev: bool,
     /// Sampling rate.
     pub sample_rate: base::SampleRate,
+    /// Format of the trace the data comes from, if known.
+    #[serde(default)]
+    pub trace_format: Option<TraceFormat>,
+    /// Information about the run the trace comes from, if the trace has any.
+    #[serde(default)]
+    pub trace_info: Option<TraceInfo>,
 }
 
 impl Default for Init {
@@ -425,6 +516,8 @@ impl Default for Init {
             word_size: 8,
             callstack_is_rev: false,
             sample_rate: SampleRate::new(1.0, 8),
+            trace_format: None,
+            trace_info: None,
         }
     }
 }
@@ -443,6 +536,8 @@ impl Init {
             word_size,
             callstack_is_rev,
             sample_rate: SampleRate::new(1.0, convert(word_size, "Init::new, word_size")),
+            trace_format: None,
+            trace_info: None,
         }
     }
 
@@ -454,4 +549,97 @@ impl Init {
         );
         self
     }
+
+    /// Sets the trace format.
+    pub fn trace_format(mut self, trace_format: TraceFormat) -> Self {
+        self.trace_format = Some(trace_format);
+        self
+    }
+
+    /// Sets the trace information.
+    pub fn trace_info(mut self, trace_info: TraceInfo) -> Self {
+        self.trace_info = Some(trace_info);
+        self
+    }
+}
+
+/// Information about the run a trace comes from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TraceInfo {
+    /// Name of the executable.
+    pub exe_name: String,
+    /// Parameters of the executable.
+    pub exe_params: String,
+    /// Name of the host system.
+    pub host_name: String,
+    /// PID of the process.
+    pub pid: u64,
+    /// Sample rate, the probability for each word allocated to be sampled.
+    pub sample_rate: f64,
+    /// Size of machine words in bits.
+    pub word_size: u8,
+}
+
+impl TraceInfo {
+    /// Factor applied to the sampled sizes to estimate the real ones, `1 / sample_rate`.
+    ///
+    /// ```rust
+    /// # use alloc_data::TraceInfo;
+    /// let info = TraceInfo {
+    ///     exe_name: "main.exe".into(),
+    ///     exe_params: "".into(),
+    ///     host_name: "localhost".into(),
+    ///     pid: 42,
+    ///     sample_rate: 0.001,
+    ///     word_size: 64,
+    /// };
+    /// assert_eq!(info.scaling_factor(), 1000.);
+    /// ```
+    pub fn scaling_factor(&self) -> f64 {
+        1. / self.sample_rate
+    }
+}
+
+/// Format of a trace, and what memthol can do with it.
+///
+/// Built by the trace parser from its schema for the version of the trace, so that the UI can show
+/// which features the trace cannot support.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceFormat {
+    /// Name of the format.
+    pub name: String,
+    /// Version of the format.
+    pub version: u16,
+    /// Kinds of events the format can contain.
+    pub events: Vec<String>,
+    /// Quirks of the format.
+    pub quirks: Vec<String>,
+    /// Features memthol derives from the format.
+    pub features: Vec<FormatFeature>,
+    /// Context of the run, if the format has one.
+    pub context: Option<String>,
+}
+
+impl std::fmt::Display for TraceFormat {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{} v{}", self.name, self.version)
+    }
+}
+
+/// A memthol feature that depends on the format of the trace.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatFeature {
+    /// Name of the feature.
+    pub name: String,
+    /// Description of the feature.
+    pub desc: String,
+    /// Reason why the feature is unavailable, if it is.
+    pub unavailable: Option<String>,
+}
+
+impl FormatFeature {
+    /// True if the feature is available.
+    pub fn is_available(&self) -> bool {
+        self.unavailable.is_none()
+    }
 }
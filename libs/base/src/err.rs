@@ -44,10 +44,27 @@ error_chain::error_chain! {
     }
 
     links {}
-    errors {}
+    errors {
+        /// Access to an allocation that was pruned.
+        PrunedAlloc(uid: crate::uid::Alloc) {
+            description("access to a pruned allocation")
+            display("allocation #{} was pruned", uid)
+        }
+    }
 }
 
 impl Error {
+    /// True if the error is an access to a pruned allocation.
+    ///
+    /// Pruned allocations are accounted for in aggregates, so accessing one is usually not a
+    /// failure.
+    pub fn is_pruned_alloc(&self) -> bool {
+        match self.kind() {
+            ErrorKind::PrunedAlloc(_) => true,
+            _ => false,
+        }
+    }
+
     /// Multi-line representation of a trace of errors.
     ///
     /// See the [module-level documentation] for more.
@@ -133,6 +133,46 @@ pub trait DurationExt: From<Duration> {
         Ok(duration.into())
     }
 
+    /// Parses a human-readable duration, the way [`fmt_human`] formats durations.
+    ///
+    /// A duration is a sequence of amounts, each followed by a unit: `h`, `m` (or `min`), `s`,
+    /// `ms`, `us` (or `µs`) and `ns`. Units go from the largest to the smallest, at most once
+    /// each, and only the last amount can have decimals. Amounts without a unit are ambiguous and
+    /// rejected, except for `0`, and so are negative amounts.
+    ///
+    /// ```rust
+    /// use base::prelude::time::{Duration, DurationExt, SinceStart};
+    /// let ok = vec![
+    ///     ("1.5s", Duration::new(1, 500_000_000)),
+    ///     ("250ms", Duration::new(0, 250_000_000)),
+    ///     ("2m30s", Duration::new(150, 0)),
+    ///     ("1h", Duration::new(3600, 0)),
+    ///     ("1h 2min 3.25s", Duration::new(3723, 250_000_000)),
+    ///     ("12.5µs", Duration::new(0, 12_500)),
+    ///     ("0", Duration::new(0, 0)),
+    /// ];
+    /// for (s, exp) in ok {
+    ///     assert_eq!(*SinceStart::parse_human(s).unwrap(), exp)
+    /// }
+    /// let ko = vec![
+    ///     "", "30", "1.5", "-1s", "1.5m30s", "30s2m", "1s1s", "2 parsecs", "1.0000000001s",
+    /// ];
+    /// for s in ko {
+    ///     assert!(SinceStart::parse_human(s).is_err(), "parsed `{}`", s)
+    /// }
+    /// ```
+    ///
+    /// [`fmt_human`]: #method.fmt_human (The fmt_human method)
+    fn parse_human<Str>(s: &Str) -> Res<Self>
+    where
+        Str: ?Sized + AsRef<str>,
+    {
+        let s = s.as_ref();
+        parse_human(s)
+            .map(Self::from)
+            .chain_err(|| format!("while parsing duration `{}`", s))
+    }
+
     /// Pretty displayable version of a duration, millisecond precision.
     fn display_millis<'me>(&'me self) -> DurationDisplay<'me, Self, Millis> {
         self.into()
@@ -145,6 +185,183 @@ pub trait DurationExt: From<Duration> {
     fn display_nanos<'me>(&'me self) -> DurationDisplay<'me, Self, Nanos> {
         self.into()
     }
+    /// Pretty displayable version of a duration, in the largest unit below it.
+    ///
+    /// Three significant digits, except for nanoseconds, minutes and hours.
+    ///
+    /// ```rust
+    /// use base::prelude::time::{Duration, DurationExt};
+    /// let list = vec![
+    ///     (Duration::new(0, 0), "0ns"),
+    ///     (Duration::new(0, 750), "750ns"),
+    ///     (Duration::new(0, 12_340), "12.3µs"),
+    ///     (Duration::new(0, 1_500_000), "1.50ms"),
+    ///     (Duration::new(42, 0), "42.0s"),
+    ///     (Duration::new(90, 0), "1.50min"),
+    ///     (Duration::new(2 * 3600 + 360, 0), "2.10h"),
+    /// ];
+    /// for (duration, exp) in list {
+    ///     assert_eq!(duration.display_scaled().to_string(), exp)
+    /// }
+    /// ```
+    fn display_scaled<'me>(&'me self) -> DurationDisplay<'me, Self, Scaled> {
+        self.into()
+    }
+    /// Human-readable version of a duration, that [`parse_human`] parses back.
+    ///
+    /// Exact, in the largest unit below the duration, or in hours, minutes and seconds above a
+    /// minute.
+    ///
+    /// ```rust
+    /// use base::prelude::time::{Duration, DurationExt};
+    /// let list = vec![
+    ///     (Duration::new(0, 0), "0s"),
+    ///     (Duration::new(0, 750), "750ns"),
+    ///     (Duration::new(0, 12_340), "12.34µs"),
+    ///     (Duration::new(0, 1_500_000), "1.5ms"),
+    ///     (Duration::new(42, 0), "42s"),
+    ///     (Duration::new(150, 0), "2m30s"),
+    ///     (Duration::new(3600, 0), "1h"),
+    ///     (Duration::new(3723, 250_000_000), "1h2m3.25s"),
+    /// ];
+    /// for (duration, exp) in list {
+    ///     assert_eq!(duration.fmt_human().to_string(), exp);
+    ///     assert_eq!(Duration::parse_human(exp).unwrap(), duration);
+    /// }
+    /// ```
+    ///
+    /// [`parse_human`]: #method.parse_human (The parse_human method)
+    fn fmt_human<'me>(&'me self) -> DurationDisplay<'me, Self, Human> {
+        self.into()
+    }
+}
+
+/// Nanoseconds in an hour.
+const HOUR_NANOS: u128 = 3_600_000_000_000;
+/// Nanoseconds in a minute.
+const MINUTE_NANOS: u128 = 60_000_000_000;
+/// Nanoseconds in a second.
+const SECOND_NANOS: u128 = 1_000_000_000;
+
+/// Units of human-readable durations, with their length in nanoseconds.
+const HUMAN_UNITS: [(&str, u128); 8] = [
+    ("h", HOUR_NANOS),
+    ("m", MINUTE_NANOS),
+    ("min", MINUTE_NANOS),
+    ("s", SECOND_NANOS),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("µs", 1_000),
+    ("ns", 1),
+];
+
+/// Parses a human-readable duration, see [`DurationExt::parse_human`].
+///
+/// [`DurationExt::parse_human`]: trait.DurationExt.html#method.parse_human
+/// (DurationExt::parse_human)
+fn parse_human(s: &str) -> Res<Duration> {
+    let s = s.trim();
+    if s.is_empty() {
+        bail!("expected a duration, found nothing")
+    } else if s == "0" {
+        return Ok(Duration::new(0, 0));
+    } else if s.starts_with('-') {
+        bail!("durations cannot be negative")
+    }
+
+    let mut nanos: u128 = 0;
+    // Last unit and whether its amount had decimals.
+    let mut last: Option<(&str, u128, bool)> = None;
+    let mut rest = s;
+
+    while !rest.is_empty() {
+        let amount_len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let amount = &rest[..amount_len];
+        rest = rest[amount_len..].trim_start();
+        let unit_len = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = &rest[..unit_len];
+        rest = rest[unit_len..].trim_start();
+
+        if amount.is_empty() {
+            match (unit, rest.chars().next()) {
+                ("", Some('-')) => bail!("durations cannot be negative"),
+                ("", Some(c)) => bail!("unexpected character `{}`", c),
+                ("", None) => unreachable!("`rest` is not empty"),
+                (unit, _) => bail!("expected an amount before unit `{}`", unit),
+            }
+        }
+        if unit.is_empty() {
+            bail!(
+                "missing unit after `{}`, expected one of h, m, s, ms, µs or ns",
+                amount
+            )
+        }
+        let unit_nanos = HUMAN_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, nanos)| *nanos)
+            .ok_or_else(|| {
+                format!(
+                    "unknown unit `{}`, expected one of h, m, s, ms, µs or ns",
+                    unit
+                )
+            })?;
+        if let Some((last_unit, last_nanos, decimals)) = last {
+            if unit_nanos >= last_nanos {
+                bail!(
+                    "unit `{}` cannot come after unit `{}`, \
+                    units must go from the largest to the smallest",
+                    unit,
+                    last_unit
+                )
+            } else if decimals {
+                bail!(
+                    "only the last amount can have decimals, found `{}{}` after it",
+                    amount,
+                    unit
+                )
+            }
+        }
+
+        let mut parts = amount.split('.');
+        let (int, frac) = (parts.next().unwrap_or(""), parts.next());
+        if parts.next().is_some() {
+            bail!("found more than one `.` character in `{}`", amount)
+        } else if int.is_empty() && frac.map(str::is_empty).unwrap_or(true) {
+            bail!("expected an amount before unit `{}`", unit)
+        }
+        let too_large = || format!("`{}{}` is too large", amount, unit);
+        let int = if int.is_empty() {
+            0
+        } else {
+            u128::from_str(int).map_err(|_| too_large())?
+        };
+        let mut amount_nanos = int.checked_mul(unit_nanos).ok_or_else(too_large)?;
+        if let Some(frac) = frac.filter(|frac| !frac.is_empty()) {
+            let too_precise = || format!("`{}{}` is more precise than a nanosecond", amount, unit);
+            if frac.len() > 18 {
+                bail!(too_precise())
+            }
+            let den = 10u128.pow(frac.len() as u32);
+            let num = u128::from_str(frac).map_err(|_| too_large())? * unit_nanos;
+            if num % den != 0 {
+                bail!(too_precise())
+            }
+            amount_nanos += num / den
+        }
+        nanos = nanos.checked_add(amount_nanos).ok_or_else(too_large)?;
+        last = Some((unit, unit_nanos, frac.is_some()))
+    }
+
+    let secs = nanos / SECOND_NANOS;
+    if secs > u64::MAX as u128 {
+        bail!("duration is too large")
+    }
+    Ok(Duration::new(secs as u64, (nanos % SECOND_NANOS) as u32))
 }
 
 impl DurationExt for Duration {
@@ -201,6 +418,96 @@ impl TimePrecision for Millis {
     }
 }
 
+/// Precision depending on the duration itself, see [`DurationExt::display_scaled`].
+///
+/// [`DurationExt::display_scaled`]: trait.DurationExt.html#method.display_scaled
+/// (DurationExt::display_scaled)
+pub struct Scaled;
+impl TimePrecision for Scaled {
+    fn duration_fmt(duration: &Duration, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let nanos = duration.as_nanos();
+        if nanos < 1_000 {
+            return write!(fmt, "{}ns", nanos);
+        }
+        let secs = duration.as_secs_f64();
+        let (val, unit) = if nanos < 1_000_000 {
+            (secs * 1e6, "µs")
+        } else if nanos < 1_000_000_000 {
+            (secs * 1e3, "ms")
+        } else if secs < 60. {
+            (secs, "s")
+        } else if secs < 3600. {
+            (secs / 60., "min")
+        } else {
+            (secs / 3600., "h")
+        };
+        let decimals = if val < 10. {
+            2
+        } else if val < 100. {
+            1
+        } else {
+            0
+        };
+        write!(fmt, "{:.*}{}", decimals, val, unit)
+    }
+}
+
+/// Human-readable precision, see [`DurationExt::fmt_human`].
+///
+/// [`DurationExt::fmt_human`]: trait.DurationExt.html#method.fmt_human
+/// (DurationExt::fmt_human)
+pub struct Human;
+impl Human {
+    /// Writes `nanos` in some unit exactly, without trailing zeros.
+    fn write_exact(
+        fmt: &mut fmt::Formatter,
+        nanos: u128,
+        unit_nanos: u128,
+        unit: &str,
+    ) -> fmt::Result {
+        let (int, mut frac) = (nanos / unit_nanos, nanos % unit_nanos);
+        write!(fmt, "{}", int)?;
+        if frac > 0 {
+            let mut digits = unit_nanos.to_string().len() - 1;
+            while frac % 10 == 0 {
+                frac /= 10;
+                digits -= 1
+            }
+            write!(fmt, ".{:0>width$}", frac, width = digits)?
+        }
+        write!(fmt, "{}", unit)
+    }
+}
+impl TimePrecision for Human {
+    fn duration_fmt(duration: &Duration, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let nanos = duration.as_nanos();
+        if nanos == 0 {
+            write!(fmt, "0s")
+        } else if nanos < 1_000 {
+            Self::write_exact(fmt, nanos, 1, "ns")
+        } else if nanos < 1_000_000 {
+            Self::write_exact(fmt, nanos, 1_000, "µs")
+        } else if nanos < SECOND_NANOS {
+            Self::write_exact(fmt, nanos, 1_000_000, "ms")
+        } else if nanos < MINUTE_NANOS {
+            Self::write_exact(fmt, nanos, SECOND_NANOS, "s")
+        } else {
+            let (hours, nanos) = (nanos / HOUR_NANOS, nanos % HOUR_NANOS);
+            let (minutes, nanos) = (nanos / MINUTE_NANOS, nanos % MINUTE_NANOS);
+            if hours > 0 {
+                write!(fmt, "{}h", hours)?
+            }
+            if minutes > 0 {
+                write!(fmt, "{}m", minutes)?
+            }
+            if nanos > 0 {
+                Self::write_exact(fmt, nanos, SECOND_NANOS, "s")?
+            }
+            Ok(())
+        }
+    }
+}
+
 /// Thin wrapper around a reference to a duration.
 pub struct DurationDisplay<'a, T: DurationExt + ?Sized, Precision: TimePrecision> {
     /// The actual duration.
@@ -233,8 +540,64 @@ impl<'a, T: DurationExt + ?Sized> From<&'a T> for DurationDisplay<'a, T, Millis>
         }
     }
 }
+impl<'a, T: DurationExt + ?Sized> From<&'a T> for DurationDisplay<'a, T, Scaled> {
+    fn from(duration: &'a T) -> Self {
+        Self {
+            duration,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+impl<'a, T: DurationExt + ?Sized> From<&'a T> for DurationDisplay<'a, T, Human> {
+    fn from(duration: &'a T) -> Self {
+        Self {
+            duration,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
 impl<T: DurationExt, Precision: TimePrecision> fmt::Display for DurationDisplay<'_, T, Precision> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         Precision::duration_fmt(self.duration.as_duration(), fmt)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    #[test]
+    fn human_format_parses_back() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        for _ in 0..10_000 {
+            // Durations of all magnitudes, from nanoseconds to days.
+            let magnitude = rng.gen_range(0, 18);
+            let nanos = rng.gen_range(0, 10u64.pow(magnitude)) * rng.gen_range(1, 10);
+            // Round values, such as `2m30s`, matter as much as arbitrary ones.
+            let round = 10u64.pow(rng.gen_range(0, 13));
+            let duration = Duration::from_nanos(nanos / round * round);
+
+            let human = duration.fmt_human().to_string();
+            let parsed = Duration::parse_human(&human)
+                .unwrap_or_else(|e| panic!("failed to parse `{}`: {}", human, e.to_pretty()));
+            assert_eq!(parsed, duration, "`{}` does not parse back", human);
+            assert_eq!(parsed.fmt_human().to_string(), human);
+        }
+    }
+
+    #[test]
+    fn human_parse_errors() {
+        let err = |s: &str| Duration::parse_human(s).unwrap_err().to_pretty();
+        assert!(err("30").contains("missing unit after `30`"));
+        assert!(err("-2s").contains("cannot be negative"));
+        assert!(err("1m -2s").contains("cannot be negative"));
+        assert!(err("3s2m").contains("unit `m` cannot come after unit `s`"));
+        assert!(err("1m2min").contains("unit `min` cannot come after unit `m`"));
+        assert!(err("1.5m3s").contains("only the last amount can have decimals"));
+        assert!(err("3 weeks").contains("unknown unit `weeks`"));
+        assert!(err("0.5ns").contains("more precise than a nanosecond"));
+        assert!(err("1.2.3s").contains("more than one `.`"));
+        assert!(err("99999999999999999999999999999999999999999h").contains("too large"));
+    }
+}
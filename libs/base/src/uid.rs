@@ -22,16 +22,18 @@
 //! All UID types implement serialize and deserialize.
 //!
 //! Types [`Chart`], [`Filter`] and [`SubFilter`] are the straightforward UIDs. This module also has
-//! a [`Line`] type which augments [`Filter`] with two additional variants:
+//! a [`Line`] type which augments [`Filter`] with three additional variants:
 //!
 //! - the "catch-all filter", which is the filter that catches everything the other filters do not
 //!   catch;
 //! - the "everything filter", which is the filter that catches **all** allocations, independently
-//!   of the user-defined filters.
+//!   of the user-defined filters;
+//! - [`Segment`] lines, for charts that group allocations by trace segment instead of filters.
 //!
 //! [`Chart`]: struct.Chart.html (The Chart struct)
 //! [`Filter`]: struct.Filter.html (The Filter struct)
 //! [`Line`]: enum.Line.html (The Line enum)
+//! [`Segment`]: struct.Segment.html (The Segment struct)
 //! [`SubFilter`]: struct.SubFilter.html (The SubFilter struct)
 
 use std::fmt;
@@ -123,6 +125,23 @@ macro_rules! new_uids {
                         *factory += 1;
                         uid.into()
                     }
+
+                    /// Makes sure the factory never yields a UID lower than or equal to `uid`.
+                    ///
+                    /// Used when restoring UIDs from a previous run, so that fresh UIDs do not
+                    /// collide with them.
+                    pub fn reserve(uid: $uid_type_name) {
+                        let mut factory = COUNTER.lock().unwrap_or_else(|e| {
+                            panic!(
+                                "[sync] unable to access UID factory for `{}`: {}",
+                                stringify!($uid_type_name),
+                                e
+                            )
+                        });
+                        if *factory <= uid.get() {
+                            *factory = uid.get() + 1
+                        }
+                    }
                 }
             )?
         }
@@ -156,6 +175,13 @@ new_uids! {
         SubFilter,
         fresh_fn: fresh,
     }
+
+    mod segment_uid {
+        /// Trace segment UID.
+        ///
+        /// The index of the segment, segments are consecutive time ranges of the same duration.
+        Segment,
+    }
 }
 
 implement! {
@@ -174,7 +200,7 @@ implement! {
 /// A UID for a line in the chart.
 ///
 /// A line in the chart is either an actual filter, or the "catch-all" line, or the "everything"
-/// line.
+/// line, or a trace segment line.
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub enum Line {
     /// An actual filter.
@@ -183,6 +209,8 @@ pub enum Line {
     CatchAll,
     /// The everything filter.
     Everything,
+    /// A trace segment.
+    Segment(Segment),
 }
 
 impl From<Filter> for Line {
@@ -196,7 +224,15 @@ impl Line {
     pub fn filter_uid(self) -> Option<Filter> {
         match self {
             Self::Filter(uid) => Some(uid),
-            Self::CatchAll | Self::Everything => None,
+            Self::CatchAll | Self::Everything | Self::Segment(_) => None,
+        }
+    }
+
+    /// The segment UID, if any.
+    pub fn segment_uid(self) -> Option<Segment> {
+        match self {
+            Self::Segment(uid) => Some(uid),
+            Self::Filter(_) | Self::CatchAll | Self::Everything => None,
         }
     }
 
@@ -215,6 +251,7 @@ impl Line {
             Self::Filter(uid) => format!("y_{}", uid),
             Self::CatchAll => "y_catch_all".into(),
             Self::Everything => "y".into(),
+            Self::Segment(uid) => format!("y_{}{}", line_uid::SEGMENT_PREFIX, uid),
         }
     }
 }
@@ -225,6 +262,7 @@ impl fmt::Display for Line {
             Self::Filter(uid) => uid.fmt(fmt),
             Self::CatchAll => line_uid::CATCH_ALL_STR.fmt(fmt),
             Self::Everything => line_uid::EVERYTHING_STR.fmt(fmt),
+            Self::Segment(uid) => write!(fmt, "{}{}", line_uid::SEGMENT_PREFIX, uid),
         }
     }
 }
@@ -236,6 +274,8 @@ mod line_uid {
     pub const CATCH_ALL_STR: &str = "catch_all";
     /// String representing the `Everything` variant of `Line`.
     pub const EVERYTHING_STR: &str = "everything";
+    /// Prefix of the string representation of the `Segment` variant of `Line`.
+    pub const SEGMENT_PREFIX: &str = "segment_";
 
     impl serde::Serialize for Line {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -250,7 +290,9 @@ mod line_uid {
         type Value = Line;
 
         fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-            formatter.write_str("a UID (usize), or `catch_all`, or `everything`")
+            formatter.write_str(
+                "a UID (usize), or `catch_all`, or `everything`, or `segment_` followed by a UID",
+            )
         }
 
         fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
@@ -262,6 +304,10 @@ mod line_uid {
                 Ok(Line::CatchAll)
             } else if value == EVERYTHING_STR {
                 Ok(Line::Everything)
+            } else if let Some(index) = value.strip_prefix(SEGMENT_PREFIX) {
+                usize::from_str(index)
+                    .map(|index| Line::Segment(Segment::from(index)))
+                    .map_err(|e| E::custom(e.to_string()))
             } else {
                 usize::from_str(value)
                     .map(|index| Line::Filter(Filter::from(index)))
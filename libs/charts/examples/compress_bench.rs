@@ -0,0 +1,135 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Measures the compression of a big point message.
+//!
+//! Builds a message with 50k time/size points over a few filters, and reports the size of the
+//! message and the time it takes to encode and decode it, with and without compression. Run it in
+//! release mode, the numbers are meaningless otherwise:
+//!
+//! ```bash
+//! cargo run --release -p charts --example compress_bench
+//! ```
+
+use std::time::{Duration, Instant};
+
+use charts::{
+    msg::{compress, to_client},
+    point::{ChartPoints, Point, PointVal, Points, Size, TimeSizePoints},
+    prelude::*,
+};
+
+/// Number of points in the message.
+const POINT_COUNT: u64 = 50_000;
+/// Number of user filters, the everything and catch-all filters come on top of these.
+const FILTER_COUNT: usize = 3;
+/// Number of times the message is encoded and decoded.
+const RUN_COUNT: u32 = 20;
+
+fn main() {
+    if let Err(e) = work() {
+        for e in e.iter() {
+            eprintln!("{}", e)
+        }
+        std::process::exit(2)
+    }
+}
+
+/// Builds the message, with deterministic pseudo-random sizes.
+fn message() -> to_client::Msg {
+    let mut seed: u64 = 42;
+    let mut noise = move |max: u64| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) % max
+    };
+
+    let mut lines = vec![uid::Line::Everything, uid::Line::CatchAll];
+    lines.extend((0..FILTER_COUNT).map(|index| uid::Line::Filter(uid::Filter::from(index))));
+    let mut sizes: Vec<u64> = lines.iter().map(|_| 0).collect();
+
+    let points: TimeSizePoints = (0..POINT_COUNT)
+        .map(|cnt| {
+            let mut vals = PointVal::empty();
+            for (line, size) in lines.iter().zip(sizes.iter_mut()) {
+                *size = (*size + noise(4096)).saturating_sub(noise(4000));
+                vals.map.insert(*line, Size::new(*size));
+            }
+            let time =
+                time::SinceStart::from_nano_timestamp(cnt / 1000, (cnt % 1000) as u32 * 1000);
+            Point::new(time, vals)
+        })
+        .collect();
+
+    let mut chart_points = ChartPoints::new();
+    chart_points.insert(uid::Chart::from(0), Points::from(points));
+    to_client::ChartsMsg::points(chart_points, true)
+}
+
+/// Average duration of `RUN_COUNT` runs of an action.
+fn average<T>(mut action: impl FnMut() -> Res<T>) -> Res<(T, Duration)> {
+    let start = Instant::now();
+    let mut res = action()?;
+    for _ in 1..RUN_COUNT {
+        res = action()?;
+    }
+    Ok((res, start.elapsed() / RUN_COUNT))
+}
+
+fn work() -> Res<()> {
+    let msg = message();
+    println!(
+        "message with {} points over {} lines",
+        POINT_COUNT,
+        FILTER_COUNT + 2
+    );
+
+    let (plain, plain_encode) = average(|| msg.to_bytes())?;
+    let (_, plain_decode) = average(|| to_client::Msg::from_bytes(&plain))?;
+
+    let (sealed, sealed_encode) = average(|| msg.to_envelope_bytes())?;
+    let (_, sealed_decode) = average(|| to_client::Msg::from_envelope_bytes(&sealed))?;
+
+    if !compress::Envelope::from_bytes(&sealed)?.compressed {
+        bail!("the message was not compressed")
+    }
+
+    println!(
+        "|            | {:>12} | {:>12} | {:>12} |",
+        "bytes", "encode", "decode"
+    );
+    println!(
+        "| plain      | {:>12} | {:>12?} | {:>12?} |",
+        plain.len(),
+        plain_encode,
+        plain_decode
+    );
+    println!(
+        "| compressed | {:>12} | {:>12?} | {:>12?} |",
+        sealed.len(),
+        sealed_encode,
+        sealed_decode
+    );
+    println!(
+        "compressed message is {:.1}% of the plain one",
+        sealed.len() as f64 * 100.0 / plain.len() as f64
+    );
+    Ok(())
+}
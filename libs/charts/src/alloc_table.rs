@@ -0,0 +1,342 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Paged tables of the allocations of a line.
+//!
+//! Users browse the allocations of a line one page at a time. The server sorts **all** the
+//! allocations of the line before slicing the page, so that consecutive pages are consistent.
+
+prelude! {}
+
+/// Default number of rows in a page.
+pub const DEFAULT_PAGE_SIZE: usize = 25;
+/// Maximum number of rows in a page.
+pub const MAX_PAGE_SIZE: usize = 200;
+
+/// Columns the allocations can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SortBy {
+    /// UID, which is also the order of creation.
+    Uid,
+    /// Size.
+    Size,
+    /// Time of creation.
+    Toc,
+    /// Lifetime, live allocations are still alive at the current time of the data.
+    Lifetime,
+}
+
+impl SortBy {
+    /// Description of a column.
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::Uid => "uid",
+            Self::Size => "size",
+            Self::Toc => "allocation time",
+            Self::Lifetime => "lifetime",
+        }
+    }
+}
+
+impl fmt::Display for SortBy {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.desc().fmt(fmt)
+    }
+}
+
+/// A request for a page of the allocation table of a line.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Request {
+    /// Line of the allocations.
+    pub line: uid::Line,
+    /// Column the allocations are sorted by.
+    pub sort_by: SortBy,
+    /// True if the allocations are sorted in descending order.
+    pub descending: bool,
+    /// Index of the page, starting at `0`.
+    pub page: usize,
+    /// Number of rows in a page.
+    pub page_size: usize,
+}
+
+impl Request {
+    /// Constructor, first page of the allocations of a line by order of creation.
+    pub fn new(line: uid::Line) -> Self {
+        Self {
+            line,
+            sort_by: SortBy::Uid,
+            descending: false,
+            page: 0,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Sorts by some column, flips the direction if the allocations are already sorted by it.
+    ///
+    /// Goes back to the first page.
+    pub fn sort(&mut self, sort_by: SortBy) {
+        if self.sort_by == sort_by {
+            self.descending = !self.descending
+        } else {
+            self.sort_by = sort_by;
+            self.descending = false
+        }
+        self.page = 0
+    }
+
+    /// Number of pages for some number of rows, at least `1`.
+    pub fn page_count(&self, total: usize) -> usize {
+        let page_size = self.page_size.max(1);
+        ((total + page_size - 1) / page_size).max(1)
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{}, by {} ({}), page {} of size {}",
+            self.line,
+            self.sort_by,
+            if self.descending { "desc" } else { "asc" },
+            self.page,
+            self.page_size,
+        )
+    }
+}
+
+/// A row of an allocation table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Row {
+    /// UID of the allocation.
+    pub uid: uid::Alloc,
+    /// Size of the allocation, in bytes.
+    pub size: u32,
+    /// Time of creation.
+    pub toc: time::SinceStart,
+    /// Time of death, `None` if the allocation is still alive.
+    pub tod: Option<time::SinceStart>,
+    /// Top frame of the backtrace of the allocation, if any.
+    pub frame: Option<String>,
+}
+
+/// A page of the allocation table of a line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllocTable {
+    /// Request the page answers, the page index is the one of the actual page.
+    pub request: Request,
+    /// Total number of allocations of the line.
+    pub total: usize,
+    /// Rows of the page.
+    pub rows: Vec<Row>,
+}
+
+impl AllocTable {
+    /// Number of pages of the table.
+    pub fn page_count(&self) -> usize {
+        self.request.page_count(self.total)
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl AllocTable {
+    /// Lifetime of an allocation, live allocations are still alive at `now`.
+    fn lifetime(alloc: &Alloc, now: time::SinceStart) -> time::SinceStart {
+        let end = alloc.tod.unwrap_or(now);
+        if end > alloc.toc {
+            end - alloc.toc
+        } else {
+            time::SinceStart::zero()
+        }
+    }
+
+    /// Constructor, see the [module-level documentation](index.html).
+    ///
+    /// - `line_of`: the line of an allocation, besides the everything line.
+    ///
+    /// Requests for a page past the last one yield the last page.
+    pub fn new(
+        data: &data::Data,
+        mut request: Request,
+        line_of: impl Fn(&uid::Alloc) -> uid::Line,
+    ) -> Res<Self> {
+        if request.page_size == 0 || request.page_size > MAX_PAGE_SIZE {
+            bail!(
+                "illegal page size {}, expected a value between 1 and {}",
+                request.page_size,
+                MAX_PAGE_SIZE,
+            )
+        }
+
+        let now = *data.current_time();
+        let mut allocs: Vec<&Alloc> = data
+            .iter_allocs()
+            .filter(|alloc| {
+                request.line == uid::Line::Everything || line_of(alloc.uid()) == request.line
+            })
+            .collect();
+
+        allocs.sort_by(|lft, rgt| {
+            let cmp = match request.sort_by {
+                SortBy::Uid => std::cmp::Ordering::Equal,
+                SortBy::Size => lft.real_size.cmp(&rgt.real_size),
+                SortBy::Toc => lft.toc.cmp(&rgt.toc),
+                SortBy::Lifetime => Self::lifetime(lft, now).cmp(&Self::lifetime(rgt, now)),
+            };
+            cmp.then_with(|| lft.uid().cmp(rgt.uid()))
+        });
+        if request.descending {
+            allocs.reverse()
+        }
+
+        let total = allocs.len();
+        request.page = request.page.min(request.page_count(total) - 1);
+        let rows = allocs
+            .into_iter()
+            .skip(request.page * request.page_size)
+            .take(request.page_size)
+            .map(|alloc| Row {
+                uid: *alloc.uid(),
+                size: alloc.real_size,
+                toc: alloc.toc,
+                tod: alloc.tod,
+                frame: alloc.trace().last().map(|frame| frame.loc.to_string()),
+            })
+            .collect();
+
+        Ok(Self {
+            request,
+            total,
+            rows,
+        })
+    }
+
+    /// Allocation table of a line of some filters, using the global data.
+    pub fn of_filters(filters: &filter::Filters, request: Request) -> Res<Self> {
+        let data = data::get().chain_err(|| "while computing an allocation table")?;
+        Self::new(&data, request, |alloc| filters.line_of(alloc))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixture::{self, millis};
+
+    /// Builds some data with 10 allocations, created every millisecond.
+    ///
+    /// Allocation `idx` has size `8 * (10 - idx)`, even allocations never die and odd ones die
+    /// `idx` milliseconds after their creation. The current time is 20 milliseconds.
+    fn data() -> data::Data {
+        let mut synth = fixture::Synth::new();
+        let (trace, labels) = (fixture::trace(&[]), fixture::labels(&[]));
+
+        let mut events = vec![];
+        for idx in 0..10u64 {
+            events.push((idx, Some(idx)));
+            if idx % 2 == 1 {
+                events.push((2 * idx, None));
+            }
+        }
+        events.push((20, None));
+        events.sort_by_key(|(ms, new)| (*ms, new.is_some()));
+
+        for (ms, new) in events {
+            match new {
+                Some(idx) => {
+                    synth.alloc(ms, 8 * (10 - idx as u32), &trace, &labels);
+                }
+                None if ms == 20 => synth.mark(ms),
+                None => synth.dead(ms, ms / 2),
+            }
+        }
+
+        synth.done()
+    }
+
+    /// Puts even allocations in the catch-all line, odd ones in a filter line.
+    fn line_of(alloc: &uid::Alloc) -> uid::Line {
+        if alloc.get() % 2 == 0 {
+            uid::Line::CatchAll
+        } else {
+            uid::Line::Filter(uid::Filter::from(0))
+        }
+    }
+
+    fn uids(table: &AllocTable) -> Vec<usize> {
+        table.rows.iter().map(|row| row.uid.get()).collect()
+    }
+
+    #[test]
+    fn sort_then_page() {
+        let data = data();
+        let mut request = Request::new(uid::Line::Everything);
+        request.page_size = 4;
+
+        let table = AllocTable::new(&data, request.clone(), line_of).unwrap();
+        assert_eq!(table.total, 10);
+        assert_eq!(table.page_count(), 3);
+        assert_eq!(uids(&table), vec![0, 1, 2, 3]);
+        assert_eq!(table.rows[1].tod, Some(millis(2)));
+        assert_eq!(table.rows[2].tod, None);
+
+        // Smallest first, the page is sliced after sorting everything.
+        request.sort(SortBy::Size);
+        let table = AllocTable::new(&data, request.clone(), line_of).unwrap();
+        assert_eq!(uids(&table), vec![9, 8, 7, 6]);
+        request.sort(SortBy::Size);
+        assert!(request.descending);
+        let table = AllocTable::new(&data, request.clone(), line_of).unwrap();
+        assert_eq!(uids(&table), vec![0, 1, 2, 3]);
+
+        // Last page, and past the last page.
+        request.page = 2;
+        let table = AllocTable::new(&data, request.clone(), line_of).unwrap();
+        assert_eq!(uids(&table), vec![8, 9]);
+        request.page = 7;
+        let table = AllocTable::new(&data, request.clone(), line_of).unwrap();
+        assert_eq!(table.request.page, 2);
+        assert_eq!(uids(&table), vec![8, 9]);
+
+        request.page_size = 0;
+        assert!(AllocTable::new(&data, request, line_of).is_err());
+    }
+
+    #[test]
+    fn lifetimes_and_lines() {
+        let data = data();
+
+        // Live allocations live until the current time: `20 - idx` for even allocations, `idx`
+        // for odd ones.
+        let mut request = Request::new(uid::Line::Everything);
+        request.sort(SortBy::Lifetime);
+        let table = AllocTable::new(&data, request.clone(), line_of).unwrap();
+        assert_eq!(uids(&table), vec![1, 3, 5, 7, 9, 8, 6, 4, 2, 0]);
+
+        request.line = uid::Line::CatchAll;
+        let table = AllocTable::new(&data, request.clone(), line_of).unwrap();
+        assert_eq!(table.total, 5);
+        assert_eq!(uids(&table), vec![8, 6, 4, 2, 0]);
+
+        request.line = uid::Line::Filter(uid::Filter::from(0));
+        request.sort(SortBy::Lifetime);
+        let table = AllocTable::new(&data, request, line_of).unwrap();
+        assert_eq!(uids(&table), vec![9, 7, 5, 3, 1]);
+    }
+}
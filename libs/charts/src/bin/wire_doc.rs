@@ -0,0 +1,48 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Generates the description of the wire format of the client/server messages.
+//!
+//! Writes the description to the file given as argument, or to stdout if none. From the root of
+//! the repository:
+//!
+//! ```bash
+//! cargo run -p charts --bin wire_doc -- rsc/docs/wire.json
+//! ```
+
+use charts::{msg::wire, prelude::*};
+
+fn main() {
+    if let Err(e) = work() {
+        for e in e.iter() {
+            eprintln!("{}", e)
+        }
+        std::process::exit(2)
+    }
+}
+
+fn work() -> Res<()> {
+    let json = wire::Doc::generate()?.to_json()?;
+    match std::env::args().nth(1) {
+        Some(path) => std::fs::write(&path, json)
+            .chain_err(|| format!("while writing wire format description to `{}`", path))?,
+        None => print!("{}", json),
+    }
+    Ok(())
+}
@@ -26,8 +26,18 @@ use axis::{XAxis, YAxis};
 pub mod spec;
 
 pub mod axis;
+pub mod export;
+pub mod scatter;
+pub mod seq;
 pub mod settings;
+pub mod sketch;
+pub mod skew;
 pub mod time;
+pub mod transform;
+
+#[cfg(test)]
+mod golden;
+
 pub use spec::ChartSpec;
 
 /// A chart with no UID.
@@ -35,6 +45,8 @@ pub use spec::ChartSpec;
 pub enum RawChart {
     /// A time chart.
     Time(time::TimeChart),
+    /// An allocation size over lifetime scatter chart.
+    Scatter(scatter::ScatterChart),
 }
 
 #[cfg(any(test, feature = "server"))]
@@ -45,11 +57,15 @@ impl RawChart {
         filters: &mut Filters,
         init: bool,
         resolution: settings::Resolution,
+        quality: settings::Quality,
         time_windopt: &TimeWindopt,
     ) -> Res<Option<Points>> {
         match self {
             Self::Time(time_chart) => {
-                time_chart.new_points(filters, init, resolution, time_windopt)
+                time_chart.new_points(filters, init, resolution, quality, time_windopt)
+            }
+            Self::Scatter(scatter_chart) => {
+                scatter_chart.new_points(filters, init, resolution, quality, time_windopt)
             }
         }
     }
@@ -58,6 +74,23 @@ impl RawChart {
     fn reset(&mut self, filters: &filter::Filters) {
         match self {
             Self::Time(chart) => chart.reset(filters),
+            Self::Scatter(chart) => chart.reset(filters),
+        }
+    }
+
+    /// Sets the size percentile of a raw chart, if it uses one.
+    fn set_size_percentile(&mut self, size_percentile: settings::SizePercentile) {
+        match self {
+            Self::Time(chart) => chart.set_size_percentile(size_percentile),
+            Self::Scatter(_) => (),
+        }
+    }
+
+    /// Sets the lifetime statistic of a raw chart, if it uses one.
+    fn set_lifetime_stat(&mut self, lifetime_stat: settings::LifetimeStat) {
+        match self {
+            Self::Time(chart) => chart.set_lifetime_stat(lifetime_stat),
+            Self::Scatter(_) => (),
         }
     }
 }
@@ -65,11 +98,46 @@ impl RawChart {
 #[cfg(any(test, feature = "server"))]
 impl RawChart {
     /// Constructor.
+    ///
+    /// Fails on the charts that do not replay the pruned allocations while pruning, see
+    /// [`YAxis::replays_pruned`].
+    ///
+    /// [`YAxis::replays_pruned`]: axis/enum.YAxis.html#method.replays_pruned
+    /// (The replays_pruned method of YAxis)
     pub fn new(filters: &filter::Filters, x_axis: XAxis, y_axis: YAxis) -> Res<Self> {
-        let chart = match x_axis {
-            XAxis::Time => Self::Time(match y_axis {
-                YAxis::TotalSize => time::TimeChart::new_total_size(filters),
-            }),
+        if data::prune::is_enabled() && !y_axis.replays_pruned() {
+            bail!(
+                "cannot build a chart of the {} while pruning allocations",
+                y_axis
+            )
+        }
+        let chart = match (x_axis, y_axis) {
+            (XAxis::Time, YAxis::TotalSize) => Self::Time(time::TimeChart::new_total_size(filters)),
+            (XAxis::Time, YAxis::ChurnRatio) => Self::Time(time::TimeChart::new_churn(filters)),
+            (XAxis::Time, YAxis::AllocCount) => Self::Time(time::TimeChart::new_count(filters)),
+            (XAxis::Time, YAxis::LargeAllocCount) => {
+                Self::Time(time::TimeChart::new_large_allocs(filters))
+            }
+            (XAxis::Time, YAxis::AllocRate) => Self::Time(time::TimeChart::new_rate(filters)),
+            (XAxis::Time, YAxis::SegmentSize) => Self::Time(time::TimeChart::new_segment(filters)),
+            (XAxis::Time, YAxis::HeapSize) => Self::Time(time::TimeChart::new_heap(filters)),
+            (XAxis::Time, YAxis::LiveLifetime) => {
+                Self::Time(time::TimeChart::new_live_lifetime(filters))
+            }
+            (XAxis::Lifetime, YAxis::AllocSize) => {
+                Self::Scatter(scatter::ScatterChart::new(filters))
+            }
+            (XAxis::Time, YAxis::AllocSize)
+            | (XAxis::Lifetime, YAxis::TotalSize)
+            | (XAxis::Lifetime, YAxis::ChurnRatio)
+            | (XAxis::Lifetime, YAxis::AllocCount)
+            | (XAxis::Lifetime, YAxis::LargeAllocCount)
+            | (XAxis::Lifetime, YAxis::AllocRate)
+            | (XAxis::Lifetime, YAxis::SegmentSize)
+            | (XAxis::Lifetime, YAxis::HeapSize)
+            | (XAxis::Lifetime, YAxis::LiveLifetime) => {
+                bail!("cannot build a chart of the {} over the {}", y_axis, x_axis)
+            }
         };
         Ok(chart)
     }
@@ -85,6 +153,12 @@ pub struct Chart {
     /// Raw chart.
     #[allow(dead_code)]
     chart: RawChart,
+    /// Transforms applied to the points of the raw chart, built from the settings.
+    pipeline: transform::Pipeline,
+    /// What deduplication remembers of the points sent so far.
+    dedup: Option<point::DedupMemory>,
+    /// Number of points removed by deduplication since the last reset.
+    elided: usize,
     /// If true, the chart has not been initialized yet.
     ///
     /// This typically happens server-side, as the server needs the actual resolution of the chart
@@ -103,36 +177,78 @@ impl Chart {
         let spec = ChartSpec::new(x_axis, y_axis, active);
         let settings = settings::Chart::from_axes(spec.desc(), x_axis, y_axis);
         let chart = RawChart::new(filters, x_axis, y_axis)?;
+        let pipeline = transform::Pipeline::new(settings.transforms());
         let slf = Self {
             spec,
             settings,
             chart,
+            pipeline,
+            dedup: None,
+            elided: 0,
             still_init: true,
         };
         Ok(slf)
     }
 
     /// Constructor.
-    pub fn from_spec(title: Option<String>, filters: &Filters, spec: ChartSpec) -> Res<Self> {
+    ///
+    /// The title, if any, overrides the title of the spec.
+    pub fn from_spec(title: Option<String>, filters: &Filters, mut spec: ChartSpec) -> Res<Self> {
+        if title.is_some() {
+            spec.set_title(title);
+        }
         let settings = settings::Chart::from_axes(
-            title.unwrap_or_else(|| spec.desc()),
+            spec.display_title(),
             spec.x_axis().clone(),
             spec.y_axis().clone(),
         );
         let chart = RawChart::new(filters, spec.x_axis().clone(), spec.y_axis().clone())?;
+        let pipeline = transform::Pipeline::new(settings.transforms());
         Ok(Self {
             spec,
             settings,
             chart,
+            pipeline,
+            dedup: None,
+            elided: 0,
             still_init: true,
         })
     }
 
+    /// Constructor from a specification and some settings, *e.g.* from a [session].
+    ///
+    /// [session]: ../session/index.html (The session module)
+    pub fn restore(filters: &Filters, spec: ChartSpec, settings: settings::Chart) -> Res<Self> {
+        let mut slf = Self::from_spec(None, filters, spec)?;
+        slf.settings = settings;
+        slf.sync_settings();
+        slf.pipeline = transform::Pipeline::new(slf.settings.transforms());
+        Ok(slf)
+    }
+
     /// Applies an update to its settings.
     pub fn update(&mut self, msg: msg::to_server::ChartMsg) -> bool {
         use msg::to_server::ChartMsg::*;
         match msg {
-            SettingsUpdate(msg) => self.settings.update(msg),
+            SettingsUpdate(msg) => {
+                let reload = self.settings.update(msg);
+                self.sync_settings();
+                // Reloading sends all the points again, rebuilding the pipeline is cheap.
+                if reload {
+                    self.pipeline = transform::Pipeline::new(self.settings.transforms())
+                }
+                reload
+            }
+        }
+    }
+
+    /// Propagates the settings the raw chart depends on.
+    fn sync_settings(&mut self) {
+        if let Some(size_percentile) = self.settings.size_percentile() {
+            self.chart.set_size_percentile(size_percentile)
+        }
+        if let Some(lifetime_stat) = self.settings.lifetime_stat() {
+            self.chart.set_lifetime_stat(lifetime_stat)
         }
     }
 
@@ -142,6 +258,30 @@ impl Chart {
         &self.spec
     }
 
+    /// True if the chart shows one of some lines, or is normalized by one of them.
+    pub fn shows_any(&self, lines: &BTSet<uid::Line>) -> bool {
+        let normalize = self.settings.normalize();
+        lines
+            .iter()
+            .any(|line| self.spec.in_scope(*line) || normalize == Some(*line))
+    }
+
+    /// Sets the custom title of the chart, `None` or an empty title for the default title.
+    ///
+    /// Returns true if the title changed.
+    pub fn rename(&mut self, title: Option<String>) -> bool {
+        let changed = self.spec.set_title(title);
+        self.settings.set_title(self.spec.display_title());
+        changed
+    }
+
+    /// Sets the scope of the chart.
+    ///
+    /// The chart must be reloaded afterwards, so that its points only mention the new scope.
+    pub fn set_scope(&mut self, scope: Option<BTSet<uid::Line>>) {
+        self.spec.set_scope(scope)
+    }
+
     /// Settings accessor.
     #[inline]
     pub fn settings(&self) -> &settings::Chart {
@@ -163,6 +303,9 @@ impl Chart {
 #[cfg(any(test, feature = "server"))]
 impl Chart {
     /// Retrieves new points since the last time it was called.
+    ///
+    /// If the chart has a time window, the points only cover this last amount of time: the values
+    /// before the window are accumulated in the first point.
     pub fn new_points(
         &mut self,
         init: bool,
@@ -170,19 +313,99 @@ impl Chart {
         time_windopt: &TimeWindopt,
     ) -> Res<Option<Points>> {
         self.still_init = self.still_init || init;
-        if let Some(resolution) = self.settings.resolution() {
-            let res = self
-                .chart
-                .new_points(filters, self.still_init, resolution, time_windopt);
+        if let Some(resolution) = self.settings.points_resolution() {
+            let time_windopt = &self.time_windopt(time_windopt)?;
+            if self.still_init {
+                self.pipeline.reset();
+                self.dedup = None;
+                self.elided = 0
+            }
+            let mut res = self.chart.new_points(
+                filters,
+                self.still_init,
+                resolution,
+                self.settings.quality(),
+                time_windopt,
+            );
             self.still_init = false;
+            // Transform first, a normalizing line might not be in the scope.
+            if let Ok(Some(points)) = res.as_mut() {
+                self.pipeline.apply(points)?
+            }
+            if let (Some(scope), Ok(Some(points))) = (self.spec.scope(), res.as_mut()) {
+                // Scopes are sets of filters, they do not apply to segment lines.
+                let segments = self.spec.y_axis().has_segment_lines();
+                points.retain_lines(|line| {
+                    scope.contains(&line) || (segments && !line.is_everything())
+                })
+            }
+            if let (true, Ok(Some(points))) = (self.settings.dedup(), res.as_mut()) {
+                self.dedup(points, time_windopt)?
+            }
             res
         } else {
             Ok(None)
         }
     }
 
+    /// Removes the values and points that do not change how the lines are drawn, see
+    /// [`Points::dedup`].
+    ///
+    /// [`Points::dedup`]: ../point/enum.Points.html#method.dedup (Points::dedup)
+    fn dedup(&mut self, points: &mut Points, time_windopt: &TimeWindopt) -> Res<()> {
+        if points.is_empty() {
+            return Ok(());
+        }
+        let gaps = self.gaps(time_windopt)?;
+        let elided = points.dedup(&mut self.dedup, &gaps);
+        if elided > 0 {
+            self.elided += elided;
+            log::debug!(
+                "chart #{}: elided {} redundant point(s), {} since the last reset",
+                self.uid(),
+                elided,
+                self.elided,
+            )
+        }
+        Ok(())
+    }
+
+    /// Number of points removed by deduplication since the last reset.
+    pub fn elided(&self) -> usize {
+        self.elided
+    }
+
     /// Resets a chart.
     pub fn reset(&mut self, filters: &filter::Filters) {
         self.chart.reset(filters)
     }
+
+    /// Time window of the points of the chart.
+    ///
+    /// This is the time window of all the charts, restricted to the chart's own time window if
+    /// any.
+    fn time_windopt(&self, time_windopt: &TimeWindopt) -> Res<TimeWindopt> {
+        if self.settings.time_window().is_none() {
+            return Ok(time_windopt.clone());
+        }
+        let now = *data::get()?.current_time();
+        Ok(self.settings.time_windopt(time_windopt, now))
+    }
+
+    /// Time ranges with no data that are large enough to be displayed on this chart.
+    pub fn gaps(&self, time_windopt: &TimeWindopt) -> Res<Vec<TimeWindow>> {
+        let data = data::get()?;
+        if let Some(resolution) = self.settings.points_resolution() {
+            let time_window = self
+                .settings
+                .time_windopt(time_windopt, *data.current_time())
+                .to_time_window(|| *data.current_time());
+            Ok(data.gaps_in(
+                &time_window,
+                resolution.min_time_spacing(self.settings.quality(), data.current_time()),
+            ))
+        } else {
+            Ok(vec![])
+        }
+    }
 }
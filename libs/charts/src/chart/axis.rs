@@ -26,22 +26,50 @@ prelude! {}
 pub enum XAxis {
     /// Time.
     Time,
+    /// Lifetime of the allocations, for scatter charts.
+    Lifetime,
 }
 impl XAxis {
     /// Description of a x-axis.
     pub fn desc(&self) -> &'static str {
         match self {
             Self::Time => "time",
+            Self::Lifetime => "lifetime",
         }
     }
 
     /// The legal y-axes that can be combined with this x-axis.
     pub fn y_axes(&self) -> Vec<YAxis> {
         match self {
-            Self::Time => vec![YAxis::TotalSize],
+            Self::Time => vec![
+                YAxis::TotalSize,
+                YAxis::AllocCount,
+                YAxis::ChurnRatio,
+                YAxis::LargeAllocCount,
+                YAxis::AllocRate,
+                YAxis::SegmentSize,
+                YAxis::HeapSize,
+                YAxis::LiveLifetime,
+            ],
+            Self::Lifetime => vec![YAxis::AllocSize],
         }
     }
 
+    /// True if a y-axis can be combined with this x-axis.
+    ///
+    /// ```rust
+    /// # use charts::chart::axis::*;
+    /// assert!(XAxis::Time.accepts(YAxis::TotalSize));
+    /// assert!(!XAxis::Time.accepts(YAxis::AllocSize));
+    /// for x_axis in XAxis::all() {
+    ///     let legal = YAxis::all().into_iter().filter(|y| x_axis.accepts(*y));
+    ///     assert_eq!(legal.collect::<Vec<_>>().len(), x_axis.y_axes().len());
+    /// }
+    /// ```
+    pub fn accepts(&self, y_axis: YAxis) -> bool {
+        self.y_axes().contains(&y_axis)
+    }
+
     /// A list of all the x-axes.
     pub fn all() -> Vec<XAxis> {
         use strum::IntoEnumIterator;
@@ -62,19 +90,104 @@ impl Default for XAxis {
 }
 
 /// Y-axis spec.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, strum_macros::EnumIter)]
 pub enum YAxis {
     /// Total size.
     TotalSize,
-    // /// Highest lifetime.
-    // MaxLifetime,
+    /// Ratio of the bytes freed to the bytes allocated, per time bucket.
+    ChurnRatio,
+    /// Number of live allocations.
+    AllocCount,
+    /// Number of allocations at or above a size percentile, per time bucket.
+    ///
+    /// The percentile is computed over the sizes of **all** the allocations created during a
+    /// trailing window, see [`SizePercentile`].
+    ///
+    /// [`SizePercentile`]: ../settings/struct.SizePercentile.html (SizePercentile struct)
+    LargeAllocCount,
+    /// Bytes allocated per time bucket.
+    AllocRate,
+    /// Total size of the live allocations of each trace segment.
+    ///
+    /// Lines are trace segments instead of filters, see the [segment chart].
+    ///
+    /// [segment chart]: ../time/segment/index.html (The segment module)
+    SegmentSize,
+    /// Total size of the live allocations on the minor heap and on the major heap.
+    ///
+    /// Each line has two sub-series, see the [heap chart].
+    ///
+    /// [heap chart]: ../time/heap/index.html (The heap module)
+    HeapSize,
+    /// Size of individual allocations, for scatter charts.
+    ///
+    /// Points are a sample of the dead allocations, see the [scatter chart].
+    ///
+    /// [scatter chart]: ../scatter/index.html (The scatter module)
+    AllocSize,
+    /// Statistic over the lifetimes of the live allocations.
+    ///
+    /// The statistic is a setting of the chart, see [`LifetimeStat`].
+    ///
+    /// [`LifetimeStat`]: ../settings/enum.LifetimeStat.html (LifetimeStat enum)
+    LiveLifetime,
 }
 impl YAxis {
     /// Description of a y-axis.
     pub fn desc(self) -> &'static str {
         match self {
             Self::TotalSize => "total size",
-            // Self::MaxLifetime => "highest lifetime",
+            Self::ChurnRatio => "churn ratio",
+            Self::AllocCount => "allocation count",
+            Self::LargeAllocCount => "large allocation count",
+            Self::AllocRate => "allocation rate",
+            Self::SegmentSize => "size by segment",
+            Self::HeapSize => "heap size",
+            Self::AllocSize => "allocation size",
+            Self::LiveLifetime => "live lifetime",
+        }
+    }
+
+    /// Documentation for a y-axis, displayed as a tooltip.
+    pub fn doc(self) -> &'static str {
+        match self {
+            Self::TotalSize => "total size of the live allocations",
+            Self::AllocCount => "number of live allocations",
+            Self::ChurnRatio => {
+                "ratio (between 0 and 1) of the bytes freed to the bytes allocated in each time \
+                bucket (plain lines), and spread (between 0 and 1) between the sizes of the \
+                allocations freed and the sizes of the allocations created in each time bucket \
+                (dashed lines); this is a rough proxy for heap fragmentation, not a measure of \
+                actual allocator fragmentation"
+            }
+            Self::LargeAllocCount => {
+                "number of allocations created in each time bucket whose size is at or above the \
+                size percentile of all the allocations created during the trailing window ending \
+                when they were created; the percentile is approximated and only allocations, not \
+                deallocations, are counted"
+            }
+            Self::AllocRate => {
+                "total size of the allocations created in each time bucket, deallocations are \
+                ignored; the width of the buckets depends on the resolution of the chart"
+            }
+            Self::SegmentSize => {
+                "total size of the live allocations, grouped by the trace segment they were \
+                created in; filters are ignored, older segments are eventually merged"
+            }
+            Self::HeapSize => {
+                "total size of the live allocations on the minor heap (dashed) and on the major \
+                heap (plain); promotions move allocations from the minor heap to the major heap"
+            }
+            Self::AllocSize => {
+                "size of individual dead allocations against their lifetime, both on a logarithmic \
+                scale; each line only shows a uniform sample of its allocations, hover a point for \
+                details and click it to pin them"
+            }
+            Self::LiveLifetime => {
+                "mean, median, 95th percentile or maximum, depending on the settings of the chart, \
+                of the lifetimes (in seconds) of the live allocations; a line with no live \
+                allocation is zero"
+            }
         }
     }
 
@@ -82,8 +195,96 @@ impl YAxis {
     pub fn can_stack_area(self) -> bool {
         match self {
             Self::TotalSize => true,
+            Self::ChurnRatio | Self::HeapSize | Self::AllocSize | Self::LiveLifetime => false,
+            Self::AllocCount | Self::LargeAllocCount | Self::AllocRate | Self::SegmentSize => true,
+        }
+    }
+
+    /// True if the points of `self` can be deduplicated, see [`TimePoints::dedup`].
+    ///
+    /// Charts that do not draw lines, such as heatmaps or histograms, must opt out.
+    ///
+    /// [`TimePoints::dedup`]: ../../point/enum.TimePoints.html#method.dedup (TimePoints::dedup)
+    pub fn can_dedup(self) -> bool {
+        match self {
+            Self::TotalSize
+            | Self::ChurnRatio
+            | Self::AllocCount
+            | Self::LargeAllocCount
+            | Self::AllocRate
+            | Self::SegmentSize
+            | Self::LiveLifetime => true,
+            Self::HeapSize | Self::AllocSize => false,
+        }
+    }
+
+    /// True if the lines of `self` are trace segments instead of filters.
+    pub fn has_segment_lines(self) -> bool {
+        match self {
+            Self::SegmentSize => true,
+            Self::TotalSize
+            | Self::ChurnRatio
+            | Self::AllocCount
+            | Self::LargeAllocCount
+            | Self::AllocRate
+            | Self::HeapSize
+            | Self::AllocSize
+            | Self::LiveLifetime => false,
         }
     }
+
+    /// True if the charts of `self` replay the [pruned] allocations.
+    ///
+    /// The other charts need individual allocations or promotions, they are not available while
+    /// pruning.
+    ///
+    /// [pruned]: ../../data/prune/index.html (The data::prune module)
+    pub fn replays_pruned(self) -> bool {
+        match self {
+            Self::TotalSize | Self::ChurnRatio | Self::AllocCount | Self::AllocRate => true,
+            Self::LargeAllocCount
+            | Self::SegmentSize
+            | Self::HeapSize
+            | Self::AllocSize
+            | Self::LiveLifetime => false,
+        }
+    }
+
+    /// True if `self` relies on a size percentile setting.
+    pub fn needs_size_percentile(self) -> bool {
+        match self {
+            Self::LargeAllocCount => true,
+            Self::TotalSize
+            | Self::ChurnRatio
+            | Self::AllocCount
+            | Self::AllocRate
+            | Self::SegmentSize
+            | Self::HeapSize
+            | Self::AllocSize
+            | Self::LiveLifetime => false,
+        }
+    }
+
+    /// True if `self` relies on a lifetime statistic setting.
+    pub fn needs_lifetime_stat(self) -> bool {
+        match self {
+            Self::LiveLifetime => true,
+            Self::TotalSize
+            | Self::ChurnRatio
+            | Self::AllocCount
+            | Self::LargeAllocCount
+            | Self::AllocRate
+            | Self::SegmentSize
+            | Self::HeapSize
+            | Self::AllocSize => false,
+        }
+    }
+
+    /// A list of all the y-axes.
+    pub fn all() -> Vec<YAxis> {
+        use strum::IntoEnumIterator;
+        Self::iter().collect()
+    }
 }
 
 impl fmt::Display for YAxis {
@@ -0,0 +1,397 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Export of a chart as a standalone HTML page, or as CSV.
+//!
+//! The page embeds the points of the chart as JSON, and a small inline script draws them on a
+//! canvas and displays the values under the mouse. It does not load anything, so it can be pasted
+//! in a wiki or sent as is.
+//!
+//! The CSV version has one row per timestamp and one column per line, for spreadsheets.
+//!
+//! The points exported are the ones the client has, which the server already compressed for the
+//! resolution of the chart.
+
+prelude! {}
+
+use chart::ChartSpec;
+
+/// Script drawing the chart, reads the JSON data from the element with id `data`.
+const SCRIPT: &str = r##"
+(function() {
+    var chart = JSON.parse(document.getElementById("data").textContent);
+    var canvas = document.getElementById("chart");
+    var info = document.getElementById("info");
+    var ctx = canvas.getContext("2d");
+    var w = canvas.width, h = canvas.height, m = 60;
+    var xmin = Infinity, xmax = -Infinity, ymin = 0, ymax = -Infinity;
+    chart.series.forEach(function(s) {
+        s.points.forEach(function(p) {
+            xmin = Math.min(xmin, p[0]);
+            xmax = Math.max(xmax, p[0]);
+            ymin = Math.min(ymin, p[1]);
+            ymax = Math.max(ymax, p[1]);
+        });
+    });
+    if (!isFinite(xmin)) { info.textContent = "no points"; return; }
+    if (xmax <= xmin) { xmax = xmin + 1; }
+    if (ymax <= ymin) { ymax = ymin + 1; }
+    var x = function(v) { return m + (v - xmin) / (xmax - xmin) * (w - 2 * m); };
+    var y = function(v) { return h - m - (v - ymin) / (ymax - ymin) * (h - 2 * m); };
+    var inGap = function(a, b) {
+        return chart.gaps.some(function(g) { return a <= g[0] && g[1] <= b; });
+    };
+    ctx.strokeStyle = "#000000";
+    ctx.beginPath();
+    ctx.moveTo(m, m);
+    ctx.lineTo(m, h - m);
+    ctx.lineTo(w - m, h - m);
+    ctx.stroke();
+    ctx.fillStyle = "#000000";
+    ctx.font = "12px sans-serif";
+    ctx.fillText(ymax.toPrecision(4), 2, m);
+    ctx.fillText(ymin.toPrecision(4), 2, h - m);
+    ctx.fillText(xmin.toFixed(3) + "s", m, h - m + 15);
+    ctx.fillText(xmax.toFixed(3) + "s", w - 2 * m, h - m + 15);
+    chart.series.forEach(function(s) {
+        ctx.strokeStyle = s.color;
+        ctx.lineWidth = 2;
+        ctx.beginPath();
+        s.points.forEach(function(p, i) {
+            if (i == 0 || inGap(s.points[i - 1][0], p[0])) {
+                ctx.moveTo(x(p[0]), y(p[1]));
+            } else {
+                ctx.lineTo(x(p[0]), y(p[1]));
+            }
+        });
+        ctx.stroke();
+    });
+    canvas.addEventListener("mousemove", function(e) {
+        var t = xmin + (e.offsetX - m) / (w - 2 * m) * (xmax - xmin);
+        var desc = t.toFixed(3) + "s";
+        chart.series.forEach(function(s) {
+            var last = null;
+            s.points.forEach(function(p) { if (p[0] <= t) { last = p; } });
+            if (last !== null) { desc += " | " + s.name + ": " + last[1].toPrecision(4); }
+        });
+        info.textContent = desc;
+    });
+})();
+"##;
+
+/// A line of an exported chart.
+#[derive(Debug, Clone, Serialize)]
+pub struct Series {
+    /// Name of the line.
+    pub name: filter::FilterName,
+    /// Color of the line, as an RGB string.
+    pub color: String,
+    /// Points of the line, `(x, y)` pairs sorted by `x`.
+    pub points: Vec<(f64, f64)>,
+}
+
+/// A chart ready to export.
+#[derive(Debug, Clone, Serialize)]
+pub struct Export {
+    /// Title of the chart.
+    pub title: String,
+    /// Description of the chart.
+    pub desc: String,
+    /// Lines of the chart.
+    pub series: Vec<Series>,
+    /// Time ranges with no data, in seconds, where lines are interrupted.
+    pub gaps: Vec<(f64, f64)>,
+}
+
+impl Export {
+    /// Constructor.
+    ///
+    /// Only exports the lines in `lines`, given with their name and color, in this order.
+    pub fn new(
+        spec: &ChartSpec,
+        title: impl Into<String>,
+        points: &Points,
+        gaps: &[TimeWindow],
+        lines: impl IntoIterator<Item = (uid::Line, filter::FilterName, Color)>,
+    ) -> Self {
+        let mut all_series = points.series();
+        let series = lines
+            .into_iter()
+            .map(|(uid, name, color)| Series {
+                name,
+                color: color.to_string(),
+                points: all_series.remove(&uid).unwrap_or_else(Vec::new),
+            })
+            .collect();
+        let gaps = gaps
+            .iter()
+            .map(|gap| (gap.lbound.as_secs_f64(), gap.ubound.as_secs_f64()))
+            .collect();
+        Self {
+            title: title.into(),
+            desc: spec.desc(),
+            series,
+            gaps,
+        }
+    }
+
+    /// Filename-safe name for the HTML page, based on the title.
+    ///
+    /// Uses the same slugs as filter names, see [`FilterName::slug`].
+    ///
+    /// ```rust
+    /// # use charts::chart::export::Export;
+    /// let export = Export {
+    ///     title: "Total size (big allocs)".into(),
+    ///     desc: "total size over time".into(),
+    ///     series: vec![],
+    ///     gaps: vec![],
+    /// };
+    /// assert_eq!(export.file_name(), "total-size-big-allocs.html");
+    /// assert_eq!(export.csv_file_name(), "total-size-big-allocs.csv");
+    /// ```
+    ///
+    /// [`FilterName::slug`]: ../../filter/struct.FilterName.html#method.slug (The FilterName::slug method)
+    pub fn file_name(&self) -> String {
+        format!("{}.html", self.slug())
+    }
+    /// Filename-safe name for the CSV version, see [`file_name`].
+    ///
+    /// [`file_name`]: #method.file_name (The file_name method)
+    pub fn csv_file_name(&self) -> String {
+        format!("{}.csv", self.slug())
+    }
+    /// Filename-safe version of the title.
+    fn slug(&self) -> String {
+        filter::FilterName::new_truncated(self.title.clone()).slug()
+    }
+
+    /// Renders the points as CSV.
+    ///
+    /// One row per timestamp, with the date (ISO-8601) and the number of seconds since the start of
+    /// the run, and one column per line. The cell of a line with no point at some timestamp is
+    /// blank, so that tools can tell "no point" from zero. The date column is blank if the start
+    /// date of the run is unknown. Line names are escaped with [`FilterName::csv_field`].
+    ///
+    /// [`FilterName::csv_field`]: ../../filter/struct.FilterName.html#method.csv_field
+    /// (The FilterName::csv_field method)
+    pub fn to_csv(&self, start_date: Option<&time::Date>) -> String {
+        let mut res = "date,seconds since start".to_string();
+        for series in &self.series {
+            res.push(',');
+            res.push_str(&series.name.csv_field())
+        }
+        res.push('\n');
+
+        let mut times: Vec<f64> = self
+            .series
+            .iter()
+            .flat_map(|series| series.points.iter().map(|(x, _)| *x))
+            .collect();
+        times.sort_by(|lft, rgt| lft.partial_cmp(rgt).unwrap_or(std::cmp::Ordering::Equal));
+        times.dedup();
+
+        // Index of the next point of each series, the points of a series are sorted.
+        let mut next = vec![0; self.series.len()];
+        for time in times {
+            if let Some(start_date) = start_date {
+                let since_start = time::SinceStart::from(time::Duration::from_secs_f64(time));
+                res.push_str(&(start_date + since_start).date().to_rfc3339())
+            }
+            res.push(',');
+            res.push_str(&time.to_string());
+            for (series, next) in self.series.iter().zip(next.iter_mut()) {
+                res.push(',');
+                match series.points.get(*next) {
+                    Some((x, y)) if *x == time => {
+                        res.push_str(&y.to_string());
+                        *next += 1
+                    }
+                    _ => (),
+                }
+            }
+            res.push('\n')
+        }
+        res
+    }
+
+    /// Renders the standalone HTML page.
+    pub fn to_html(&self) -> Res<String> {
+        let data = ::serde_json::to_string(self)
+            .map_err(|e| format!("while encoding the points of a chart: {}", e))?
+            // Prevents names from closing the script element.
+            .replace("</", "<\\/");
+        let legend: String = self
+            .series
+            .iter()
+            .map(|series| {
+                format!(
+                    "<li><span style=\"color: {}\">&#9632;</span> {}</li>\n",
+                    series.color,
+                    escape(&series.name),
+                )
+            })
+            .collect();
+        Ok(format!(
+            "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+</head>
+<body style=\"font-family: sans-serif\">
+<h2>{title}</h2>
+<p>{desc}</p>
+<canvas id=\"chart\" width=\"900\" height=\"450\"></canvas>
+<p id=\"info\">&nbsp;</p>
+<ul style=\"list-style: none\">
+{legend}</ul>
+<script id=\"data\" type=\"application/json\">{data}</script>
+<script>{script}</script>
+</body>
+</html>
+",
+            title = escape(&self.title),
+            desc = escape(&self.desc),
+            legend = legend,
+            data = data,
+            script = SCRIPT,
+        ))
+    }
+}
+
+/// Escapes a string for use as HTML text.
+///
+/// ```rust
+/// # use charts::chart::export::escape;
+/// assert_eq!(escape(r#"<b>"a" & 'b'</b>"#), "&lt;b&gt;&quot;a&quot; &amp; &#39;b&#39;&lt;/b&gt;");
+/// ```
+pub fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn html_is_standalone() {
+        let spec = ChartSpec::new(
+            chart::axis::XAxis::Time,
+            chart::axis::YAxis::TotalSize,
+            BTMap::new(),
+        );
+        let mut vals = point::PointVal::empty();
+        vals.map
+            .insert(uid::Line::Everything, point::Size::new(42u64));
+        let points: point::TimeSizePoints =
+            vec![point::Point::new(time::SinceStart::from_secs(1), vals)];
+        let points = Points::Time(points.into());
+
+        let export = Export::new(
+            &spec,
+            "</script><script>alert(1)</script>",
+            &points,
+            &[],
+            vec![(
+                uid::Line::Everything,
+                filter::FilterName::new("</script>").unwrap(),
+                Color::new(0, 0, 255),
+            )],
+        );
+        assert_eq!(export.series.len(), 1);
+        assert_eq!(export.series[0].points, vec![(1.0, 42.0)]);
+
+        let html = export.to_html().unwrap();
+        // Only the two legit script elements are closed.
+        assert_eq!(html.matches("</script>").count(), 2);
+        assert!(!html.contains("http"));
+        assert!(html.contains("#0000ff"));
+    }
+
+    #[test]
+    fn csv_blank_cells() {
+        let spec = ChartSpec::new(
+            chart::axis::XAxis::Time,
+            chart::axis::YAxis::TotalSize,
+            BTMap::new(),
+        );
+        let point = |secs: u64, vals: &[(uid::Line, u64)]| {
+            let mut point_vals = point::PointVal::empty();
+            for (line, size) in vals {
+                point_vals.map.insert(*line, point::Size::new(*size));
+            }
+            point::Point::new(time::SinceStart::from_secs(secs), point_vals)
+        };
+        let points: point::TimeSizePoints = vec![
+            point(1, &[(uid::Line::Everything, 42), (uid::Line::CatchAll, 0)]),
+            point(2, &[(uid::Line::Everything, 7)]),
+        ];
+        let points = Points::Time(points.into());
+
+        let export = Export::new(
+            &spec,
+            "sizes",
+            &points,
+            &[],
+            vec![
+                (
+                    uid::Line::Everything,
+                    filter::FilterName::new("all, really").unwrap(),
+                    Color::new(0, 0, 0),
+                ),
+                (
+                    uid::Line::CatchAll,
+                    filter::FilterName::new("=rest").unwrap(),
+                    Color::new(0, 0, 0),
+                ),
+            ],
+        );
+
+        let csv = export.to_csv(None);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "date,seconds since start,\"all, really\",'=rest",
+                ",1,42,0",
+                ",2,7,",
+            ]
+        );
+
+        let start = time::Date::from_timestamp(0, 0);
+        let csv = export.to_csv(Some(&start));
+        let row = csv.lines().nth(2).unwrap();
+        let date = row.split(',').next().unwrap();
+        let parsed = time::chrono::DateTime::parse_from_rfc3339(date).unwrap();
+        assert_eq!(parsed.timestamp(), 2);
+        assert!(row.ends_with(",2,7,"));
+    }
+}
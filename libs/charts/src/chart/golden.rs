@@ -0,0 +1,445 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Golden tests for the points of all the chart kinds.
+//!
+//! A [`Case`] generates the points of a chart over a small synthetic [`Trace`], and compares them
+//! with the golden points stored as JSON in the `golden` directory of the crate, up to
+//! [`TOLERANCE`]. A failing case names the first point that diverges.
+//!
+//! Setting the [`BLESS_VAR`] environment variable (re)generates the goldens instead of checking
+//! them, *e.g.* `MEMTHOL_BLESS=1 cargo test -p charts golden`. Goldens must be reviewed by hand
+//! before they are committed.
+//!
+//! Each chart kind, *i.e.* legal pair of axes, must have at least [`MIN_CASES`] cases.
+//!
+//! [`Case`]: struct.Case.html (The Case struct)
+//! [`Trace`]: enum.Trace.html (The Trace enum)
+//! [`TOLERANCE`]: constant.TOLERANCE.html (The TOLERANCE constant)
+//! [`BLESS_VAR`]: constant.BLESS_VAR.html (The BLESS_VAR constant)
+//! [`MIN_CASES`]: constant.MIN_CASES.html (The MIN_CASES constant)
+
+prelude! {}
+
+use chart::settings::{Quality, Resolution};
+
+/// Environment variable that makes the cases write their golden instead of checking it.
+const BLESS_VAR: &str = "MEMTHOL_BLESS";
+
+/// Relative tolerance over the coordinates of the points.
+const TOLERANCE: f64 = 1e-6;
+
+/// Minimal number of cases for each chart kind.
+const MIN_CASES: usize = 2;
+
+/// Flattened points, see [`Points::series`], with the lines as strings.
+///
+/// The minor and major heaps of heap charts are flattened separately, see [`heap::split`], and so
+/// are the ratio and the spread of churn charts, see [`churn::split`].
+///
+/// [`Points::series`]: ../../point/enum.Points.html#method.series (Points::series)
+/// [`heap::split`]: ../../point/heap/fn.split.html (The heap::split function)
+/// [`churn::split`]: ../../point/churn/fn.split.html (The churn::split function)
+type Series = BTMap<String, Vec<(f64, f64)>>;
+
+/// Synthetic traces.
+#[derive(Debug, Clone, Copy)]
+enum Trace {
+    /// One allocation every 10ms for one second, each dies 35ms after its creation.
+    ///
+    /// One allocation in four is major, one minor allocation in three is promoted 20ms after its
+    /// creation.
+    Steady,
+    /// Bursts of eight allocations every 100ms for one second, half of which die 50ms later.
+    ///
+    /// The last allocation of each burst is major, the other survivors are promoted 30ms after the
+    /// burst. The first allocation of each burst is promoted when it dies, which is ignored.
+    Bursts,
+}
+
+impl Trace {
+    /// Name of the trace, used in the name of the goldens.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Steady => "steady",
+            Self::Bursts => "bursts",
+        }
+    }
+
+    /// Millisecond of creation, size, millisecond of death, majorness, and millisecond of
+    /// promotion of the allocations.
+    fn allocs(self) -> Vec<(u64, u32, Option<u64>, bool, Option<u64>)> {
+        match self {
+            Self::Steady => (0..100)
+                .map(|idx| {
+                    let is_major = idx % 4 == 3;
+                    let promoted_at = if !is_major && idx % 3 == 0 {
+                        Some(10 * idx + 20)
+                    } else {
+                        None
+                    };
+                    (
+                        10 * idx,
+                        16 + 8 * (idx % 4) as u32,
+                        Some(10 * idx + 35),
+                        is_major,
+                        promoted_at,
+                    )
+                })
+                .collect(),
+            Self::Bursts => (0..10)
+                .flat_map(|burst| {
+                    (0..8).map(move |idx| {
+                        let toc = 100 * burst + idx;
+                        let tod = if idx % 2 == 0 {
+                            Some(100 * burst + 50)
+                        } else {
+                            None
+                        };
+                        let is_major = idx == 7;
+                        let promoted_at = match idx {
+                            0 => Some(100 * burst + 50),
+                            _ if idx % 2 == 1 && !is_major => Some(100 * burst + 30),
+                            _ => None,
+                        };
+                        (toc, 8 << (idx % 5) as u32, tod, is_major, promoted_at)
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Builds the data of the trace.
+    ///
+    /// Allocations alternate between two allocation sites, so that segment charts have several
+    /// lines.
+    fn data(self) -> data::Data {
+        let mut synth = crate::fixture::Synth::new();
+        let trace = |file: &str| crate::fixture::trace(&[("main.ml", 1), (file, 3)]);
+        let traces = [trace("left.ml"), trace("right.ml")];
+        let labels = crate::fixture::labels(&[]);
+
+        // Allocations, deaths and promotions must come in chronological order, deaths first and
+        // promotions last on ties.
+        let (dead, new, promotion) = (0, 1, 2);
+        let mut events = vec![];
+        for (idx, (toc, size, tod, is_major, promoted_at)) in self.allocs().into_iter().enumerate()
+        {
+            let idx = idx as u64;
+            let kind = if is_major {
+                alloc::AllocKind::Major
+            } else {
+                alloc::AllocKind::Minor
+            };
+            events.push((toc, new, idx, size, kind));
+            if let Some(tod) = tod {
+                events.push((tod, dead, idx, size, kind))
+            }
+            if let Some(promoted_at) = promoted_at {
+                events.push((promoted_at, promotion, idx, size, kind))
+            }
+        }
+        events.sort_by_key(|(ms, event, idx, _, _)| (*ms, *event, *idx));
+
+        // Allocations are created in order, their UIDs are their indices.
+        for (ms, event, idx, size, kind) in events {
+            if event == new {
+                synth.alloc_kind(kind, ms, size, &traces[idx as usize % 2], &labels);
+            } else if event == dead {
+                synth.dead(ms, idx)
+            } else {
+                synth.promotion(ms, idx)
+            }
+        }
+        synth.done()
+    }
+}
+
+/// A golden test case.
+#[derive(Debug, Clone, Copy)]
+struct Case {
+    /// Trace the points come from.
+    trace: Trace,
+    /// X-axis of the chart.
+    x_axis: chart::axis::XAxis,
+    /// Y-axis of the chart.
+    y_axis: chart::axis::YAxis,
+    /// Quality of the points.
+    quality: Quality,
+}
+
+impl Case {
+    /// Resolution of the charts of all the cases.
+    const RESOLUTION: (u32, u32) = (800, 400);
+
+    /// Name of the case, also the name of its golden file.
+    fn name(&self) -> String {
+        format!(
+            "{}-{}-{}-{}",
+            self.trace.name(),
+            self.x_axis,
+            self.y_axis,
+            self.quality.desc(),
+        )
+        .replace(' ', "_")
+    }
+
+    /// Path of the golden file of the case.
+    fn golden_path(&self) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("golden")
+            .join(format!("{}.json", self.name()))
+    }
+
+    /// Generates the points of the case.
+    ///
+    /// The traces are small enough for a single extraction to handle all their events.
+    fn points(&self) -> Res<Series> {
+        let _lock = data::set_for_test(self.trace.data());
+
+        let mut filters = Filters::new();
+        let active = filters.fold(BTMap::new(), |mut map, uid| {
+            let _ = map.insert(uid, true);
+            map
+        });
+        let mut chart = chart::Chart::new(&filters, self.x_axis, self.y_axis, active)?;
+        chart
+            .settings_mut()
+            .set_resolution(Resolution::from(Self::RESOLUTION));
+        let _ = chart.settings_mut().set_quality(self.quality);
+
+        let points = chart
+            .new_points(true, &mut filters, &TimeWindopt::new(None, None))?
+            .ok_or("the chart did not generate any point")?;
+        let series = |points: &Points, suffix: &str| {
+            points
+                .series()
+                .into_iter()
+                .map(move |(line, series)| (format!("{}{}", line, suffix), series))
+                .collect::<Vec<_>>()
+        };
+        match &points {
+            Points::Time(point::TimePoints::Heap(points)) => {
+                let (minor, major) = point::heap::split(points);
+                let (minor, major) = (Points::Time(minor.into()), Points::Time(major.into()));
+                Ok(series(&minor, " (minor)")
+                    .into_iter()
+                    .chain(series(&major, " (major)"))
+                    .collect())
+            }
+            Points::Time(point::TimePoints::Churn(churn)) => {
+                let (_, spread) = point::churn::split(churn);
+                let spread = Points::Time(spread.into());
+                Ok(series(&points, "")
+                    .into_iter()
+                    .chain(series(&spread, " (spread)"))
+                    .collect())
+            }
+            _ => Ok(series(&points, "").into_iter().collect()),
+        }
+    }
+
+    /// Checks the points of the case against its golden, or writes the golden if blessing.
+    fn check(&self) {
+        let name = self.name();
+        let path = self.golden_path();
+        let actual = self.points().unwrap_or_else(|e| {
+            panic!(
+                "[golden] {}: failed to generate the points\n{}",
+                name,
+                e.to_pretty()
+            )
+        });
+
+        if std::env::var_os(BLESS_VAR).is_some() {
+            let json = serde_json::to_string_pretty(&actual).unwrap();
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, json + "\n").unwrap();
+            println!("[golden] {}: blessed {}", name, path.display());
+            return;
+        }
+
+        let expected: Series = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                panic!(
+                    "[golden] {}: illegal golden {}: {}",
+                    name,
+                    path.display(),
+                    e
+                )
+            }),
+            Err(e) => panic!(
+                "[golden] {}: cannot read golden {} ({}), run with `{}=1` to create it",
+                name,
+                path.display(),
+                e,
+                BLESS_VAR,
+            ),
+        };
+        if let Some(divergence) = first_divergence(&expected, &actual) {
+            panic!(
+                "[golden] {}: {}\nrun with `{}=1` to update {} if this is intended",
+                name,
+                divergence,
+                BLESS_VAR,
+                path.display(),
+            )
+        }
+    }
+}
+
+/// True if two coordinates are equal up to [`TOLERANCE`].
+///
+/// [`TOLERANCE`]: constant.TOLERANCE.html (The TOLERANCE constant)
+fn close(lft: f64, rgt: f64) -> bool {
+    (lft - rgt).abs() <= TOLERANCE * lft.abs().max(rgt.abs()).max(1.0)
+}
+
+/// Description of the first point where two series diverge, if any.
+fn first_divergence(expected: &Series, actual: &Series) -> Option<String> {
+    let lines: BTSet<&String> = expected.keys().chain(actual.keys()).collect();
+    for line in lines {
+        let (expected, actual) = match (expected.get(line), actual.get(line)) {
+            (Some(expected), Some(actual)) => (expected, actual),
+            (Some(_), None) => return Some(format!("line `{}` is missing", line)),
+            (None, Some(_)) => return Some(format!("line `{}` is unexpected", line)),
+            (None, None) => unreachable!(),
+        };
+        for (idx, (exp, act)) in expected.iter().zip(actual.iter()).enumerate() {
+            if !close(exp.0, act.0) || !close(exp.1, act.1) {
+                return Some(format!(
+                    "line `{}`, point #{}: expected {:?}, got {:?}",
+                    line, idx, exp, act
+                ));
+            }
+        }
+        if expected.len() != actual.len() {
+            let idx = expected.len().min(actual.len());
+            return Some(format!(
+                "line `{}`: expected {} points, got {}, point #{} is {:?}",
+                line,
+                expected.len(),
+                actual.len(),
+                idx,
+                expected.get(idx).or_else(|| actual.get(idx)).unwrap(),
+            ));
+        }
+    }
+    None
+}
+
+/// Declares the cases, one test per case.
+macro_rules! cases {
+    ($($test:ident: $trace:ident, $x_axis:ident, $y_axis:ident, $quality:ident;)*) => {
+        /// All the cases.
+        fn cases() -> Vec<Case> {
+            vec![$(
+                Case {
+                    trace: Trace::$trace,
+                    x_axis: chart::axis::XAxis::$x_axis,
+                    y_axis: chart::axis::YAxis::$y_axis,
+                    quality: Quality::$quality,
+                },
+            )*]
+        }
+
+        $(
+            #[test]
+            fn $test() {
+                Case {
+                    trace: Trace::$trace,
+                    x_axis: chart::axis::XAxis::$x_axis,
+                    y_axis: chart::axis::YAxis::$y_axis,
+                    quality: Quality::$quality,
+                }
+                .check()
+            }
+        )*
+    };
+}
+
+cases! {
+    steady_total_size: Steady, Time, TotalSize, Native;
+    bursts_total_size: Bursts, Time, TotalSize, Low;
+    steady_alloc_count: Steady, Time, AllocCount, Native;
+    bursts_alloc_count: Bursts, Time, AllocCount, Low;
+    steady_churn_ratio: Steady, Time, ChurnRatio, Native;
+    bursts_churn_ratio: Bursts, Time, ChurnRatio, Medium;
+    steady_large_alloc_count: Steady, Time, LargeAllocCount, Native;
+    bursts_large_alloc_count: Bursts, Time, LargeAllocCount, Medium;
+    steady_alloc_rate: Steady, Time, AllocRate, Native;
+    bursts_alloc_rate: Bursts, Time, AllocRate, Medium;
+    steady_segment_size: Steady, Time, SegmentSize, Native;
+    bursts_segment_size: Bursts, Time, SegmentSize, Low;
+    steady_heap_size: Steady, Time, HeapSize, Native;
+    bursts_heap_size: Bursts, Time, HeapSize, Low;
+    steady_live_lifetime: Steady, Time, LiveLifetime, Native;
+    bursts_live_lifetime: Bursts, Time, LiveLifetime, Medium;
+    steady_alloc_size: Steady, Lifetime, AllocSize, Native;
+    bursts_alloc_size: Bursts, Lifetime, AllocSize, Native;
+}
+
+#[test]
+fn every_chart_kind_has_cases() {
+    let cases = cases();
+    for x_axis in chart::axis::XAxis::all() {
+        for y_axis in x_axis.y_axes() {
+            let count = cases
+                .iter()
+                .filter(|case| case.x_axis == x_axis && case.y_axis == y_axis)
+                .count();
+            assert!(
+                count >= MIN_CASES,
+                "[golden] charts of the {} over the {} have {} case(s), at least {} are needed",
+                y_axis,
+                x_axis,
+                count,
+                MIN_CASES,
+            )
+        }
+    }
+}
+
+#[test]
+fn divergences() {
+    let series = |points: Vec<(f64, f64)>| -> Series {
+        Some(("everything".to_string(), points))
+            .into_iter()
+            .collect()
+    };
+    let expected = series(vec![(0.0, 8.0), (0.5, 16.0)]);
+
+    assert_eq!(first_divergence(&expected, &expected), None);
+    assert_eq!(
+        first_divergence(&expected, &series(vec![(0.0, 8.0), (0.5, 16.0 + 1e-9)])),
+        None
+    );
+    assert_eq!(
+        first_divergence(&expected, &series(vec![(0.0, 8.0), (0.5, 17.0)])),
+        Some("line `everything`, point #1: expected (0.5, 16.0), got (0.5, 17.0)".into())
+    );
+    assert_eq!(
+        first_divergence(&expected, &series(vec![(0.0, 8.0)])),
+        Some("line `everything`: expected 2 points, got 1, point #1 is (0.5, 16.0)".into())
+    );
+    assert_eq!(
+        first_divergence(&expected, &Series::new()),
+        Some("line `everything` is missing".into())
+    );
+}
@@ -0,0 +1,165 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Allocation size over lifetime scatter chart.
+//!
+//! Plots the allocations created and dead in the time window, with their lifetime on the x-axis and
+//! their size on the y-axis. Profiles easily have millions of allocations, so each line (filters,
+//! catch-all and everything) only keeps a [uniform sample] of at most [`sample_size`] allocations.
+//! Samples are updated incrementally as allocations die. Changing the filters resets the chart,
+//! which resamples all the allocations.
+//!
+//! [uniform sample]: ../../point/scatter/struct.Sample.html#method.offer (Sample::offer)
+//! [`sample_size`]: fn.sample_size.html (The sample_size function)
+
+prelude! {}
+
+#[cfg(any(test, feature = "server"))]
+use base::rand::{rngs::SmallRng, SeedableRng};
+#[cfg(any(test, feature = "server"))]
+use point::scatter::{ScatterPoint, ScatterPoints};
+
+/// Default maximum number of allocations sampled for each line.
+pub const DEFAULT_SAMPLE_SIZE: usize = 500;
+
+/// Maximum number of allocations sampled for each line.
+#[cfg(any(test, feature = "server"))]
+static SAMPLE_SIZE: sync::atomic::AtomicUsize = sync::atomic::AtomicUsize::new(DEFAULT_SAMPLE_SIZE);
+
+/// Maximum number of allocations sampled for each line.
+#[cfg(any(test, feature = "server"))]
+pub fn sample_size() -> usize {
+    SAMPLE_SIZE.load(sync::atomic::Ordering::Relaxed)
+}
+
+/// Sets the maximum number of allocations sampled for each line.
+///
+/// Only affects the samples built afterwards.
+#[cfg(any(test, feature = "server"))]
+pub fn set_sample_size(size: usize) {
+    SAMPLE_SIZE.store(size, sync::atomic::Ordering::Relaxed)
+}
+
+/// Allocation size over lifetime scatter chart.
+#[derive(Debug)]
+pub struct ScatterChart {
+    /// UID of the last allocation, and timestamp of the last deallocation.
+    #[cfg(any(test, feature = "server"))]
+    last: Option<(uid::Alloc, time::SinceStart)>,
+    /// Samples of the lines.
+    #[cfg(any(test, feature = "server"))]
+    points: ScatterPoints,
+    /// Random generator of the samples.
+    ///
+    /// Re-seeded on reset, so that reloading the same data yields the same samples.
+    #[cfg(any(test, feature = "server"))]
+    rng: SmallRng,
+}
+
+impl ScatterChart {
+    /// Seed of the random generator.
+    #[cfg(any(test, feature = "server"))]
+    const SEED: u64 = 42;
+
+    /// Constructor.
+    pub fn new(_filters: &filter::Filters) -> Self {
+        Self {
+            #[cfg(any(test, feature = "server"))]
+            last: None,
+            #[cfg(any(test, feature = "server"))]
+            points: ScatterPoints::new(),
+            #[cfg(any(test, feature = "server"))]
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl ScatterChart {
+    /// Retrieves the new points since the last time it was called.
+    ///
+    /// Yields the whole samples whenever allocations died since the last call.
+    pub fn new_points(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        _resolution: chart::settings::Resolution,
+        _quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<Points>> {
+        let data = data::get()?;
+
+        if !data.has_new_stuff_since(self.last.clone()) {
+            return Ok(None);
+        }
+        if init {
+            self.reset(filters);
+        }
+
+        let time_window = time_windopt.to_time_window(|| *data.current_time());
+        let capacity = sample_size();
+        let mut changed = init;
+
+        let (points, rng) = (&mut self.points, &mut self.rng);
+
+        data.iter_new_events(self.last.clone(), |new_or_dead| {
+            let (tod, alloc) = match new_or_dead {
+                Either::Left(_) => return Ok(true),
+                Either::Right(dead) => dead,
+            };
+            match time_window.cmp(tod) {
+                base::RangeCmp::Below => return Ok(true),
+                base::RangeCmp::Above => return Ok(false),
+                base::RangeCmp::Inside => (),
+            }
+            if time_window.cmp(alloc.toc) == base::RangeCmp::Below {
+                return Ok(true);
+            }
+
+            let line = if let Some(f_uid) = filters.find_match(data.current_time(), alloc) {
+                uid::Line::Filter(f_uid)
+            } else {
+                uid::Line::CatchAll
+            };
+            let point = ScatterPoint::of_dead(alloc, tod);
+            points.sample_mut(line).offer(point.clone(), capacity, rng);
+            points
+                .sample_mut(uid::Line::Everything)
+                .offer(point, capacity, rng);
+            changed = true;
+
+            Ok(true)
+        })?;
+
+        self.last = data.last_events();
+
+        if changed {
+            Ok(Some(Points::Scatter(self.points.clone())))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Resets (drops) all its samples.
+    pub fn reset(&mut self, _filters: &filter::Filters) {
+        self.last = None;
+        self.points = ScatterPoints::new();
+        self.rng = SmallRng::seed_from_u64(Self::SEED);
+    }
+}
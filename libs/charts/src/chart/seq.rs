@@ -0,0 +1,285 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Ordering of the messages carrying points.
+//!
+//! Points reach a client either as `NewPoints`, which overwrite the points of a chart, or as
+//! `AddPoints`, which extend them. Nothing guarantees that a reload sent before some new points
+//! does not arrive after them, in which case the client would drop newer points. So, the server
+//! stamps the points of each chart with a sequence number, see [`Sequencer`]. Clients only apply
+//! the points that come after the ones they applied last, see [`Tracker`]:
+//!
+//! - points older than the last ones applied are ignored;
+//! - overwriting points are applied as long as they are newer than the last ones applied;
+//! - extending points are applied only if they come right after the last ones applied. Otherwise
+//!   some points are missing, and the client asks the server to send all the points of the chart
+//!   again.
+//!
+//! [`Sequencer`]: struct.Sequencer.html (The Sequencer struct)
+//! [`Tracker`]: struct.Tracker.html (The Tracker struct)
+
+prelude! {}
+
+/// Sequence number of the points of a chart.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub struct Seq(u64);
+impl Seq {
+    /// Sequence number following this one.
+    pub fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+}
+base::implement! {
+    impl Seq {
+        Display {
+            |&self, fmt| write!(fmt, "seq#{}", self.0)
+        }
+    }
+}
+
+/// Issues the sequence numbers of the points sent to a client, on the server side.
+#[derive(Debug, Clone, Default)]
+pub struct Sequencer {
+    /// Last sequence number issued for each chart.
+    last: BTMap<uid::Chart, Seq>,
+}
+impl Sequencer {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stamps the points of all the charts in some points with their next sequence number.
+    pub fn stamp(&mut self, points: &mut point::ChartPoints) {
+        let uids: Vec<uid::Chart> = points.keys().cloned().collect();
+        for uid in uids {
+            let seq = self.last.entry(uid).or_insert_with(Seq::default);
+            *seq = seq.next();
+            points.set_seq(uid, *seq)
+        }
+    }
+}
+
+/// Outcome of checking the sequence number of some points, see [`Tracker::check`].
+///
+/// [`Tracker::check`]: struct.Tracker.html#method.check (The check method of Tracker)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeqCheck {
+    /// The points should be applied.
+    Apply,
+    /// The points should be ignored.
+    Ignore,
+    /// The points should be ignored, and the client should ask for all the points of the chart.
+    Resend,
+}
+
+/// Sequence numbers of the points applied by a client.
+#[derive(Debug, Clone, Default)]
+pub struct Tracker {
+    /// Last sequence number applied for each chart.
+    last: BTMap<uid::Chart, Seq>,
+    /// Charts the client already asked all the points of, waiting for overwriting points.
+    resending: BTSet<uid::Chart>,
+}
+impl Tracker {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the sequence number of some points for a chart, `overwrite` if they overwrite the
+    /// points of the chart.
+    ///
+    /// Points without a sequence number are always applied. Once some points are missing, only
+    /// asks for all the points once: the next checks yield [`Ignore`] until overwriting points are
+    /// applied.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, chart::seq::*};
+    /// let (mut sequencer, mut tracker) = (Sequencer::new(), Tracker::new());
+    /// let uid = uid::Chart::from(0);
+    /// let mut seq = || {
+    ///     let mut points = point::ChartPoints::new();
+    ///     points.insert(uid, point::TimeSizePoints::new().into());
+    ///     sequencer.stamp(&mut points);
+    ///     points.seq(uid)
+    /// };
+    /// let (fst, snd, thd, fth) = (seq(), seq(), seq(), seq());
+    ///
+    /// assert_eq!(tracker.check(uid, fst, false), SeqCheck::Apply);
+    /// assert_eq!(tracker.check(uid, fst, false), SeqCheck::Ignore);
+    /// // `snd` is missing.
+    /// assert_eq!(tracker.check(uid, thd, false), SeqCheck::Resend);
+    /// assert_eq!(tracker.check(uid, fth, false), SeqCheck::Ignore);
+    /// // Overwriting points can skip some sequence numbers.
+    /// assert_eq!(tracker.check(uid, fth, true), SeqCheck::Apply);
+    /// assert_eq!(tracker.check(uid, snd, true), SeqCheck::Ignore);
+    /// ```
+    ///
+    /// [`Ignore`]: enum.SeqCheck.html#variant.Ignore (The Ignore variant of SeqCheck)
+    pub fn check(&mut self, uid: uid::Chart, seq: Option<Seq>, overwrite: bool) -> SeqCheck {
+        let seq = if let Some(seq) = seq {
+            seq
+        } else {
+            return SeqCheck::Apply;
+        };
+        let last = self.last.get(&uid).cloned().unwrap_or_default();
+
+        if seq <= last {
+            SeqCheck::Ignore
+        } else if overwrite || seq == last.next() {
+            let _ = self.last.insert(uid, seq);
+            if overwrite {
+                let _ = self.resending.remove(&uid);
+            }
+            SeqCheck::Apply
+        } else if self.resending.insert(uid) {
+            SeqCheck::Resend
+        } else {
+            SeqCheck::Ignore
+        }
+    }
+
+    /// Forgets about a chart.
+    pub fn forget(&mut self, uid: uid::Chart) {
+        let _ = self.last.remove(&uid);
+        let _ = self.resending.remove(&uid);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// UID of the only chart.
+    fn uid() -> uid::Chart {
+        uid::Chart::from(0)
+    }
+
+    /// Points of a chart from `(seconds, size)` pairs.
+    fn points(vals: &[(u64, u64)]) -> Points {
+        let points: point::TimeSizePoints = vals
+            .iter()
+            .map(|(secs, size)| {
+                let mut vals = PointVal::empty();
+                vals.map
+                    .insert(uid::Line::Everything, point::Size::new(*size));
+                Point::new(time::SinceStart::from_secs(*secs), vals)
+            })
+            .collect();
+        points.into()
+    }
+
+    /// Server side: the points of the chart, and the sequencer.
+    struct Server {
+        vals: Vec<(u64, u64)>,
+        seqs: Sequencer,
+    }
+    impl Server {
+        fn new() -> Self {
+            Self {
+                vals: vec![],
+                seqs: Sequencer::new(),
+            }
+        }
+        /// Stamps the points of a message.
+        fn msg(&mut self, vals: &[(u64, u64)], overwrite: bool) -> (point::ChartPoints, bool) {
+            let mut msg = point::ChartPoints::new();
+            msg.insert(uid(), points(vals));
+            self.seqs.stamp(&mut msg);
+            (msg, overwrite)
+        }
+        /// A new point, sent as `AddPoints`.
+        fn tick(&mut self) -> (point::ChartPoints, bool) {
+            let val = (self.vals.len() as u64, 10 * self.vals.len() as u64);
+            self.vals.push(val);
+            self.msg(&[val], false)
+        }
+        /// All the points, sent as `NewPoints`.
+        fn reload(&mut self) -> (point::ChartPoints, bool) {
+            let vals = self.vals.clone();
+            self.msg(&vals, true)
+        }
+    }
+
+    /// Client side: a mirror of the points of the chart.
+    struct Client {
+        points: Option<Points>,
+        tracker: Tracker,
+    }
+    impl Client {
+        fn new() -> Self {
+            Self {
+                points: None,
+                tracker: Tracker::new(),
+            }
+        }
+        /// Receives a message, returns `true` if the client asks for all the points.
+        fn receive(&mut self, (mut msg, overwrite): (point::ChartPoints, bool)) -> bool {
+            let mut points = msg.remove(&uid()).unwrap();
+            match self.tracker.check(uid(), msg.seq(uid()), overwrite) {
+                SeqCheck::Apply => (),
+                SeqCheck::Ignore => return false,
+                SeqCheck::Resend => return true,
+            }
+            match self.points.as_mut() {
+                Some(current) if !overwrite => {
+                    current.extend(&mut points).unwrap();
+                }
+                _ => self.points = Some(points),
+            }
+            false
+        }
+    }
+
+    #[test]
+    fn slow_reload() {
+        let (mut server, mut client) = (Server::new(), Client::new());
+        assert!(!client.receive(server.reload()));
+        assert!(!client.receive(server.tick()));
+
+        // A reload takes a while, ticks go through in the meantime.
+        let slow = server.reload();
+        let (fast_1, fast_2) = (server.tick(), server.tick());
+        assert!(client.receive(fast_1));
+        // Only asks once.
+        assert!(!client.receive(fast_2));
+        assert!(!client.receive(slow.clone()));
+
+        // The server answers with all the points, the old reload is ignored.
+        let resend = server.reload();
+        let fast_3 = server.tick();
+        assert!(!client.receive(resend));
+        assert!(!client.receive(slow));
+        assert!(!client.receive(fast_3));
+
+        let expected = points(&server.vals).series();
+        assert_eq!(client.points.unwrap().series(), expected);
+        assert_eq!(expected[&uid::Line::Everything].len(), 4);
+    }
+
+    #[test]
+    fn unsequenced_points() {
+        let mut tracker = Tracker::new();
+        assert_eq!(tracker.check(uid(), None, false), SeqCheck::Apply);
+        assert_eq!(tracker.check(uid(), None, false), SeqCheck::Apply);
+    }
+}
@@ -21,6 +21,8 @@
 
 prelude! {}
 
+use chart::transform::Transform;
+
 /// A chart's display mode.
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
 pub enum DisplayMode {
@@ -95,24 +97,86 @@ impl fmt::Display for DisplayMode {
     }
 }
 
+/// Bounds of the number of points across the width of a chart users can ask for, see
+/// [`Chart::set_max_points`].
+///
+/// [`Chart::set_max_points`]: struct.Chart.html#method.set_max_points (The set_max_points method)
+pub const MAX_POINTS_BOUNDS: (u32, u32) = (50, 5_000);
+
 /// Resolution.
 ///
 /// Mostly used so that the client can send the resolution of each chart to the server. The reason
 /// is that resolution is taken into account when extracting the actual points to send to the
 /// client. This extraction will typically compress points that are too close together, for both
 /// readability and performance.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Resolution {
     /// Width.
     pub width: u32,
     /// Height.
     pub height: u32,
+    /// Number of points across the width users asked for, overrides the quality.
+    ///
+    /// Not part of what the client measures, see [`Chart::points_resolution`].
+    ///
+    /// [`Chart::points_resolution`]: struct.Chart.html#method.points_resolution (The points_resolution method)
+    #[serde(default)]
+    pub max_points: Option<u32>,
+}
+
+impl Resolution {
+    /// Maximal number of points across the width of the chart for some quality.
+    ///
+    /// `None` if the quality does not bound the number of points. The number of points users asked
+    /// for, if any, overrides the quality.
+    ///
+    /// ```rust
+    /// # use charts::chart::settings::{Quality, Resolution};
+    /// let mut resolution = Resolution::from((1000, 400));
+    /// assert_eq!(resolution.max_points(Quality::Low), Some(100));
+    /// assert_eq!(resolution.max_points(Quality::Medium), Some(200));
+    /// assert_eq!(resolution.max_points(Quality::High), Some(500));
+    /// assert_eq!(resolution.max_points(Quality::Native), None);
+    /// // Never zero, even for tiny charts.
+    /// assert_eq!(Resolution::from((3, 2)).max_points(Quality::Medium), Some(1));
+    ///
+    /// resolution.max_points = Some(1_500);
+    /// assert_eq!(resolution.max_points(Quality::Low), Some(1_500));
+    /// assert_eq!(resolution.max_points(Quality::Native), Some(1_500));
+    /// resolution.max_points = Some(0);
+    /// assert_eq!(resolution.max_points(Quality::Medium), Some(1));
+    /// ```
+    pub fn max_points(&self, quality: Quality) -> Option<u32> {
+        if let Some(max_points) = self.max_points {
+            return Some(std::cmp::max(max_points, 1));
+        }
+        quality
+            .px_per_point()
+            .map(|px_per_point| std::cmp::max(self.width / px_per_point, 1))
+    }
+
+    /// Minimal time spacing between two points, given the duration of the run and a quality.
+    ///
+    /// Points closer than this are merged, and gaps shorter than this are not displayed. Zero for
+    /// [native quality], which does not merge anything.
+    ///
+    /// [native quality]: enum.Quality.html#variant.Native (The Native variant of Quality)
+    pub fn min_time_spacing(
+        &self,
+        quality: Quality,
+        duration: &time::SinceStart,
+    ) -> time::SinceStart {
+        match self.max_points(quality) {
+            Some(max_points) => *duration / max_points,
+            None => time::SinceStart::zero(),
+        }
+    }
 }
 
 base::implement! {
     impl Resolution {
         From {
-            from (u32, u32) => |(width, height)| Self { width, height },
+            from (u32, u32) => |(width, height)| Self { width, height, max_points: None },
         }
         Display {
             |&self, fmt| write!(fmt, "{}x{}", self.width, self.height),
@@ -120,6 +184,431 @@ base::implement! {
     }
 }
 
+/// Quality of the points of a chart.
+///
+/// Scales the number of points the server generates for the resolution of a chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quality {
+    /// Few points, for large traces or slow machines.
+    Low,
+    /// Default quality.
+    Medium,
+    /// Many points.
+    High,
+    /// One point per event, no merging at all.
+    Native,
+}
+impl Default for Quality {
+    fn default() -> Self {
+        Self::Medium
+    }
+}
+impl Quality {
+    /// Short description of the quality.
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Native => "native",
+        }
+    }
+
+    /// Width of the chart per point, in pixels, `None` for native quality.
+    pub fn px_per_point(self) -> Option<u32> {
+        match self {
+            Self::Low => Some(10),
+            Self::Medium => Some(5),
+            Self::High => Some(2),
+            Self::Native => None,
+        }
+    }
+
+    /// Warning about the quality, if any.
+    pub fn warning(self) -> Option<&'static str> {
+        match self {
+            Self::Native => Some(
+                "native quality sends one point per event, \
+                which can be a lot of data for large traces",
+            ),
+            Self::Low | Self::Medium | Self::High => None,
+        }
+    }
+
+    /// List of all the qualities.
+    pub fn all() -> Vec<Self> {
+        base::debug_do! {
+            // If you get an error here, it means the definition of `Quality` changed. You need to
+            // update the following `match` statement, as well as the list returned by this function
+            // (below).
+            match Self::Low {
+                Self::Low
+                | Self::Medium
+                | Self::High
+                | Self::Native => ()
+            }
+        }
+        vec![Self::Low, Self::Medium, Self::High, Self::Native]
+    }
+}
+impl fmt::Display for Quality {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.desc().fmt(fmt)
+    }
+}
+
+/// Angle of the labels of the x-axis.
+///
+/// Text can only be rotated by quarter turns when drawing charts, hence the two options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LabelAngle {
+    /// Horizontal labels.
+    Flat,
+    /// Labels rotated by a quarter turn.
+    Vertical,
+}
+impl LabelAngle {
+    /// Angle in degrees.
+    pub fn degrees(self) -> u32 {
+        match self {
+            Self::Flat => 0,
+            Self::Vertical => 90,
+        }
+    }
+}
+
+/// Tick configuration of the axes of a chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickConf {
+    /// Minimal distance between two ticks of the x-axis, in pixels.
+    pub x_spacing: u32,
+    /// Minimal distance between two ticks of the y-axis, in pixels.
+    pub y_spacing: u32,
+    /// Angle of the labels of the x-axis.
+    pub x_angle: LabelAngle,
+}
+impl Default for TickConf {
+    fn default() -> Self {
+        Self {
+            x_spacing: 100,
+            y_spacing: 30,
+            x_angle: LabelAngle::Flat,
+        }
+    }
+}
+impl TickConf {
+    /// Smallest legal spacing between two ticks.
+    pub const MIN_SPACING: u32 = 10;
+    /// Width under which the auto mode rotates the labels of the x-axis.
+    const NARROW_WIDTH: u32 = 500;
+
+    /// Configuration derived from the resolution of a chart.
+    ///
+    /// Narrow charts get vertical x-axis labels, which can be closer to each other.
+    ///
+    /// ```rust
+    /// # use charts::chart::settings::*;
+    /// let wide = TickConf::auto(Resolution::from((1200, 400)));
+    /// assert_eq!(wide.x_angle, LabelAngle::Flat);
+    /// assert_eq!(wide.x_labels(1200), 12);
+    /// assert_eq!(wide.y_labels(400), 8);
+    ///
+    /// let narrow = TickConf::auto(Resolution::from((300, 200)));
+    /// assert_eq!(narrow.x_angle, LabelAngle::Vertical);
+    /// assert_eq!(narrow.x_labels(300), 7);
+    /// assert_eq!(narrow.y_labels(200), 5);
+    /// ```
+    pub fn auto(resolution: Resolution) -> Self {
+        let (x_spacing, x_angle) = if resolution.width < Self::NARROW_WIDTH {
+            (40, LabelAngle::Vertical)
+        } else {
+            (100, LabelAngle::Flat)
+        };
+        let y_spacing = if resolution.height < 300 { 40 } else { 50 };
+        Self {
+            x_spacing,
+            y_spacing,
+            x_angle,
+        }
+    }
+
+    /// Number of labels on an axis of some length, at least two.
+    fn labels(length: u32, spacing: u32) -> usize {
+        let spacing = spacing.max(Self::MIN_SPACING);
+        ((length / spacing) as usize).max(2)
+    }
+    /// Number of labels on an x-axis of some width.
+    pub fn x_labels(&self, width: u32) -> usize {
+        Self::labels(width, self.x_spacing)
+    }
+    /// Number of labels on a y-axis of some height.
+    pub fn y_labels(&self, height: u32) -> usize {
+        Self::labels(height, self.y_spacing)
+    }
+
+    /// Checks whether `self` is legal.
+    pub fn is_legal(&self) -> Option<String> {
+        if self.x_spacing < Self::MIN_SPACING || self.y_spacing < Self::MIN_SPACING {
+            Some(format!(
+                "illegal tick spacing, expected at least {} pixels",
+                Self::MIN_SPACING
+            ))
+        } else {
+            None
+        }
+    }
+}
+impl fmt::Display for TickConf {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "x every {}px at {}°, y every {}px",
+            self.x_spacing,
+            self.x_angle.degrees(),
+            self.y_spacing
+        )
+    }
+}
+
+/// Tick density of the axes of a chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ticks {
+    /// Derived from the resolution of the chart, see [`TickConf::auto`].
+    ///
+    /// [`TickConf::auto`]: struct.TickConf.html#method.auto (The TickConf::auto function)
+    Auto,
+    /// Set by the user.
+    Manual(TickConf),
+}
+impl Default for Ticks {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+impl Ticks {
+    /// Tick configuration for a chart of some resolution.
+    pub fn conf(&self, resolution: Resolution) -> TickConf {
+        match self {
+            Self::Auto => TickConf::auto(resolution),
+            Self::Manual(conf) => *conf,
+        }
+    }
+
+    /// True if the ticks are derived from the resolution.
+    pub fn is_auto(&self) -> bool {
+        match self {
+            Self::Auto => true,
+            Self::Manual(_) => false,
+        }
+    }
+}
+impl fmt::Display for Ticks {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Auto => write!(fmt, "auto"),
+            Self::Manual(conf) => conf.fmt(fmt),
+        }
+    }
+}
+
+/// Size percentile over a trailing time window.
+///
+/// Used by charts that only count allocations whose size is at or above the `percentile`-th
+/// percentile of the sizes of the allocations created during the last `window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizePercentile {
+    /// Percentile, between `1` and `100`.
+    pub percentile: u8,
+    /// Duration of the trailing window.
+    pub window: time::SinceStart,
+}
+
+impl Default for SizePercentile {
+    fn default() -> Self {
+        Self {
+            percentile: 99,
+            window: time::SinceStart::from_secs(10),
+        }
+    }
+}
+
+impl SizePercentile {
+    /// Percentile as a quantile, between `0` and `1`.
+    pub fn quantile(&self) -> f64 {
+        (self.percentile as f64) / 100.
+    }
+
+    /// Checks whether `self` is legal.
+    pub fn is_legal(&self) -> Option<String> {
+        if self.percentile == 0 || self.percentile > 100 {
+            Some(format!(
+                "illegal percentile {}, expected a value between 1 and 100",
+                self.percentile
+            ))
+        } else if self.window.is_zero() {
+            Some("the trailing window of a size percentile cannot be empty".into())
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for SizePercentile {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "p{} over the last {}",
+            self.percentile,
+            self.window.fmt_human()
+        )
+    }
+}
+
+/// Statistic over the lifetimes of the live allocations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, strum_macros::EnumIter)]
+pub enum LifetimeStat {
+    /// Mean lifetime.
+    Mean,
+    /// Median lifetime.
+    Median,
+    /// 95th percentile of the lifetimes.
+    P95,
+    /// Highest lifetime, *i.e.* the lifetime of the oldest live allocation.
+    Max,
+}
+
+impl Default for LifetimeStat {
+    fn default() -> Self {
+        Self::Median
+    }
+}
+
+impl LifetimeStat {
+    /// Description of a statistic.
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::Mean => "mean",
+            Self::Median => "median",
+            Self::P95 => "p95",
+            Self::Max => "max",
+        }
+    }
+
+    /// Quantile of the statistic, `None` for the mean.
+    pub fn quantile(self) -> Option<f64> {
+        match self {
+            Self::Mean => None,
+            Self::Median => Some(0.5),
+            Self::P95 => Some(0.95),
+            Self::Max => Some(1.0),
+        }
+    }
+
+    /// A list of all the statistics.
+    pub fn all() -> Vec<Self> {
+        use strum::IntoEnumIterator;
+        Self::iter().collect()
+    }
+}
+
+impl fmt::Display for LifetimeStat {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.desc().fmt(fmt)
+    }
+}
+
+/// How time charts label their x-axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, strum_macros::EnumIter)]
+pub enum TimeAxis {
+    /// Time since the start of the run, *e.g.* `1.5s` or `2m30s`.
+    SinceStart,
+    /// Wall-clock time, *e.g.* `14:02:37.5`.
+    WallClock,
+}
+
+impl Default for TimeAxis {
+    fn default() -> Self {
+        Self::SinceStart
+    }
+}
+
+impl TimeAxis {
+    /// Description of a time axis.
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::SinceStart => "since start",
+            Self::WallClock => "wall clock",
+        }
+    }
+
+    /// A list of all the time axes.
+    pub fn all() -> Vec<Self> {
+        use strum::IntoEnumIterator;
+        Self::iter().collect()
+    }
+
+    /// Label of a time, `start_date` is the start date of the run if known.
+    ///
+    /// Wall-clock labels fall back to the time since start when the start date is unknown.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, chart::settings::TimeAxis};
+    /// let secs = |secs| time::SinceStart::from(time::Duration::from_secs_f64(secs));
+    /// assert_eq!(TimeAxis::SinceStart.label(secs(0.0), None), "0s");
+    /// assert_eq!(TimeAxis::SinceStart.label(secs(1.5), None), "1.5s");
+    /// assert_eq!(TimeAxis::SinceStart.label(secs(150.0), None), "2m30s");
+    /// assert_eq!(TimeAxis::SinceStart.label(secs(3725.25), None), "1h2m5.25s");
+    ///
+    /// assert_eq!(TimeAxis::WallClock.label(secs(1.5), None), "1.5s");
+    /// let start = time::Date::from_timestamp(1566489242, 0);
+    /// // Can't check the hours as this depends on the local system time.
+    /// assert!(TimeAxis::WallClock.label(secs(1.5), Some(&start)).ends_with(":54:03.5"));
+    /// ```
+    pub fn label(self, time: time::SinceStart, start_date: Option<&time::Date>) -> String {
+        /// Removes the trailing zeros of the milliseconds, and the dot if they are all zeros.
+        fn trim_millis(s: &str) -> &str {
+            s.trim_end_matches('0').trim_end_matches('.')
+        }
+
+        match (self, start_date) {
+            (Self::WallClock, Some(start_date)) => {
+                let date = (start_date + time)
+                    .date()
+                    .format("%H:%M:%S%.3f")
+                    .to_string();
+                trim_millis(&date).into()
+            }
+            (Self::SinceStart, _) | (Self::WallClock, None) => {
+                let millis = time.as_millis();
+                let (hours, millis) = (millis / 3_600_000, millis % 3_600_000);
+                let (mins, millis) = (millis / 60_000, millis % 60_000);
+                let mut res = String::new();
+                if hours > 0 {
+                    res.push_str(&format!("{}h", hours))
+                }
+                if hours > 0 || mins > 0 {
+                    res.push_str(&format!("{}m", mins))
+                }
+                let secs = format!("{}.{:03}", millis / 1_000, millis % 1_000);
+                res.push_str(trim_millis(&secs));
+                res.push('s');
+                res
+            }
+        }
+    }
+}
+
+impl fmt::Display for TimeAxis {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.desc().fmt(fmt)
+    }
+}
+
+/// Preset time windows of the charts, in seconds, see [`Chart::time_window`].
+///
+/// [`Chart::time_window`]: struct.Chart.html#method.time_window (The time_window method)
+pub const TIME_WINDOW_PRESETS: [u64; 3] = [30, 2 * 60, 10 * 60];
+
 /// Settings for a chart.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Chart {
@@ -137,6 +626,45 @@ pub struct Chart {
     y_log: bool,
     /// Resolution of the chart, if it is known.
     resolution: Option<Resolution>,
+    /// Quality of the points.
+    #[serde(default)]
+    quality: Quality,
+    /// Number of points across the width of the chart users asked for, overrides the quality.
+    #[serde(default)]
+    max_points: Option<u32>,
+    /// Size percentile, for charts that need one.
+    size_percentile: Option<SizePercentile>,
+    /// Lifetime statistic, for charts that need one.
+    #[serde(default)]
+    lifetime_stat: Option<LifetimeStat>,
+    /// Transforms applied to the points of the chart, in order.
+    ///
+    /// Normalization is one of them: the values of each line are shown as a percentage of the
+    /// value of some line at the same point.
+    #[serde(default)]
+    transforms: Vec<Transform>,
+    /// Tick density of the axes.
+    #[serde(default)]
+    ticks: Ticks,
+    /// True if the server removes the points that do not change how the lines are drawn.
+    #[serde(default)]
+    dedup: bool,
+    /// If any, the chart only shows the points of this last amount of time.
+    #[serde(default)]
+    time_window: Option<time::SinceStart>,
+    /// True if the everything line is hidden.
+    ///
+    /// The server still generates its points, the client just does not draw it.
+    #[serde(default)]
+    hide_everything: bool,
+    /// How the x-axis of time charts labels times.
+    #[serde(default)]
+    time_axis: TimeAxis,
+    /// Lines hidden by clicking on them in the legend of the chart.
+    ///
+    /// The server still generates their points, the client just does not draw them.
+    #[serde(default)]
+    hidden: BTSet<uid::Line>,
 }
 impl Chart {
     /// Constructor.
@@ -149,16 +677,39 @@ impl Chart {
             x_log: false,
             y_log: false,
             resolution: None,
+            quality: Quality::default(),
+            max_points: None,
+            size_percentile: None,
+            lifetime_stat: None,
+            transforms: vec![],
+            ticks: Ticks::Auto,
+            dedup: false,
+            time_window: None,
+            hide_everything: false,
+            time_axis: TimeAxis::default(),
+            hidden: BTSet::new(),
         }
     }
 
     /// Constructor from a pair of axes.
     pub fn from_axes(
         title: impl Into<String>,
-        _x: chart::axis::XAxis,
+        x: chart::axis::XAxis,
         y: chart::axis::YAxis,
     ) -> Self {
-        Self::new(title, y.can_stack_area())
+        let mut slf = Self::new(title, y.can_stack_area());
+        if x == chart::axis::XAxis::Lifetime {
+            slf.x_log = true;
+            slf.y_log = true
+        }
+        if y.needs_size_percentile() {
+            slf.size_percentile = Some(SizePercentile::default())
+        }
+        if y.needs_lifetime_stat() {
+            slf.lifetime_stat = Some(LifetimeStat::default())
+        }
+        slf.dedup = y.can_dedup();
+        slf
     }
 
     /// Applies an update.
@@ -181,6 +732,29 @@ impl Chart {
                 self.set_resolution(resolution);
                 true
             }
+            SetQuality(quality) => self.set_quality(quality),
+            SetMaxPoints(max_points) => self.set_max_points(max_points),
+            SetSizePercentile(size_percentile) => self.set_size_percentile(size_percentile),
+            SetLifetimeStat(lifetime_stat) => self.set_lifetime_stat(lifetime_stat),
+            SetNormalize(line) => self.set_normalize(line),
+            SetTransforms(transforms) => self.set_transforms(transforms),
+            SetTicks(ticks) => {
+                self.set_ticks(ticks);
+                false
+            }
+            SetTimeWindow(time_window) => self.set_time_window(time_window),
+            SetHideEverything(hide) => {
+                self.set_hide_everything(hide);
+                false
+            }
+            SetTimeAxis(time_axis) => {
+                self.set_time_axis(time_axis);
+                false
+            }
+            SetLineHidden(line, hide) => {
+                self.set_line_hidden(line, hide);
+                false
+            }
         }
     }
 
@@ -255,9 +829,10 @@ impl Chart {
     }
     /// List of legal display modes for this chart.
     ///
-    /// None if the chart supports only one display mode.
+    /// None if the chart supports only one display mode. Transformed charts, including normalized
+    /// ones, are never stacked.
     pub fn legal_display_modes(&self) -> Option<Vec<DisplayMode>> {
-        if !self.can_stacked_area {
+        if !self.can_stacked_area || !self.transforms.is_empty() {
             None
         } else {
             Some(DisplayMode::all())
@@ -272,6 +847,311 @@ impl Chart {
     pub fn resolution(&self) -> Option<Resolution> {
         self.resolution
     }
+    /// Resolution used to generate the points of the chart, if one was set.
+    ///
+    /// Same as [`resolution`], with the number of points users asked for if any.
+    ///
+    /// [`resolution`]: #method.resolution (The resolution method)
+    pub fn points_resolution(&self) -> Option<Resolution> {
+        self.resolution.map(|resolution| Resolution {
+            max_points: self.max_points,
+            ..resolution
+        })
+    }
+
+    /// Quality of the points.
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+    /// Sets the quality of the points.
+    ///
+    /// Returns `true` if the quality changed.
+    pub fn set_quality(&mut self, quality: Quality) -> bool {
+        let changed = self.quality != quality;
+        self.quality = quality;
+        changed
+    }
+    /// Number of points across the width of the chart users asked for, if any.
+    pub fn max_points(&self) -> Option<u32> {
+        self.max_points
+    }
+    /// Sets the number of points across the width of the chart, `None` to follow the quality.
+    ///
+    /// The number of points is clamped to [`MAX_POINTS_BOUNDS`]. Returns `true` if the setting
+    /// changed.
+    ///
+    /// ```rust
+    /// # use charts::chart::settings::{Chart, MAX_POINTS_BOUNDS};
+    /// let mut settings = Chart::new("chart", false);
+    /// assert!(settings.set_max_points(Some(0)));
+    /// assert_eq!(settings.max_points(), Some(MAX_POINTS_BOUNDS.0));
+    /// assert!(!settings.set_max_points(Some(1)));
+    /// assert!(settings.set_max_points(Some(1_000_000)));
+    /// assert_eq!(settings.max_points(), Some(MAX_POINTS_BOUNDS.1));
+    /// assert!(settings.set_max_points(None));
+    /// assert_eq!(settings.max_points(), None);
+    /// ```
+    ///
+    /// [`MAX_POINTS_BOUNDS`]: constant.MAX_POINTS_BOUNDS.html (The MAX_POINTS_BOUNDS constant)
+    pub fn set_max_points(&mut self, max_points: Option<u32>) -> bool {
+        let (min, max) = MAX_POINTS_BOUNDS;
+        let max_points = max_points.map(|max_points| max_points.max(min).min(max));
+        let changed = self.max_points != max_points;
+        self.max_points = max_points;
+        changed
+    }
+    /// Description of the resolution of the points of the chart, if the resolution is known.
+    pub fn resolution_desc(&self) -> Option<String> {
+        self.points_resolution().map(|resolution| {
+            let points = match resolution.max_points(self.quality) {
+                Some(max_points) => format!("up to {} points", max_points),
+                None => "one point per event".into(),
+            };
+            if self.max_points.is_some() {
+                format!(
+                    "custom resolution: {} across {}px, the {} quality is ignored",
+                    points, resolution.width, self.quality
+                )
+            } else {
+                format!(
+                    "{} quality: {} across {}px",
+                    self.quality, points, resolution.width
+                )
+            }
+        })
+    }
+
+    /// Size percentile accessor, `None` if the chart does not need one.
+    pub fn size_percentile(&self) -> Option<SizePercentile> {
+        self.size_percentile
+    }
+    /// Sets the size percentile of the chart.
+    ///
+    /// Returns `true` if the chart needs a size percentile and `size_percentile` is legal and
+    /// different from the current one.
+    pub fn set_size_percentile(&mut self, size_percentile: SizePercentile) -> bool {
+        match self.size_percentile.as_mut() {
+            Some(current)
+                if *current != size_percentile && size_percentile.is_legal().is_none() =>
+            {
+                *current = size_percentile;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Lifetime statistic accessor, `None` if the chart does not need one.
+    pub fn lifetime_stat(&self) -> Option<LifetimeStat> {
+        self.lifetime_stat
+    }
+    /// Sets the lifetime statistic of the chart.
+    ///
+    /// Returns `true` if the chart needs a lifetime statistic and `lifetime_stat` is different
+    /// from the current one.
+    pub fn set_lifetime_stat(&mut self, lifetime_stat: LifetimeStat) -> bool {
+        match self.lifetime_stat.as_mut() {
+            Some(current) if *current != lifetime_stat => {
+                *current = lifetime_stat;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Line the chart is normalized by, if any.
+    pub fn normalize(&self) -> Option<uid::Line> {
+        self.transforms
+            .iter()
+            .find_map(|transform| match transform {
+                Transform::Normalize(line) => Some(*line),
+                Transform::Rate | Transform::Smooth(_) => None,
+            })
+    }
+    /// Sets the line the chart is normalized by.
+    ///
+    /// Normalization comes first, before the other transforms. Returns `true` if the setting
+    /// changed, see [`set_transforms`].
+    ///
+    /// [`set_transforms`]: #method.set_transforms (The set_transforms method)
+    pub fn set_normalize(&mut self, line: Option<uid::Line>) -> bool {
+        let transforms = line
+            .map(Transform::Normalize)
+            .into_iter()
+            .chain(
+                self.transforms
+                    .iter()
+                    .filter(|transform| !matches!(transform, Transform::Normalize(_)))
+                    .cloned(),
+            )
+            .collect();
+        self.set_transforms(transforms)
+    }
+
+    /// Transforms applied to the points of the chart, in order.
+    pub fn transforms(&self) -> &[Transform] {
+        &self.transforms
+    }
+    /// Sets the transforms applied to the points of the chart, ignored if one of them is illegal.
+    ///
+    /// Transforming a chart resets its display mode to normal. Returns `true` if the setting
+    /// changed.
+    ///
+    /// ```rust
+    /// # use charts::chart::{settings::Chart, transform::Transform};
+    /// let mut settings = Chart::new("chart", true);
+    /// assert!(settings.set_transforms(vec![Transform::Rate, Transform::Smooth(3)]));
+    /// assert!(!settings.set_transforms(vec![Transform::Smooth(0)]));
+    /// assert!(settings.legal_display_modes().is_none());
+    ///
+    /// assert!(settings.set_normalize(Some(charts::prelude::uid::Line::Everything)));
+    /// assert_eq!(settings.transforms().len(), 3);
+    /// assert!(settings.set_normalize(None));
+    /// assert_eq!(settings.transforms(), &[Transform::Rate, Transform::Smooth(3)]);
+    /// ```
+    pub fn set_transforms(&mut self, transforms: Vec<Transform>) -> bool {
+        if transforms
+            .iter()
+            .any(|transform| transform.is_legal().is_some())
+        {
+            return false;
+        }
+        if !transforms.is_empty() {
+            self.display_mode = DisplayMode::Normal
+        }
+        let changed = self.transforms != transforms;
+        self.transforms = transforms;
+        changed
+    }
+
+    /// Tick density of the axes.
+    pub fn ticks(&self) -> Ticks {
+        self.ticks
+    }
+    /// Sets the tick density of the axes, ignored if illegal.
+    pub fn set_ticks(&mut self, ticks: Ticks) {
+        match ticks {
+            Ticks::Manual(conf) if conf.is_legal().is_some() => (),
+            _ => self.ticks = ticks,
+        }
+    }
+
+    /// True if the server removes the points that do not change how the lines are drawn, see
+    /// [`TimePoints::dedup`].
+    ///
+    /// [`TimePoints::dedup`]: ../../point/enum.TimePoints.html#method.dedup (TimePoints::dedup)
+    pub fn dedup(&self) -> bool {
+        self.dedup
+    }
+    /// Sets the deduplication setting.
+    pub fn set_dedup(&mut self, dedup: bool) {
+        self.dedup = dedup
+    }
+
+    /// Time window of the chart: if any, the chart only shows the points of this last amount of
+    /// time.
+    pub fn time_window(&self) -> Option<time::SinceStart> {
+        self.time_window
+    }
+    /// Sets the time window of the chart, an empty window means no window.
+    ///
+    /// Returns `true` if the window changed.
+    pub fn set_time_window(&mut self, time_window: Option<time::SinceStart>) -> bool {
+        let time_window = time_window.filter(|window| !window.is_zero());
+        let changed = self.time_window != time_window;
+        self.time_window = time_window;
+        changed
+    }
+
+    /// Start of the time window of the chart when the current time is `now`, if any.
+    pub fn window_start(&self, now: time::SinceStart) -> Option<time::SinceStart> {
+        self.time_window.map(|window| {
+            if now > window {
+                now - window
+            } else {
+                time::SinceStart::zero()
+            }
+        })
+    }
+
+    /// Restricts the time window of all the charts to the time window of the chart.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, chart::settings::Chart};
+    /// let secs = time::SinceStart::from_secs;
+    /// let mut settings = Chart::new("chart", false);
+    /// let all = TimeWindopt::new(Some(secs(5)), None);
+    /// assert_eq!(settings.time_windopt(&all, secs(60)), all);
+    ///
+    /// settings.set_time_window(Some(secs(30)));
+    /// assert_eq!(
+    ///     settings.time_windopt(&all, secs(60)),
+    ///     TimeWindopt::new(Some(secs(30)), None),
+    /// );
+    /// // The window of all the charts starts after the one of the chart.
+    /// assert_eq!(settings.time_windopt(&all, secs(20)), all);
+    /// ```
+    pub fn time_windopt(&self, time_windopt: &TimeWindopt, now: time::SinceStart) -> TimeWindopt {
+        let mut res = time_windopt.clone();
+        if let Some(mut start) = self.window_start(now) {
+            if let Some(ubound) = res.ubound {
+                start = std::cmp::min(start, ubound)
+            }
+            res.lbound = Some(
+                res.lbound
+                    .map_or(start, |lbound| std::cmp::max(lbound, start)),
+            )
+        }
+        res
+    }
+
+    /// True if the everything line is hidden.
+    pub fn hide_everything(&self) -> bool {
+        self.hide_everything
+    }
+    /// Hides or shows the everything line.
+    pub fn set_hide_everything(&mut self, hide: bool) {
+        self.hide_everything = hide
+    }
+    /// Lines hidden from the legend of the chart.
+    pub fn hidden(&self) -> &BTSet<uid::Line> {
+        &self.hidden
+    }
+    /// Hides or shows a line, from the legend of the chart.
+    pub fn set_line_hidden(&mut self, line: uid::Line, hide: bool) {
+        if hide {
+            self.hidden.insert(line);
+        } else {
+            self.hidden.remove(&line);
+        }
+    }
+    /// True if a line is drawn, *i.e.* it is not hidden from the legend, and it is not the
+    /// everything line or the everything line is not hidden.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, chart::settings::Chart};
+    /// let mut settings = Chart::new("chart", false);
+    /// assert!(settings.shows_line(uid::Line::Everything));
+    /// settings.set_hide_everything(true);
+    /// assert!(!settings.shows_line(uid::Line::Everything));
+    /// assert!(settings.shows_line(uid::Line::CatchAll));
+    /// settings.set_line_hidden(uid::Line::CatchAll, true);
+    /// assert!(!settings.shows_line(uid::Line::CatchAll));
+    /// settings.set_line_hidden(uid::Line::CatchAll, false);
+    /// assert!(settings.shows_line(uid::Line::CatchAll));
+    /// ```
+    pub fn shows_line(&self, line: uid::Line) -> bool {
+        (!self.hide_everything || !line.is_everything()) && !self.hidden.contains(&line)
+    }
+
+    /// How the x-axis of time charts labels times.
+    pub fn time_axis(&self) -> TimeAxis {
+        self.time_axis
+    }
+    /// Sets how the x-axis of time charts labels times.
+    pub fn set_time_axis(&mut self, time_axis: TimeAxis) {
+        self.time_axis = time_axis
+    }
 
     /// Sets the x-axis-log setting.
     pub fn set_x_log(&mut self, x_log: bool) {
@@ -0,0 +1,540 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Quantile sketches, used by charts relying on percentiles.
+//!
+//! A [`QuantileSketch`] stores values in buckets growing exponentially, so that the memory it
+//! needs is logarithmic in the range of the values rather than linear in the number of values.
+//! The value of a quantile is approximated with a *relative accuracy* `alpha`: if the actual
+//! quantile is `v`, the sketch yields a value between `v * (1 - alpha)` and `v * (1 + alpha)`.
+//!
+//! Unlike most sketches, values can also be removed, which [`TrailingSketch`] uses to maintain
+//! quantiles over a trailing time window.
+//!
+//! When values are added in increasing order, a [`SortedSketch`] yields exact quantiles in
+//! logarithmic time. All the structures use the nearest [`rank`] of a quantile.
+//!
+//! [`QuantileSketch`]: struct.QuantileSketch.html (The QuantileSketch struct)
+//! [`TrailingSketch`]: struct.TrailingSketch.html (The TrailingSketch struct)
+//! [`SortedSketch`]: struct.SortedSketch.html (The SortedSketch struct)
+//! [`rank`]: fn.rank.html (The rank function)
+
+prelude! {}
+
+/// Default relative accuracy of the sketches.
+pub const DEFAULT_ALPHA: f64 = 0.01;
+
+/// Nearest rank of a quantile among `count > 0` values, between `1` and `count`.
+///
+/// Input `q` is clamped between `0` and `1`, `NaN` is `0`.
+///
+/// ```rust
+/// # use charts::chart::sketch::rank;
+/// assert_eq!(rank(0.5, 20), 10);
+/// assert_eq!(rank(0.95, 20), 19);
+/// assert_eq!(rank(0., 20), 1);
+/// assert_eq!(rank(f64::NAN, 20), 1);
+/// assert_eq!(rank(7., 20), 20);
+/// ```
+pub fn rank(q: f64, count: u64) -> u64 {
+    let q = if q.is_nan() { 0. } else { q.max(0.).min(1.) };
+    ((q * count as f64).ceil() as u64).max(1).min(count)
+}
+
+/// A quantile sketch over `u64` values.
+///
+/// ```rust
+/// # use charts::chart::sketch::QuantileSketch;
+/// let mut sketch = QuantileSketch::new(0.01).unwrap();
+/// for val in 1..=1000 {
+///     sketch.add(val)
+/// }
+/// assert_eq!(sketch.count(), 1000);
+///
+/// let p99 = sketch.quantile(0.99).unwrap() as f64;
+/// assert!(990. * 0.99 <= p99 && p99 <= 990. * 1.01);
+///
+/// // Removing the largest values lowers the 99th percentile.
+/// for val in 901..=1000 {
+///     sketch.remove(val).unwrap()
+/// }
+/// let p99 = sketch.quantile(0.99).unwrap() as f64;
+/// assert!(891. * 0.99 <= p99 && p99 <= 891. * 1.01);
+/// ```
+#[derive(Debug, Clone)]
+pub struct QuantileSketch {
+    /// Growth factor of the buckets: bucket `k` stores the values in `(gamma^(k-1), gamma^k]`.
+    gamma: f64,
+    /// Natural logarithm of `gamma`.
+    ln_gamma: f64,
+    /// Number of `0` values, which have no bucket.
+    zeros: u64,
+    /// Maps bucket indices to the number of values they contain.
+    buckets: BTMap<i32, u64>,
+    /// Total number of values.
+    count: u64,
+}
+
+impl QuantileSketch {
+    /// Constructor from a relative accuracy, fails if `alpha` is not in `(0, 1)`.
+    pub fn new(alpha: f64) -> Res<Self> {
+        if !(0. < alpha && alpha < 1.) {
+            bail!(
+                "illegal relative accuracy {} for quantile sketch, expected a value in (0, 1)",
+                alpha
+            )
+        }
+        let gamma = (1. + alpha) / (1. - alpha);
+        Ok(Self {
+            gamma,
+            ln_gamma: gamma.ln(),
+            zeros: 0,
+            buckets: BTMap::new(),
+            count: 0,
+        })
+    }
+
+    /// Number of values in the sketch.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+    /// True if the sketch contains no value.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Clears the sketch.
+    pub fn clear(&mut self) {
+        self.zeros = 0;
+        self.buckets.clear();
+        self.count = 0;
+    }
+
+    /// Index of the bucket of a (non-zero) value.
+    fn key(&self, val: u64) -> i32 {
+        debug_assert!(val > 0);
+        ((val as f64).ln() / self.ln_gamma).ceil() as i32
+    }
+
+    /// Representative value of a bucket.
+    fn val(&self, key: i32) -> u64 {
+        (2. * self.gamma.powi(key) / (self.gamma + 1.)).round() as u64
+    }
+
+    /// Adds a value.
+    pub fn add(&mut self, val: u64) {
+        if val == 0 {
+            self.zeros += 1
+        } else {
+            *self.buckets.entry(self.key(val)).or_insert(0) += 1
+        }
+        self.count += 1
+    }
+
+    /// Removes a value, fails if the value is not in the sketch.
+    pub fn remove(&mut self, val: u64) -> Res<()> {
+        if val == 0 {
+            if self.zeros == 0 {
+                bail!("trying to remove a value that is not in the sketch")
+            }
+            self.zeros -= 1
+        } else {
+            let key = self.key(val);
+            let remove_bucket = match self.buckets.get_mut(&key) {
+                Some(count) => {
+                    *count -= 1;
+                    *count == 0
+                }
+                None => bail!("trying to remove a value that is not in the sketch"),
+            };
+            if remove_bucket {
+                let _ = self.buckets.remove(&key);
+            }
+        }
+        self.count -= 1;
+        Ok(())
+    }
+
+    /// Bucket of a quantile, `None` if the sketch is empty.
+    ///
+    /// The inner option is `None` if the quantile is `0`, which has no bucket.
+    fn quantile_key(&self, q: f64) -> Option<Option<i32>> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = rank(q, self.count);
+
+        if rank <= self.zeros {
+            return Some(None);
+        }
+        let mut seen = self.zeros;
+        for (key, count) in &self.buckets {
+            seen += count;
+            if rank <= seen {
+                return Some(Some(*key));
+            }
+        }
+        unreachable!("the total number of values in the buckets is `self.count`")
+    }
+
+    /// Approximates a quantile, `None` if the sketch is empty.
+    ///
+    /// Input `q` is clamped between `0` and `1`.
+    pub fn quantile(&self, q: f64) -> Option<u64> {
+        self.quantile_key(q)
+            .map(|key_opt| key_opt.map(|key| self.val(key)).unwrap_or(0))
+    }
+
+    /// True if `val` is at or above a quantile, `false` if the sketch is empty.
+    ///
+    /// Values are compared at the sketch's precision: `val` is considered at or above the quantile
+    /// if it falls in the same bucket, even if the approximated quantile is larger than `val`.
+    pub fn reaches(&self, val: u64, q: f64) -> bool {
+        match self.quantile_key(q) {
+            None => false,
+            Some(None) => true,
+            Some(Some(key)) => val > 0 && self.key(val) >= key,
+        }
+    }
+}
+
+/// A quantile sketch over a trailing time window.
+///
+/// ```rust
+/// # use charts::{prelude::*, chart::sketch::TrailingSketch};
+/// let secs = time::SinceStart::from_secs;
+/// let mut sketch = TrailingSketch::new(secs(10), 0.01).unwrap();
+/// sketch.push(secs(0), 1_000);
+/// sketch.push(secs(5), 10);
+/// assert!(sketch.reaches(1_000, 1.));
+/// assert!(!sketch.reaches(10, 1.));
+///
+/// // The first value is more than ten seconds old now.
+/// sketch.push(secs(11), 10);
+/// assert_eq!(sketch.count(), 2);
+/// assert!(sketch.reaches(10, 1.));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TrailingSketch {
+    /// Duration of the trailing window.
+    window: time::SinceStart,
+    /// Values in the window, oldest first.
+    entries: std::collections::VecDeque<(time::SinceStart, u64)>,
+    /// Sketch over the values in the window.
+    sketch: QuantileSketch,
+}
+
+impl TrailingSketch {
+    /// Constructor.
+    pub fn new(window: time::SinceStart, alpha: f64) -> Res<Self> {
+        Ok(Self {
+            window,
+            entries: std::collections::VecDeque::new(),
+            sketch: QuantileSketch::new(alpha)?,
+        })
+    }
+
+    /// Number of values in the window.
+    pub fn count(&self) -> u64 {
+        self.sketch.count()
+    }
+
+    /// Clears the sketch.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.sketch.clear()
+    }
+
+    /// Adds a value, and drops the values that are out of the window ending at `timestamp`.
+    ///
+    /// Values must be pushed in chronological order.
+    pub fn push(&mut self, timestamp: time::SinceStart, val: u64) {
+        while let Some((oldest, old_val)) = self.entries.front().cloned() {
+            if oldest + self.window < timestamp {
+                let _ = self.entries.pop_front();
+                self.sketch
+                    .remove(old_val)
+                    .expect("values in the window are in the sketch")
+            } else {
+                break;
+            }
+        }
+        self.entries.push_back((timestamp, val));
+        self.sketch.add(val)
+    }
+
+    /// Approximates a quantile over the window, `None` if the window is empty.
+    pub fn quantile(&self, q: f64) -> Option<u64> {
+        self.sketch.quantile(q)
+    }
+
+    /// True if `val` is at or above a quantile over the window, see [`QuantileSketch::reaches`].
+    ///
+    /// [`QuantileSketch::reaches`]: struct.QuantileSketch.html#method.reaches
+    /// (QuantileSketch::reaches)
+    pub fn reaches(&self, val: u64, q: f64) -> bool {
+        self.sketch.reaches(val, q)
+    }
+}
+
+/// Exact quantiles over values added in increasing order, which can be removed.
+///
+/// Values are identified by keys, added in increasing order too. Removed values are only marked as
+/// such, and dropped once they are the majority. A Fenwick tree over the liveness of the values
+/// counts the live values by prefix, so that the live value of some rank is found in logarithmic
+/// time.
+///
+/// ```rust
+/// # use charts::chart::sketch::SortedSketch;
+/// let mut sketch = SortedSketch::new();
+/// for key in 1..=10 {
+///     sketch.add(key, 100 * key)
+/// }
+/// assert_eq!(sketch.quantile(0.5), Some(&500));
+///
+/// // Removing the smallest values raises the median.
+/// for key in 1..=4 {
+///     assert_eq!(sketch.remove(&key), Some(100 * key))
+/// }
+/// assert_eq!(sketch.len(), 6);
+/// assert_eq!(sketch.quantile(0.5), Some(&700));
+/// assert_eq!(sketch.nth(1), Some(&500));
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SortedSketch<K, V> {
+    /// Keys of the values, sorted.
+    keys: Vec<K>,
+    /// Values, same order as `keys`.
+    vals: Vec<V>,
+    /// Liveness of the values, same order as `keys`.
+    live: Vec<bool>,
+    /// Fenwick tree over the liveness of the values.
+    tree: Vec<u32>,
+    /// Number of live values.
+    count: u32,
+}
+
+impl<K, V> Default for SortedSketch<K, V> {
+    fn default() -> Self {
+        Self {
+            keys: vec![],
+            vals: vec![],
+            live: vec![],
+            tree: vec![],
+            count: 0,
+        }
+    }
+}
+
+impl<K: Ord, V> SortedSketch<K, V> {
+    /// Minimal number of values before removed ones are dropped.
+    const MIN_COMPACT_LEN: usize = 64;
+
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of live values.
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+    /// True if there are no live values.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Lowest set bit of a Fenwick tree index.
+    fn low_bit(idx: usize) -> usize {
+        idx & idx.wrapping_neg()
+    }
+
+    /// Number of live values among the first `len` ones.
+    fn prefix(&self, mut len: usize) -> u32 {
+        let mut sum = 0;
+        while len > 0 {
+            sum += self.tree[len - 1];
+            len -= Self::low_bit(len);
+        }
+        sum
+    }
+
+    /// Rebuilds the Fenwick tree from the liveness of the values.
+    fn rebuild_tree(&mut self) {
+        self.tree = self.live.iter().map(|live| *live as u32).collect();
+        let len = self.tree.len();
+        for idx in 1..=len {
+            let parent = idx + Self::low_bit(idx);
+            if parent <= len {
+                self.tree[parent - 1] += self.tree[idx - 1]
+            }
+        }
+    }
+
+    /// Drops the removed values if they are the majority.
+    fn compact_if_needed(&mut self) {
+        let len = self.keys.len();
+        if len < Self::MIN_COMPACT_LEN || 2 * (self.count as usize) > len {
+            return;
+        }
+        let live = std::mem::take(&mut self.live);
+        let mut live_iter = live.iter();
+        self.keys.retain(|_| *live_iter.next().unwrap());
+        let mut live_iter = live.iter();
+        self.vals.retain(|_| *live_iter.next().unwrap());
+        self.live = vec![true; self.keys.len()];
+        self.rebuild_tree()
+    }
+
+    /// Adds a value.
+    ///
+    /// Ignores values whose key is not greater than the key of the last value added.
+    pub fn add(&mut self, key: K, val: V) {
+        if self.keys.last().map(|last| *last >= key).unwrap_or(false) {
+            return;
+        }
+        // The new node of the tree covers the range of the values between the node's low bit and
+        // itself.
+        let idx = self.tree.len() + 1;
+        let node = 1 + self.prefix(idx - 1) - self.prefix(idx - Self::low_bit(idx));
+        self.keys.push(key);
+        self.vals.push(val);
+        self.live.push(true);
+        self.tree.push(node);
+        self.count += 1;
+    }
+
+    /// Removes the value of a key, yields it if it was live.
+    pub fn remove(&mut self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        let idx = match self.keys.binary_search(key) {
+            Ok(idx) if self.live[idx] => idx,
+            Ok(_) | Err(_) => return None,
+        };
+        self.live[idx] = false;
+        let mut node = idx + 1;
+        while node <= self.tree.len() {
+            self.tree[node - 1] -= 1;
+            node += Self::low_bit(node);
+        }
+        self.count -= 1;
+        let val = self.vals[idx].clone();
+        self.compact_if_needed();
+        Some(val)
+    }
+
+    /// The `nth` live value, starting at `1`, `None` if there are less than `nth` live values.
+    pub fn nth(&self, nth: usize) -> Option<&V> {
+        if nth == 0 || nth > self.len() {
+            return None;
+        }
+        let mut nth = nth as u32;
+        let len = self.tree.len();
+        let mut step = len.checked_next_power_of_two().unwrap_or(0);
+        let mut pos = 0;
+        while step > 0 {
+            if pos + step <= len && self.tree[pos + step - 1] < nth {
+                pos += step;
+                nth -= self.tree[pos - 1];
+            }
+            step >>= 1
+        }
+        Some(&self.vals[pos])
+    }
+
+    /// Quantile of the live values, `None` if there are none, see [`rank`].
+    ///
+    /// [`rank`]: fn.rank.html (The rank function)
+    pub fn quantile(&self, q: f64) -> Option<&V> {
+        if self.is_empty() {
+            return None;
+        }
+        self.nth(rank(q, self.count as u64) as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn relative_accuracy() {
+        let alpha = 0.02;
+        let mut sketch = QuantileSketch::new(alpha).unwrap();
+        let mut vals: Vec<u64> = (0..5_000u64).map(|n| (n * 7_919) % 1_000_003).collect();
+        for val in &vals {
+            sketch.add(*val)
+        }
+        vals.sort();
+
+        for percentile in 1..=100 {
+            let q = percentile as f64 / 100.;
+            let rank = ((q * vals.len() as f64).ceil() as usize).max(1);
+            let actual = vals[rank - 1] as f64;
+            let approx = sketch.quantile(q).unwrap() as f64;
+            assert!(
+                (approx - actual).abs() <= actual * alpha + 1.,
+                "p{}: expected {}, got {}",
+                percentile,
+                actual,
+                approx,
+            );
+            assert!(sketch.reaches(vals[rank - 1], q));
+        }
+    }
+
+    #[test]
+    fn illegal_removal() {
+        let mut sketch = QuantileSketch::new(DEFAULT_ALPHA).unwrap();
+        sketch.add(0);
+        sketch.add(100);
+        assert!(sketch.remove(1_000_000).is_err());
+        sketch.remove(0).unwrap();
+        assert!(sketch.remove(0).is_err());
+        sketch.remove(100).unwrap();
+        assert!(sketch.is_empty());
+        assert_eq!(sketch.quantile(0.5), None);
+        assert!(QuantileSketch::new(1.).is_err());
+    }
+
+    #[test]
+    fn compaction_keeps_the_live_values() {
+        let mut sketch = SortedSketch::new();
+        for key in 0..1000u64 {
+            sketch.add(key, key);
+            // Only keep multiples of 10.
+            if key >= 5 && (key - 5) % 10 != 0 {
+                assert_eq!(sketch.remove(&(key - 5)), Some(key - 5))
+            }
+        }
+        assert!(sketch.keys.len() < 1000);
+        assert_eq!(sketch.prefix(sketch.keys.len()), sketch.count);
+        // Live: 0, 10, ..., 990 and 995, ..., 999.
+        assert_eq!(sketch.len(), 100 + 5);
+        assert_eq!(sketch.nth(1), Some(&0));
+        assert_eq!(sketch.nth(100), Some(&990));
+        assert_eq!(sketch.nth(105), Some(&999));
+        assert_eq!(sketch.nth(106), None);
+        assert_eq!(sketch.remove(&990), Some(990));
+        assert_eq!(sketch.remove(&990), None);
+        assert_eq!(sketch.quantile(1.), Some(&999));
+    }
+}
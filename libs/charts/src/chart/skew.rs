@@ -0,0 +1,169 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Time synchronization checks between a client and the server.
+//!
+//! The allocation statistics the server sends periodically carry its current trace time, the
+//! duration of the run. Clients compare it with the time of the last point of each of their charts,
+//! and with the trace time of the previous statistics. A divergence larger than the [`tolerance`]
+//! is a [`Skew`]: the client and the server disagree on what the points are, and the client should
+//! ask the server to send the points again.
+//!
+//! [`tolerance`]: fn.tolerance.html (The tolerance function)
+//! [`Skew`]: struct.Skew.html (The Skew struct)
+
+prelude! {}
+
+/// Divergence tolerated for a run of some duration.
+///
+/// Points closer than a fraction of the duration of the run are merged, so the last point of a
+/// chart can lag a bit behind the trace time. The tolerance is 5% of the duration, and at least one
+/// second.
+///
+/// ```rust
+/// # use charts::{prelude::*, chart::skew::tolerance};
+/// assert_eq!(tolerance(time::SinceStart::from_secs(4)), time::SinceStart::one_sec());
+/// assert_eq!(tolerance(time::SinceStart::from_secs(100)), time::SinceStart::from_secs(5));
+/// ```
+pub fn tolerance(duration: time::SinceStart) -> time::SinceStart {
+    let tolerance = duration / 20;
+    if tolerance < time::SinceStart::one_sec() {
+        time::SinceStart::one_sec()
+    } else {
+        tolerance
+    }
+}
+
+/// A divergence between the time of a client and the time of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Skew {
+    /// Chart whose last point diverges, `None` if the trace time of the server went backwards.
+    pub chart: Option<uid::Chart>,
+    /// Time on the client side.
+    ///
+    /// Time of the last point of the chart, or previous trace time of the server if `chart` is
+    /// `None`.
+    pub client: time::SinceStart,
+    /// Trace time of the server.
+    pub server: time::SinceStart,
+}
+
+impl Skew {
+    /// Checks the time of the last point of a chart against the trace time of the server.
+    ///
+    /// If `bounded`, the points of the chart stop at the upper bound of the time window, so only
+    /// points after the trace time of the server are skews.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, chart::skew::Skew};
+    /// let secs = time::SinceStart::from_secs;
+    /// let uid = uid::Chart::from(0);
+    ///
+    /// // Lagging by 1% of the run.
+    /// assert_eq!(Skew::check_chart(uid, secs(99), secs(100), false), None);
+    /// // Lagging by a minute.
+    /// let skew = Skew::check_chart(uid, secs(40), secs(100), false).unwrap();
+    /// assert_eq!(skew.to_string(), "chart #0 at 40.000s, 60.000s behind the server (100.000s)");
+    /// // Fine if the chart stops at the end of the time window.
+    /// assert_eq!(Skew::check_chart(uid, secs(40), secs(100), true), None);
+    /// // Ahead of the server.
+    /// let skew = Skew::check_chart(uid, secs(110), secs(100), true).unwrap();
+    /// assert_eq!(skew.to_string(), "chart #0 at 110.000s, 10.000s ahead of the server (100.000s)");
+    /// ```
+    pub fn check_chart(
+        chart: uid::Chart,
+        last_point: time::SinceStart,
+        server: time::SinceStart,
+        bounded: bool,
+    ) -> Option<Self> {
+        let tolerance = tolerance(server);
+        let skewed = if last_point > server {
+            last_point - server > tolerance
+        } else {
+            !bounded && server - last_point > tolerance
+        };
+        if skewed {
+            Some(Self {
+                chart: Some(chart),
+                client: last_point,
+                server,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Checks the trace time of the server against its previous value.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, chart::skew::Skew};
+    /// let secs = time::SinceStart::from_secs;
+    /// assert_eq!(Skew::check_server(secs(100), secs(120)), None);
+    /// assert_eq!(Skew::check_server(secs(100), secs(99)), None);
+    /// let skew = Skew::check_server(secs(100), secs(60)).unwrap();
+    /// assert_eq!(skew.to_string(), "server went back from 100.000s to 60.000s");
+    /// ```
+    pub fn check_server(previous: time::SinceStart, server: time::SinceStart) -> Option<Self> {
+        if server < previous && previous - server > tolerance(previous) {
+            Some(Self {
+                chart: None,
+                client: previous,
+                server,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Signed divergence in seconds, positive if the client is ahead of the server.
+    pub fn secs(&self) -> f64 {
+        self.client.as_secs_f64() - self.server.as_secs_f64()
+    }
+}
+
+base::implement! {
+    impl Skew {
+        Display {
+            |&self, fmt| match self.chart {
+                Some(chart) => {
+                    let (diff, dir) = if self.client > self.server {
+                        (self.client - self.server, "ahead of")
+                    } else {
+                        (self.server - self.client, "behind")
+                    };
+                    write!(
+                        fmt,
+                        "chart #{} at {}s, {}s {} the server ({}s)",
+                        chart,
+                        self.client.display_millis(),
+                        diff.display_millis(),
+                        dir,
+                        self.server.display_millis(),
+                    )
+                }
+                None => write!(
+                    fmt,
+                    "server went back from {}s to {}s",
+                    self.client.display_millis(),
+                    self.server.display_millis(),
+                ),
+            }
+        }
+    }
+}
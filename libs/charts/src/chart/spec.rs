@@ -32,6 +32,19 @@ pub struct ChartSpec {
     y_axis: YAxis,
     /// Active filters.
     active: BTMap<uid::Line, bool>,
+    /// Lines the chart is scoped to, if any.
+    ///
+    /// Unscoped charts show all lines, including lines created after the chart. Scoped charts only
+    /// show the lines of their scope: newly created lines are excluded from scoped charts.
+    #[serde(default)]
+    scope: Option<BTSet<uid::Line>>,
+    /// Custom title of the chart, if any.
+    ///
+    /// Charts with no custom title are titled by their [description].
+    ///
+    /// [description]: #method.desc (The desc method)
+    #[serde(default)]
+    title: Option<String>,
 }
 impl ChartSpec {
     /// Creates a new chart spec.
@@ -41,6 +54,8 @@ impl ChartSpec {
             x_axis,
             y_axis,
             active,
+            scope: None,
+            title: None,
         }
     }
 
@@ -49,6 +64,42 @@ impl ChartSpec {
         format!("{} over {}", self.y_axis.desc(), self.x_axis.desc())
     }
 
+    /// Custom title accessor, `None` if the chart has no custom title.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_ref().map(String::as_str)
+    }
+    /// Title of the chart, its custom title if any, its description otherwise.
+    pub fn display_title(&self) -> String {
+        self.title.clone().unwrap_or_else(|| self.desc())
+    }
+    /// Sets the custom title of the chart.
+    ///
+    /// The title is trimmed, an empty title reverts to the description of the chart. Returns true
+    /// if the title changed.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, chart::{axis::*, ChartSpec}};
+    /// let mut spec = ChartSpec::new(XAxis::Time, YAxis::TotalSize, BTMap::new());
+    /// assert_eq!(spec.display_title(), spec.desc());
+    ///
+    /// assert!(spec.set_title(Some("  heap growth ".into())));
+    /// assert_eq!(spec.title(), Some("heap growth"));
+    /// assert_eq!(spec.display_title(), "heap growth");
+    /// assert!(!spec.set_title(Some("heap growth".into())));
+    ///
+    /// assert!(spec.set_title(Some(" ".into())));
+    /// assert_eq!(spec.title(), None);
+    /// assert_eq!(spec.display_title(), spec.desc());
+    /// ```
+    pub fn set_title(&mut self, title: Option<String>) -> bool {
+        let title = title
+            .map(|title| title.trim().to_string())
+            .filter(|title| !title.is_empty());
+        let changed = title != self.title;
+        self.title = title;
+        changed
+    }
+
     /// UID accessor.
     pub fn uid(&self) -> uid::Chart {
         self.uid
@@ -76,4 +127,50 @@ impl ChartSpec {
     pub fn has_active_filters(&self) -> bool {
         self.active.iter().any(|(_, active)| *active)
     }
+
+    /// Scope accessor, `None` if the chart is not scoped.
+    pub fn scope(&self) -> Option<&BTSet<uid::Line>> {
+        self.scope.as_ref()
+    }
+    /// True if a line is in the scope of the chart.
+    ///
+    /// Always true if the chart is not scoped.
+    pub fn in_scope(&self, line: uid::Line) -> bool {
+        self.scope
+            .as_ref()
+            .map(|scope| scope.contains(&line))
+            .unwrap_or(true)
+    }
+    /// Sets the scope of the chart.
+    ///
+    /// Lines in the new scope become active, lines outside of it become inactive.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, chart::{axis::*, ChartSpec}};
+    /// let mut active = BTMap::new();
+    /// active.insert(uid::Line::Everything, true);
+    /// active.insert(uid::Line::CatchAll, true);
+    /// let mut spec = ChartSpec::new(XAxis::Time, YAxis::TotalSize, active);
+    /// assert!(spec.in_scope(uid::Line::CatchAll));
+    ///
+    /// let scope = Some(vec![uid::Line::Everything].into_iter().collect());
+    /// spec.set_scope(scope);
+    /// assert!(spec.in_scope(uid::Line::Everything));
+    /// assert!(!spec.in_scope(uid::Line::CatchAll));
+    /// assert_eq!(spec.active().get(&uid::Line::CatchAll), Some(&false));
+    ///
+    /// spec.set_scope(None);
+    /// assert!(spec.in_scope(uid::Line::CatchAll));
+    /// assert_eq!(spec.active().get(&uid::Line::CatchAll), Some(&true));
+    /// ```
+    pub fn set_scope(&mut self, scope: Option<BTSet<uid::Line>>) {
+        self.scope = scope;
+        for (line, active) in self.active.iter_mut() {
+            *active = self
+                .scope
+                .as_ref()
+                .map(|scope| scope.contains(line))
+                .unwrap_or(true)
+        }
+    }
 }
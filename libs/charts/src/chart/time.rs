@@ -21,8 +21,22 @@
 
 prelude! {}
 
+pub mod churn;
+pub mod count;
+pub mod heap;
+pub mod large;
+pub mod lifetime;
+pub mod rate;
+pub mod segment;
 pub mod size;
 
+pub use churn::TimeChurn;
+pub use count::TimeCount;
+pub use heap::TimeHeap;
+pub use large::TimeLargeAllocs;
+pub use lifetime::TimeLifetime;
+pub use rate::TimeRate;
+pub use segment::TimeSegment;
 pub use size::TimeSize;
 
 /// A time chart.
@@ -30,6 +44,20 @@ pub use size::TimeSize;
 pub enum TimeChart {
     /// Total size over time chart.
     Size(TimeSize),
+    /// Churn ratio over time chart.
+    Churn(TimeChurn),
+    /// Live allocation count over time chart.
+    Count(TimeCount),
+    /// Large allocation count over time chart.
+    LargeAllocs(TimeLargeAllocs),
+    /// Allocation rate over time chart.
+    Rate(TimeRate),
+    /// Live size by trace segment over time chart.
+    Segment(TimeSegment),
+    /// Minor and major heap live size over time chart.
+    Heap(TimeHeap),
+    /// Lifetime statistic of the live allocations over time chart.
+    Lifetime(TimeLifetime),
 }
 
 impl TimeChart {
@@ -47,11 +75,33 @@ impl TimeChart {
         filters: &mut Filters,
         init: bool,
         resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
         time_windopt: &TimeWindopt,
     ) -> Res<Option<Points>> {
         match self {
             Self::Size(time_size_chart) => {
-                time_size_chart.new_points(filters, init, resolution, time_windopt)
+                time_size_chart.new_points(filters, init, resolution, quality, time_windopt)
+            }
+            Self::Churn(time_churn_chart) => {
+                time_churn_chart.new_points(filters, init, resolution, quality, time_windopt)
+            }
+            Self::Count(time_count_chart) => {
+                time_count_chart.new_points(filters, init, resolution, quality, time_windopt)
+            }
+            Self::LargeAllocs(time_large_chart) => {
+                time_large_chart.new_points(filters, init, resolution, quality, time_windopt)
+            }
+            Self::Rate(time_rate_chart) => {
+                time_rate_chart.new_points(filters, init, resolution, quality, time_windopt)
+            }
+            Self::Segment(time_segment_chart) => {
+                time_segment_chart.new_points(filters, init, resolution, quality, time_windopt)
+            }
+            Self::Heap(time_heap_chart) => {
+                time_heap_chart.new_points(filters, init, resolution, quality, time_windopt)
+            }
+            Self::Lifetime(time_lifetime_chart) => {
+                time_lifetime_chart.new_points(filters, init, resolution, quality, time_windopt)
             }
         }
     }
@@ -60,6 +110,13 @@ impl TimeChart {
     pub fn reset(&mut self, filters: &Filters) {
         match self {
             Self::Size(chart) => chart.reset(filters),
+            Self::Churn(chart) => chart.reset(filters),
+            Self::Count(chart) => chart.reset(filters),
+            Self::LargeAllocs(chart) => chart.reset(filters),
+            Self::Rate(chart) => chart.reset(filters),
+            Self::Segment(chart) => chart.reset(filters),
+            Self::Heap(chart) => chart.reset(filters),
+            Self::Lifetime(chart) => chart.reset(filters),
         }
     }
 }
@@ -69,4 +126,67 @@ impl TimeChart {
     pub fn new_total_size(filters: &Filters) -> Self {
         Self::Size(TimeSize::new(filters))
     }
+
+    /// Churn ratio over time constructor.
+    pub fn new_churn(filters: &Filters) -> Self {
+        Self::Churn(TimeChurn::new(filters))
+    }
+
+    /// Live allocation count over time constructor.
+    pub fn new_count(filters: &Filters) -> Self {
+        Self::Count(TimeCount::new(filters))
+    }
+
+    /// Large allocation count over time constructor.
+    pub fn new_large_allocs(filters: &Filters) -> Self {
+        Self::LargeAllocs(TimeLargeAllocs::new(filters))
+    }
+
+    /// Allocation rate over time constructor.
+    pub fn new_rate(filters: &Filters) -> Self {
+        Self::Rate(TimeRate::new(filters))
+    }
+
+    /// Live size by trace segment over time constructor.
+    pub fn new_segment(filters: &Filters) -> Self {
+        Self::Segment(TimeSegment::new(filters))
+    }
+
+    /// Minor and major heap live size over time constructor.
+    pub fn new_heap(filters: &Filters) -> Self {
+        Self::Heap(TimeHeap::new(filters))
+    }
+
+    /// Lifetime statistic of the live allocations over time constructor.
+    pub fn new_live_lifetime(filters: &Filters) -> Self {
+        Self::Lifetime(TimeLifetime::new(filters))
+    }
+
+    /// Sets the size percentile of the chart, if it uses one.
+    pub fn set_size_percentile(&mut self, size_percentile: chart::settings::SizePercentile) {
+        match self {
+            Self::LargeAllocs(chart) => chart.set_size_percentile(size_percentile),
+            Self::Size(_)
+            | Self::Churn(_)
+            | Self::Count(_)
+            | Self::Rate(_)
+            | Self::Segment(_)
+            | Self::Heap(_)
+            | Self::Lifetime(_) => (),
+        }
+    }
+
+    /// Sets the lifetime statistic of the chart, if it uses one.
+    pub fn set_lifetime_stat(&mut self, lifetime_stat: chart::settings::LifetimeStat) {
+        match self {
+            Self::Lifetime(chart) => chart.set_lifetime_stat(lifetime_stat),
+            Self::Size(_)
+            | Self::Churn(_)
+            | Self::Count(_)
+            | Self::LargeAllocs(_)
+            | Self::Rate(_)
+            | Self::Segment(_)
+            | Self::Heap(_) => (),
+        }
+    }
 }
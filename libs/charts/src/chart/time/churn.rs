@@ -0,0 +1,337 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Churn ratio over time chart.
+//!
+//! For each time bucket, and for each filter, this chart computes the ratio of the bytes freed to
+//! the bytes allocated in that bucket. This is a rough proxy for heap fragmentation: a high churn
+//! ratio means the program keeps freeing and re-allocating memory. It is **not** a measure of
+//! actual allocator fragmentation, which depends on the allocator and the addresses involved.
+//!
+//! Each bucket also has a size spread, which compares the sizes of the allocations freed to the
+//! sizes of the allocations created, see [`SizeClasses::spread`]. Freeing allocations whose size
+//! differs from the ones being created leaves holes the allocator cannot reuse.
+//!
+//! The chart only relies on the allocation/deallocation events [`TimeSize`] also uses, it does
+//! not scan the allocations.
+//!
+//! [`TimeSize`]: ../size/struct.TimeSize.html (TimeSize chart)
+//! [`SizeClasses::spread`]: ../../../point/churn/struct.SizeClasses.html#method.spread
+//! (The spread method of SizeClasses)
+
+prelude! {}
+
+use point::{churn::SizeClasses, Churn, TimeChurnPoints};
+
+/// Churn ratio over time chart.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeChurn {
+    /// UID of the last allocation, and timestamp of the last deallocation.
+    last: Option<(uid::Alloc, time::SinceStart)>,
+    /// Time bucket currently being filled: start timestamp and churn values.
+    bucket: Option<(time::SinceStart, PointVal<Churn>)>,
+    /// Size classes of the allocations of each line in the current bucket.
+    #[serde(default)]
+    classes: BTMap<uid::Line, SizeClasses>,
+    /// Points.
+    points: TimeChurnPoints,
+}
+
+impl TimeChurn {
+    /// Constructor.
+    pub fn new(_filters: &filter::Filters) -> Self {
+        Self {
+            last: None,
+            bucket: None,
+            classes: BTMap::new(),
+            points: TimeChurnPoints::with_capacity(32),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl TimeChurn {
+    /// Retrieves the new points since the last time it was called.
+    pub fn new_points(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<Points>> {
+        self.do_it(filters, init, resolution, quality, time_windopt)
+            .map(|opt| opt.map(Points::from))
+    }
+
+    /// Resets (drops) all its points and re-initializes itself for `filters`.
+    pub fn reset(&mut self, _filters: &filter::Filters) {
+        self.last = None;
+        self.bucket = None;
+        self.classes.clear();
+        self.points.clear();
+    }
+}
+
+/// # Helpers for point generation
+#[cfg(any(test, feature = "server"))]
+impl TimeChurn {
+    /// Turns a bucket into a point, sets the spread of its values.
+    fn point(
+        (start, vals): &(time::SinceStart, PointVal<Churn>),
+        classes: &BTMap<uid::Line, SizeClasses>,
+    ) -> Point<time::SinceStart, Churn> {
+        let mut vals = vals.clone();
+        for (line, classes) in classes.iter() {
+            if let Some(val) = vals.map.get_mut(line) {
+                val.spread = classes.spread()
+            }
+        }
+        Point::new(*start, vals)
+    }
+
+    /// Closes a bucket: turns it into a point and forgets the size classes of the bucket.
+    fn close(
+        bucket: (time::SinceStart, PointVal<Churn>),
+        classes: &mut BTMap<uid::Line, SizeClasses>,
+    ) -> Point<time::SinceStart, Churn> {
+        let point = Self::point(&bucket, classes);
+        classes.values_mut().for_each(SizeClasses::clear);
+        point
+    }
+
+    fn do_it(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<TimeChurnPoints>> {
+        let data = data::get()?;
+
+        if init {
+            self.reset(filters);
+        }
+
+        if !init && !data.has_new_stuff_since(self.last.clone()) {
+            return Ok(None);
+        }
+
+        let time_window = time_windopt.to_time_window(|| *data.current_time());
+        let min_time_spacing = resolution.min_time_spacing(quality, data.current_time());
+
+        debug_assert!(self.points.is_empty());
+
+        let (bucket, classes, points) = (&mut self.bucket, &mut self.classes, &mut self.points);
+
+        let since = self.last;
+        data.iter_new_changes(since, filters, |filters, change, _| {
+            let (timestamp, size, count) = (change.time, change.bytes, change.count);
+            let (add, f_uid) = (change.add, change.line);
+
+            match time_window.cmp(timestamp) {
+                // Below the time-window, nothing to do.
+                base::RangeCmp::Below => Ok(true),
+
+                // Inside the time-window, update the current bucket.
+                base::RangeCmp::Inside => {
+                    // Close the current bucket if `timestamp` is too far from its start.
+                    if let Some((start, _)) = bucket.as_ref() {
+                        if timestamp - *start >= min_time_spacing {
+                            let closed = bucket
+                                .take()
+                                .expect("`take` after `as_ref().is_some()` cannot fail");
+                            points.push(Self::close(closed, classes))
+                        }
+                    }
+
+                    let (_, vals) = bucket.get_or_insert_with(|| {
+                        (timestamp, PointVal::new(Churn::default(), filters))
+                    });
+
+                    for uid in [f_uid, uid::Line::Everything].iter() {
+                        let val = vals.get_mut_or(*uid, Churn::default());
+                        if add {
+                            val.alloc += size
+                        } else {
+                            val.freed += size
+                        }
+                        classes
+                            .entry(*uid)
+                            .or_insert_with(SizeClasses::new)
+                            .record(add, count, size)
+                    }
+
+                    Ok(true)
+                }
+
+                // Above the range, early exit.
+                base::RangeCmp::Above => Ok(false),
+            }
+        })?;
+
+        // The last bucket stays open since the next events may fall in it. The client still gets
+        // it as a provisional point, which the point of the same bucket replaces later on, see
+        // `TimePoints::extend`.
+        if let Some(open) = bucket.as_ref() {
+            points.push(Self::point(open, classes))
+        }
+
+        // Make sure the client always has something to show.
+        if init && points.is_empty() {
+            let zero = PointVal::new(Churn::default(), filters);
+            points.push(Point::new(time_window.lbound, zero.clone()));
+            points.push(Point::new(time_window.ubound, zero));
+        }
+
+        self.last = data.last_events();
+
+        if points.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(points.drain(0..).collect()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buckets() {
+        use chart::settings::{Quality, Resolution};
+        let mut synth = crate::fixture::Synth::new();
+        let (trace, labels) = (crate::fixture::trace(&[]), crate::fixture::labels(&[]));
+        // First bucket, allocations only.
+        let small = synth.alloc(0, 16, &trace, &labels);
+        let big = synth.alloc(5, 1024, &trace, &labels);
+        let other_small = synth.alloc(10, 16, &trace, &labels);
+        // Second bucket, frees as much as it allocates with the same sizes.
+        synth.dead(30, small.get() as u64);
+        synth.alloc(35, 16, &trace, &labels);
+        // Third bucket, frees less than it allocates with different sizes.
+        synth.dead(60, big.get() as u64);
+        synth.dead(60, other_small.get() as u64);
+        synth.alloc(62, 16, &trace, &labels);
+        synth.alloc(62, 16, &trace, &labels);
+        synth.alloc(62, 4096, &trace, &labels);
+        synth.mark(100);
+        let _lock = data::set_for_test(synth.done());
+
+        // Four points over a hundred milliseconds, the buckets are 25ms wide.
+        let mut resolution = Resolution::from((1000, 400));
+        resolution.max_points = Some(4);
+        let mut filters = Filters::new();
+        let mut chart = TimeChurn::new(&filters);
+        let points = chart
+            .do_it(
+                &mut filters,
+                true,
+                resolution,
+                Quality::Low,
+                &Range::new(None, None),
+            )
+            .unwrap()
+            .unwrap();
+
+        for line in &[uid::Line::Everything, uid::Line::CatchAll] {
+            let churn: Vec<_> = points
+                .iter()
+                .map(|point| (point.key.as_millis(), point.vals.map[line]))
+                .collect();
+            assert_eq!(churn.len(), 3);
+            assert_eq!(churn[0], (0, Churn::new(1056, 0)));
+            assert_eq!(churn[1], (30, Churn::new(16, 16)));
+            let (start, last) = churn[2];
+            assert_eq!(start, 60);
+            assert_eq!((last.alloc, last.freed), (4128, 1040));
+            assert!((last.ratio() - 1040.0 / 4128.0).abs() < 1e-6);
+            // Half of the allocations freed are in a class no allocation created is in.
+            assert!((last.spread - 0.5).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn open_bucket_across_calls() {
+        use chart::settings::{Quality, Resolution};
+        use point::TimePoints;
+        // Allocations by size, and deaths by UID.
+        let events = vec![
+            (0, Either::Left(16)),
+            (5, Either::Left(1024)),
+            (10, Either::Left(16)),
+            (30, Either::Right(0)),
+            (35, Either::Left(16)),
+            (60, Either::Right(1)),
+            (60, Either::Right(2)),
+            (62, Either::Left(16)),
+            (62, Either::Left(4096)),
+        ];
+        let data = |until: u64| {
+            let mut synth = crate::fixture::Synth::new();
+            let (trace, labels) = (crate::fixture::trace(&[]), crate::fixture::labels(&[]));
+            for (time, event) in events.iter().filter(|(time, _)| *time <= until) {
+                match event {
+                    Either::Left(size) => {
+                        let _ = synth.alloc(*time, *size, &trace, &labels);
+                    }
+                    Either::Right(uid) => synth.dead(*time, *uid),
+                }
+            }
+            synth.mark(100);
+            synth.done()
+        };
+
+        let mut resolution = Resolution::from((1000, 400));
+        resolution.max_points = Some(4);
+        let time_windopt = Range::new(None, None);
+        let mut filters = Filters::new();
+        let mut chart = TimeChurn::new(&filters);
+        let mut do_it = |init| {
+            let points = chart
+                .do_it(&mut filters, init, resolution, Quality::Low, &time_windopt)
+                .unwrap()
+                .unwrap();
+            TimePoints::from(points)
+        };
+        let churn = |points: TimePoints| -> Vec<(u128, BTMap<uid::Line, Churn>)> {
+            match points {
+                TimePoints::Churn(points) => points
+                    .into_iter()
+                    .map(|point| (point.key.as_millis(), point.vals.map.into_iter().collect()))
+                    .collect(),
+                _ => panic!("expected churn points"),
+            }
+        };
+
+        let lock = data::set_for_test(data(100));
+        let expected = churn(do_it(true));
+        drop(lock);
+
+        // The first call stops in the middle of the second bucket.
+        let lock = data::set_for_test(data(33));
+        let mut points = do_it(true);
+        drop(lock);
+        let _lock = data::set_for_test(data(100));
+        points.extend(&mut do_it(false)).unwrap();
+        assert_eq!(churn(points), expected);
+    }
+}
@@ -0,0 +1,249 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Live allocation count over time chart.
+//!
+//! Same as the [total size chart], but counts the live allocations instead of summing their sizes.
+//!
+//! [total size chart]: ../size/struct.TimeSize.html (TimeSize struct)
+
+prelude! {}
+
+use point::TimeCountPoints;
+
+/// Live allocation count over time chart.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeCount {
+    /// UID of the last allocation, and timestamp of the last deallocation.
+    last: Option<(uid::Alloc, time::SinceStart)>,
+    /// Current number of live allocations.
+    count: PointVal<u64>,
+    /// Optional last timestamp.
+    last_time_stamp: Option<time::SinceStart>,
+    /// Points.
+    points: TimeCountPoints,
+}
+
+impl TimeCount {
+    /// Constructor.
+    pub fn new(filters: &filter::Filters) -> Self {
+        Self {
+            last: None,
+            count: PointVal::new(0, filters),
+            last_time_stamp: None,
+            points: TimeCountPoints::with_capacity(32),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl TimeCount {
+    /// Retrieves the new points since the last time it was called.
+    pub fn new_points(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<Points>> {
+        self.do_it(filters, init, resolution, quality, time_windopt)
+            .map(|opt| opt.map(Points::from))
+    }
+
+    /// Resets (drops) all its points and re-initializes itself for `filters`.
+    pub fn reset(&mut self, filters: &filter::Filters) {
+        self.last = None;
+        self.last_time_stamp = None;
+        self.count = PointVal::new(0, filters);
+        self.points.clear();
+    }
+}
+
+/// # Helpers for point generation
+#[cfg(any(test, feature = "server"))]
+impl TimeCount {
+    /// Registers `n` new (`add`) or dead allocations for a line in some counts.
+    ///
+    /// Also updates the everything line. Counts never go negative: a death the counts do not know
+    /// the allocation of, *e.g.* because the filter catching it changed since its creation, leaves
+    /// the count at `0`.
+    ///
+    /// Returns the values before the update for `line` and the everything line, in this order.
+    fn update_by(counts: &mut PointVal<u64>, line: uid::Line, add: bool, n: u64) -> (u64, u64) {
+        let mut update = |uid| {
+            let count = counts.get_mut_or(uid, 0);
+            let prev = *count;
+            *count = if add {
+                prev + n
+            } else {
+                prev.saturating_sub(n)
+            };
+            prev
+        };
+        (update(line), update(uid::Line::Everything))
+    }
+
+    fn do_it(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<TimeCountPoints>> {
+        let data = data::get()?;
+
+        if !data.has_new_stuff_since(self.last.clone()) {
+            return Ok(None);
+        }
+
+        let time_window = time_windopt.to_time_window(|| *data.current_time());
+        let min_time_spacing = resolution.min_time_spacing(quality, data.current_time());
+
+        debug_assert!(self.points.is_empty());
+        if init {
+            self.reset(filters);
+        }
+
+        self.points.push(Point::new(
+            self.last_time_stamp
+                .or(time_windopt.lbound)
+                .unwrap_or_else(time::SinceStart::zero),
+            self.count.clone(),
+        ));
+
+        let (last_time_stamp, count, points) =
+            (&mut self.last_time_stamp, &mut self.count, &mut self.points);
+
+        data.iter_new_changes(self.last, filters, |_, change, _| {
+            let (timestamp, f_uid) = (change.time, change.line);
+            let (add, n) = (change.add, change.count);
+
+            match time_window.cmp(timestamp) {
+                // Below the time-window, update the first point.
+                base::RangeCmp::Below => {
+                    debug_assert!(points.len() == 1);
+                    *last_time_stamp = Some(timestamp);
+                    Self::update_by(count, f_uid, add, n);
+                    if let Some(first) = points.last_mut() {
+                        first.vals = count.clone()
+                    }
+                    Ok(true)
+                }
+
+                // Inside the time-window.
+                base::RangeCmp::Inside => {
+                    let timestamp = match last_time_stamp {
+                        Some(last) if timestamp - *last < min_time_spacing => *last,
+                        _ => {
+                            *last_time_stamp = Some(timestamp);
+                            timestamp
+                        }
+                    };
+
+                    let (prev, prev_everything) = Self::update_by(count, f_uid, add, n);
+                    let new = (count.map[&f_uid], count.map[&uid::Line::Everything]);
+
+                    let last_key = points.last().map(|point| point.key);
+                    if last_key != Some(timestamp) {
+                        // Repeat the previous values so that the lines are step-shaped.
+                        let mut repeat = Point::new(timestamp, PointVal::empty());
+                        repeat.vals.map.insert(f_uid, prev);
+                        repeat
+                            .vals
+                            .map
+                            .insert(uid::Line::Everything, prev_everything);
+                        points.push(repeat.clone());
+                        points.push(repeat);
+                    } else if points.len() >= 2 {
+                        // The repeated point may not mention this line yet.
+                        let penultimate = points.len() - 2;
+                        if points[penultimate].key == timestamp {
+                            points[penultimate]
+                                .vals
+                                .map
+                                .get_or_insert_with(f_uid, || prev);
+                        }
+                    }
+
+                    let last = points
+                        .last_mut()
+                        .expect("`last_mut` after `push` cannot fail");
+                    last.vals.map.insert(f_uid, new.0);
+                    last.vals.map.insert(uid::Line::Everything, new.1);
+
+                    Ok(true)
+                }
+
+                // Above the range: generate the very last point and early exit.
+                base::RangeCmp::Above => {
+                    let end_time = time_window.ubound;
+                    if let Some(last) = points.last() {
+                        if last.key < end_time {
+                            let mut last = last.clone();
+                            last.key = end_time;
+                            points.push(last)
+                        }
+                    }
+                    Ok(false)
+                }
+            }
+        })?;
+
+        // Pad the points up to the end of the time-window, in particular so that a single point
+        // still shows as a line.
+        if points
+            .last()
+            .map(|last| last.key < time_window.ubound)
+            .unwrap_or(true)
+        {
+            points.push(Point::new(time_window.ubound, count.clone()))
+        }
+
+        self.last = data.last_events();
+
+        debug_assert!(!points.is_empty());
+        Ok(Some(points.drain(0..).collect()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counts_never_go_negative() {
+        let line = uid::Line::Filter(uid::Filter::fresh());
+        let mut counts = PointVal::empty();
+
+        // A death before any allocation, for instance of an allocation predating the chart.
+        assert_eq!(TimeCount::update_by(&mut counts, line, false, 1), (0, 0));
+        assert_eq!(counts.map[&line], 0);
+        assert_eq!(counts.map[&uid::Line::Everything], 0);
+
+        assert_eq!(TimeCount::update_by(&mut counts, line, true, 1), (0, 0));
+        assert_eq!(TimeCount::update_by(&mut counts, line, true, 1), (1, 1));
+        assert_eq!(TimeCount::update_by(&mut counts, line, false, 1), (2, 2));
+        assert_eq!(TimeCount::update_by(&mut counts, line, false, 1), (1, 1));
+        assert_eq!(TimeCount::update_by(&mut counts, line, false, 1), (0, 0));
+        assert_eq!(counts.map[&line], 0);
+        assert_eq!(counts.map[&uid::Line::Everything], 0);
+    }
+}
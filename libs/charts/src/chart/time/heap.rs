@@ -0,0 +1,402 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Minor and major heap live size over time chart.
+//!
+//! Same as the [total size chart], but keeps the live size on the minor heap apart from the one on
+//! the major heap. Allocations start on the major heap if their [kind] says so, and on the minor
+//! heap otherwise. A promotion moves the size of an allocation from the minor heap to the major
+//! heap, and a collection removes it from whichever heap it is on.
+//!
+//! [total size chart]: ../size/struct.TimeSize.html (TimeSize struct)
+//! [kind]: ../../../../alloc_data/enum.AllocKind.html#method.is_major (AllocKind::is_major)
+
+prelude! {}
+
+use point::{HeapSize, TimeHeapPoints};
+
+/// Minor and major heap live size over time chart.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeHeap {
+    /// UID of the last allocation, and timestamp of the last deallocation.
+    last: Option<(uid::Alloc, time::SinceStart)>,
+    /// Timestamp of the last promotion.
+    last_promotion: Option<time::SinceStart>,
+    /// Current live sizes.
+    size: PointVal<HeapSize>,
+    /// Optional last timestamp.
+    last_time_stamp: Option<time::SinceStart>,
+    /// Points.
+    points: TimeHeapPoints,
+}
+
+impl TimeHeap {
+    /// Constructor.
+    pub fn new(filters: &filter::Filters) -> Self {
+        Self {
+            last: None,
+            last_promotion: None,
+            size: PointVal::new(HeapSize::default(), filters),
+            last_time_stamp: None,
+            points: TimeHeapPoints::with_capacity(32),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl TimeHeap {
+    /// Retrieves the new points since the last time it was called.
+    pub fn new_points(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<Points>> {
+        self.do_it(filters, init, resolution, quality, time_windopt)
+            .map(|opt| opt.map(Points::from))
+    }
+
+    /// Resets (drops) all its points and re-initializes itself for `filters`.
+    pub fn reset(&mut self, filters: &filter::Filters) {
+        self.last = None;
+        self.last_promotion = None;
+        self.last_time_stamp = None;
+        self.size = PointVal::new(HeapSize::default(), filters);
+        self.points.clear();
+    }
+}
+
+/// A change of the live sizes.
+#[cfg(any(test, feature = "server"))]
+#[derive(Debug, Clone, Copy)]
+struct Event {
+    /// Time of the change.
+    timestamp: time::SinceStart,
+    /// Line the allocation belongs to.
+    line: uid::Line,
+    /// Size of the allocation.
+    size: u64,
+    /// Heap the allocation leaves, `None` for new allocations, `Some(true)` for the major heap.
+    from: Option<bool>,
+    /// Heap the allocation goes to, `None` for dead allocations, `Some(true)` for the major heap.
+    to: Option<bool>,
+}
+
+/// # Helpers for point generation
+#[cfg(any(test, feature = "server"))]
+impl TimeHeap {
+    /// Line of an allocation.
+    fn line_of(filters: &mut Filters, data: &data::Data, alloc: &Alloc) -> uid::Line {
+        if let Some(f_uid) = filters.find_match(data.current_time(), alloc) {
+            uid::Line::Filter(f_uid)
+        } else {
+            uid::Line::CatchAll
+        }
+    }
+
+    /// Registers an event for a line in some sizes.
+    ///
+    /// Also updates the everything line. Sizes never go negative: an event the sizes do not know
+    /// the allocation of, *e.g.* because the filter catching it changed since its creation, leaves
+    /// the size of the heap the allocation leaves at `0`.
+    ///
+    /// Returns the values before the update for the line and the everything line, in this order.
+    fn update(sizes: &mut PointVal<HeapSize>, event: &Event) -> (HeapSize, HeapSize) {
+        let mut update = |uid| {
+            let size = sizes.get_mut_or(uid, HeapSize::default());
+            let prev = *size;
+            if let Some(major) = event.from {
+                let heap = size.heap_mut(major);
+                *heap = heap.saturating_sub(event.size)
+            }
+            if let Some(major) = event.to {
+                *size.heap_mut(major) += event.size
+            }
+            prev
+        };
+        (update(event.line), update(uid::Line::Everything))
+    }
+
+    /// Handles an event w.r.t. the time-window.
+    ///
+    /// Returns `false` if the event is above the time-window, in which case the last point has
+    /// been generated.
+    fn handle(
+        points: &mut TimeHeapPoints,
+        sizes: &mut PointVal<HeapSize>,
+        last_time_stamp: &mut Option<time::SinceStart>,
+        time_window: &TimeWindow,
+        min_time_spacing: time::SinceStart,
+        event: Event,
+    ) -> bool {
+        match time_window.cmp(event.timestamp) {
+            // Below the time-window, update the first point.
+            base::RangeCmp::Below => {
+                debug_assert!(points.len() == 1);
+                *last_time_stamp = Some(event.timestamp);
+                Self::update(sizes, &event);
+                if let Some(first) = points.last_mut() {
+                    first.vals = sizes.clone()
+                }
+                true
+            }
+
+            // Inside the time-window.
+            base::RangeCmp::Inside => {
+                let timestamp = match last_time_stamp {
+                    Some(last) if event.timestamp - *last < min_time_spacing => *last,
+                    _ => {
+                        *last_time_stamp = Some(event.timestamp);
+                        event.timestamp
+                    }
+                };
+
+                let line = event.line;
+                let (prev, prev_everything) = Self::update(sizes, &event);
+                let new = (sizes.map[&line], sizes.map[&uid::Line::Everything]);
+
+                let last_key = points.last().map(|point| point.key);
+                if last_key != Some(timestamp) {
+                    // Repeat the previous values so that the lines are step-shaped.
+                    let mut repeat = Point::new(timestamp, PointVal::empty());
+                    repeat.vals.map.insert(line, prev);
+                    repeat
+                        .vals
+                        .map
+                        .insert(uid::Line::Everything, prev_everything);
+                    points.push(repeat.clone());
+                    points.push(repeat);
+                } else if points.len() >= 2 {
+                    // The repeated point may not mention this line yet.
+                    let penultimate = points.len() - 2;
+                    if points[penultimate].key == timestamp {
+                        points[penultimate]
+                            .vals
+                            .map
+                            .get_or_insert_with(line, || prev);
+                    }
+                }
+
+                let last = points
+                    .last_mut()
+                    .expect("`last_mut` after `push` cannot fail");
+                last.vals.map.insert(line, new.0);
+                last.vals.map.insert(uid::Line::Everything, new.1);
+
+                true
+            }
+
+            // Above the range: generate the very last point.
+            base::RangeCmp::Above => {
+                let end_time = time_window.ubound;
+                if let Some(last) = points.last() {
+                    if last.key < end_time {
+                        let mut last = last.clone();
+                        last.key = end_time;
+                        points.push(last)
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    fn do_it(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<TimeHeapPoints>> {
+        let data = data::get()?;
+
+        if !data.has_new_stuff_since(self.last.clone())
+            && data.last_promotion() == self.last_promotion
+        {
+            return Ok(None);
+        }
+
+        let time_window = time_windopt.to_time_window(|| *data.current_time());
+        let min_time_spacing = resolution.min_time_spacing(quality, data.current_time());
+
+        debug_assert!(self.points.is_empty());
+        if init {
+            self.reset(filters);
+        }
+
+        self.points.push(Point::new(
+            self.last_time_stamp
+                .or(time_windopt.lbound)
+                .unwrap_or_else(time::SinceStart::zero),
+            self.size.clone(),
+        ));
+
+        let (last_time_stamp, sizes, points) =
+            (&mut self.last_time_stamp, &mut self.size, &mut self.points);
+
+        // Promotions are applied before the events strictly after them, so that an allocation
+        // promoted when it dies is collected from the minor heap.
+        let mut promotions = data.iter_promotions_after(self.last_promotion).peekable();
+        let mut promote = |filters: &mut Filters,
+                           before: Option<time::SinceStart>,
+                           points: &mut TimeHeapPoints,
+                           sizes: &mut PointVal<HeapSize>,
+                           last_time_stamp: &mut Option<time::SinceStart>|
+         -> bool {
+            while let Some((promoted_at, alloc)) = promotions.peek().cloned() {
+                if before.map(|before| before <= promoted_at).unwrap_or(false) {
+                    break;
+                }
+                let _ = promotions.next();
+                let promoted = !alloc.kind.is_major()
+                    && alloc.tod.map(|tod| promoted_at < tod).unwrap_or(true);
+                if !promoted {
+                    continue;
+                }
+                let event = Event {
+                    timestamp: promoted_at,
+                    line: Self::line_of(filters, &data, alloc),
+                    size: alloc.real_size as u64,
+                    from: Some(false),
+                    to: Some(true),
+                };
+                let keep_going = Self::handle(
+                    points,
+                    sizes,
+                    last_time_stamp,
+                    &time_window,
+                    min_time_spacing,
+                    event,
+                );
+                if !keep_going {
+                    return false;
+                }
+            }
+            true
+        };
+
+        let mut keep_going = true;
+        data.iter_new_events(self.last.clone(), |new_or_dead| {
+            let (timestamp, alloc, from, to) = new_or_dead.as_ref().either(
+                |alloc| (alloc.toc, *alloc, None, Some(alloc.kind.is_major())),
+                |(tod, alloc)| (*tod, *alloc, Some(alloc.is_major_at(*tod)), None),
+            );
+
+            keep_going = promote(filters, Some(timestamp), points, sizes, last_time_stamp);
+            if !keep_going {
+                return Ok(false);
+            }
+
+            let event = Event {
+                timestamp,
+                line: Self::line_of(filters, &data, alloc),
+                size: alloc.real_size as u64,
+                from,
+                to,
+            };
+            keep_going = Self::handle(
+                points,
+                sizes,
+                last_time_stamp,
+                &time_window,
+                min_time_spacing,
+                event,
+            );
+            Ok(keep_going)
+        })?;
+
+        // Promotions after the last (de)allocation.
+        if keep_going {
+            let _ = promote(filters, None, points, sizes, last_time_stamp);
+        }
+
+        // Pad the points up to the end of the time-window, in particular so that a single point
+        // still shows as a line.
+        if points
+            .last()
+            .map(|last| last.key < time_window.ubound)
+            .unwrap_or(true)
+        {
+            points.push(Point::new(time_window.ubound, sizes.clone()))
+        }
+
+        self.last = data.last_events();
+        self.last_promotion = data.last_promotion();
+
+        debug_assert!(!points.is_empty());
+        Ok(Some(points.drain(0..).collect()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sizes_move_between_heaps() {
+        let line = uid::Line::Filter(uid::Filter::fresh());
+        let mut sizes = PointVal::empty();
+        let event = |size, from, to| Event {
+            timestamp: time::SinceStart::zero(),
+            line,
+            size,
+            from,
+            to,
+        };
+        let heap = |minor, major| HeapSize::new(minor, major);
+
+        // A death before any allocation, for instance of an allocation predating the chart.
+        let dead = event(8, Some(false), None);
+        assert_eq!(
+            TimeHeap::update(&mut sizes, &dead),
+            (heap(0, 0), heap(0, 0))
+        );
+        assert_eq!(sizes.map[&line], heap(0, 0));
+
+        // A minor allocation, a major one, and a promotion.
+        let minor = event(8, None, Some(false));
+        assert_eq!(
+            TimeHeap::update(&mut sizes, &minor),
+            (heap(0, 0), heap(0, 0))
+        );
+        let major = event(32, None, Some(true));
+        assert_eq!(
+            TimeHeap::update(&mut sizes, &major),
+            (heap(8, 0), heap(8, 0))
+        );
+        let promotion = event(8, Some(false), Some(true));
+        assert_eq!(
+            TimeHeap::update(&mut sizes, &promotion),
+            (heap(8, 32), heap(8, 32))
+        );
+        assert_eq!(sizes.map[&line], heap(0, 40));
+
+        // Collections remove sizes from their heap.
+        let dead = event(32, Some(true), None);
+        assert_eq!(
+            TimeHeap::update(&mut sizes, &dead),
+            (heap(0, 40), heap(0, 40))
+        );
+        assert_eq!(sizes.map[&line], heap(0, 8));
+        assert_eq!(sizes.map[&uid::Line::Everything], heap(0, 8));
+    }
+}
@@ -0,0 +1,212 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+//! Large allocation count over time chart.
+//!
+//! For each time bucket, and for each filter, this chart counts the allocations created in that
+//! bucket whose size is at or above a size percentile. The percentile is maintained by a
+//! [`TrailingSketch`] over the sizes of **all** the allocations (regardless of filters) created
+//! during a trailing window, and each allocation is compared to the percentile of the window
+//! ending when it was created.
+//!
+//! This is useful to spot which filters produce the rare huge allocations, without having to
+//! hand-tune a size threshold.
+//!
+//! [`TrailingSketch`]: ../../sketch/struct.TrailingSketch.html (TrailingSketch struct)
+
+prelude! {}
+
+use chart::{settings::SizePercentile, sketch::TrailingSketch};
+use point::TimeCountPoints;
+
+/// Large allocation count over time chart.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeLargeAllocs {
+    /// UID of the last allocation, and timestamp of the last deallocation.
+    last: Option<(uid::Alloc, time::SinceStart)>,
+    /// Size percentile and trailing window.
+    size_percentile: SizePercentile,
+    /// Sketch over the sizes of the allocations in the trailing window.
+    #[serde(skip)]
+    sketch: Option<TrailingSketch>,
+    /// Time bucket currently being filled: start timestamp and counts.
+    bucket: Option<(time::SinceStart, PointVal<u64>)>,
+    /// Points.
+    points: TimeCountPoints,
+}
+
+impl TimeLargeAllocs {
+    /// Constructor.
+    pub fn new(_filters: &filter::Filters) -> Self {
+        Self {
+            last: None,
+            size_percentile: SizePercentile::default(),
+            sketch: None,
+            bucket: None,
+            points: TimeCountPoints::with_capacity(32),
+        }
+    }
+
+    /// Sets the size percentile and trailing window.
+    ///
+    /// Drops the sketch if they change, the chart must then be reset for this to take effect on the
+    /// points already generated.
+    pub fn set_size_percentile(&mut self, size_percentile: SizePercentile) {
+        if self.size_percentile != size_percentile {
+            self.size_percentile = size_percentile;
+            self.sketch = None
+        }
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl TimeLargeAllocs {
+    /// Retrieves the new points since the last time it was called.
+    pub fn new_points(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<Points>> {
+        self.do_it(filters, init, resolution, quality, time_windopt)
+            .map(|opt| opt.map(Points::from))
+    }
+
+    /// Resets (drops) all its points and re-initializes itself for `filters`.
+    pub fn reset(&mut self, _filters: &filter::Filters) {
+        self.last = None;
+        self.sketch = None;
+        self.bucket = None;
+        self.points.clear();
+    }
+}
+
+/// # Helpers for point generation
+#[cfg(any(test, feature = "server"))]
+impl TimeLargeAllocs {
+    fn do_it(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<TimeCountPoints>> {
+        let data = data::get()?;
+
+        if init {
+            self.reset(filters);
+        }
+
+        if !init && !data.has_new_stuff_since(self.last.clone()) {
+            return Ok(None);
+        }
+
+        let time_window = time_windopt.to_time_window(|| *data.current_time());
+        let min_time_spacing = resolution.min_time_spacing(quality, data.current_time());
+        let quantile = self.size_percentile.quantile();
+
+        debug_assert!(self.points.is_empty());
+
+        if self.sketch.is_none() {
+            self.sketch = Some(TrailingSketch::new(
+                self.size_percentile.window,
+                chart::sketch::DEFAULT_ALPHA,
+            )?)
+        }
+        let (trailing, bucket, points) = (
+            self.sketch
+                .as_mut()
+                .expect("`as_mut` after `is_none()` check cannot fail"),
+            &mut self.bucket,
+            &mut self.points,
+        );
+
+        data.iter_new_events(self.last.clone(), |new_or_dead| {
+            let alloc = match new_or_dead.as_ref().left() {
+                Some(alloc) => alloc,
+                // Deallocations are irrelevant.
+                None => return Ok(true),
+            };
+            let (timestamp, size) = (alloc.toc, alloc.real_size as u64);
+
+            // Allocations below the time-window still contribute to the trailing window.
+            trailing.push(timestamp, size);
+
+            match time_window.cmp(timestamp) {
+                // Below the time-window, nothing else to do.
+                base::RangeCmp::Below => Ok(true),
+
+                // Inside the time-window, update the current bucket.
+                base::RangeCmp::Inside => {
+                    // Close the current bucket if `timestamp` is too far from its start.
+                    if let Some((start, _)) = bucket.as_ref() {
+                        if timestamp - *start >= min_time_spacing {
+                            let (start, vals) = bucket
+                                .take()
+                                .expect("`take` after `as_ref().is_some()` cannot fail");
+                            points.push(Point::new(start, vals))
+                        }
+                    }
+
+                    let (_, vals) =
+                        bucket.get_or_insert_with(|| (timestamp, PointVal::new(0, filters)));
+
+                    if trailing.reaches(size, quantile) {
+                        let f_uid =
+                            if let Some(f_uid) = filters.find_match(data.current_time(), alloc) {
+                                uid::Line::Filter(f_uid)
+                            } else {
+                                uid::Line::CatchAll
+                            };
+                        for uid in [f_uid, uid::Line::Everything].iter() {
+                            *vals.get_mut_or(*uid, 0) += 1
+                        }
+                    }
+
+                    Ok(true)
+                }
+
+                // Above the range, early exit.
+                base::RangeCmp::Above => Ok(false),
+            }
+        })?;
+
+        // Flush the last bucket so that the client sees the most recent events.
+        if let Some((start, vals)) = bucket.take() {
+            points.push(Point::new(start, vals))
+        }
+
+        // Make sure the client always has something to show.
+        if init && points.is_empty() {
+            let zero = PointVal::new(0, filters);
+            points.push(Point::new(time_window.lbound, zero.clone()));
+            points.push(Point::new(time_window.ubound, zero));
+        }
+
+        self.last = data.last_events();
+
+        if points.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(points.drain(0..).collect()))
+        }
+    }
+}
@@ -0,0 +1,391 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+//! Lifetime statistic of the live allocations over time chart.
+//!
+//! At each point, and for each filter, the value is a statistic over the lifetimes of the
+//! allocations that are alive at the time of the point: the mean, the median, the 95th percentile
+//! or the maximum, see [`LifetimeStat`]. Lifetimes grow with time, so the statistic is computed at the
+//! timestamp of each point, and all the lines have a value at all the points. Lines with no live
+//! allocation are zero.
+//!
+//! Each line keeps the times of creation of its live allocations in a [`LiveTocs`], which answers
+//! percentile queries in logarithmic time with a [`SortedSketch`]. The maximum is the lifetime of
+//! the oldest live allocation: when it dies, the maximum drops to the lifetime of the next oldest
+//! one.
+//!
+//! [`LifetimeStat`]: ../../settings/enum.LifetimeStat.html (LifetimeStat enum)
+//! [`LiveTocs`]: struct.LiveTocs.html (LiveTocs struct)
+//! [`SortedSketch`]: ../../sketch/struct.SortedSketch.html (SortedSketch struct)
+
+prelude! {}
+
+use chart::{
+    settings::LifetimeStat,
+    sketch::{self, SortedSketch},
+};
+use point::TimeFloatPoints;
+
+/// Times of creation of the live allocations of a line.
+///
+/// Allocations must be added in order of creation, which is the order of their UIDs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveTocs {
+    /// Times of creation of the allocations, by UID.
+    tocs: SortedSketch<uid::Alloc, time::SinceStart>,
+    /// Sum of the times of creation of the live allocations.
+    toc_sum: time::SinceStart,
+}
+
+impl Default for LiveTocs {
+    fn default() -> Self {
+        Self {
+            tocs: SortedSketch::new(),
+            toc_sum: time::SinceStart::zero(),
+        }
+    }
+}
+
+impl LiveTocs {
+    /// Number of live allocations.
+    pub fn len(&self) -> usize {
+        self.tocs.len()
+    }
+    /// True if there are no live allocations.
+    pub fn is_empty(&self) -> bool {
+        self.tocs.is_empty()
+    }
+
+    /// Registers a new allocation.
+    ///
+    /// Ignores allocations that are not more recent than the last one added.
+    pub fn add(&mut self, uid: uid::Alloc, toc: time::SinceStart) {
+        let len = self.tocs.len();
+        self.tocs.add(uid, toc);
+        if self.tocs.len() > len {
+            self.toc_sum = self.toc_sum + toc
+        }
+    }
+
+    /// Registers the death of an allocation.
+    ///
+    /// Ignores allocations it does not know, *e.g.* because the filter catching it changed since
+    /// its creation.
+    pub fn remove(&mut self, uid: &uid::Alloc) {
+        if let Some(toc) = self.tocs.remove(uid) {
+            self.toc_sum = self.toc_sum - toc
+        }
+    }
+
+    /// Value of a lifetime statistic at some time, in seconds.
+    ///
+    /// Percentiles use the nearest [`rank`]. Zero if there are no live allocations.
+    ///
+    /// [`rank`]: ../../sketch/fn.rank.html (The rank function)
+    pub fn stat(&self, stat: LifetimeStat, now: time::SinceStart) -> f32 {
+        let count = self.len();
+        if count == 0 {
+            return 0.0;
+        }
+        let toc = match stat.quantile() {
+            None => self.toc_sum.as_secs_f64() / (count as f64),
+            Some(quantile) => {
+                // Younger allocations have shorter lifetimes: the rank of a lifetime is the
+                // reverse of the rank of the time of creation.
+                let rank = sketch::rank(quantile, count as u64) as usize;
+                self.tocs
+                    .nth(count - rank + 1)
+                    .expect("ranks are between 1 and the number of live allocations")
+                    .as_secs_f64()
+            }
+        };
+        (now.as_secs_f64() - toc).max(0.0) as f32
+    }
+}
+
+/// Lifetime statistic of the live allocations over time chart.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeLifetime {
+    /// UID of the last allocation, and timestamp of the last deallocation.
+    last: Option<(uid::Alloc, time::SinceStart)>,
+    /// Statistic the chart shows.
+    lifetime_stat: LifetimeStat,
+    /// Live allocations of each line.
+    live: PointVal<LiveTocs>,
+    /// Optional last timestamp.
+    last_time_stamp: Option<time::SinceStart>,
+    /// Points.
+    points: TimeFloatPoints,
+}
+
+impl TimeLifetime {
+    /// Constructor.
+    pub fn new(filters: &filter::Filters) -> Self {
+        Self {
+            last: None,
+            lifetime_stat: LifetimeStat::default(),
+            live: PointVal::new(LiveTocs::default(), filters),
+            last_time_stamp: None,
+            points: TimeFloatPoints::with_capacity(32),
+        }
+    }
+
+    /// Sets the statistic the chart shows.
+    ///
+    /// Only affects the points generated after the next reset.
+    pub fn set_lifetime_stat(&mut self, lifetime_stat: LifetimeStat) {
+        self.lifetime_stat = lifetime_stat
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl TimeLifetime {
+    /// Retrieves the new points since the last time it was called.
+    pub fn new_points(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<Points>> {
+        self.do_it(filters, init, resolution, quality, time_windopt)
+            .map(|opt| opt.map(Points::from))
+    }
+
+    /// Resets (drops) all its points and re-initializes itself for `filters`.
+    pub fn reset(&mut self, filters: &filter::Filters) {
+        self.last = None;
+        self.last_time_stamp = None;
+        self.live = PointVal::new(LiveTocs::default(), filters);
+        self.points.clear();
+    }
+}
+
+/// # Helpers for point generation
+#[cfg(any(test, feature = "server"))]
+impl TimeLifetime {
+    /// Registers a new (`add`) or dead allocation for a line, and the everything line.
+    fn update(live: &mut PointVal<LiveTocs>, line: uid::Line, add: bool, alloc: &Alloc) {
+        let mut update = |line| {
+            let tocs = live.get_mut_or(line, LiveTocs::default());
+            if add {
+                tocs.add(*alloc.uid(), alloc.toc)
+            } else {
+                tocs.remove(alloc.uid())
+            }
+        };
+        update(line);
+        update(uid::Line::Everything)
+    }
+
+    /// Value of the statistic for all the lines at some time.
+    fn stats(
+        live: &PointVal<LiveTocs>,
+        stat: LifetimeStat,
+        now: time::SinceStart,
+    ) -> PointVal<f32> {
+        let mut vals = PointVal::empty();
+        for (line, tocs) in live.map.iter() {
+            vals.map.insert(*line, tocs.stat(stat, now));
+        }
+        vals
+    }
+
+    fn do_it(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<TimeFloatPoints>> {
+        let data = data::get()?;
+
+        if !data.has_new_stuff_since(self.last.clone()) {
+            return Ok(None);
+        }
+
+        let time_window = time_windopt.to_time_window(|| *data.current_time());
+        let min_time_spacing = resolution.min_time_spacing(quality, data.current_time());
+
+        debug_assert!(self.points.is_empty());
+        if init {
+            self.reset(filters);
+        }
+
+        let stat = self.lifetime_stat;
+        let first = self
+            .last_time_stamp
+            .or(time_windopt.lbound)
+            .unwrap_or_else(time::SinceStart::zero);
+        self.points
+            .push(Point::new(first, Self::stats(&self.live, stat, first)));
+
+        let (last_time_stamp, live, points) =
+            (&mut self.last_time_stamp, &mut self.live, &mut self.points);
+        // True if events below the time-window changed the live allocations since the first point
+        // was computed.
+        let mut first_is_stale = false;
+
+        data.iter_new_events(self.last.clone(), |new_or_dead| {
+            let (timestamp, add, alloc) = new_or_dead.as_ref().either(
+                |alloc| (alloc.toc, true, alloc),
+                |(tod, alloc)| (*tod, false, alloc),
+            );
+            let f_uid = if let Some(f_uid) = filters.find_match(data.current_time(), alloc) {
+                uid::Line::Filter(f_uid)
+            } else {
+                uid::Line::CatchAll
+            };
+
+            if first_is_stale && time_window.cmp(timestamp) != base::RangeCmp::Below {
+                first_is_stale = false;
+                if let Some(first) = points.first_mut() {
+                    first.vals = Self::stats(live, stat, first.key)
+                }
+            }
+
+            match time_window.cmp(timestamp) {
+                // Below the time-window, the first point is updated lazily.
+                base::RangeCmp::Below => {
+                    debug_assert!(points.len() == 1);
+                    *last_time_stamp = Some(timestamp);
+                    Self::update(live, f_uid, add, alloc);
+                    first_is_stale = true;
+                    Ok(true)
+                }
+
+                // Inside the time-window.
+                base::RangeCmp::Inside => {
+                    let timestamp = match last_time_stamp {
+                        Some(last) if timestamp - *last < min_time_spacing => *last,
+                        _ => {
+                            *last_time_stamp = Some(timestamp);
+                            timestamp
+                        }
+                    };
+
+                    let last_key = points.last().map(|point| point.key);
+                    if last_key != Some(timestamp) {
+                        // The values right before the event, lifetimes grew since the last point.
+                        let before = Point::new(timestamp, Self::stats(live, stat, timestamp));
+                        points.push(before.clone());
+                        points.push(before);
+                    }
+
+                    Self::update(live, f_uid, add, alloc);
+
+                    let last = points
+                        .last_mut()
+                        .expect("`last_mut` after `push` cannot fail");
+                    for line in &[f_uid, uid::Line::Everything] {
+                        let val = live.map[line].stat(stat, timestamp);
+                        last.vals.map.insert(*line, val);
+                    }
+
+                    Ok(true)
+                }
+
+                // Above the range: generate the very last point and early exit.
+                base::RangeCmp::Above => {
+                    let end_time = time_window.ubound;
+                    if points
+                        .last()
+                        .map(|last| last.key < end_time)
+                        .unwrap_or(true)
+                    {
+                        points.push(Point::new(end_time, Self::stats(live, stat, end_time)))
+                    }
+                    Ok(false)
+                }
+            }
+        })?;
+
+        if first_is_stale {
+            if let Some(first) = points.first_mut() {
+                first.vals = Self::stats(live, stat, first.key)
+            }
+        }
+
+        // Pad the points up to the end of the time-window, in particular so that a single point
+        // still shows as a line.
+        if points
+            .last()
+            .map(|last| last.key < time_window.ubound)
+            .unwrap_or(true)
+        {
+            points.push(Point::new(
+                time_window.ubound,
+                Self::stats(live, stat, time_window.ubound),
+            ))
+        }
+
+        self.last = data.last_events();
+
+        debug_assert!(!points.is_empty());
+        Ok(Some(points.drain(0..).collect()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn secs(secs: u64) -> time::SinceStart {
+        time::SinceStart::from_secs(secs)
+    }
+
+    #[test]
+    fn stats() {
+        let mut tocs = LiveTocs::default();
+        assert_eq!(tocs.stat(LifetimeStat::Mean, secs(10)), 0.0);
+        assert_eq!(tocs.stat(LifetimeStat::P95, secs(10)), 0.0);
+
+        // Allocations created at 1, 2, ..., 20 seconds.
+        for idx in 1..=20 {
+            tocs.add(uid::Alloc::from(idx), secs(idx))
+        }
+        assert_eq!(tocs.len(), 20);
+        assert_eq!(tocs.stat(LifetimeStat::Mean, secs(20)), 9.5);
+        assert_eq!(tocs.stat(LifetimeStat::Median, secs(20)), 9.0);
+        assert_eq!(tocs.stat(LifetimeStat::P95, secs(20)), 18.0);
+        assert_eq!(tocs.stat(LifetimeStat::Max, secs(20)), 19.0);
+        // Lifetimes grow with time.
+        assert_eq!(tocs.stat(LifetimeStat::Median, secs(30)), 19.0);
+
+        // Kill the oldest half, and an unknown allocation.
+        for idx in 1..=10 {
+            tocs.remove(&uid::Alloc::new(idx))
+        }
+        tocs.remove(&uid::Alloc::new(42));
+        tocs.remove(&uid::Alloc::new(1));
+        assert_eq!(tocs.len(), 10);
+        assert_eq!(tocs.stat(LifetimeStat::Mean, secs(20)), 4.5);
+        assert_eq!(tocs.stat(LifetimeStat::Median, secs(20)), 4.0);
+        assert_eq!(tocs.stat(LifetimeStat::P95, secs(20)), 9.0);
+        // The oldest allocation died, the maximum is the lifetime of the next oldest one.
+        assert_eq!(tocs.stat(LifetimeStat::Max, secs(20)), 9.0);
+
+        for idx in 11..=20 {
+            tocs.remove(&uid::Alloc::new(idx))
+        }
+        assert!(tocs.is_empty());
+        assert_eq!(tocs.stat(LifetimeStat::Median, secs(20)), 0.0);
+    }
+}
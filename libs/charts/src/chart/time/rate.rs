@@ -0,0 +1,280 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Allocation rate over time chart.
+//!
+//! For each time bucket, and for each filter, this chart sums the sizes of the allocations created
+//! in that bucket. It shows allocation *pressure*, which [`TimeSize`] does not: a program can
+//! allocate a lot while its live size stays flat. Deaths are ignored.
+//!
+//! The width of the buckets is the minimal time spacing of the resolution of the chart, computed
+//! when the chart is (re)generated. Buckets with no allocation yield explicit zero points, so that
+//! the lines drop to zero during quiet periods instead of interpolating across them.
+//!
+//! [`TimeSize`]: ../size/struct.TimeSize.html (TimeSize chart)
+
+prelude! {}
+
+use point::{Size, TimeSizePoints};
+
+/// Allocation rate over time chart.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeRate {
+    /// UID of the last allocation, and timestamp of the last deallocation.
+    last: Option<(uid::Alloc, time::SinceStart)>,
+    /// Bucketing state, `None` until the chart is generated.
+    buckets: Option<Buckets>,
+    /// Points.
+    points: TimeSizePoints,
+}
+
+impl TimeRate {
+    /// Constructor.
+    pub fn new(_filters: &filter::Filters) -> Self {
+        Self {
+            last: None,
+            buckets: None,
+            points: TimeSizePoints::with_capacity(32),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl TimeRate {
+    /// Retrieves the new points since the last time it was called.
+    pub fn new_points(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<Points>> {
+        self.do_it(filters, init, resolution, quality, time_windopt)
+            .map(|opt| opt.map(Points::from))
+    }
+
+    /// Resets (drops) all its points and re-initializes itself for `filters`.
+    pub fn reset(&mut self, _filters: &filter::Filters) {
+        self.last = None;
+        self.buckets = None;
+        self.points.clear();
+    }
+}
+
+/// # Helpers for point generation
+#[cfg(any(test, feature = "server"))]
+impl TimeRate {
+    fn do_it(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<TimeSizePoints>> {
+        let data = data::get()?;
+
+        if init {
+            self.reset(filters);
+        }
+
+        if !init && !data.has_new_stuff_since(self.last.clone()) {
+            return Ok(None);
+        }
+
+        let time_window = time_windopt.to_time_window(|| *data.current_time());
+        // The width only changes when the chart is regenerated, so that all buckets have the same
+        // width even though the duration of the run grows.
+        let buckets = self.buckets.get_or_insert_with(|| {
+            Buckets::new(resolution.min_time_spacing(quality, data.current_time()))
+        });
+
+        debug_assert!(self.points.is_empty());
+        let points = &mut self.points;
+
+        let since = self.last;
+        data.iter_new_changes(since, filters, |filters, change, _| {
+            // Deaths do not matter.
+            if !change.add {
+                return Ok(true);
+            }
+
+            match time_window.cmp(change.time) {
+                // Below the time-window, nothing to do.
+                base::RangeCmp::Below => Ok(true),
+                // Inside the time-window, update the current bucket.
+                base::RangeCmp::Inside => {
+                    buckets.add(points, filters, change.time, change.line, change.bytes);
+                    Ok(true)
+                }
+                // Above the range, early exit.
+                base::RangeCmp::Above => Ok(false),
+            }
+        })?;
+
+        // Flush the last bucket so that the client sees the most recent events, and drop to zero
+        // if nothing happened since.
+        buckets.close(points);
+        buckets.fill(points, time_window.ubound, filters);
+
+        // Make sure the client always has something to show.
+        if init && points.is_empty() {
+            let zero = PointVal::new(Size::new(0u64), filters);
+            points.push(Point::new(time_window.lbound, zero.clone()));
+            points.push(Point::new(time_window.ubound, zero));
+        }
+
+        self.last = data.last_events();
+
+        if points.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(points.drain(0..).collect()))
+        }
+    }
+}
+
+/// Bucketing state of an allocation rate chart.
+#[derive(Debug, Serialize, Deserialize)]
+struct Buckets {
+    /// Width of the buckets.
+    width: time::SinceStart,
+    /// Start of the last bucket turned into a point, if any.
+    last_start: Option<time::SinceStart>,
+    /// Key of the last point generated, if any.
+    last_key: Option<time::SinceStart>,
+    /// Bucket currently being filled: start timestamp and sizes.
+    current: Option<(time::SinceStart, PointVal<Size>)>,
+}
+
+#[cfg(any(test, feature = "server"))]
+impl Buckets {
+    /// Constructor.
+    fn new(width: time::SinceStart) -> Self {
+        Self {
+            width,
+            last_start: None,
+            last_key: None,
+            current: None,
+        }
+    }
+
+    /// Registers an allocation.
+    fn add(
+        &mut self,
+        points: &mut TimeSizePoints,
+        filters: &Filters,
+        timestamp: time::SinceStart,
+        line: uid::Line,
+        size: u64,
+    ) {
+        // Close the current bucket if `timestamp` is too far from its start.
+        if let Some((start, _)) = self.current.as_ref() {
+            if timestamp - *start >= self.width {
+                self.close(points)
+            }
+        }
+        if self.current.is_none() {
+            self.fill(points, timestamp, filters);
+            self.current = Some((timestamp, PointVal::new(Size::new(0u64), filters)))
+        }
+        if let Some((_, vals)) = self.current.as_mut() {
+            for uid in [line, uid::Line::Everything].iter() {
+                vals.get_mut_or(*uid, Size::new(0u64)).size += size
+            }
+        }
+    }
+
+    /// Turns the current bucket, if any, into a point.
+    fn close(&mut self, points: &mut TimeSizePoints) {
+        if let Some((start, vals)) = self.current.take() {
+            self.last_start = Some(start);
+            self.push(points, start, vals)
+        }
+    }
+
+    /// Generates zero points for the empty buckets between the last bucket and `until`, if any.
+    ///
+    /// The line drops to zero right after the last bucket, and stays there until right before
+    /// `until`.
+    fn fill(&mut self, points: &mut TimeSizePoints, until: time::SinceStart, filters: &Filters) {
+        let last_end = match self.last_start {
+            // Zero-width buckets cannot be empty.
+            Some(_) if self.width.is_zero() => return,
+            Some(last_start) => last_start + self.width,
+            None => return,
+        };
+        if last_end + self.width <= until {
+            let zero = PointVal::new(Size::new(0u64), filters);
+            self.push(points, last_end, zero.clone());
+            self.push(points, until - self.width, zero)
+        }
+    }
+
+    /// Pushes a point, ignores it if its key is not after the key of the last point.
+    fn push(&mut self, points: &mut TimeSizePoints, key: time::SinceStart, vals: PointVal<Size>) {
+        if self.last_key.map(|last| last < key).unwrap_or(true) {
+            self.last_key = Some(key);
+            points.push(Point::new(key, vals))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn empty_buckets_are_zero() {
+        let filters = Filters::new();
+        let secs = time::SinceStart::from_secs;
+        let mut points = TimeSizePoints::new();
+        let mut buckets = Buckets::new(secs(10));
+
+        for (timestamp, size) in vec![(1, 5), (3, 5), (15, 7), (60, 1)] {
+            buckets.add(
+                &mut points,
+                &filters,
+                secs(timestamp),
+                uid::Line::CatchAll,
+                size,
+            )
+        }
+        buckets.close(&mut points);
+        // The last bucket is recent, nothing to fill.
+        buckets.fill(&mut points, secs(65), &filters);
+        let res: Vec<_> = points
+            .iter()
+            .map(|point| {
+                (
+                    point.key.as_secs(),
+                    point.vals.map[&uid::Line::Everything].size,
+                )
+            })
+            .collect();
+        assert_eq!(res, vec![(1, 10), (15, 7), (25, 0), (50, 0), (60, 1)]);
+
+        // Trailing quiet period, and no duplicate point when filling up to the same point again.
+        buckets.fill(&mut points, secs(100), &filters);
+        buckets.fill(&mut points, secs(100), &filters);
+        let keys: Vec<_> = points.iter().map(|point| point.key.as_secs()).collect();
+        assert_eq!(keys, vec![1, 15, 25, 50, 60, 70, 90]);
+    }
+}
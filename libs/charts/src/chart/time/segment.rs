@@ -0,0 +1,387 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Live size by trace segment over time chart.
+//!
+//! The run is split in consecutive *segments* of the same duration. Each allocation belongs to the
+//! segment it was created in, and this chart shows the total size of the live allocations of each
+//! segment. Stacked, it shows old segments draining as their allocations die. Filters play no role
+//! in this chart: its lines are [segment lines] and the *everything* line.
+//!
+//! The duration of the segments is decided when the chart is (re)generated, so that the run at that
+//! point spans [`MAX_SEGMENTS`] segments. At most [`MAX_SEGMENTS`] segments have their own line
+//! though: beyond that, the oldest segment is folded in the catch-all line, which then stands for
+//! all the older segments. A segment that is over and has no live allocation left loses its line.
+//!
+//! [segment lines]: ../../../../base/uid/enum.Line.html#variant.Segment (Segment lines)
+//! [`MAX_SEGMENTS`]: constant.MAX_SEGMENTS.html (MAX_SEGMENTS constant)
+
+prelude! {}
+
+use point::{Size, TimeSizePoints};
+
+/// Maximum number of segments that have their own line.
+pub const MAX_SEGMENTS: usize = 12;
+
+/// Minimal duration of a segment, in nanoseconds.
+#[cfg(any(test, feature = "server"))]
+const MIN_SPAN_NANOS: u32 = 100_000_000;
+
+/// Specifications of the lines of a segment chart that are not filters.
+///
+/// Segments are colored from oldest (light) to newest (dark). The catch-all line, if any, stands
+/// for the older segments and is gray.
+///
+/// ```rust
+/// # use charts::prelude::*;
+/// use charts::chart::time::segment;
+/// let (old, new) = (uid::Segment::from(3), uid::Segment::from(7));
+/// let lines = vec![
+///     uid::Line::Everything,
+///     uid::Line::CatchAll,
+///     uid::Line::Segment(new),
+///     uid::Line::Segment(old),
+/// ];
+/// let specs = segment::line_specs(lines);
+/// let uids: Vec<_> = specs.iter().map(|spec| spec.uid()).collect();
+/// assert_eq!(
+///     uids,
+///     vec![uid::Line::CatchAll, uid::Line::Segment(old), uid::Line::Segment(new)],
+/// );
+/// assert_eq!(specs[0].name().as_str(), "older segments");
+/// assert!(specs[1].color().luminance() > specs[2].color().luminance());
+/// ```
+pub fn line_specs(lines: impl IntoIterator<Item = uid::Line>) -> Vec<filter::FilterSpec> {
+    let lines: BTSet<uid::Line> = lines.into_iter().collect();
+    let segments: Vec<uid::Segment> = lines.iter().filter_map(|line| line.segment_uid()).collect();
+    let mut specs = Vec::with_capacity(segments.len() + 1);
+    if lines.contains(&uid::Line::CatchAll) {
+        let mut spec = filter::FilterSpec::new_catch_all();
+        spec.set_name(filter::FilterName::new_truncated("older segments"));
+        spec.set_color(Color::new(0x99, 0x99, 0x99));
+        specs.push(spec)
+    }
+    let max = segments.len().saturating_sub(1).max(1) as f64;
+    for (index, segment) in segments.into_iter().enumerate() {
+        specs.push(filter::FilterSpec::new_segment(
+            segment,
+            Color::sequential(index as f64 / max),
+        ))
+    }
+    specs
+}
+
+/// Live size by trace segment over time chart.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeSegment {
+    /// UID of the last allocation, and timestamp of the last deallocation.
+    last: Option<(uid::Alloc, time::SinceStart)>,
+    /// Live sizes of the segments, `None` until the chart is generated.
+    lines: Option<Lines>,
+    /// Points.
+    points: TimeSizePoints,
+}
+
+impl TimeSegment {
+    /// Constructor.
+    pub fn new(_filters: &filter::Filters) -> Self {
+        Self {
+            last: None,
+            lines: None,
+            points: TimeSizePoints::with_capacity(32),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl TimeSegment {
+    /// Retrieves the new points since the last time it was called.
+    pub fn new_points(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<Points>> {
+        self.do_it(filters, init, resolution, quality, time_windopt)
+            .map(|opt| opt.map(Points::from))
+    }
+
+    /// Resets (drops) all its points and re-initializes itself.
+    pub fn reset(&mut self, _filters: &filter::Filters) {
+        self.last = None;
+        self.lines = None;
+        self.points.clear();
+    }
+}
+
+/// # Helpers for point generation
+#[cfg(any(test, feature = "server"))]
+impl TimeSegment {
+    fn do_it(
+        &mut self,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+    ) -> Res<Option<TimeSizePoints>> {
+        let data = data::get()?;
+
+        if init {
+            self.reset(filters);
+        }
+
+        if !init && !data.has_new_stuff_since(self.last.clone()) {
+            return Ok(None);
+        }
+
+        let time_window = time_windopt.to_time_window(|| *data.current_time());
+        let min_time_spacing = resolution.min_time_spacing(quality, data.current_time());
+        let lines = self
+            .lines
+            .get_or_insert_with(|| Lines::new(Lines::span(data.current_time())));
+
+        debug_assert!(self.points.is_empty());
+        let points = &mut self.points;
+        // Key of the first point, only when generating the chart from scratch.
+        let mut start = if init { Some(time_window.lbound) } else { None };
+
+        data.iter_new_events(self.last.clone(), |new_or_dead| {
+            let (timestamp, size, add, alloc) = new_or_dead.as_ref().either(
+                |alloc| (alloc.toc, alloc.real_size, true, alloc),
+                |(tod, alloc)| (*tod, alloc.real_size, false, alloc),
+            );
+
+            match time_window.cmp(timestamp) {
+                // Below the time-window, only update the sizes.
+                base::RangeCmp::Below => {
+                    lines.update(timestamp, alloc.toc, size as u64, add)?;
+                    Ok(true)
+                }
+                // Inside the time-window, update the last point or create a new one.
+                base::RangeCmp::Inside => {
+                    if let Some(start) = start.take() {
+                        points.push(Point::new(start, lines.live.clone()))
+                    }
+                    let dropped = lines.update(timestamp, alloc.toc, size as u64, add)?;
+
+                    let coalesce = points
+                        .last()
+                        .map(|last| timestamp - last.key < min_time_spacing)
+                        .unwrap_or(false);
+                    if !coalesce {
+                        points.push(Point::new(timestamp, PointVal::empty()))
+                    }
+                    let vals = &mut points
+                        .last_mut()
+                        .expect("`last_mut` after `push` cannot fail")
+                        .vals;
+                    for (line, val) in lines.live.map.iter() {
+                        vals.map.insert(*line, *val);
+                    }
+                    for line in dropped {
+                        vals.map.insert(line, Size::new(0u64));
+                    }
+                    Ok(true)
+                }
+                // Above the range, early exit.
+                base::RangeCmp::Above => Ok(false),
+            }
+        })?;
+
+        if let Some(start) = start {
+            points.push(Point::new(start, lines.live.clone()))
+        }
+        // Show the current sizes until the end of the time window.
+        if points
+            .last()
+            .map(|last| last.key < time_window.ubound)
+            .unwrap_or(false)
+        {
+            points.push(Point::new(time_window.ubound, lines.live.clone()))
+        }
+
+        self.last = data.last_events();
+
+        if points.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(points.drain(0..).collect()))
+        }
+    }
+}
+
+/// Live sizes of the segments of a segment chart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Lines {
+    /// Duration of the segments.
+    span: time::SinceStart,
+    /// Newest segment folded in the catch-all line, if any.
+    folded: Option<uid::Segment>,
+    /// Live size of each line.
+    live: PointVal<Size>,
+}
+
+#[cfg(any(test, feature = "server"))]
+impl Lines {
+    /// Constructor.
+    fn new(span: time::SinceStart) -> Self {
+        let mut live = PointVal::empty();
+        live.map.insert(uid::Line::CatchAll, Size::new(0u64));
+        live.map.insert(uid::Line::Everything, Size::new(0u64));
+        Self {
+            span,
+            folded: None,
+            live,
+        }
+    }
+
+    /// Duration of the segments for a run of some duration.
+    fn span(duration: &time::SinceStart) -> time::SinceStart {
+        let span = *duration / MAX_SEGMENTS as u32;
+        let min = time::SinceStart::from_nano_timestamp(0, MIN_SPAN_NANOS);
+        if span < min {
+            min
+        } else {
+            span
+        }
+    }
+
+    /// Segment of an allocation created at `toc`.
+    fn segment_of(&self, toc: time::SinceStart) -> uid::Segment {
+        uid::Segment::from((toc.as_nanos() / self.span.as_nanos()) as usize)
+    }
+
+    /// True if a segment is over at some point in time.
+    fn is_over(&self, segment: uid::Segment, timestamp: time::SinceStart) -> bool {
+        (segment.get() as u128 + 1) * self.span.as_nanos() <= timestamp.as_nanos()
+    }
+
+    /// Registers an allocation created at `toc`, or its death.
+    ///
+    /// Returns the lines that lost their value: drained and folded segments.
+    fn update(
+        &mut self,
+        timestamp: time::SinceStart,
+        toc: time::SinceStart,
+        size: u64,
+        add: bool,
+    ) -> Res<Vec<uid::Line>> {
+        let segment = self.segment_of(toc);
+        let line = if self.folded.map(|folded| segment <= folded).unwrap_or(false) {
+            uid::Line::CatchAll
+        } else {
+            uid::Line::Segment(segment)
+        };
+
+        for line in [line, uid::Line::Everything].iter() {
+            let val = self.live.get_mut_or(*line, Size::new(0u64));
+            if add {
+                val.size += size
+            } else if val.size < size {
+                bail!("underflow on line {}", line)
+            } else {
+                val.size -= size
+            }
+        }
+
+        let mut dropped = vec![];
+
+        if !add && line.segment_uid().is_some() && self.is_over(segment, timestamp) {
+            if let Some(Size { size: 0 }) = self.live.map.get(&line) {
+                self.live.map.remove(&line);
+                dropped.push(line)
+            }
+        }
+
+        while self
+            .live
+            .map
+            .keys()
+            .filter(|line| line.segment_uid().is_some())
+            .count()
+            > MAX_SEGMENTS
+        {
+            let oldest = self
+                .live
+                .map
+                .keys()
+                .find_map(|line| line.segment_uid())
+                .expect("there are more than `MAX_SEGMENTS` segments");
+            let line = uid::Line::Segment(oldest);
+            if let Some(val) = self.live.map.remove(&line) {
+                self.live
+                    .get_mut_or(uid::Line::CatchAll, Size::new(0u64))
+                    .size += val.size
+            }
+            self.folded = Some(oldest);
+            dropped.push(line)
+        }
+
+        Ok(dropped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn segments_drain_and_fold() {
+        let secs = time::SinceStart::from_secs;
+        let segment = |index: usize| uid::Line::Segment(uid::Segment::from(index));
+        let mut lines = Lines::new(secs(10));
+        let live = |lines: &Lines, line| lines.live.map.get(&line).map(|val| val.size);
+
+        lines.update(secs(1), secs(1), 5, true).unwrap();
+        lines.update(secs(12), secs(12), 7, true).unwrap();
+        assert_eq!(live(&lines, segment(0)), Some(5));
+        assert_eq!(live(&lines, segment(1)), Some(7));
+        assert_eq!(live(&lines, uid::Line::Everything), Some(12));
+
+        // Draining a segment that is not over keeps its line.
+        lines.update(secs(15), secs(12), 7, false).unwrap();
+        assert_eq!(live(&lines, segment(1)), Some(0));
+        // Draining a segment that is over drops it.
+        let dropped = lines.update(secs(15), secs(1), 5, false).unwrap();
+        assert_eq!(dropped, vec![segment(0)]);
+        assert_eq!(live(&lines, segment(0)), None);
+        assert!(lines.update(secs(16), secs(1), 1, false).is_err());
+
+        // Going over the maximum number of segments folds the oldest ones.
+        for index in 2..(MAX_SEGMENTS + 4) {
+            let toc = secs(10 * index as u64);
+            lines.update(toc, toc, 1, true).unwrap();
+        }
+        assert_eq!(lines.folded, Some(uid::Segment::from(3)));
+        assert_eq!(live(&lines, segment(1)), None);
+        assert_eq!(live(&lines, segment(3)), None);
+        assert_eq!(live(&lines, segment(4)), Some(1));
+        assert_eq!(live(&lines, uid::Line::CatchAll), Some(2));
+        // The allocations of folded segments are accounted for in the catch-all line.
+        lines.update(secs(200), secs(25), 1, false).unwrap();
+        assert_eq!(live(&lines, uid::Line::CatchAll), Some(1));
+        assert_eq!(
+            live(&lines, uid::Line::Everything),
+            Some(MAX_SEGMENTS as u64 + 1)
+        );
+    }
+}
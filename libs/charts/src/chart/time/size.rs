@@ -26,6 +26,13 @@ use point::{Size, TimeSizePoints};
 /// Initial size value.
 const INIT_SIZE_VALUE: u64 = 0;
 
+/// Maximal number of events handled by one point extraction.
+///
+/// Bounds the latency of an extraction when lots of events arrive at once, *e.g.* during the
+/// initial load. The remaining events are handled by the next extractions.
+#[cfg(any(test, feature = "server"))]
+const CHUNK_LEN: usize = 100_000;
+
 /// Total size over time chart.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TimeSize {
@@ -35,6 +42,11 @@ pub struct TimeSize {
     size: PointVal<Size>,
     /// Optional last timestamp.
     last_time_stamp: Option<time::SinceStart>,
+    /// UID of the last allocation and timestamp of the last deallocation handled, if the last
+    /// extraction stopped at the end of a chunk.
+    #[cfg(any(test, feature = "server"))]
+    #[serde(skip)]
+    pending: Option<(Option<uid::Alloc>, Option<time::SinceStart>)>,
     /// Points.
     points: TimeSizePoints,
 }
@@ -46,6 +58,8 @@ impl TimeSize {
             last: None,
             size: Self::init_size_point(filters),
             last_time_stamp: None,
+            #[cfg(any(test, feature = "server"))]
+            pending: None,
             points: TimeSizePoints::with_capacity(32),
         }
     }
@@ -59,9 +73,10 @@ impl TimeSize {
         filters: &mut Filters,
         init: bool,
         resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
         time_windopt: &TimeWindopt,
     ) -> Res<Option<Points>> {
-        self.do_it(filters, init, resolution, time_windopt)
+        self.do_it(filters, init, resolution, quality, time_windopt)
             .map(|opt| opt.map(Points::from))
     }
 
@@ -69,6 +84,7 @@ impl TimeSize {
     pub fn reset(&mut self, filters: &filter::Filters) {
         self.last = None;
         self.last_time_stamp = None;
+        self.pending = None;
         self.size = Self::init_size_point(filters);
     }
 }
@@ -81,6 +97,8 @@ impl TimeSize {
             last: None,
             size,
             last_time_stamp: None,
+            #[cfg(any(test, feature = "server"))]
+            pending: None,
             points: TimeSizePoints::with_capacity(32),
         }
     }
@@ -99,16 +117,42 @@ impl TimeSize {
         filters: &mut Filters,
         init: bool,
         resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
         time_windopt: &TimeWindopt,
     ) -> Res<Option<TimeSizePoints>> {
         let data = data::get()?;
+        self.extract(
+            &data,
+            filters,
+            init,
+            resolution,
+            quality,
+            time_windopt,
+            CHUNK_LEN,
+        )
+    }
 
-        if !data.has_new_stuff_since(self.last.clone()) {
+    /// Generates the points for, at most, the next `chunk_len` events.
+    ///
+    /// When there are more events than that, the next extraction resumes after the last event
+    /// handled. The events of a deallocation timestamp are handled together, so a chunk can be a
+    /// bit longer than `chunk_len`.
+    fn extract(
+        &mut self,
+        data: &data::Data,
+        filters: &mut Filters,
+        init: bool,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        time_windopt: &TimeWindopt,
+        chunk_len: usize,
+    ) -> Res<Option<TimeSizePoints>> {
+        if (init || self.pending.is_none()) && !data.has_new_stuff_since(self.last.clone()) {
             return Ok(None);
         }
 
         let time_window = time_windopt.to_time_window(|| *data.current_time());
-        let min_time_spacing = data.current_time().clone() / (resolution.width / 5);
+        let min_time_spacing = resolution.min_time_spacing(quality, data.current_time());
 
         debug_assert!(self.points.is_empty());
         if init {
@@ -127,28 +171,32 @@ impl TimeSize {
         ));
         let points = &mut self.points;
 
-        let (last_time_stamp, last_size, last) =
-            (&mut self.last_time_stamp, &mut self.size, self.last.clone());
+        // Resume after the last events handled, by the last chunk or by the last extraction.
+        let (mut last_alloc, mut last_tod) = match self.pending.take() {
+            Some(pending) => pending,
+            None => (self.last.map(|(uid, _)| uid), self.last.map(|(_, tod)| tod)),
+        };
+        let (last_time_stamp, last_size) = (&mut self.last_time_stamp, &mut self.size);
+
+        // Number of events handled, and whether the events went above the time window.
+        let (mut event_count, mut above) = (0, false);
 
         macro_rules! update {
             ($f_uid:expr, _, last_size => |ref mut $val:pat| $action:expr) => {{
                 let $val = last_size
                     .map
-                    .entry($f_uid)
-                    .or_insert_with(|| INIT_SIZE_VALUE.into());
+                    .get_or_insert_with($f_uid, || INIT_SIZE_VALUE.into());
                 $action;
                 let $val = last_size
                     .map
-                    .entry(uid::Line::Everything)
-                    .or_insert_with(|| INIT_SIZE_VALUE.into());
+                    .get_or_insert_with(uid::Line::Everything, || INIT_SIZE_VALUE.into());
                 $action;
             }};
             ($f_uid:expr, $map:expr => |ref mut $val:pat| $action:expr) => {{
-                let $val = $map.entry($f_uid).or_insert_with(|| update!(@last size $f_uid));
+                let $val = $map.get_or_insert_with($f_uid, || update!(@last size $f_uid));
                 $action;
                 let $val = $map
-                    .entry(uid::Line::Everything)
-                    .or_insert_with(|| update!(@last size $f_uid));
+                    .get_or_insert_with(uid::Line::Everything, || update!(@last size $f_uid));
                 $action;
             }};
             ($f_uid:expr, $map:expr, last_size => |ref mut $val:pat| $action:expr) => {{
@@ -157,7 +205,7 @@ impl TimeSize {
             }};
 
             (@last size $f_uid:expr) => {
-                *last_size.map.entry($f_uid).or_insert_with(|| INIT_SIZE_VALUE.into())
+                *last_size.map.get_or_insert_with($f_uid, || INIT_SIZE_VALUE.into())
             };
         }
 
@@ -194,16 +242,10 @@ impl TimeSize {
             }};
         }
 
-        data.iter_new_events(last, |new_or_dead| {
-            let (timestamp, size, add, alloc) = new_or_dead.as_ref().either(
-                |alloc| (alloc.toc, alloc.real_size, true, alloc),
-                |(tod, alloc)| (*tod, alloc.real_size, false, alloc),
-            );
-            let f_uid = if let Some(f_uid) = filters.find_match(data.current_time(), alloc) {
-                uid::Line::Filter(f_uid)
-            } else {
-                uid::Line::CatchAll
-            };
+        let last_events = (last_alloc, last_tod);
+        data.iter_changes_after(last_events, filters, |_, change, _| {
+            let (timestamp, size) = (change.time, change.bytes);
+            let (add, f_uid) = (change.add, change.line);
 
             match time_window.cmp(timestamp) {
                 // Below the time-window, update the first point if any.
@@ -225,12 +267,12 @@ impl TimeSize {
 
                     update!(
                         f_uid, last_map, last_size => |ref mut val| if add {
-                            val.size += size as u64
+                            val.size += size
                         } else {
-                            if val.size < size as u64 {
+                            if val.size < size {
                                 underflow = true;
                             }
-                            val.size -= size as u64
+                            val.size -= size
                         }
                     );
 
@@ -240,7 +282,13 @@ impl TimeSize {
                     }
 
                     debug_assert!(points.len() == 1);
-                    Ok(true)
+                    Ok(Self::handled(
+                        &change,
+                        &mut last_alloc,
+                        &mut last_tod,
+                        &mut event_count,
+                        chunk_len,
+                    ))
                 }
 
                 // Inside the time-window.
@@ -305,12 +353,12 @@ impl TimeSize {
 
                     update! {
                         f_uid, vals => |ref mut val| if add {
-                            val.size += size as u64
+                            val.size += size
                         } else {
-                            if val.size < size as u64 {
+                            if val.size < size {
                                 underflow = Some(val.size)
                             }
-                            val.size -= size as u64
+                            val.size -= size
                         }
                     }
 
@@ -338,12 +386,12 @@ impl TimeSize {
 
                     update! {
                         f_uid, _, last_size => |ref mut val| if add {
-                            val.size += size as u64
+                            val.size += size
                         } else {
-                            if val.size < size as u64 {
+                            if val.size < size {
                                 underflow = Some(val.size)
                             }
-                            val.size -= size as u64
+                            val.size -= size
                         }
                     }
 
@@ -353,11 +401,18 @@ impl TimeSize {
                         bail!("underflow on {} (last_size)", prev)
                     }
 
-                    Ok(true)
+                    Ok(Self::handled(
+                        &change,
+                        &mut last_alloc,
+                        &mut last_tod,
+                        &mut event_count,
+                        chunk_len,
+                    ))
                 }
 
                 // Above the range: generate the very last point and early exit.
                 base::RangeCmp::Above => {
+                    above = true;
                     let end_time = time_window.ubound;
                     if let Some(last) = points.last() {
                         if last.key < end_time {
@@ -371,14 +426,21 @@ impl TimeSize {
             }
         })?;
 
-        if let Some(ts) = last_time_stamp {
-            if *ts != time_window.ubound {
-                let point = Point::new(time_window.ubound, self.size.clone());
-                points.push(point)
+        if event_count >= chunk_len && !above {
+            // Stopped at the end of a chunk, the next extraction handles the remaining events. The
+            // last point is not extended to the end of the time window since its value is not the
+            // final one.
+            self.pending = Some((last_alloc, last_tod));
+        } else {
+            if let Some(ts) = last_time_stamp {
+                if *ts != time_window.ubound {
+                    let point = Point::new(time_window.ubound, self.size.clone());
+                    points.push(point)
+                }
             }
-        }
 
-        self.last = data.last_events();
+            self.last = data.last_events();
+        }
 
         debug_assert!(!points.is_empty());
         // println!();
@@ -393,4 +455,212 @@ impl TimeSize {
         // println!("}}");
         Ok(Some(points.drain(0..).collect()))
     }
+
+    /// Registers that a change was handled, returns `false` if the chunk is full.
+    fn handled(
+        change: &data::prune::Change,
+        last_alloc: &mut Option<uid::Alloc>,
+        last_tod: &mut Option<time::SinceStart>,
+        event_count: &mut usize,
+        chunk_len: usize,
+    ) -> bool {
+        match change.last {
+            Either::Left(uid) => *last_alloc = Some(uid),
+            Either::Right(tod) => *last_tod = Some(tod),
+        }
+        *event_count += 1;
+        *event_count < chunk_len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Data with some allocations, half of which die two by two at the same time.
+    fn data() -> data::Data {
+        let mut synth = crate::fixture::Synth::new();
+        let (trace, labels) = (crate::fixture::trace(&[]), crate::fixture::labels(&[]));
+        for idx in 0..40u64 {
+            synth.alloc(10 * idx, 8 + idx as u32, &trace, &labels);
+            if idx % 2 == 1 {
+                synth.dead(10 * idx + 5, idx - 1);
+                synth.dead(10 * idx + 5, idx);
+            }
+        }
+        synth.done()
+    }
+
+    /// Data where allocations of the same key are created at the same time, and die at various
+    /// times, possibly the time of creation of other allocations.
+    fn scattered() -> data::Data {
+        let mut synth = crate::fixture::Synth::new();
+        let (trace, labels) = (crate::fixture::trace(&[]), crate::fixture::labels(&[]));
+        let mut deaths: BTMap<u64, Vec<u64>> = BTMap::new();
+        for idx in 0..60u64 {
+            let toc = 3 * (idx / 2);
+            let later = deaths.split_off(&(toc + 1));
+            for (tod, uids) in std::mem::replace(&mut deaths, later) {
+                for uid in uids {
+                    synth.dead(tod, uid)
+                }
+            }
+            let uid = synth.alloc(toc, 8 + 16 * (idx / 2 % 3) as u32, &trace, &labels);
+            match idx % 3 {
+                0 => deaths
+                    .entry(toc + 4)
+                    .or_insert_with(Vec::new)
+                    .push(uid.get() as u64),
+                1 => deaths
+                    .entry(toc + 40)
+                    .or_insert_with(Vec::new)
+                    .push(uid.get() as u64),
+                _ => (),
+            }
+        }
+        for (tod, uids) in deaths {
+            for uid in uids {
+                synth.dead(tod, uid)
+            }
+        }
+        synth.done()
+    }
+
+    /// Filters catching the allocations of eight bytes or less.
+    fn small_filters() -> Filters {
+        let mut filter = Filter::new(filter::FilterSpec::new(Color::random())).unwrap();
+        let sub = filter::SizeFilter::Cmp {
+            cmp: filter::ord::Cmp::Le,
+            val: 8,
+        };
+        filter
+            .insert(filter::sub::SubFilter::new(
+                filter::sub::SubFilter::default().uid(),
+                sub.into(),
+            ))
+            .unwrap();
+        Filters::new_with(vec![filter])
+    }
+
+    /// Extracts all the points, yields the last sizes of each timestamp and the number of
+    /// extractions.
+    fn extract_all(
+        data: &data::Data,
+        mut filters: Filters,
+        resolution: chart::settings::Resolution,
+        quality: chart::settings::Quality,
+        chunk_len: usize,
+    ) -> (BTMap<time::SinceStart, BTMap<uid::Line, u64>>, usize) {
+        let mut chart = TimeSize::new(&filters);
+        let time_windopt = Range::new(None, None);
+        let (mut sizes, mut current, mut extractions) = (BTMap::new(), BTMap::new(), 0);
+        while let Some(points) = chart
+            .extract(
+                data,
+                &mut filters,
+                extractions == 0,
+                resolution,
+                quality,
+                &time_windopt,
+                chunk_len,
+            )
+            .unwrap()
+        {
+            extractions += 1;
+            assert!(extractions <= 1000, "extraction does not terminate");
+            // Points only mention some of the lines, the others keep their previous size.
+            for point in points {
+                for (line, val) in point.vals.map {
+                    let _ = current.insert(line, val.size);
+                }
+                let _ = sizes.insert(point.key, current.clone());
+            }
+        }
+        (sizes, extractions)
+    }
+
+    #[test]
+    fn chunks_do_not_double_count() {
+        use chart::settings::{Quality, Resolution};
+        let data = data();
+
+        // Native quality has one point per timestamp, low quality merges several events per point
+        // which lets chunks end in the middle of a point.
+        for (resolution, quality) in vec![
+            (Resolution::from((1000, 400)), Quality::Native),
+            (Resolution::from((30, 10)), Quality::Low),
+        ] {
+            let (expected, extractions) =
+                extract_all(&data, Filters::new(), resolution, quality, usize::MAX);
+            assert_eq!(extractions, 1);
+            let last = expected
+                .values()
+                .last()
+                .map(|sizes| sizes[&uid::Line::Everything]);
+            assert_eq!(last, Some(0));
+
+            for chunk_len in vec![1, 2, 3, 7] {
+                let (sizes, extractions) =
+                    extract_all(&data, Filters::new(), resolution, quality, chunk_len);
+                assert!(extractions > 1);
+                assert_eq!(sizes, expected, "chunk length {}", chunk_len);
+            }
+        }
+    }
+
+    #[test]
+    fn pruning_keeps_the_points() {
+        use chart::settings::{Quality, Resolution};
+        let millis = crate::fixture::millis;
+        let (resolution, quality) = (Resolution::from((1000, 400)), Quality::Native);
+
+        let mut data = data();
+        let (expected, _) = extract_all(&data, Filters::new(), resolution, quality, usize::MAX);
+        let count = data.prune(millis(200)).unwrap();
+
+        // The allocations that died at or before 200ms are the first twenty.
+        assert_eq!(count, 20);
+        assert!(data
+            .get_alloc(uid::Alloc::new(0))
+            .unwrap_err()
+            .is_pruned_alloc());
+        assert!(data.get_alloc(uid::Alloc::new(20)).is_ok());
+
+        for chunk_len in vec![usize::MAX, 3] {
+            let (sizes, _) = extract_all(&data, Filters::new(), resolution, quality, chunk_len);
+            assert_eq!(sizes, expected, "chunk length {}", chunk_len);
+        }
+    }
+
+    #[test]
+    fn pruned_allocations_follow_the_filters() {
+        use chart::settings::{Quality, Resolution};
+        let (resolution, quality) = (Resolution::from((1000, 400)), Quality::Native);
+        let mut data = scattered();
+        let all_filters = vec![Filters::new(), small_filters()];
+        let expected: Vec<_> = all_filters
+            .iter()
+            .map(|filters| extract_all(&data, filters.clone(), resolution, quality, usize::MAX).0)
+            .collect();
+        assert_ne!(expected[0], expected[1]);
+
+        // Prune twice, the second pruning drops allocations older than some live ones.
+        let (first, second) = (
+            data.prune(crate::fixture::millis(30)).unwrap(),
+            data.prune(crate::fixture::millis(70)).unwrap(),
+        );
+        assert!(first > 0 && second > 0);
+
+        for (idx, filters) in all_filters.iter().enumerate() {
+            for chunk_len in vec![usize::MAX, 1, 3] {
+                let (sizes, _) =
+                    extract_all(&data, filters.clone(), resolution, quality, chunk_len);
+                assert_eq!(
+                    sizes, expected[idx],
+                    "filters {}, chunk length {}",
+                    idx, chunk_len
+                );
+            }
+        }
+    }
 }
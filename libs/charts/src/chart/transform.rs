@@ -0,0 +1,365 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Post-processing of the points of a chart.
+//!
+//! A [`SeriesTransform`] turns the time series of each line of a chart into other time series. The
+//! settings of a chart store an ordered list of [`Transform`]s, which the chart turns into a
+//! [`Pipeline`] applied to the points it generates. Order matters: the rate of a smoothed series
+//! is not the smoothed rate of this series.
+//!
+//! Points reach the pipeline in batches, the pipeline remembers the end of the previous batches so
+//! that transforms looking at previous values produce the same points as if they saw the whole
+//! series at once, see [`SeriesTransform::history`].
+//!
+//! [`SeriesTransform`]: trait.SeriesTransform.html (The SeriesTransform trait)
+//! [`SeriesTransform::history`]: trait.SeriesTransform.html#method.history
+//! (The history method of SeriesTransform)
+//! [`Transform`]: enum.Transform.html (The Transform enum)
+//! [`Pipeline`]: struct.Pipeline.html (The Pipeline struct)
+
+prelude! {}
+
+/// Post-processes the points of a chart.
+///
+/// Transforms must produce exactly one point per point they are given, with the same key, and the
+/// values of a point can only depend on this point and the ones before it.
+pub trait SeriesTransform: fmt::Display + Send + Sync {
+    /// Number of previous values of each line the transform needs to see.
+    ///
+    /// Defaults to `0`, for transforms that only look at one point at a time.
+    fn history(&self) -> usize {
+        0
+    }
+    /// Applies the transform.
+    fn apply(&self, points: &mut Points);
+}
+
+/// Replaces some points by the result of a function on these points.
+fn map_points(points: &mut Points, f: impl FnOnce(Points) -> Points) {
+    let empty: point::TimeFloatPoints = vec![];
+    let taken = std::mem::replace(points, empty.into());
+    *points = f(taken)
+}
+
+/// Normalizes the points by a line, see [`Points::normalize`].
+///
+/// [`Points::normalize`]: ../../point/enum.Points.html#method.normalize (Points::normalize)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Normalize(pub uid::Line);
+impl SeriesTransform for Normalize {
+    fn apply(&self, points: &mut Points) {
+        map_points(points, |points| points.normalize(self.0))
+    }
+}
+base::implement! {
+    impl Normalize {
+        Display {
+            |&self, fmt| write!(fmt, "normalize by {}", self.0)
+        }
+    }
+}
+
+/// Variation per second of each line, see [`Points::rate`].
+///
+/// [`Points::rate`]: ../../point/enum.Points.html#method.rate (Points::rate)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate;
+impl SeriesTransform for Rate {
+    fn history(&self) -> usize {
+        1
+    }
+    fn apply(&self, points: &mut Points) {
+        map_points(points, Points::rate)
+    }
+}
+base::implement! {
+    impl Rate {
+        Display {
+            |&self, fmt| write!(fmt, "rate")
+        }
+    }
+}
+
+/// Moving average of each line over some number of values, see [`Points::smooth`].
+///
+/// [`Points::smooth`]: ../../point/enum.Points.html#method.smooth (Points::smooth)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Smooth(pub usize);
+impl SeriesTransform for Smooth {
+    fn history(&self) -> usize {
+        self.0.saturating_sub(1)
+    }
+    fn apply(&self, points: &mut Points) {
+        map_points(points, |points| points.smooth(self.0))
+    }
+}
+base::implement! {
+    impl Smooth {
+        Display {
+            |&self, fmt| write!(fmt, "smooth over {} values", self.0)
+        }
+    }
+}
+
+/// A transform, as stored in the settings of a chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Transform {
+    /// Normalization by a line, see [`Normalize`].
+    ///
+    /// [`Normalize`]: struct.Normalize.html (The Normalize struct)
+    Normalize(uid::Line),
+    /// Variation per second, see [`Rate`].
+    ///
+    /// [`Rate`]: struct.Rate.html (The Rate struct)
+    Rate,
+    /// Moving average over some number of values, see [`Smooth`].
+    ///
+    /// [`Smooth`]: struct.Smooth.html (The Smooth struct)
+    Smooth(usize),
+}
+impl Transform {
+    /// Default width of the moving average for smoothing.
+    pub const DEFAULT_SMOOTH: usize = 5;
+    /// Maximum width of the moving average for smoothing.
+    pub const MAX_SMOOTH: usize = 1_000;
+
+    /// Checks whether the transform is legal.
+    ///
+    /// ```rust
+    /// # use charts::chart::transform::Transform;
+    /// assert!(Transform::Rate.is_legal().is_none());
+    /// assert!(Transform::Smooth(3).is_legal().is_none());
+    /// assert!(Transform::Smooth(0).is_legal().is_some());
+    /// assert!(Transform::Smooth(Transform::MAX_SMOOTH + 1).is_legal().is_some());
+    /// ```
+    pub fn is_legal(&self) -> Option<String> {
+        match self {
+            Self::Smooth(width) if *width == 0 || *width > Self::MAX_SMOOTH => Some(format!(
+                "illegal smoothing width {}, expected a value between 1 and {}",
+                width,
+                Self::MAX_SMOOTH,
+            )),
+            Self::Normalize(_) | Self::Rate | Self::Smooth(_) => None,
+        }
+    }
+
+    /// Builds the actual transform.
+    pub fn build(&self) -> Box<dyn SeriesTransform> {
+        match *self {
+            Self::Normalize(line) => Box::new(Normalize(line)),
+            Self::Rate => Box::new(Rate),
+            Self::Smooth(width) => Box::new(Smooth(width)),
+        }
+    }
+}
+base::implement! {
+    impl Transform {
+        Display {
+            |&self, fmt| match self {
+                Self::Normalize(line) => write!(fmt, "{}", Normalize(*line)),
+                Self::Rate => write!(fmt, "{}", Rate),
+                Self::Smooth(width) => write!(fmt, "{}", Smooth(*width)),
+            }
+        }
+    }
+}
+
+/// A transform and the end of the points it was given so far.
+struct Stage {
+    /// The transform.
+    transform: Box<dyn SeriesTransform>,
+    /// Last input values of each line, as many as the transform needs.
+    history: Option<Points>,
+}
+
+/// An ordered list of transforms.
+///
+/// ```rust
+/// # use charts::{prelude::*, point::*, chart::transform::*};
+/// let point = |secs, size: u64| {
+///     let mut vals = PointVal::empty();
+///     vals.map.insert(uid::Line::Everything, Size::new(size));
+///     Point::new(time::SinceStart::from_secs(secs), vals)
+/// };
+/// let mut pipeline = Pipeline::new(&[Transform::Rate, Transform::Smooth(2)]);
+///
+/// // All the points at once.
+/// let all: TimeSizePoints = vec![point(0, 0), point(1, 2), point(2, 6), point(3, 6)];
+/// let mut all = Points::from(all);
+/// pipeline.apply(&mut all).unwrap();
+///
+/// // The same points in two batches.
+/// pipeline.reset();
+/// let (first, second): (TimeSizePoints, TimeSizePoints) =
+///     (vec![point(0, 0), point(1, 2)], vec![point(2, 6), point(3, 6)]);
+/// let (mut first, mut second) = (Points::from(first), Points::from(second));
+/// pipeline.apply(&mut first).unwrap();
+/// pipeline.apply(&mut second).unwrap();
+/// first.extend(&mut second).unwrap();
+///
+/// let expected = vec![(1.0, 2.0), (2.0, 3.0), (3.0, 2.0)];
+/// assert_eq!(all.series()[&uid::Line::Everything], expected);
+/// assert_eq!(first.series()[&uid::Line::Everything], expected);
+/// ```
+#[derive(Default)]
+pub struct Pipeline {
+    /// The transforms, in the order they are applied.
+    stages: Vec<Stage>,
+}
+impl Pipeline {
+    /// Constructor.
+    pub fn new<'a>(transforms: impl IntoIterator<Item = &'a Transform>) -> Self {
+        let mut slf = Self::default();
+        for transform in transforms {
+            slf.push(transform.build())
+        }
+        slf
+    }
+
+    /// Adds a transform at the end of the pipeline.
+    pub fn push(&mut self, transform: Box<dyn SeriesTransform>) {
+        self.stages.push(Stage {
+            transform,
+            history: None,
+        })
+    }
+
+    /// True if the pipeline has no transform.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Forgets the points seen so far.
+    ///
+    /// Must be called before giving the pipeline points that do not follow the previous ones.
+    pub fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.history = None
+        }
+    }
+
+    /// Applies the transforms in order.
+    ///
+    /// Fails if the points are not compatible with the points previously given to the pipeline.
+    pub fn apply(&mut self, points: &mut Points) -> Res<()> {
+        for stage in &mut self.stages {
+            let history = stage.transform.history();
+            if history == 0 {
+                stage.transform.apply(points);
+                continue;
+            }
+
+            let mut input = match stage.history.take() {
+                Some(mut input) => {
+                    input
+                        .extend(&mut points.clone())
+                        .chain_err(|| format!("while applying transform `{}`", stage.transform))?;
+                    input
+                }
+                None => points.clone(),
+            };
+            let known = input.len() - points.len();
+            stage.history = Some(input.last_vals(history));
+            stage.transform.apply(&mut input);
+            input.drop_first(known);
+            *points = input;
+        }
+        Ok(())
+    }
+}
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_list()
+            .entries(self.stages.iter().map(|stage| stage.transform.to_string()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use point::{Point, PointVal, Size, TimeSizePoints};
+
+    /// Everything-line size points from `(seconds, size)` pairs.
+    fn points(vals: &[(u64, u64)]) -> Points {
+        let points: TimeSizePoints = vals
+            .iter()
+            .map(|(secs, size)| {
+                let mut vals = PointVal::empty();
+                vals.map.insert(uid::Line::Everything, Size::new(*size));
+                Point::new(time::SinceStart::from_secs(*secs), vals)
+            })
+            .collect();
+        points.into()
+    }
+
+    /// Applies some transforms to some points, yields the everything line.
+    fn run(transforms: &[Transform], points: &mut Points) -> Vec<(f64, f64)> {
+        Pipeline::new(transforms).apply(points).unwrap();
+        points
+            .series()
+            .remove(&uid::Line::Everything)
+            .unwrap_or_else(Vec::new)
+    }
+
+    #[test]
+    fn order_matters() {
+        let input = points(&[(0, 0), (1, 10), (2, 10), (3, 40)]);
+
+        let rate_then_smooth = run(&[Transform::Rate, Transform::Smooth(2)], &mut input.clone());
+        // Rates are `10, 0, 30`.
+        assert_eq!(rate_then_smooth, vec![(1.0, 10.0), (2.0, 5.0), (3.0, 15.0)]);
+
+        let smooth_then_rate = run(&[Transform::Smooth(2), Transform::Rate], &mut input.clone());
+        // Means are `0, 5, 10, 25`.
+        assert_eq!(smooth_then_rate, vec![(1.0, 5.0), (2.0, 5.0), (3.0, 15.0)]);
+
+        assert_ne!(rate_then_smooth, smooth_then_rate);
+    }
+
+    #[test]
+    fn batches_match_whole_series() {
+        let transforms = [Transform::Smooth(3), Transform::Rate, Transform::Smooth(2)];
+        let vals = [(0, 3), (1, 8), (2, 2), (4, 12), (5, 7), (7, 1), (8, 30)];
+
+        let whole = run(&transforms, &mut points(&vals));
+
+        let mut pipeline = Pipeline::new(&transforms);
+        let mut batched = Vec::new();
+        for batch in vals.chunks(2) {
+            let mut batch = points(batch);
+            pipeline.apply(&mut batch).unwrap();
+            batched.extend(
+                batch
+                    .series()
+                    .remove(&uid::Line::Everything)
+                    .unwrap_or_else(Vec::new),
+            )
+        }
+        assert_eq!(whole, batched);
+    }
+
+    #[test]
+    fn normalize_is_a_transform() {
+        let mut input = points(&[(0, 4), (1, 0)]);
+        let normalized = run(&[Transform::Normalize(uid::Line::Everything)], &mut input);
+        assert_eq!(normalized, vec![(0.0, 100.0), (1.0, 100.0)]);
+    }
+}
@@ -31,6 +31,155 @@ lazy_static! {
     static ref RNG: sync::RwLock<SmallRng> = sync::RwLock::new(
         SmallRng::seed_from_u64(42u64)
     );
+    /// Palette new colors are allocated from.
+    static ref PALETTE: sync::RwLock<Palette> = sync::RwLock::new(Palette::default());
+}
+
+/// Palette new colors are allocated from.
+pub fn palette() -> Palette {
+    *PALETTE.read().expect("failed to retrieve color palette")
+}
+
+/// Sets the palette new colors are allocated from.
+///
+/// Only impacts colors allocated afterwards, the colors of existing filters do not change.
+pub fn set_palette(palette: Palette) {
+    *PALETTE.write().expect("failed to retrieve color palette") = palette
+}
+
+/// Palette used to allocate the colors of new filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Palette {
+    /// Saturated hues, spread on the color wheel.
+    Hues,
+    /// Okabe and Ito's colorblind-safe categorical palette, without black.
+    OkabeIto,
+    /// Categorical palette sampled from the viridis colormap.
+    Viridis,
+}
+impl Default for Palette {
+    fn default() -> Self {
+        Self::Hues
+    }
+}
+impl fmt::Display for Palette {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.name().fmt(fmt)
+    }
+}
+impl Palette {
+    /// All the palettes.
+    pub const ALL: [Self; 3] = [Self::Hues, Self::OkabeIto, Self::Viridis];
+
+    /// Okabe-Ito colors.
+    const OKABE_ITO: [Color; 7] = [
+        Color::hex(0xe69f00),
+        Color::hex(0x56b4e9),
+        Color::hex(0x009e73),
+        Color::hex(0xf0e442),
+        Color::hex(0x0072b2),
+        Color::hex(0xd55e00),
+        Color::hex(0xcc79a7),
+    ];
+    /// Viridis samples, interleaved so that consecutive colors are far apart on the colormap.
+    const VIRIDIS: [Color; 8] = [
+        Color::hex(0x440154),
+        Color::hex(0x1fa187),
+        Color::hex(0x365c8d),
+        Color::hex(0xa0da39),
+        Color::hex(0x46327e),
+        Color::hex(0x4ac16d),
+        Color::hex(0x277f8e),
+        Color::hex(0xfde725),
+    ];
+    /// Distinct colors assigned to new filters with the [`Hues`](#variant.Hues) palette.
+    ///
+    /// All of them contrast enough with the background of the charts.
+    const DISTINCT: [Color; 12] = [
+        Color::hex(0x1f77b4),
+        Color::hex(0xd62728),
+        Color::hex(0x2ca02c),
+        Color::hex(0x9467bd),
+        Color::hex(0xd95f02),
+        Color::hex(0x17808a),
+        Color::hex(0xd6338a),
+        Color::hex(0x8c564b),
+        Color::hex(0x393b79),
+        Color::hex(0xa87900),
+        Color::hex(0x7b4173),
+        Color::hex(0x636363),
+    ];
+
+    /// Name of the palette, as used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hues => "hues",
+            Self::OkabeIto => "okabe_ito",
+            Self::Viridis => "viridis",
+        }
+    }
+
+    /// Palette from its name.
+    ///
+    /// ```rust
+    /// # use charts::color::Palette;
+    /// for palette in &Palette::ALL {
+    ///     assert_eq!(Palette::from_name(palette.name()), Some(*palette));
+    /// }
+    /// assert_eq!(Palette::from_name("rainbow"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .cloned()
+            .find(|palette| palette.name() == name)
+    }
+
+    /// Colors of a categorical palette, `None` for [`Hues`](#variant.Hues).
+    ///
+    /// ```rust
+    /// # use charts::color::Palette;
+    /// assert!(Palette::Hues.colors().is_none());
+    /// let okabe_ito = Palette::OkabeIto.colors().unwrap();
+    /// assert_eq!(okabe_ito[0].to_string(), "#e69f00");
+    /// assert_eq!(okabe_ito.len(), 7);
+    /// ```
+    pub fn colors(self) -> Option<&'static [Color]> {
+        match self {
+            Self::Hues => None,
+            Self::OkabeIto => Some(&Self::OKABE_ITO),
+            Self::Viridis => Some(&Self::VIRIDIS),
+        }
+    }
+
+    /// Colors assigned to new filters, in order.
+    ///
+    /// ```rust
+    /// # use charts::color::Palette;
+    /// assert_eq!(Palette::Hues.filter_colors().len(), 12);
+    /// assert_eq!(Palette::OkabeIto.filter_colors(), Palette::OkabeIto.colors().unwrap());
+    /// ```
+    pub fn filter_colors(self) -> &'static [Color] {
+        self.colors().unwrap_or(&Self::DISTINCT)
+    }
+
+    /// Color for a new filter, given the colors of the existing filters.
+    ///
+    /// Picks the first of the [filter colors](#method.filter_colors) used the least: unused
+    /// colors come first, colors freed by deleting a filter are available again, and colors wrap
+    /// around once they are all used.
+    pub fn next_filter_color<'a>(self, used: impl IntoIterator<Item = &'a Color>) -> Color {
+        let colors = self.filter_colors();
+        let mut counts = vec![0usize; colors.len()];
+        for color in used {
+            if let Some(idx) = colors.iter().position(|candidate| candidate == color) {
+                counts[idx] += 1
+            }
+        }
+        let min = counts.iter().cloned().min().unwrap_or(0);
+        let idx = counts.iter().position(|count| *count == min).unwrap_or(0);
+        colors[idx]
+    }
 }
 
 /// Convenience macro giving write-access to the global RNG.
@@ -49,12 +198,17 @@ pub struct Color {
     pub g: u8,
     /// Blue component.
     pub b: u8,
+    /// Alpha component, `255` is opaque.
+    ///
+    /// Colors from before alpha was supported load as opaque.
+    #[serde(default = "Color::opaque_alpha")]
+    pub a: u8,
 }
 
 impl plotters_backend::BackendStyle for Color {
     fn color(&self) -> plotters_backend::BackendColor {
         plotters_backend::BackendColor {
-            alpha: 1.0,
+            alpha: self.alpha_ratio(),
             rgb: (self.r, self.g, self.b),
         }
     }
@@ -64,12 +218,17 @@ impl plotters::style::Color for Color {
         (self.r, self.g, self.b)
     }
     fn alpha(&self) -> f64 {
-        1.0
+        self.alpha_ratio()
     }
 }
+/// Canonical form of a color: `#rrggbb` if it is opaque, `#rrggbbaa` otherwise.
 impl fmt::Display for Color {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "#{:0>2x}{:0>2x}{:0>2x}", self.r, self.g, self.b)
+        write!(fmt, "#{:0>2x}{:0>2x}{:0>2x}", self.r, self.g, self.b)?;
+        if !self.is_opaque() {
+            write!(fmt, "{:0>2x}", self.a)?
+        }
+        Ok(())
     }
 }
 
@@ -84,50 +243,175 @@ impl Color {
     /// assert_eq!(&color.to_string(), "#ff0000")
     /// ```
     pub fn new(r: u8, g: u8, b: u8) -> Self {
-        Self { r, g, b }
+        Self { r, g, b, a: 255 }
+    }
+
+    /// Same color with some alpha component.
+    ///
+    /// ```rust
+    /// # use charts::color::Color;
+    /// let color = Color::new(0xff, 0x00, 0x00).with_alpha(0x80);
+    /// assert_eq!(&color.to_string(), "#ff000080");
+    /// assert_eq!(&color.opaque().to_string(), "#ff0000");
+    /// ```
+    pub fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Same color, opaque.
+    ///
+    /// Color inputs only handle opaque colors.
+    pub fn opaque(self) -> Self {
+        self.with_alpha(255)
+    }
+
+    /// True if the color is opaque.
+    pub fn is_opaque(&self) -> bool {
+        self.a == 255
+    }
+
+    /// Alpha component of opaque colors.
+    fn opaque_alpha() -> u8 {
+        255
+    }
+
+    /// Alpha component, between `0` (transparent) and `1` (opaque).
+    fn alpha_ratio(&self) -> f64 {
+        self.a as f64 / 255.
+    }
+
+    /// Constructor from a `0xRRGGBB` integer.
+    const fn hex(rgb: u32) -> Self {
+        Self {
+            r: (rgb >> 16) as u8,
+            g: (rgb >> 8) as u8,
+            b: rgb as u8,
+            a: 255,
+        }
     }
 
     /// Black.
-    pub const BLACK: Self = Self { r: 0, g: 0, b: 0 };
+    pub const BLACK: Self = Self {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+    /// White.
+    pub const WHITE: Self = Self {
+        r: 255,
+        g: 255,
+        b: 255,
+        a: 255,
+    };
+
+    /// Background of the charts.
+    pub const CHART_BACKGROUND: Self = Self::WHITE;
+
+    /// Minimal contrast of a line against the background of the charts.
+    ///
+    /// This is the WCAG threshold for graphical objects.
+    pub const MIN_CONTRAST: f64 = 3.0;
+
+    /// Formats [`from_str`] accepts, for error messages.
+    ///
+    /// [`from_str`]: #method.from_str (The from_str function)
+    pub const FORMATS: &'static str =
+        "`#rgb`, `#rrggbb`, `#rrggbbaa`, `rgb(r, g, b)` or `rgba(r, g, b, a)`";
 
     /// Constructs a color from a string.
     ///
-    /// - only accepts RGB strings starting with `#`.
+    /// Accepts hexadecimal colors with 3, 6 or 8 digits, the last two being the alpha component,
+    /// and the CSS `rgb(r, g, b)` and `rgba(r, g, b, a)` syntaxes. CSS components are clamped:
+    /// `r`, `g` and `b` between `0` and `255`, or percentages, and `a` between `0` and `1`, or a
+    /// percentage. Fails on anything else, listing the accepted [formats].
+    ///
+    /// The result displays in canonical form, see the `Display` implementation.
     ///
     /// # Examples
     ///
     /// ```rust
     /// # use charts::color::Color;
-    /// let color = Color::from_str("#ff0000").unwrap();
-    /// assert_eq!(&color.to_string(), "#ff0000")
+    /// let color = |s: &str| Color::from_str(s).unwrap().to_string();
+    /// assert_eq!(color("#ff0000"), "#ff0000");
+    /// assert_eq!(color("#F00"), "#ff0000");
+    /// assert_eq!(color("#ff000080"), "#ff000080");
+    /// assert_eq!(color("#ff0000ff"), "#ff0000");
+    /// assert_eq!(color("rgb(255, 0, 0)"), "#ff0000");
+    /// assert_eq!(color(" rgba(255, 0, 300, 0.5) "), "#ff00ff80");
+    /// assert_eq!(color("rgb(100%, 0%, 50%)"), "#ff0080");
+    ///
+    /// let err = Color::from_str("#12345").unwrap_err().to_string();
+    /// assert!(err.contains(Color::FORMATS), "{}", err);
+    /// assert!(Color::from_str("rgba(1, 2, 3)").is_err());
+    /// assert!(Color::from_str("red").is_err());
     /// ```
+    ///
+    /// [formats]: #associatedconstant.FORMATS (The FORMATS constant)
     pub fn from_str<Str: AsRef<str>>(text: Str) -> Res<Self> {
-        let text = text.as_ref();
+        let text = text.as_ref().trim();
+        match Self::parse_hex(text).or_else(|| Self::parse_css(text)) {
+            Some(color) => Ok(color),
+            None => bail!("illegal color `{}`, expected {}", text, Self::FORMATS),
+        }
+    }
 
-        macro_rules! fail {
-            () => {
-                bail!("illegal RGB color string `{}`", text)
-            };
-            ($e:expr) => {
-                if let Ok(res) = $e {
-                    res
-                } else {
-                    fail!()
-                }
-            };
+    /// Parses a hexadecimal color with 3, 6 or 8 digits.
+    fn parse_hex(text: &str) -> Option<Self> {
+        let digits = text.strip_prefix('#')?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let component = |idx: usize| u8::from_str_radix(&digits[idx..idx + 2], 16).ok();
+        match digits.len() {
+            3 => {
+                // `#rgb` is `#rrggbb`.
+                let short = |idx: usize| u8::from_str_radix(&digits[idx..idx + 1], 16).ok();
+                let (r, g, b) = (short(0)?, short(1)?, short(2)?);
+                Some(Self::new(r * 0x11, g * 0x11, b * 0x11))
+            }
+            6 => Some(Self::new(component(0)?, component(2)?, component(4)?)),
+            8 => Some(
+                Self::new(component(0)?, component(2)?, component(4)?).with_alpha(component(6)?),
+            ),
+            _ => None,
         }
+    }
 
-        if text.len() != 7 || &text[0..1] != "#" {
-            fail!()
+    /// Parses a CSS `rgb(r, g, b)` or `rgba(r, g, b, a)` color.
+    fn parse_css(text: &str) -> Option<Self> {
+        let (args, has_alpha) = if let Some(args) = text.strip_prefix("rgba(") {
+            (args, true)
+        } else {
+            (text.strip_prefix("rgb(")?, false)
+        };
+        let args: Vec<&str> = args.strip_suffix(')')?.split(',').map(str::trim).collect();
+        if args.len() != if has_alpha { 4 } else { 3 } {
+            return None;
         }
 
-        let (r, g, b) = (
-            fail!(u8::from_str_radix(&text[1..3], 16)),
-            fail!(u8::from_str_radix(&text[3..5], 16)),
-            fail!(u8::from_str_radix(&text[5..7], 16)),
-        );
+        // Parses a number or a percentage, as a ratio of `max`, and clamps it in `[0, max]`.
+        let number = |arg: &str, max: f64| -> Option<u8> {
+            let val = match arg.strip_suffix('%') {
+                Some(percent) => f64::from_str(percent).ok()? * max / 100.,
+                None => f64::from_str(arg).ok()?,
+            };
+            if !val.is_finite() {
+                return None;
+            }
+            Some((val.max(0.).min(max) * 255. / max).round() as u8)
+        };
 
-        Ok(Self::new(r, g, b))
+        let color = Self::new(
+            number(args[0], 255.)?,
+            number(args[1], 255.)?,
+            number(args[2], 255.)?,
+        );
+        if has_alpha {
+            Some(color.with_alpha(number(args[3], 1.)?))
+        } else {
+            Some(color)
+        }
     }
 
     /// Turns itself in a `plotters`-compliant color.
@@ -179,12 +463,14 @@ impl Color {
         let (r, g, b) = ((r + m) * 255., (g + m) * 255., (b + m) * 255.);
         let (r, g, b) = (r as u8, g as u8, b as u8);
 
-        Self { r, g, b }
+        Self::new(r, g, b)
     }
 
-    /// Constructs `n` random colors evenly spread on the color wheel.
+    /// Constructs `n` colors from the current [`palette`](fn.palette.html).
     ///
-    /// - the starting point on the color wheel is random.
+    /// With the [`Hues`](enum.Palette.html#variant.Hues) palette, colors are evenly spread on the
+    /// color wheel from a random starting point. Categorical palettes yield their colors in order,
+    /// cycling if `n` is bigger than the palette.
     ///
     /// # Guarantees
     ///
@@ -193,6 +479,9 @@ impl Color {
         if n == 0 {
             return vec![];
         }
+        if let Some(colors) = palette().colors() {
+            return colors.iter().cloned().cycle().take(n).collect();
+        }
 
         let inc = 360. / (n as f32);
         let mut current = rng!().gen::<f32>() * 360f32;
@@ -207,22 +496,182 @@ impl Color {
             .collect()
     }
 
-    /// Constructs a random color.
+    /// `n`-th color assigned to new filters with the current [`palette`](fn.palette.html), wraps
+    /// around.
     ///
-    /// - `dark` indicates whether the random color should be relatively dark.
+    /// See also [`next_palette`](#method.next_palette).
+    pub fn palette(n: usize) -> Self {
+        let colors = palette().filter_colors();
+        colors[n % colors.len()]
+    }
+
+    /// Color for a new filter with the current [`palette`](fn.palette.html), given the colors of
+    /// the existing filters.
+    ///
+    /// See [`Palette::next_filter_color`](enum.Palette.html#method.next_filter_color).
+    pub fn next_palette<'a>(used: impl IntoIterator<Item = &'a Color>) -> Self {
+        palette().next_filter_color(used)
+    }
+
+    /// Color of a sequential colormap, from light blue (`0`) to dark blue (`1`).
+    ///
+    /// Used for lines that have an order, such as trace segments. `ratio` is clamped to `[0, 1]`.
+    ///
+    /// ```rust
+    /// # use charts::color::Color;
+    /// assert_eq!(Color::sequential(0.).to_string(), "#c6dbef");
+    /// assert_eq!(Color::sequential(7.).to_string(), "#08306b");
+    /// let (old, new) = (Color::sequential(0.25), Color::sequential(0.75));
+    /// assert!(old.luminance() > new.luminance());
+    /// ```
+    pub fn sequential(ratio: f64) -> Self {
+        const LIGHT: Color = Color::hex(0xc6dbef);
+        const DARK: Color = Color::hex(0x08306b);
+        let ratio = ratio.max(0.).min(1.);
+        let mix =
+            |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * ratio).round() as u8;
+        Self::new(
+            mix(LIGHT.r, DARK.r),
+            mix(LIGHT.g, DARK.g),
+            mix(LIGHT.b, DARK.b),
+        )
+    }
+
+    /// Constructs a random color from the current [`palette`](fn.palette.html).
     pub fn random() -> Self {
+        match palette().colors() {
+            Some(colors) => colors[rng!().gen_range(0, colors.len())],
+            None => Self::random_hue(),
+        }
+    }
+
+    /// Constructs a color with a random hue.
+    fn random_hue() -> Self {
         Self::from_hue(rng!().gen::<f32>() * 360f32, 1.0, 0.5)
     }
 
     /// Keeps on constructing colors until the input predicate is true.
+    ///
+    /// Tries the colors of the current [`palette`](fn.palette.html) first, and falls back on random
+    /// hues if none of them verifies the predicate.
     pub fn random_until(pred: impl Fn(&Color) -> bool) -> Self {
-        let mut color = Self::random();
+        if let Some(colors) = palette().colors() {
+            let start = rng!().gen_range(0, colors.len());
+            let candidates = colors.iter().cycle().skip(start).take(colors.len());
+            if let Some(color) = candidates.cloned().find(|color| pred(color)) {
+                return color;
+            }
+        }
+        let mut color = Self::random_hue();
         while !pred(&color) {
-            color = Self::random()
+            color = Self::random_hue()
         }
         color
     }
 
+    /// Relative luminance, between `0` (black) and `1` (white).
+    ///
+    /// See the [WCAG definition].
+    ///
+    /// [WCAG definition]: https://www.w3.org/TR/WCAG21/#dfn-relative-luminance
+    /// (Relative luminance in WCAG 2.1)
+    pub fn luminance(&self) -> f64 {
+        let linear = |c: u8| {
+            let c = c as f64 / 255.;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// Contrast ratio with another color, between `1` and `21`.
+    ///
+    /// ```rust
+    /// # use charts::color::Color;
+    /// assert_eq!(Color::BLACK.contrast(&Color::WHITE), 21.);
+    /// assert_eq!(Color::WHITE.contrast(&Color::BLACK), 21.);
+    /// assert_eq!(Color::WHITE.contrast(&Color::WHITE), 1.);
+    /// ```
+    pub fn contrast(&self, other: &Self) -> f64 {
+        let (l_1, l_2) = (self.luminance(), other.luminance());
+        let (light, dark) = if l_1 >= l_2 { (l_1, l_2) } else { (l_2, l_1) };
+        (light + 0.05) / (dark + 0.05)
+    }
+
+    /// Color of readable text over this color: black or white, whichever contrasts more.
+    ///
+    /// ```rust
+    /// # use charts::color::Color;
+    /// assert_eq!(Color::new(0xff, 0xff, 0x00).contrast_text(), Color::BLACK);
+    /// assert_eq!(Color::new(0x00, 0x00, 0x80).contrast_text(), Color::WHITE);
+    /// assert_eq!(Color::BLACK.contrast_text(), Color::WHITE);
+    /// ```
+    pub fn contrast_text(&self) -> Self {
+        if self.contrast(&Self::BLACK) >= self.contrast(&Self::WHITE) {
+            Self::BLACK
+        } else {
+            Self::WHITE
+        }
+    }
+
+    /// True if the color does not have enough contrast with the background of the charts.
+    ///
+    /// See [`MIN_CONTRAST`](#associatedconstant.MIN_CONTRAST).
+    pub fn has_low_contrast(&self) -> bool {
+        self.contrast(&Self::CHART_BACKGROUND) < Self::MIN_CONTRAST
+    }
+
+    /// Closest color with at least some contrast with a background.
+    ///
+    /// Returns the color itself if its contrast is high enough. Otherwise, moves the color towards
+    /// black or white, whichever contrasts more with the background, until the contrast is high
+    /// enough.
+    ///
+    /// ```rust
+    /// # use charts::color::Color;
+    /// let yellow = Color::new(0xff, 0xff, 0x00);
+    /// assert!(yellow.has_low_contrast());
+    /// let fixed = yellow.with_contrast(&Color::CHART_BACKGROUND, Color::MIN_CONTRAST);
+    /// assert!(!fixed.has_low_contrast());
+    /// ```
+    pub fn with_contrast(&self, bg: &Self, min: f64) -> Self {
+        if self.contrast(bg) >= min {
+            return *self;
+        }
+        let target = if Self::BLACK.contrast(bg) >= Self::WHITE.contrast(bg) {
+            Self::BLACK
+        } else {
+            Self::WHITE
+        };
+        let mix = |from: u8, to: u8, ratio: f64| {
+            (from as f64 + (to as f64 - from as f64) * ratio).round() as u8
+        };
+        const STEPS: usize = 100;
+        for step in 1..=STEPS {
+            let ratio = step as f64 / STEPS as f64;
+            let color = Self::new(
+                mix(self.r, target.r, ratio),
+                mix(self.g, target.g, ratio),
+                mix(self.b, target.b, ratio),
+            )
+            .with_alpha(self.a);
+            if color.contrast(bg) >= min {
+                return color;
+            }
+        }
+        target.with_alpha(self.a)
+    }
+
+    /// Closest color with enough contrast with the background of the charts.
+    ///
+    /// See [`MIN_CONTRAST`](#associatedconstant.MIN_CONTRAST).
+    pub fn contrasting(&self) -> Self {
+        self.with_contrast(&Self::CHART_BACKGROUND, Self::MIN_CONTRAST)
+    }
+
     /// Returns true if two colors are very similar.
     ///
     /// "Very similar" here means that all components are less than `15u8` apart.
@@ -239,3 +688,179 @@ impl Color {
         check!(self.r, other.r) && check!(self.g, other.g) && check!(self.b, other.b)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    lazy_static! {
+        /// Serializes the tests that rely on the global palette.
+        static ref PALETTE_LOCK: sync::Mutex<()> = sync::Mutex::new(());
+    }
+
+    fn color(s: &str) -> Color {
+        Color::from_str(s).unwrap()
+    }
+
+    fn assert_contrast(lft: &str, rgt: &str, expected: f64) {
+        let contrast = color(lft).contrast(&color(rgt));
+        assert!(
+            (contrast - expected).abs() < 0.01,
+            "contrast of {} and {} is {}, expected {}",
+            lft,
+            rgt,
+            contrast,
+            expected,
+        )
+    }
+
+    #[test]
+    fn known_contrasts() {
+        assert_contrast("#000000", "#ffffff", 21.);
+        assert_contrast("#ff0000", "#ffffff", 4.00);
+        assert_contrast("#0000ff", "#ffffff", 8.59);
+        assert_contrast("#777777", "#ffffff", 4.48);
+        assert_contrast("#ffff00", "#ffffff", 1.07);
+        assert_contrast("#00ff00", "#000000", 15.30);
+        assert_contrast("#313131", "#313131", 1.);
+    }
+
+    #[test]
+    fn parse_colors() {
+        for (text, expected) in &[
+            ("#000", "#000000"),
+            ("#abc", "#aabbcc"),
+            ("#ABCDEF", "#abcdef"),
+            ("#abcdef00", "#abcdef00"),
+            ("rgb(0,0,0)", "#000000"),
+            ("rgb( 12 , 34 , 56 )", "#0c2238"),
+            ("rgb(-5, 256, 1e3)", "#00ffff"),
+            ("rgba(12, 34, 56, 1)", "#0c2238"),
+            ("rgba(12, 34, 56, 2)", "#0c2238"),
+            ("rgba(12, 34, 56, 0)", "#0c223800"),
+            ("rgba(12, 34, 56, 50%)", "#0c223880"),
+        ] {
+            assert_eq!(&color(text).to_string(), expected, "parsing `{}`", text);
+            // The canonical form parses back to the same color.
+            assert_eq!(color(expected), color(text));
+        }
+        for text in &[
+            "",
+            "#",
+            "#12345",
+            "#1234",
+            "#1234567",
+            "#12345g",
+            "#+1+2+3",
+            "ff0000",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4)",
+            "rgba(1, 2, 3)",
+            "rgb(1, 2, three)",
+            "rgb(1, 2, NaN)",
+            "rgb 1, 2, 3",
+            "hsl(0, 100%, 50%)",
+        ] {
+            let err = Color::from_str(text).unwrap_err().to_string();
+            assert!(err.contains(Color::FORMATS), "parsing `{}`: {}", text, err)
+        }
+    }
+
+    #[test]
+    fn colors_without_alpha_load() {
+        let mut json = serde_json::to_value(color("#123456")).unwrap();
+        json.as_object_mut().unwrap().remove("a");
+        let loaded: Color = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded, color("#123456"));
+        assert!(loaded.is_opaque());
+    }
+
+    #[test]
+    fn fix_low_contrast() {
+        let white = Color::WHITE;
+        let dark = color("#313131");
+
+        // High enough already, unchanged.
+        let red = color("#ff0000");
+        assert_eq!(red.with_contrast(&white, 3.), red);
+
+        // Yellow on white goes darker, keeping its hue.
+        let yellow = color("#ffff00");
+        let fixed = yellow.with_contrast(&white, 3.);
+        assert!(fixed.contrast(&white) >= 3.);
+        assert_eq!(fixed.r, fixed.g);
+        assert_eq!(fixed.b, 0);
+        // Not darker than needed.
+        assert!(fixed.r >= 0x80);
+
+        // Dark blue on dark grey goes lighter.
+        let blue = color("#000080");
+        let fixed = blue.with_contrast(&dark, 3.);
+        assert!(fixed.contrast(&dark) >= 3.);
+        assert!(fixed.luminance() > blue.luminance());
+    }
+
+    #[test]
+    fn categorical_palettes() {
+        for palette in &Palette::ALL {
+            let colors = palette.filter_colors();
+            for (idx, color) in colors.iter().enumerate() {
+                for other in &colors[idx + 1..] {
+                    assert!(
+                        !color.is_similar_to(other),
+                        "{}: {} ~ {}",
+                        palette,
+                        color,
+                        other
+                    )
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn palette_allocation() {
+        let _lock = PALETTE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let okabe_ito = Palette::OkabeIto.colors().unwrap();
+        set_palette(Palette::OkabeIto);
+        let colors = Color::randoms(10);
+        let picked = Color::random_until(|color| !color.has_low_contrast());
+        // No palette color verifies the predicate, falls back on random hues.
+        let fallback = Color::random_until(|color| !okabe_ito.contains(color));
+        set_palette(Palette::Hues);
+
+        assert_eq!(colors.len(), 10);
+        assert_eq!(&colors[..7], okabe_ito);
+        assert_eq!(&colors[7..], &okabe_ito[..3]);
+        assert!(okabe_ito.contains(&picked));
+        assert!(!picked.has_low_contrast());
+        assert!(!okabe_ito.contains(&fallback));
+    }
+
+    #[test]
+    fn new_filter_colors() {
+        let _lock = PALETTE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(palette(), Palette::Hues);
+
+        // Creates 15 filters, each one gets the next color given the colors of the previous ones.
+        let mut used = vec![];
+        for _ in 0..15 {
+            let color = Color::next_palette(&used);
+            assert!(!color.has_low_contrast(), "{}", color);
+            used.push(color)
+        }
+        for (idx, color) in used[..12].iter().enumerate() {
+            assert_eq!(*color, Color::palette(idx));
+            assert!(used[idx + 1..12].iter().all(|other| other != color));
+        }
+        assert_eq!(&used[12..], &used[..3]);
+
+        // Deleting a filter frees its color.
+        let deleted = used.remove(5);
+        assert_eq!(Color::next_palette(&used), deleted);
+        // Recolored filters do not hold a palette color.
+        let mut used: Vec<Color> = (0..12).map(Color::palette).collect();
+        used[3] = Color::BLACK;
+        assert_eq!(Color::next_palette(&used), Color::palette(3));
+    }
+}
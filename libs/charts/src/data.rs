@@ -21,28 +21,49 @@
 
 prelude! {}
 
+mod allocs;
+pub mod cache;
+pub mod massif;
+pub mod prune;
 mod watcher;
 
+pub use allocs::Allocs;
+
 pub use watcher::Watcher;
 
+/// Number of events whose allocations [`Data::iter_changes_after`] matches at once.
+///
+/// Matching the allocations is the bulk of the work when regenerating points, it runs in parallel
+/// over batches of events, see [`Filters::find_matches`].
+///
+/// [`Data::iter_changes_after`]: struct.Data.html#method.iter_changes_after
+/// (The iter_changes_after method of Data)
+/// [`Filters::find_matches`]: ../filter/struct.Filters.html#method.find_matches
+/// (The find_matches method of Filters)
+pub const MATCH_BATCH_LEN: usize = 1 << 14;
+
 /// Factory used when parsing dump-data.
 ///
 /// The role of this factory is to get write-locks over the different factories needed at
 /// parse-time. This avoids asking for the lock each time it is needed.
-pub struct FullFactory<'a> {
+///
+/// The data is the global one by default, see [`with_data`] to build some data separately.
+///
+/// [`with_data`]: #method.with_data (The with_data constructor)
+pub struct FullFactory<'a, D = sync::RwLockWriteGuard<'a, Data>> {
     /// Lock over the allocation-data factories.
     factory: alloc_data::mem::Factory<'a>,
     /// Lock over the `Data` structure storing the whole dump.
-    data: sync::RwLockWriteGuard<'a, Data>,
+    data: D,
 }
 
-impl<'a> std::ops::Deref for FullFactory<'a> {
+impl<'a, D> std::ops::Deref for FullFactory<'a, D> {
     type Target = alloc_data::mem::Factory<'a>;
     fn deref(&self) -> &Self::Target {
         &self.factory
     }
 }
-impl<'a> std::ops::DerefMut for FullFactory<'a> {
+impl<'a, D> std::ops::DerefMut for FullFactory<'a, D> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.factory
     }
@@ -51,12 +72,27 @@ impl<'a> std::ops::DerefMut for FullFactory<'a> {
 impl<'a> FullFactory<'a> {
     /// Constructor.
     pub fn new(callstack_is_rev: bool) -> Self {
+        Self::with_data(callstack_is_rev, get_mut().unwrap())
+    }
+}
+
+impl<'a, D> FullFactory<'a, D>
+where
+    D: ops::DerefMut<Target = Data>,
+{
+    /// Constructor for some data other than the global one.
+    pub fn with_data(callstack_is_rev: bool, data: D) -> Self {
         Self {
             factory: alloc_data::mem::Factory::new(callstack_is_rev),
-            data: get_mut().unwrap(),
+            data,
         }
     }
 
+    /// Releases the allocation-data factories, yields the data.
+    pub fn into_data(self) -> D {
+        self.data
+    }
+
     /// Builds a new allocation.
     pub fn build_new(&mut self, alloc: alloc::Builder) -> Res<()> {
         self.data.build_new(alloc)
@@ -69,6 +105,10 @@ impl<'a> FullFactory<'a> {
     pub fn add_dead(&mut self, timestamp: time::SinceStart, uid: uid::Alloc) -> Res<()> {
         self.data.add_dead(timestamp, uid)
     }
+    /// Registers the promotion of an allocation to the major heap.
+    pub fn add_promotion(&mut self, timestamp: time::SinceStart, uid: uid::Alloc) -> Res<()> {
+        self.data.add_promotion(timestamp, uid)
+    }
 
     /// Fills the statistics of the underlying data structure for the whole dump.
     pub fn fill_stats(&mut self) -> Res<()> {
@@ -79,6 +119,193 @@ impl<'a> FullFactory<'a> {
     pub fn mark_timestamp(&mut self, ts: time::SinceStart) {
         self.data.mark_timestamp(ts)
     }
+
+    /// Registers a time range for which no data is available.
+    pub fn add_gap(&mut self, gap: TimeWindow) {
+        self.data.add_gap(gap)
+    }
+}
+
+/// If true, location-resolution failures are fatal errors when parsing.
+static STRICT: sync::atomic::AtomicBool = sync::atomic::AtomicBool::new(false);
+
+/// True if location-resolution failures are fatal errors when parsing.
+pub fn is_strict() -> bool {
+    STRICT.load(sync::atomic::Ordering::Relaxed)
+}
+
+/// Sets whether location-resolution failures are fatal errors when parsing.
+///
+/// When not strict, allocations with unknown location codes are still registered, see
+/// [`UnresolvedPolicy`][policy].
+///
+/// [policy]: ../filter/loc/enum.UnresolvedPolicy.html (UnresolvedPolicy enum)
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, sync::atomic::Ordering::Relaxed)
+}
+
+/// Sampling of the packets of CTF traces, `1` to load everything.
+static PACKET_SAMPLING: sync::atomic::AtomicUsize = sync::atomic::AtomicUsize::new(1);
+
+/// Sampling of the packets of CTF traces.
+pub fn packet_sampling() -> usize {
+    PACKET_SAMPLING.load(sync::atomic::Ordering::Relaxed)
+}
+
+/// Sets the sampling of the packets of CTF traces.
+///
+/// With a sampling of `n > 1`, only the allocations of one packet in `n` are loaded, and sizes
+/// and allocation counts are scaled by `n`. The user can then load everything with [`load_all`].
+///
+/// [`load_all`]: fn.load_all.html (The load_all function)
+pub fn set_packet_sampling(sampling: usize) {
+    PACKET_SAMPLING.store(sampling, sync::atomic::Ordering::Relaxed)
+}
+
+/// Format of the input trace, index of the format in [`InputFormat::ALL`].
+///
+/// [`InputFormat::ALL`]: enum.InputFormat.html#associatedconstant.ALL
+/// (The ALL constant of InputFormat)
+static INPUT_FORMAT: sync::atomic::AtomicUsize = sync::atomic::AtomicUsize::new(0);
+
+/// Format of the input trace.
+pub fn input_format() -> InputFormat {
+    InputFormat::ALL[INPUT_FORMAT.load(sync::atomic::Ordering::Relaxed)]
+}
+
+/// Sets the format of the input trace.
+pub fn set_input_format(format: InputFormat) {
+    let index = InputFormat::ALL
+        .iter()
+        .position(|fmt| *fmt == format)
+        .expect("all input formats are in `InputFormat::ALL`");
+    INPUT_FORMAT.store(index, sync::atomic::Ordering::Relaxed)
+}
+
+/// Format of the trace files memthol loads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputFormat {
+    /// Massif profiles if the file is named like one, memtrace CTF files otherwise.
+    Auto,
+    /// Memtrace CTF files.
+    Ctf,
+    /// Profiles of valgrind's massif tool, see the [`massif`] module.
+    ///
+    /// [`massif`]: massif/index.html (The massif module)
+    Massif,
+}
+impl InputFormat {
+    /// All the formats.
+    pub const ALL: [Self; 3] = [Self::Auto, Self::Ctf, Self::Massif];
+
+    /// Name of the format, as used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Ctf => "ctf",
+            Self::Massif => "massif",
+        }
+    }
+
+    /// Format from its name.
+    ///
+    /// ```rust
+    /// # use charts::data::InputFormat;
+    /// for format in &InputFormat::ALL {
+    ///     assert_eq!(InputFormat::from_name(format.name()), Some(*format));
+    /// }
+    /// assert_eq!(InputFormat::from_name("heaptrack"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .cloned()
+            .find(|format| format.name() == name)
+    }
+
+    /// Format of a file, never [`Auto`](#variant.Auto).
+    ///
+    /// ```rust
+    /// # use charts::data::InputFormat;
+    /// assert_eq!(InputFormat::Auto.of("massif.out.42"), InputFormat::Massif);
+    /// assert_eq!(InputFormat::Auto.of("trace.ctf"), InputFormat::Ctf);
+    /// assert_eq!(InputFormat::Massif.of("trace.ctf"), InputFormat::Massif);
+    /// ```
+    pub fn of(self, path: impl AsRef<std::path::Path>) -> Self {
+        match self {
+            Self::Auto if massif::is_massif_file(path) => Self::Massif,
+            Self::Auto => Self::Ctf,
+            format => format,
+        }
+    }
+}
+
+/// Loads all the allocations in the background, when the data is an estimate.
+///
+/// The current data stays available while loading, and is replaced once everything is loaded.
+/// Charts notice the new data with its [generation] and recompute their points. Tags are lost
+/// since allocation UIDs change.
+///
+/// Returns the new statistics of the current data.
+///
+/// [generation]: struct.Data.html#method.generation (The generation method of Data)
+pub fn load_all() -> Res<AllocStats> {
+    let (target, stats) = {
+        let mut data = get_mut()?;
+        let stats = data
+            .stats
+            .as_mut()
+            .ok_or("cannot load everything before the data is initialized")?;
+        match stats.estimate.as_mut() {
+            Some(estimate) if !estimate.loading_all => estimate.loading_all = true,
+            Some(_) => bail!("already loading everything"),
+            None => bail!("everything is loaded already"),
+        }
+        (stats.dump_dir.clone(), stats.clone())
+    };
+    Watcher::spawn_load_all(target);
+    Ok(stats)
+}
+
+/// Reloads the current trace from scratch, or switches to another memtrace CTF file or massif
+/// profile.
+///
+/// The trace is fully loaded by a separate thread in some new data while the current data stays
+/// available, and replaces it once loaded. The thread reports its [progress] like the initial load.
+/// Like with [`load_all`], charts notice the new data with its [generation] and recompute their
+/// points with the current filters. Tags are lost. The new trace is not followed, packets written
+/// after the load do not show up.
+///
+/// Fails if the file does not exist. If loading fails, the current data is left untouched and the
+/// error is registered as a non-fatal error.
+///
+/// [progress]: progress/index.html (The progress module)
+/// [`load_all`]: fn.load_all.html (The load_all function)
+/// [generation]: struct.Data.html#method.generation (The generation method of Data)
+pub fn load_trace(target: Option<std::path::PathBuf>) -> Res<()> {
+    let target = {
+        let data = get()?;
+        let stats = data
+            .stats
+            .as_ref()
+            .ok_or("cannot load a trace before the data is initialized")?;
+        if stats
+            .estimate
+            .map(|estimate| estimate.loading_all)
+            .unwrap_or(false)
+        {
+            bail!("cannot load a trace while loading everything")
+        }
+        target.unwrap_or_else(|| stats.dump_dir.clone())
+    };
+    if !target.is_file() {
+        bail!(
+            "cannot load `{}`, expected a memtrace CTF file or a massif profile",
+            target.display()
+        )
+    }
+    Watcher::spawn_switch(target);
+    Ok(())
 }
 
 /// Starts global data handling.
@@ -96,6 +323,10 @@ lazy_static! {
     static ref DATA: sync::RwLock<Data> = sync::RwLock::new(Data::new());
     /// Errors.
     static ref ERRORS: sync::RwLock<Vec<String>> = sync::RwLock::new(vec![]);
+    /// Serializes tag updates.
+    static ref TAGGING: sync::Mutex<()> = sync::Mutex::new(());
+    /// Serializes trace (re)loads.
+    static ref LOADING: sync::Mutex<()> = sync::Mutex::new(());
 }
 
 /// Handles progress information.
@@ -134,12 +365,42 @@ pub mod progress {
         Ok(())
     }
 
+    /// Number of CTF packets parsed between two progress reports, `1_024` by default.
+    static EVERY_PACKETS: sync::atomic::AtomicUsize = sync::atomic::AtomicUsize::new(1_024);
+
+    /// Number of CTF packets parsed between two progress reports.
+    pub fn every_packets() -> usize {
+        EVERY_PACKETS.load(sync::atomic::Ordering::Relaxed)
+    }
+    /// Sets the number of CTF packets parsed between two progress reports, at least `1`.
+    ///
+    /// Reports lock the progress, reporting too often slows down parsing.
+    pub fn set_every_packets(every: usize) {
+        EVERY_PACKETS.store(std::cmp::max(every, 1), sync::atomic::Ordering::Relaxed)
+    }
+
     /// Sets the total number of dumps to load.
     ///
     /// Also sets the number of dumps loaded to `0`.
     pub fn set_total(total: usize) -> Res<()> {
         let mut prog = write()?;
-        *prog = Some(LoadInfo { total, loaded: 0 });
+        *prog = Some(LoadInfo {
+            total,
+            loaded: 0,
+            bytes: false,
+        });
+        Ok(())
+    }
+    /// Sets the total number of bytes of a CTF file to load.
+    ///
+    /// Also sets the number of bytes loaded to `0`.
+    pub fn set_total_bytes(total: usize) -> Res<()> {
+        let mut prog = write()?;
+        *prog = Some(LoadInfo {
+            total,
+            loaded: 0,
+            bytes: true,
+        });
         Ok(())
     }
     /// Sets the number of dumps loaded.
@@ -153,14 +414,14 @@ pub mod progress {
 
     /// Increments the number of dumps loaded.
     pub fn inc_loaded() -> Res<()> {
-        if let Some(mut prog) = write()?.as_mut() {
+        if let Some(prog) = write()?.as_mut() {
             prog.loaded += 1;
         }
         Ok(())
     }
     /// Adds to the number of dumps loaded.
     pub fn add_loaded(n: usize) -> Res<()> {
-        if let Some(mut prog) = write()?.as_mut() {
+        if let Some(prog) = write()?.as_mut() {
             prog.loaded += n;
         }
         Ok(())
@@ -197,18 +458,237 @@ fn get_mut<'a>() -> Res<sync::RwLockWriteGuard<'a, Data>> {
         .chain_err(|| "while reading the global state")
 }
 
+/// Replaces the global data, for tests.
+///
+/// Tests using the global data run one at a time: the data stays in place as long as the guard
+/// returned lives.
+#[cfg(test)]
+pub fn set_for_test(data: Data) -> sync::MutexGuard<'static, ()> {
+    lazy_static! {
+        static ref TEST_LOCK: sync::Mutex<()> = sync::Mutex::new(());
+    }
+    // A failing test poisons the lock, which does not concern the other tests.
+    let guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    *get_mut().unwrap() = data;
+    guard
+}
+
+/// Changes the labels of some allocations.
+///
+/// The new labels are registered without holding the lock on the data, since parsing locks the
+/// label factory before the data.
+fn relabel(allocs: &[uid::Alloc], update: impl Fn(&mut Vec<alloc::Str>)) -> Res<()> {
+    let labels: Vec<_> = {
+        let data = get()?;
+        let mut labels = Vec::with_capacity(allocs.len());
+        for uid in allocs {
+            match data.get_alloc(*uid) {
+                Ok(alloc) => labels.push((*uid, alloc.labels())),
+                // Pruned allocations do not appear in the points anymore.
+                Err(e) if e.is_pruned_alloc() => (),
+                Err(e) => return Err(e),
+            }
+        }
+        labels
+    };
+    let labels: Vec<_> = labels
+        .into_iter()
+        .map(|(uid, labels)| {
+            let mut labels = (*labels).clone();
+            update(&mut labels);
+            (uid, alloc::Labels::new(labels))
+        })
+        .collect();
+    let mut data = get_mut()?;
+    for (uid, labels) in labels {
+        if let Some(alloc) = data.uid_map.get_mut(uid) {
+            alloc.set_labels(labels)
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the data with a full load of the same run, the tagged allocations keep their tags.
+///
+/// The allocations of the full load are tagged before it replaces the data, so that the data is
+/// only locked to swap them.
+fn replace_with_full_load(mut full: Data) -> Res<()> {
+    let _lock = TAGGING
+        .lock()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while carrying the tags over to the full load")?;
+    let tags: Vec<(String, AllocUidSet)> = {
+        let data = get()?;
+        data.tags
+            .iter()
+            .map(|(tag, tagged)| {
+                let tagged = tagged
+                    .iter()
+                    .map(|uid| data.trace_uids.get(uid.get()).cloned().unwrap_or(*uid))
+                    .collect();
+                (tag.clone(), tagged)
+            })
+            .collect()
+    };
+    for (tag, tagged) in tags {
+        let label = alloc::Str::new(&tag);
+        for uid in &tagged {
+            if let Some(alloc) = full.uid_map.get_mut(*uid) {
+                let mut labels = (*alloc.labels()).clone();
+                labels.push(label);
+                alloc.set_labels(alloc::Labels::new(labels))
+            }
+        }
+        let _ = full.tags.insert(tag, tagged);
+    }
+    let sample = get_mut()?.replace(full);
+    drop(sample);
+    Ok(())
+}
+
+/// Tags some allocations, returns the number of allocations tagged.
+///
+/// Appends `tag` to the labels of the allocations, see the [`tag`] module. Allocations that already
+/// have this tag are ignored.
+///
+/// [`tag`]: ../tag/index.html (The tag module)
+pub fn tag(tag: &str, allocs: impl IntoIterator<Item = uid::Alloc>) -> Res<usize> {
+    let _lock = TAGGING
+        .lock()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while tagging allocations")?;
+    let new: Vec<_> = {
+        let data = get()?;
+        let tagged = data.tags.get(tag);
+        allocs
+            .into_iter()
+            .filter(|uid| tagged.map(|set| !set.contains(uid)).unwrap_or(true))
+            .collect()
+    };
+    if new.is_empty() {
+        return Ok(0);
+    }
+
+    let label = alloc::Str::new(tag);
+    relabel(&new, |labels| labels.push(label))?;
+
+    let mut data = get_mut()?;
+    data.tags
+        .entry(tag.into())
+        .or_insert_with(AllocUidSet::new)
+        .extend(new.iter().cloned());
+    data.tags_version += 1;
+    Ok(new.len())
+}
+
+/// Removes a tag, returns the number of allocations that had it.
+pub fn untag(tag: &str) -> Res<usize> {
+    let _lock = TAGGING
+        .lock()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while untagging allocations")?;
+    let tagged: Vec<_> = match get_mut()?.tags.remove(tag) {
+        Some(tagged) => tagged.into_iter().collect(),
+        None => return Ok(0),
+    };
+
+    let label = alloc::Str::new(tag);
+    relabel(&tagged, |labels| {
+        if let Some(idx) = labels.iter().rposition(|l| *l == label) {
+            labels.remove(idx);
+        }
+    })?;
+
+    get_mut()?.tags_version += 1;
+    Ok(tagged.len())
+}
+
+/// The tags and the number of allocations they tag.
+pub fn tags() -> Res<tag::Tags> {
+    get().map(|data| {
+        data.tags
+            .iter()
+            .map(|(tag, allocs)| (tag.clone(), allocs.len()))
+            .collect()
+    })
+}
+
+/// Version of the tags, changes each time some allocations are (un)tagged.
+pub fn tags_version() -> Res<u64> {
+    get().map(|data| data.tags_version)
+}
+
+/// Generation of the data, see [`Data::generation`].
+///
+/// [`Data::generation`]: struct.Data.html#method.generation (The generation method of Data)
+pub fn generation() -> Res<u64> {
+    get().map(|data| data.generation)
+}
+
+/// Epoch of the pruning, changes each time some allocations are pruned, see [`prune`].
+///
+/// [`prune`]: prune/index.html (The prune module)
+pub fn prune_epoch() -> Res<u64> {
+    get().map(|data| data.pruned.epoch())
+}
+
+/// Prunes the allocations that died a long time ago, if pruning is enabled and due.
+///
+/// Returns the number of allocations pruned, see the [`prune`] module.
+///
+/// [`prune`]: prune/index.html (The prune module)
+pub fn prune() -> Res<usize> {
+    let limit = {
+        let data = get()?;
+        data.pruned.limit(*data.current_time())
+    };
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(0),
+    };
+    let count = get_mut()?
+        .prune(limit)
+        .chain_err(|| "while pruning allocations")?;
+    if count > 0 {
+        log::info!("pruned {} allocation(s) dead since {}", count, limit)
+    }
+    Ok(count)
+}
+
+/// Information about the run the trace comes from, if any.
+pub fn trace_info() -> Res<Option<alloc::TraceInfo>> {
+    get().map(|data| data.init.as_ref().and_then(|init| init.trace_info.clone()))
+}
+
 /// Structures that aggregates all the information about the allocations so far.
 pub struct Data {
     /// Init state.
     init: Option<alloc::Init>,
     /// Map from allocation UIDs to allocation data.
-    uid_map: uid::AllocMap<Alloc>,
+    uid_map: Allocs,
     /// Map from time-of-death to allocation UIDs.
     tod_map: BTMap<time::SinceStart, BTSet<uid::Alloc>>,
+    /// Map from time-of-promotion to allocation UIDs.
+    promotion_map: BTMap<time::SinceStart, AllocUidSet>,
     /// Time of the latest diff.
     current_time: time::SinceStart,
     /// Statistics.
     stats: Option<AllocStats>,
+    /// Time ranges for which no data is available, sorted and non-overlapping.
+    gaps: Vec<TimeWindow>,
+    /// Generation of the data, changes when the data is replaced by a full load.
+    generation: u64,
+    /// Allocations tagged by users, by tag.
+    tags: BTMap<String, AllocUidSet>,
+    /// Version of the tags.
+    tags_version: u64,
+    /// Aggregates of the pruned allocations.
+    pruned: prune::Pruned,
+    /// UIDs of the allocations in the trace, by UID in the data.
+    ///
+    /// Only used when the data is a sample of the packets of a CTF trace, the UIDs of the data are
+    /// the ones of the trace otherwise. Lets the tags carry over to the full load of the trace.
+    trace_uids: Vec<uid::Alloc>,
 }
 
 impl ops::Index<uid::Alloc> for Data {
@@ -223,10 +703,17 @@ impl Data {
     pub fn new() -> Self {
         Self {
             init: None,
-            uid_map: uid::AllocMap::new(),
+            uid_map: Allocs::new(),
             tod_map: BTMap::new(),
+            promotion_map: BTMap::new(),
             current_time: time::SinceStart::zero(),
             stats: None,
+            gaps: vec![],
+            generation: 0,
+            tags: BTMap::new(),
+            tags_version: 0,
+            pruned: prune::Pruned::new(),
+            trace_uids: vec![],
         }
     }
 
@@ -242,6 +729,92 @@ impl Data {
         self.current_time = ts
     }
 
+    /// Registers a time range for which no data is available.
+    ///
+    /// Empty ranges are ignored, and a gap overlapping the last gap registered is merged with it.
+    /// Gaps must be registered in chronological order.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::{time::SinceStart, TimeWindow}, data::Data};
+    /// let secs = SinceStart::from_secs;
+    /// let mut data = Data::new();
+    /// data.add_gap(TimeWindow::new(secs(1), secs(3)));
+    /// data.add_gap(TimeWindow::new(secs(2), secs(4)));
+    /// data.add_gap(TimeWindow::new(secs(5), secs(5)));
+    /// data.add_gap(TimeWindow::new(secs(7), secs(8)));
+    /// assert_eq!(
+    ///     data.gaps(),
+    ///     &[TimeWindow::new(secs(1), secs(4)), TimeWindow::new(secs(7), secs(8))],
+    /// );
+    ///
+    /// // Only keep gaps of at least two seconds, restricted to the window.
+    /// let window = TimeWindow::new(secs(2), secs(10));
+    /// assert_eq!(
+    ///     data.gaps_in(&window, secs(2)),
+    ///     vec![TimeWindow::new(secs(2), secs(4))],
+    /// );
+    /// ```
+    pub fn add_gap(&mut self, gap: TimeWindow) {
+        if gap.lbound >= gap.ubound {
+            return;
+        }
+        if let Some(last) = self.gaps.last_mut() {
+            if gap.lbound <= last.ubound {
+                if last.ubound < gap.ubound {
+                    last.ubound = gap.ubound
+                }
+                return;
+            }
+        }
+        self.gaps.push(gap)
+    }
+
+    /// Generation of the data.
+    ///
+    /// Changes when the data is replaced by a full load, see [`load_all`] and [`load_trace`].
+    ///
+    /// [`load_all`]: fn.load_all.html (The load_all function)
+    /// [`load_trace`]: fn.load_trace.html (The load_trace function)
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Version of the tags, changes each time some allocations are (un)tagged.
+    pub fn tags_version(&self) -> u64 {
+        self.tags_version
+    }
+
+    /// Sampling of the packets the data comes from, `1` if it is not an estimate.
+    pub fn sampling(&self) -> usize {
+        self.stats
+            .as_ref()
+            .and_then(|stats| stats.estimate)
+            .map(|estimate| estimate.sampling)
+            .unwrap_or(1)
+    }
+
+    /// UID of the next allocation registered.
+    pub fn next_uid(&self) -> uid::Alloc {
+        self.uid_map.next_index()
+    }
+
+    /// Time ranges for which no data is available.
+    pub fn gaps(&self) -> &[TimeWindow] {
+        &self.gaps
+    }
+
+    /// Gaps intersecting a time window that are at least as long as some minimal duration.
+    ///
+    /// The gaps are restricted to the time window.
+    pub fn gaps_in(&self, window: &TimeWindow, min_duration: time::SinceStart) -> Vec<TimeWindow> {
+        self.gaps
+            .iter()
+            .filter(|gap| gap.ubound - gap.lbound >= min_duration)
+            .map(|gap| gap.intersection(*window))
+            .filter(|gap| gap.lbound < gap.ubound)
+            .collect()
+    }
+
     /// Init accessor.
     pub fn init(&self) -> Option<&alloc::Init> {
         self.init.as_ref()
@@ -251,11 +824,16 @@ impl Data {
         self.init().is_some()
     }
 
-    /// Total number of allocations.
+    /// Total number of allocations, pruned ones excluded.
     pub fn alloc_count(&self) -> usize {
         self.uid_map.len()
     }
 
+    /// Aggregates of the pruned allocations.
+    pub fn pruned(&self) -> &prune::Pruned {
+        &self.pruned
+    }
+
     /// Allocation statistics stored in the global data.
     pub fn get_stats() -> Res<Option<AllocStats>> {
         get().map(|data| data.stats())
@@ -282,9 +860,22 @@ impl Data {
 
     /// Alloc accessor.
     ///
-    /// Fails if the UID is unknown.
-    pub fn get_alloc(&self, uid: uid::Alloc) -> Option<&Alloc> {
-        self.uid_map.get(uid)
+    /// Fails if the UID is unknown, or with [`ErrorKind::PrunedAlloc`] if the allocation was pruned.
+    ///
+    /// [`ErrorKind::PrunedAlloc`]: ../../base/err/enum.ErrorKind.html (The ErrorKind enum)
+    pub fn get_alloc(&self, uid: uid::Alloc) -> Res<&Alloc> {
+        if let Some(alloc) = self.uid_map.get(uid) {
+            Ok(alloc)
+        } else if self.uid_map.is_pruned(uid) {
+            Err(err::ErrorKind::PrunedAlloc(uid).into())
+        } else {
+            bail!("unknown allocation #{}", uid)
+        }
+    }
+
+    /// True if an allocation was pruned.
+    pub fn is_pruned(&self, uid: uid::Alloc) -> bool {
+        self.uid_map.is_pruned(uid)
     }
 
     /// Iterates over all the allocations.
@@ -292,29 +883,67 @@ impl Data {
         self.uid_map.iter()
     }
 
+    /// Iterates over the allocations, skipping most of the ones created before some time.
+    ///
+    /// Only skips allocations that are cheap to skip, callers must ignore the allocations created
+    /// before `time` they get. See [`Allocs::iter_from`].
+    ///
+    /// [`Allocs::iter_from`]: allocs/struct.Allocs.html#method.iter_from
+    /// (The iter_from method of Allocs)
+    pub fn iter_allocs_from(&self, time: time::SinceStart) -> impl Iterator<Item = &Alloc> {
+        self.uid_map.iter_from(time)
+    }
+
     /// True if there are any new events since some timestamp.
     pub fn has_new_stuff_since(&self, time: Option<(uid::Alloc, time::SinceStart)>) -> bool {
         if let Some((uid, tod)) = time {
-            self.uid_map[uid..].is_empty() || self.tod_map.keys().rev().next() != Some(&tod)
+            self.uid_map.next_index() <= uid || self.last_tod() != Some(tod)
         } else {
             !self.uid_map.is_empty()
         }
     }
 
     /// Yields the last events at the current time.
+    ///
+    /// The last allocation and the last deallocations may have been pruned.
     pub fn last_events(&self) -> Option<(uid::Alloc, time::SinceStart)> {
-        self.uid_map.last().map(|alloc| {
+        self.uid_map.next_index().get().checked_sub(1).map(|uid| {
             (
-                alloc.uid,
-                self.tod_map
-                    .keys()
-                    .cloned()
-                    .last()
-                    .unwrap_or_else(time::SinceStart::zero),
+                uid.into(),
+                self.last_tod().unwrap_or_else(time::SinceStart::zero),
             )
         })
     }
 
+    /// Time of the last deallocation, pruned or not, if any.
+    fn last_tod(&self) -> Option<time::SinceStart> {
+        let last = self.tod_map.keys().next_back().cloned();
+        std::cmp::max(last, self.pruned.last_tod())
+    }
+
+    /// Time of the last promotion, if any.
+    pub fn last_promotion(&self) -> Option<time::SinceStart> {
+        self.promotion_map.keys().next_back().cloned()
+    }
+
+    /// Iterates over the promotions strictly after some time, in chronological order.
+    ///
+    /// `None` stands for *before the first promotion*. Promotions are not part of
+    /// [`iter_events_after`], charts that care about them interleave the two.
+    ///
+    /// [`iter_events_after`]: #method.iter_events_after (The iter_events_after method)
+    pub fn iter_promotions_after<'me>(
+        &'me self,
+        last_time: Option<time::SinceStart>,
+    ) -> impl Iterator<Item = (time::SinceStart, &'me Alloc)> + 'me {
+        let start = last_time
+            .map(|last_time| last_time + time::SinceStart::from_nano_timestamp(0, 1))
+            .unwrap_or_else(time::SinceStart::zero);
+        self.promotion_map
+            .range(start..)
+            .flat_map(move |(time, uids)| uids.iter().map(move |uid| (*time, &self.uid_map[*uid])))
+    }
+
     /// Iterates over the new (de)allocation events in chronological order.
     ///
     /// Argument `since` is an optional pair containing an allocation UID, and a time-of-death
@@ -325,22 +954,35 @@ impl Data {
     pub fn iter_new_events<'me>(
         &'me self,
         since: Option<(uid::Alloc, time::SinceStart)>,
-        mut action: impl FnMut(Either<&'me Alloc, (time::SinceStart, &'me Alloc)>) -> Res<bool>,
+        action: impl FnMut(Either<&'me Alloc, (time::SinceStart, &'me Alloc)>) -> Res<bool>,
     ) -> Res<()> {
-        let (mut new_iter, mut dead_iter) = if let Some((last_alloc, last_time)) = since {
-            let mut alloc_iter = self.uid_map[last_alloc..].iter();
-            // First element is `last_alloc`, skipping it.
-            let _alloc = alloc_iter.next();
-            debug_assert!(_alloc.unwrap().uid == last_alloc);
+        self.iter_events_after(
+            since.map(|(last_alloc, _)| last_alloc),
+            since.map(|(_, last_time)| last_time),
+            action,
+        )
+    }
 
+    /// Iterates over the (de)allocation events after some allocation and some TOD, in
+    /// chronological order.
+    ///
+    /// Same as [`iter_new_events`], except that the allocation UID and the TOD are independent:
+    /// `None` stands for *before the first allocation* and *before the first deallocation*
+    /// respectively. This allows to resume an iteration that stopped before any deallocation.
+    ///
+    /// [`iter_new_events`]: #method.iter_new_events (The iter_new_events method)
+    pub fn iter_events_after<'me>(
+        &'me self,
+        last_alloc: Option<uid::Alloc>,
+        last_time: Option<time::SinceStart>,
+        mut action: impl FnMut(Either<&'me Alloc, (time::SinceStart, &'me Alloc)>) -> Res<bool>,
+    ) -> Res<()> {
+        let mut new_iter = self.uid_map.iter_after(last_alloc);
+        let mut dead_iter = if let Some(last_time) = last_time {
             let last_time = last_time + time::SinceStart::from_nano_timestamp(0, 1);
-
-            (alloc_iter, self.tod_map.range(last_time..))
+            self.tod_map.range(last_time..)
         } else {
-            (
-                self.uid_map.iter(),
-                self.tod_map.range(time::SinceStart::zero()..),
-            )
+            self.tod_map.range(time::SinceStart::zero()..)
         };
 
         let (mut next_new, mut next_dead) = (new_iter.next(), dead_iter.next());
@@ -393,6 +1035,144 @@ impl Data {
     }
 }
 
+impl Data {
+    /// Iterates over the changes after some events, in chronological order.
+    ///
+    /// Same as [`iter_new_events`], but yields [`Change`]s and also replays the [pruned]
+    /// allocations where their events were. Pruned changes use the same cursors as the events.
+    ///
+    /// The line of an event is the one `filters` give its allocation, the line of a pruned change
+    /// is the one `filters` give its key. Input `action` also takes the filters, and the event for
+    /// changes that are not pruned changes.
+    ///
+    /// [`iter_new_events`]: #method.iter_new_events (The iter_new_events method)
+    /// [`Change`]: prune/struct.Change.html (The Change struct)
+    /// [pruned]: prune/index.html (The prune module)
+    pub fn iter_new_changes<'me>(
+        &'me self,
+        since: Option<(uid::Alloc, time::SinceStart)>,
+        filters: &mut filter::Filters,
+        action: impl FnMut(
+            &mut filter::Filters,
+            prune::Change,
+            Option<&Either<&'me Alloc, (time::SinceStart, &'me Alloc)>>,
+        ) -> Res<bool>,
+    ) -> Res<()> {
+        let last_events = (
+            since.map(|(last_alloc, _)| last_alloc),
+            since.map(|(_, last_time)| last_time),
+        );
+        self.iter_changes_after(last_events, filters, action)
+    }
+
+    /// Iterates over the changes after some allocation and some TOD, in chronological order.
+    ///
+    /// Same as [`iter_new_changes`], with independent cursors as in [`iter_events_after`]:
+    /// `last_events` is the UID of the last allocation and the TOD of the last deallocation handled.
+    /// The cursor after each change is [`Change::last`].
+    ///
+    /// The allocations of the events are matched against the filters in parallel, by batches of
+    /// [`MATCH_BATCH_LEN`] events. The changes are the same as when matching them one by one: only
+    /// the matches of the events actually handled are remembered, and when `action` stops on a
+    /// deallocation the other deallocations of the same TOD, pruned or not, are still handled.
+    ///
+    /// [`iter_new_changes`]: #method.iter_new_changes (The iter_new_changes method)
+    /// [`iter_events_after`]: #method.iter_events_after (The iter_events_after method)
+    /// [`Change::last`]: prune/struct.Change.html#structfield.last (The last field of Change)
+    /// [`MATCH_BATCH_LEN`]: constant.MATCH_BATCH_LEN.html (The MATCH_BATCH_LEN constant)
+    pub fn iter_changes_after<'me>(
+        &'me self,
+        last_events: (Option<uid::Alloc>, Option<time::SinceStart>),
+        filters: &mut filter::Filters,
+        mut action: impl FnMut(
+            &mut filter::Filters,
+            prune::Change,
+            Option<&Either<&'me Alloc, (time::SinceStart, &'me Alloc)>>,
+        ) -> Res<bool>,
+    ) -> Res<()> {
+        let mut pruned = self.pruned.changes_after(last_events).peekable();
+        let mut lines = prune::Lines::new();
+
+        let mut stopped = false;
+        // TOD of the death the action stopped on, the other deaths of this TOD are handled anyway,
+        // as `iter_events_after` does.
+        let mut stopped_on_tod = None;
+
+        // Replays the pruned changes that precede some change, all of them if none.
+        macro_rules! replay {
+            (before $next:expr) => {
+                while let Some((key, mut change)) = pruned.peek().cloned() {
+                    let next: Option<&prune::Change> = $next;
+                    let handle = if stopped {
+                        stopped_on_tod.is_some() && change.last.right() == stopped_on_tod
+                    } else {
+                        next.map(|next| change.precedes(next)).unwrap_or(true)
+                    };
+                    if !handle {
+                        break;
+                    }
+                    let _ = pruned.next();
+                    change.line = lines.get(self, filters, key);
+                    if !action(filters, change, None)? && !stopped {
+                        stopped = true;
+                        stopped_on_tod = change.last.right();
+                    }
+                }
+            };
+        }
+
+        let (mut last_alloc, mut last_time) = last_events;
+        let mut batch = Vec::with_capacity(MATCH_BATCH_LEN);
+
+        loop {
+            // The iteration only pauses once all the deaths of a TOD are in the batch, so the
+            // cursors never split the deaths of a TOD.
+            self.iter_events_after(last_alloc, last_time, |event| {
+                batch.push(event);
+                Ok(batch.len() < MATCH_BATCH_LEN)
+            })?;
+            let is_last_batch = batch.len() < MATCH_BATCH_LEN;
+
+            let allocs: Vec<&Alloc> = batch
+                .iter()
+                .map(|event| event.as_ref().either(|alloc| *alloc, |(_, alloc)| *alloc))
+                .collect();
+            let matches = filters.find_matches(&self.current_time, &allocs);
+
+            for (event, filter) in batch.drain(..).zip(matches) {
+                let line = filter.map(uid::Line::Filter).unwrap_or(uid::Line::CatchAll);
+                let change = prune::Change::of_event(&event, line);
+                replay!(before Some(&change));
+                if stopped {
+                    match event {
+                        Either::Right((tod, _)) if Some(tod) == stopped_on_tod => (),
+                        _ => break,
+                    }
+                }
+
+                match event {
+                    Either::Left(alloc) => last_alloc = Some(alloc.uid),
+                    Either::Right((tod, _)) => last_time = Some(tod),
+                }
+                let alloc = event.as_ref().either(|alloc| *alloc, |(_, alloc)| *alloc);
+                filters.remember_match(alloc.uid, filter);
+                let cont = action(filters, change, Some(&event))?;
+                if !cont && !stopped {
+                    stopped = true;
+                    stopped_on_tod = change.last.right();
+                }
+            }
+
+            if stopped || is_last_batch {
+                break;
+            }
+        }
+
+        replay!(before None);
+        Ok(())
+    }
+}
+
 /// # Mutable Functions
 impl Data {
     /// Mutable reference to `self.tod_map[tod]`.
@@ -413,20 +1193,56 @@ impl Data {
             .stats
             .as_mut()
             .ok_or_else(|| "[charts data] trying to fill stats of uninitialized data")?;
-        stats.alloc_count = self.uid_map.len();
+        let sampling = stats
+            .estimate
+            .map(|estimate| estimate.sampling)
+            .unwrap_or(1);
+        stats.alloc_count = self.uid_map.next_index().get() * sampling;
         stats.duration = self.current_time;
         Ok(())
     }
 
+    /// Replaces the data with a full load of the same run, or of another trace, yields the previous
+    /// data.
+    ///
+    /// Dropping the data takes time, callers should drop the previous data once they released
+    /// their lock.
+    fn replace(&mut self, mut data: Data) -> Data {
+        data.generation = self.generation + 1;
+        data.tags_version = if self.tags.is_empty() {
+            self.tags_version
+        } else {
+            self.tags_version + 1
+        };
+        std::mem::replace(self, data)
+    }
+
+    /// Registers the UID in the trace of the next allocation, when the data is a sample of the
+    /// packets of a CTF trace.
+    pub fn sample_uid(&mut self, trace_uid: uid::Alloc) {
+        debug_assert_eq!(self.trace_uids.len(), self.uid_map.next_index().get());
+        self.trace_uids.push(trace_uid)
+    }
+
     /// Resets the data.
     ///
     /// Called when the init file of a run has changed.
     pub fn reset(&mut self, dump_dir: impl Into<std::path::PathBuf>, init: alloc::Init) {
-        self.stats = Some(AllocStats::new(dump_dir, init.start_time));
+        let mut stats = AllocStats::new(dump_dir, init.start_time);
+        stats.trace_format = init.trace_format.clone();
+        self.stats = Some(stats);
         self.init = Some(init);
         self.uid_map.clear();
         self.tod_map.clear();
+        self.promotion_map.clear();
+        self.pruned = prune::Pruned::new();
+        self.trace_uids.clear();
         self.current_time = time::SinceStart::zero();
+        self.gaps.clear();
+        if !self.tags.is_empty() {
+            self.tags.clear();
+            self.tags_version += 1
+        }
     }
 
     /// Builds a new allocation.
@@ -449,10 +1265,14 @@ impl Data {
 
     /// Registers a new allocation.
     pub fn add_new(&mut self, alloc: Alloc) -> Res<()> {
-        self.stats
+        let stats = self
+            .stats
             .as_mut()
-            .ok_or_else(|| "trying to add allocation before initialization")?
-            .total_size += alloc.real_size as u64;
+            .ok_or_else(|| "trying to add allocation before initialization")?;
+        stats.total_size += alloc.real_size as u64;
+        if alloc.has_unresolved_locs() {
+            stats.unresolved_locs += 1
+        }
         self.current_time = alloc.toc;
         let uid = self.uid_map.next_index();
         if uid != alloc.uid {
@@ -487,6 +1307,73 @@ impl Data {
         Ok(())
     }
 
+    /// Registers the promotion of an allocation to the major heap.
+    pub fn add_promotion(&mut self, timestamp: time::SinceStart, uid: uid::Alloc) -> Res<()> {
+        self.uid_map[uid].set_promoted_at(timestamp)?;
+        self.current_time = timestamp;
+        self.promotion_map
+            .entry(timestamp)
+            .or_insert_with(AllocUidSet::new)
+            .insert(uid);
+        Ok(())
+    }
+
+    /// Prunes the allocations that died at or before some time, see the [`prune`] module.
+    ///
+    /// Returns the number of allocations pruned.
+    ///
+    /// [`prune`]: prune/index.html (The prune module)
+    pub fn prune(&mut self, limit: time::SinceStart) -> Res<usize> {
+        let dead = |alloc: &Alloc| alloc.tod.map(|tod| tod <= limit).unwrap_or(false);
+        let mut pruned = vec![];
+        self.uid_map
+            .drop_old(|alloc| !dead(alloc), |alloc| pruned.push(alloc));
+        // Allocations created after `limit` cannot be dead since before `limit`.
+        self.uid_map
+            .drop_created_until(limit, |alloc| !dead(alloc), |alloc| pruned.push(alloc));
+
+        let mut untagged = false;
+        for alloc in &pruned {
+            self.pruned.add(alloc)?;
+
+            if let Some(tod) = alloc.tod {
+                let empty = self
+                    .tod_map
+                    .get_mut(&tod)
+                    .map(|uids| {
+                        uids.remove(&alloc.uid);
+                        uids.is_empty()
+                    })
+                    .unwrap_or(false);
+                if empty {
+                    self.tod_map.remove(&tod);
+                }
+            }
+            if let Some(promoted_at) = alloc.promoted_at {
+                let empty = self
+                    .promotion_map
+                    .get_mut(&promoted_at)
+                    .map(|uids| {
+                        uids.remove(&alloc.uid);
+                        uids.is_empty()
+                    })
+                    .unwrap_or(false);
+                if empty {
+                    self.promotion_map.remove(&promoted_at);
+                }
+            }
+            for tagged in self.tags.values_mut() {
+                untagged = tagged.remove(&alloc.uid) || untagged
+            }
+        }
+        if untagged {
+            self.tags_version += 1
+        }
+
+        self.pruned.ran(self.current_time, pruned.len());
+        Ok(pruned.len())
+    }
+
     /// Registers a diff.
     pub fn add_diff(&mut self, diff: alloc::Diff) -> Res<()> {
         self.current_time = diff.time;
@@ -558,3 +1445,205 @@ pub mod invariants {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn tags_are_labels() {
+        let mut synth = fixture::Synth::new();
+        let (trace, labels) = (fixture::trace(&[]), fixture::labels(&["ocaml"]));
+        for idx in 0..3u64 {
+            synth.alloc(idx, 8, &trace, &labels);
+        }
+        let _lock = set_for_test(synth.done());
+        let uid = |idx: u64| uid::Alloc::from(idx);
+        let labels = |idx: u64| -> Vec<String> {
+            get().unwrap()[uid(idx)]
+                .labels()
+                .iter()
+                .map(|label| label.to_string())
+                .collect()
+        };
+        let tagged = |idx: u64| {
+            let data = get().unwrap();
+            filter::sub::RawSubFilter::Label(tag::label_filter("leak"))
+                .apply(data.current_time(), &data[uid(idx)])
+        };
+        let version = tags_version().unwrap();
+
+        assert_eq!(tag("leak", vec![uid(0), uid(2)]).unwrap(), 2);
+        // Already tagged.
+        assert_eq!(tag("leak", vec![uid(2)]).unwrap(), 0);
+        assert_ne!(tags_version().unwrap(), version);
+        assert_eq!(tags().unwrap().get("leak"), Some(&2));
+        assert_eq!(labels(0), vec!["ocaml", "leak"]);
+        assert_eq!(labels(1), vec!["ocaml"]);
+        assert!(tagged(0) && !tagged(1) && tagged(2));
+
+        assert_eq!(untag("leak").unwrap(), 2);
+        assert!(tags().unwrap().is_empty());
+        assert_eq!(labels(0), vec!["ocaml"]);
+        assert!(!tagged(0) && !tagged(2));
+    }
+
+    #[test]
+    fn full_loads_keep_the_tags() {
+        let build = |trace_uids: &[u64]| {
+            let mut synth = fixture::Synth::new();
+            let (trace, labels) = (fixture::trace(&[]), fixture::labels(&["ocaml"]));
+            for trace_uid in trace_uids {
+                if trace_uids.len() < 4 {
+                    synth.data_mut().sample_uid(uid::Alloc::from(*trace_uid))
+                }
+                synth.alloc(*trace_uid, 8, &trace, &labels);
+            }
+            synth.done()
+        };
+
+        // A sample with the allocations `1` and `3` of the trace.
+        let _lock = set_for_test(build(&[1, 3]));
+        assert_eq!(tag("leak", vec![uid::Alloc::new(1)]).unwrap(), 1);
+        let version = tags_version().unwrap();
+
+        replace_with_full_load(build(&[0, 1, 2, 3])).unwrap();
+        assert_ne!(tags_version().unwrap(), version);
+        assert_eq!(tags().unwrap().get("leak"), Some(&1));
+        let data = get().unwrap();
+        let labels = |idx: usize| -> Vec<String> {
+            data[uid::Alloc::new(idx)]
+                .labels()
+                .iter()
+                .map(|label| label.to_string())
+                .collect()
+        };
+        assert_eq!(labels(1), vec!["ocaml"]);
+        assert_eq!(labels(3), vec!["ocaml", "leak"]);
+    }
+
+    #[test]
+    fn parallel_matching_is_sequential() {
+        use filter::label::{LabelPred, LabelSpec};
+
+        let mut synth = fixture::Synth::new();
+
+        // Deterministic pseudo-random labels, enough allocations for several batches.
+        let names = ["parse", "parse_int", "print", "eval", "eval_app", "gc"];
+        let trace = fixture::trace(&[]);
+        let mut seed = 42u64;
+        let count = 3 * MATCH_BATCH_LEN as u64;
+        for idx in 0..count {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let labels: Vec<&str> = (0..(seed >> 60) % 3)
+                .map(|n| names[((seed >> (8 * n)) % 6) as usize])
+                .collect();
+            let size = 8 * (1 + (seed >> 32) as u32 % 16);
+            synth.alloc(idx, size, &trace, &fixture::labels(&labels));
+        }
+        for idx in (0..count).step_by(3) {
+            synth.dead(count + idx, idx)
+        }
+        let data = synth.done();
+
+        let filters = {
+            let new = |specs: &[&str]| {
+                let mut filter =
+                    filter::Filter::new(filter::FilterSpec::new(Color::new(0, 0, 0))).unwrap();
+                let specs = specs
+                    .iter()
+                    .map(|spec| LabelSpec::new(*spec).unwrap())
+                    .collect();
+                filter
+                    .insert(filter::LabelFilter::new(LabelPred::Contain, specs))
+                    .unwrap();
+                filter
+            };
+            filter::Filters::new_with(vec![
+                new(&["#\"^parse_.*\"#"]),
+                new(&["#\"^eval\"#", "**"]),
+                new(&["print"]),
+                new(&["#\"^(gc|eval_.*)$\"#"]),
+            ])
+        };
+
+        let mut sequential = filters.clone();
+        let mut expected = vec![];
+        data.iter_events_after(None, None, |event| {
+            let alloc = event.as_ref().either(|alloc| *alloc, |(_, alloc)| *alloc);
+            let line = sequential
+                .find_match(data.current_time(), alloc)
+                .map(uid::Line::Filter)
+                .unwrap_or(uid::Line::CatchAll);
+            expected.push(prune::Change::of_event(&event, line));
+            Ok(true)
+        })
+        .unwrap();
+
+        let mut parallel = filters;
+        let mut changes = vec![];
+        data.iter_changes_after((None, None), &mut parallel, |_, change, _| {
+            changes.push(change);
+            Ok(true)
+        })
+        .unwrap();
+
+        assert_eq!(changes.len(), (count + count / 3) as usize);
+        assert_eq!(changes, expected);
+        for idx in 0..count {
+            let uid = uid::Alloc::from(idx);
+            assert_eq!(parallel.line_of(&uid), sequential.line_of(&uid))
+        }
+    }
+
+    #[test]
+    fn failed_trace_loads_keep_the_data() {
+        let _lock = set_for_test(Data::new());
+        // Not initialized yet.
+        assert!(load_trace(None).is_err());
+
+        get_mut().unwrap().reset("test", alloc::Init::default());
+        let generation = generation().unwrap();
+        // `test` is not a file.
+        assert!(load_trace(None).is_err());
+        assert!(load_trace(Some("/memthol/does/not/exist.ctf".into())).is_err());
+
+        let data = get().unwrap();
+        assert_eq!(data.generation(), generation);
+        assert_eq!(
+            data.stats().map(|stats| stats.dump_dir),
+            Some(std::path::PathBuf::from("test"))
+        );
+    }
+
+    #[test]
+    fn iter_allocs_from() {
+        let mut synth = fixture::Synth::new();
+        let (trace, labels) = (fixture::trace(&[]), fixture::labels(&[]));
+        for idx in 0..10u64 {
+            synth.alloc(10 * idx, 8, &trace, &labels);
+            if idx % 2 == 1 {
+                synth.dead(10 * idx + 5, idx)
+            }
+        }
+        let mut data = synth.done();
+        let uids = |data: &Data, ms: u64| -> Vec<usize> {
+            data.iter_allocs_from(fixture::millis(ms))
+                .map(|alloc| alloc.uid.get())
+                .collect()
+        };
+        assert_eq!(uids(&data, 0), (0..10).collect::<Vec<_>>());
+        assert_eq!(uids(&data, 65), vec![7, 8, 9]);
+        assert_eq!(uids(&data, 70), vec![7, 8, 9]);
+        assert_eq!(uids(&data, 100), Vec::<usize>::new());
+
+        // Allocations that survive pruning are always yielded.
+        let count = data.prune(fixture::millis(50)).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(uids(&data, 0), vec![0, 2, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(uids(&data, 70), vec![0, 2, 4, 5, 7, 8, 9]);
+    }
+}
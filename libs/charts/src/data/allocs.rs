@@ -0,0 +1,221 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Storage for the allocations, indexed by UID.
+//!
+//! Without pruning, this is a vector of allocations in UID order. Pruning drops old allocations,
+//! see the [`prune`] module: the store keeps the allocations from some UID on in a vector, and the
+//! older ones that were not pruned in a map.
+//!
+//! [`prune`]: ../prune/index.html (The prune module)
+
+prelude! {}
+
+use std::collections::VecDeque;
+
+/// Storage for the allocations, indexed by UID.
+#[derive(Debug, Clone)]
+pub struct Allocs {
+    /// Allocations older than `first` that were not pruned.
+    old: BTMap<uid::Alloc, Alloc>,
+    /// UID of the first allocation of `recent`.
+    first: usize,
+    /// Allocations from `first` on, in UID order.
+    recent: VecDeque<Alloc>,
+}
+
+impl Allocs {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self {
+            old: BTMap::new(),
+            first: 0,
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Reserves space for some allocations.
+    pub fn reserve(&mut self, capa: usize) {
+        self.recent.reserve(capa)
+    }
+
+    /// Removes all the allocations.
+    pub fn clear(&mut self) {
+        self.old.clear();
+        self.first = 0;
+        self.recent.clear()
+    }
+
+    /// Number of allocations stored, pruned ones excluded.
+    pub fn len(&self) -> usize {
+        self.old.len() + self.recent.len()
+    }
+    /// True if no allocation is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// UID of the next allocation pushed.
+    ///
+    /// Also the number of allocations ever pushed, pruned ones included.
+    pub fn next_index(&self) -> uid::Alloc {
+        (self.first + self.recent.len()).into()
+    }
+
+    /// Pushes an allocation, returns its UID.
+    pub fn push(&mut self, alloc: Alloc) -> uid::Alloc {
+        let uid = self.next_index();
+        self.recent.push_back(alloc);
+        uid
+    }
+
+    /// True if an allocation was pruned.
+    pub fn is_pruned(&self, uid: uid::Alloc) -> bool {
+        uid.get() < self.first && !self.old.contains_key(&uid)
+    }
+
+    /// Allocation accessor, `None` if the allocation was pruned or does not exist.
+    pub fn get(&self, uid: uid::Alloc) -> Option<&Alloc> {
+        if uid.get() < self.first {
+            self.old.get(&uid)
+        } else {
+            self.recent.get(uid.get() - self.first)
+        }
+    }
+    /// Allocation mutable accessor, `None` if the allocation was pruned or does not exist.
+    pub fn get_mut(&mut self, uid: uid::Alloc) -> Option<&mut Alloc> {
+        if uid.get() < self.first {
+            self.old.get_mut(&uid)
+        } else {
+            self.recent.get_mut(uid.get() - self.first)
+        }
+    }
+
+    /// Last allocation pushed, if it was not pruned.
+    pub fn last(&self) -> Option<&Alloc> {
+        self.recent.back().or_else(|| self.old.values().next_back())
+    }
+
+    /// Iterates over the allocations in UID order.
+    pub fn iter(&self) -> impl Iterator<Item = &Alloc> {
+        self.iter_after(None)
+    }
+
+    /// Iterates over the allocations strictly after some UID, in UID order.
+    ///
+    /// `None` stands for *before the first allocation*.
+    pub fn iter_after(&self, uid: Option<uid::Alloc>) -> impl Iterator<Item = &Alloc> {
+        use std::ops::Bound::{Excluded, Unbounded};
+        let (old_start, recent_start) = match uid {
+            None => (Unbounded, 0),
+            Some(uid) => (
+                Excluded(uid),
+                (uid.get() + 1)
+                    .saturating_sub(self.first)
+                    .min(self.recent.len()),
+            ),
+        };
+        self.old
+            .range((old_start, Unbounded))
+            .map(|(_, alloc)| alloc)
+            .chain(self.recent.range(recent_start..))
+    }
+
+    /// Iterates over the allocations in UID order, skipping the recent ones created before some
+    /// time.
+    ///
+    /// Allocations are created in UID order, so the first recent allocation created at or after
+    /// `time` is found by binary search. Old allocations are all yielded, callers must skip the
+    /// ones created before `time`.
+    pub fn iter_from(&self, time: time::SinceStart) -> impl Iterator<Item = &Alloc> {
+        let (mut low, mut high) = (0, self.recent.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.recent[mid].toc < time {
+                low = mid + 1
+            } else {
+                high = mid
+            }
+        }
+        self.old.values().chain(self.recent.range(low..))
+    }
+
+    /// Drops the allocations created up to some time, in UID order.
+    ///
+    /// The allocations for which `keep` is true are moved to the map of old allocations, the
+    /// others are passed to `pruned` and dropped.
+    pub fn drop_created_until(
+        &mut self,
+        time: time::SinceStart,
+        mut keep: impl FnMut(&Alloc) -> bool,
+        mut pruned: impl FnMut(Alloc),
+    ) {
+        while self
+            .recent
+            .front()
+            .map(|alloc| alloc.toc <= time)
+            .unwrap_or(false)
+        {
+            let alloc = self
+                .recent
+                .pop_front()
+                .expect("`pop_front` after `front().is_some()` cannot fail");
+            self.first += 1;
+            if keep(&alloc) {
+                let _prev = self.old.insert(alloc.uid, alloc);
+                debug_assert!(_prev.is_none())
+            } else {
+                pruned(alloc)
+            }
+        }
+    }
+
+    /// Drops the old allocations for which `keep` is false, passing them to `pruned`.
+    pub fn drop_old(
+        &mut self,
+        mut keep: impl FnMut(&Alloc) -> bool,
+        mut pruned: impl FnMut(Alloc),
+    ) {
+        let dropped: Vec<_> = self
+            .old
+            .values()
+            .filter(|alloc| !keep(alloc))
+            .map(|alloc| alloc.uid)
+            .collect();
+        for uid in dropped {
+            if let Some(alloc) = self.old.remove(&uid) {
+                pruned(alloc)
+            }
+        }
+    }
+}
+
+impl ops::Index<uid::Alloc> for Allocs {
+    type Output = Alloc;
+    fn index(&self, uid: uid::Alloc) -> &Alloc {
+        self.get(uid)
+            .unwrap_or_else(|| panic!("cannot access unknown or pruned allocation #{}", uid))
+    }
+}
+impl ops::IndexMut<uid::Alloc> for Allocs {
+    fn index_mut(&mut self, uid: uid::Alloc) -> &mut Alloc {
+        self.get_mut(uid)
+            .unwrap_or_else(|| panic!("cannot access unknown or pruned allocation #{}", uid))
+    }
+}
@@ -0,0 +1,734 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Parse cache of CTF traces.
+//!
+//! Parsing a big trace takes a while. After a full load, the data is written next to the trace, see
+//! [`path_of`], with a [`Fingerprint`] of the part of the trace it comes from. Opening the same trace
+//! again loads the cache instead of parsing the trace, unless the trace changed since, see
+//! [`Check`].
+//!
+//! Strings, labels and callstacks are shared through per-process factories, so a [`Cache`] has its
+//! own tables of strings, labels and callstacks, which are registered again when it is loaded.
+//!
+//! [`path_of`]: fn.path_of.html (The path_of function)
+//! [`Fingerprint`]: struct.Fingerprint.html (The Fingerprint struct)
+//! [`Check`]: enum.Check.html (The Check enum)
+//! [`Cache`]: struct.Cache.html (The Cache struct)
+
+prelude! {}
+
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use base::bincode::Options;
+
+use super::Data;
+
+/// Version of the cache format.
+///
+/// Caches with a different version, or written by a different version of memthol, are ignored.
+pub const VERSION: u32 = 3;
+
+/// Extension of cache files, appended to the name of the trace.
+pub const EXTENSION: &str = "memthol_cache";
+
+/// True if the cache is enabled.
+static ENABLED: sync::atomic::AtomicBool = sync::atomic::AtomicBool::new(true);
+
+/// True if the cache is enabled.
+pub fn is_enabled() -> bool {
+    ENABLED.load(sync::atomic::Ordering::Relaxed)
+}
+
+/// Enables or disables the cache.
+///
+/// When disabled, traces are always parsed and caches are neither read nor written.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, sync::atomic::Ordering::Relaxed)
+}
+
+/// Bincode options of the cache.
+fn bincode_options() -> impl Options {
+    base::bincode::DefaultOptions::new()
+}
+
+/// Path of the cache of a trace.
+///
+/// ```rust
+/// # use charts::data::cache::path_of;
+/// assert_eq!(
+///     path_of("dir/trace.ctf"),
+///     std::path::PathBuf::from("dir/trace.ctf.memthol_cache"),
+/// );
+/// ```
+pub fn path_of(trace: impl AsRef<Path>) -> PathBuf {
+    let mut path = trace.as_ref().as_os_str().to_owned();
+    path.push(".");
+    path.push(EXTENSION);
+    path.into()
+}
+
+/// Fingerprint of the beginning of a trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Number of bytes of the trace.
+    pub len: u64,
+    /// FNV-1a hash of these bytes.
+    pub hash: u64,
+}
+
+impl Fingerprint {
+    /// FNV-1a offset basis.
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    /// FNV-1a prime.
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// Fingerprint of some bytes.
+    pub fn of_bytes(bytes: &[u8]) -> Self {
+        Self {
+            len: bytes.len() as u64,
+            hash: Self::hash(Self::OFFSET, bytes),
+        }
+    }
+
+    /// Fingerprint of the first `len` bytes of a file.
+    ///
+    /// Fails if the file has less than `len` bytes.
+    pub fn of_file(path: impl AsRef<Path>, len: u64) -> Res<Self> {
+        let path = path.as_ref();
+        let file = fs::File::open(path)
+            .chain_err(|| format!("while opening `{}` to hash it", path.display()))?;
+        let mut reader = io::BufReader::new(file).take(len);
+        let (mut hash, mut read) = (Self::OFFSET, 0);
+        let mut buf = vec![0; 1 << 16];
+        loop {
+            let cnt = reader
+                .read(&mut buf)
+                .chain_err(|| format!("while hashing `{}`", path.display()))?;
+            if cnt == 0 {
+                break;
+            }
+            hash = Self::hash(hash, &buf[0..cnt]);
+            read += cnt as u64;
+        }
+        if read < len {
+            bail!(
+                "cannot hash the first {} bytes of `{}`, it only has {} bytes",
+                len,
+                path.display(),
+                read,
+            )
+        }
+        Ok(Self { len, hash })
+    }
+
+    /// Hashes some bytes, starting from some hash.
+    fn hash(mut hash: u64, bytes: &[u8]) -> u64 {
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(Self::PRIME)
+        }
+        hash
+    }
+}
+
+/// Result of checking a cache against its trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// The trace did not change since the cache was written.
+    Valid,
+    /// The cache comes from a different cache format or a different version of memthol.
+    Version,
+    /// The trace grew since the cache was written, it is probably still being written.
+    ///
+    /// The cache is still valid for the beginning of the trace, but the trace parser cannot resume
+    /// from the middle of a trace. The trace must be parsed again.
+    Grown,
+    /// The trace was replaced since the cache was written.
+    Replaced,
+}
+impl Check {
+    /// True if the cache is valid.
+    pub fn is_valid(self) -> bool {
+        self == Self::Valid
+    }
+}
+base::implement! {
+    impl Check {
+        Display {
+            |&self, fmt| match self {
+                Self::Valid => write!(fmt, "the trace did not change"),
+                Self::Version => write!(fmt, "the cache comes from a different version of memthol"),
+                Self::Grown => write!(fmt, "the trace grew since the cache was written"),
+                Self::Replaced => write!(fmt, "the trace was replaced since the cache was written"),
+            }
+        }
+    }
+}
+
+/// Header of a cache file, decoded before the rest of the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    /// Version of the cache format.
+    version: u32,
+    /// Version of memthol.
+    memthol: String,
+    /// Fingerprint of the part of the trace the cache comes from.
+    trace: Fingerprint,
+}
+
+impl Header {
+    /// Constructor.
+    pub fn new(trace: Fingerprint) -> Self {
+        Self {
+            version: VERSION,
+            memthol: env!("CARGO_PKG_VERSION").into(),
+            trace,
+        }
+    }
+
+    /// Checks the header against a trace.
+    pub fn check(&self, trace: impl AsRef<Path>) -> Res<Check> {
+        let trace = trace.as_ref();
+        if self.version != VERSION || self.memthol != env!("CARGO_PKG_VERSION") {
+            return Ok(Check::Version);
+        }
+        let len = fs::metadata(trace)
+            .chain_err(|| format!("could not retrieve metadata of `{}`", trace.display()))?
+            .len();
+        if len < self.trace.len || Fingerprint::of_file(trace, self.trace.len)? != self.trace {
+            Ok(Check::Replaced)
+        } else if len > self.trace.len {
+            Ok(Check::Grown)
+        } else {
+            Ok(Check::Valid)
+        }
+    }
+}
+
+/// A location of a callstack in a cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedLoc {
+    /// Index of the file in the strings of the cache.
+    file: usize,
+    /// Line in the file.
+    line: usize,
+    /// Column span at that line in the file.
+    span: alloc_data::Span,
+    /// Number of times the location is repeated.
+    cnt: usize,
+}
+
+/// An allocation in a cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedAlloc {
+    /// Allocation kind.
+    kind: alloc::AllocKind,
+    /// Size of the allocation.
+    size: u32,
+    /// Real size of the allocation.
+    real_size: u32,
+    /// Sample count.
+    nsamples: u32,
+    /// Index of the callstack in the callstacks of the cache.
+    trace: usize,
+    /// Index of the labels in the labels of the cache.
+    labels: usize,
+    /// Time of creation.
+    toc: time::SinceStart,
+    /// Time of death.
+    tod: Option<time::SinceStart>,
+    /// Time of promotion to the major heap.
+    promoted_at: Option<time::SinceStart>,
+    /// True if some of the locations of the callstack could not be resolved.
+    unresolved_locs: bool,
+}
+
+/// Data of a trace, independent of the process that built it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cache {
+    /// Init state.
+    init: alloc::Init,
+    /// Time of the latest event.
+    current_time: time::SinceStart,
+    /// Time ranges for which no data is available.
+    gaps: Vec<TimeWindow>,
+    /// Strings of the locations and the labels.
+    strs: Vec<String>,
+    /// Labels, as indices in `strs`.
+    labels: Vec<Vec<usize>>,
+    /// Callstacks.
+    traces: Vec<Vec<CachedLoc>>,
+    /// Allocations, ordered by UID.
+    allocs: Vec<CachedAlloc>,
+}
+
+/// Tables of a cache under construction.
+#[derive(Default)]
+struct Tables {
+    /// Strings.
+    strs: Vec<String>,
+    /// Maps shared strings to their index in `strs`.
+    str_indices: HMap<alloc::Str, usize>,
+    /// Labels.
+    labels: Vec<Vec<usize>>,
+    /// Maps labels to their index in `labels`.
+    label_indices: HMap<Vec<usize>, usize>,
+    /// Callstacks.
+    traces: Vec<Vec<CachedLoc>>,
+    /// Maps shared callstacks to their index in `traces`.
+    trace_indices: HMap<alloc::Trace, usize>,
+}
+
+impl Tables {
+    /// Index of a string.
+    fn str(&mut self, s: alloc::Str) -> usize {
+        let strs = &mut self.strs;
+        *self.str_indices.entry(s).or_insert_with(|| {
+            strs.push(s.to_string());
+            strs.len() - 1
+        })
+    }
+
+    /// Index of some labels.
+    fn labels(&mut self, labels: &[alloc::Str]) -> usize {
+        let labels: Vec<_> = labels.iter().map(|label| self.str(*label)).collect();
+        if let Some(idx) = self.label_indices.get(&labels) {
+            return *idx;
+        }
+        self.labels.push(labels.clone());
+        let idx = self.labels.len() - 1;
+        let _prev = self.label_indices.insert(labels, idx);
+        idx
+    }
+
+    /// Index of the callstack of an allocation.
+    fn trace(&mut self, alloc: &Alloc) -> usize {
+        let shared = alloc.shared_trace();
+        if let Some(idx) = self.trace_indices.get(&shared) {
+            return *idx;
+        }
+        let trace = alloc
+            .trace()
+            .iter()
+            .map(|cloc| CachedLoc {
+                file: self.str(cloc.loc.file),
+                line: cloc.loc.line,
+                span: cloc.loc.span,
+                cnt: cloc.cnt,
+            })
+            .collect();
+        self.traces.push(trace);
+        let idx = self.traces.len() - 1;
+        let _prev = self.trace_indices.insert(shared, idx);
+        idx
+    }
+}
+
+impl Cache {
+    /// Builds the cache of some data.
+    ///
+    /// Tags are not cached, they are removed from the labels of the allocations.
+    pub fn of_data(data: &Data) -> Res<Self> {
+        let init = data.init.clone().ok_or("cannot cache uninitialized data")?;
+        if data.pruned.count() > 0 {
+            bail!("cannot cache data with pruned allocations")
+        }
+        let mut tables = Tables::default();
+        let mut label_indices: HMap<alloc::Labels, usize> = HMap::new();
+
+        let allocs = data
+            .uid_map
+            .iter()
+            .map(|alloc| {
+                let mut tags = data
+                    .tags
+                    .iter()
+                    .filter(|(_, tagged)| tagged.contains(&alloc.uid))
+                    .map(|(tag, _)| tag)
+                    .peekable();
+                let labels = if tags.peek().is_none() {
+                    let shared = alloc.shared_labels();
+                    match label_indices.get(&shared) {
+                        Some(idx) => *idx,
+                        None => {
+                            let idx = tables.labels(&alloc.labels());
+                            let _prev = label_indices.insert(shared, idx);
+                            idx
+                        }
+                    }
+                } else {
+                    let mut labels = (*alloc.labels()).clone();
+                    for tag in tags {
+                        if let Some(idx) = labels.iter().rposition(|label| label == tag.as_str()) {
+                            labels.remove(idx);
+                        }
+                    }
+                    tables.labels(&labels)
+                };
+                CachedAlloc {
+                    kind: alloc.kind,
+                    size: alloc.size,
+                    real_size: alloc.real_size,
+                    nsamples: alloc.nsamples,
+                    trace: tables.trace(alloc),
+                    labels,
+                    toc: alloc.toc,
+                    tod: alloc.tod,
+                    promoted_at: alloc.promoted_at,
+                    unresolved_locs: alloc.unresolved_locs,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            init,
+            current_time: data.current_time,
+            gaps: data.gaps.clone(),
+            strs: tables.strs,
+            labels: tables.labels,
+            traces: tables.traces,
+            allocs,
+        })
+    }
+
+    /// Rebuilds the data of a trace.
+    ///
+    /// Registers the strings, labels and callstacks of the cache in the factories of the process.
+    pub fn into_data(self, trace: impl Into<PathBuf>) -> Res<Data> {
+        let Self {
+            init,
+            current_time,
+            gaps,
+            strs,
+            labels,
+            traces,
+            allocs,
+        } = self;
+        let sample_rate = init.sample_rate.clone();
+
+        let (labels, traces) = {
+            let mut factory = alloc::mem::Factory::new(false);
+            let strs: Vec<_> = strs.iter().map(|s| factory.register_str(s)).collect();
+            let str_of = |idx: usize| {
+                strs.get(idx)
+                    .cloned()
+                    .ok_or_else(|| format!("illegal string index {} in cache", idx))
+            };
+            let mut shared_labels = Vec::with_capacity(labels.len());
+            for indices in labels {
+                let labels = indices.into_iter().map(str_of).collect::<Result<_, _>>()?;
+                shared_labels.push(factory.register_labels(labels))
+            }
+            let mut shared_traces = Vec::with_capacity(traces.len());
+            for trace in traces {
+                let trace = trace
+                    .into_iter()
+                    .map(|loc| {
+                        str_of(loc.file).map(|file| {
+                            alloc::CLoc::new(alloc::Loc::new(file, loc.line, loc.span), loc.cnt)
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                shared_traces.push(factory.register_trace(trace))
+            }
+            (shared_labels, shared_traces)
+        };
+
+        let mut data = Data::new();
+        data.reset(trace, init);
+        data.reserve(allocs.len());
+        let mut deaths = Vec::with_capacity(allocs.len());
+        let mut promotions = vec![];
+        for cached in allocs {
+            let (trace, labels) = match (traces.get(cached.trace), labels.get(cached.labels)) {
+                (Some(trace), Some(labels)) => (*trace, *labels),
+                _ => bail!("illegal callstack or labels index in cache"),
+            };
+            let uid = data.next_uid();
+            let mut alloc = Alloc::new(
+                &sample_rate,
+                uid,
+                cached.kind,
+                cached.size,
+                trace,
+                labels,
+                cached.toc,
+                None,
+            )
+            .nsamples(cached.nsamples);
+            alloc.real_size = cached.real_size;
+            alloc.unresolved_locs = cached.unresolved_locs;
+            data.add_new(alloc)?;
+            if let Some(tod) = cached.tod {
+                deaths.push((tod, uid))
+            }
+            if let Some(promoted_at) = cached.promoted_at {
+                promotions.push((promoted_at, uid))
+            }
+        }
+        for (tod, uid) in deaths {
+            data.add_dead(tod, uid)?
+        }
+        for (promoted_at, uid) in promotions {
+            data.add_promotion(promoted_at, uid)?
+        }
+        data.mark_timestamp(current_time);
+        data.gaps = gaps;
+        data.fill_stats()?;
+        Ok(data)
+    }
+
+    /// Writes the cache of a trace, yields the size of the cache file.
+    ///
+    /// The cache comes from the first `len` bytes of the trace. The cache is written in a temporary
+    /// file first, so that an interrupted write does not leave a broken cache.
+    pub fn write(&self, trace: impl AsRef<Path>, len: u64) -> Res<u64> {
+        let trace = trace.as_ref();
+        let header = Header::new(Fingerprint::of_file(trace, len)?);
+        let path = path_of(trace);
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+
+        {
+            use std::io::Write;
+            let file = fs::File::create(&tmp)
+                .chain_err(|| format!("while creating cache file `{}`", path.display()))?;
+            let mut writer = io::BufWriter::new(file);
+            bincode_options().serialize_into(&mut writer, &header)?;
+            bincode_options().serialize_into(&mut writer, self)?;
+            writer
+                .flush()
+                .chain_err(|| format!("while writing cache file `{}`", path.display()))?;
+        }
+        fs::rename(&tmp, &path)
+            .chain_err(|| format!("while writing cache file `{}`", path.display()))?;
+
+        let size = fs::metadata(&path)
+            .chain_err(|| format!("could not retrieve metadata of `{}`", path.display()))?
+            .len();
+        Ok(size)
+    }
+}
+
+/// Loads the cache of a trace, if it is valid.
+///
+/// Yields the data and the size of the cache file, `None` if the trace has no cache or if the trace
+/// changed since the cache was written.
+pub fn load(trace: impl AsRef<Path>) -> Res<Option<(Data, u64)>> {
+    let trace = trace.as_ref();
+    let path = path_of(trace);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let file = fs::File::open(&path)
+        .chain_err(|| format!("while opening cache file `{}`", path.display()))?;
+    let size = file
+        .metadata()
+        .chain_err(|| format!("could not retrieve metadata of `{}`", path.display()))?
+        .len();
+    let mut reader = io::BufReader::new(file);
+
+    let header: Header = bincode_options()
+        .deserialize_from(&mut reader)
+        .chain_err(|| format!("while reading cache file `{}`", path.display()))?;
+    let check = header.check(trace)?;
+    if !check.is_valid() {
+        log::info!("ignoring cache file `{}`: {}", path.display(), check);
+        return Ok(None);
+    }
+
+    let cache: Cache = bincode_options()
+        .deserialize_from(&mut reader)
+        .chain_err(|| format!("while reading cache file `{}`", path.display()))?;
+    let data = cache.into_data(trace)?;
+    Ok(Some((data, size)))
+}
+
+/// Sets the status of the cache in the global data.
+fn set_status(status: CacheStatus) {
+    if let Ok(mut data) = super::get_mut() {
+        data.stats_do(|stats| stats.cache = Some(status))
+    }
+}
+
+/// Writes the cache of the global data in the background.
+///
+/// The global data must come from the first `len` bytes of the trace. The cache is built right away,
+/// only hashing the trace and writing the cache happen in the background. Does nothing but update
+/// the status of the cache if the cache is disabled.
+pub fn spawn_write(trace: impl Into<PathBuf>, len: u64) {
+    if !is_enabled() {
+        set_status(CacheStatus::Disabled);
+        return;
+    }
+    let trace = trace.into();
+    let cache = match super::get().and_then(|data| Cache::of_data(&data)) {
+        Ok(cache) => cache,
+        Err(e) => {
+            log::warn!("could not build the cache of `{}`", trace.display());
+            set_status(CacheStatus::Failed(e.to_string()));
+            return;
+        }
+    };
+    set_status(CacheStatus::Writing);
+
+    let _ = std::thread::spawn(move || {
+        let status = match cache.write(&trace, len) {
+            Ok(size) => {
+                log::info!("done writing the cache of `{}`", trace.display());
+                CacheStatus::Written(size)
+            }
+            Err(e) => {
+                log::warn!("could not write the cache of `{}`", trace.display());
+                log::warn!("{}", e.to_pretty());
+                CacheStatus::Failed(e.to_string())
+            }
+        };
+        set_status(status)
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Path of a temporary file for a test.
+    fn tmp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("memthol_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn stale_caches() {
+        let trace = tmp_path("stale_caches.ctf");
+        fs::write(&trace, b"first packets").unwrap();
+        let header = Header::new(Fingerprint::of_file(&trace, 13).unwrap());
+        assert_eq!(header.trace, Fingerprint::of_bytes(b"first packets"));
+        assert_eq!(header.check(&trace).unwrap(), Check::Valid);
+
+        // Live trace, new packets are appended.
+        fs::write(&trace, b"first packets, more packets").unwrap();
+        assert_eq!(header.check(&trace).unwrap(), Check::Grown);
+
+        // Same length, different content.
+        fs::write(&trace, b"other packets").unwrap();
+        assert_eq!(header.check(&trace).unwrap(), Check::Replaced);
+        // Different content that is longer than the original.
+        fs::write(&trace, b"other packets, more packets").unwrap();
+        assert_eq!(header.check(&trace).unwrap(), Check::Replaced);
+        // Shorter.
+        fs::write(&trace, b"first").unwrap();
+        assert_eq!(header.check(&trace).unwrap(), Check::Replaced);
+
+        let mut old = header.clone();
+        old.version = VERSION + 1;
+        fs::write(&trace, b"first packets").unwrap();
+        assert_eq!(old.check(&trace).unwrap(), Check::Version);
+
+        fs::remove_file(&trace).unwrap();
+    }
+
+    #[test]
+    fn round_trip() {
+        let init = alloc::Init::default();
+        let sample_rate = init.sample_rate.clone();
+        let mut data = Data::new();
+        data.reset("trace", init);
+
+        let file = alloc::Str::new("cache_test.ml");
+        let traces = vec![
+            alloc::mem::trace::add(vec![alloc::CLoc::new(alloc::Loc::new(file, 1, (0, 3)), 1)]),
+            alloc::mem::trace::add(vec![alloc::CLoc::new(alloc::Loc::new(file, 7, (2, 5)), 2)]),
+        ];
+        let labels = alloc::mem::labels::add(vec![alloc::Str::new("ocaml")]);
+        for idx in 0..10u64 {
+            let toc = time::SinceStart::from(time::Duration::from_millis(idx));
+            let alloc = Alloc::new(
+                &sample_rate,
+                idx,
+                alloc::AllocKind::Minor,
+                8 + idx as u32,
+                traces[idx as usize % 2].clone(),
+                labels.clone(),
+                toc,
+                None,
+            );
+            data.add_new(alloc).unwrap()
+        }
+        for idx in (0..10u64).step_by(3) {
+            let tod = time::SinceStart::from(time::Duration::from_millis(20 + idx));
+            data.add_dead(tod, uid::Alloc::from(idx)).unwrap()
+        }
+        for idx in (0..10u64).step_by(2) {
+            let promoted_at = time::SinceStart::from(time::Duration::from_millis(10 + idx));
+            data.add_promotion(promoted_at, uid::Alloc::from(idx))
+                .unwrap()
+        }
+        let secs = time::SinceStart::from_secs;
+        data.add_gap(TimeWindow::new(secs(1), secs(2)));
+        data.fill_stats().unwrap();
+
+        // Tags are not cached.
+        let tag = alloc::Str::new("cache_tag");
+        let mut tagged = data.uid_map[uid::Alloc::new(4)].clone();
+        tagged.set_labels(alloc::mem::labels::add(vec![alloc::Str::new("ocaml"), tag]));
+        data.uid_map[uid::Alloc::new(4)] = tagged;
+        data.tags.insert(
+            "cache_tag".into(),
+            vec![uid::Alloc::new(4)].into_iter().collect(),
+        );
+
+        let trace = tmp_path("round_trip.ctf");
+        fs::write(&trace, b"some trace").unwrap();
+        let size = Cache::of_data(&data).unwrap().write(&trace, 10).unwrap();
+        let (loaded, loaded_size) = load(&trace).unwrap().unwrap();
+        assert_eq!(size, loaded_size);
+
+        assert_eq!(loaded.stats().unwrap().alloc_count, 10);
+        assert_eq!(
+            loaded.stats().unwrap().total_size,
+            data.stats().unwrap().total_size
+        );
+        assert_eq!(loaded.current_time(), data.current_time());
+        assert_eq!(loaded.gaps(), data.gaps());
+        assert_eq!(loaded.tod_map, data.tod_map);
+        assert_eq!(loaded.promotion_map, data.promotion_map);
+        for (old, new) in data.iter_allocs().zip(loaded.iter_allocs()) {
+            assert_eq!(old.uid, new.uid);
+            assert_eq!(old.size, new.size);
+            assert_eq!(old.real_size, new.real_size);
+            assert_eq!(old.toc, new.toc);
+            assert_eq!(old.tod, new.tod);
+            assert_eq!(old.promoted_at, new.promoted_at);
+            assert_eq!(old.trace(), new.trace());
+            assert_eq!(*new.labels(), vec![alloc::Str::new("ocaml")]);
+        }
+
+        // The trace grows, the cache is ignored.
+        fs::write(&trace, b"some trace, more").unwrap();
+        assert!(load(&trace).unwrap().is_none());
+
+        fs::remove_file(&trace).unwrap();
+        fs::remove_file(path_of(&trace)).unwrap();
+    }
+}
@@ -0,0 +1,756 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Loader for the heap profiles of valgrind's massif tool.
+//!
+//! Massif does not record allocations, it takes snapshots of the size of the heap. Some snapshots
+//! are *detailed*: they come with an allocation tree attributing the size of the heap to
+//! callstacks. The loader replays the snapshots as allocations and deaths, for each [`Site`]:
+//!
+//! - when the size of a site grows between two snapshots, an allocation of the difference is
+//!   created at the time of the second snapshot;
+//! - when it shrinks, the most recent allocations of the site die at the time of the second
+//!   snapshot, and an allocation makes up for the difference if too much died.
+//!
+//! Lifetimes are thus approximate: an allocation stays alive until the next snapshot where the
+//! size of its site shrinks. Non-detailed snapshots only give the size of the heap, the difference
+//! with the size attributed to callstacks goes to a site with an empty callstack. The [trace
+//! format] lists these approximations as quirks so that they show up in the UI.
+//!
+//! Callstacks go from the allocation function to the outermost caller, like massif's trees. The
+//! labels of an allocation are the names of the functions of its callstack, so that label filters
+//! match functions. Massif's time unit (instructions, milliseconds or bytes allocated) is shown as
+//! milliseconds.
+//!
+//! [`Site`]: struct.Site.html (The Site struct)
+//! [trace format]: struct.Profile.html#method.trace_format (The trace_format method of Profile)
+
+prelude! {}
+
+use std::path::Path;
+
+/// True if a file is named like a massif profile: `massif.out.<pid>` or `<name>.massif`.
+///
+/// ```rust
+/// # use charts::data::massif::is_massif_file;
+/// assert!(is_massif_file("massif.out.1234"));
+/// assert!(is_massif_file("traces/run.massif"));
+/// assert!(!is_massif_file("traces/run.ctf"));
+/// assert!(!is_massif_file("massif"));
+/// ```
+pub fn is_massif_file(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    let name_ok = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with("massif.out"))
+        .unwrap_or(false);
+    name_ok || path.extension().map(|ext| ext == "massif").unwrap_or(false)
+}
+
+/// Loads a massif profile in some new data.
+///
+/// Does not lock the global data.
+pub fn load(target: &Path) -> Res<data::Data> {
+    log::info!("loading massif file `{}`", target.display());
+    let text = std::fs::read_to_string(target)
+        .chain_err(|| format!("while reading massif file `{}`", target.display()))?;
+    let profile = Profile::parse(&text)
+        .chain_err(|| format!("while parsing massif file `{}`", target.display()))?;
+
+    let mut factory = data::FullFactory::with_data(false, Box::new(data::Data::new()));
+    factory.data.reset(target, profile.init());
+    let mut replay = Replay::new();
+    for snapshot in &profile.snapshots {
+        replay
+            .snapshot(&mut factory, snapshot)
+            .chain_err(|| format!("while loading massif file `{}`", target.display()))?
+    }
+    factory.fill_stats()?;
+
+    log::info!("done loading massif file `{}`", target.display());
+    Ok(*factory.into_data())
+}
+
+/// Converts a massif time to a time since the start of the run.
+fn since_start(time: u64) -> time::SinceStart {
+    time::SinceStart::from(time::Duration::from_millis(time))
+}
+
+/// A massif profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// Description of the run, the options massif ran with.
+    pub desc: String,
+    /// Command massif profiled.
+    pub cmd: String,
+    /// Unit of the times of the snapshots: `i`, `ms` or `B`.
+    pub time_unit: String,
+    /// Snapshots, in chronological order.
+    pub snapshots: Vec<Snapshot>,
+}
+
+impl Profile {
+    /// Parses a massif profile.
+    pub fn parse(text: &str) -> Res<Self> {
+        let mut lines = Lines::new(text);
+        let desc = lines.field("desc", ": ")?.to_string();
+        let cmd = lines.field("cmd", ": ")?.to_string();
+        let time_unit = lines.field("time_unit", ": ")?.to_string();
+
+        let mut snapshots: Vec<Snapshot> = vec![];
+        while lines.peek().is_some() {
+            let _index = lines.field("snapshot", "=")?;
+            let time = lines.int_field("time")?;
+            if let Some(last) = snapshots.last() {
+                if time < last.time {
+                    bail!(
+                        "line {}: snapshot at time {} comes after a snapshot at time {}",
+                        lines.line,
+                        time,
+                        last.time
+                    )
+                }
+            }
+            let heap = lines.int_field("mem_heap_B")?;
+            let _extra = lines.int_field("mem_heap_extra_B")?;
+            let _stacks = lines.int_field("mem_stacks_B")?;
+            let tree = match lines.field("heap_tree", "=")? {
+                "empty" => None,
+                "detailed" | "peak" => Some(Node::parse(&mut lines, 0)?),
+                kind => bail!("line {}: unknown heap tree kind `{}`", lines.line, kind),
+            };
+            snapshots.push(Snapshot { time, heap, tree })
+        }
+
+        Ok(Self {
+            desc,
+            cmd,
+            time_unit,
+            snapshots,
+        })
+    }
+
+    /// Initial state of the data of the profile.
+    ///
+    /// Massif records neither the date of the run nor a sampling rate. Sizes are in bytes, a word
+    /// size of 8 bits makes them exact.
+    pub fn init(&self) -> alloc::Init {
+        let end_time = self
+            .snapshots
+            .last()
+            .map(|snapshot| since_start(snapshot.time));
+        alloc::Init::new(time::Date::from_timestamp(0, 0), end_time, 8, false)
+            .trace_format(self.trace_format())
+    }
+
+    /// Format of the profile, lists the approximations of the loader as quirks.
+    pub fn trace_format(&self) -> alloc::TraceFormat {
+        let mut quirks = vec![
+            "approximate lifetimes, allocations die at the first snapshot where the size of \
+            their callstack shrinks"
+                .to_string(),
+            "sizes of non-detailed snapshots are not attributed to callstacks".to_string(),
+        ];
+        match self.time_unit.as_str() {
+            "ms" => (),
+            "i" => quirks.push("times are instruction counts, shown as milliseconds".into()),
+            "B" => quirks.push("times are bytes allocated, shown as milliseconds".into()),
+            unit => quirks.push(format!("times are in `{}`, shown as milliseconds", unit)),
+        }
+        alloc::TraceFormat {
+            name: "massif".into(),
+            // Massif profiles are not versioned.
+            version: 1,
+            events: vec!["snapshot".into(), "detailed snapshot".into()],
+            quirks,
+            features: vec![],
+            context: Some(format!("{} ({})", self.cmd, self.desc)),
+        }
+    }
+}
+
+/// A snapshot of the heap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Time of the snapshot, in the time unit of the profile.
+    pub time: u64,
+    /// Size of the heap in bytes, administrative bytes excluded.
+    pub heap: u64,
+    /// Allocation tree, for detailed snapshots.
+    pub tree: Option<Node>,
+}
+
+/// A node of an allocation tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// Number of bytes allocated under the node.
+    pub bytes: u64,
+    /// Frame of the node, `None` for the root and for the entries below massif's threshold.
+    pub frame: Option<Frame>,
+    /// Children of the node, its callers.
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    /// Parses a node and its children.
+    ///
+    /// - `depth`: depth of the node, the root is at depth `0`.
+    fn parse(lines: &mut Lines, depth: usize) -> Res<Self> {
+        let line = lines
+            .next()
+            .ok_or("unexpected end of file in a heap tree")?;
+        let line_idx = lines.line;
+        let err = || format!("line {}: illegal heap tree node `{}`", line_idx, line);
+
+        let node = line.trim_start().strip_prefix('n').ok_or_else(err)?;
+        let (count, node) = split2(node, ": ").ok_or_else(err)?;
+        let count = usize::from_str(count).map_err(|_| err())?;
+        let (bytes, desc) = split2(node, " ").ok_or_else(err)?;
+        let bytes = u64::from_str(bytes).map_err(|_| err())?;
+        let frame = if depth == 0 || desc.contains("below massif's threshold") {
+            None
+        } else {
+            Some(Frame::parse(desc).ok_or_else(err)?)
+        };
+
+        let mut children = Vec::with_capacity(count);
+        for _ in 0..count {
+            children.push(Self::parse(lines, depth + 1)?)
+        }
+        Ok(Self {
+            bytes,
+            frame,
+            children,
+        })
+    }
+
+    /// Sizes the tree attributes to each site.
+    ///
+    /// Bytes of a node its children do not account for are attributed to the node's own
+    /// callstack, which is empty for the root.
+    pub fn sites(&self) -> BTMap<Site, u64> {
+        let mut sites = BTMap::new();
+        self.sites_in(true, &mut vec![], &mut sites);
+        sites
+    }
+
+    /// Adds the sites of a node to a map.
+    ///
+    /// - `frames`: frames from the root to the parent of the node.
+    fn sites_in(&self, is_root: bool, frames: &mut Vec<Frame>, sites: &mut BTMap<Site, u64>) {
+        let below_threshold = !is_root && self.frame.is_none();
+        if let Some(frame) = self.frame.as_ref() {
+            frames.push(frame.clone())
+        }
+
+        let children_bytes: u64 = self.children.iter().map(|child| child.bytes).sum();
+        if self.bytes > children_bytes {
+            let site = Site {
+                frames: frames.clone(),
+                below_threshold,
+            };
+            *sites.entry(site).or_insert(0) += self.bytes - children_bytes
+        }
+        for child in &self.children {
+            child.sites_in(false, frames, sites)
+        }
+
+        if self.frame.is_some() {
+            frames.pop();
+        }
+    }
+}
+
+/// A frame of a callstack.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Frame {
+    /// Code address.
+    pub addr: String,
+    /// Function, `???` if unknown.
+    pub fun: String,
+    /// Location of the code.
+    pub loc: FrameLoc,
+}
+
+impl Frame {
+    /// Parses a frame from the description of a node, `None` if illegal.
+    ///
+    /// ```rust
+    /// # use charts::data::massif::{Frame, FrameLoc};
+    /// let desc = "0x4C2DB8F: operator new(unsigned long) (vg_replace_malloc.c:334)";
+    /// let frame = Frame::parse(desc).unwrap();
+    /// assert_eq!(frame.addr, "0x4C2DB8F");
+    /// assert_eq!(frame.fun, "operator new(unsigned long)");
+    /// assert_eq!(
+    ///     frame.loc,
+    ///     FrameLoc::Source { file: "vg_replace_malloc.c".into(), line: 334 },
+    /// );
+    ///
+    /// let frame = Frame::parse("0x4005E0: ??? (in /usr/lib/libfoo.so)").unwrap();
+    /// assert_eq!(frame.fun, "???");
+    /// assert_eq!(frame.loc, FrameLoc::Object("/usr/lib/libfoo.so".into()));
+    ///
+    /// let frame = Frame::parse("0x4005E0: main").unwrap();
+    /// assert_eq!(frame.loc, FrameLoc::Unknown);
+    ///
+    /// assert!(Frame::parse("in 3 places, all below massif's threshold (1.00%)").is_none());
+    /// ```
+    pub fn parse(desc: &str) -> Option<Self> {
+        let (addr, rest) = split2(desc, ": ")?;
+        if !addr.starts_with("0x") {
+            return None;
+        }
+        let (fun, loc) = match (rest.rfind(" ("), rest.ends_with(')')) {
+            (Some(idx), true) => {
+                let loc = &rest[idx + 2..rest.len() - 1];
+                let loc = match loc.strip_prefix("in ") {
+                    Some(object) => FrameLoc::Object(object.into()),
+                    None => match loc.rfind(':') {
+                        Some(colon) => FrameLoc::Source {
+                            file: loc[..colon].into(),
+                            line: usize::from_str(&loc[colon + 1..]).ok()?,
+                        },
+                        None => return None,
+                    },
+                };
+                (&rest[..idx], loc)
+            }
+            _ => (rest, FrameLoc::Unknown),
+        };
+        Some(Self {
+            addr: addr.into(),
+            fun: fun.into(),
+            loc,
+        })
+    }
+
+    /// True if the function of the frame is known.
+    pub fn has_fun(&self) -> bool {
+        self.fun != "???"
+    }
+}
+
+/// Location of the code of a frame.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FrameLoc {
+    /// Line in a source file.
+    Source {
+        /// Source file.
+        file: String,
+        /// Line in the file.
+        line: usize,
+    },
+    /// Object file, when there is no debug information.
+    Object(String),
+    /// Unknown location.
+    Unknown,
+}
+
+/// A callstack sizes are attributed to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Site {
+    /// Frames, from the allocation function to the outermost caller.
+    pub frames: Vec<Frame>,
+    /// True for the entries massif gathers below its significance threshold.
+    pub below_threshold: bool,
+}
+
+impl Site {
+    /// Site of the sizes attributed to no callstack.
+    fn unattributed() -> Self {
+        Self {
+            frames: vec![],
+            below_threshold: false,
+        }
+    }
+}
+
+/// Replays the snapshots of a profile as allocations and deaths.
+struct Replay {
+    /// Live allocations of each site with their size, oldest first.
+    live: BTMap<Site, Vec<(uid::Alloc, u64)>>,
+    /// Callstack and labels of the sites seen so far, and whether the callstack has unresolved
+    /// locations.
+    registered: HMap<Site, (alloc::Trace, alloc::Labels, bool)>,
+}
+
+impl Replay {
+    /// Constructor.
+    fn new() -> Self {
+        Self {
+            live: BTMap::new(),
+            registered: HMap::new(),
+        }
+    }
+
+    /// Size of the live allocations of a site.
+    fn live_size(&self, site: &Site) -> u64 {
+        self.live
+            .get(site)
+            .map(|allocs| allocs.iter().map(|(_, size)| size).sum())
+            .unwrap_or(0)
+    }
+
+    /// Replays a snapshot.
+    fn snapshot<'a, D>(
+        &mut self,
+        factory: &mut data::FullFactory<'a, D>,
+        snapshot: &Snapshot,
+    ) -> Res<()>
+    where
+        D: std::ops::DerefMut<Target = data::Data>,
+    {
+        let timestamp = since_start(snapshot.time);
+        let unattributed = Site::unattributed();
+
+        let mut targets = match snapshot.tree.as_ref() {
+            Some(tree) => tree.sites(),
+            // Sites keep their size.
+            None => self
+                .live
+                .keys()
+                .filter(|site| **site != unattributed)
+                .map(|site| (site.clone(), self.live_size(site)))
+                .collect(),
+        };
+        let attributed: u64 = targets.values().sum();
+        *targets.entry(unattributed).or_insert(0) += snapshot.heap.saturating_sub(attributed);
+        // Sites absent from the snapshot shrink to nothing.
+        for site in self.live.keys() {
+            targets.entry(site.clone()).or_insert(0);
+        }
+
+        for (site, target) in targets {
+            self.resize(factory, timestamp, site, target)?
+        }
+        factory.mark_timestamp(timestamp);
+        Ok(())
+    }
+
+    /// Creates or kills allocations so that the live allocations of a site have some size.
+    fn resize<'a, D>(
+        &mut self,
+        factory: &mut data::FullFactory<'a, D>,
+        timestamp: time::SinceStart,
+        site: Site,
+        target: u64,
+    ) -> Res<()>
+    where
+        D: std::ops::DerefMut<Target = data::Data>,
+    {
+        let mut size = self.live_size(&site);
+        if size == target {
+            return Ok(());
+        }
+
+        let (trace, labels, unresolved_locs) = self.register(factory, &site);
+        let live = self.live.entry(site).or_insert_with(Vec::new);
+        while size > target {
+            let (uid, alloc_size) = live.pop().ok_or("[massif] live size of a site is off")?;
+            factory.add_dead(timestamp, uid)?;
+            size -= alloc_size
+        }
+        while size < target {
+            let alloc_size = std::cmp::min(target - size, u32::MAX as u64);
+            let uid = factory.data.next_uid();
+            let builder = alloc::Builder::new(
+                None,
+                alloc::AllocKind::Unknown,
+                alloc_size as u32,
+                trace.clone(),
+                labels.clone(),
+                timestamp,
+                None,
+            )
+            .unresolved_locs(unresolved_locs);
+            factory.build_new(builder)?;
+            live.push((uid, alloc_size));
+            size += alloc_size
+        }
+        Ok(())
+    }
+
+    /// Callstack and labels of a site, registers them if needed.
+    fn register<'a, D>(
+        &mut self,
+        factory: &mut data::FullFactory<'a, D>,
+        site: &Site,
+    ) -> (alloc::Trace, alloc::Labels, bool) {
+        if let Some(registered) = self.registered.get(site) {
+            return registered.clone();
+        }
+
+        let mut unresolved_locs = false;
+        let mut trace = Vec::with_capacity(site.frames.len());
+        let mut labels = vec![];
+        for frame in &site.frames {
+            let loc = match &frame.loc {
+                FrameLoc::Source { file, line } => {
+                    alloc::Loc::new(factory.register_str(file), *line, (0, 0))
+                }
+                FrameLoc::Object(object) => {
+                    alloc::Loc::new(factory.register_str(object), 0, (0, 0))
+                }
+                FrameLoc::Unknown => {
+                    unresolved_locs = true;
+                    let file =
+                        factory.register_str(&format!("<unresolved location {}>", frame.addr));
+                    alloc::Loc::new(file, 0, (0, 0))
+                }
+            };
+            trace.push(alloc::CLoc::new(loc, 1));
+            if frame.has_fun() {
+                labels.push(factory.register_str(&frame.fun))
+            }
+        }
+
+        let registered = (
+            factory.register_trace(trace),
+            factory.register_labels(labels),
+            unresolved_locs,
+        );
+        self.registered.insert(site.clone(), registered.clone());
+        registered
+    }
+}
+
+/// Splits a string at the first occurrence of a separator.
+fn split2<'s>(s: &'s str, sep: &str) -> Option<(&'s str, &'s str)> {
+    let idx = s.find(sep)?;
+    Some((&s[..idx], &s[idx + sep.len()..]))
+}
+
+/// Lines of a massif profile, skips comments and empty lines.
+struct Lines<'s> {
+    /// Lines.
+    lines: std::iter::Peekable<std::str::Lines<'s>>,
+    /// Number of the last line yielded, starting at `1`.
+    line: usize,
+}
+
+impl<'s> Lines<'s> {
+    /// Constructor.
+    fn new(text: &'s str) -> Self {
+        Self {
+            lines: text.lines().peekable(),
+            line: 0,
+        }
+    }
+
+    /// Skips comments and empty lines.
+    fn skip(&mut self) {
+        while let Some(line) = self.lines.peek() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                self.line += 1;
+                self.lines.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Next line, if any.
+    fn peek(&mut self) -> Option<&'s str> {
+        self.skip();
+        self.lines.peek().cloned()
+    }
+
+    /// Parses a `<key><sep><value>` line, yields the value.
+    fn field(&mut self, key: &str, sep: &str) -> Res<&'s str> {
+        let line = self
+            .next()
+            .ok_or_else(|| format!("unexpected end of file, expected `{}{}...`", key, sep))?;
+        match split2(line, sep) {
+            Some((k, value)) if k == key => Ok(value.trim()),
+            _ => bail!(
+                "line {}: expected `{}{}...`, found `{}`",
+                self.line,
+                key,
+                sep,
+                line
+            ),
+        }
+    }
+
+    /// Parses a `<key>=<integer>` line, yields the integer.
+    fn int_field(&mut self, key: &str) -> Res<u64> {
+        let value = self.field(key, "=")?;
+        u64::from_str(value).map_err(|_| {
+            format!(
+                "line {}: expected an integer for `{}`, found `{}`",
+                self.line, key, value
+            )
+            .into()
+        })
+    }
+}
+
+impl<'s> Iterator for Lines<'s> {
+    type Item = &'s str;
+    fn next(&mut self) -> Option<&'s str> {
+        self.skip();
+        let line = self.lines.next()?;
+        self.line += 1;
+        Some(line)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PROFILE: &str = "\
+desc: --time-unit=ms
+cmd: ./prog 42
+time_unit: ms
+#-----------
+snapshot=0
+#-----------
+time=0
+mem_heap_B=0
+mem_heap_extra_B=0
+mem_stacks_B=0
+heap_tree=empty
+#-----------
+snapshot=1
+#-----------
+time=10
+mem_heap_B=1000
+mem_heap_extra_B=16
+mem_stacks_B=0
+heap_tree=detailed
+n2: 1000 (heap allocation functions) malloc/new/new[], --alloc-fns, etc.
+ n1: 600 0x4005A1: make_list (list.c:5)
+  n0: 600 0x4005D2: main (main.c:20)
+ n0: 400 0x4005E0: ??? (in /usr/lib/libfoo.so)
+#-----------
+snapshot=2
+#-----------
+time=20
+mem_heap_B=1200
+mem_heap_extra_B=16
+mem_stacks_B=0
+heap_tree=empty
+#-----------
+snapshot=3
+#-----------
+time=30
+mem_heap_B=900
+mem_heap_extra_B=16
+mem_stacks_B=0
+heap_tree=peak
+n2: 900 (heap allocation functions) malloc/new/new[], --alloc-fns, etc.
+ n1: 800 0x4005A1: make_list (list.c:5)
+  n0: 800 0x4005D2: main (main.c:20)
+ n0: 100 in 2 places, below massif's threshold (1.00%)
+#-----------
+snapshot=4
+#-----------
+time=40
+mem_heap_B=500
+mem_heap_extra_B=16
+mem_stacks_B=0
+heap_tree=detailed
+n1: 500 (heap allocation functions) malloc/new/new[], --alloc-fns, etc.
+ n1: 500 0x4005A1: make_list (list.c:5)
+  n0: 500 0x4005D2: main (main.c:20)
+";
+
+    #[test]
+    fn parse() {
+        let profile = Profile::parse(PROFILE).unwrap();
+        assert_eq!(profile.desc, "--time-unit=ms");
+        assert_eq!(profile.cmd, "./prog 42");
+        assert_eq!(profile.time_unit, "ms");
+        assert_eq!(profile.snapshots.len(), 5);
+        assert!(profile.snapshots[2].tree.is_none());
+
+        let tree = profile.snapshots[3].tree.as_ref().unwrap();
+        assert_eq!(tree.bytes, 900);
+        assert!(tree.frame.is_none());
+        assert_eq!(tree.children.len(), 2);
+        assert_eq!(tree.children[0].frame.as_ref().unwrap().fun, "make_list");
+        assert_eq!(
+            tree.children[0].children[0].frame.as_ref().unwrap().fun,
+            "main"
+        );
+        assert!(tree.children[1].frame.is_none());
+
+        let sites: Vec<_> = tree
+            .sites()
+            .into_iter()
+            .map(|(site, size)| (site.frames.len(), site.below_threshold, size))
+            .collect();
+        assert_eq!(sites, vec![(0, true, 100), (2, false, 800)]);
+
+        let err = Profile::parse("desc: (none)\ncmd: ./prog\ntime_unit: i\nsnapshot=0\ntime=x\n");
+        assert!(err.is_err());
+        let err = Profile::parse("desc: (none)\ntime_unit: i\n");
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn replay() {
+        let profile = Profile::parse(PROFILE).unwrap();
+        let mut factory = data::FullFactory::with_data(false, Box::new(data::Data::new()));
+        factory.data.reset("test.massif", profile.init());
+        let mut replay = Replay::new();
+        for snapshot in &profile.snapshots {
+            replay.snapshot(&mut factory, snapshot).unwrap()
+        }
+        let data = factory.into_data();
+
+        let millis = |ms: u64| time::SinceStart::from(time::Duration::from_millis(ms));
+        let allocs: Vec<_> = data
+            .iter_allocs()
+            .map(|alloc| (alloc.real_size, alloc.toc, alloc.tod))
+            .collect();
+        assert_eq!(
+            allocs,
+            vec![
+                // `make_list`, dies when `make_list` shrinks at 40 since newer allocations are
+                // too small.
+                (600, millis(10), Some(millis(40))),
+                // Library, absent at 30.
+                (400, millis(10), Some(millis(30))),
+                // Unattributed growth of the non-detailed snapshot.
+                (200, millis(20), Some(millis(30))),
+                // Below the threshold.
+                (100, millis(30), Some(millis(40))),
+                // `make_list` grows.
+                (200, millis(30), Some(millis(40))),
+                // `make_list` shrinks.
+                (500, millis(40), None),
+            ]
+        );
+
+        let labels: Vec<Vec<String>> = data
+            .iter_allocs()
+            .map(|alloc| {
+                let labels = alloc.shared_labels();
+                labels.get().iter().map(|label| label.to_string()).collect()
+            })
+            .collect();
+        assert_eq!(labels[0], vec!["make_list", "main"]);
+        assert!(labels[1].is_empty());
+        assert!(labels[2].is_empty());
+        assert_eq!(labels[5], vec!["make_list", "main"]);
+    }
+}
@@ -0,0 +1,437 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Pruning of the allocations that died a long time ago.
+//!
+//! Keeping all the allocations of a multi-hour trace takes a lot of memory. With a pruning
+//! [horizon], allocations that have been dead for longer than the horizon are dropped. Allocations
+//! with the same attributes (kind, size, callstack, labels...) share a key, and [`Pruned`] only
+//! remembers the key and the times of the pruned allocations: consecutive creations of a key at the
+//! same time are folded in a [`Run`], and deaths in a number of deaths per key and time of death.
+//!
+//! Pruned allocations are replayed as [`Change`]s between the events of the other allocations, in
+//! the same order, see [`Data::iter_changes_after`]. Keys do not depend on the filters: charts
+//! match the key of each pruned change against their own filters, see [`Lines`]. So charts yield
+//! the same points with and without pruning, and editing a filter moves pruned allocations like
+//! the other ones. Pruned changes use the same cursors as the events, pruning does not reload any
+//! chart.
+//!
+//! Charts that need individual allocations or promotions cannot replay pruned allocations, see
+//! [`YAxis::replays_pruned`], and neither can [time-dependent] filters. While pruning is enabled,
+//! the server refuses them, along with explanations, allocation tables and snapshot diffs.
+//!
+//! [horizon]: fn.horizon.html (The horizon function)
+//! [`Pruned`]: struct.Pruned.html (The Pruned struct)
+//! [`Run`]: struct.Run.html (The Run struct)
+//! [`Change`]: struct.Change.html (The Change struct)
+//! [`Lines`]: struct.Lines.html (The Lines struct)
+//! [`Data::iter_changes_after`]: ../struct.Data.html#method.iter_changes_after
+//! (The iter_changes_after method of Data)
+//! [`YAxis::replays_pruned`]: ../../chart/axis/enum.YAxis.html#method.replays_pruned
+//! (The replays_pruned method of YAxis)
+//! [time-dependent]: ../../filter/struct.Filter.html#method.is_time_dependent
+//! (The is_time_dependent method of Filter)
+
+prelude! {}
+
+/// Number of times pruning runs per horizon of trace time, at most.
+pub const RUNS_PER_HORIZON: u32 = 4;
+
+/// Pruning horizon in milliseconds, `0` if pruning is disabled.
+static HORIZON_MILLIS: sync::atomic::AtomicU64 = sync::atomic::AtomicU64::new(0);
+
+/// Pruning horizon, `None` if pruning is disabled.
+pub fn horizon() -> Option<time::SinceStart> {
+    match HORIZON_MILLIS.load(sync::atomic::Ordering::Relaxed) {
+        0 => None,
+        millis => Some(time::Duration::from_millis(millis).into()),
+    }
+}
+
+/// Sets the pruning horizon, `None` disables pruning.
+///
+/// Horizons are rounded to the millisecond, a horizon of less than a millisecond disables pruning.
+pub fn set_horizon(horizon: Option<time::SinceStart>) {
+    let millis = horizon
+        .map(|horizon| horizon.as_millis() as u64)
+        .unwrap_or(0);
+    HORIZON_MILLIS.store(millis, sync::atomic::Ordering::Relaxed)
+}
+
+/// True if pruning is enabled.
+pub fn is_enabled() -> bool {
+    horizon().is_some()
+}
+
+/// Attributes of an allocation, everything but its UID and its times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    /// Allocation kind.
+    kind: alloc::AllocKind,
+    /// Size.
+    size: u32,
+    /// Real size.
+    real_size: u32,
+    /// Sample count.
+    nsamples: u32,
+    /// Allocation-site callstack.
+    trace: alloc::Trace,
+    /// Labels.
+    labels: alloc::Labels,
+    /// True if some of the locations of the callstack could not be resolved.
+    unresolved_locs: bool,
+}
+impl Key {
+    /// Key of an allocation.
+    fn of_alloc(alloc: &Alloc) -> Self {
+        Self {
+            kind: alloc.kind,
+            size: alloc.size,
+            real_size: alloc.real_size,
+            nsamples: alloc.nsamples,
+            trace: alloc.shared_trace(),
+            labels: alloc.shared_labels(),
+            unresolved_locs: alloc.unresolved_locs,
+        }
+    }
+}
+
+/// Pruned allocations with consecutive UIDs, created at the same time and with the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Run {
+    /// Key of the allocations, see [`Pruned::template`].
+    ///
+    /// [`Pruned::template`]: struct.Pruned.html#method.template (The template method of Pruned)
+    pub key: usize,
+    /// Time of creation.
+    pub toc: time::SinceStart,
+    /// Number of allocations.
+    pub len: usize,
+}
+
+/// The creation or death of some allocations of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Change {
+    /// Time of the change.
+    pub time: time::SinceStart,
+    /// Line of the allocations.
+    pub line: uid::Line,
+    /// True for creations, false for deaths.
+    pub add: bool,
+    /// Number of allocations.
+    pub count: u64,
+    /// Number of bytes.
+    pub bytes: u64,
+    /// Cursor after the change: the UID of the last allocation created, or the time of death.
+    pub last: Either<uid::Alloc, time::SinceStart>,
+}
+impl Change {
+    /// Change corresponding to an event.
+    pub fn of_event(event: &Either<&Alloc, (time::SinceStart, &Alloc)>, line: uid::Line) -> Self {
+        let (time, last, alloc) = event.as_ref().either(
+            |alloc| (alloc.toc, Either::Left(alloc.uid), *alloc),
+            |(tod, alloc)| (*tod, Either::Right(*tod), *alloc),
+        );
+        Self {
+            time,
+            line,
+            add: last.is_left(),
+            count: 1,
+            bytes: alloc.real_size as u64,
+            last,
+        }
+    }
+
+    /// True if the change comes strictly before another one, in the order of the events.
+    ///
+    /// Creations come in the order of their UIDs, and before the deaths of the same time. Deaths of
+    /// the same time are not ordered.
+    pub fn precedes(&self, other: &Self) -> bool {
+        match (self.last, other.last) {
+            (Either::Left(uid), Either::Left(other_uid)) => uid < other_uid,
+            (Either::Left(_), Either::Right(tod)) => self.time <= tod,
+            (Either::Right(tod), Either::Left(_)) => tod < other.time,
+            (Either::Right(tod), Either::Right(other_tod)) => tod < other_tod,
+        }
+    }
+}
+
+/// Pruned allocations.
+#[derive(Debug, Clone, Default)]
+pub struct Pruned {
+    /// Allocation with the attributes of each key, by key.
+    templates: Vec<Alloc>,
+    /// Keys, by attributes.
+    keys: HMap<Key, usize>,
+    /// Runs of creations, by UID of their first allocation.
+    created: BTMap<uid::Alloc, Run>,
+    /// Number of deaths of each key, by time of death.
+    freed: BTMap<time::SinceStart, BTMap<usize, usize>>,
+    /// Number of allocations pruned.
+    count: usize,
+    /// Changes each time some allocations are pruned.
+    epoch: u64,
+    /// Time of the last pruning, if any.
+    last_run: Option<time::SinceStart>,
+}
+
+impl Pruned {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of allocations pruned.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Changes each time some allocations are pruned.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Time of the last death, if any.
+    pub fn last_tod(&self) -> Option<time::SinceStart> {
+        self.freed.keys().next_back().cloned()
+    }
+
+    /// An allocation with the attributes of a key.
+    ///
+    /// Only its attributes are meaningful, its UID and times are the ones of the first allocation
+    /// pruned with this key.
+    pub fn template(&self, key: usize) -> &Alloc {
+        &self.templates[key]
+    }
+
+    /// Changes after some allocation and some TOD, in chronological order, with the key of their
+    /// allocations.
+    ///
+    /// The cursors are the same as the ones of [`Data::iter_events_after`]. The line of the changes
+    /// is the catch-all line, callers get the actual line from the key, see [`Lines`].
+    ///
+    /// [`Data::iter_events_after`]: ../struct.Data.html#method.iter_events_after
+    /// (The iter_events_after method of Data)
+    /// [`Lines`]: struct.Lines.html (The Lines struct)
+    pub fn changes_after(
+        &self,
+        (last_alloc, last_tod): (Option<uid::Alloc>, Option<time::SinceStart>),
+    ) -> impl Iterator<Item = (usize, Change)> + '_ {
+        use std::ops::Bound::{Excluded, Unbounded};
+
+        // The run of the allocation after `last_alloc` may start before it.
+        let partial = last_alloc.and_then(|last| {
+            let (first, run) = self.created.range(..=last).next_back()?;
+            let end = first.get() + run.len;
+            if last.get() + 1 < end {
+                let len = end - last.get() - 1;
+                Some((uid::Alloc::new(end - 1), Run { len, ..*run }))
+            } else {
+                None
+            }
+        });
+        let start = last_alloc.map(Excluded).unwrap_or(Unbounded);
+        let runs = self
+            .created
+            .range((start, Unbounded))
+            .map(|(first, run)| (uid::Alloc::new(first.get() + run.len - 1), *run));
+        let mut runs = partial.into_iter().chain(runs).peekable();
+
+        let start = last_tod.map(Excluded).unwrap_or(Unbounded);
+        let mut deaths = self
+            .freed
+            .range((start, Unbounded))
+            .flat_map(|(tod, counts)| counts.iter().map(move |(key, count)| (*tod, *key, *count)))
+            .peekable();
+
+        std::iter::from_fn(move || {
+            let creation = match (runs.peek(), deaths.peek()) {
+                (Some((_, run)), Some((tod, _, _))) => run.toc <= *tod,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return None,
+            };
+            let (key, time, add, count, last) = if creation {
+                let (last, run) = runs.next()?;
+                (run.key, run.toc, true, run.len, Either::Left(last))
+            } else {
+                let (tod, key, count) = deaths.next()?;
+                (key, tod, false, count, Either::Right(tod))
+            };
+            let count = count as u64;
+            let change = Change {
+                time,
+                line: uid::Line::CatchAll,
+                add,
+                count,
+                bytes: count * self.templates[key].real_size as u64,
+                last,
+            };
+            Some((key, change))
+        })
+    }
+
+    /// Time up to which the allocations should die to be pruned, if pruning should run now.
+    pub fn limit(&self, now: time::SinceStart) -> Option<time::SinceStart> {
+        let horizon = horizon()?;
+        if now < horizon {
+            return None;
+        }
+        match self.last_run {
+            Some(last_run) if now - last_run < horizon / RUNS_PER_HORIZON => None,
+            _ => Some(now - horizon),
+        }
+    }
+
+    /// Folds a dead allocation.
+    ///
+    /// Allocations are folded in the order of their UIDs, so that consecutive allocations end up
+    /// in the same run.
+    pub fn add(&mut self, alloc: &Alloc) -> Res<()> {
+        let tod = alloc
+            .tod
+            .ok_or_else(|| format!("cannot prune live allocation #{}", alloc.uid))?;
+
+        let templates = &mut self.templates;
+        let key = *self.keys.entry(Key::of_alloc(alloc)).or_insert_with(|| {
+            templates.push(alloc.clone());
+            templates.len() - 1
+        });
+
+        let extends = self
+            .created
+            .range_mut(..alloc.uid)
+            .next_back()
+            .filter(|(first, run)| {
+                first.get() + run.len == alloc.uid.get() && run.key == key && run.toc == alloc.toc
+            });
+        if let Some((_, run)) = extends {
+            run.len += 1
+        } else {
+            let run = Run {
+                key,
+                toc: alloc.toc,
+                len: 1,
+            };
+            let _ = self.created.insert(alloc.uid, run);
+        }
+
+        *self
+            .freed
+            .entry(tod)
+            .or_insert_with(BTMap::new)
+            .entry(key)
+            .or_insert(0) += 1;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Registers a pruning that dropped `count` allocations.
+    pub fn ran(&mut self, now: time::SinceStart, count: usize) {
+        self.last_run = Some(now);
+        if count > 0 {
+            self.epoch += 1
+        }
+    }
+}
+
+/// Lines of the keys of the pruned allocations for some filters, computed lazily.
+///
+/// Filters must not change while the lines are in use.
+#[derive(Debug, Clone, Default)]
+pub struct Lines {
+    /// Line of the keys computed so far.
+    lines: HMap<usize, uid::Line>,
+}
+impl Lines {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Line of a key of the pruned allocations of some data.
+    pub fn get(&mut self, data: &data::Data, filters: &filter::Filters, key: usize) -> uid::Line {
+        *self.lines.entry(key).or_insert_with(|| {
+            let template = data.pruned().template(key);
+            filters
+                .matching_uncached(data.current_time(), template)
+                .map(uid::Line::Filter)
+                .unwrap_or(uid::Line::CatchAll)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn runs_resume_in_the_middle() {
+        let millis = crate::fixture::millis;
+        let (trace, labels) = (crate::fixture::trace(&[]), crate::fixture::labels(&[]));
+        let alloc = |uid, size, toc, tod| {
+            let mut alloc =
+                crate::fixture::alloc(uid, alloc::AllocKind::Minor, size, &trace, &labels, toc);
+            alloc.tod = Some(millis(tod));
+            alloc
+        };
+
+        // Four allocations created at the same time with the same key, then one of another key.
+        let mut pruned = Pruned::new();
+        for uid in 0..4 {
+            pruned.add(&alloc(uid, 8, 1, 5 + uid % 2)).unwrap();
+        }
+        pruned.add(&alloc(4, 16, 3, 5)).unwrap();
+        assert_eq!(pruned.count(), 5);
+        assert_eq!(pruned.created.len(), 2);
+
+        let changes = |cursors| -> Vec<(bool, u64, Either<uid::Alloc, time::SinceStart>)> {
+            pruned
+                .changes_after(cursors)
+                .map(|(_, change)| (change.add, change.bytes, change.last))
+                .collect()
+        };
+        let (new, dead) = (
+            |uid| Either::Left(uid::Alloc::new(uid)),
+            |tod| Either::Right(millis(tod)),
+        );
+        let size = |key| pruned.template(key).real_size as u64;
+        let (small, big) = (size(0), size(1));
+        assert_eq!(
+            changes((None, None)),
+            vec![
+                (true, 4 * small, new(3)),
+                (true, big, new(4)),
+                (false, 2 * small, dead(5)),
+                (false, big, dead(5)),
+                (false, 2 * small, dead(6)),
+            ]
+        );
+
+        // Cursors in the middle of the run and after the first deaths.
+        assert_eq!(
+            changes((Some(uid::Alloc::new(1)), Some(millis(5)))),
+            vec![
+                (true, 2 * small, new(3)),
+                (true, big, new(4)),
+                (false, 2 * small, dead(6)),
+            ]
+        );
+    }
+}
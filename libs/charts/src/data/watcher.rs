@@ -24,6 +24,7 @@ prelude! {}
 use std::{
     ffi::OsString,
     path::{Path, PathBuf},
+    task::Poll,
     thread::sleep,
     time::{Duration, SystemTime},
 };
@@ -60,10 +61,16 @@ impl Watcher {
         let path = target.as_ref();
 
         if path.is_file() {
-            let path = path.display().to_string();
-            let _ = std::thread::spawn(move || match Self::ctf_run(path) {
-                Ok(()) => (),
-                Err(e) => err::register_fatal(e),
+            let format = super::input_format().of(path);
+            let path = path.to_path_buf();
+            let _ = std::thread::spawn(move || {
+                let res = match format {
+                    super::InputFormat::Massif => Self::massif_run(path),
+                    _ => Self::ctf_run(path),
+                };
+                if let Err(e) = res {
+                    err::register_fatal(e)
+                }
             });
         } else if path.is_dir() {
             let mut watcher = Self::new(target);
@@ -84,7 +91,7 @@ impl Watcher {
             });
         } else {
             err::register_fatal(format!(
-                "expected dump directory or memtrace CTF file\n\
+                "expected dump directory, memtrace CTF file or massif profile\n\
                 got `{}` which is neither or a file nor a directory",
                 path.display()
             ))
@@ -92,78 +99,274 @@ impl Watcher {
     }
 
     /// Runs on a memtrace CTF file.
+    ///
+    /// Waits for the trace info if the file does not have it yet, and fails if it does not show up
+    /// within a minute.
+    ///
+    /// Only loads the allocations of some of the packets if the packet sampling is not `1`, see
+    /// [`set_packet_sampling`]. Otherwise, keeps following the file once the part available at
+    /// startup is loaded, so that the packets of a trace still being written show up live.
+    ///
+    /// Loads the [cache] of the file instead, if the file has a valid one. Full loads are cached.
+    ///
+    /// Stops following the file once the data is replaced, see [`load_trace`].
+    ///
+    /// [`load_trace`]: ../fn.load_trace.html (The load_trace function)
+    /// [`set_packet_sampling`]: ../fn.set_packet_sampling.html (The set_packet_sampling function)
+    /// [cache]: ../cache/index.html (The cache module)
     pub fn ctf_run(target: impl AsRef<Path>) -> Res<()> {
-        base::new_time_stats! {
-            struct Prof {
-                total => "total",
-                load => "loading",
-                parse => "parsing",
-            }
+        let target = target.as_ref();
+        if super::cache::is_enabled() && Self::ctf_load_cache(target)? {
+            return Ok(());
         }
-        let mut prof = Prof::new();
-        prof.total.start();
 
-        let target = target.as_ref();
+        let sampling = super::packet_sampling();
+        let mut loader = CtfLoader::new(target, sampling)?;
 
         log::info!("loading ctf file `{}`", target.display());
 
-        prof.load.start();
-        let bytes = {
-            use std::io::Read;
-            let mut file = std::fs::OpenOptions::new()
-                .read(true)
-                .open(target)
-                .chain_err(|| format!("while opening ctf file `{}`", target.display()))?;
-            let len = file
-                .metadata()
-                .map(|meta| meta.len() as usize)
-                .unwrap_or(150_000);
-            let mut buff = Vec::with_capacity(len);
-            let data_len = file
-                .read_to_end(&mut buff)
-                .chain_err(|| format!("while reading ctf file `{}`", target.display()))?;
-            super::progress::set_total(data_len)?;
-            buff
+        let init = loader.wait_init()?;
+        let generation = {
+            let mut data = super::get_mut().chain_err(|| "while registering the initial state")?;
+            if data.has_init() {
+                bail!("live profiling restart is not supported yet")
+            }
+            data.reset(target, init);
+            data.stats_do(|stats| stats.estimate = Estimate::new(sampling));
+            data.generation()
         };
-        prof.load.stop();
-
-        let mut factory = data::FullFactory::new(false);
-        prof.parse.start();
-        ctf::parse(
-            &bytes,
-            &mut factory,
-            |bytes_progress| {
-                err::unwrap_register_fatal(super::progress::set_loaded(bytes_progress))
-            },
-            |factory, init| {
-                if factory.data.has_init() {
-                    panic!("live profiling restart is not supported yet")
-                } else {
-                    factory.data.reset(target, init)
+
+        super::progress::set_total_bytes(loader.file_len()?)?;
+        // True while loading the part of the file available at startup.
+        let mut loading = true;
+        // Number of packets parsed at the last progress report.
+        let mut reported = 0;
+
+        loop {
+            let at_end = loader.parse()?;
+            if loader.has_packets() {
+                // Only lock the data when there is something to load.
+                let mut factory = data::FullFactory::new(false);
+                if factory.data.generation() != generation {
+                    log::info!(
+                        "data replaced, stop following ctf file `{}`",
+                        target.display()
+                    );
+                    if loading {
+                        super::progress::set_done()?
+                    }
+                    break Ok(());
+                }
+                loader.load(&mut factory)?;
+                factory.fill_stats()?;
+            }
+            if loading && loader.parsed() - reported >= super::progress::every_packets() {
+                reported = loader.parsed();
+                super::progress::set_loaded(loader.consumed())?;
+            }
+
+            if at_end {
+                if loading {
+                    loading = false;
+                    super::progress::set_done()?;
+                    loader.log_prof();
+                    log::info!("done loading ctf file `{}`", target.display());
+                    if sampling == 1 {
+                        log::info!(
+                            "following ctf file `{}`, new packets will show up live",
+                            target.display()
+                        )
+                    }
+                    // Only full loads are cached.
+                    if sampling == 1 {
+                        super::cache::spawn_write(target, loader.consumed() as u64)
+                    }
+                }
+                // When sampling, the data is replaced by a full load of the file, see
+                // `spawn_load_all`. Following the file would load the new packets in the old data.
+                if sampling > 1 {
+                    break Ok(());
                 }
-            },
-            |factory, builder| err::unwrap_register_fatal(factory.build_new(builder)),
-            |factory, timestamp, uid| err::unwrap_register_fatal(factory.add_dead(timestamp, uid)),
-            |factory, timestamp| factory.mark_timestamp(timestamp),
-        )
-        .chain_err(|| format!("while parsing ctf file `{}`", target.display()))?;
-        prof.parse.stop();
+                sleep(Duration::from_millis(200))
+            }
+        }
+    }
+
+    /// Runs on a massif profile, see the [`massif`] module.
+    ///
+    /// The file is not followed, massif writes its profile when the program exits.
+    ///
+    /// [`massif`]: ../massif/index.html (The massif module)
+    pub fn massif_run(target: impl AsRef<Path>) -> Res<()> {
+        let target = target.as_ref();
+        let loaded = super::massif::load(target)?;
+        {
+            let mut data = super::get_mut().chain_err(|| "while registering the massif data")?;
+            if data.has_init() {
+                bail!("live profiling restart is not supported yet")
+            }
+            *data = loaded;
+        }
+        super::progress::set_done()
+    }
 
-        factory.fill_stats()?;
+    /// Spawns a thread replacing the data with a full load of a trace, see [`load_trace`].
+    ///
+    /// [`load_trace`]: ../fn.load_trace.html (The load_trace function)
+    pub fn spawn_switch(target: PathBuf) {
+        let _ = std::thread::spawn(move || {
+            if let Err(e) = Self::switch(target) {
+                err::register_non_fatal(e.to_pretty())
+            }
+        });
+    }
 
+    /// Replaces the data with a full load of a trace, reports the progress of the load.
+    ///
+    /// Trace loads are serialized. The data is left untouched if loading fails.
+    fn switch(target: PathBuf) -> Res<()> {
+        let _lock = super::LOADING
+            .lock()
+            .map_err(|e| err::Error::from(e.to_string()))
+            .chain_err(|| format!("while loading trace `{}`", target.display()))?;
+        let res = match super::input_format().of(&target) {
+            super::InputFormat::Massif => Self::massif_switch(target),
+            _ => Self::ctf_switch(target),
+        };
         super::progress::set_done()?;
+        res
+    }
 
-        prof.all_do(
-            || log::info!("done loading ctf file `{}`", target.display()),
-            |desc, sw| log::info!("| {:>9}: {}", desc, sw),
-        );
-        if !Prof::TIME_STATS_ACTIVE {
-            log::info!("done loading ctf file `{}`", target.display());
+    /// Replaces the data with a massif profile, see [`load_trace`].
+    ///
+    /// The data is left untouched if loading fails.
+    ///
+    /// [`load_trace`]: ../fn.load_trace.html (The load_trace function)
+    fn massif_switch(target: PathBuf) -> Res<()> {
+        super::progress::set_unknown()?;
+        let loaded = super::massif::load(&target)?;
+        let previous = super::get_mut()?.replace(loaded);
+        drop(previous);
+        log::info!("switched to massif file `{}`", target.display());
+        Ok(())
+    }
+
+    /// Loads the data of a CTF file from its cache, see the [`cache`] module.
+    ///
+    /// Returns false if the file has no valid cache. The file is not followed: a valid cache means
+    /// the file did not change since the cache was written.
+    ///
+    /// [`cache`]: ../cache/index.html (The cache module)
+    fn ctf_load_cache(target: &Path) -> Res<bool> {
+        let (mut cached, size) = match super::cache::load(target) {
+            Ok(Some(res)) => res,
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                log::warn!(
+                    "could not load the cache of ctf file `{}`",
+                    target.display()
+                );
+                log::warn!("{}", e.to_pretty());
+                return Ok(false);
+            }
+        };
+        cached.stats_do(|stats| stats.cache = Some(CacheStatus::Loaded(size)));
+        {
+            let mut data = super::get_mut().chain_err(|| "while registering the cached data")?;
+            if data.has_init() {
+                bail!("live profiling restart is not supported yet")
+            }
+            *data = cached;
         }
+        super::progress::set_done()?;
+        log::info!("loaded ctf file `{}` from its cache", target.display());
+        Ok(true)
+    }
+
+    /// Spawns a thread loading all the allocations of a CTF file, see [`load_all`].
+    ///
+    /// [`load_all`]: ../fn.load_all.html (The load_all function)
+    pub fn spawn_load_all(target: PathBuf) {
+        let _ = std::thread::spawn(move || {
+            let res = Self::ctf_load_all(&target, false).and_then(|(data, len)| {
+                super::replace_with_full_load(data)?;
+                super::cache::spawn_write(target.clone(), len);
+                Ok(())
+            });
+            if let Err(e) = res {
+                // Lets users try again.
+                if let Ok(mut data) = super::get_mut() {
+                    data.stats_do(|stats| {
+                        if let Some(estimate) = stats.estimate.as_mut() {
+                            estimate.loading_all = false
+                        }
+                    })
+                }
+                err::register_non_fatal(e.to_pretty())
+            }
+        });
+    }
 
+    /// Replaces the data with a full load of a CTF file, see [`load_trace`].
+    ///
+    /// The data is left untouched if loading fails.
+    ///
+    /// [`load_trace`]: ../fn.load_trace.html (The load_trace function)
+    fn ctf_switch(target: PathBuf) -> Res<()> {
+        let (data, len) = Self::ctf_load_all(&target, true)?;
+        let previous = super::get_mut()?.replace(data);
+        drop(previous);
+        log::info!("switched to ctf file `{}`", target.display());
+        super::cache::spawn_write(target, len);
         Ok(())
     }
 
+    /// Loads all the allocations of the part of a CTF file available, in some new data.
+    ///
+    /// Also yields the number of bytes of the file loaded. Does not lock the global data, and only
+    /// locks the allocation-data factories while loading the packets parsed. Reports the progress
+    /// of the load if `report` is true.
+    fn ctf_load_all(target: &Path, report: bool) -> Res<(data::Data, u64)> {
+        let mut loader = CtfLoader::new(target, 1)?;
+        if report {
+            super::progress::set_total_bytes(loader.file_len()?)?
+        }
+        let mut reported = 0;
+
+        log::info!("loading all allocations of ctf file `{}`", target.display());
+
+        let init = loader.poll_init()?.ok_or_else(|| {
+            format!(
+                "ctf file `{}` ends before the end of its trace info",
+                target.display()
+            )
+        })?;
+        let mut data = data::Data::new();
+        data.reset(target, init);
+        data.stats_do(|stats| stats.estimate = Estimate::new(1));
+
+        loop {
+            let at_end = loader.parse()?;
+            loader.load(&mut data::FullFactory::with_data(false, &mut data))?;
+            if at_end {
+                break;
+            }
+            if report && loader.parsed() - reported >= super::progress::every_packets() {
+                reported = loader.parsed();
+                super::progress::set_loaded(loader.consumed())?
+            }
+        }
+        data.fill_stats()?;
+
+        loader.log_prof();
+        log::info!(
+            "done loading all allocations of ctf file `{}`",
+            target.display()
+        );
+
+        Ok((data, loader.consumed() as u64))
+    }
+
     /// Runs the watcher.
     pub fn run(&mut self, forever: bool) -> Res<()> {
         crate::data::progress::set_unknown()?;
@@ -550,3 +753,246 @@ impl Watcher {
         .chain_err(|| format!("while reading content of file `{}`", path.to_string_lossy()))
     }
 }
+
+/// Number of CTF packets parsed before loading them.
+const CTF_BATCH_LEN: usize = 64;
+/// Time to wait for the trace info of a CTF file to be written, in seconds.
+///
+/// Memtrace writes the trace info as soon as tracing starts, a file that stays without one for
+/// that long is not a trace being written.
+const CTF_INIT_TIMEOUT_SECS: u64 = 60;
+/// Time between two reports while waiting for the trace info of a CTF file, in seconds.
+const CTF_INIT_REPORT_SECS: u64 = 5;
+
+/// Incremental loader for a memtrace CTF file.
+///
+/// Parses the packets of the file as they become available, see [`ctf::StreamParser`], and loads
+/// them in some data. A trailing incomplete packet is loaded once the rest of it is written.
+///
+/// [`ctf::StreamParser`]: ../../../ctf/stream/struct.StreamParser.html
+/// (The StreamParser struct)
+struct CtfLoader {
+    /// Path to the CTF file.
+    target: PathBuf,
+    /// Packet parser.
+    stream: ctf::StreamParser<std::fs::File>,
+    /// Packet decoder, available once the trace info is parsed.
+    decoder: Option<ctf::Decoder>,
+    /// Packet sampling.
+    sampling: usize,
+    /// Maps the UIDs of the allocations in the trace to their UIDs in the data.
+    ///
+    /// Only used when sampling since the UIDs of the trace are not consecutive anymore.
+    uid_map: std::cell::RefCell<Option<BTMap<uid::Alloc, uid::Alloc>>>,
+    /// Packets parsed but not loaded yet.
+    packets: Vec<ctf::stream::Packet>,
+    /// Number of packets parsed.
+    parsed: usize,
+}
+
+impl CtfLoader {
+    /// Constructor.
+    fn new(target: &Path, sampling: usize) -> Res<Self> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .open(target)
+            .chain_err(|| format!("while opening ctf file `{}`", target.display()))?;
+        Ok(Self {
+            target: target.into(),
+            stream: ctf::StreamParser::new(file),
+            decoder: None,
+            sampling,
+            uid_map: std::cell::RefCell::new(if sampling > 1 {
+                Some(BTMap::new())
+            } else {
+                None
+            }),
+            packets: Vec::with_capacity(CTF_BATCH_LEN),
+            parsed: 0,
+        })
+    }
+
+    /// Current size of the CTF file.
+    fn file_len(&self) -> Res<usize> {
+        let meta = std::fs::metadata(&self.target).chain_err(|| {
+            format!(
+                "could not retrieve metadata of ctf file `{}`",
+                self.target.display()
+            )
+        })?;
+        Ok(meta.len() as usize)
+    }
+
+    /// Number of bytes of the file parsed.
+    fn consumed(&self) -> usize {
+        self.stream.consumed()
+    }
+    /// Number of packets parsed.
+    fn parsed(&self) -> usize {
+        self.parsed
+    }
+
+    /// True if some packets are parsed but not loaded yet.
+    fn has_packets(&self) -> bool {
+        !self.packets.is_empty()
+    }
+
+    /// Logs the profiling information of the decoder, if any.
+    fn log_prof(&self) {
+        if let Some(decoder) = self.decoder.as_ref() {
+            decoder.log_prof()
+        }
+    }
+
+    /// Parses the init info of the trace, `None` if it is not completely written yet.
+    fn poll_init(&mut self) -> Res<Option<alloc::Init>> {
+        let init = self
+            .stream
+            .poll_init()
+            .chain_err(|| format!("while parsing ctf file `{}`", self.target.display()))?;
+        match init {
+            Poll::Pending => Ok(None),
+            Poll::Ready(init) => {
+                let header = self
+                    .stream
+                    .header()
+                    .ok_or("[ctf loader] no ctf header after parsing the trace info")?;
+                self.decoder = Some(ctf::Decoder::new(header, super::is_strict(), self.sampling));
+                Ok(Some(init))
+            }
+        }
+    }
+
+    /// Waits for the init info of the trace to be written, and parses it.
+    ///
+    /// Reports its progress every [`CTF_INIT_REPORT_SECS`] seconds, fails after
+    /// [`CTF_INIT_TIMEOUT_SECS`] seconds.
+    ///
+    /// [`CTF_INIT_REPORT_SECS`]: constant.CTF_INIT_REPORT_SECS.html
+    /// (The CTF_INIT_REPORT_SECS constant)
+    /// [`CTF_INIT_TIMEOUT_SECS`]: constant.CTF_INIT_TIMEOUT_SECS.html
+    /// (The CTF_INIT_TIMEOUT_SECS constant)
+    fn wait_init(&mut self) -> Res<alloc::Init> {
+        let start = std::time::Instant::now();
+        let mut reported = 0;
+        loop {
+            if let Some(init) = self.poll_init()? {
+                return Ok(init);
+            }
+            let waited = start.elapsed().as_secs();
+            if waited >= CTF_INIT_TIMEOUT_SECS {
+                bail!(
+                    "ctf file `{}` has no complete trace info after {} seconds ({} bytes)",
+                    self.target.display(),
+                    waited,
+                    self.file_len()?,
+                )
+            } else if waited >= reported + CTF_INIT_REPORT_SECS {
+                reported = waited;
+                log::info!(
+                    "waiting for the trace info of ctf file `{}` ({} bytes, {}s elapsed)",
+                    self.target.display(),
+                    self.file_len()?,
+                    waited,
+                )
+            }
+            sleep(Duration::from_millis(200))
+        }
+    }
+
+    /// Parses packets, yields true if it reached the end of what is written.
+    fn parse(&mut self) -> Res<bool> {
+        while self.packets.len() < CTF_BATCH_LEN {
+            let packet = self
+                .stream
+                .poll_packet()
+                .chain_err(|| format!("while parsing ctf file `{}`", self.target.display()))?;
+            match packet {
+                Poll::Ready(packet) => {
+                    self.parsed += 1;
+                    self.packets.push(packet)
+                }
+                Poll::Pending => return Ok(true),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Loads the packets parsed so far.
+    ///
+    /// Fails on the first allocation, death or promotion the data rejects.
+    fn load<'a, D>(&mut self, factory: &mut data::FullFactory<'a, D>) -> Res<()>
+    where
+        D: std::ops::DerefMut<Target = data::Data>,
+    {
+        let Self {
+            target,
+            decoder,
+            uid_map,
+            packets,
+            ..
+        } = self;
+        let decoder = decoder
+            .as_mut()
+            .ok_or("[ctf loader] trying to load packets before parsing the trace info")?;
+        // First error of the current packet, the decoder callbacks cannot fail.
+        let error = std::cell::RefCell::new(None);
+        let register = |res: Res<()>| {
+            if let Err(e) = res {
+                error.borrow_mut().get_or_insert(e);
+            }
+        };
+
+        for ctf::stream::Packet { header, events } in packets.drain(0..) {
+            decoder
+                .packet(
+                    factory,
+                    &header,
+                    events.into_iter().map(Ok),
+                    |factory, mut builder| {
+                        if let Some(uid_map) = uid_map.borrow_mut().as_mut() {
+                            if let Some(uid) = builder.uid_hint.take() {
+                                uid_map.insert(uid, factory.data.next_uid());
+                                factory.data.sample_uid(uid)
+                            }
+                        }
+                        register(factory.build_new(builder))
+                    },
+                    |factory, timestamp, uid| {
+                        let uid = match uid_map.borrow_mut().as_mut() {
+                            None => uid,
+                            Some(uid_map) => match uid_map.remove(&uid) {
+                                Some(uid) => uid,
+                                // Not loaded.
+                                None => return,
+                            },
+                        };
+                        register(factory.add_dead(timestamp, uid))
+                    },
+                    |factory, timestamp, uid| {
+                        let uid = match std::cell::RefCell::borrow(uid_map).as_ref() {
+                            None => uid,
+                            Some(uid_map) => match uid_map.get(&uid) {
+                                Some(uid) => *uid,
+                                // Not loaded.
+                                None => return,
+                            },
+                        };
+                        register(factory.add_promotion(timestamp, uid))
+                    },
+                    |factory, timestamp| factory.mark_timestamp(timestamp),
+                    |factory, gap| factory.add_gap(gap),
+                )
+                .chain_err(|| format!("while loading ctf file `{}`", target.display()))?;
+            if let Some(e) = error.borrow_mut().take() {
+                return Err(e)
+                    .chain_err(|| format!("while loading ctf file `{}`", target.display()));
+            }
+        }
+        let skipped_events = decoder.skipped_events();
+        factory
+            .data
+            .stats_do(|stats| stats.skipped_events = skipped_events);
+        Ok(())
+    }
+}
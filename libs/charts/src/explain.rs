@@ -0,0 +1,296 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Explains a time range by the allocation sites that allocate more in it than before it.
+//!
+//! The *baseline* of a range is the window of the same length right before the range. Allocation
+//! sites are ranked by how many more bytes they allocate in the range than in its baseline.
+
+prelude! {}
+
+/// Maximum number of contributors in an explanation.
+pub const MAX_CONTRIBUTORS: usize = 10;
+/// Maximum number of frames in the example callstack of a contributor.
+pub const MAX_FRAMES: usize = 7;
+
+/// Time budget of an explanation, in milliseconds.
+///
+/// Once the budget runs out the explanation is partial: it only accounts for the allocations
+/// scanned so far.
+pub const BUDGET_MS: u64 = 2_000;
+
+/// Subfilter matching the allocations of an allocation site.
+pub fn site_subfilter(file: &str, line: usize) -> filter::sub::RawSubFilter {
+    let pred = filter::string_like::Pred::Contain;
+    let site = filter::loc::LocSpec::Value {
+        value: file.into(),
+        line: filter::loc::LineSpec::line(line),
+    };
+    let loc_spec = vec![filter::loc::LocSpec::Anything, site];
+    filter::loc::LocFilter::new(pred, loc_spec).into()
+}
+
+/// An allocation site allocating more in a range than in its baseline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contributor {
+    /// File of the allocation site.
+    pub file: String,
+    /// Line of the allocation site.
+    pub line: usize,
+    /// Bytes allocated by the site in the range.
+    pub bytes: u64,
+    /// Bytes allocated by the site in the baseline.
+    pub baseline: u64,
+    /// Example callstack of an allocation of the site in the range, allocation site first.
+    pub example: Vec<String>,
+}
+
+impl Contributor {
+    /// Increase in bytes allocated between the baseline and the range.
+    pub fn increase(&self) -> u64 {
+        self.bytes.saturating_sub(self.baseline)
+    }
+
+    /// Relative increase, `None` if the site did not allocate anything in the baseline.
+    ///
+    /// ```rust
+    /// # use charts::explain::Contributor;
+    /// let mut contributor = Contributor {
+    ///     file: "file.ml".into(),
+    ///     line: 7,
+    ///     bytes: 300,
+    ///     baseline: 100,
+    ///     example: vec![],
+    /// };
+    /// assert_eq!(contributor.increase(), 200);
+    /// assert_eq!(contributor.relative_increase(), Some(2.));
+    /// contributor.baseline = 0;
+    /// assert_eq!(contributor.relative_increase(), None);
+    /// ```
+    pub fn relative_increase(&self) -> Option<f64> {
+        if self.baseline == 0 {
+            None
+        } else {
+            Some(self.increase() as f64 / self.baseline as f64)
+        }
+    }
+
+    /// Subfilter matching the allocations of this site.
+    pub fn subfilter(&self) -> filter::sub::RawSubFilter {
+        site_subfilter(&self.file, self.line)
+    }
+
+    /// Ranking order: largest increase first, then largest relative increase.
+    ///
+    /// Sites that did not allocate anything in the baseline have an infinite relative increase.
+    #[cfg(any(test, feature = "server"))]
+    fn rank_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.increase().cmp(&self.increase()).then_with(|| {
+            match (self.relative_increase(), other.relative_increase()) {
+                (None, None) => std::cmp::Ordering::Equal,
+                (None, Some(_)) => std::cmp::Ordering::Less,
+                (Some(_), None) => std::cmp::Ordering::Greater,
+                (Some(lft), Some(rgt)) => {
+                    rgt.partial_cmp(&lft).unwrap_or(std::cmp::Ordering::Equal)
+                }
+            }
+        })
+    }
+}
+
+base::implement! {
+    impl Contributor {
+        Display {
+            |&self, fmt| write!(fmt, "`{}`:{}", self.file, self.line)
+        }
+    }
+}
+
+/// Ranking of the allocation sites explaining a time range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Explanation {
+    /// Range explained.
+    pub range: TimeWindow,
+    /// Baseline the range is compared to.
+    ///
+    /// Shorter than the range if the range starts less than its own length after the beginning of
+    /// the run.
+    pub baseline: TimeWindow,
+    /// Top contributors, largest contributor first.
+    pub contributors: Vec<Contributor>,
+    /// False if the time budget ran out before all the allocations were scanned.
+    pub complete: bool,
+}
+
+#[cfg(any(test, feature = "server"))]
+impl Explanation {
+    /// Explains a range given some data, see the [module-level documentation](index.html).
+    pub fn new(data: &data::Data, range: TimeWindow, budget: time::Duration) -> Self {
+        let start = time::Instant::now();
+
+        let spread: time::Duration = range.spread::<time::SinceStart>().into();
+        let baseline = TimeWindow::new(
+            range
+                .lbound
+                .checked_sub(spread)
+                .map(time::SinceStart::from)
+                .unwrap_or_else(time::SinceStart::zero),
+            range.lbound,
+        );
+
+        // Maps allocation sites to the bytes allocated in the range, in the baseline, and an
+        // example callstack.
+        let mut sites: HMap<alloc::Loc, (u64, u64, Option<Arc<Vec<alloc::CLoc>>>)> = HMap::new();
+        let mut complete = true;
+
+        // Allocations are sorted by time of creation. The ones skipped count in the budget too.
+        for (idx, alloc) in data
+            .iter_allocs_from(baseline.lbound)
+            .take_while(|alloc| alloc.toc <= range.ubound)
+            .enumerate()
+        {
+            if idx % 1024 == 1023 && start.elapsed() > budget {
+                complete = false;
+                break;
+            }
+            if alloc.toc < baseline.lbound {
+                continue;
+            }
+
+            let trace = alloc.trace();
+            let site = match trace.last() {
+                Some(site) => site.loc.clone(),
+                None => continue,
+            };
+            let (bytes, base_bytes, example) = sites.entry(site).or_insert((0, 0, None));
+            if alloc.toc < range.lbound {
+                *base_bytes += alloc.real_size as u64
+            } else {
+                *bytes += alloc.real_size as u64;
+                if example.is_none() {
+                    *example = Some(trace)
+                }
+            }
+        }
+
+        let mut contributors: Vec<_> = sites
+            .into_iter()
+            .filter(|(_, (bytes, baseline, _))| bytes > baseline)
+            .map(|(site, (bytes, baseline, example))| Contributor {
+                file: site.file.to_string(),
+                line: site.line,
+                bytes,
+                baseline,
+                example: example
+                    .map(|trace| {
+                        trace
+                            .iter()
+                            .rev()
+                            .take(MAX_FRAMES)
+                            .map(|cloc| cloc.to_string())
+                            .collect()
+                    })
+                    .unwrap_or_else(Vec::new),
+            })
+            .collect();
+        contributors.sort_by(Contributor::rank_cmp);
+        contributors.truncate(MAX_CONTRIBUTORS);
+
+        Self {
+            range,
+            baseline,
+            contributors,
+            complete,
+        }
+    }
+
+    /// Explains a range using the global data.
+    pub fn of_range(range: TimeWindow) -> Res<Self> {
+        let data = data::get().chain_err(|| "while explaining a time range")?;
+        Ok(Self::new(
+            &data,
+            range,
+            time::Duration::from_millis(BUDGET_MS),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixture::{self, millis};
+
+    /// Builds some data with one allocation per millisecond for 200 milliseconds.
+    ///
+    /// Allocations alternate between `steady.ml` and `late.ml`. Those of `late.ml` are 8 bytes
+    /// before the 100th millisecond, and 64 bytes after that.
+    fn data() -> data::Data {
+        let mut synth = fixture::Synth::new();
+        let trace = |file: &str| fixture::trace(&[("main.ml", 1), (file, 3)]);
+        let (steady, late) = (trace("steady.ml"), trace("late.ml"));
+        let labels = fixture::labels(&[]);
+
+        for idx in 0..200u64 {
+            let (trace, size) = if idx % 2 == 0 {
+                (&steady, 8)
+            } else {
+                (&late, if idx < 100 { 8 } else { 64 })
+            };
+            synth.alloc(idx, size, trace, &labels);
+        }
+
+        synth.done()
+    }
+
+    #[test]
+    fn ranks_late_site() {
+        let data = data();
+        let range = TimeWindow::new(millis(101), millis(199));
+        let explanation = Explanation::new(&data, range, time::Duration::from_secs(10));
+
+        assert!(explanation.complete);
+        assert_eq!(
+            explanation.baseline,
+            TimeWindow::new(millis(3), millis(101))
+        );
+        assert_eq!(explanation.contributors.len(), 1);
+
+        let late = &explanation.contributors[0];
+        assert_eq!((late.file.as_str(), late.line), ("late.ml", 3));
+        assert_eq!(late.bytes, 50 * 64);
+        assert_eq!(late.baseline, 49 * 8);
+        assert_eq!(late.example.len(), 2);
+        assert!(late.example[0].contains("late.ml"));
+        assert!(late.example[1].contains("main.ml"));
+    }
+
+    #[test]
+    fn empty_baseline() {
+        let data = data();
+        let range = TimeWindow::new(millis(0), millis(9));
+        let explanation = Explanation::new(&data, range, time::Duration::from_secs(10));
+
+        assert_eq!(explanation.baseline, TimeWindow::new(millis(0), millis(0)));
+        assert_eq!(explanation.contributors.len(), 2);
+        assert!(explanation
+            .contributors
+            .iter()
+            .all(|contributor| contributor.relative_increase().is_none()));
+    }
+}
@@ -23,28 +23,50 @@
 
 prelude! {}
 
+pub mod cache;
+pub mod complete;
+pub mod diff;
+pub mod export;
+pub mod frame;
+pub mod group;
 pub mod label;
 pub mod loc;
 pub mod ord;
+pub mod preview;
 mod spec;
 pub mod stats;
 pub mod string_like;
 pub mod sub;
+pub mod text;
 
 #[cfg(any(test, feature = "server"))]
 pub mod gen;
 
+pub use export::Export;
+pub use frame::FrameFilter;
 #[cfg(any(test, feature = "server"))]
 pub use gen::FilterGen;
+pub use group::{Group, GroupOp};
 pub use label::LabelFilter;
 pub use loc::LocFilter;
 use ord::OrdFilter;
-pub use spec::FilterSpec;
+pub use spec::{FilterName, FilterSpec};
 pub use sub::SubFilter;
 
+/// Number of allocations a thread matches at a time in [`Filters::find_matches`].
+///
+/// [`Filters::find_matches`]: struct.Filters.html#method.find_matches (Filters::find_matches)
+pub const MATCH_CHUNK_LEN: usize = 1_024;
+
 /// A filter over allocation sizes.
 pub type SizeFilter = OrdFilter<u32>;
 
+/// A filter over the number of samples of allocations.
+///
+/// The size of an allocation is a statistical estimate backed by its samples, the more samples the
+/// more reliable the estimate.
+pub type NSamplesFilter = OrdFilter<u32>;
+
 /// A filter over allocation lifetimes.
 pub type LifetimeFilter = OrdFilter<time::Lifetime>;
 impl LifetimeFilter {
@@ -112,6 +134,10 @@ pub enum FilterKind {
     Label,
     /// Location filter.
     Loc,
+    /// Frame filter.
+    Frame,
+    /// Sample count filter.
+    NSamples,
 }
 impl fmt::Display for FilterKind {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
@@ -120,6 +146,8 @@ impl fmt::Display for FilterKind {
             Self::Lifetime => write!(fmt, "lifetime"),
             Self::Label => write!(fmt, "labels"),
             Self::Loc => write!(fmt, "callstack"),
+            Self::Frame => write!(fmt, "frame"),
+            Self::NSamples => write!(fmt, "nsamples"),
         }
     }
 }
@@ -135,6 +163,8 @@ impl FilterKind {
                 Self::Lifetime => (),
                 Self::Label => (),
                 Self::Loc => (),
+                Self::Frame => (),
+                Self::NSamples => (),
             }
         }
 
@@ -144,8 +174,44 @@ impl FilterKind {
             FilterKind::Lifetime,
             // FilterKind::Label,
             FilterKind::Loc,
+            FilterKind::Frame,
+            FilterKind::NSamples,
         ]
     }
+
+    /// Short description of the syntax of a filter kind, for the help of the client.
+    pub fn help(self) -> &'static str {
+        match self {
+            Self::Size => {
+                "compares the size of the allocation (in machine words) with a value (`=`, `≥`, \
+                `≤`), or checks it is in a range `[lb, ub]`"
+            }
+            Self::Lifetime => {
+                "compares the lifetime of the allocation with a duration, or checks it is in a \
+                range `[lb, ub]`, the lifetime of a live allocation is its age"
+            }
+            Self::Label => {
+                "checks the labels of the allocation contain (or exclude) a sequence of labels, \
+                where a label is a string, a regex `#\"...\"#`, or `**` for any sequence of labels"
+            }
+            Self::Loc => {
+                "checks the callstack of the allocation contains (or excludes) a sequence of \
+                locations `file:line`, where `file` can be a regex `#\"...\"#`, `line` can be a \
+                range `[lb, ub]` with `_` for no bound, and `**` stands for any sequence of \
+                locations"
+            }
+            Self::Frame => {
+                "checks the callstack of the allocation has (or does not have) a frame in some \
+                file and, optionally, some range of lines, where the file is a path, a prefix \
+                `path*`, or a regex `#\"...\"#`"
+            }
+            Self::NSamples => {
+                "compares the number of samples of the allocation with a value (`=`, `≥`, `≤`), or \
+                checks it is in a range `[lb, ub]`, allocations with more samples have more \
+                reliable size estimates"
+            }
+        }
+    }
 }
 
 /// A list of filters.
@@ -157,7 +223,8 @@ impl FilterKind {
 ///     user-defined filters;
 /// - a list of [`Filter`]s;
 /// - a memory from allocation UIDs to filter UIDs that tells which filter takes care of some
-///     allocation.
+///     allocation;
+/// - a [cache] of the results of each filter on the allocations.
 ///
 /// The point of the memory is that it is not possible to know which filter takes care of a given
 /// allocation after the first time we saw that allocation. Which we want to know when registering
@@ -167,6 +234,7 @@ impl FilterKind {
 ///
 /// [`FilterSpec`]: struct.FilterSpec.html (The FilterSpec struct)
 /// [`Filter`]: struct.Filter.html (The Filter struct)
+/// [cache]: cache/index.html (The cache module)
 #[derive(Debug, Clone)]
 pub struct Filters {
     /// The specification of the "catch-all" filter.
@@ -177,6 +245,8 @@ pub struct Filters {
     filters: Vec<Filter>,
     /// Remembers which filter is responsible for an allocation.
     memory: BTMap<uid::Alloc, uid::Filter>,
+    /// Results of the filters on the allocations.
+    cache: cache::MatchCache,
 }
 
 impl Filters {
@@ -187,6 +257,7 @@ impl Filters {
             catch_all: FilterSpec::new_catch_all(),
             everything: FilterSpec::new_everything(),
             memory: BTMap::new(),
+            cache: cache::MatchCache::new(),
         }
     }
     /// Constructor.
@@ -196,6 +267,7 @@ impl Filters {
             catch_all: FilterSpec::new_catch_all(),
             everything: FilterSpec::new_everything(),
             memory: BTMap::new(),
+            cache: cache::MatchCache::new(),
         }
     }
 
@@ -211,6 +283,10 @@ impl Filters {
     pub fn filters(&self) -> &Vec<Filter> {
         &self.filters
     }
+    /// Cache of the results of the filters.
+    pub fn cache(&self) -> &cache::MatchCache {
+        &self.cache
+    }
 
     /// Runs filter generation.
     ///
@@ -274,19 +350,183 @@ impl Filters {
         }
     }
 
+    /// Searches for a filter that matches on the input allocation, without remembering it.
+    ///
+    /// Only borrows the filters immutably, so that threads can match allocations concurrently.
+    /// Cached results are used, but fresh results are not cached.
+    pub fn matching(&self, timestamp: &time::SinceStart, alloc: &Alloc) -> Option<uid::Filter> {
+        self.matching_do(timestamp, alloc, &mut vec![])
+    }
+
+    /// Searches for a filter that matches on the input allocation, without using the cache.
+    ///
+    /// Used for the [templates] of the pruned allocations, which share their UID with an allocation
+    /// the cache may know.
+    ///
+    /// [templates]: ../data/prune/struct.Pruned.html#method.template
+    /// (The template method of Pruned)
+    pub fn matching_uncached(
+        &self,
+        timestamp: &time::SinceStart,
+        alloc: &Alloc,
+    ) -> Option<uid::Filter> {
+        self.filters
+            .iter()
+            .find(|filter| filter.apply(timestamp, alloc))
+            .map(Filter::uid)
+    }
+
+    /// Searches for a filter that matches on the input allocation, using the cache.
+    ///
+    /// The results of the filters that actually ran are pushed on `fresh`, unless they depend on
+    /// the time, for the caller to cache them.
+    fn matching_do(
+        &self,
+        timestamp: &time::SinceStart,
+        alloc: &Alloc,
+        fresh: &mut Vec<(uid::Filter, bool)>,
+    ) -> Option<uid::Filter> {
+        let dead = alloc.tod().map(|tod| tod <= *timestamp).unwrap_or(false);
+        self.filters
+            .iter()
+            .find(|filter| {
+                let uid = filter.uid();
+                if let Some(matches) = self.cache.get(uid, *alloc.uid()) {
+                    return matches;
+                }
+                self.cache.count_evaluation();
+                let matches = filter.apply(timestamp, alloc);
+                if dead || !filter.is_time_dependent() {
+                    fresh.push((uid, matches))
+                }
+                matches
+            })
+            .map(Filter::uid)
+    }
+
+    /// Caches the results of some filters on an allocation.
+    fn cache_results(&mut self, alloc: uid::Alloc, fresh: Vec<(uid::Filter, bool)>) {
+        for (filter, matches) in fresh {
+            self.cache.insert(filter, alloc, matches)
+        }
+    }
+
+    /// Memoizes the results of the label regexes of the filters on the labels of some
+    /// allocations.
+    ///
+    /// Matching only reads the caches of the [label regexes][regex], this fills them before
+    /// matching the allocations.
+    ///
+    /// [regex]: label/struct.LabelRegex.html (The LabelRegex struct)
+    pub fn memoize_labels(&mut self, allocs: &[&Alloc]) {
+        let mut labels = HSet::new();
+        for alloc in allocs {
+            labels.extend(alloc.labels().iter().cloned())
+        }
+        let labels: Vec<_> = labels.into_iter().collect();
+        for filter in &mut self.filters {
+            filter.memoize_labels(&labels)
+        }
+    }
+
     /// Searches for a filter that matches on the input allocation.
     pub fn find_match(
         &mut self,
         timestamp: &time::SinceStart,
         alloc: &Alloc,
     ) -> Option<uid::Filter> {
-        for filter in &self.filters {
-            if filter.apply(timestamp, alloc) {
-                Self::remember(&mut self.memory, alloc.uid().clone(), filter.uid());
-                return Some(filter.uid());
-            }
+        self.memoize_labels(&[alloc]);
+        let mut fresh = vec![];
+        let filter = self.matching_do(timestamp, alloc, &mut fresh);
+        self.cache_results(*alloc.uid(), fresh);
+        self.remember_match(*alloc.uid(), filter);
+        filter
+    }
+
+    /// Searches for the filters that match on some allocations, without remembering them.
+    ///
+    /// Same as calling [`matching`] on each allocation in order, except that the results of the
+    /// filters are cached. Threads match the allocations by chunks of [`MATCH_CHUNK_LEN`], after
+    /// the results of the label regexes are [memoized][memoize]: threads only read the filters.
+    ///
+    /// Callers [remember] the matches of the allocations they actually handle.
+    ///
+    /// [`matching`]: #method.matching (The matching method)
+    /// [`MATCH_CHUNK_LEN`]: constant.MATCH_CHUNK_LEN.html (The MATCH_CHUNK_LEN constant)
+    /// [memoize]: #method.memoize_labels (The memoize_labels method)
+    /// [remember]: #method.remember_match (The remember_match method)
+    #[cfg(any(test, feature = "server"))]
+    pub fn find_matches(
+        &mut self,
+        timestamp: &time::SinceStart,
+        allocs: &[&Alloc],
+    ) -> Vec<Option<uid::Filter>> {
+        use rayon::prelude::*;
+
+        self.memoize_labels(allocs);
+
+        let slf = &*self;
+        let chunks: Vec<Vec<_>> = allocs
+            .par_chunks(MATCH_CHUNK_LEN)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .map(|alloc| {
+                        let mut fresh = vec![];
+                        let filter = slf.matching_do(timestamp, alloc, &mut fresh);
+                        (filter, fresh)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut matches = Vec::with_capacity(allocs.len());
+        for (alloc, (filter, fresh)) in allocs.iter().zip(chunks.into_iter().flatten()) {
+            self.cache_results(*alloc.uid(), fresh);
+            matches.push(filter)
+        }
+        matches
+    }
+
+    /// Remembers the filter that matched an allocation, if any.
+    ///
+    /// See [`find_matches`].
+    ///
+    /// [`find_matches`]: #method.find_matches (The find_matches method)
+    pub fn remember_match(&mut self, alloc: uid::Alloc, filter: Option<uid::Filter>) {
+        if let Some(filter) = filter {
+            Self::remember(&mut self.memory, alloc, filter)
+        }
+    }
+
+    /// Matches all the allocations of some data again, from scratch.
+    ///
+    /// Only the filters that changed since they last ran on an allocation actually run again, see
+    /// the [cache].
+    ///
+    /// [cache]: cache/index.html (The cache module)
+    #[cfg(any(test, feature = "server"))]
+    pub fn rematch(&mut self, data: &data::Data) -> Res<()> {
+        self.sync_cache(data)?;
+        self.reset();
+        let allocs: Vec<&Alloc> = data.iter_allocs().collect();
+        let matches = self.find_matches(data.current_time(), &allocs);
+        for (alloc, filter) in allocs.into_iter().zip(matches) {
+            self.remember_match(*alloc.uid(), filter)
         }
-        None
+        Ok(())
+    }
+
+    /// Clears the cache of the results of the filters if the data changed.
+    ///
+    /// The data changes when the run restarts, when it is fully reloaded, and when tags change the
+    /// labels of the allocations.
+    #[cfg(any(test, feature = "server"))]
+    pub fn sync_cache(&mut self, data: &data::Data) -> Res<()> {
+        let start_time = data.start_time()?;
+        self.cache
+            .sync((start_time, data.generation(), data.tags_version()));
+        Ok(())
     }
 
     /// Searches for a filter that matches on the input allocation, for its death.
@@ -294,6 +534,22 @@ impl Filters {
         self.memory.get(alloc).map(|uid| *uid)
     }
 
+    /// Forgets the filters that matched the allocations some data pruned, see [`data::prune`].
+    ///
+    /// [`data::prune`]: ../data/prune/index.html (The data::prune module)
+    #[cfg(any(test, feature = "server"))]
+    pub fn forget_pruned(&mut self, data: &data::Data) {
+        self.memory.retain(|alloc, _| !data.is_pruned(*alloc))
+    }
+
+    /// Line of an allocation, the catch-all line if no filter matched it.
+    pub fn line_of(&self, alloc: &uid::Alloc) -> uid::Line {
+        self.memory
+            .get(alloc)
+            .map(|filter| uid::Line::from(*filter))
+            .unwrap_or(uid::Line::CatchAll)
+    }
+
     /// Resets all the filters.
     pub fn reset(&mut self) {
         self.memory.clear()
@@ -322,23 +578,108 @@ impl Filters {
 }
 
 /// # Message handling
+#[cfg(any(test, feature = "server"))]
 impl Filters {
     /// Applies a filter message.
+    ///
+    /// Messages that would take the number of filters over the [limits] of the server are rejected,
+    /// see [`reject_over_limit`], and so are the ones pruning cannot handle, see
+    /// [`reject_unreplayable`].
+    ///
+    /// [limits]: ../limits/fn.get.html (The limits::get function)
+    /// [`reject_over_limit`]: #method.reject_over_limit (The reject_over_limit method)
+    /// [`reject_unreplayable`]: #method.reject_unreplayable (The reject_unreplayable method)
     pub fn update(&mut self, msg: msg::to_server::FiltersMsg) -> Res<(msg::to_client::Msgs, bool)> {
         use msg::to_server::FiltersMsg::*;
+        if let Some(rejection) = self.reject_over_limit(&msg, limits::get()) {
+            return Ok((vec![rejection], false));
+        }
+        if let Some(rejection) = Self::reject_unreplayable(&msg, data::prune::is_enabled()) {
+            return Ok((vec![rejection], false));
+        }
         let (res, should_reload) = match msg {
             RequestNew => (self.add_new(), false),
             RequestNewSub(uid) => (self.add_new_sub(uid), false),
+            RequestSite { file, line } => (self.add_new_site(file, line), false),
             Revert => (self.revert(), false),
+            Import(export) => (self.import(export), false),
+            Append(filters) => (self.append(filters), false),
+            Duplicate(filter) => (self.duplicate(filter), false),
+            // Generation is over by the time filters receive messages, nothing to cancel.
+            CancelGen => (Ok(vec![]), false),
+            Preview { id, filter } => (Ok(vec![Self::preview(id, &filter)]), false),
+            CompleteLabel(prefix) => (Ok(vec![Self::complete_label(prefix)]), false),
             UpdateAll {
                 everything,
                 filters,
                 catch_all,
-            } => (self.update_all(everything, filters, catch_all), true),
+            } => return self.update_all(everything, filters, catch_all),
+            RequestCatchAllSite => return self.add_catch_all_site(),
         };
         res.map(|msgs| (msgs, should_reload))
     }
 
+    /// Rejection of a message that would take the number of filters over some limits, if any.
+    ///
+    /// New filters are only registered when the client saves them, so the server checks the
+    /// filters it has plus the ones the message creates. Saving is checked against the limits
+    /// too, since the client may have accumulated new filters without saving.
+    pub fn reject_over_limit(
+        &self,
+        msg: &msg::to_server::FiltersMsg,
+        limits: limits::Limits,
+    ) -> Option<msg::to_client::Msg> {
+        msg.filter_count(self.filters.len())
+            .and_then(|count| limits.check_filters(count))
+            .map(|reason| msg::to_client::Msg::rejected(format!("filters({})", msg), reason))
+    }
+
+    /// Rejection of a message that would register [time-dependent] filters while pruning, if any.
+    ///
+    /// The [pruned] allocations cannot be replayed through such filters, since the filter that
+    /// catches an allocation depends on when it is matched.
+    ///
+    /// [time-dependent]: struct.Filter.html#method.is_time_dependent
+    /// (The is_time_dependent method of Filter)
+    /// [pruned]: ../data/prune/index.html (The data::prune module)
+    pub fn reject_unreplayable(
+        msg: &msg::to_server::FiltersMsg,
+        pruning: bool,
+    ) -> Option<msg::to_client::Msg> {
+        match msg {
+            msg::to_server::FiltersMsg::UpdateAll { filters, .. }
+                if pruning && filters.iter().any(Filter::is_time_dependent) =>
+            {
+                let reason = msg::to_client::Rejection::Pruning;
+                Some(msg::to_client::Msg::rejected(
+                    format!("filters({})", msg),
+                    reason,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Previews the live allocations a filter catches, without registering it.
+    ///
+    /// Failing to compute the preview is not fatal, the error is sent to the client with the
+    /// preview.
+    pub fn preview(id: u64, filter: &Filter) -> msg::to_client::Msg {
+        let preview = preview::Preview::of_filter(filter).map_err(|e| e.to_pretty());
+        msg::to_client::FiltersMsg::preview(id, filter.uid(), preview)
+    }
+
+    /// Completes a label from the labels of the trace.
+    ///
+    /// Failing to compute the completions is not fatal, the client gets no completions.
+    pub fn complete_label(prefix: String) -> msg::to_client::Msg {
+        let labels = complete::complete(&prefix).unwrap_or_else(|e| {
+            log::error!("{}", e.to_pretty());
+            vec![]
+        });
+        msg::to_client::FiltersMsg::label_completions(prefix, labels)
+    }
+
     /// Sends all the filters to the client.
     pub fn revert(&self) -> Res<msg::to_client::Msgs> {
         let catch_all = self.catch_all.clone();
@@ -350,26 +691,215 @@ impl Filters {
     }
 
     /// Updates all the filters.
+    ///
+    /// The boolean returned is `true` if the points must be regenerated, *i.e.* if the new filters
+    /// do not catch the same allocations as the old ones. Changing the specification (name, color)
+    /// of a filter or of a built-in line does not require to regenerate the points: the client
+    /// refreshes its series by itself.
+    ///
+    /// The new filters replace the old ones, along with the match caches of their [label
+    /// regexes][regex]. So caches never outlive the specification they memoize. Likewise, the
+    /// [cached results] of the filters whose subfilters changed are invalidated, and the ones of
+    /// the filters removed are forgotten.
+    ///
+    /// Fails on time-dependent filters while pruning, see [`reject_unreplayable`].
+    ///
+    /// [regex]: label/struct.LabelRegex.html (The LabelRegex struct)
+    /// [cached results]: cache/index.html (The cache module)
+    /// [`reject_unreplayable`]: #method.reject_unreplayable (The reject_unreplayable method)
     pub fn update_all(
         &mut self,
         everything: FilterSpec,
         filters: Vec<Filter>,
         catch_all: FilterSpec,
-    ) -> Res<msg::to_client::Msgs> {
+    ) -> Res<(msg::to_client::Msgs, bool)> {
+        if data::prune::is_enabled() && filters.iter().any(Filter::is_time_dependent) {
+            bail!("lifetime filters are not available while pruning allocations")
+        }
+        let should_reload = self.filters.len() != filters.len()
+            || self
+                .filters
+                .iter()
+                .zip(filters.iter())
+                .any(|(old, new)| old.uid() != new.uid() || !old.same_matches(new));
+        for new in &filters {
+            let unchanged = self
+                .filters
+                .iter()
+                .any(|old| old.uid() == new.uid() && old.same_matches(new));
+            if !unchanged {
+                self.cache.invalidate(new.uid())
+            }
+        }
+        for old in &self.filters {
+            if filters.iter().all(|new| new.uid() != old.uid()) {
+                self.cache.remove(old.uid())
+            }
+        }
         self.catch_all = catch_all;
         self.everything = everything;
         self.filters = filters;
-        Ok(vec![])
+        Ok((vec![], should_reload))
+    }
+
+    /// Lines whose allocations may change if the filters were replaced by some new filters.
+    ///
+    /// Filters are tried in order, so changing a filter changes the lines of the filters after it
+    /// and of the catch-all filter. Changing only specifications (name, color) changes no line.
+    ///
+    /// Yields `None` if the new filters add or remove filters, in which case all lines change.
+    pub fn changed_lines(&self, new: &[Filter]) -> Option<BTSet<uid::Line>> {
+        let same_uids = self.filters.len() == new.len()
+            && new
+                .iter()
+                .all(|filter| self.filters.iter().any(|old| old.uid() == filter.uid()));
+        if !same_uids {
+            return None;
+        }
+        let first = self
+            .filters
+            .iter()
+            .zip(new.iter())
+            .position(|(old, new)| old.uid() != new.uid() || !old.same_matches(new));
+        let mut lines = BTSet::new();
+        if let Some(first) = first {
+            lines.extend(
+                new[first..]
+                    .iter()
+                    .map(|filter| uid::Line::Filter(filter.uid())),
+            );
+            lines.insert(uid::Line::CatchAll);
+        }
+        Some(lines)
+    }
+
+    /// Imports filters, giving them fresh UIDs.
+    ///
+    /// Like new filters, imported filters are only registered when the client saves them.
+    pub fn import(&self, mut export: Export) -> Res<msg::to_client::Msgs> {
+        export.refresh_uids();
+        Ok(vec![msg::to_client::FiltersMsg::import(export)])
+    }
+
+    /// Appends shared filters to the filters of the client, giving them fresh UIDs.
+    ///
+    /// Like new filters, appended filters are only registered when the client saves them.
+    pub fn append(&self, mut filters: Vec<Filter>) -> Res<msg::to_client::Msgs> {
+        for filter in &mut filters {
+            filter.refresh_uids()
+        }
+        Ok(vec![msg::to_client::FiltersMsg::append(filters)])
+    }
+
+    /// Duplicates the client's version of a filter.
+    ///
+    /// The copy gets fresh UIDs and a color that is not similar to the colors of the filters. Like
+    /// new filters, it is only registered when the client saves it.
+    pub fn duplicate(&self, filter: Filter) -> Res<msg::to_client::Msgs> {
+        let color = Color::random_until(|color| {
+            !color.has_low_contrast()
+                && !filter.spec().color().is_similar_to(color)
+                && self
+                    .filters
+                    .iter()
+                    .map(Filter::spec)
+                    .chain(vec![&self.catch_all, &self.everything])
+                    .all(|spec| !spec.color().is_similar_to(color))
+        });
+        let msg = msg::to_client::FiltersMsg::add(filter.duplicate(color));
+        Ok(vec![msg])
+    }
+
+    /// Color for a new filter, the next palette color the filters do not use.
+    ///
+    /// See [`Color::next_palette`].
+    ///
+    /// [`Color::next_palette`]: ../color/struct.Color.html#method.next_palette
+    /// (Color::next_palette)
+    pub fn next_color(&self) -> Color {
+        Color::next_palette(self.filters.iter().map(|filter| filter.spec().color()))
     }
 
     /// Adds a new filter.
     pub fn add_new(&mut self) -> Res<msg::to_client::Msgs> {
-        let spec = FilterSpec::new(Color::random());
+        let spec = FilterSpec::new(self.next_color());
         let filter = Filter::new(spec).chain_err(|| "while creating new filter")?;
         let msg = msg::to_client::FiltersMsg::add(filter);
         Ok(vec![msg])
     }
 
+    /// Adds a new filter for an allocation site.
+    pub fn add_new_site(&mut self, file: String, line: usize) -> Res<msg::to_client::Msgs> {
+        let filter = self.site_filter(&file, line)?;
+        let msg = msg::to_client::FiltersMsg::add(filter);
+        Ok(vec![msg])
+    }
+
+    /// A new filter for an allocation site.
+    fn site_filter(&self, file: &str, line: usize) -> Res<Filter> {
+        let mut spec = FilterSpec::new(self.next_color());
+        spec.set_name(FilterName::new_truncated(format!("{}:{}", file, line)));
+        let mut filter = Filter::new(spec).chain_err(|| "while creating new site filter")?;
+        filter.insert(explain::site_subfilter(file, line))?;
+        Ok(filter)
+    }
+
+    /// Registers a new filter for the top allocation site of the catch-all filter.
+    ///
+    /// The filter comes after the other filters, so it only takes allocations from the catch-all
+    /// filter. See [`catch_all_top_site`].
+    ///
+    /// [`catch_all_top_site`]: #method.catch_all_top_site (The catch_all_top_site method)
+    pub fn add_catch_all_site(&mut self) -> Res<(msg::to_client::Msgs, bool)> {
+        let site = {
+            let data = data::get()?;
+            self.catch_all_top_site(&data)
+        };
+        let (file, line) = match site {
+            Some(site) => site,
+            None => {
+                let alert = msg::to_client::Msg::alert(
+                    "the catch-all filter has no live allocations with an allocation site",
+                    false,
+                );
+                return Ok((vec![alert], false));
+            }
+        };
+        let filter = self.site_filter(&file, line)?;
+        let mut filters = self.filters.clone();
+        filters.push(filter.clone());
+        let (mut msgs, reload) =
+            self.update_all(self.everything.clone(), filters, self.catch_all.clone())?;
+        msgs.push(msg::to_client::FiltersMsg::add_saved(filter));
+        Ok((msgs, reload))
+    }
+
+    /// Allocation site allocating the most live bytes among the allocations of the catch-all
+    /// filter, if any.
+    ///
+    /// Allocations the filters did not go through yet count as caught by the catch-all filter.
+    /// Ties go to the first site in lexicographical order.
+    pub fn catch_all_top_site(&self, data: &data::Data) -> Option<(String, usize)> {
+        let mut sites: HMap<alloc::Loc, u64> = HMap::new();
+        for alloc in data.iter_allocs() {
+            if alloc.tod.is_some() || self.line_of(alloc.uid()) != uid::Line::CatchAll {
+                continue;
+            }
+            if let Some(site) = alloc.trace().last() {
+                *sites.entry(site.loc.clone()).or_insert(0) += alloc.real_size as u64
+            }
+        }
+        sites
+            .into_iter()
+            .map(|(site, bytes)| (bytes, site.file.to_string(), site.line))
+            .max_by(|(bytes_1, file_1, line_1), (bytes_2, file_2, line_2)| {
+                bytes_1
+                    .cmp(bytes_2)
+                    .then_with(|| (file_2, line_2).cmp(&(file_1, line_1)))
+            })
+            .map(|(_, file, line)| (file, line))
+    }
+
     /// Adds a new sub-filter.
     pub fn add_new_sub(&mut self, uid: uid::Filter) -> Res<msg::to_client::Msgs> {
         let msg = msg::to_client::FiltersMsg::add_sub(uid, SubFilter::default());
@@ -377,29 +907,103 @@ impl Filters {
     }
 
     /// Extract filter statistics.
+    ///
+    /// Goes through the events in chronological order to find when the live size of each line,
+    /// including the everything line, reached its peak. The [pruned] allocations are replayed
+    /// between the events.
+    ///
+    /// Muted lines have no statistics. Their allocations are still counted as caught though, so
+    /// that muting a filter does not move them to the catch-all line.
+    ///
+    /// [pruned]: ../data/prune/index.html (The data::prune module)
     #[cfg(any(test, feature = "server"))]
     pub fn filter_stats(&self) -> Res<stats::AllFilterStats> {
         let mut stats = stats::AllFilterStats::new();
         let mut registered = 0;
 
-        for (_, filter) in &self.memory {
-            registered += 1;
-            stats.stats_do((*filter).into(), |stats| stats.inc())
+        let data = data::get()?;
+
+        // The memory may remember allocations pruned since it last forgot them.
+        for (alloc, filter) in &self.memory {
+            if !data.is_pruned(*alloc) {
+                registered += 1;
+                stats.stats_do((*filter).into(), |stats| stats.inc())
+            }
         }
 
-        let total = data::alloc_count()?;
-        if registered > total {
+        if registered > data.alloc_count() {
             bail!(
                 "inconsistent state, extracted filter stats for {} allocation, \
                 but allocation count is {}",
                 registered,
-                total,
+                data.alloc_count(),
             )
         }
 
+        let register = |stats: &mut stats::AllFilterStats, change: data::prune::Change| {
+            let size = change.bytes / std::cmp::max(change.count, 1);
+            for line in &[change.line, uid::Line::Everything] {
+                stats.stats_do(*line, |stats| {
+                    for _ in 0..change.count {
+                        if change.add {
+                            stats.add_live(change.time, size)
+                        } else {
+                            stats.remove_live(size)
+                        }
+                    }
+                })
+            }
+        };
+
+        let mut pruned = data.pruned().changes_after((None, None)).peekable();
+        let mut lines = data::prune::Lines::new();
+
+        // Registers the pruned changes that precede some change, all of them if none.
+        macro_rules! replay {
+            (before $next:expr) => {
+                while let Some((key, mut change)) = pruned.peek().cloned() {
+                    let next: Option<&data::prune::Change> = $next;
+                    if !next.map(|next| change.precedes(next)).unwrap_or(true) {
+                        break;
+                    }
+                    let _ = pruned.next();
+                    change.line = lines.get(&data, self, key);
+                    if change.add && change.line != uid::Line::CatchAll {
+                        registered += change.count as usize;
+                        stats.stats_do(change.line, |stats| {
+                            stats.alloc_count += change.count as usize
+                        })
+                    }
+                    register(&mut stats, change)
+                }
+            };
+        }
+
+        data.iter_new_events(None, |event| {
+            let alloc = event.as_ref().either(|alloc| *alloc, |(_, alloc)| *alloc);
+            let change = data::prune::Change::of_event(&event, self.line_of(alloc.uid()));
+            replay!(before Some(&change));
+            register(&mut stats, change);
+            Ok(true)
+        })?;
+        replay!(before None);
+
+        let total = data.alloc_count() + data.pruned().count();
         stats.stats_do(uid::Line::CatchAll, |stats| {
             stats.alloc_count = total - registered
         });
+        stats.stats_do(uid::Line::Everything, |stats| stats.alloc_count = total);
+
+        // Sizes are already scaled by the parser.
+        stats.scale_counts(data.sampling());
+
+        let specs = Some(&self.everything)
+            .into_iter()
+            .chain(Some(&self.catch_all))
+            .chain(self.filters.iter().map(Filter::spec));
+        for spec in specs.filter(|spec| spec.is_muted()) {
+            stats.remove(spec.uid());
+        }
 
         Ok(stats)
     }
@@ -407,11 +1011,28 @@ impl Filters {
 
 /// A filter that combines `SubFilter`s.
 ///
-/// Also contains a [`FilterSpec`](struct.FilterSpec.html).
+/// Subfilters are organized in [`Group`]s, see the [`group`] module. Also contains a
+/// [`FilterSpec`](struct.FilterSpec.html).
+///
+/// [`Group`]: group/struct.Group.html (The Group struct)
+/// [`group`]: group/index.html (The group module)
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Filter {
     /// Actual list of filters.
     subs: BTMap<uid::SubFilter, SubFilter>,
+    /// Root group of the subfilters.
+    ///
+    /// Filters defined before groups existed have none, their subfilters are all in the root.
+    #[serde(default)]
+    root: Group,
+    /// Order of the subfilters.
+    ///
+    /// Filters defined before subfilters could be reordered have none, their subfilters are in
+    /// UID order. Subfilters the order does not mention come last, see [`iter`].
+    ///
+    /// [`iter`]: #method.iter (The iter method)
+    #[serde(default)]
+    order: Vec<uid::SubFilter>,
     /// Filter specification.
     spec: FilterSpec,
 }
@@ -424,6 +1045,8 @@ impl Filter {
         }
         let slf = Self {
             subs: BTMap::new(),
+            root: Group::default(),
+            order: vec![],
             spec,
         };
         Ok(slf)
@@ -439,7 +1062,7 @@ impl Filter {
     }
 
     /// Name of the filter.
-    pub fn name(&self) -> &str {
+    pub fn name(&self) -> &FilterName {
         self.spec().name()
     }
 
@@ -452,13 +1075,244 @@ impl Filter {
     }
 
     /// Applies the filters to an allocation.
+    ///
+    /// A filter with no subfilters matches all allocations. Empty groups are ignored, see
+    /// [`GroupOp::fold`].
+    ///
+    /// [`GroupOp::fold`]: group/enum.GroupOp.html#method.fold (The fold method of GroupOp)
     pub fn apply(&self, timestamp: &time::SinceStart, alloc: &Alloc) -> bool {
-        for filter in self.subs.values() {
-            if !filter.apply(timestamp, alloc) {
-                return false;
+        let ungrouped = self
+            .ungrouped()
+            .map(|filter| Some(filter.apply(timestamp, alloc)));
+        let nested = self
+            .root
+            .groups()
+            .iter()
+            .map(|group| group.apply(&self.subs, timestamp, alloc));
+        self.root.op().fold(ungrouped.chain(nested)) != Some(false)
+    }
+
+    /// True if two filters match the same allocations, *i.e.* if they have the same subfilters and
+    /// groups.
+    ///
+    /// Specifications (name, color) are ignored.
+    pub fn same_matches(&self, other: &Self) -> bool {
+        self.subs == other.subs && self.root == other.root
+    }
+
+    /// True if the filter depends on the time, *i.e.* if it has a lifetime subfilter.
+    ///
+    /// The result of such a filter on an allocation changes as long as the allocation is alive.
+    pub fn is_time_dependent(&self) -> bool {
+        self.subs
+            .values()
+            .any(|sub| matches!(sub.raw(), sub::RawSubFilter::Lifetime(_)))
+    }
+
+    /// Gives fresh UIDs to the filter and its subfilters.
+    pub fn refresh_uids(&mut self) {
+        self.spec.refresh_uid();
+        let mut renaming = BTMap::new();
+        self.subs = std::mem::take(&mut self.subs)
+            .into_iter()
+            .map(|(old_uid, mut sub)| {
+                sub.refresh_uid();
+                renaming.insert(old_uid, sub.uid());
+                (sub.uid(), sub)
+            })
+            .collect();
+        self.order = self
+            .order
+            .iter()
+            .filter_map(|sub| renaming.get(sub).cloned())
+            .collect();
+        self.root.rename_subs(&|sub| renaming.get(&sub).cloned())
+    }
+
+    /// Root group of the subfilters.
+    pub fn root(&self) -> &Group {
+        &self.root
+    }
+
+    /// Subfilters that are in no nested group, *i.e.* the subfilters of the root group, in order.
+    pub fn ungrouped(&self) -> impl Iterator<Item = &SubFilter> {
+        let root = &self.root;
+        self.iter().filter(move |sub| !root.contains(sub.uid()))
+    }
+
+    /// Subfilters of a nested group in order, nested groups excluded.
+    pub fn group_subs<'a>(&'a self, group: &'a Group) -> impl Iterator<Item = &'a SubFilter> + 'a {
+        self.iter()
+            .filter(move |sub| group.subs().any(|uid| uid == sub.uid()))
+    }
+
+    /// UIDs of the subfilters, in order.
+    pub fn order(&self) -> Vec<uid::SubFilter> {
+        self.iter().map(SubFilter::uid).collect()
+    }
+
+    /// Sets the order of the subfilters.
+    ///
+    /// Unknown subfilters are ignored. The subfilters `order` does not mention come last, in their
+    /// current order.
+    pub fn reorder(&mut self, order: &[uid::SubFilter]) {
+        let mut new: Vec<uid::SubFilter> = vec![];
+        for sub in order {
+            if self.subs.contains_key(sub) && !new.contains(sub) {
+                new.push(*sub)
+            }
+        }
+        for sub in self.order() {
+            if !new.contains(&sub) {
+                new.push(sub)
+            }
+        }
+        self.order = new
+    }
+
+    /// Swaps a subfilter with the previous subfilter of its group, or the next one if `!up`.
+    ///
+    /// Subfilters only move among the subfilters of their group, nested groups excluded. Returns
+    /// false if there is no such subfilter, *i.e.* the subfilter is the first (last) of its group.
+    ///
+    /// ```rust
+    /// # use charts::{color::Color, filter::*};
+    /// let mut filter = Filter::new(FilterSpec::new(Color::new(0, 0, 0))).unwrap();
+    /// let subs = vec![SubFilter::default(), SubFilter::default(), SubFilter::default()];
+    /// let (a, b, c) = (subs[0].uid(), subs[1].uid(), subs[2].uid());
+    /// for sub in subs {
+    ///     filter.insert(sub).unwrap()
+    /// }
+    ///
+    /// assert!(!filter.shift_sub(a, true).unwrap());
+    /// assert!(filter.shift_sub(a, false).unwrap());
+    /// assert_eq!(filter.order(), vec![b, a, c]);
+    ///
+    /// // `b` is alone in its group, it cannot move.
+    /// filter.add_group(&[], GroupOp::Or).unwrap();
+    /// filter.move_sub(b, &[0]).unwrap();
+    /// assert!(!filter.shift_sub(b, false).unwrap());
+    /// assert!(filter.shift_sub(c, true).unwrap());
+    /// assert_eq!(filter.order(), vec![b, c, a]);
+    /// ```
+    pub fn shift_sub(&mut self, sub: uid::SubFilter, up: bool) -> Res<bool> {
+        if !self.subs.contains_key(&sub) {
+            bail!("failed to move unknown subfilter UID #{}", sub)
+        }
+        let group = self.root.path_of(sub);
+        let mut order = self.order();
+        // Indices in `order` of the subfilters of the group.
+        let siblings: Vec<usize> = order
+            .iter()
+            .enumerate()
+            .filter(|(_, uid)| self.root.path_of(**uid) == group)
+            .map(|(index, _)| index)
+            .collect();
+        let pos = siblings
+            .iter()
+            .position(|index| order[*index] == sub)
+            .ok_or_else(|| format!("subfilter #{} is not in its own group", sub))?;
+        let other = if up {
+            pos.checked_sub(1)
+        } else {
+            Some(pos + 1)
+        };
+        match other.and_then(|other| siblings.get(other)) {
+            Some(other) => {
+                order.swap(siblings[pos], *other);
+                self.order = order;
+                Ok(true)
             }
+            None => Ok(false),
+        }
+    }
+
+    /// Adds a nested group to the group at some path, see [`Group::add_group`].
+    ///
+    /// [`Group::add_group`]: group/struct.Group.html#method.add_group
+    /// (The add_group method of Group)
+    pub fn add_group(&mut self, path: &[usize], op: GroupOp) -> Res<()> {
+        self.root.add_group(path, op)
+    }
+
+    /// Removes the nested group at some path, see [`Group::ungroup`].
+    ///
+    /// [`Group::ungroup`]: group/struct.Group.html#method.ungroup (The ungroup method of Group)
+    pub fn ungroup(&mut self, path: &[usize]) -> Res<()> {
+        self.root.ungroup(path)
+    }
+
+    /// Sets the operator of the group at some path.
+    pub fn set_group_op(&mut self, path: &[usize], op: GroupOp) -> Res<()> {
+        self.root
+            .get_mut(path)
+            .ok_or_else(|| format!("unknown group {:?}", path))?
+            .set_op(op);
+        Ok(())
+    }
+
+    /// Moves a subfilter to the group at some path.
+    pub fn move_sub(&mut self, sub: uid::SubFilter, path: &[usize]) -> Res<()> {
+        if !self.subs.contains_key(&sub) {
+            bail!("failed to move unknown subfilter UID #{}", sub)
+        }
+        if self.root.get(path).is_none() {
+            bail!(
+                "failed to move subfilter #{} to unknown group {:?}",
+                sub,
+                path
+            )
+        }
+        self.root.remove_sub(sub);
+        if !path.is_empty() {
+            self.root
+                .get_mut(path)
+                .expect("group existence was checked above")
+                .insert(sub)
         }
-        true
+        Ok(())
+    }
+
+    /// Replaces the root group, fails if the group is illegal for this filter.
+    pub fn set_root(&mut self, root: Group) -> Res<()> {
+        let old = std::mem::replace(&mut self.root, root);
+        self.check_groups().map_err(|e| {
+            self.root = old;
+            e
+        })
+    }
+
+    /// Checks the groups of the filter are legal.
+    pub fn check_groups(&self) -> Res<()> {
+        let known = self.subs.keys().cloned().collect();
+        self.root
+            .check(true, &known)
+            .chain_err(|| format!("illegal groups for filter `{}`", self.name()))
+    }
+
+    /// Copy of the filter with fresh UIDs, a different name and some color.
+    ///
+    /// ```rust
+    /// # use charts::{color::Color, filter::*};
+    /// let mut filter = Filter::new(FilterSpec::new(Color::new(0, 0, 0))).unwrap();
+    /// filter.spec_mut().set_name(FilterName::new("big").unwrap());
+    /// filter.insert(SubFilter::default()).unwrap();
+    ///
+    /// let copy = filter.duplicate(Color::new(0xff, 0, 0));
+    /// assert_ne!(copy.uid(), filter.uid());
+    /// assert_eq!(copy.name().as_str(), "big (copy)");
+    /// assert_eq!(copy.spec().color(), &Color::new(0xff, 0, 0));
+    /// let (sub, copied_sub) = (filter.iter().next().unwrap(), copy.iter().next().unwrap());
+    /// assert_ne!(copied_sub.uid(), sub.uid());
+    /// assert_eq!(copied_sub.raw(), sub.raw());
+    /// ```
+    pub fn duplicate(&self, color: Color) -> Self {
+        let mut copy = self.clone();
+        copy.refresh_uids();
+        copy.spec
+            .set_name(FilterName::new_truncated(format!("{} (copy)", self.name())));
+        copy.spec.set_color(color);
+        copy
     }
 
     /// Removes a subfilter.
@@ -467,12 +1321,19 @@ impl Filter {
         if prev.is_none() {
             bail!("failed to remove unknown subfilter UID #{}", sub_uid)
         }
+        self.root.remove_sub(sub_uid);
+        self.order.retain(|sub| *sub != sub_uid);
         Ok(())
     }
 
-    /// Iterator over the subfilters.
+    /// Iterator over the subfilters, in order.
     pub fn iter(&self) -> impl Iterator<Item = &SubFilter> {
-        self.subs.values()
+        let ordered = self.order.iter().filter_map(move |uid| self.subs.get(uid));
+        let unordered = self
+            .subs
+            .values()
+            .filter(move |sub| !self.order.contains(&sub.uid()));
+        ordered.chain(unordered)
     }
 
     /// Mutable iterator over the subfilters.
@@ -480,15 +1341,25 @@ impl Filter {
         self.subs.values_mut()
     }
 
-    /// Inserts a subfilter.
+    /// Memoizes the results of the label regexes of the filter on some labels.
+    pub fn memoize_labels(&mut self, labels: &[alloc::Str]) {
+        for sub in self.iter_mut() {
+            sub.memoize_labels(labels)
+        }
+    }
+
+    /// Inserts a subfilter in the root group, after the other subfilters.
     ///
     /// Fails if the subfilter is **not** new.
     pub fn insert(&mut self, sub: impl Into<SubFilter>) -> Res<()> {
         let sub = sub.into();
-        let prev = self.subs.insert(sub.uid(), sub);
-        if let Some(prev) = prev {
-            bail!("subfilter UID collision on #{}", prev.uid())
+        let uid = sub.uid();
+        if self.subs.contains_key(&uid) {
+            bail!("subfilter UID collision on #{}", uid)
         }
+        self.order = self.order();
+        self.order.push(uid);
+        self.subs.insert(uid, sub);
         Ok(())
     }
 
@@ -504,3 +1375,383 @@ impl Filter {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn update_all_reload() {
+        let mut filters = Filters::new();
+        let filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        let reload = |filters: &mut Filters, everything, new| {
+            let (_, reload) = filters
+                .update_all(everything, new, FilterSpec::new_catch_all())
+                .unwrap();
+            reload
+        };
+
+        assert!(reload(
+            &mut filters,
+            FilterSpec::new_everything(),
+            vec![filter.clone()]
+        ));
+
+        // Renaming/recoloring a filter or a built-in line does not change the points.
+        let mut renamed = filter.clone();
+        renamed
+            .spec_mut()
+            .set_name(FilterName::new("renamed").unwrap());
+        let mut everything = FilterSpec::new_everything();
+        everything.set_name(FilterName::new("all").unwrap());
+        everything.set_color(Color::new(0, 0, 0));
+        assert!(!reload(
+            &mut filters,
+            everything.clone(),
+            vec![renamed.clone()]
+        ));
+        assert_eq!(filters.everything().name().to_string(), "all");
+
+        // Changing the subfilters does.
+        let mut with_sub = renamed.clone();
+        with_sub.insert(SubFilter::default()).unwrap();
+        assert!(reload(&mut filters, everything.clone(), vec![with_sub]));
+        assert!(reload(&mut filters, everything, vec![]));
+    }
+
+    #[test]
+    fn groups() {
+        use ord::Cmp;
+        let init = alloc::Init::default();
+        let trace = alloc::mem::trace::add(vec![]);
+        let labels = alloc::mem::labels::add(vec![]);
+        let new_alloc = |size| {
+            Alloc::new(
+                &init.sample_rate,
+                uid::Alloc::new(0),
+                alloc::AllocKind::Minor,
+                size,
+                trace.clone(),
+                labels.clone(),
+                time::SinceStart::zero(),
+                None,
+            )
+        };
+        let (size_4, size_16, size_100) = (new_alloc(4), new_alloc(16), new_alloc(100));
+        let now = time::SinceStart::zero();
+
+        let mut filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        let (small, big) = (SubFilter::default(), SubFilter::default());
+        let (small, big) = (small.uid(), big.uid());
+        filter
+            .insert(SubFilter::new(
+                small,
+                SizeFilter::Cmp {
+                    cmp: Cmp::Le,
+                    val: 8,
+                }
+                .into(),
+            ))
+            .unwrap();
+        filter
+            .insert(SubFilter::new(
+                big,
+                SizeFilter::Cmp {
+                    cmp: Cmp::Ge,
+                    val: 64,
+                }
+                .into(),
+            ))
+            .unwrap();
+        let matches = |filter: &Filter| {
+            vec![
+                filter.apply(&now, &size_4),
+                filter.apply(&now, &size_16),
+                filter.apply(&now, &size_100),
+            ]
+        };
+
+        // Ungrouped subfilters are in a conjunction by default.
+        assert_eq!(matches(&filter), vec![false, false, false]);
+        filter.set_group_op(&[], GroupOp::Or).unwrap();
+        assert_eq!(matches(&filter), vec![true, false, true]);
+
+        // Empty groups are ignored.
+        filter.add_group(&[], GroupOp::And).unwrap();
+        filter.add_group(&[0], GroupOp::Or).unwrap();
+        assert_eq!(matches(&filter), vec![true, false, true]);
+
+        // `big || (small && ())`.
+        filter.move_sub(small, &[0]).unwrap();
+        assert_eq!(filter.ungrouped().count(), 1);
+        assert_eq!(matches(&filter), vec![true, false, true]);
+        // `big || (() && (small))`.
+        filter.move_sub(small, &[0, 0]).unwrap();
+        assert_eq!(filter.root().get(&[0]).unwrap().subs().count(), 0);
+        assert_eq!(matches(&filter), vec![true, false, true]);
+        // `() || (() && (small || big))`.
+        filter.move_sub(big, &[0, 0]).unwrap();
+        assert_eq!(filter.ungrouped().count(), 0);
+        assert_eq!(matches(&filter), vec![true, false, true]);
+        // `() || (() && (small && big))`.
+        filter.set_group_op(&[0, 0], GroupOp::And).unwrap();
+        assert_eq!(matches(&filter), vec![false, false, false]);
+
+        // Nesting is limited.
+        filter.add_group(&[0, 0], GroupOp::And).unwrap();
+        assert!(filter.add_group(&[0, 0, 0], GroupOp::And).is_err());
+        assert!(filter.check_groups().is_ok());
+
+        // Ungrouping moves the members to the parent group, or out of the tree for the root.
+        filter.ungroup(&[0, 0]).unwrap();
+        assert_eq!(filter.root().get(&[0]).unwrap().subs().count(), 2);
+        assert_eq!(filter.root().depth(), 2);
+        filter.ungroup(&[0]).unwrap();
+        assert_eq!(filter.ungrouped().count(), 2);
+        assert_eq!(filter.root().depth(), 1);
+
+        // Refreshing the UIDs keeps the structure, removing a subfilter removes it from its group.
+        filter.move_sub(small, &[0]).unwrap();
+        let mut copy = filter.clone();
+        copy.refresh_uids();
+        assert!(copy.check_groups().is_ok());
+        assert_eq!(copy.root().get(&[0]).unwrap().subs().count(), 1);
+        assert_eq!(matches(&copy), matches(&filter));
+        filter.remove(small).unwrap();
+        assert_eq!(filter.root().get(&[0]).unwrap().subs().count(), 0);
+        assert!(filter.check_groups().is_ok());
+
+        // Groups cannot mention unknown subfilters.
+        let mut root = Group::default();
+        root.add_group(&[], GroupOp::Or).unwrap();
+        root.get_mut(&[0]).unwrap().insert(small);
+        assert!(filter.set_root(root).is_err());
+    }
+
+    #[test]
+    fn unordered_filters_load() {
+        let mut filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        let (sub_a, sub_b) = (SubFilter::default(), SubFilter::default());
+        let (a, b) = (sub_a.uid(), sub_b.uid());
+        filter.insert(sub_b).unwrap();
+        filter.insert(sub_a).unwrap();
+        assert_eq!(filter.order(), vec![b, a]);
+
+        // Order survives serialization.
+        let json = serde_json::to_string(&filter).unwrap();
+        let loaded: Filter = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.order(), vec![b, a]);
+
+        // Filters with no order list their subfilters in UID order.
+        let mut json: serde_json::Value = serde_json::to_value(&filter).unwrap();
+        json.as_object_mut().unwrap().remove("order").unwrap();
+        let mut loaded: Filter = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.order(), vec![a, b]);
+        assert!(loaded.shift_sub(b, true).unwrap());
+        assert_eq!(loaded.order(), vec![b, a]);
+        assert_eq!(loaded, filter);
+    }
+
+    #[test]
+    fn ungrouped_filters_load() {
+        let mut filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        filter.insert(SubFilter::default()).unwrap();
+        let mut json: serde_json::Value = serde_json::to_value(&filter).unwrap();
+        json.as_object_mut().unwrap().remove("root").unwrap();
+        let loaded: Filter = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded, filter);
+        assert_eq!(loaded.root(), &Group::default());
+    }
+
+    #[test]
+    fn filter_limit_edge() {
+        use msg::to_server::FiltersMsg;
+        let limits = limits::Limits::new(3, 1);
+        let new_filter = || Filter::new(FilterSpec::new(Color::random())).unwrap();
+        let rejected =
+            |filters: &Filters, req: &FiltersMsg| match filters.reject_over_limit(req, limits) {
+                Some(msg::to_client::Msg::Rejected { reason, .. }) => {
+                    assert_eq!(reason, msg::to_client::Rejection::FilterLimit(3));
+                    true
+                }
+                Some(other) => panic!("expected a rejection, got {}", other),
+                None => false,
+            };
+
+        // Two filters, one more reaches the limit exactly.
+        let mut filters = Filters::new_with(vec![new_filter(), new_filter()]);
+        assert!(!rejected(&filters, &FiltersMsg::RequestNew));
+        assert!(!rejected(&filters, &FiltersMsg::Duplicate(new_filter())));
+        let export = Export::new(FilterSpec::new_catch_all(), vec![new_filter()]);
+        assert!(!rejected(&filters, &FiltersMsg::Import(export)));
+        let export = Export::new(
+            FilterSpec::new_catch_all(),
+            vec![new_filter(), new_filter()],
+        );
+        assert!(rejected(&filters, &FiltersMsg::Import(export)));
+        assert!(!rejected(&filters, &FiltersMsg::Append(vec![new_filter()])));
+        assert!(rejected(
+            &filters,
+            &FiltersMsg::Append(vec![new_filter(), new_filter()])
+        ));
+
+        // At the limit, nothing can be added but saving the same number of filters is fine.
+        filters.filters.push(new_filter());
+        assert!(rejected(&filters, &FiltersMsg::RequestNew));
+        assert!(rejected(
+            &filters,
+            &FiltersMsg::RequestSite {
+                file: "file.ml".into(),
+                line: 7,
+            }
+        ));
+        assert!(rejected(&filters, &FiltersMsg::Duplicate(new_filter())));
+        assert!(!rejected(
+            &filters,
+            &FiltersMsg::RequestNewSub(new_filter().uid())
+        ));
+        let saved = filters.filters.clone();
+        let update = |filters| FiltersMsg::UpdateAll {
+            everything: FilterSpec::new_everything(),
+            filters,
+            catch_all: FilterSpec::new_catch_all(),
+        };
+        assert!(!rejected(&filters, &update(saved.clone())));
+        let mut over = saved;
+        over.push(new_filter());
+        assert!(rejected(&filters, &update(over)));
+    }
+
+    #[test]
+    fn pruning_rejects_lifetime_filters() {
+        use msg::to_server::FiltersMsg;
+        let update = |filter: Filter| FiltersMsg::UpdateAll {
+            everything: FilterSpec::new_everything(),
+            filters: vec![filter],
+            catch_all: FilterSpec::new_catch_all(),
+        };
+        let rejected = |msg: &FiltersMsg, pruning| match Filters::reject_unreplayable(msg, pruning)
+        {
+            Some(msg::to_client::Msg::Rejected { reason, .. }) => {
+                assert_eq!(reason, msg::to_client::Rejection::Pruning);
+                true
+            }
+            Some(other) => panic!("expected a rejection, got {}", other),
+            None => false,
+        };
+
+        let mut filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        filter
+            .insert(explain::site_subfilter("file.ml", 7))
+            .unwrap();
+        assert!(!rejected(&update(filter.clone()), true));
+
+        let uid = SubFilter::default().uid();
+        filter
+            .insert(SubFilter::new(uid, LifetimeFilter::default().into()))
+            .unwrap();
+        assert!(!rejected(&update(filter.clone()), false));
+        assert!(rejected(&update(filter.clone()), true));
+        assert!(!rejected(&FiltersMsg::Duplicate(filter), true));
+    }
+
+    #[test]
+    fn catch_all_top_site() {
+        let mut data = data::Data::new();
+        let init = alloc::Init::default();
+        let sample_rate = init.sample_rate.clone();
+        data.reset("test", init);
+        let trace = |file: &str| {
+            let site = alloc::Loc::new(alloc::Str::new(file), 3, (0, 1));
+            alloc::mem::trace::add(vec![alloc::CLoc::new(site, 1)])
+        };
+        let labels = alloc::mem::labels::add(vec![]);
+        let millis = |ms: u64| time::SinceStart::from(time::Duration::from_millis(ms));
+        // Site and size of the allocations, the last one dies.
+        let allocs = [
+            ("filtered.ml", 512),
+            ("big.ml", 64),
+            ("big.ml", 64),
+            ("small.ml", 8),
+            ("small.ml", 8),
+            ("dead.ml", 1024),
+        ];
+        for (idx, (file, size)) in allocs.iter().enumerate() {
+            let alloc = Alloc::new(
+                &sample_rate,
+                idx as u64,
+                alloc::AllocKind::Minor,
+                *size,
+                trace(file),
+                labels.clone(),
+                millis(idx as u64),
+                None,
+            );
+            data.add_new(alloc).unwrap()
+        }
+        data.add_dead(millis(10), uid::Alloc::from(allocs.len() - 1))
+            .unwrap();
+
+        let mut filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        filter
+            .insert(explain::site_subfilter("filtered.ml", 3))
+            .unwrap();
+        let mut filters = Filters::new_with(vec![filter]);
+        filters.rematch(&data).unwrap();
+        assert_eq!(
+            filters.catch_all_top_site(&data),
+            Some(("big.ml".to_string(), 3))
+        );
+
+        // A filter for the top site takes its allocations away from the catch-all filter.
+        let mut new = filters.filters().clone();
+        new.push(filters.site_filter("big.ml", 3).unwrap());
+        let (everything, catch_all) = (filters.everything().clone(), filters.catch_all().clone());
+        let (_, reload) = filters.update_all(everything, new, catch_all).unwrap();
+        assert!(reload);
+        filters.rematch(&data).unwrap();
+        assert_eq!(
+            filters.catch_all_top_site(&data),
+            Some(("small.ml".to_string(), 3))
+        );
+    }
+
+    #[test]
+    fn muted_stats() {
+        let mut synth = crate::fixture::Synth::new();
+        let labels = crate::fixture::labels(&[]);
+        for (toc, file) in ["muted.ml", "muted.ml", "other.ml"].iter().enumerate() {
+            let trace = crate::fixture::trace(&[(file, 3)]);
+            synth.alloc(toc as u64, 8, &trace, &labels);
+        }
+        let data = synth.done();
+
+        let mut filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        filter
+            .insert(explain::site_subfilter("muted.ml", 3))
+            .unwrap();
+        let line = uid::Line::Filter(filter.uid());
+        let mut filters = Filters::new_with(vec![filter]);
+        filters.rematch(&data).unwrap();
+        let _lock = data::set_for_test(data);
+
+        let stats = filters.filter_stats().unwrap();
+        let count = |line| stats.get(line).map(|stats| stats.alloc_count);
+        let (caught, others, all) = (
+            count(line),
+            count(uid::Line::CatchAll),
+            count(uid::Line::Everything),
+        );
+        assert!(caught.is_some());
+
+        // Muting removes the stats of the line, without giving its allocations to the catch-all.
+        filters
+            .iter_mut()
+            .for_each(|filter| filter.spec_mut().set_muted(true));
+        let stats = filters.filter_stats().unwrap();
+        let count = |line| stats.get(line).map(|stats| stats.alloc_count);
+        assert_eq!(count(line), None);
+        assert_eq!(count(uid::Line::CatchAll), others);
+        assert_eq!(count(uid::Line::Everything), all);
+    }
+}
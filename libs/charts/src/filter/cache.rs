@@ -0,0 +1,218 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Cache of the results of the filters on the allocations.
+//!
+//! Reloading the points of a chart matches all the allocations against the filters again. The
+//! cache remembers whether each filter matches each allocation, so that only the filters that
+//! changed actually run. Results are keyed by the UID of the filter and its *revision*, which
+//! [`Filters::update_all`] bumps when the subfilters of the filter change.
+//!
+//! Results that depend on the time are not cached, *i.e.* the results of filters with a lifetime
+//! subfilter on allocations that are still alive. The cache is [cleared] when the data or the tags
+//! change, since tags change the labels of the allocations.
+//!
+//! [`Filters::update_all`]: ../struct.Filters.html#method.update_all (Filters::update_all)
+//! [cleared]: struct.MatchCache.html#method.sync (The sync method)
+
+prelude! {}
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Results of a filter.
+#[derive(Debug, Clone, Default)]
+struct Results {
+    /// Revision of the filter.
+    revision: u64,
+    /// Result of the filter on each allocation, indexed by allocation UID.
+    matches: Vec<Option<bool>>,
+}
+
+/// Cache of the results of the filters on the allocations.
+#[derive(Debug, Default)]
+pub struct MatchCache {
+    /// Results of each filter.
+    results: BTMap<uid::Filter, Results>,
+    /// Start time, generation and tags version of the data the results are about.
+    data: Option<(time::Date, u64, u64)>,
+    /// Number of times a filter ran on an allocation because the result was not in the cache.
+    evaluations: AtomicUsize,
+}
+
+impl Clone for MatchCache {
+    fn clone(&self) -> Self {
+        Self {
+            results: self.results.clone(),
+            data: self.data.clone(),
+            evaluations: AtomicUsize::new(self.evaluations()),
+        }
+    }
+}
+
+impl MatchCache {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Revision of a filter.
+    pub fn revision(&self, filter: uid::Filter) -> u64 {
+        self.results
+            .get(&filter)
+            .map(|results| results.revision)
+            .unwrap_or(0)
+    }
+
+    /// Cached result of a filter on an allocation, if any.
+    pub fn get(&self, filter: uid::Filter, alloc: uid::Alloc) -> Option<bool> {
+        self.results
+            .get(&filter)
+            .and_then(|results| results.matches.get(alloc.get()).cloned().flatten())
+    }
+
+    /// Caches the result of a filter on an allocation.
+    pub fn insert(&mut self, filter: uid::Filter, alloc: uid::Alloc, matches: bool) {
+        let results = &mut self.results.entry(filter).or_default().matches;
+        let idx = alloc.get();
+        if results.len() <= idx {
+            results.resize(idx + 1, None)
+        }
+        results[idx] = Some(matches)
+    }
+
+    /// Forgets the results of a filter and bumps its revision, *e.g.* when its subfilters change.
+    pub fn invalidate(&mut self, filter: uid::Filter) {
+        let results = self.results.entry(filter).or_default();
+        results.revision += 1;
+        results.matches.clear()
+    }
+
+    /// Forgets a filter entirely, *e.g.* when it is removed.
+    pub fn remove(&mut self, filter: uid::Filter) {
+        self.results.remove(&filter);
+    }
+
+    /// Clears the cache if the data changed.
+    ///
+    /// `data` is the start time, the generation and the version of the tags of the data.
+    pub fn sync(&mut self, data: (time::Date, u64, u64)) {
+        if self.data.as_ref() != Some(&data) {
+            for results in self.results.values_mut() {
+                results.matches.clear()
+            }
+            self.data = Some(data)
+        }
+    }
+
+    /// Counts a run of a filter on an allocation.
+    pub fn count_evaluation(&self) {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+    }
+    /// Number of times a filter ran on an allocation because the result was not in the cache.
+    pub fn evaluations(&self) -> usize {
+        self.evaluations.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use filter::label::{LabelPred, LabelSpec};
+
+    /// Data with `count` allocations, labeled `parse`, `print` and `eval` in turn.
+    fn data(count: u64) -> data::Data {
+        let mut synth = crate::fixture::Synth::new();
+        let names = ["parse", "print", "eval"];
+        let trace = crate::fixture::trace(&[]);
+        for idx in 0..count {
+            let labels = crate::fixture::labels(&[names[idx as usize % 3]]);
+            synth.alloc(idx, 8, &trace, &labels);
+        }
+        synth.done()
+    }
+
+    /// A filter on a label.
+    fn filter(label: &str) -> Filter {
+        let mut filter = Filter::new(filter::FilterSpec::new(Color::new(0, 0, 0))).unwrap();
+        filter
+            .insert(filter::LabelFilter::new(
+                LabelPred::Contain,
+                vec![LabelSpec::new(label).unwrap()],
+            ))
+            .unwrap();
+        filter
+    }
+
+    #[test]
+    fn only_changed_filters_run_again() {
+        let data = data(300);
+        let mut filters = Filters::new_with(vec![filter("parse"), filter("print")]);
+        let everything = filters.everything().clone();
+        let catch_all = filters.catch_all().clone();
+
+        filters.rematch(&data).unwrap();
+        // The first filter runs on everything, the second one on what the first one rejects.
+        assert_eq!(filters.cache().evaluations(), 300 + 200);
+
+        // Only changing colors changes no line, and nothing runs again.
+        let mut recolored = filters.filters().clone();
+        for filter in &mut recolored {
+            filter.spec_mut().set_color(Color::new(255, 0, 0))
+        }
+        assert_eq!(filters.changed_lines(&recolored), Some(BTSet::new()));
+        let (_, reload) = filters
+            .update_all(everything.clone(), recolored.clone(), catch_all.clone())
+            .unwrap();
+        assert!(!reload);
+        filters.rematch(&data).unwrap();
+        assert_eq!(filters.cache().evaluations(), 500);
+
+        // Changing the second filter only runs the second filter again, on the allocations the
+        // first filter rejects.
+        let mut changed = recolored;
+        let second = changed[1].uid();
+        changed[1] = {
+            let mut filter = filter("eval");
+            *filter.spec_mut() = changed[1].spec().clone();
+            filter
+        };
+        let lines = filters.changed_lines(&changed);
+        let expected: BTSet<_> = vec![uid::Line::Filter(second), uid::Line::CatchAll]
+            .into_iter()
+            .collect();
+        assert_eq!(lines, Some(expected));
+        let first = filters.filters()[0].uid();
+        let revisions = (
+            filters.cache().revision(first),
+            filters.cache().revision(second),
+        );
+        let (_, reload) = filters.update_all(everything, changed, catch_all).unwrap();
+        assert!(reload);
+        assert_eq!(filters.cache().revision(first), revisions.0);
+        assert_eq!(filters.cache().revision(second), revisions.1 + 1);
+        filters.rematch(&data).unwrap();
+        assert_eq!(filters.cache().evaluations(), 500 + 200);
+        assert_eq!(
+            filters.line_of(&uid::Alloc::new(2)),
+            uid::Line::Filter(second)
+        );
+        assert_eq!(filters.line_of(&uid::Alloc::new(1)), uid::Line::CatchAll);
+    }
+}
@@ -0,0 +1,176 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Completion of the labels users type in label subfilters.
+//!
+//! The server indexes the distinct labels of the allocations of the trace the first time a client
+//! asks for completions, and re-indexes them when the data or the tags change. Completions of a
+//! prefix are the labels starting with it, the labels carried by the most allocations first.
+
+#[cfg(any(test, feature = "server"))]
+prelude! {}
+
+/// Maximum number of completions for a prefix.
+pub const MAX_COMPLETIONS: usize = 20;
+
+/// Index of the distinct labels of some data.
+#[cfg(any(test, feature = "server"))]
+#[derive(Debug, Clone)]
+pub struct LabelIndex {
+    /// Generation of the data indexed.
+    generation: u64,
+    /// Version of the tags of the data indexed.
+    tags_version: u64,
+    /// Number of allocations carrying each label.
+    labels: BTMap<String, usize>,
+}
+
+#[cfg(any(test, feature = "server"))]
+impl LabelIndex {
+    /// Indexes the labels of some data.
+    pub fn new(data: &data::Data) -> Self {
+        // Labels are interned, count the allocations of each list of labels first.
+        let mut lists: HMap<alloc::Labels, usize> = HMap::new();
+        for alloc in data.iter_allocs() {
+            *lists.entry(alloc.shared_labels()).or_insert(0) += 1
+        }
+
+        let mut labels = BTMap::new();
+        for (list, count) in lists {
+            // A label can appear several times in a list, count the allocation once.
+            let distinct: BTSet<String> =
+                list.get().iter().map(|label| label.to_string()).collect();
+            for label in distinct {
+                *labels.entry(label).or_insert(0) += count
+            }
+        }
+
+        Self {
+            generation: data.generation(),
+            tags_version: data.tags_version(),
+            labels,
+        }
+    }
+
+    /// Number of distinct labels.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// True if the index is up to date for some data.
+    pub fn is_up_to_date(&self, data: &data::Data) -> bool {
+        self.generation == data.generation() && self.tags_version == data.tags_version()
+    }
+
+    /// The labels starting with some prefix, the labels carried by the most allocations first.
+    ///
+    /// Yields at most `limit` labels.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<String> {
+        use std::ops::Bound::{Included, Unbounded};
+        let mut matches: Vec<_> = self
+            .labels
+            .range::<str, _>((Included(prefix), Unbounded))
+            .take_while(|(label, _)| label.starts_with(prefix))
+            .collect();
+        // Stable, so labels carried by as many allocations stay sorted.
+        matches.sort_by(|(_, lft), (_, rgt)| rgt.cmp(lft));
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(label, _)| label.clone())
+            .collect()
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+lazy_static! {
+    /// Label index of the global data, built on the first completion request.
+    static ref INDEX: sync::RwLock<Option<LabelIndex>> = sync::RwLock::new(None);
+}
+
+/// The labels of the global data starting with some prefix, see [`LabelIndex::complete`].
+///
+/// Builds the index if it does not exist or is outdated.
+///
+/// [`LabelIndex::complete`]: struct.LabelIndex.html#method.complete
+/// (The complete method of LabelIndex)
+#[cfg(any(test, feature = "server"))]
+pub fn complete(prefix: &str) -> Res<Vec<String>> {
+    let data = data::get().chain_err(|| "while completing a label")?;
+    {
+        let index = INDEX
+            .read()
+            .map_err(|e| err::Error::from(e.to_string()))
+            .chain_err(|| "while reading the label index")?;
+        if let Some(index) = index.as_ref().filter(|index| index.is_up_to_date(&data)) {
+            return Ok(index.complete(prefix, MAX_COMPLETIONS));
+        }
+    }
+
+    let index = LabelIndex::new(&data);
+    log::info!("indexed {} distinct label(s)", index.len());
+    let completions = index.complete(prefix, MAX_COMPLETIONS);
+    *INDEX
+        .write()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while updating the label index")? = Some(index);
+    Ok(completions)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Builds some data where `(label, n)` labels `n` allocations, plus one allocation with no
+    /// label.
+    fn data(labels: &[(&str, usize)]) -> data::Data {
+        let mut synth = crate::fixture::Synth::new();
+        let trace = crate::fixture::trace(&[]);
+        let lists = labels
+            .iter()
+            .flat_map(|(label, n)| std::iter::repeat(crate::fixture::labels(&[label])).take(*n))
+            .chain(Some(crate::fixture::labels(&[])));
+
+        for (idx, labels) in lists.enumerate() {
+            synth.alloc(idx as u64, 8, &trace, &labels);
+        }
+        synth.done()
+    }
+
+    #[test]
+    fn most_common_labels_first() {
+        let data = data(&[
+            ("parse_int", 1),
+            ("parse_list", 3),
+            ("print", 5),
+            ("parse", 3),
+        ]);
+        let index = LabelIndex::new(&data);
+        assert_eq!(index.len(), 4);
+        assert!(index.is_up_to_date(&data));
+
+        assert_eq!(
+            index.complete("parse", MAX_COMPLETIONS),
+            vec!["parse", "parse_list", "parse_int"],
+        );
+        assert_eq!(index.complete("p", 2), vec!["print", "parse"]);
+        assert_eq!(index.complete("", 1), vec!["print"]);
+        assert!(index.complete("q", MAX_COMPLETIONS).is_empty());
+    }
+}
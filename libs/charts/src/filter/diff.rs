@@ -0,0 +1,465 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Differences between two versions of the filters.
+//!
+//! The client edits a copy of the filters and only sends it to the server on save. A [`Change`]
+//! describes one edit between the last version the server acknowledged and the one being edited,
+//! so that users can review (and revert) edits one by one before saving.
+//!
+//! [`Change`]: enum.Change.html (The Change enum)
+
+prelude! {}
+
+use filter::{Filter, FilterName, FilterSpec, Group, SubFilter};
+
+/// A version of the filters.
+#[derive(Debug, Clone, Copy)]
+pub struct Collection<'a> {
+    /// Everything filter.
+    pub everything: &'a FilterSpec,
+    /// Custom filters.
+    pub filters: &'a [Filter],
+    /// Catch-all filter.
+    pub catch_all: &'a FilterSpec,
+}
+
+impl<'a> Collection<'a> {
+    /// Constructor.
+    pub fn new(
+        everything: &'a FilterSpec,
+        filters: &'a [Filter],
+        catch_all: &'a FilterSpec,
+    ) -> Self {
+        Self {
+            everything,
+            filters,
+            catch_all,
+        }
+    }
+
+    /// Index and filter of a custom filter UID.
+    fn get(&self, uid: uid::Filter) -> Option<(usize, &'a Filter)> {
+        self.filters
+            .iter()
+            .enumerate()
+            .find(|(_, filter)| filter.uid() == uid)
+    }
+
+    /// Changes from `self` to `new`.
+    ///
+    /// Changes come in the order of the filters of `new`, followed by the removed filters in the
+    /// order of `self`.
+    pub fn diff(&self, new: &Self) -> Vec<Change> {
+        let mut changes = vec![];
+
+        Change::of_specs(self.everything, new.everything, &mut changes);
+
+        // Custom filters that exist in both versions, in the order of each version. A filter is
+        // moved if its position among them changed, so that adding or removing a filter does not
+        // show the filters after it as moved.
+        let kept = |of: &Self, other: &Self| -> Vec<uid::Filter> {
+            of.filters
+                .iter()
+                .map(Filter::uid)
+                .filter(|uid| other.get(*uid).is_some())
+                .collect()
+        };
+        let (old_kept, new_kept) = (kept(self, new), kept(new, self));
+
+        for (new_index, filter) in new.filters.iter().enumerate() {
+            let uid = filter.uid();
+            let (old_index, old) = if let Some(res) = self.get(uid) {
+                res
+            } else {
+                changes.push(Change::Added {
+                    uid,
+                    name: filter.name().clone(),
+                });
+                continue;
+            };
+
+            Change::of_specs(old.spec(), filter.spec(), &mut changes);
+
+            for sub in filter.iter() {
+                match old.iter().find(|old_sub| old_sub.uid() == sub.uid()) {
+                    None => changes.push(Change::SubAdded {
+                        uid,
+                        sub: sub.clone(),
+                    }),
+                    Some(old_sub) if old_sub.raw() != sub.raw() => {
+                        changes.push(Change::SubModified {
+                            uid,
+                            old: old_sub.clone(),
+                            new: sub.clone(),
+                        })
+                    }
+                    Some(_) => (),
+                }
+            }
+            for old_sub in old.iter() {
+                if filter.iter().all(|sub| sub.uid() != old_sub.uid()) {
+                    changes.push(Change::SubRemoved {
+                        uid,
+                        sub: old_sub.clone(),
+                    })
+                }
+            }
+            if old.root() != filter.root() {
+                changes.push(Change::Regrouped {
+                    uid,
+                    old: old.root().clone(),
+                    new: filter.root().clone(),
+                })
+            }
+            // Same as for filters, only the subfilters that exist in both versions matter.
+            let kept_subs = |of: &Filter, other: &Filter| -> Vec<uid::SubFilter> {
+                of.order()
+                    .into_iter()
+                    .filter(|sub| other.iter().any(|other_sub| other_sub.uid() == *sub))
+                    .collect()
+            };
+            if kept_subs(old, filter) != kept_subs(filter, old) {
+                changes.push(Change::SubsReordered {
+                    uid,
+                    old: old.order(),
+                })
+            }
+
+            let position = |kept: &[uid::Filter]| kept.iter().position(|kept| *kept == uid);
+            if position(&old_kept) != position(&new_kept) {
+                changes.push(Change::Moved {
+                    uid,
+                    old: old_index,
+                    new: new_index,
+                })
+            }
+        }
+
+        for (index, old) in self.filters.iter().enumerate() {
+            if new.get(old.uid()).is_none() {
+                changes.push(Change::Removed {
+                    index,
+                    filter: old.clone(),
+                })
+            }
+        }
+
+        Change::of_specs(self.catch_all, new.catch_all, &mut changes);
+
+        changes
+    }
+}
+
+/// A change between two versions of the filters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A new custom filter.
+    Added {
+        /// UID of the filter.
+        uid: uid::Filter,
+        /// Name of the filter.
+        name: FilterName,
+    },
+    /// A removed custom filter.
+    Removed {
+        /// Index of the filter in the old version.
+        index: usize,
+        /// Old version of the filter.
+        filter: Filter,
+    },
+    /// A renamed filter.
+    Renamed {
+        /// UID of the filter.
+        uid: uid::Line,
+        /// Old name.
+        old: FilterName,
+        /// New name.
+        new: FilterName,
+    },
+    /// A recolored filter.
+    Recolored {
+        /// UID of the filter.
+        uid: uid::Line,
+        /// Old color.
+        old: Color,
+        /// New color.
+        new: Color,
+    },
+    /// A new subfilter.
+    SubAdded {
+        /// UID of the filter.
+        uid: uid::Filter,
+        /// New subfilter.
+        sub: SubFilter,
+    },
+    /// A removed subfilter.
+    SubRemoved {
+        /// UID of the filter.
+        uid: uid::Filter,
+        /// Old subfilter.
+        sub: SubFilter,
+    },
+    /// A modified subfilter.
+    SubModified {
+        /// UID of the filter.
+        uid: uid::Filter,
+        /// Old version of the subfilter.
+        old: SubFilter,
+        /// New version of the subfilter.
+        new: SubFilter,
+    },
+    /// The groups of the subfilters of a filter changed.
+    Regrouped {
+        /// UID of the filter.
+        uid: uid::Filter,
+        /// Old root group.
+        old: Group,
+        /// New root group.
+        new: Group,
+    },
+    /// The subfilters of a filter changed order.
+    SubsReordered {
+        /// UID of the filter.
+        uid: uid::Filter,
+        /// Old order of the subfilters.
+        old: Vec<uid::SubFilter>,
+    },
+    /// A custom filter moved w.r.t. the other custom filters.
+    Moved {
+        /// UID of the filter.
+        uid: uid::Filter,
+        /// Old index.
+        old: usize,
+        /// New index.
+        new: usize,
+    },
+}
+
+impl Change {
+    /// Pushes the name and color changes between two specifications of the same filter.
+    fn of_specs(old: &FilterSpec, new: &FilterSpec, changes: &mut Vec<Change>) {
+        if old.name() != new.name() {
+            changes.push(Self::Renamed {
+                uid: new.uid(),
+                old: old.name().clone(),
+                new: new.name().clone(),
+            })
+        }
+        if old.color() != new.color() {
+            changes.push(Self::Recolored {
+                uid: new.uid(),
+                old: old.color().clone(),
+                new: new.color().clone(),
+            })
+        }
+    }
+
+    /// UID of the line the change is about.
+    pub fn line(&self) -> uid::Line {
+        match self {
+            Self::Renamed { uid, .. } | Self::Recolored { uid, .. } => *uid,
+            Self::Added { uid, .. }
+            | Self::SubAdded { uid, .. }
+            | Self::SubRemoved { uid, .. }
+            | Self::SubModified { uid, .. }
+            | Self::Regrouped { uid, .. }
+            | Self::SubsReordered { uid, .. }
+            | Self::Moved { uid, .. } => uid::Line::Filter(*uid),
+            Self::Removed { filter, .. } => uid::Line::Filter(filter.uid()),
+        }
+    }
+}
+
+base::implement! {
+    impl Change {
+        Display {
+            |&self, fmt| match self {
+                Self::Added { name, .. } => write!(fmt, "new filter `{}`", name),
+                Self::Removed { filter, .. } => write!(fmt, "removed filter `{}`", filter.name()),
+                Self::Renamed { old, new, .. } => write!(fmt, "renamed `{}` to `{}`", old, new),
+                Self::Recolored { old, new, .. } => write!(fmt, "recolored from {} to {}", old, new),
+                Self::SubAdded { sub, .. } => write!(fmt, "new subfilter `{}`", sub.raw()),
+                Self::SubRemoved { sub, .. } => write!(fmt, "removed subfilter `{}`", sub.raw()),
+                Self::SubModified { old, new, .. } => {
+                    write!(fmt, "subfilter `{}` is now `{}`", old.raw(), new.raw())
+                }
+                Self::Regrouped { .. } => write!(fmt, "regrouped subfilters"),
+                Self::SubsReordered { .. } => write!(fmt, "reordered subfilters"),
+                Self::Moved { old, new, .. } => {
+                    write!(fmt, "moved from position {} to {}", old + 1, new + 1)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use filter::{ord::Cmp, sub::RawSubFilter, GroupOp, SizeFilter};
+
+    fn size_geq(val: u32) -> RawSubFilter {
+        SizeFilter::Cmp { cmp: Cmp::Ge, val }.into()
+    }
+
+    fn new_filter(name: &str) -> Filter {
+        let mut filter = Filter::new(FilterSpec::new(Color::new(0, 0, 0))).unwrap();
+        filter.spec_mut().set_name(FilterName::new(name).unwrap());
+        filter.insert(size_geq(10)).unwrap();
+        filter
+    }
+
+    #[test]
+    fn changes() {
+        let (everything, catch_all) = (FilterSpec::new_everything(), FilterSpec::new_catch_all());
+        let (a, b, c) = (new_filter("a"), new_filter("b"), new_filter("c"));
+        let old = vec![a.clone(), b.clone(), c.clone()];
+        let old = Collection::new(&everything, &old, &catch_all);
+
+        assert!(old.diff(&old).is_empty());
+
+        // Removing `a` does not move `b` and `c`.
+        let new = vec![b.clone(), c.clone()];
+        let changes = old.diff(&Collection::new(&everything, &new, &catch_all));
+        assert_eq!(
+            changes,
+            vec![Change::Removed {
+                index: 0,
+                filter: a.clone()
+            }]
+        );
+
+        // Swapping `b` and `c`, editing `c` and adding `d`.
+        let mut new_c = c.clone();
+        new_c.spec_mut().set_name(FilterName::new("c'").unwrap());
+        new_c.spec_mut().set_color(Color::new(0xff, 0, 0));
+        let old_sub = new_c.iter().next().unwrap().clone();
+        let mut new_sub = old_sub.clone();
+        *new_sub = size_geq(42);
+        new_c.replace(new_sub.clone()).unwrap();
+        let added_sub: SubFilter = size_geq(7).into();
+        new_c.insert(added_sub.clone()).unwrap();
+        let d = new_filter("d");
+        let new = vec![a.clone(), new_c.clone(), b.clone(), d.clone()];
+        let changes = old.diff(&Collection::new(&everything, &new, &catch_all));
+        assert_eq!(
+            changes,
+            vec![
+                Change::Renamed {
+                    uid: new_c.spec().uid(),
+                    old: c.name().clone(),
+                    new: new_c.name().clone(),
+                },
+                Change::Recolored {
+                    uid: new_c.spec().uid(),
+                    old: Color::new(0, 0, 0),
+                    new: Color::new(0xff, 0, 0),
+                },
+                Change::SubModified {
+                    uid: c.uid(),
+                    old: old_sub.clone(),
+                    new: new_sub.clone(),
+                },
+                Change::SubAdded {
+                    uid: c.uid(),
+                    sub: added_sub,
+                },
+                Change::Moved {
+                    uid: c.uid(),
+                    old: 2,
+                    new: 1,
+                },
+                Change::Moved {
+                    uid: b.uid(),
+                    old: 1,
+                    new: 2,
+                },
+                Change::Added {
+                    uid: d.uid(),
+                    name: d.name().clone(),
+                },
+            ]
+        );
+        assert_eq!(
+            changes[2].to_string(),
+            "subfilter `size ≥ 10` is now `size ≥ 42`"
+        );
+
+        // Grouping the subfilter of `a`.
+        let mut grouped_a = a.clone();
+        let sub_uid = grouped_a.iter().next().unwrap().uid();
+        grouped_a.add_group(&[], GroupOp::Or).unwrap();
+        grouped_a.move_sub(sub_uid, &[0]).unwrap();
+        let new = vec![grouped_a.clone(), b.clone(), c.clone()];
+        let changes = old.diff(&Collection::new(&everything, &new, &catch_all));
+        assert_eq!(
+            changes,
+            vec![Change::Regrouped {
+                uid: a.uid(),
+                old: a.root().clone(),
+                new: grouped_a.root().clone(),
+            }]
+        );
+
+        // Reordering the subfilters of `c`, adding one does not reorder the others.
+        let mut reordered_c = new_c.clone();
+        let (first, second) = (new_c.order()[0], new_c.order()[1]);
+        assert!(reordered_c.shift_sub(second, true).unwrap());
+        let new = vec![a.clone(), b.clone(), reordered_c.clone()];
+        let changes = old.diff(&Collection::new(&everything, &new, &catch_all));
+        assert!(changes.iter().all(|change| match change {
+            Change::SubsReordered { .. } => false,
+            _ => true,
+        }));
+        let old_c = vec![a.clone(), b.clone(), new_c.clone()];
+        let changes = Collection::new(&everything, &old_c, &catch_all).diff(&Collection::new(
+            &everything,
+            &new,
+            &catch_all,
+        ));
+        assert_eq!(
+            changes,
+            vec![Change::SubsReordered {
+                uid: c.uid(),
+                old: vec![first, second],
+            }]
+        );
+
+        // Removing a subfilter of `a` and recoloring the catch-all.
+        let mut new_catch_all = catch_all.clone();
+        new_catch_all.set_color(Color::new(0, 0xff, 0));
+        let mut new_a = a.clone();
+        let sub = new_a.iter().next().unwrap().clone();
+        new_a.remove(sub.uid()).unwrap();
+        let new = vec![new_a, b, c];
+        let changes = old.diff(&Collection::new(&everything, &new, &new_catch_all));
+        assert_eq!(
+            changes,
+            vec![
+                Change::SubRemoved { uid: a.uid(), sub },
+                Change::Recolored {
+                    uid: uid::Line::CatchAll,
+                    old: catch_all.color().clone(),
+                    new: Color::new(0, 0xff, 0),
+                },
+            ]
+        );
+    }
+}
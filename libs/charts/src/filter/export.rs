@@ -0,0 +1,174 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Filter export and import.
+//!
+//! An [`Export`] is the catch-all specification and the custom filters, as a JSON document users
+//! can keep around and import in a later session. The *everything* line is not part of it, it does
+//! not depend on the filters.
+//!
+//! The UIDs of an export are meaningless in another session: the server [refreshes] them on import
+//! so that they do not collide with the UIDs of the current session.
+//!
+//! [`Export`]: struct.Export.html (The Export struct)
+//! [refreshes]: struct.Export.html#method.refresh_uids (The refresh_uids method)
+
+prelude! {}
+
+use filter::{Filter, FilterSpec};
+
+/// Filter definitions, as exported by users.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Export {
+    /// Specification of the catch-all filter.
+    pub catch_all: FilterSpec,
+    /// Custom filters.
+    pub filters: Vec<Filter>,
+}
+
+impl Export {
+    /// Constructor.
+    pub fn new(catch_all: FilterSpec, filters: Vec<Filter>) -> Self {
+        Self { catch_all, filters }
+    }
+
+    /// JSON version of the filters.
+    pub fn to_json(&self) -> Res<String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("while encoding the filters: {}", e))?;
+        Ok(json)
+    }
+
+    /// Parses and checks some JSON filters.
+    ///
+    /// Fails on malformed regular expressions, and on filters that are not consistent.
+    ///
+    /// ```rust
+    /// # use charts::color::Color;
+    /// use charts::filter::{Export, Filter, FilterSpec};
+    /// let filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+    /// let export = Export::new(FilterSpec::new_catch_all(), vec![filter]);
+    /// let json = export.to_json().unwrap();
+    /// assert_eq!(Export::from_json(&json).unwrap(), export);
+    ///
+    /// // The catch-all specification must be one.
+    /// let bad = Export::new(FilterSpec::new_everything(), vec![]);
+    /// assert!(Export::from_json(&bad.to_json().unwrap()).is_err());
+    /// ```
+    pub fn from_json(json: &str) -> Res<Self> {
+        let slf: Self =
+            serde_json::from_str(json).map_err(|e| format!("illegal filter definitions: {}", e))?;
+        slf.check()?;
+        Ok(slf)
+    }
+
+    /// Checks the filters are consistent.
+    ///
+    /// Done by [`from_json`], and when restoring a [session].
+    ///
+    /// [`from_json`]: #method.from_json (The from_json method)
+    /// [session]: ../../session/index.html (The session module)
+    pub fn check(&self) -> Res<()> {
+        if !self.catch_all.is_catch_all() {
+            bail!(
+                "illegal filter definitions: expected catch-all specification, found `{}`",
+                self.catch_all.name()
+            )
+        }
+        let mut uids = BTSet::new();
+        for filter in &self.filters {
+            let uid = match filter.spec().uid() {
+                uid::Line::Filter(uid) => uid,
+                uid::Line::CatchAll | uid::Line::Everything | uid::Line::Segment(_) => bail!(
+                    "illegal filter definitions: filter `{}` is not a custom filter",
+                    filter.name()
+                ),
+            };
+            if !uids.insert(uid) {
+                bail!("illegal filter definitions: two filters have UID #{}", uid)
+            }
+            for (sub_uid, sub) in &filter.subs {
+                if *sub_uid != sub.uid() {
+                    bail!(
+                        "illegal filter definitions: inconsistent subfilter UIDs in filter `{}`",
+                        filter.name()
+                    )
+                }
+            }
+            let mut ordered = BTSet::new();
+            for sub_uid in &filter.order {
+                if !filter.subs.contains_key(sub_uid) || !ordered.insert(*sub_uid) {
+                    bail!(
+                        "illegal filter definitions: inconsistent subfilter order in filter `{}`",
+                        filter.name()
+                    )
+                }
+            }
+            filter
+                .check_groups()
+                .chain_err(|| "illegal filter definitions")?;
+        }
+        Ok(())
+    }
+
+    /// Gives fresh UIDs to all the filters and their subfilters.
+    pub fn refresh_uids(&mut self) {
+        for filter in &mut self.filters {
+            filter.refresh_uids()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn malformed_regex() {
+        let mut filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        filter
+            .insert(filter::LabelFilter::contain(vec![
+                filter::label::LabelSpec::new("#\"a+\"#").unwrap(),
+            ]))
+            .unwrap();
+        let json = Export::new(FilterSpec::new_catch_all(), vec![filter])
+            .to_json()
+            .unwrap();
+        assert!(json.contains("a+"));
+        assert!(Export::from_json(&json).is_ok());
+        assert!(Export::from_json(&json.replace("a+", "a(")).is_err());
+    }
+
+    #[test]
+    fn refreshed_uids_do_not_collide() {
+        let mut filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        filter.insert(filter::SubFilter::default()).unwrap();
+        let mut export = Export::new(FilterSpec::new_catch_all(), vec![filter.clone()]);
+
+        export.refresh_uids();
+        let imported = &export.filters[0];
+        assert_ne!(imported.uid(), filter.uid());
+        assert_eq!(imported.name(), filter.name());
+        assert_eq!(imported.iter().count(), 1);
+        for sub in imported.iter() {
+            assert!(filter.iter().all(|old| old.uid() != sub.uid()));
+        }
+        assert!(export.check().is_ok());
+    }
+}
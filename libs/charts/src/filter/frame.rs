@@ -0,0 +1,279 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Frame filters.
+//!
+//! A frame filter checks whether the callstack of an allocation has a frame in some file, and
+//! optionally in some range of lines. Unlike [location filters], it does not describe the shape of
+//! the whole callstack, which makes it simpler for questions such as *"allocations with a frame
+//! from `src/parser/` between lines 100 and 200"*.
+//!
+//! [location filters]: ../loc/type.LocFilter.html (The LocFilter type)
+
+prelude! {}
+
+use filter::string_like::Pred;
+
+/// A frame predicate: contain or exclude.
+pub type FramePred = Pred;
+
+/// Path specification of a frame filter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PathSpec {
+    /// Matches a precise path.
+    Exact(String),
+    /// Matches the paths starting with some string.
+    Prefix(String),
+    /// Matches the paths a regex matches.
+    Regex(#[serde(with = "serde_regex")] Regex),
+}
+impl std::cmp::PartialEq for PathSpec {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Exact(lft), Self::Exact(rgt)) => lft == rgt,
+            (Self::Prefix(lft), Self::Prefix(rgt)) => lft == rgt,
+            (Self::Regex(lft), Self::Regex(rgt)) => lft.as_str() == rgt.as_str(),
+            (Self::Exact(_), _) | (Self::Prefix(_), _) | (Self::Regex(_), _) => false,
+        }
+    }
+}
+impl std::cmp::Eq for PathSpec {}
+
+impl Default for PathSpec {
+    fn default() -> Self {
+        Self::Prefix("src/".into())
+    }
+}
+
+impl fmt::Display for PathSpec {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Exact(path) => path.fmt(fmt),
+            Self::Prefix(prefix) => write!(fmt, "{}*", prefix),
+            Self::Regex(regex) => write!(fmt, "#\"{}\"#", regex),
+        }
+    }
+}
+
+impl PathSpec {
+    /// Constructor from strings.
+    ///
+    /// Same convention as [label specifications] for regexes, and a trailing `*` denotes a prefix.
+    ///
+    /// ```rust
+    /// use charts::filter::frame::PathSpec;
+    /// let exact = PathSpec::new("src/parser/lexer.ml").unwrap();
+    /// assert!(exact.matches("src/parser/lexer.ml"));
+    /// assert!(!exact.matches("src/parser/lexer.mli"));
+    ///
+    /// let prefix = PathSpec::new(" src/parser/* ").unwrap();
+    /// assert_eq!(prefix.to_string(), "src/parser/*");
+    /// assert!(prefix.matches("src/parser/lexer.ml"));
+    /// assert!(!prefix.matches("src/main.ml"));
+    ///
+    /// let regex = PathSpec::new(r##"#"^src/.*\.ml$"#"##).unwrap();
+    /// assert!(regex.matches("src/main.ml"));
+    /// assert!(!regex.matches("src/main.mli"));
+    ///
+    /// assert!(PathSpec::new(r##"#"src/("#"##).is_err());
+    /// assert!(PathSpec::new("").is_err());
+    /// ```
+    ///
+    /// [label specifications]: ../label/enum.LabelSpec.html#method.new (LabelSpec's constructor)
+    pub fn new(s: impl AsRef<str>) -> Res<Self> {
+        let path = s.as_ref().trim();
+        if path.is_empty() {
+            bail!("illegal empty path specification")
+        }
+        if path.len() > 2 && &path[0..2] == "#\"" {
+            if &path[path.len() - 2..] != "\"#" {
+                bail!("illegal regex `{}`, a regex must end with `\"#`", path)
+            }
+            let regex = Regex::new(&path[2..path.len() - 2])
+                .chain_err(|| format!("illegal regex `{}`", path))?;
+            Ok(Self::Regex(regex))
+        } else if path.ends_with('*') {
+            Ok(Self::Prefix(path[0..path.len() - 1].into()))
+        } else {
+            Ok(Self::Exact(path.into()))
+        }
+    }
+
+    /// True if the specification matches a path.
+    pub fn matches(&self, path: &str) -> bool {
+        match self {
+            Self::Exact(exact) => path == exact,
+            Self::Prefix(prefix) => path.starts_with(prefix.as_str()),
+            Self::Regex(regex) => regex.is_match(path),
+        }
+    }
+}
+
+/// A frame filter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameFilter {
+    /// Predicate.
+    pub pred: FramePred,
+    /// Path of the frame.
+    pub path: PathSpec,
+    /// Optional lower bound on the line of the frame, inclusive.
+    pub line_lb: Option<usize>,
+    /// Optional upper bound on the line of the frame, inclusive.
+    pub line_ub: Option<usize>,
+}
+
+impl Default for FrameFilter {
+    fn default() -> Self {
+        Self::new(Pred::Contain, PathSpec::default(), None, None)
+    }
+}
+
+impl fmt::Display for FrameFilter {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} a frame in {}", self.pred, self.path)?;
+        match (self.line_lb, self.line_ub) {
+            (None, None) => Ok(()),
+            (lb, ub) => write!(
+                fmt,
+                " at line [{}, {}]",
+                lb.map(|lb| lb.to_string()).unwrap_or_else(|| "_".into()),
+                ub.map(|ub| ub.to_string()).unwrap_or_else(|| "_".into()),
+            ),
+        }
+    }
+}
+
+impl FrameFilter {
+    /// Constructor.
+    pub fn new(
+        pred: FramePred,
+        path: PathSpec,
+        line_lb: Option<usize>,
+        line_ub: Option<usize>,
+    ) -> Self {
+        Self {
+            pred,
+            path,
+            line_lb,
+            line_ub,
+        }
+    }
+
+    /// True if a frame matches the path and lines of the filter.
+    pub fn matches(&self, frame: &alloc::CLoc) -> bool {
+        let line = frame.loc.line;
+        self.line_lb.map(|lb| lb <= line).unwrap_or(true)
+            && self.line_ub.map(|ub| line <= ub).unwrap_or(true)
+            && frame.loc.file.str_do(|file| self.path.matches(file))
+    }
+
+    /// Updates the filter.
+    ///
+    /// Returns `true` iff the filter changed.
+    pub fn update(&mut self, update: FrameUpdate) -> Res<bool> {
+        let changed = match update {
+            FrameUpdate::Pred(pred) => std::mem::replace(&mut self.pred, pred) != pred,
+            FrameUpdate::Path(path) => {
+                let path = PathSpec::new(path).chain_err(|| "while updating frame filter")?;
+                std::mem::replace(&mut self.path, path) != self.path
+            }
+            FrameUpdate::LineLb(lb) => std::mem::replace(&mut self.line_lb, lb) != lb,
+            FrameUpdate::LineUb(ub) => std::mem::replace(&mut self.line_ub, ub) != ub,
+        };
+        Ok(changed)
+    }
+}
+
+impl filter::FilterExt<[alloc::CLoc]> for FrameFilter {
+    /// Applies the filter to a callstack.
+    ///
+    /// An empty callstack has no frame, a *contain* filter never matches it.
+    fn apply(&self, trace: &[alloc::CLoc]) -> bool {
+        let res = trace.iter().any(|frame| self.matches(frame));
+        match self.pred {
+            Pred::Contain => res,
+            Pred::Exclude => !res,
+        }
+    }
+}
+
+/// An update for a frame filter.
+pub enum FrameUpdate {
+    /// Changes the predicate.
+    Pred(FramePred),
+    /// Changes the path, parsed with [`PathSpec::new`].
+    ///
+    /// [`PathSpec::new`]: enum.PathSpec.html#method.new (PathSpec's constructor)
+    Path(String),
+    /// Changes the lower bound on lines.
+    LineLb(Option<usize>),
+    /// Changes the upper bound on lines.
+    LineUb(Option<usize>),
+}
+impl fmt::Display for FrameUpdate {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Pred(pred) => write!(fmt, "pred <- {}", pred),
+            Self::Path(path) => write!(fmt, "path <- {}", path),
+            Self::LineLb(lb) => write!(fmt, "line lbound <- {:?}", lb),
+            Self::LineUb(ub) => write!(fmt, "line ubound <- {:?}", ub),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use filter::FilterExt;
+
+    #[test]
+    fn frames() {
+        let mut factory = alloc::mem::Factory::new(false);
+        let mut frame = |file: &str, line| {
+            let file = factory.register_str(file);
+            alloc::CLoc::new(
+                alloc::Loc::new(
+                    file,
+                    line,
+                    alloc::Span {
+                        lbound: 0,
+                        ubound: 1,
+                    },
+                ),
+                1,
+            )
+        };
+        let trace = vec![frame("src/main.ml", 7), frame("src/parser/lexer.ml", 150)];
+        let other = vec![frame("src/parser/lexer.ml", 250)];
+        // Releases the factory locks, needed to access strings.
+        drop(factory);
+        let path = PathSpec::new("src/parser/*").unwrap();
+
+        let contain = FrameFilter::new(Pred::Contain, path.clone(), Some(100), Some(200));
+        assert!(contain.apply(&trace));
+        assert!(!contain.apply(&other));
+        // No location data, no frame to contain.
+        assert!(!contain.apply(&[]));
+
+        let exclude = FrameFilter::new(Pred::Exclude, path, Some(100), None);
+        assert!(!exclude.apply(&trace));
+        assert!(!exclude.apply(&other));
+        assert!(exclude.apply(&[]));
+    }
+}
@@ -23,9 +23,13 @@
 //! parameters (if any) for an actual filter-generation strategy. The following functions allow to
 //! interact with this global configuration: [`get`], [`set`], and [`set_from_cla`].
 //!
+//! The result of the active generator does not depend on the client, so it is computed once per
+//! version of the data and [`share`]d by all clients.
+//!
 //! # Filter Generation Strategies
 //!
-//! Strategies are defined in sub-modules such as [`inactive`] and [`alloc_site`]. They typically
+//! Strategies are defined in sub-modules such as [`inactive`], [`alloc_site`] and [`top_sites`].
+//! They typically
 //! define a unit-struct (*e.g.* [`AllocSite`]) implementing the [`FilterGenExt` trait][ext] which
 //! defines a parameter type [`Params`] among other things.
 //!
@@ -39,16 +43,36 @@
 //!
 //! Filter generation also handles *chart generation*, which is is the [`chart_gen` module].
 //!
+//! # Progress and Cancellation
+//!
+//! Generators run with a [`GenCxt`] they use to report their progress and check whether they have
+//! been cancelled, typically every [`BATCH_SIZE`] allocations. Generators must not have any side
+//! effect: they produce filters and charts in a staging area, which is only committed by the
+//! caller once the generator is done. Hence, a cancelled generator leaves no partial filters
+//! behind.
+//!
+//! # Limits
+//!
+//! The context also stores the [limits] over the number of filters and charts. Generators keep
+//! the filters that matter most when there is not enough room for all of them, and
+//! [`FilterGen::run_with`] drops the charts that do not fit. Both log a warning.
+//!
 //! [`FilterGen`]: enum.FilterGen.html (FilterGen enum)
 //! [`get`]: fn.get.html (get function)
 //! [`set`]: fn.set.html (set function)
 //! [`set_from_cla`]: fn.set_from_cla.html (set_from_cla function)
+//! [`share`]: fn.share.html (share function)
 //! [`inactive`]: ./inactive (inactive module)
 //! [`alloc_site`]: ./alloc_site (alloc_site module)
+//! [`top_sites`]: ./top_sites (top_sites module)
 //! [`AllocSite`]: ./alloc_site/struct.AllocSite.html (AllocSite struct)
 //! [ext]: trait.FilterGenExt.html (FilterGenExt trait)
 //! [`Params`]: trait.FilterGenExt.html#associatedtype.Params (FilterGenExt trait)
 //! [`chart_gen` module]: ./chart_gen (chart_gen module)
+//! [`GenCxt`]: struct.GenCxt.html (GenCxt struct)
+//! [`BATCH_SIZE`]: constant.BATCH_SIZE.html (BATCH_SIZE constant)
+//! [limits]: ../../limits/struct.Limits.html (Limits struct)
+//! [`FilterGen::run_with`]: enum.FilterGen.html#method.run_with (run_with method on FilterGen)
 
 prelude! {}
 
@@ -57,11 +81,13 @@ pub mod parser;
 pub mod alloc_site;
 pub mod chart_gen;
 pub mod inactive;
+pub mod top_sites;
 
 use self::{
     alloc_site::{AllocSite, AllocSiteParams},
     inactive::Inactive,
     parser::Parser,
+    top_sites::{TopSites, TopSitesParams},
 };
 
 /// Retrieves the active filter generator.
@@ -92,12 +118,165 @@ pub fn set_from_cla(args: &str) -> Res<()> {
     Ok(())
 }
 
+/// Result of the active filter generator on some version of the data.
+struct Shared {
+    /// Generation of the data the generator ran on.
+    data_generation: u64,
+    /// Generated filters.
+    filters: Filters,
+    /// Generated charts.
+    charts: Vec<chart_gen::ChartDesc>,
+}
+
+/// Retrieves the result of the active filter generator on some data, if any.
+///
+/// `None` if the generator did not run on this version of the data, see [`share`].
+///
+/// [`share`]: fn.share.html (share function)
+pub fn shared(data: &data::Data) -> Res<Option<(Filters, Vec<chart::Chart>)>> {
+    let shared = SHARED
+        .read()
+        .expect("global shared filter generation was poisoned");
+    let shared = match shared.as_ref() {
+        Some(shared) if shared.data_generation == data.generation() => shared,
+        _ => return Ok(None),
+    };
+    let filters = shared.filters.clone();
+    let charts = shared
+        .charts
+        .iter()
+        .map(|desc| desc.clone().into_chart(&filters))
+        .collect::<Res<_>>()?;
+    Ok(Some((filters, charts)))
+}
+
+/// Remembers the result of the active filter generator on some data.
+///
+/// All clients share this result as long as the data does not change, so that the generator only
+/// runs once.
+pub fn share(data: &data::Data, filters: &Filters, charts: &[chart::Chart]) {
+    let charts = charts
+        .iter()
+        .map(|chart| chart_gen::ChartDesc {
+            title: Some(chart.settings().title().to_string()),
+            spec: chart.spec().clone(),
+        })
+        .collect();
+    let mut shared = SHARED
+        .write()
+        .expect("global shared filter generation was poisoned");
+    *shared = Some(Shared {
+        data_generation: data.generation(),
+        filters: filters.clone(),
+        charts,
+    })
+}
+
+/// Number of allocations generators should handle between two progress/cancellation checks.
+pub const BATCH_SIZE: usize = 10_000;
+
+/// Cancellation token for filter generation.
+///
+/// Clones of a token share the same cancellation flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    /// Cancellation flag.
+    flag: sync::Arc<sync::atomic::AtomicBool>,
+}
+impl CancelToken {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the generation.
+    pub fn cancel(&self) {
+        self.flag.store(true, sync::atomic::Ordering::SeqCst)
+    }
+
+    /// True if the generation was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(sync::atomic::Ordering::SeqCst)
+    }
+}
+
+/// Filter generation context.
+///
+/// Handles progress reporting, cancellation, and the limits over the number of filters and charts.
+pub struct GenCxt<'a> {
+    /// Cancellation token.
+    token: CancelToken,
+    /// Progress callback.
+    progress: Box<dyn FnMut(GenProgress) + 'a>,
+    /// Limits the generated filters and charts must fit in.
+    limits: limits::Limits,
+}
+impl<'a> GenCxt<'a> {
+    /// Constructor, uses the limits of the server.
+    pub fn new(token: CancelToken, progress: impl FnMut(GenProgress) + 'a) -> Self {
+        Self {
+            token,
+            progress: Box::new(progress),
+            limits: limits::get(),
+        }
+    }
+
+    /// Sets the limits the generated filters and charts must fit in.
+    pub fn with_limits(mut self, limits: limits::Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Limits the generated filters and charts must fit in.
+    pub fn limits(&self) -> limits::Limits {
+        self.limits
+    }
+
+    /// Context that ignores progress and cannot be cancelled.
+    pub fn silent() -> Self {
+        Self::new(CancelToken::new(), |_| ())
+    }
+
+    /// Reports some progress.
+    pub fn progress(&mut self, fraction: f32, phase: impl Into<String>) {
+        (self.progress)(GenProgress::new(fraction, phase))
+    }
+
+    /// True if the generation was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Reports progress over a batch of allocations, returns `true` if the generation was
+    /// cancelled.
+    ///
+    /// Only does something every [`BATCH_SIZE`] allocations: `count` is the number of allocations
+    /// handled so far, and `total` the total number of allocations to handle.
+    ///
+    /// [`BATCH_SIZE`]: constant.BATCH_SIZE.html (BATCH_SIZE constant)
+    pub fn batch_check(&mut self, count: usize, total: usize, phase: &str) -> bool {
+        if count % BATCH_SIZE == 0 {
+            let fraction = if total == 0 {
+                1.
+            } else {
+                (count as f32) / (total as f32)
+            };
+            self.progress(fraction, phase);
+            self.is_cancelled()
+        } else {
+            false
+        }
+    }
+}
+
 lazy_static! {
     /// Stores the active filter generator.
     ///
     /// This is currently written once during CLAP.
     static ref ACTIVE_GEN: sync::RwLock<FilterGen> =
         sync::RwLock::new(FilterGen::default());
+    /// Result of the active filter generator, shared by all clients.
+    static ref SHARED: sync::RwLock<Option<Shared>> = sync::RwLock::new(None);
 }
 
 /// Enumeration of the filter generation techniques.
@@ -109,6 +288,8 @@ lazy_static! {
 pub enum FilterGen {
     /// Generate one allocation filter per allocation site.
     AllocSite(AllocSiteParams),
+    /// Generate one allocation filter for each of the top allocation sites.
+    TopSites(TopSitesParams),
     /// No filter generation.
     Inactive,
 }
@@ -117,6 +298,11 @@ impl From<AllocSiteParams> for FilterGen {
         Self::AllocSite(params)
     }
 }
+impl From<TopSitesParams> for FilterGen {
+    fn from(params: TopSitesParams) -> Self {
+        Self::TopSites(params)
+    }
+}
 
 impl Default for FilterGen {
     fn default() -> Self {
@@ -134,6 +320,7 @@ macro_rules! all_gens {
         [
             $($pref)* Inactive $($suff)*,
             $($pref)* AllocSite $($suff)*,
+            $($pref)* TopSites $($suff)*,
         ]
     };
     // Generates an array `[ ... ]` token tree. Its elements are the names of the generators, each
@@ -146,10 +333,52 @@ macro_rules! all_gens {
 impl FilterGen {
     /// Runs the filter generator represented by `self` on some data.
     pub fn run(self, data: &data::Data) -> Res<(Filters, Vec<chart::Chart>)> {
-        match self {
-            Self::AllocSite(params) => AllocSite::work(data, params),
-            Self::Inactive => Inactive::work(data, ()),
+        self.run_with(data, &mut GenCxt::silent())?
+            .ok_or_else(|| "filter generation was cancelled".into())
+    }
+
+    /// Runs the filter generator represented by `self` on some data, with a context.
+    ///
+    /// Returns `None` if the generation was cancelled, including while the generator was producing
+    /// its charts, unless the generator is inactive. Drops the last charts if there are more than
+    /// the limits of the context allow.
+    pub fn run_with(
+        self,
+        data: &data::Data,
+        cxt: &mut GenCxt,
+    ) -> Res<Option<(Filters, Vec<chart::Chart>)>> {
+        let cancellable = !matches!(self, Self::Inactive);
+        let mut res = match self {
+            Self::AllocSite(params) => AllocSite::work(data, params, cxt),
+            Self::TopSites(params) => TopSites::work(data, params, cxt),
+            Self::Inactive => Inactive::work(data, (), cxt),
+        }?;
+        if cancellable && cxt.is_cancelled() {
+            return Ok(None);
         }
+        if let Some((filters, charts)) = res.as_mut() {
+            let max = cxt.limits().filters;
+            if filters.len() > max {
+                bail!(
+                    "filter generation produced {} filters, but the limit is {}",
+                    filters.len(),
+                    max,
+                )
+            }
+            let max = cxt.limits().charts;
+            if charts.len() > max {
+                log::warn!(
+                    "filter generation produced {} charts, only keeping the first {} \
+                    (raise the limit with `{}`)",
+                    charts.len(),
+                    max,
+                    limits::Limits::CHARTS_FLAG,
+                );
+                charts.truncate(max)
+            }
+            cxt.progress(1., "done")
+        }
+        Ok(res)
     }
 
     /// List of all the filter-generator keys.
@@ -181,7 +410,7 @@ among several generators and parameterize them.
 (Show this help message at any time with `--filter_gen help`.)
 
 This flag takes a string argument, which has shapes
-- `<gen> {{ <params> }}`: use generator `<gen>` with parameters `<params>`, or
+- `<gen> {{ <params> }}` or `<gen>(<params>)`: use generator `<gen>` with parameters `<params>`, or
 - `<gen>`: use generator `<gen>` in its default mode.
 
 The different generators are
@@ -199,8 +428,8 @@ The different generators are
 
     /// Generates itself from arguments for the `--filter_gen` flag.
     ///
-    /// The expected format of the arguments is `<gen_key> { <gen_params> }` or just `<gen_key>`,
-    /// where
+    /// The expected format of the arguments is `<gen_key> { <gen_params> }`,
+    /// `<gen_key>(<gen_params>)`, or just `<gen_key>`, where
     ///
     /// - `<gen_key>` must be a [`KEY`] identifier corresponding to one of the generators, and
     /// - `<gen_params>`, if any, is a generator-specific parameter specification; this
@@ -241,7 +470,7 @@ The different generators are
                 )
             } else {
                 bail!(
-                    "expected block `{{ ... }}` of parameters or nothing after key `{}`",
+                    "expected block `{{ ... }}` or `( ... )` of parameters or nothing after key `{}`",
                     key
                 )
             }
@@ -269,7 +498,8 @@ The different generators are
 /// - [`add_help`]: generate the part of the help message for `--filter_gen` specific to this filter
 ///   generator,
 /// - [`KEY`], [`FMT`]: generate relevant error messages during CLAP, and
-/// - [`work`] actually run the generator.
+/// - [`work`] actually run the generator, reporting progress and checking for cancellation with
+///   its [`GenCxt`].
 ///
 /// [`FilterGen`]: enum.FilterGen.html (FilterGen enum)
 /// [`parse_args`]: #tymethod.parse_args (parse_args abstract method)
@@ -277,6 +507,7 @@ The different generators are
 /// [`add_help`]: #tymethod.add_help (add_help abstract method)
 /// [`FMT`]: #associatedconstant.FMT (FMT abstract constant)
 /// [`work`]: #tymethod.work (work abstract method)
+/// [`GenCxt`]: struct.GenCxt.html (GenCxt struct)
 pub trait FilterGenExt {
     /// Type of the parameters of the filter generator.
     type Params: Default;
@@ -300,5 +531,190 @@ pub trait FilterGenExt {
     fn add_help(s: &mut String);
 
     /// Runs the generator on some data given some parameters.
-    fn work(data: &data::Data, params: Self::Params) -> Res<(Filters, Vec<chart::Chart>)>;
+    ///
+    /// Returns `None` if the generation was cancelled. Implementations should check for
+    /// cancellation between batches of allocations, see [`GenCxt::batch_check`].
+    ///
+    /// [`GenCxt::batch_check`]: struct.GenCxt.html#method.batch_check (batch_check method)
+    fn work(
+        data: &data::Data,
+        params: Self::Params,
+        cxt: &mut GenCxt,
+    ) -> Res<Option<(Filters, Vec<chart::Chart>)>>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Builds some data with `count` allocations spread over `files` allocation-site files.
+    ///
+    /// Files go by pairs in the same directory, so that chart generation produces one chart per
+    /// pair of files.
+    fn data(count: usize, files: usize) -> data::Data {
+        let mut synth = crate::fixture::Synth::new();
+        let traces: Vec<_> = (0..files)
+            .map(|idx| {
+                let file = format!("dir_{}/file_{}.ml", idx / 2, idx);
+                crate::fixture::trace(&[(&file, 1)])
+            })
+            .collect();
+        let labels = crate::fixture::labels(&[]);
+
+        for idx in 0..count {
+            synth.alloc(idx as u64, 8, &traces[idx % files], &labels);
+        }
+
+        synth.done()
+    }
+
+    #[test]
+    fn alloc_site_completes() {
+        let data = data(3 * BATCH_SIZE, 3);
+        let mut progress = vec![];
+        let res = {
+            let mut cxt = GenCxt::new(CancelToken::new(), |p| progress.push(p));
+            FilterGen::default().run_with(&data, &mut cxt).unwrap()
+        };
+        let (filters, _charts) = res.expect("generation was not cancelled");
+        assert_eq!(filters.filters().len(), 3);
+        assert!(progress.len() > 1);
+        assert_eq!(progress.last().map(|p| p.fraction), Some(1.));
+    }
+
+    #[test]
+    fn alloc_site_cancellation() {
+        let data = data(3 * BATCH_SIZE, 3);
+        let token = CancelToken::new();
+        let mut max_fraction = 0.;
+        let res = {
+            let canceller = token.clone();
+            let mut cxt = GenCxt::new(token.clone(), |p| {
+                max_fraction = p.fraction;
+                // Cancel once the generator is done with the first batch.
+                if p.fraction > 0. {
+                    canceller.cancel()
+                }
+            });
+            FilterGen::default().run_with(&data, &mut cxt).unwrap()
+        };
+        assert!(token.is_cancelled());
+        assert!(res.is_none());
+        assert!(max_fraction < 1.);
+    }
+
+    #[test]
+    fn alloc_site_late_cancellation() {
+        let data = data(10, 3);
+        let token = CancelToken::new();
+        let res = {
+            let canceller = token.clone();
+            let mut cxt = GenCxt::new(token.clone(), |p| {
+                if p.phase == "generating charts" {
+                    canceller.cancel()
+                }
+            });
+            FilterGen::default().run_with(&data, &mut cxt).unwrap()
+        };
+        assert!(token.is_cancelled());
+        assert!(res.is_none());
+    }
+
+    #[test]
+    fn inactive_completes() {
+        let data = data(10, 1);
+        let token = CancelToken::new();
+        token.cancel();
+        let mut cxt = GenCxt::new(token, |_| ());
+        let res = FilterGen::Inactive.run_with(&data, &mut cxt).unwrap();
+        let (filters, _charts) = res.expect("inactive generator cannot be cancelled");
+        assert!(filters.filters().is_empty());
+    }
+
+    #[test]
+    fn top_sites_parsing() {
+        let count = |args: &str| match FilterGen::from_cla(args).unwrap() {
+            FilterGen::TopSites(params) => params.count(),
+            gen => panic!("expected a top-sites generator, got {:?}", gen),
+        };
+        assert_eq!(count("top_sites(7)"), 7);
+        assert_eq!(count("top_sites ( 3 )"), 3);
+        assert_eq!(count("top_sites"), TopSitesParams::DEFAULT_COUNT);
+        assert!(FilterGen::from_cla("top_sites(seven)").is_err());
+        assert!(FilterGen::from_cla("top_sites(7").is_err());
+    }
+
+    /// Runs the top-sites generator for `count` sites.
+    fn run_top_sites(data: &data::Data, count: usize) -> (Filters, Vec<chart::Chart>) {
+        FilterGen::from(TopSitesParams::new(count))
+            .run(data)
+            .expect("top-sites generation failed")
+    }
+
+    #[test]
+    fn top_sites_ranking() {
+        // Four allocations in `file_0`, three in `file_1` and `file_2`, all of the same size.
+        let data = data(10, 3);
+        let (filters, _charts) = run_top_sites(&data, 2);
+        let names: Vec<_> = filters
+            .filters()
+            .iter()
+            .map(|filter| filter.name().as_str().to_string())
+            .collect();
+        // Ties are broken by file name.
+        assert_eq!(names, vec!["dir_0/file_0.ml:1", "dir_0/file_1.ml:1"]);
+        let colors: HSet<_> = filters
+            .filters()
+            .iter()
+            .map(|filter| filter.spec().color().to_string())
+            .collect();
+        assert_eq!(colors.len(), 2);
+
+        // Asking for more sites than there are.
+        let (filters, _charts) = run_top_sites(&data, 7);
+        assert_eq!(filters.filters().len(), 3);
+    }
+
+    /// Runs the default generator with some limits, checks the charts only use generated filters.
+    fn run_limited(data: &data::Data, limits: limits::Limits) -> (Filters, Vec<chart::Chart>) {
+        let mut cxt = GenCxt::silent().with_limits(limits);
+        let (filters, charts) = FilterGen::default()
+            .run_with(data, &mut cxt)
+            .unwrap()
+            .expect("generation was not cancelled");
+        for chart in &charts {
+            for (line, active) in chart.spec().active() {
+                if let (uid::Line::Filter(uid), true) = (line, active) {
+                    assert!(filters.filters().iter().any(|filter| filter.uid() == *uid))
+                }
+            }
+        }
+        (filters, charts)
+    }
+
+    #[test]
+    fn limits_exact() {
+        // Six files in three directories: six filters, one chart per directory plus everything.
+        let data = data(60, 6);
+        let (filters, charts) = run_limited(&data, limits::Limits::new(6, 4));
+        assert_eq!(filters.filters().len(), 6);
+        assert_eq!(charts.len(), 4);
+    }
+
+    #[test]
+    fn limits_truncate() {
+        let data = data(60, 6);
+
+        let (filters, charts) = run_limited(&data, limits::Limits::new(6, 2));
+        assert_eq!(filters.filters().len(), 6);
+        assert_eq!(charts.len(), 2);
+
+        let (filters, charts) = run_limited(&data, limits::Limits::new(4, 40));
+        assert_eq!(filters.filters().len(), 4);
+        assert!(!charts.is_empty());
+
+        let (filters, charts) = run_limited(&data, limits::Limits::new(0, 0));
+        assert!(filters.filters().is_empty());
+        assert!(charts.is_empty());
+    }
 }
@@ -24,6 +24,9 @@
 //! same when `min_count` is `0` or when it is `1`.
 //!
 //! When no `min_count` parameter is present, the current behavior is the same as `min_count == 1`.
+//!
+//! When there are more allocation-site-files than the filter limit allows, only the files with the
+//! most allocations get a filter. The allocations of the other files end up in the catch-all line.
 
 prelude! {}
 
@@ -91,10 +94,17 @@ impl AllocSiteWork {
     }
 
     /// Scans the input data to populate the map from file names to allocation count.
-    pub fn scan(&mut self, data: &data::Data) {
-        for alloc in data.iter_allocs() {
+    ///
+    /// Returns `false` if the generation was cancelled.
+    pub fn scan(&mut self, data: &data::Data, cxt: &mut GenCxt) -> bool {
+        let total = data.alloc_count();
+        for (count, alloc) in data.iter_allocs().enumerate() {
+            if cxt.batch_check(count, total, "scanning allocation sites") {
+                return false;
+            }
             alloc.alloc_site_do(|cloc_opt| self.inc(cloc_opt.map(|cloc| cloc.loc.file)))
         }
+        true
     }
 
     /// Generates a subfilter for a specific file name.
@@ -110,8 +120,8 @@ impl AllocSiteWork {
         filter.into()
     }
 
-    /// Extracts allocation-site-file filters.
-    pub fn extract(&mut self, params: &AllocSiteParams) -> Res<Vec<Filter>> {
+    /// Extracts allocation-site-file filters, at most `max` of them.
+    pub fn extract(&mut self, params: &AllocSiteParams, max: usize) -> Res<Vec<Filter>> {
         let mut res = Vec::with_capacity(self.map.len());
 
         if self.map.is_empty() || (self.map.len() == 1 && self.unk == 0) {
@@ -128,18 +138,13 @@ impl AllocSiteWork {
 
         let validate = |count: usize| min_count <= count;
 
-        let filter_count = self.map.iter().fold(
-            0,
-            |acc, (_, (count, _))| if validate(*count) { acc + 1 } else { acc },
-        );
-
         for (file, (count, uid_opt)) in &mut self.map {
             if validate(*count) {
                 let sub_filter = Self::generate_subfilter(&file);
 
                 let color = Color::BLACK.clone();
                 let mut spec = filter::FilterSpec::new(color);
-                spec.set_name(file.clone());
+                spec.set_name(filter::FilterName::new_truncated(file.clone()));
 
                 let mut filter = filter::Filter::new(spec)?;
                 filter.insert(sub_filter)?;
@@ -155,18 +160,44 @@ impl AllocSiteWork {
 
         // Rev-sorting by number of allocations. Note that the order does not matter as the filter
         // exact-match different allocation-site-files.
+        //
+        // Names can be truncated, use filter UIDs to retrieve the counts.
+        let counts: BTMap<uid::Filter, usize> = self
+            .map
+            .values()
+            .filter_map(|(count, uid_opt)| uid_opt.map(|uid| (uid, *count)))
+            .collect();
         res.sort_by(|lft, rgt| {
-            let lft = self.map.get(lft.name()).cloned().unwrap_or((0, None));
-            let rgt = self.map.get(rgt.name()).cloned().unwrap_or((0, None));
+            let lft = counts.get(&lft.uid()).cloned().unwrap_or(0);
+            let rgt = counts.get(&rgt.uid()).cloned().unwrap_or(0);
             // rev-sorting
             rgt.cmp(&lft)
         });
 
-        let mut colors = Color::randoms(filter_count).into_iter();
+        if res.len() > max {
+            log::warn!(
+                "generated {} allocation-site filters, only keeping the {} largest ones \
+                (raise the limit with `{}`)",
+                res.len(),
+                max,
+                limits::Limits::FILTERS_FLAG,
+            );
+            let dropped: BTSet<uid::Filter> = res.drain(max..).map(|filter| filter.uid()).collect();
+            // Forget the UIDs of the dropped filters so that chart generation ignores them.
+            for (_, uid_opt) in self.map.values_mut() {
+                if uid_opt.map(|uid| dropped.contains(&uid)).unwrap_or(false) {
+                    *uid_opt = None
+                }
+            }
+        }
+
+        let mut colors = Color::randoms(res.len()).into_iter();
         for filter in &mut res {
-            filter.spec_mut().set_color(colors.next().expect(
-                "internal error, `filter_count` is not consistant with the actual filter count",
-            ))
+            filter.spec_mut().set_color(
+                colors
+                    .next()
+                    .expect("internal error, `Color::randoms` did not produce enough colors"),
+            )
         }
 
         // log::info!("allocation sites:");
@@ -207,12 +238,25 @@ impl FilterGenExt for AllocSite {
     const KEY: &'static str = "alloc_site";
     const FMT: Option<&'static str> = Some("min: <int>, chart_gen: <bool>");
 
-    fn work(data: &data::Data, params: Self::Params) -> Res<(Filters, Vec<chart::Chart>)> {
+    fn work(
+        data: &data::Data,
+        params: Self::Params,
+        cxt: &mut GenCxt,
+    ) -> Res<Option<(Filters, Vec<chart::Chart>)>> {
         let mut work = AllocSiteWork::new();
-        work.scan(data);
-        let filters = work.extract(&params).map(Filters::new_with)?;
+        if !work.scan(data, cxt) {
+            return Ok(None);
+        }
+        cxt.progress(1., "extracting filters");
+        let filters = work
+            .extract(&params, cxt.limits().filters)
+            .map(Filters::new_with)?;
+        if cxt.is_cancelled() {
+            return Ok(None);
+        }
+        cxt.progress(1., "generating charts");
         let charts = work.chart_gen(&params, &filters)?;
-        Ok((filters, charts))
+        Ok(Some((filters, charts)))
     }
 
     fn parse_args(parser: Option<Parser>) -> Option<FilterGen> {
@@ -22,6 +22,7 @@
 prelude! {}
 
 /// Description of a chart.
+#[derive(Debug, Clone)]
 pub struct ChartDesc {
     /// Title of the chart.
     pub title: Option<String>,
@@ -30,10 +30,14 @@ impl FilterGenExt for Inactive {
     const KEY: &'static str = "none";
     const FMT: Option<&'static str> = None;
 
-    fn work(_data: &data::Data, (): Self::Params) -> Res<(Filters, Vec<chart::Chart>)> {
+    fn work(
+        _data: &data::Data,
+        (): Self::Params,
+        _cxt: &mut GenCxt,
+    ) -> Res<Option<(Filters, Vec<chart::Chart>)>> {
         let filters = Filters::new();
         let charts = chart_gen::single(&filters)?;
-        Ok((filters, charts))
+        Ok(Some((filters, charts)))
     }
 
     fn parse_args(parser: Option<Parser>) -> Option<FilterGen> {
@@ -196,19 +196,23 @@ impl<'input> Parser<'input> {
         }
     }
 
-    /// Extracts the content of a block `{ ... }` and generate a sub-parser.
+    /// Extracts the content of a block `{ ... }` or `( ... )` and generate a sub-parser.
     pub fn block(&mut self) -> Res<Option<Self>> {
-        if !self.char('{') {
+        let (open, close) = if self.char('{') {
+            ('{', '}')
+        } else if self.char('(') {
+            ('(', ')')
+        } else {
             return Ok(None);
-        }
+        };
         let mut count = 1;
         let start = self.pos;
         let mut end = self.pos;
 
         for c in chars!(self) {
-            if c == '}' {
+            if c == close {
                 count -= 1;
-            } else if c == '{' {
+            } else if c == open {
                 count += 1;
             }
 
@@ -222,7 +226,7 @@ impl<'input> Parser<'input> {
         }
 
         if count > 0 {
-            bail!("ill-formed block, some braces are unmatched")
+            bail!("ill-formed block, unmatched `{}`", open)
         }
 
         Ok(Some(Self::new(&self.txt[start..end])))
@@ -0,0 +1,245 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Top-allocation-sites automatic filter generation.
+//!
+//! Parameterized with a number of sites `N`, `5` by default. This generator groups allocations by
+//! their allocation site, *i.e.* the innermost location of their callstack, and generates one
+//! filter for each of the `N` sites allocating the most bytes. The filters match the file and line
+//! of the site in the innermost frame, and are named after it.
+//!
+//! Sites with the same number of bytes are ranked by file name and line, so that generation is
+//! deterministic. Allocations without a location are never captured by a site filter, they end up
+//! in the catch-all line with the allocations of the sites outside of the top `N`.
+
+prelude! {}
+
+use filter::gen::*;
+
+/// Parameters for the top-sites generator.
+#[derive(Debug, Clone)]
+pub struct TopSitesParams {
+    /// Number of sites to generate a filter for.
+    count: usize,
+}
+impl Default for TopSitesParams {
+    fn default() -> Self {
+        Self {
+            count: Self::DEFAULT_COUNT,
+        }
+    }
+}
+
+impl TopSitesParams {
+    /// Default number of sites.
+    pub const DEFAULT_COUNT: usize = 5;
+
+    /// Constructor.
+    pub fn new(count: usize) -> Self {
+        Self { count }
+    }
+
+    /// Number of sites to generate a filter for.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// An allocation site: a file and a line.
+type Site = (String, usize);
+
+/// Actual top-sites generator worker.
+pub struct TopSitesWork {
+    /// Maps sites to the number of bytes they allocate.
+    map: BTMap<Site, u64>,
+    /// Number of bytes allocated without a location.
+    unk: u64,
+}
+
+impl TopSitesWork {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self {
+            map: BTMap::new(),
+            unk: 0,
+        }
+    }
+
+    /// Adds some bytes to a site, `None` for allocations without a location.
+    pub fn inc(&mut self, loc: Option<&alloc::Loc>, bytes: u64) {
+        if let Some(loc) = loc {
+            let site = loc.file.str_do(|file| (file.to_string(), loc.line));
+            *self.map.entry(site).or_insert(0) += bytes
+        } else {
+            self.unk += bytes
+        }
+    }
+
+    /// Scans the input data to populate the map from sites to bytes.
+    ///
+    /// Returns `false` if the generation was cancelled.
+    pub fn scan(&mut self, data: &data::Data, cxt: &mut GenCxt) -> bool {
+        let total = data.alloc_count();
+        for (count, alloc) in data.iter_allocs().enumerate() {
+            if cxt.batch_check(count, total, "scanning allocation sites") {
+                return false;
+            }
+            alloc.alloc_site_do(|cloc_opt| {
+                self.inc(cloc_opt.map(|cloc| &cloc.loc), alloc.real_size as u64)
+            })
+        }
+        true
+    }
+
+    /// Ranks the sites, most bytes first.
+    ///
+    /// Sites with the same number of bytes are ranked by file name and line.
+    pub fn ranking(&self) -> Vec<(&Site, u64)> {
+        let mut sites: Vec<_> = self
+            .map
+            .iter()
+            .map(|(site, bytes)| (site, *bytes))
+            .collect();
+        // Stable sort, ties keep the order of the map.
+        sites.sort_by(|(_, lft), (_, rgt)| rgt.cmp(lft));
+        sites
+    }
+
+    /// Generates a subfilter matching the allocations of a site.
+    pub fn generate_subfilter((file, line): &Site) -> filter::sub::RawSubFilter {
+        let pred = filter::string_like::Pred::Contain;
+        let final_loc_spec = filter::loc::LocSpec::Value {
+            value: file.as_str().into(),
+            line: filter::loc::LineSpec::line(*line),
+        };
+        let loc_spec = vec![filter::loc::LocSpec::Anything, final_loc_spec];
+        let filter = filter::loc::LocFilter::new(pred, loc_spec);
+        filter.into()
+    }
+
+    /// Extracts the filters of the top sites, at most `max` of them.
+    pub fn extract(&self, params: &TopSitesParams, max: usize) -> Res<Vec<Filter>> {
+        let count = if params.count > max {
+            log::warn!(
+                "asked for the top {} allocation sites, only keeping the {} largest ones \
+                (raise the limit with `{}`)",
+                params.count,
+                max,
+                limits::Limits::FILTERS_FLAG,
+            );
+            max
+        } else {
+            params.count
+        };
+
+        if self.unk > 0 {
+            log::debug!(
+                "{} byte(s) allocated without a location, not captured by any site filter",
+                self.unk
+            )
+        }
+
+        let ranking = self.ranking();
+        let mut colors = Color::randoms(count.min(ranking.len())).into_iter();
+        let mut res = Vec::with_capacity(count);
+
+        for (site, _bytes) in ranking.into_iter().take(count) {
+            let color = colors
+                .next()
+                .expect("internal error, `Color::randoms` did not produce enough colors");
+            let mut spec = filter::FilterSpec::new(color);
+            spec.set_name(filter::FilterName::new_truncated(format!(
+                "{}:{}",
+                site.0, site.1
+            )));
+
+            let mut filter = filter::Filter::new(spec)?;
+            filter.insert(Self::generate_subfilter(site))?;
+            res.push(filter)
+        }
+
+        Ok(res)
+    }
+}
+
+/// Unit-struct handling CLAP and creating/running the actual generator.
+#[derive(Debug, Clone, Copy)]
+pub struct TopSites;
+
+impl FilterGenExt for TopSites {
+    type Params = TopSitesParams;
+
+    const KEY: &'static str = "top_sites";
+    const FMT: Option<&'static str> = Some("<int>");
+
+    fn work(
+        data: &data::Data,
+        params: Self::Params,
+        cxt: &mut GenCxt,
+    ) -> Res<Option<(Filters, Vec<chart::Chart>)>> {
+        let mut work = TopSitesWork::new();
+        if !work.scan(data, cxt) {
+            return Ok(None);
+        }
+        cxt.progress(1., "extracting filters");
+        let filters = work
+            .extract(&params, cxt.limits().filters)
+            .map(Filters::new_with)?;
+        if cxt.is_cancelled() {
+            return Ok(None);
+        }
+        cxt.progress(1., "generating charts");
+        let charts = chart_gen::default(&filters)?;
+        Ok(Some((filters, charts)))
+    }
+
+    fn parse_args(parser: Option<Parser>) -> Option<FilterGen> {
+        let mut parser = if let Some(parser) = parser {
+            parser
+        } else {
+            return Some(Self::Params::default().into());
+        };
+
+        parser.ws();
+        let count = parser.usize()?;
+        parser.ws();
+
+        if !parser.is_at_eoi() {
+            return None;
+        }
+
+        Some(TopSitesParams::new(count).into())
+    }
+
+    fn add_help(s: &mut String) {
+        s.push_str(&format!(
+            "\
+- top allocation sites generator: `{0}({1})`
+    Generates one filter for each of the `{1}` allocation sites (innermost location of the
+    callstack) allocating the most bytes.
+    Defaults: `{0}({2})`.
+
+\
+            ",
+            Self::KEY,
+            Self::FMT.unwrap(),
+            TopSitesParams::DEFAULT_COUNT,
+        ));
+    }
+}
@@ -0,0 +1,299 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Boolean structure of the subfilters of a filter.
+//!
+//! The subfilters of a [`Filter`] form a tree of groups. A group matches an allocation if all (or
+//! any) of its subfilters and nested groups do. The root group holds the subfilters that are in no
+//! nested group: a filter with no nested group is the conjunction of its subfilters, which is also
+//! how filters defined before groups existed load.
+//!
+//! Nested groups are designated by their path, the indices of the groups leading to them from the
+//! root. The empty path is the root group. Groups nest at most [`MAX_DEPTH`] levels deep.
+//!
+//! [`Filter`]: ../struct.Filter.html (The Filter struct)
+//! [`MAX_DEPTH`]: constant.MAX_DEPTH.html (The MAX_DEPTH constant)
+
+prelude! {}
+
+/// Maximum number of nested group levels under the root group.
+pub const MAX_DEPTH: usize = 3;
+
+/// Operator of a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GroupOp {
+    /// All members must match.
+    And,
+    /// At least one member must match.
+    Or,
+}
+
+impl Default for GroupOp {
+    fn default() -> Self {
+        Self::And
+    }
+}
+
+impl GroupOp {
+    /// All the operators.
+    pub fn all() -> Vec<Self> {
+        vec![Self::And, Self::Or]
+    }
+
+    /// Description of the operator.
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::And => "all of",
+            Self::Or => "any of",
+        }
+    }
+
+    /// Combines the results of the members of a group.
+    ///
+    /// Empty groups have no result (`None`) and are ignored. The result is `None` if no member has
+    /// a result.
+    ///
+    /// ```rust
+    /// # use charts::filter::group::GroupOp;
+    /// assert_eq!(GroupOp::And.fold(vec![Some(true), None, Some(false)]), Some(false));
+    /// assert_eq!(GroupOp::Or.fold(vec![Some(true), None, Some(false)]), Some(true));
+    /// assert_eq!(GroupOp::Or.fold(vec![None, Some(false)]), Some(false));
+    /// assert_eq!(GroupOp::Or.fold(vec![None, None]), None);
+    /// ```
+    pub fn fold(self, results: impl IntoIterator<Item = Option<bool>>) -> Option<bool> {
+        let mut res = None;
+        for result in results.into_iter().flatten() {
+            match (self, result) {
+                (Self::And, false) | (Self::Or, true) => return Some(result),
+                _ => res = Some(result),
+            }
+        }
+        res
+    }
+}
+
+base::implement! {
+    impl GroupOp {
+        Display {
+            |&self, fmt| write!(fmt, "{}", self.desc())
+        }
+    }
+}
+
+/// A group of subfilters and nested groups.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Group {
+    /// Operator combining the members.
+    op: GroupOp,
+    /// Subfilters of the group, always empty for the root group.
+    subs: BTSet<uid::SubFilter>,
+    /// Nested groups.
+    groups: Vec<Group>,
+}
+
+impl Group {
+    /// Constructor.
+    pub fn new(op: GroupOp) -> Self {
+        Self {
+            op,
+            subs: BTSet::new(),
+            groups: vec![],
+        }
+    }
+
+    /// Operator accessor.
+    pub fn op(&self) -> GroupOp {
+        self.op
+    }
+    /// Sets the operator.
+    pub fn set_op(&mut self, op: GroupOp) {
+        self.op = op
+    }
+
+    /// UIDs of the subfilters of the group, nested groups excluded.
+    pub fn subs(&self) -> impl Iterator<Item = uid::SubFilter> + '_ {
+        self.subs.iter().cloned()
+    }
+    /// Nested groups.
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    /// True if a subfilter is in the group or in one of its nested groups.
+    pub fn contains(&self, sub: uid::SubFilter) -> bool {
+        self.subs.contains(&sub) || self.groups.iter().any(|group| group.contains(sub))
+    }
+
+    /// Path of the group a subfilter is in, nested groups excluded.
+    ///
+    /// Empty if the subfilter is in no nested group, *i.e.* if it is in the root group.
+    pub fn path_of(&self, sub: uid::SubFilter) -> Vec<usize> {
+        self.find_path(sub).unwrap_or_default()
+    }
+    /// Path of the group a subfilter is in, `None` if it is in none of the groups.
+    fn find_path(&self, sub: uid::SubFilter) -> Option<Vec<usize>> {
+        if self.subs.contains(&sub) {
+            return Some(vec![]);
+        }
+        self.groups.iter().enumerate().find_map(|(index, group)| {
+            group.find_path(sub).map(|mut path| {
+                path.insert(0, index);
+                path
+            })
+        })
+    }
+
+    /// Number of nested group levels, `0` if there are no nested groups.
+    pub fn depth(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|group| group.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Group at some path.
+    pub fn get(&self, path: &[usize]) -> Option<&Group> {
+        match path.split_first() {
+            None => Some(self),
+            Some((index, tail)) => self.groups.get(*index)?.get(tail),
+        }
+    }
+    /// Mutable group at some path.
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut Group> {
+        match path.split_first() {
+            None => Some(self),
+            Some((index, tail)) => self.groups.get_mut(*index)?.get_mut(tail),
+        }
+    }
+
+    /// Adds a subfilter to the group.
+    pub fn insert(&mut self, sub: uid::SubFilter) {
+        self.subs.insert(sub);
+    }
+
+    /// Adds a nested group, fails if it would be more than [`MAX_DEPTH`] levels deep.
+    ///
+    /// - `path`: path of the group to add a nested group to.
+    ///
+    /// [`MAX_DEPTH`]: constant.MAX_DEPTH.html (The MAX_DEPTH constant)
+    pub fn add_group(&mut self, path: &[usize], op: GroupOp) -> Res<()> {
+        if path.len() >= MAX_DEPTH {
+            bail!("groups cannot nest more than {} levels deep", MAX_DEPTH)
+        }
+        self.get_mut(path)
+            .ok_or_else(|| format!("unknown group {:?}", path))?
+            .groups
+            .push(Self::new(op));
+        Ok(())
+    }
+
+    /// Removes a nested group, its members go to its parent group.
+    ///
+    /// Subfilters going to the root group are just removed from the tree, since the root group
+    /// holds the subfilters that are in no nested group.
+    pub fn ungroup(&mut self, path: &[usize]) -> Res<()> {
+        let (index, parent_path) = path.split_last().ok_or("cannot ungroup the root group")?;
+        let parent = self
+            .get_mut(parent_path)
+            .ok_or_else(|| format!("unknown group {:?}", path))?;
+        if *index >= parent.groups.len() {
+            bail!("unknown group {:?}", path)
+        }
+        let group = parent.groups.remove(*index);
+        if !parent_path.is_empty() {
+            parent.subs.extend(group.subs)
+        }
+        parent.groups.extend(group.groups);
+        Ok(())
+    }
+
+    /// Removes a subfilter from the group and its nested groups.
+    ///
+    /// Returns `true` if the subfilter was in the group.
+    pub fn remove_sub(&mut self, sub: uid::SubFilter) -> bool {
+        let mut removed = self.subs.remove(&sub);
+        for group in &mut self.groups {
+            removed = group.remove_sub(sub) || removed
+        }
+        removed
+    }
+
+    /// Renames the subfilters of the group and its nested groups.
+    ///
+    /// Subfilters for which `rename` yields `None` are removed.
+    pub fn rename_subs(&mut self, rename: &impl Fn(uid::SubFilter) -> Option<uid::SubFilter>) {
+        self.subs = self.subs.iter().filter_map(|sub| rename(*sub)).collect();
+        for group in &mut self.groups {
+            group.rename_subs(rename)
+        }
+    }
+
+    /// Checks the group is legal.
+    ///
+    /// - `is_root`: true if the group is the root group of a filter;
+    /// - `known`: the UIDs of the subfilters of the filter.
+    pub fn check(&self, is_root: bool, known: &BTSet<uid::SubFilter>) -> Res<()> {
+        if self.depth() > MAX_DEPTH {
+            bail!("groups cannot nest more than {} levels deep", MAX_DEPTH)
+        }
+        if is_root && !self.subs.is_empty() {
+            bail!("the root group cannot list subfilters")
+        }
+        let mut seen = BTSet::new();
+        let mut stack: Vec<&Group> = self.groups.iter().collect();
+        while let Some(group) = stack.pop() {
+            for sub in &group.subs {
+                if !known.contains(sub) {
+                    bail!("group mentions unknown subfilter #{}", sub)
+                }
+                if !seen.insert(*sub) {
+                    bail!("subfilter #{} appears in more than one group", sub)
+                }
+            }
+            stack.extend(group.groups.iter())
+        }
+        Ok(())
+    }
+
+    /// Applies a nested group to an allocation.
+    ///
+    /// - `subs`: the subfilters of the filter the group belongs to.
+    ///
+    /// Yields `None` if the group and its nested groups have no subfilters, see [`GroupOp::fold`].
+    ///
+    /// [`GroupOp::fold`]: enum.GroupOp.html#method.fold (The fold method of GroupOp)
+    pub fn apply(
+        &self,
+        subs: &BTMap<uid::SubFilter, filter::SubFilter>,
+        timestamp: &time::SinceStart,
+        alloc: &Alloc,
+    ) -> Option<bool> {
+        let own = self
+            .subs
+            .iter()
+            .filter_map(|uid| subs.get(uid))
+            .map(|sub| Some(sub.apply(timestamp, alloc)));
+        let nested = self
+            .groups
+            .iter()
+            .map(|group| group.apply(subs, timestamp, alloc));
+        self.op.fold(own.chain(nested))
+    }
+}
@@ -18,6 +18,17 @@
 */
 
 //! Label filters.
+//!
+//! Allocations share few distinct labels, and labels are interned as [`alloc::Str`]. So, label
+//! regexes memoize their result for the labels of the allocations they are about to match, see
+//! [`LabelRegex`].
+//!
+//! Prefix and suffix specifications, written `foo*` and `*foo`, are cheaper than the equivalent
+//! regexes, see [`LabelSpec::new`].
+//!
+//! [`alloc::Str`]: ../../../alloc_data/mem/str/struct.Str.html (The Str struct)
+//! [`LabelRegex`]: struct.LabelRegex.html (The LabelRegex struct)
+//! [`LabelSpec::new`]: enum.LabelSpec.html#method.new (LabelSpec's constructor)
 
 prelude! {}
 
@@ -32,6 +43,93 @@ pub type LabelPred = string_like::Pred;
 /// An update for a label filter.
 pub type LabelUpdate = string_like::Update;
 
+/// Maximum number of labels a [`LabelRegex`] memoizes the result of.
+///
+/// A cache that reaches this size is cleared.
+///
+/// [`LabelRegex`]: struct.LabelRegex.html (The LabelRegex struct)
+pub const MAX_CACHED_LABELS: usize = 1 << 16;
+
+/// A regular expression over labels, memoizes its result for each label.
+///
+/// Matching only reads the cache, so that threads can match allocations concurrently without
+/// locking: results are memoized beforehand with [`memoize`], and labels the cache does not know
+/// run the regex. See [`Filters::memoize_labels`].
+///
+/// The cache lives as long as the regex, it is not serialized and clones start with an empty
+/// cache. So changing the specification of a filter, which replaces its regexes, invalidates it.
+/// Interned labels never change, so the cache never needs to be invalidated otherwise.
+///
+/// [`memoize`]: #method.memoize (The memoize method)
+/// [`Filters::memoize_labels`]: ../struct.Filters.html#method.memoize_labels
+/// (The memoize_labels method of Filters)
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LabelRegex {
+    /// Actual regex.
+    #[serde(with = "serde_regex")]
+    regex: Regex,
+    /// Result of the regex for the labels it has memoized.
+    #[serde(skip)]
+    cache: HMap<alloc::Str, bool>,
+}
+impl fmt::Debug for LabelRegex {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_tuple("LabelRegex").field(&self.regex).finish()
+    }
+}
+impl Clone for LabelRegex {
+    fn clone(&self) -> Self {
+        Self::new(self.regex.clone())
+    }
+}
+impl LabelRegex {
+    /// Constructor.
+    pub fn new(regex: Regex) -> Self {
+        Self {
+            regex,
+            cache: HMap::new(),
+        }
+    }
+
+    /// Actual regex.
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Number of labels the regex has memoized the result of.
+    pub fn cached_count(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Memoizes the result of the regex on a label, if not already memoized.
+    pub fn memoize(&mut self, label: alloc::Str) {
+        if self.cache.contains_key(&label) {
+            return;
+        }
+        if self.cache.len() >= MAX_CACHED_LABELS {
+            self.cache.clear()
+        }
+        let is_match = label.str_do(|label| self.regex.is_match(label));
+        let _ = self.cache.insert(label, is_match);
+    }
+
+    /// True if the regex matches a label.
+    ///
+    /// Only runs the regex if the result is not memoized.
+    pub fn is_match(&self, label: alloc::Str) -> bool {
+        match self.cache.get(&label) {
+            Some(is_match) => *is_match,
+            None => label.str_do(|label| self.regex.is_match(label)),
+        }
+    }
+}
+impl fmt::Display for LabelRegex {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.regex.fmt(fmt)
+    }
+}
+
 /// Label specification.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum LabelSpec {
@@ -39,17 +137,26 @@ pub enum LabelSpec {
     Anything,
     /// An actualy label value.
     Value(String),
+    /// Matches the labels starting with some string.
+    Prefix(String),
+    /// Matches the labels ending with some string.
+    Suffix(String),
     /// A regular expression.
-    #[serde(with = "serde_regex")]
-    Regex(Regex),
+    Regex(LabelRegex),
 }
 impl std::cmp::PartialEq for LabelSpec {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Anything, Self::Anything) => true,
             (Self::Value(lft), Self::Value(rgt)) => lft == rgt,
-            (Self::Regex(lft), Self::Regex(rgt)) => lft.as_str() == rgt.as_str(),
-            (Self::Anything, _) | (Self::Value(_), _) | (Self::Regex(_), _) => false,
+            (Self::Prefix(lft), Self::Prefix(rgt)) => lft == rgt,
+            (Self::Suffix(lft), Self::Suffix(rgt)) => lft == rgt,
+            (Self::Regex(lft), Self::Regex(rgt)) => lft.regex().as_str() == rgt.regex().as_str(),
+            (Self::Anything, _)
+            | (Self::Value(_), _)
+            | (Self::Prefix(_), _)
+            | (Self::Suffix(_), _)
+            | (Self::Regex(_), _) => false,
         }
     }
 }
@@ -59,7 +166,9 @@ impl FilterExt<str> for LabelSpec {
     fn apply(&self, label: &str) -> bool {
         match self {
             LabelSpec::Value(value) => label == value,
-            LabelSpec::Regex(regex) => regex.is_match(label),
+            LabelSpec::Prefix(prefix) => label.starts_with(prefix.as_str()),
+            LabelSpec::Suffix(suffix) => label.ends_with(suffix.as_str()),
+            LabelSpec::Regex(regex) => regex.regex().is_match(label),
             LabelSpec::Anything => true,
         }
     }
@@ -77,6 +186,7 @@ impl string_like::SpecExt for LabelSpec {
     fn is_empty(&self) -> bool {
         match self {
             LabelSpec::Value(s) => s == "",
+            LabelSpec::Prefix(_) | LabelSpec::Suffix(_) => false,
             LabelSpec::Regex(_) => false,
             LabelSpec::Anything => false,
         }
@@ -90,25 +200,25 @@ impl string_like::SpecExt for LabelSpec {
     fn matches(&self, data: &Self::Data) -> bool {
         match self {
             LabelSpec::Value(value) => data == value,
-            LabelSpec::Regex(regex) => data.str_do(|s| regex.is_match(s)),
+            LabelSpec::Prefix(prefix) => data.str_do(|label| label.starts_with(prefix.as_str())),
+            LabelSpec::Suffix(suffix) => data.str_do(|label| label.ends_with(suffix.as_str())),
+            LabelSpec::Regex(regex) => regex.is_match(*data),
             LabelSpec::Anything => true,
         }
     }
 
     /// True if the specification matches a repetition of anything.
     fn matches_anything(&self) -> bool {
-        match self {
-            Self::Anything => true,
-            Self::Value(_) => false,
-            Self::Regex(_) => false,
-        }
+        LabelSpec::matches_anything(self)
     }
 }
 
 impl fmt::Display for LabelSpec {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Value(label) => label.fmt(fmt),
+            Self::Value(label) => escape(label).fmt(fmt),
+            Self::Prefix(prefix) => write!(fmt, "{}*", escape(prefix)),
+            Self::Suffix(suffix) => write!(fmt, "*{}", escape(suffix)),
             Self::Regex(regex) => write!(fmt, "#\"{}\"#", regex),
             Self::Anything => write!(fmt, "**"),
         }
@@ -121,8 +231,54 @@ impl Default for LabelSpec {
     }
 }
 
+/// Escapes the asterisks of a string, see [`LabelSpec::new`].
+///
+/// [`LabelSpec::new`]: enum.LabelSpec.html#method.new (LabelSpec's constructor)
+fn escape(s: &str) -> String {
+    s.replace('*', "\\*")
+}
+
 impl LabelSpec {
+    /// Memoizes the results of the specification on some labels, if it is a regex.
+    pub fn memoize(&mut self, labels: &[alloc::Str]) {
+        if let Self::Regex(regex) = self {
+            for label in labels {
+                regex.memoize(*label)
+            }
+        }
+    }
+
     /// Constructor from strings.
+    ///
+    /// - `**` matches any sequence of labels;
+    /// - `#"<regex>"#` matches the labels the regex matches;
+    /// - `foo*` matches the labels starting with `foo`, and `*foo` the ones ending with `foo`;
+    /// - anything else matches the labels equal to it.
+    ///
+    /// `\*` is a literal asterisk. Unescaped asterisks anywhere else than at the start or at the
+    /// end, or at both, are literal too, so that `a*b` still matches the label `a*b`.
+    ///
+    /// ```rust
+    /// # use charts::filter::{FilterExt, label::LabelSpec};
+    /// let prefix = LabelSpec::new("request_*").unwrap();
+    /// assert_eq!(prefix, LabelSpec::Prefix("request_".into()));
+    /// assert!(prefix.apply("request_42"));
+    /// assert!(!prefix.apply("response_42"));
+    ///
+    /// let suffix = LabelSpec::new("*_42").unwrap();
+    /// assert_eq!(suffix, LabelSpec::Suffix("_42".into()));
+    /// assert!(suffix.apply("request_42"));
+    ///
+    /// let escaped = LabelSpec::new(r"wild\*").unwrap();
+    /// assert_eq!(escaped, LabelSpec::Value("wild*".into()));
+    /// assert!(escaped.apply("wild*"));
+    /// assert!(!escaped.apply("wildcard"));
+    /// assert_eq!(escaped.to_string(), r"wild\*");
+    ///
+    /// assert_eq!(LabelSpec::new("a*b").unwrap(), LabelSpec::Value("a*b".into()));
+    /// assert_eq!(LabelSpec::new("*a*").unwrap(), LabelSpec::Value("*a*".into()));
+    /// assert_eq!(LabelSpec::new("**").unwrap(), LabelSpec::Anything);
+    /// ```
     pub fn new(s: impl Into<String>) -> Res<Self> {
         let label = s.into();
         macro_rules! illegal {
@@ -139,8 +295,39 @@ impl LabelSpec {
             let regex = Regex::new(&label[2..label.len() - 2])
                 .map_err(|e| illegal!().chain_err(|| format!("{}", e)))?;
             Ok(regex.into())
+        } else if label == "**" {
+            Ok(Self::Anything)
         } else {
-            Ok(label.into())
+            Ok(Self::parse_wildcards(&label))
+        }
+    }
+
+    /// Parses a value, a prefix or a suffix, see [`new`].
+    ///
+    /// [`new`]: #method.new (The new constructor)
+    fn parse_wildcards(label: &str) -> Self {
+        // Unescaped string, and positions of the unescaped asterisks in it.
+        let mut value = String::with_capacity(label.len());
+        let mut wildcards = vec![];
+        let mut chars = label.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&'*') => {
+                    let _ = chars.next();
+                    value.push('*')
+                }
+                '*' => {
+                    wildcards.push(value.len());
+                    value.push('*')
+                }
+                c => value.push(c),
+            }
+        }
+
+        match wildcards.as_slice() {
+            [0] if value.len() > 1 => Self::Suffix(value[1..].into()),
+            [idx] if *idx == value.len() - 1 => Self::Prefix(value[..*idx].into()),
+            _ => Self::Value(value),
         }
     }
 
@@ -148,7 +335,7 @@ impl LabelSpec {
     pub fn matches_anything(&self) -> bool {
         match self {
             Self::Anything => true,
-            Self::Value(_) => false,
+            Self::Value(_) | Self::Prefix(_) | Self::Suffix(_) => false,
             Self::Regex(_) => false,
         }
     }
@@ -170,7 +357,7 @@ impl<'a> From<&'a str> for LabelSpec {
 }
 impl From<Regex> for LabelSpec {
     fn from(re: Regex) -> Self {
-        Self::Regex(re)
+        Self::Regex(LabelRegex::new(re))
     }
 }
 
@@ -186,3 +373,167 @@ impl Default for string_like::StringLikeFilter<LabelSpec> {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use string_like::SpecExt;
+
+    /// Number of allocations of the cache test.
+    const ALLOC_COUNT: usize = 10_000;
+    /// Number of distinct labels of the cache test.
+    const LABEL_COUNT: usize = 1_000;
+
+    /// Builds allocations with three labels each, out of `LABEL_COUNT` distinct labels.
+    fn allocs() -> Vec<Alloc> {
+        let labels: Vec<_> = (0..LABEL_COUNT)
+            .map(|idx| {
+                let kind = if idx % 3 == 0 { "request" } else { "response" };
+                format!("{}_{}_{}", kind, idx, idx % 7)
+            })
+            .collect();
+        let trace = crate::fixture::trace(&[]);
+        (0..ALLOC_COUNT)
+            .map(|idx| {
+                let labels: Vec<&str> = (0..3)
+                    .map(|offset| labels[(idx * 7 + offset) % LABEL_COUNT].as_str())
+                    .collect();
+                let labels = crate::fixture::labels(&labels);
+                let idx = idx as u64;
+                crate::fixture::alloc(idx, alloc::AllocKind::Minor, 8, &trace, &labels, idx)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn regex_cache() {
+        let allocs = allocs();
+        let regex = "^request_[0-9]*[13579]_(1|3|5)$";
+        let mut filter = LabelFilter::contain(vec![
+            LabelSpec::Anything,
+            LabelSpec::new(format!("#\"{}\"#", regex)).unwrap(),
+            LabelSpec::Anything,
+        ]);
+        let regex = Regex::new(regex).unwrap();
+
+        // What label filters used to do: run the regex on each label of each allocation.
+        let expected: Vec<bool> = allocs
+            .iter()
+            .map(|alloc| {
+                alloc
+                    .labels()
+                    .iter()
+                    .any(|label| label.str_do(|label| regex.is_match(label)))
+            })
+            .collect();
+        assert!(expected.iter().any(|is_match| *is_match));
+        assert!(expected.iter().any(|is_match| !is_match));
+
+        fn cached_count(filter: &LabelFilter) -> usize {
+            match &filter.specs()[1] {
+                LabelSpec::Regex(regex) => regex.cached_count(),
+                spec => panic!("expected a regex, got {}", spec),
+            }
+        }
+        let check = |filter: &LabelFilter| {
+            let actual: Vec<bool> = allocs
+                .iter()
+                .map(|alloc| filter.apply(&alloc.labels()))
+                .collect();
+            assert_eq!(actual, expected);
+        };
+
+        // Matching never memoizes anything.
+        check(&filter);
+        assert_eq!(cached_count(&filter), 0);
+
+        // Each distinct label is memoized once, and matching gives the same results.
+        for alloc in &allocs {
+            for spec in filter.specs_mut() {
+                spec.memoize(&alloc.labels())
+            }
+        }
+        assert_eq!(cached_count(&filter), LABEL_COUNT);
+        check(&filter);
+        assert_eq!(cached_count(&filter), LABEL_COUNT);
+    }
+
+    #[test]
+    fn regex_cache_is_not_shared() {
+        let mut spec = LabelSpec::new("#\"^a+$\"#").unwrap();
+        let label = alloc::Str::new("aaa");
+        spec.memoize(&[label]);
+        assert!(spec.matches(&label));
+        let (regex, clone) = match (&spec, spec.clone()) {
+            (LabelSpec::Regex(regex), LabelSpec::Regex(clone)) => (regex, clone),
+            _ => panic!("expected a regex, got {}", spec),
+        };
+        assert_eq!(regex.cached_count(), 1);
+        assert_eq!(clone.cached_count(), 0);
+        assert_eq!(spec, LabelSpec::Regex(clone));
+    }
+
+    #[test]
+    fn prefix_and_suffix() {
+        let prefix = LabelSpec::new("request_*").unwrap();
+        let suffix = LabelSpec::new("*_3").unwrap();
+        assert_eq!(prefix, LabelSpec::Prefix("request_".into()));
+        assert_eq!(suffix, LabelSpec::Suffix("_3".into()));
+
+        for (label, prefix_ok, suffix_ok) in vec![
+            ("request_1_3", true, true),
+            ("request_1_4", true, false),
+            ("response_1_3", false, true),
+            ("request", false, false),
+        ] {
+            let str = alloc::Str::new(label);
+            assert_eq!(prefix.matches(&str), prefix_ok, "{} on {}", prefix, label);
+            assert_eq!(suffix.matches(&str), suffix_ok, "{} on {}", suffix, label);
+            assert_eq!(prefix.apply(label), prefix_ok, "{} on {}", prefix, label);
+            assert_eq!(suffix.apply(label), suffix_ok, "{} on {}", suffix, label);
+        }
+    }
+
+    #[test]
+    fn literal_asterisks() {
+        // Labels created before prefix and suffix specs existed must still match literally.
+        for label in vec!["foo*", "*foo", "a*b", "*a*", "**", "*"] {
+            let spec = LabelSpec::Value(label.into());
+            let json = serde_json::to_string(&spec).unwrap();
+            let from_json: LabelSpec = serde_json::from_str(&json).unwrap();
+            assert_eq!(from_json, spec);
+
+            let reparsed = LabelSpec::new(spec.to_string()).unwrap();
+            assert_eq!(reparsed, spec, "{} from {}", reparsed, label);
+            assert!(reparsed.apply(label));
+            assert!(!reparsed.apply("foo"));
+        }
+
+        assert_eq!(
+            LabelSpec::new(r"\*foo\*").unwrap(),
+            LabelSpec::Value("*foo*".into())
+        );
+        assert_eq!(
+            LabelSpec::new(r"a\*b*").unwrap(),
+            LabelSpec::Prefix("a*b".into())
+        );
+        assert_eq!(
+            LabelSpec::new(r"a\b").unwrap(),
+            LabelSpec::Value(r"a\b".into())
+        );
+    }
+
+    #[test]
+    fn prefix_and_suffix_round_trip() {
+        for spec in vec![
+            LabelSpec::Prefix("req*".into()),
+            LabelSpec::Suffix("*_3".into()),
+            LabelSpec::Prefix("".into()),
+        ] {
+            let json = serde_json::to_string(&spec).unwrap();
+            let from_json: LabelSpec = serde_json::from_str(&json).unwrap();
+            assert_eq!(from_json, spec);
+            assert_eq!(LabelSpec::new(spec.to_string()).unwrap(), spec);
+        }
+    }
+}
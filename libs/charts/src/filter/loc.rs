@@ -32,6 +32,84 @@ pub type LocPred = string_like::Pred;
 /// An update for a location filter.
 pub type LocUpdate = string_like::Update;
 
+/// Specifies how location filters treat allocations with unresolved locations.
+///
+/// Unresolved locations appear in callstacks as placeholders, which location filters cannot
+/// meaningfully match against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UnresolvedPolicy {
+    /// Location filters never match allocations with unresolved locations.
+    NeverMatch,
+    /// Location filters always match allocations with unresolved locations.
+    MatchAll,
+}
+impl Default for UnresolvedPolicy {
+    fn default() -> Self {
+        Self::NeverMatch
+    }
+}
+base::implement! {
+    impl UnresolvedPolicy {
+        Display {
+            |&self, fmt| match self {
+                Self::NeverMatch => "never matched".fmt(fmt),
+                Self::MatchAll => "always matched".fmt(fmt),
+            }
+        }
+    }
+}
+impl UnresolvedPolicy {
+    /// Constructor from a command-line argument.
+    ///
+    /// ```rust
+    /// # use charts::filter::loc::UnresolvedPolicy;
+    /// assert_eq!(UnresolvedPolicy::from_cla("never").unwrap(), UnresolvedPolicy::NeverMatch);
+    /// assert_eq!(UnresolvedPolicy::from_cla(" all ").unwrap(), UnresolvedPolicy::MatchAll);
+    /// assert!(UnresolvedPolicy::from_cla("sometimes").is_err());
+    /// ```
+    pub fn from_cla(arg: &str) -> Res<Self> {
+        match arg.trim() {
+            "never" => Ok(Self::NeverMatch),
+            "all" => Ok(Self::MatchAll),
+            arg => bail!(
+                "unknown unresolved-location policy `{}`, expected `never` or `all`",
+                arg
+            ),
+        }
+    }
+
+    /// Result of a location filter on an allocation with unresolved locations.
+    pub fn result(self) -> bool {
+        match self {
+            Self::NeverMatch => false,
+            Self::MatchAll => true,
+        }
+    }
+}
+
+lazy_static! {
+    /// Stores the active unresolved-location policy.
+    ///
+    /// This is currently written once during CLAP.
+    static ref UNRESOLVED_POLICY: sync::RwLock<UnresolvedPolicy> =
+        sync::RwLock::new(UnresolvedPolicy::default());
+}
+
+/// Retrieves the active unresolved-location policy.
+pub fn unresolved_policy() -> UnresolvedPolicy {
+    *UNRESOLVED_POLICY
+        .read()
+        .expect("global unresolved-location policy was poisoned")
+}
+
+/// Sets the active unresolved-location policy.
+pub fn set_unresolved_policy(policy: UnresolvedPolicy) {
+    let mut active = UNRESOLVED_POLICY
+        .write()
+        .expect("global unresolved-location policy was poisoned");
+    *active = policy
+}
+
 /// A line specification.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LineSpec {
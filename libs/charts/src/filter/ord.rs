@@ -215,6 +215,9 @@ pub type SizeUpdate = Update<u32>;
 /// An update for a lifetime filter.
 pub type LifetimeUpdate = Update<time::Lifetime>;
 
+/// An update for a sample count filter.
+pub type NSamplesUpdate = Update<u32>;
+
 /// An update for an ordered filter.
 pub enum Update<Val> {
     /// Change the comparator of a `Cmp` filter.
@@ -287,3 +290,130 @@ impl<Num: fmt::Display + PartialEq + Eq> OrdFilter<Num> {
         Ok(has_changed)
     }
 }
+
+/// Parses a size, *i.e.* a number of machine words, with an optional unit prefix.
+///
+/// Prefixes are the ones of [`num_fmt`], so that pretty sizes parse back:
+///
+/// - `k`, `M` and `G` are decimal prefixes, *e.g.* `k` is `1000`;
+/// - `Ki`, `Mi` and `Gi` are binary prefixes, *e.g.* `Ki` is `1024`.
+///
+/// Prefixes are case-insensitive, and can be followed by a `B` so that `1KiB` parses. Sizes are
+/// still in machine words though, not bytes. Values can have a fractional part as long as the size
+/// is a whole number.
+///
+/// ```rust
+/// # use charts::filter::ord::parse_size;
+/// assert_eq!(parse_size("42").unwrap(), 42);
+/// assert_eq!(parse_size("1k").unwrap(), 1_000);
+/// assert_eq!(parse_size("1K").unwrap(), 1_000);
+/// assert_eq!(parse_size("1KiB").unwrap(), 1_024);
+/// assert_eq!(parse_size("2.5M").unwrap(), 2_500_000);
+/// assert_eq!(parse_size(" 1 G ").unwrap(), 1_000_000_000);
+/// assert!(parse_size("1.0005k").is_err());
+/// assert!(parse_size("1T").is_err());
+/// assert!(parse_size("5G").is_err());
+/// ```
+///
+/// [`num_fmt`]: ../../prelude/num_fmt/index.html (The num_fmt module)
+pub fn parse_size(s: &str) -> Res<u32> {
+    use std::convert::TryFrom;
+
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or_else(|| s.len());
+    let (num, unit) = (&s[..split], s[split..].trim_start());
+
+    let prefix = unit.strip_suffix('B').unwrap_or(unit);
+    let factor: u128 = match prefix.to_lowercase().as_str() {
+        "" => 1,
+        "k" => 1_000,
+        "m" => 1_000_000,
+        "g" => 1_000_000_000,
+        "ki" => 1 << 10,
+        "mi" => 1 << 20,
+        "gi" => 1 << 30,
+        _ => bail!(
+            "illegal size unit `{}`, expected `k`, `M`, `G`, `Ki`, `Mi` or `Gi`",
+            unit
+        ),
+    };
+
+    let mut parts = num.splitn(2, '.');
+    let (int, frac) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    if int.is_empty() && frac.is_empty() {
+        bail!("illegal size `{}`, expected a number", s)
+    }
+    // More digits than that can only yield a whole size if they are all zeros.
+    let frac = frac.trim_end_matches('0');
+    if frac.len() > 18 || frac.contains('.') {
+        bail!("illegal size `{}`", s)
+    }
+    let digits = |digits: &str| -> Res<u128> {
+        if digits.is_empty() {
+            Ok(0)
+        } else {
+            u128::from_str(digits).chain_err(|| format!("illegal size `{}`", s))
+        }
+    };
+    let (int, frac_den, frac) = (digits(int)?, 10u128.pow(frac.len() as u32), digits(frac)?);
+
+    if (frac * factor) % frac_den != 0 {
+        bail!("size `{}` is not a whole number of machine words", s)
+    }
+    let size = int
+        .checked_mul(factor)
+        .and_then(|size| size.checked_add(frac * factor / frac_den))
+        .ok_or_else(|| format!("size `{}` is too big", s))?;
+    u32::try_from(size).chain_err(|| format!("size `{}` is too big", s))
+}
+
+/// Human-readable version of a size that parses back to the exact same size.
+///
+/// Uses the decimal or binary prefixes of [`num_fmt`] when the pretty version is exact, and
+/// falls back on the plain number otherwise. See also [`parse_size`].
+///
+/// ```rust
+/// # use charts::filter::ord::size_to_string;
+/// assert_eq!(size_to_string(42), "42");
+/// assert_eq!(size_to_string(2_500_000), "2.50M");
+/// assert_eq!(size_to_string(1 << 20), "1.00Mi");
+/// assert_eq!(size_to_string(1_234), "1234");
+/// ```
+///
+/// [`num_fmt`]: ../../prelude/num_fmt/index.html (The num_fmt module)
+/// [`parse_size`]: fn.parse_size.html (The parse_size function)
+pub fn size_to_string(size: u32) -> String {
+    let exact =
+        |pretty: String| Some(pretty).filter(|pretty| parse_size(pretty).ok() == Some(size));
+    num_fmt::str_do(size, exact)
+        .or_else(|| num_fmt::bin_str_do(size, exact))
+        .unwrap_or_else(|| size.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn size_round_trip() {
+        let sizes = (0..2_000)
+            .chain((0..32).map(|shift| 1 << shift))
+            .chain((0..10).map(|pow| 10u32.pow(pow)))
+            .chain(vec![1_500, 2_500_000, 3 << 20, 1_048_575, std::u32::MAX]);
+        for size in sizes {
+            let s = size_to_string(size);
+            assert_eq!(parse_size(&s).unwrap(), size, "{:?}", s)
+        }
+    }
+
+    #[test]
+    fn illegal_sizes() {
+        for s in &[
+            "", "k", ".", "1..2", "1.2.3k", "-1", "1 kb", "1x", "0.5", "4.3G", "1e3",
+        ] {
+            assert!(parse_size(s).is_err(), "{:?}", s)
+        }
+    }
+}
@@ -0,0 +1,150 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Previews of the allocations a filter catches, while users edit it.
+//!
+//! A preview only applies the filter to the live allocations, it does not register the filter nor
+//! regenerate any point.
+
+prelude! {}
+
+#[cfg(any(test, feature = "server"))]
+use filter::Filter;
+
+/// Maximum number of example label lists in a preview.
+pub const MAX_EXAMPLES: usize = 5;
+
+/// Live allocations a filter catches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preview {
+    /// Number of live allocations caught.
+    pub count: u64,
+    /// Total size of the live allocations caught, in bytes.
+    pub bytes: u64,
+    /// Distinct label lists of some of the allocations caught, in order of creation.
+    pub examples: Vec<Vec<String>>,
+}
+
+base::implement! {
+    impl Preview {
+        Display {
+            |&self, fmt| write!(
+                fmt,
+                "catches {} live allocation(s), {}B",
+                num_fmt::str_do(self.count as f64, base::identity),
+                num_fmt::bin_str_do(self.bytes as f64, base::identity),
+            )
+        }
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl Preview {
+    /// Applies a filter to the live allocations of some data.
+    pub fn new(data: &data::Data, filter: &Filter) -> Self {
+        let now = data.current_time();
+        let (mut count, mut bytes, mut examples) = (0, 0, vec![]);
+
+        for alloc in data.iter_allocs() {
+            if alloc.tod.is_some() || !filter.apply(now, alloc) {
+                continue;
+            }
+            count += 1;
+            bytes += alloc.real_size as u64;
+            if examples.len() < MAX_EXAMPLES {
+                let labels: Vec<String> = alloc.labels().iter().map(|l| l.to_string()).collect();
+                if !examples.contains(&labels) {
+                    examples.push(labels)
+                }
+            }
+        }
+
+        // Sizes are already scaled by the parser.
+        count *= data.sampling() as u64;
+
+        Self {
+            count,
+            bytes,
+            examples,
+        }
+    }
+
+    /// Applies a filter to the live allocations of the global data.
+    pub fn of_filter(filter: &Filter) -> Res<Self> {
+        let data = data::get().chain_err(|| "while previewing a filter")?;
+        Ok(Self::new(&data, filter))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Builds some data with 10 live allocations labeled `leak` and 10 dead ones labeled `temp`.
+    ///
+    /// Allocations labeled `leak` have size 8, the others have size 16.
+    fn data() -> data::Data {
+        let mut synth = crate::fixture::Synth::new();
+        let trace = crate::fixture::trace(&[]);
+        let (leak, temp) = (
+            crate::fixture::labels(&["leak"]),
+            crate::fixture::labels(&["temp"]),
+        );
+
+        for idx in 0..20u64 {
+            let (labels, size) = if idx % 2 == 0 {
+                (&leak, 8)
+            } else {
+                (&temp, 16)
+            };
+            synth.alloc(idx, size, &trace, labels);
+        }
+        for idx in (1..20u64).step_by(2) {
+            synth.dead(20 + idx, idx)
+        }
+
+        synth.done()
+    }
+
+    /// A filter with a single label subfilter.
+    fn label_filter(spec: &str) -> Filter {
+        use filter::label::{LabelPred, LabelSpec};
+        let mut filter = Filter::new(filter::FilterSpec::new(Color::new(0, 0, 0))).unwrap();
+        let spec = LabelSpec::new(spec).unwrap();
+        filter
+            .insert(filter::LabelFilter::new(LabelPred::Contain, vec![spec]))
+            .unwrap();
+        filter
+    }
+
+    #[test]
+    fn live_allocations_only() {
+        let data = data();
+
+        let preview = Preview::new(&data, &label_filter("leak"));
+        assert_eq!(preview.count, 10);
+        assert_eq!(preview.bytes, 10 * 8);
+        assert_eq!(preview.examples, vec![vec!["leak".to_string()]]);
+
+        // Allocations labeled `temp` are all dead.
+        let preview = Preview::new(&data, &label_filter("temp"));
+        assert_eq!(preview.count, 0);
+        assert!(preview.examples.is_empty());
+    }
+}
@@ -21,13 +21,193 @@
 
 use super::*;
 
+/// A filter name.
+///
+/// Filter names end up in places where arbitrary strings are not welcome: CSV headers, file
+/// names... This type guarantees that a name is not empty and not longer than [`MAX_LEN`]
+/// characters, and provides escaping functions for each of these targets.
+///
+/// Names are (de)serialized as strings, deserialization fails on illegal names.
+///
+/// ```rust
+/// # use charts::filter::FilterName;
+/// let name = FilterName::new("my filter").unwrap();
+/// assert_eq!(serde_json::to_string(&name).unwrap(), r#""my filter""#);
+/// assert!(serde_json::from_str::<FilterName>(r#""""#).is_err());
+/// ```
+///
+/// [`MAX_LEN`]: #associatedconstant.MAX_LEN (MAX_LEN associated constant)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FilterName {
+    /// Actual name.
+    name: String,
+}
+impl FilterName {
+    /// Maximum length of a filter name, in characters.
+    pub const MAX_LEN: usize = 64;
+
+    /// Constructor.
+    ///
+    /// Fails if the name is empty or longer than [`MAX_LEN`] characters.
+    ///
+    /// ```rust
+    /// # use charts::filter::FilterName;
+    /// assert!(FilterName::new("my filter").is_ok());
+    /// assert!(FilterName::new("").is_err());
+    /// assert!(FilterName::new("a".repeat(FilterName::MAX_LEN)).is_ok());
+    /// assert!(FilterName::new("a".repeat(FilterName::MAX_LEN + 1)).is_err());
+    /// ```
+    ///
+    /// [`MAX_LEN`]: #associatedconstant.MAX_LEN (MAX_LEN associated constant)
+    pub fn new(name: impl Into<String>) -> Res<Self> {
+        let name = name.into();
+        if name.is_empty() {
+            bail!("filter names cannot be empty")
+        }
+        let len = name.chars().count();
+        if len > Self::MAX_LEN {
+            bail!(
+                "filter names cannot be longer than {} characters, `{}` has {}",
+                Self::MAX_LEN,
+                name,
+                len,
+            )
+        }
+        Ok(Self { name })
+    }
+
+    /// Constructor that truncates names that are too long.
+    ///
+    /// Empty names are replaced by `"?"`.
+    ///
+    /// ```rust
+    /// # use charts::filter::FilterName;
+    /// let name = FilterName::new_truncated("a".repeat(2 * FilterName::MAX_LEN));
+    /// assert_eq!(name.as_str().chars().count(), FilterName::MAX_LEN);
+    /// assert!(name.as_str().ends_with("..."));
+    /// assert_eq!(FilterName::new_truncated("").as_str(), "?");
+    /// ```
+    pub fn new_truncated(name: impl Into<String>) -> Self {
+        let mut name = name.into();
+        if name.is_empty() {
+            name.push('?')
+        } else if name.chars().count() > Self::MAX_LEN {
+            name = name.chars().take(Self::MAX_LEN - 3).collect();
+            name.push_str("...")
+        }
+        Self { name }
+    }
+
+    /// String slice of the name.
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    /// Escapes the name so that it can be used as a CSV field.
+    ///
+    /// The result is quoted whenever it contains a separator, a quote, a newline or leading or
+    /// trailing whitespaces. Names starting with a formula character (`=`, `+`, `-`, `@`) are
+    /// prefixed with `'` so that spreadsheets do not evaluate them.
+    ///
+    /// ```rust
+    /// # use charts::filter::FilterName;
+    /// let name = FilterName::new(r#"big "allocs", part 2/3"#).unwrap();
+    /// assert_eq!(name.csv_field(), r#""big ""allocs"", part 2/3""#);
+    /// let name = FilterName::new("simple").unwrap();
+    /// assert_eq!(name.csv_field(), "simple");
+    /// ```
+    pub fn csv_field(&self) -> String {
+        let mut res = String::with_capacity(self.name.len() + 2);
+        if self
+            .name
+            .starts_with(|c| c == '=' || c == '+' || c == '-' || c == '@')
+        {
+            res.push('\'')
+        }
+        res.push_str(&self.name.replace('"', "\"\""));
+        let needs_quotes = res
+            .chars()
+            .any(|c| c == ',' || c == '"' || c == '\n' || c == '\r' || c == ';')
+            || res.starts_with(char::is_whitespace)
+            || res.ends_with(char::is_whitespace);
+        if needs_quotes {
+            format!("\"{}\"", res)
+        } else {
+            res
+        }
+    }
+
+    /// Filename-safe slug for the name.
+    ///
+    /// Only keeps ASCII alphanumeric characters, `_` and `.` (except in leading position); all
+    /// other sequences of characters are replaced by a single `-`. Two names can have the same
+    /// slug.
+    ///
+    /// ```rust
+    /// # use charts::filter::FilterName;
+    /// let name = FilterName::new(r#"big "allocs", part 2/3"#).unwrap();
+    /// assert_eq!(name.slug(), "big-allocs-part-2-3");
+    /// let name = FilterName::new("../../etc/passwd").unwrap();
+    /// assert_eq!(name.slug(), "etc-passwd");
+    /// ```
+    pub fn slug(&self) -> String {
+        let mut res = String::with_capacity(self.name.len());
+        let mut pending_dash = false;
+        for c in self.name.chars() {
+            let legal = c.is_ascii_alphanumeric() || c == '_' || (c == '.' && !res.is_empty());
+            if legal {
+                if pending_dash && !res.is_empty() {
+                    res.push('-')
+                }
+                pending_dash = false;
+                res.push(c.to_ascii_lowercase())
+            } else {
+                pending_dash = true
+            }
+        }
+        // Trailing dots are not welcome on some file systems.
+        while res.ends_with('.') {
+            res.pop();
+        }
+        if res.is_empty() {
+            res.push_str("filter")
+        }
+        res
+    }
+}
+
+impl std::convert::TryFrom<String> for FilterName {
+    type Error = err::Error;
+    fn try_from(name: String) -> Res<Self> {
+        Self::new(name)
+    }
+}
+impl From<FilterName> for String {
+    fn from(name: FilterName) -> String {
+        name.name
+    }
+}
+
+base::implement! {
+    impl FilterName {
+        Display {
+            |&self, fmt| self.name.fmt(fmt),
+        }
+        Deref {
+            to str => |&self| &self.name
+        }
+    }
+}
+
 /// A filter specification.
 ///
 /// Contains the following:
 ///
 /// - an optional UID;
 /// - a name;
-/// - a color.
+/// - a color;
+/// - a mute flag.
 ///
 /// The UID is optional because the filter specification can belong the "catch all" line of charts.
 /// It is made from the points that all filters miss.
@@ -38,19 +218,25 @@ pub struct FilterSpec {
     /// None if the specification if for the catch-all filter.
     uid: uid::Line,
     /// Name of the filter.
-    name: String,
+    name: FilterName,
     /// Color of the filter.
     color: Color,
+    /// True if the filter is muted.
+    ///
+    /// A muted filter still catches allocations, but its line is hidden everywhere in the client.
+    #[serde(default)]
+    muted: bool,
 }
 impl FilterSpec {
     /// Constructor for user-defined filters.
     pub fn new(color: Color) -> Self {
         let uid = uid::Filter::fresh();
-        let name = "new filter".to_string();
+        let name = FilterName::new_truncated("new filter");
         Self {
             uid: uid::Line::Filter(uid),
             name,
             color,
+            muted: false,
         }
     }
 
@@ -58,8 +244,9 @@ impl FilterSpec {
     pub fn new_catch_all() -> Self {
         Self {
             uid: uid::Line::CatchAll,
-            name: "catch all".into(),
+            name: FilterName::new_truncated("catch all"),
             color: Color::new(0x01, 0x93, 0xff),
+            muted: false,
         }
     }
 
@@ -67,8 +254,31 @@ impl FilterSpec {
     pub fn new_everything() -> Self {
         Self {
             uid: uid::Line::Everything,
-            name: "everything".into(),
+            name: FilterName::new_truncated("everything"),
             color: Color::new(0xff, 0x66, 0x00),
+            muted: false,
+        }
+    }
+
+    /// Constructs a specification for a segment line.
+    ///
+    /// Segment lines are not filters, they only appear in charts grouping allocations by trace
+    /// segment.
+    ///
+    /// ```rust
+    /// # use charts::{color::Color, filter::FilterSpec};
+    /// # use base::prelude::uid;
+    /// let spec = FilterSpec::new_segment(uid::Segment::from(3), Color::new(0, 0, 0));
+    /// assert_eq!(spec.uid(), uid::Line::Segment(uid::Segment::from(3)));
+    /// assert_eq!(spec.name().as_str(), "segment #3");
+    /// assert!(!spec.is_user_provided());
+    /// ```
+    pub fn new_segment(segment: uid::Segment, color: Color) -> Self {
+        Self {
+            uid: uid::Line::Segment(segment),
+            name: FilterName::new_truncated(format!("segment #{}", segment)),
+            color,
+            muted: false,
         }
     }
 
@@ -82,7 +292,16 @@ impl FilterSpec {
     }
     /// True if the filter is user-provided.
     pub fn is_user_provided(&self) -> bool {
-        !(self.is_everything() || self.is_catch_all())
+        self.uid.filter_uid().is_some()
+    }
+    /// Description of the allocations a built-in line catches, `None` for user-provided filters.
+    pub fn builtin_desc(&self) -> Option<&'static str> {
+        match self.uid {
+            uid::Line::Everything => Some("all the allocations"),
+            uid::Line::CatchAll => Some("the allocations no filter catches"),
+            uid::Line::Segment(_) => Some("the allocations created during a trace segment"),
+            uid::Line::Filter(_) => None,
+        }
     }
 
     /// UID accessor.
@@ -90,13 +309,20 @@ impl FilterSpec {
         self.uid
     }
 
+    /// Gives a fresh UID to a custom filter, does nothing for built-in lines.
+    pub fn refresh_uid(&mut self) {
+        if let uid::Line::Filter(_) = self.uid {
+            self.uid = uid::Line::Filter(uid::Filter::fresh())
+        }
+    }
+
     /// Name accessor.
-    pub fn name(&self) -> &str {
+    pub fn name(&self) -> &FilterName {
         &self.name
     }
     /// Name setter.
-    pub fn set_name(&mut self, name: impl Into<String>) {
-        self.name = name.into()
+    pub fn set_name(&mut self, name: FilterName) {
+        self.name = name
     }
 
     /// Color accessor.
@@ -107,4 +333,117 @@ impl FilterSpec {
     pub fn set_color(&mut self, color: Color) {
         self.color = color
     }
+
+    /// True if the filter is muted.
+    ///
+    /// ```rust
+    /// # use charts::{color::Color, filter::FilterSpec};
+    /// let mut spec = FilterSpec::new(Color::new(0, 0, 0));
+    /// assert!(!spec.is_muted());
+    /// spec.set_muted(true);
+    /// assert!(spec.is_muted());
+    ///
+    /// // Collections saved before muting existed load unmuted.
+    /// let mut json: serde_json::Value = serde_json::to_value(&spec).unwrap();
+    /// json.as_object_mut().unwrap().remove("muted");
+    /// let spec: FilterSpec = serde_json::from_value(json).unwrap();
+    /// assert!(!spec.is_muted());
+    /// ```
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+    /// Mutes or unmutes the filter.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FilterName;
+
+    /// Names that are known to break naive exports.
+    const HOSTILE: &[&str] = &[
+        r#"big "allocs", part 2/3"#,
+        "=cmd|' /C calc'!A0",
+        "line\nbreak",
+        "carriage\rreturn",
+        r#"back\slash"#,
+        "  padded  ",
+        "../../etc/passwd",
+        "semi;colon",
+        "\"",
+        "///",
+        "CON",
+        "émoji 🔥",
+    ];
+
+    fn names() -> Vec<FilterName> {
+        HOSTILE
+            .iter()
+            .map(|name| FilterName::new(*name).unwrap())
+            .collect()
+    }
+
+    /// Parses a CSV field, fails if the field is not valid.
+    fn csv_unescape(field: &str) -> String {
+        if field.starts_with('"') {
+            assert!(field.len() >= 2 && field.ends_with('"'), "{}", field);
+            let inner = &field[1..field.len() - 1];
+            assert!(!inner.replace("\"\"", "").contains('"'), "{}", field);
+            inner.replace("\"\"", "\"")
+        } else {
+            for c in &[',', '"', '\n', '\r', ';'] {
+                assert!(!field.contains(*c), "{}", field)
+            }
+            field.into()
+        }
+    }
+
+    #[test]
+    fn csv() {
+        for name in names() {
+            let field = name.csv_field();
+            let unescaped = csv_unescape(&field);
+            if name.starts_with(|c| c == '=' || c == '+' || c == '-' || c == '@') {
+                assert_eq!(unescaped, format!("'{}", name))
+            } else {
+                assert_eq!(unescaped, name.as_str())
+            }
+        }
+    }
+
+    #[test]
+    fn slug() {
+        for name in names() {
+            let slug = name.slug();
+            assert!(!slug.is_empty());
+            assert!(!slug.starts_with('.') && !slug.starts_with('-'));
+            assert!(slug
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'));
+        }
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        for name in names() {
+            let json = serde_json::to_string(&name).unwrap();
+            assert_eq!(json, serde_json::to_string(name.as_str()).unwrap());
+            assert_eq!(serde_json::from_str::<FilterName>(&json).unwrap(), name)
+        }
+        let long = serde_json::to_string(&"a".repeat(FilterName::MAX_LEN + 1)).unwrap();
+        assert!(serde_json::from_str::<FilterName>(&long).is_err());
+    }
+
+    #[test]
+    fn length() {
+        assert!(FilterName::new("").is_err());
+        let long = "🔥".repeat(FilterName::MAX_LEN);
+        assert!(FilterName::new(long.clone()).is_ok());
+        assert!(FilterName::new(format!("{}a", long)).is_err());
+        let truncated = FilterName::new_truncated(format!("{}a", long));
+        assert_eq!(truncated.chars().count(), FilterName::MAX_LEN);
+        assert!(FilterName::new(truncated.as_str()).is_ok());
+    }
 }
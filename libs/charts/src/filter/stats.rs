@@ -28,17 +28,64 @@ prelude! {}
 pub struct FilterStats {
     /// Number of allocation caught by the filter.
     pub alloc_count: usize,
+    /// Total size of the allocations caught by the filter that are still alive, in bytes.
+    pub live_size: u64,
+    /// Number of allocations caught by the filter that are still alive.
+    pub live_count: usize,
+    /// Highest live size the filter reached, in bytes.
+    pub peak_live_size: u64,
+    /// Time at which the live size reached its peak, `None` if it never was positive.
+    pub peak_time: Option<time::SinceStart>,
 }
 impl FilterStats {
     /// Constructor.
     pub fn new() -> Self {
-        Self { alloc_count: 0 }
+        Self {
+            alloc_count: 0,
+            live_size: 0,
+            live_count: 0,
+            peak_live_size: 0,
+            peak_time: None,
+        }
     }
 
     /// Increments the number of allocations.
     pub fn inc(&mut self) {
         self.alloc_count += 1
     }
+
+    /// Registers a new live allocation at some time, updates the peak if needed.
+    ///
+    /// The first time the peak is reached is the one remembered.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, filter::stats::FilterStats};
+    /// let secs = time::SinceStart::from_secs;
+    /// let mut stats = FilterStats::new();
+    /// stats.add_live(secs(1), 10);
+    /// stats.add_live(secs(2), 5);
+    /// stats.remove_live(10);
+    /// stats.add_live(secs(3), 10);
+    /// stats.remove_live(5);
+    /// assert_eq!(stats.live_size, 10);
+    /// assert_eq!(stats.live_count, 1);
+    /// assert_eq!(stats.peak_live_size, 15);
+    /// assert_eq!(stats.peak_time, Some(secs(2)));
+    /// ```
+    pub fn add_live(&mut self, time: time::SinceStart, size: u64) {
+        self.live_count += 1;
+        self.live_size += size;
+        if self.live_size > self.peak_live_size {
+            self.peak_live_size = self.live_size;
+            self.peak_time = Some(time)
+        }
+    }
+
+    /// Registers the death of a live allocation.
+    pub fn remove_live(&mut self, size: u64) {
+        self.live_count = self.live_count.saturating_sub(1);
+        self.live_size = self.live_size.saturating_sub(size)
+    }
 }
 
 /// Contains statistics for all filters.
@@ -83,4 +130,54 @@ impl AllFilterStats {
     pub fn get(&self, filter: uid::Line) -> Option<&FilterStats> {
         self.stats.get(&filter)
     }
+
+    /// Multiplies the allocation counts by some factor.
+    ///
+    /// Used when the data is an estimate, see [`Estimate`].
+    ///
+    /// [`Estimate`]: ../../prelude/struct.Estimate.html (The Estimate struct)
+    pub fn scale_counts(&mut self, factor: usize) {
+        for stats in self.stats.values_mut() {
+            stats.alloc_count *= factor;
+            stats.live_count *= factor
+        }
+    }
+
+    /// Composition of the live heap: the live size of each line and its share of the total.
+    ///
+    /// Ignores the everything line, since it overlaps with all the others, and lines with no live
+    /// allocation. Shares are between `0` and `1`.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, filter::stats::AllFilterStats};
+    /// let mut stats = AllFilterStats::new();
+    /// stats.stats_mut(uid::Line::Everything).live_size = 100;
+    /// stats.stats_mut(uid::Line::Filter(uid::Filter::from(0))).live_size = 75;
+    /// stats.stats_mut(uid::Line::Filter(uid::Filter::from(1))).live_size = 0;
+    /// stats.stats_mut(uid::Line::CatchAll).live_size = 25;
+    /// assert_eq!(
+    ///     stats.composition(),
+    ///     vec![
+    ///         (uid::Line::Filter(uid::Filter::from(0)), 75, 0.75),
+    ///         (uid::Line::CatchAll, 25, 0.25),
+    ///     ],
+    /// );
+    /// ```
+    pub fn composition(&self) -> Vec<(uid::Line, u64, f64)> {
+        let live = || {
+            self.stats
+                .iter()
+                .filter(|(line, stats)| **line != uid::Line::Everything && stats.live_size > 0)
+        };
+        let total: u64 = live().map(|(_, stats)| stats.live_size).sum();
+        live()
+            .map(|(line, stats)| {
+                (
+                    *line,
+                    stats.live_size,
+                    stats.live_size as f64 / total as f64,
+                )
+            })
+            .collect()
+    }
 }
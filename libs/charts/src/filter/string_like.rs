@@ -131,6 +131,10 @@ impl<Spec> StringLikeFilter<Spec> {
     pub fn specs(&self) -> &Vec<Spec> {
         &self.specs
     }
+    /// Mutable specifications of a filter.
+    pub fn specs_mut(&mut self) -> &mut Vec<Spec> {
+        &mut self.specs
+    }
 }
 
 impl<Spec> FilterExt<Arc<Vec<Spec::Data>>> for StringLikeFilter<Spec>
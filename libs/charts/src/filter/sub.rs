@@ -37,6 +37,12 @@ pub enum RawSubFilter {
     Label(LabelFilter),
     /// Filter over locations.
     Loc(LocFilter),
+    /// Filter over the frames of the callstack.
+    Frame(FrameFilter),
+    /// Filter over the number of samples.
+    ///
+    /// Comes last so that the encoding of the other variants does not change.
+    NSamples(NSamplesFilter),
 }
 
 impl RawSubFilter {
@@ -47,6 +53,8 @@ impl RawSubFilter {
             Self::Lifetime(_) => FilterKind::Lifetime,
             Self::Label(_) => FilterKind::Label,
             Self::Loc(_) => FilterKind::Loc,
+            Self::Frame(_) => FilterKind::Frame,
+            Self::NSamples(_) => FilterKind::NSamples,
         }
     }
 
@@ -62,7 +70,15 @@ impl RawSubFilter {
                 filter.apply_at(&timestamp, &alloc.toc())
             }
             RawSubFilter::Label(filter) => filter.apply(&alloc.labels()),
+            RawSubFilter::Loc(_) if alloc.has_unresolved_locs() => {
+                filter::loc::unresolved_policy().result()
+            }
             RawSubFilter::Loc(filter) => filter.apply(&alloc.trace()),
+            RawSubFilter::Frame(_) if alloc.has_unresolved_locs() => {
+                filter::loc::unresolved_policy().result()
+            }
+            RawSubFilter::Frame(filter) => filter.apply(&alloc.trace()[..]),
+            RawSubFilter::NSamples(filter) => filter.apply(&alloc.nsamples),
         }
     }
 
@@ -102,6 +118,14 @@ impl RawSubFilter {
                 Update::Loc(update) => filter.update(update),
                 _ => fail!(),
             },
+            Self::Frame(filter) => match update {
+                Update::Frame(update) => filter.update(update),
+                _ => fail!(),
+            },
+            Self::NSamples(filter) => match update {
+                Update::NSamples(update) => filter.update(update),
+                _ => fail!(),
+            },
         }
     }
 }
@@ -160,6 +184,15 @@ impl SubFilter {
         &self.raw
     }
 
+    /// Memoizes the results of the label regexes of the subfilter on some labels.
+    pub fn memoize_labels(&mut self, labels: &[alloc::Str]) {
+        if let RawSubFilter::Label(filter) = &mut self.raw {
+            for spec in filter.specs_mut() {
+                spec.memoize(labels)
+            }
+        }
+    }
+
     /// True if the subfilter was created from the client.
     pub fn is_from_client(&self) -> bool {
         self.from_client
@@ -174,6 +207,11 @@ impl SubFilter {
             self.uid = uid::SubFilter::fresh()
         }
     }
+
+    /// Gives a fresh UID to the subfilter.
+    pub fn refresh_uid(&mut self) {
+        self.uid = uid::SubFilter::fresh()
+    }
 }
 
 /// Sub-filter update.
@@ -186,6 +224,10 @@ pub enum Update {
     Label(label::LabelUpdate),
     /// Location filter update.
     Loc(loc::LocUpdate),
+    /// Frame filter update.
+    Frame(frame::FrameUpdate),
+    /// Sample count filter update.
+    NSamples(ord::NSamplesUpdate),
 }
 
 base::implement! {
@@ -214,6 +256,9 @@ base::implement! {
             from LocFilter => |filter| Self::from(
                 uid::SubFilter::fresh(), RawSubFilter::from(filter)
             ),
+            from FrameFilter => |filter| Self::from(
+                uid::SubFilter::fresh(), RawSubFilter::from(filter)
+            ),
             from RawSubFilter => |filter| Self::from(
                 uid::SubFilter::fresh(), filter
             ),
@@ -234,6 +279,8 @@ base::implement! {
                 Self::Lifetime(filter) => write!(fmt, "lifetime {}", filter),
                 Self::Label(filter) => write!(fmt, "labels {}", filter),
                 Self::Loc(filter) => write!(fmt, "callstack {}", filter),
+                Self::Frame(filter) => write!(fmt, "callstack {}", filter),
+                Self::NSamples(filter) => write!(fmt, "nsamples {}", filter),
             }
         }
 
@@ -247,11 +294,15 @@ base::implement! {
                 FilterKind::Lifetime => LifetimeFilter::default().into(),
                 FilterKind::Label => LabelFilter::default().into(),
                 FilterKind::Loc => LocFilter::default().into(),
+                FilterKind::Frame => FrameFilter::default().into(),
+                // Same type as size filters, no `From` conversion.
+                FilterKind::NSamples => Self::NSamples(NSamplesFilter::default()),
             },
             from SizeFilter => |filter| Self::Size(filter),
             from LifetimeFilter => |filter| Self::Lifetime(filter),
             from LabelFilter => |filter| Self::Label(filter),
             from LocFilter => |filter| Self::Loc(filter),
+            from FrameFilter => |filter| Self::Frame(filter),
         }
     }
 
@@ -262,7 +313,64 @@ base::implement! {
                 Self::Lifetime(update) => update.fmt(fmt),
                 Self::Label(update) => update.fmt(fmt),
                 Self::Loc(update) => update.fmt(fmt),
+                Self::Frame(update) => update.fmt(fmt),
+                Self::NSamples(update) => update.fmt(fmt),
             }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nsamples() {
+        let init = alloc::Init::default();
+        let new_alloc = |nsamples| {
+            Alloc::new(
+                &init.sample_rate,
+                uid::Alloc::new(0),
+                alloc::AllocKind::Minor,
+                16,
+                alloc::mem::trace::add(vec![]),
+                alloc::mem::labels::add(vec![]),
+                time::SinceStart::zero(),
+                None,
+            )
+            .nsamples(nsamples)
+        };
+        let now = time::SinceStart::zero();
+
+        let mut sub = RawSubFilter::from(FilterKind::NSamples);
+        assert_eq!(sub.kind(), FilterKind::NSamples);
+        assert!(sub
+            .update(Update::NSamples(ord::Update::Cmp(ord::Cmp::Ge)))
+            .unwrap());
+        assert!(sub.update(Update::NSamples(ord::Update::Value(4))).unwrap());
+        assert!(sub.update(Update::Size(ord::Update::Value(4))).is_err());
+        assert_eq!(sub.to_string(), "nsamples ≥ 4");
+        assert!(!sub.apply(&now, &new_alloc(3)));
+        assert!(sub.apply(&now, &new_alloc(4)));
+        assert!(sub.apply(&now, &new_alloc(100)));
+    }
+
+    #[test]
+    fn older_variants_decode() {
+        // Filters saved before sample count filters existed.
+        let json = r#"{"Size":{"Cmp":{"cmp":"Ge","val":4}}}"#;
+        let size: RawSubFilter = serde_json::from_str(json).unwrap();
+        assert_eq!(size, SizeFilter::cmp(ord::Cmp::Ge, 4).into());
+        assert_eq!(serde_json::to_string(&size).unwrap(), json);
+        let frame: RawSubFilter = FrameFilter::default().into();
+        assert_eq!(msg::encode(&frame).unwrap()[0], 4);
+
+        let nsamples = RawSubFilter::NSamples(NSamplesFilter::cmp(ord::Cmp::Ge, 4));
+        let json = serde_json::to_string(&nsamples).unwrap();
+        assert_eq!(json, r#"{"NSamples":{"Cmp":{"cmp":"Ge","val":4}}}"#);
+        assert_eq!(
+            serde_json::from_str::<RawSubFilter>(&json).unwrap(),
+            nsamples
+        );
+    }
+}
@@ -0,0 +1,779 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Textual syntax for filters, to copy and share them.
+//!
+//! A filter is a single line: its name, its color, an optional `muted` flag and its root group.
+//! Groups are `all(...)` or `any(...)` around a comma-separated list of subfilters and nested
+//! groups, for instance
+//!
+//! ```text
+//! "big parsers" #ff6600 all(size >= 1.00Mi, any(labels contains ["parse"*], nsamples = 1))
+//! ```
+//!
+//! Subfilters are
+//!
+//! - `size <ord>`, `nsamples <ord>` and `lifetime <ord>`, where `<ord>` is `= v`, `>= v`, `<= v`
+//!   or `in [lb, ub]`; sizes are in the format of [`parse_size`], lifetimes in the format of
+//!   [`parse_human`];
+//! - `labels <pred> [<label>, ...]` where `<pred>` is `contains` or `excludes`, and a label is
+//!   `**` (anything), `"s"`, `"s"*` (prefix), `*"s"` (suffix) or `#"regex"#`;
+//! - `callstack <pred> [<loc>, ...]` where a location is `**`, `"file"` or `#"regex"#`,
+//!   optionally followed by `:` and a line `n` or a range of lines `[lb, ub]`, where bounds can be
+//!   `_`;
+//! - `frame <pred> <path>`, optionally followed by `lines [lb, ub]`, where the path is `"path"`,
+//!   `"prefix"*` or `#"regex"#`.
+//!
+//! Strings are double-quoted, with `\\`, `\"`, `\n`, `\r` and `\t` escapes. Whole filter sets are
+//! [`share`]d as the lines of their filters, deflated and encoded in URL-safe base64.
+//!
+//! [`parse_size`]: ../ord/fn.parse_size.html (The parse_size function)
+//! [`parse_human`]: ../../prelude/time/trait.DurationExt.html#method.parse_human
+//! (DurationExt::parse_human)
+//! [`share`]: fn.share.html (The share function)
+
+prelude! {}
+
+use std::fmt::Write;
+
+use filter::{
+    frame::PathSpec,
+    label::{LabelRegex, LabelSpec},
+    loc::{LineSpec, LocSpec},
+    ord::{self, Cmp, OrdFilter},
+    string_like::Pred,
+    sub::RawSubFilter,
+    FilterName, FilterSpec, FrameFilter, Group, GroupOp, LabelFilter, LocFilter, SubFilter,
+};
+
+pub use parser::filter as parse;
+
+/// A member of a group, as parsed.
+#[derive(Debug, Clone)]
+pub enum Member {
+    /// A subfilter.
+    Sub(RawSubFilter),
+    /// A nested group, with its operator and its members.
+    Group(GroupOp, Vec<Member>),
+}
+
+/// Checks the bounds of an interval filter, for the parser.
+fn ordered<T>(filter: OrdFilter<T>) -> Result<OrdFilter<T>, &'static str>
+where
+    T: PartialEq + PartialOrd + fmt::Debug,
+{
+    match filter {
+        OrdFilter::In { lb, ub } => OrdFilter::between(lb, ub)
+            .map_err(|_| "interval with a lower-bound below its upper-bound"),
+        filter => Ok(filter),
+    }
+}
+
+peg::parser! {
+    grammar parser() for str {
+        /// Whitespaces, newlines excluded since filters are single lines.
+        rule _() = quiet! {
+            [' ' | '\t']*
+        }
+        /// Separator of the elements of a list.
+        rule sep() = _ "," _
+
+        /// An escaped character, without the leading backslash.
+        rule escaped() -> char
+        = "\\" { '\\' }
+        / "\"" { '"' }
+        / "n" { '\n' }
+        / "r" { '\r' }
+        / "t" { '\t' }
+        / expected!("escape sequence")
+
+        /// A character of a string.
+        rule char() -> char
+        = "\\" c: escaped() { c }
+        / !['"' | '\\'] c: $([_]) { c.chars().next().expect("slice of one character") }
+
+        /// A double-quoted string.
+        rule string() -> String
+        = "\"" chars: (char()*) "\"" { chars.into_iter().collect() }
+        / expected!("double-quoted string")
+
+        /// A regex, as a string between hashes.
+        rule regex() -> Regex
+        = "#" s: string() "#" {? Regex::new(&s).map_err(|_| "legal regex") }
+
+        /// An integer, usize.
+        rule usize() -> usize
+        = quiet! {
+            n: $(['0'..='9']+) {? n.parse().map_err(|_| "integer (usize)") }
+        }
+        / expected!("integer (usize)")
+
+        /// An integer, u32.
+        rule u32() -> u32
+        = quiet! {
+            n: $(['0'..='9']+) {? n.parse().map_err(|_| "integer (u32)") }
+        }
+        / expected!("integer (u32)")
+
+        /// A size, see `ord::parse_size`.
+        rule size() -> u32
+        = quiet! {
+            s: $(['0'..='9' | '.']+ ['a'..='z' | 'A'..='Z']*) {?
+                ord::parse_size(s).map_err(|_| "size")
+            }
+        }
+        / expected!("size")
+
+        /// A lifetime, see `DurationExt::parse_human`.
+        rule lifetime() -> time::Lifetime
+        = quiet! {
+            s: $((['0'..='9' | '.']+ ['a'..='z' | 'µ']*)+) {?
+                time::Lifetime::parse_human(s).map_err(|_| "duration")
+            }
+        }
+        / expected!("duration")
+
+        /// A comparison operator.
+        rule cmp() -> Cmp
+        = (">=" / "≥") { Cmp::Ge }
+        / ("<=" / "≤") { Cmp::Le }
+        / "=" { Cmp::Eq }
+
+        /// A filter over an ordered quantity.
+        rule ord<T>(value: rule<T>) -> OrdFilter<T>
+        = "in" _ "[" _ lb: value() sep() ub: value() _ "]" { OrdFilter::In { lb, ub } }
+        / cmp: cmp() _ val: value() { OrdFilter::Cmp { cmp, val } }
+        / expected!("comparison or interval")
+
+        /// A comma-separated list between brackets.
+        rule list<T>(elem: rule<T>) -> Vec<T>
+        = "[" _ elems: (elem() ** sep()) _ "]" { elems }
+
+        /// A list predicate.
+        rule pred() -> Pred
+        = "contains" { Pred::Contain }
+        / "excludes" { Pred::Exclude }
+        / expected!("`contains` or `excludes`")
+
+        /// A label specification.
+        rule label() -> LabelSpec
+        = "**" { LabelSpec::Anything }
+        / "*" s: string() { LabelSpec::Suffix(s) }
+        / regex: regex() { LabelSpec::Regex(LabelRegex::new(regex)) }
+        / s: string() "*" { LabelSpec::Prefix(s) }
+        / s: string() { LabelSpec::Value(s) }
+
+        /// A bound of a range of lines, `_` if none.
+        rule bound() -> Option<usize>
+        = "_" { None }
+        / n: usize() { Some(n) }
+
+        /// A range of lines.
+        rule bounds() -> (Option<usize>, Option<usize>)
+        = "[" _ lb: bound() sep() ub: bound() _ "]" { (lb, ub) }
+
+        /// The line specification of a location, if any.
+        rule line() -> LineSpec
+        = ":" _ bounds: bounds() { LineSpec::range(bounds.0, bounds.1) }
+        / ":" _ n: usize() { LineSpec::line(n) }
+        / "" { LineSpec::any() }
+
+        /// A location specification.
+        rule loc() -> LocSpec
+        = "**" { LocSpec::Anything }
+        / regex: regex() line: line() { LocSpec::Regex { regex, line } }
+        / value: string() line: line() { LocSpec::Value { value, line } }
+
+        /// The path specification of a frame filter.
+        rule path() -> PathSpec
+        = regex: regex() { PathSpec::Regex(regex) }
+        / s: string() "*" { PathSpec::Prefix(s) }
+        / s: string() { PathSpec::Exact(s) }
+
+        /// A subfilter.
+        rule sub() -> RawSubFilter
+        = "size" _ filter: ord(<size()>) {? ordered(filter).map(RawSubFilter::Size) }
+        / "nsamples" _ filter: ord(<u32()>) {? ordered(filter).map(RawSubFilter::NSamples) }
+        / "lifetime" _ filter: ord(<lifetime()>) {? ordered(filter).map(RawSubFilter::Lifetime) }
+        / "labels" _ pred: pred() _ specs: list(<label()>) {
+            RawSubFilter::Label(LabelFilter::new(pred, specs))
+        }
+        / "callstack" _ pred: pred() _ specs: list(<loc()>) {
+            RawSubFilter::Loc(LocFilter::new(pred, specs))
+        }
+        / "frame" _ pred: pred() _ path: path() lines: (_ "lines" _ b: bounds() { b })? {
+            let (lb, ub) = lines.unwrap_or((None, None));
+            RawSubFilter::Frame(FrameFilter::new(pred, path, lb, ub))
+        }
+
+        /// A group operator.
+        rule op() -> GroupOp
+        = "all" { GroupOp::And }
+        / "any" { GroupOp::Or }
+
+        /// A member of a group.
+        rule member() -> Member
+        = group: group() { Member::Group(group.0, group.1) }
+        / sub: sub() { Member::Sub(sub) }
+        / expected!("subfilter or group")
+
+        /// A group.
+        rule group() -> (GroupOp, Vec<Member>)
+        = op: op() _ "(" _ members: (member() ** sep()) _ ")" { (op, members) }
+
+        /// The name of a filter.
+        rule name() -> FilterName
+        = name: string() {? FilterName::new(name).map_err(|_| "name of 1 to 64 characters") }
+
+        /// A color, see `Color::from_str`.
+        rule color() -> Color
+        = quiet! {
+            color: $("#" ['0'..='9' | 'a'..='f' | 'A'..='F']+) {?
+                Color::from_str(color).map_err(|_| "color")
+            }
+        }
+        / expected!("hexadecimal color")
+
+        /// A filter: name, color, muted flag and root group.
+        pub rule filter() -> (FilterName, Color, bool, (GroupOp, Vec<Member>))
+        = _ name: name() _ color: color() _ muted: ("muted" _)? root: group() _ {
+            (name, color, muted.is_some(), root)
+        }
+    }
+}
+
+/// Writes a string, double-quoted and escaped.
+fn write_string(s: &mut String, string: &str) {
+    s.push('"');
+    for c in string.chars() {
+        match c {
+            '\\' => s.push_str("\\\\"),
+            '"' => s.push_str("\\\""),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c => s.push(c),
+        }
+    }
+    s.push('"')
+}
+
+/// Writes a regex.
+fn write_regex(s: &mut String, regex: &Regex) {
+    s.push('#');
+    write_string(s, regex.as_str());
+    s.push('#')
+}
+
+/// Writes a filter over an ordered quantity.
+fn write_ord<T>(s: &mut String, filter: &OrdFilter<T>, value: impl Fn(&T) -> String) {
+    match filter {
+        OrdFilter::Cmp { cmp, val } => {
+            let cmp = match cmp {
+                Cmp::Eq => "=",
+                Cmp::Ge => ">=",
+                Cmp::Le => "<=",
+            };
+            write!(s, "{} {}", cmp, value(val)).expect("writing to a string cannot fail")
+        }
+        OrdFilter::In { lb, ub } => {
+            write!(s, "in [{}, {}]", value(lb), value(ub)).expect("writing to a string cannot fail")
+        }
+    }
+}
+
+/// Writes a range of lines.
+fn write_bounds(s: &mut String, lb: Option<usize>, ub: Option<usize>) {
+    let bound = |bound: Option<usize>| bound.map(|n| n.to_string()).unwrap_or_else(|| "_".into());
+    write!(s, "[{}, {}]", bound(lb), bound(ub)).expect("writing to a string cannot fail")
+}
+
+/// Writes a list of specifications.
+fn write_list<T>(s: &mut String, elems: &[T], write_elem: impl Fn(&mut String, &T)) {
+    s.push('[');
+    for (index, elem) in elems.iter().enumerate() {
+        if index > 0 {
+            s.push_str(", ")
+        }
+        write_elem(s, elem)
+    }
+    s.push(']')
+}
+
+/// Writes a subfilter.
+fn write_sub(s: &mut String, sub: &RawSubFilter) {
+    match sub {
+        RawSubFilter::Size(filter) => {
+            s.push_str("size ");
+            write_ord(s, filter, |size| ord::size_to_string(*size))
+        }
+        RawSubFilter::NSamples(filter) => {
+            s.push_str("nsamples ");
+            write_ord(s, filter, u32::to_string)
+        }
+        RawSubFilter::Lifetime(filter) => {
+            s.push_str("lifetime ");
+            write_ord(s, filter, |lifetime| lifetime.fmt_human().to_string())
+        }
+        RawSubFilter::Label(filter) => {
+            write!(s, "labels {} ", filter.pred()).expect("writing to a string cannot fail");
+            write_list(s, filter.specs(), |s, spec| match spec {
+                LabelSpec::Anything => s.push_str("**"),
+                LabelSpec::Value(value) => write_string(s, value),
+                LabelSpec::Prefix(prefix) => {
+                    write_string(s, prefix);
+                    s.push('*')
+                }
+                LabelSpec::Suffix(suffix) => {
+                    s.push('*');
+                    write_string(s, suffix)
+                }
+                LabelSpec::Regex(regex) => write_regex(s, regex.regex()),
+            })
+        }
+        RawSubFilter::Loc(filter) => {
+            write!(s, "callstack {} ", filter.pred()).expect("writing to a string cannot fail");
+            write_list(s, filter.specs(), |s, spec| {
+                let line = match spec {
+                    LocSpec::Anything => {
+                        s.push_str("**");
+                        return;
+                    }
+                    LocSpec::Value { value, line } => {
+                        write_string(s, value);
+                        line
+                    }
+                    LocSpec::Regex { regex, line } => {
+                        write_regex(s, regex);
+                        line
+                    }
+                };
+                match line {
+                    LineSpec::Value(n) => {
+                        write!(s, ":{}", n).expect("writing to a string cannot fail")
+                    }
+                    LineSpec::Range { lb: None, ub: None } => (),
+                    LineSpec::Range { lb, ub } => {
+                        s.push(':');
+                        write_bounds(s, *lb, *ub)
+                    }
+                }
+            })
+        }
+        RawSubFilter::Frame(filter) => {
+            write!(s, "frame {} ", filter.pred).expect("writing to a string cannot fail");
+            match &filter.path {
+                PathSpec::Exact(path) => write_string(s, path),
+                PathSpec::Prefix(prefix) => {
+                    write_string(s, prefix);
+                    s.push('*')
+                }
+                PathSpec::Regex(regex) => write_regex(s, regex),
+            }
+            if filter.line_lb.is_some() || filter.line_ub.is_some() {
+                s.push_str(" lines ");
+                write_bounds(s, filter.line_lb, filter.line_ub)
+            }
+        }
+    }
+}
+
+/// Writes a group, given its subfilters.
+fn write_group<'a>(
+    s: &mut String,
+    filter: &'a Filter,
+    group: &'a Group,
+    subs: impl Iterator<Item = &'a SubFilter>,
+) {
+    s.push_str(match group.op() {
+        GroupOp::And => "all(",
+        GroupOp::Or => "any(",
+    });
+    let mut first = true;
+    for sub in subs {
+        if !first {
+            s.push_str(", ")
+        }
+        first = false;
+        write_sub(s, sub.raw())
+    }
+    for nested in group.groups() {
+        if !first {
+            s.push_str(", ")
+        }
+        first = false;
+        write_group(s, filter, nested, filter.group_subs(nested))
+    }
+    s.push(')')
+}
+
+/// Textual version of a filter, see the [module-level documentation](index.html).
+///
+/// Nested groups come after the subfilters of their parent group.
+///
+/// ```rust
+/// # use charts::{color::Color, filter::{*, label::LabelSpec, text}};
+/// let mut filter = Filter::new(FilterSpec::new(Color::new(0xff, 0x66, 0))).unwrap();
+/// filter.spec_mut().set_name(FilterName::new("parsers").unwrap());
+/// filter
+///     .insert(LabelFilter::contain(vec![LabelSpec::Prefix("parse".into())]))
+///     .unwrap();
+///
+/// let text = text::to_text(&filter);
+/// assert_eq!(text, r#""parsers" #ff6600 all(labels contains ["parse"*])"#);
+/// let parsed = text::from_text(&text).unwrap();
+/// assert_ne!(parsed.uid(), filter.uid());
+/// assert_eq!(text::to_text(&parsed), text);
+/// ```
+pub fn to_text(filter: &Filter) -> String {
+    let mut s = String::new();
+    write_string(&mut s, filter.name().as_str());
+    write!(s, " {}", filter.spec().color()).expect("writing to a string cannot fail");
+    if filter.spec().is_muted() {
+        s.push_str(" muted")
+    }
+    s.push(' ');
+    write_group(&mut s, filter, filter.root(), filter.ungrouped());
+    s
+}
+
+/// Adds the members of a group to the group at some path of a filter.
+fn add_members(filter: &mut Filter, path: &mut Vec<usize>, members: Vec<Member>) -> Res<()> {
+    let mut groups = 0;
+    for member in members {
+        match member {
+            Member::Sub(raw) => {
+                let sub = SubFilter::from(raw);
+                let uid = sub.uid();
+                filter.insert(sub)?;
+                filter.move_sub(uid, path)?
+            }
+            Member::Group(op, members) => {
+                filter.add_group(path, op)?;
+                path.push(groups);
+                groups += 1;
+                add_members(filter, path, members)?;
+                path.pop();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses a filter, see the [module-level documentation](index.html).
+///
+/// The filter has fresh UIDs.
+///
+/// ```rust
+/// # use charts::filter::text::from_text;
+/// let filter = from_text(r#""old" #0193ff muted any(lifetime >= 2m30s, all(size in [1k, 2k]))"#)
+///     .unwrap();
+/// assert_eq!(filter.name().as_str(), "old");
+/// assert!(filter.spec().is_muted());
+/// assert_eq!(filter.iter().count(), 2);
+/// assert_eq!(filter.root().groups().len(), 1);
+///
+/// assert!(from_text(r#""old" #0193ff any(lifetime >= 2 weeks)"#).is_err());
+/// assert!(from_text(r#""old" #0193ff any(size in [1k, 2k)"#).is_err());
+/// ```
+pub fn from_text(text: &str) -> Res<Filter> {
+    let (name, color, muted, (op, members)) = parse(text)?;
+    let mut spec = FilterSpec::new(color);
+    spec.set_name(name);
+    spec.set_muted(muted);
+    let mut filter = Filter::new(spec)?;
+    filter.set_group_op(&[], op)?;
+    add_members(&mut filter, &mut vec![], members)?;
+    Ok(filter)
+}
+
+/// Textual version of some filters, one per line.
+pub fn to_texts<'a>(filters: impl IntoIterator<Item = &'a Filter>) -> String {
+    let lines: Vec<_> = filters.into_iter().map(to_text).collect();
+    lines.join("\n")
+}
+
+/// Parses some filters, one per line.
+///
+/// Blank lines are ignored. Errors mention the line of the filter that does not parse.
+///
+/// ```rust
+/// # use charts::filter::text::from_texts;
+/// let filters = from_texts("\"a\" #000000 all()\n\n  \"b\" #ffffff any(nsamples = 1)\n").unwrap();
+/// assert_eq!(filters.len(), 2);
+/// assert!(from_texts("\"a\" #000000 all()\n\"b\" #ffffff").is_err());
+/// ```
+pub fn from_texts(text: &str) -> Res<Vec<Filter>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| from_text(line).chain_err(|| format!("on line {}", index + 1)))
+        .collect()
+}
+
+/// Encodes some filters so that they fit in a URL, see [`unshare`].
+///
+/// [`unshare`]: fn.unshare.html (The unshare function)
+pub fn share<'a>(filters: impl IntoIterator<Item = &'a Filter>) -> String {
+    let text = to_texts(filters);
+    let bytes = miniz_oxide::deflate::compress_to_vec(text.as_bytes(), msg::compress::LEVEL);
+    base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Decodes some filters encoded by [`share`], as text.
+///
+/// The filters themselves are not parsed, see [`from_texts`].
+///
+/// ```rust
+/// # use charts::filter::text::*;
+/// let text = "\"a\" #000000 all()\n\"b\" #ffffff any(nsamples = 1)";
+/// let filters = from_texts(text).unwrap();
+/// assert_eq!(unshare(&share(&filters)).unwrap(), text);
+/// assert!(unshare("not base64!").is_err());
+/// assert!(unshare("bm90IGRlZmxhdGVk").is_err());
+/// ```
+///
+/// [`share`]: fn.share.html (The share function)
+/// [`from_texts`]: fn.from_texts.html (The from_texts function)
+pub fn unshare(shared: &str) -> Res<String> {
+    let bytes = base64::decode_config(shared.trim(), base64::URL_SAFE_NO_PAD)
+        .map_err(|e| format!("illegal shared filters: {}", e))?;
+    let bytes = miniz_oxide::inflate::decompress_to_vec(&bytes)
+        .map_err(|e| format!("failed to inflate shared filters: {:?}", e))?;
+    String::from_utf8(bytes).chain_err(|| "shared filters are not legal UTF-8")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use base::rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    /// Random string, with characters the syntax must escape.
+    fn string(rng: &mut SmallRng) -> String {
+        const CHARS: &[char] = &[
+            'a', 'z', '0', '9', ' ', '_', '/', '.', '*', '#', '"', '\\', '\n', '\r', '\t', ',',
+            '(', ']', 'µ', '∀',
+        ];
+        let len = rng.gen_range(0, 8);
+        (0..len)
+            .map(|_| CHARS[rng.gen_range(0, CHARS.len())])
+            .collect()
+    }
+
+    /// Random legal regex.
+    fn regex(rng: &mut SmallRng) -> Regex {
+        Regex::new(&regex::escape(&string(rng))).unwrap()
+    }
+
+    /// Random optional bound.
+    fn bound(rng: &mut SmallRng) -> Option<usize> {
+        if rng.gen() {
+            Some(rng.gen_range(0, 10_000))
+        } else {
+            None
+        }
+    }
+
+    /// Random filter over an ordered quantity.
+    fn ord<T>(rng: &mut SmallRng, value: impl Fn(&mut SmallRng) -> T) -> OrdFilter<T>
+    where
+        T: PartialEq + PartialOrd + fmt::Debug,
+    {
+        match rng.gen_range(0, 4) {
+            0 => OrdFilter::cmp(Cmp::Eq, value(rng)),
+            1 => OrdFilter::cmp(Cmp::Ge, value(rng)),
+            2 => OrdFilter::cmp(Cmp::Le, value(rng)),
+            _ => {
+                let (lb, ub) = (value(rng), value(rng));
+                if lb <= ub {
+                    OrdFilter::between(lb, ub).unwrap()
+                } else {
+                    OrdFilter::between(ub, lb).unwrap()
+                }
+            }
+        }
+    }
+
+    /// Random list predicate.
+    fn pred(rng: &mut SmallRng) -> Pred {
+        if rng.gen() {
+            Pred::Contain
+        } else {
+            Pred::Exclude
+        }
+    }
+
+    /// Random subfilter.
+    fn sub(rng: &mut SmallRng) -> RawSubFilter {
+        match rng.gen_range(0, 6) {
+            0 => RawSubFilter::Size(ord(rng, |rng| match rng.gen_range(0, 3) {
+                0 => rng.gen(),
+                1 => rng.gen_range(0, 2_000),
+                _ => rng.gen_range(1, 1_000) << 20,
+            })),
+            1 => RawSubFilter::NSamples(ord(rng, |rng| rng.gen())),
+            2 => RawSubFilter::Lifetime(ord(rng, |rng| {
+                let nanos = match rng.gen_range(0, 3) {
+                    0 => rng.gen_range(0, 1_000_000),
+                    1 => rng.gen_range(0, 1_000) * 1_000_000_000,
+                    _ => rng.gen_range(0, 1u64 << 48),
+                };
+                time::Lifetime::from(time::Duration::from_nanos(nanos))
+            })),
+            3 => {
+                let specs = (0..rng.gen_range(0, 4))
+                    .map(|_| match rng.gen_range(0, 5) {
+                        0 => LabelSpec::Anything,
+                        1 => LabelSpec::Value(string(rng)),
+                        2 => LabelSpec::Prefix(string(rng)),
+                        3 => LabelSpec::Suffix(string(rng)),
+                        _ => LabelSpec::Regex(LabelRegex::new(regex(rng))),
+                    })
+                    .collect();
+                RawSubFilter::Label(LabelFilter::new(pred(rng), specs))
+            }
+            4 => {
+                let specs = (0..rng.gen_range(0, 4))
+                    .map(|_| {
+                        let line = match rng.gen_range(0, 3) {
+                            0 => LineSpec::any(),
+                            1 => LineSpec::line(rng.gen_range(0, 10_000)),
+                            _ => LineSpec::range(bound(rng), bound(rng)),
+                        };
+                        match rng.gen_range(0, 3) {
+                            0 => LocSpec::Anything,
+                            1 => LocSpec::Value {
+                                value: string(rng),
+                                line,
+                            },
+                            _ => LocSpec::Regex {
+                                regex: regex(rng),
+                                line,
+                            },
+                        }
+                    })
+                    .collect();
+                RawSubFilter::Loc(LocFilter::new(pred(rng), specs))
+            }
+            _ => {
+                let path = match rng.gen_range(0, 3) {
+                    0 => PathSpec::Exact(string(rng)),
+                    1 => PathSpec::Prefix(string(rng)),
+                    _ => PathSpec::Regex(regex(rng)),
+                };
+                RawSubFilter::Frame(FrameFilter::new(pred(rng), path, bound(rng), bound(rng)))
+            }
+        }
+    }
+
+    /// Random members of a group at some depth.
+    fn members(rng: &mut SmallRng, depth: usize) -> Vec<Member> {
+        (0..rng.gen_range(0, 4))
+            .map(|_| {
+                if depth < filter::group::MAX_DEPTH && rng.gen_range(0, 4) == 0 {
+                    let op = if rng.gen() { GroupOp::And } else { GroupOp::Or };
+                    Member::Group(op, members(rng, depth + 1))
+                } else {
+                    Member::Sub(sub(rng))
+                }
+            })
+            .collect()
+    }
+
+    /// Random filter.
+    fn filter(rng: &mut SmallRng) -> Filter {
+        let color = Color::new(rng.gen(), rng.gen(), rng.gen());
+        let mut spec = FilterSpec::new(color);
+        let name = string(rng);
+        spec.set_name(FilterName::new_truncated(name));
+        spec.set_muted(rng.gen());
+        let mut filter = Filter::new(spec).unwrap();
+        let op = if rng.gen() { GroupOp::And } else { GroupOp::Or };
+        filter.set_group_op(&[], op).unwrap();
+        add_members(&mut filter, &mut vec![], members(rng, 0)).unwrap();
+        filter
+    }
+
+    /// True if two filters have the same raw subfilters, regardless of their order.
+    ///
+    /// The text syntax puts the ungrouped subfilters of a group before its nested groups.
+    fn same_raw_subs(lft: &Filter, rgt: &Filter) -> bool {
+        let mut rgt: Vec<_> = rgt.iter().map(SubFilter::raw).collect();
+        lft.iter().all(|sub| {
+            let idx = rgt.iter().position(|raw| *raw == sub.raw());
+            idx.map(|idx| rgt.swap_remove(idx)).is_some()
+        }) && rgt.is_empty()
+    }
+
+    #[test]
+    fn print_parse_round_trip() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        for _ in 0..10_000 {
+            let filter = filter(&mut rng);
+            let text = to_text(&filter);
+            assert!(!text.contains('\n'), "{:?}", text);
+            let parsed = match from_text(&text) {
+                Ok(parsed) => parsed,
+                Err(e) => panic!("failed to parse {:?}: {}", text, e.to_pretty()),
+            };
+            assert_eq!(to_text(&parsed), text);
+            assert_eq!(parsed.name(), filter.name(), "{:?}", text);
+            assert_eq!(parsed.spec().color(), filter.spec().color(), "{:?}", text);
+            assert_eq!(parsed.spec().is_muted(), filter.spec().is_muted());
+            assert!(same_raw_subs(&parsed, &filter), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn malformed_texts() {
+        let malformed = [
+            "",
+            "all()",
+            r##""" #000000 all()"##,
+            r##""a" all()"##,
+            r##""a" #00000g all()"##,
+            r##""a" #000000 all("##,
+            r##""a" #000000 all() trailing"##,
+            r##""a" #000000 both()"##,
+            r##""a" #000000 all(size)"##,
+            r##""a" #000000 all(size > 3)"##,
+            r##""a" #000000 all(size = 5G)"##,
+            r##""a" #000000 all(size = 1.0005k)"##,
+            r##""a" #000000 all(nsamples = 4294967296)"##,
+            r##""a" #000000 all(lifetime = 3 days)"##,
+            r##""a" #000000 all(lifetime in [1s 2s])"##,
+            r##""a" #000000 all(nsamples in [3, 2])"##,
+            r##""a" #000000 all(labels contains "a")"##,
+            r##""a" #000000 all(labels contain ["a"])"##,
+            r##""a" #000000 all(labels contains [#"("#])"##,
+            r##""a" #000000 all(labels contains ["a\q"])"##,
+            r##""a" #000000 all(labels contains ["a])"##,
+            r##""a" #000000 all(callstack contains ["a":[1 2]])"##,
+            r##""a" #000000 all(frame contains "src/"* lines 3)"##,
+            r##""a" #000000 all(any(all(any(all(size = 1)))))"##,
+        ];
+        for text in &malformed {
+            assert!(from_text(text).is_err(), "{:?}", text)
+        }
+        let lines = format!("{}\n{}", r##""a" #000000 all()"##, malformed[4]);
+        let e = from_texts(&lines).unwrap_err();
+        assert!(e.to_pretty().contains("on line 2"), "{}", e.to_pretty())
+    }
+}
@@ -0,0 +1,121 @@
+//! Synthetic allocation data for the tests.
+//!
+//! Times are in milliseconds since the start of the run, allocations use the default sample rate.
+
+prelude! {}
+
+/// Time since the start of the run of some milliseconds.
+pub fn millis(ms: u64) -> time::SinceStart {
+    time::SinceStart::from(time::Duration::from_millis(ms))
+}
+
+/// Registers a callstack, from the root to the allocation site.
+///
+/// Locations are `(file, line)` pairs.
+pub fn trace(locs: &[(&str, usize)]) -> alloc::Trace {
+    alloc::mem::trace::add(
+        locs.iter()
+            .map(|(file, line)| {
+                let loc = alloc::Loc::new(alloc::Str::new(file), *line, (0, 1));
+                alloc::CLoc::new(loc, 1)
+            })
+            .collect(),
+    )
+}
+
+/// Registers a list of labels.
+pub fn labels(labels: &[&str]) -> alloc::Labels {
+    alloc::mem::labels::add(labels.iter().map(|label| alloc::Str::new(label)).collect())
+}
+
+/// An allocation that is not dead yet.
+pub fn alloc(
+    uid: u64,
+    kind: alloc::AllocKind,
+    size: u32,
+    trace: &alloc::Trace,
+    labels: &alloc::Labels,
+    toc: u64,
+) -> Alloc {
+    Alloc::new(
+        &alloc::Init::default().sample_rate,
+        uid,
+        kind,
+        size,
+        trace.clone(),
+        labels.clone(),
+        millis(toc),
+        None,
+    )
+}
+
+/// Builds some data event by event.
+///
+/// Events must come in chronological order.
+pub struct Synth {
+    /// Data built so far.
+    data: data::Data,
+}
+
+impl Synth {
+    /// Constructor, the data is initialized with the default init.
+    pub fn new() -> Self {
+        let mut data = data::Data::new();
+        data.reset("test", alloc::Init::default());
+        Self { data }
+    }
+
+    /// Adds a minor allocation, yields its UID.
+    ///
+    /// UIDs are attributed in order of creation, starting at `0`.
+    pub fn alloc(
+        &mut self,
+        toc: u64,
+        size: u32,
+        trace: &alloc::Trace,
+        labels: &alloc::Labels,
+    ) -> uid::Alloc {
+        self.alloc_kind(alloc::AllocKind::Minor, toc, size, trace, labels)
+    }
+    /// Adds an allocation of some kind, yields its UID.
+    pub fn alloc_kind(
+        &mut self,
+        kind: alloc::AllocKind,
+        toc: u64,
+        size: u32,
+        trace: &alloc::Trace,
+        labels: &alloc::Labels,
+    ) -> uid::Alloc {
+        let uid = self.data.next_uid();
+        self.data
+            .add_new(alloc(uid.get() as u64, kind, size, trace, labels, toc))
+            .unwrap();
+        uid
+    }
+
+    /// Adds the death of an allocation.
+    pub fn dead(&mut self, tod: u64, uid: u64) {
+        self.data
+            .add_dead(millis(tod), uid::Alloc::from(uid))
+            .unwrap()
+    }
+    /// Adds the promotion of an allocation.
+    pub fn promotion(&mut self, at: u64, uid: u64) {
+        self.data
+            .add_promotion(millis(at), uid::Alloc::from(uid))
+            .unwrap()
+    }
+    /// Moves the current time forward.
+    pub fn mark(&mut self, ms: u64) {
+        self.data.mark_timestamp(millis(ms))
+    }
+
+    /// Data built so far, mutable.
+    pub fn data_mut(&mut self) -> &mut data::Data {
+        &mut self.data
+    }
+    /// Yields the data built.
+    pub fn done(self) -> data::Data {
+        self.data
+    }
+}
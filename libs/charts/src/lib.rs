@@ -41,13 +41,24 @@ pub extern crate plotters;
 #[macro_use]
 pub mod prelude;
 
+pub mod alloc_table;
 pub mod chart;
 pub mod color;
 #[cfg(any(test, feature = "server"))]
 pub mod data;
+pub mod explain;
 pub mod filter;
+#[cfg(test)]
+mod fixture;
+pub mod limits;
 pub mod msg;
+pub mod notes;
+pub mod pattern;
 pub mod point;
+pub mod session;
+pub mod snapshot;
+pub mod tag;
+pub mod view;
 
 #[cfg(any(test, feature = "server"))]
 pub use chart::Chart;
@@ -77,10 +88,22 @@ pub struct Charts {
     /// This is used to check whether we need to detect that the init file of the run has changed
     /// and that we need to reset the charts.
     start_time: Option<time::Date>,
+    /// Generation of the data the charts were computed on.
+    ///
+    /// Changes when an estimate is replaced by a full load, see [`data::load_all`].
+    ///
+    /// [`data::load_all`]: data/fn.load_all.html (The data::load_all function)
+    data_generation: u64,
     /// List of messages for the client, populated/drained when receiving messages.
     to_client_msgs: msg::to_client::Msgs,
     /// Settings.
     settings: settings::Charts,
+    /// True if no points should be sent to the client, see [`new_points`].
+    ///
+    /// [`new_points`]: #method.new_points (The new_points method)
+    paused: bool,
+    /// Sequence numbers of the points sent to the client.
+    seqs: chart::seq::Sequencer,
 }
 
 #[cfg(any(test, feature = "server"))]
@@ -91,8 +114,11 @@ impl Charts {
             charts: vec![],
             filters: Filters::new(),
             start_time: None,
+            data_generation: 0,
             to_client_msgs: msg::to_client::Msgs::with_capacity(7),
             settings: settings::Charts::new(),
+            paused: false,
+            seqs: chart::seq::Sequencer::new(),
         }
     }
 
@@ -108,6 +134,10 @@ impl Charts {
     pub fn start_time(&self) -> Option<&time::Date> {
         self.start_time.as_ref()
     }
+    /// True if the charts are paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
 
     /// Runs filter generation.
     ///
@@ -115,13 +145,108 @@ impl Charts {
     #[cfg(any(test, feature = "server"))]
     pub fn auto_gen() -> Res<Self> {
         let (filters, charts) = Filters::auto_gen(&*data::get()?, filter::gen::get())?;
-        Ok(Self {
+        Ok(Self::from_gen(filters, charts))
+    }
+
+    /// Runs filter generation with a context handling progress and cancellation.
+    ///
+    /// Returns `None` if the generation was cancelled, in which case no filter was generated.
+    #[cfg(any(test, feature = "server"))]
+    pub fn auto_gen_with(
+        generator: impl Into<filter::gen::FilterGen>,
+        cxt: &mut filter::gen::GenCxt,
+    ) -> Res<Option<Self>> {
+        let res = generator.into().run_with(&*data::get()?, cxt)?;
+        Ok(res.map(|(filters, charts)| Self::from_gen(filters, charts)))
+    }
+
+    /// Runs the active filter generator, unless it already ran on the current data.
+    ///
+    /// The result is [shared] by all clients, so that the generator only runs once per version of
+    /// the data. Returns `None` if the generation was cancelled.
+    ///
+    /// [shared]: filter/gen/fn.share.html (The share function)
+    #[cfg(any(test, feature = "server"))]
+    pub fn auto_gen_shared(cxt: &mut filter::gen::GenCxt) -> Res<Option<Self>> {
+        let data = data::get()?;
+        if let Some((filters, charts)) = filter::gen::shared(&data)? {
+            return Ok(Some(Self::from_gen(filters, charts)));
+        }
+        let res = filter::gen::get().run_with(&data, cxt)?;
+        if let Some((filters, charts)) = res.as_ref() {
+            filter::gen::share(&data, filters, charts)
+        }
+        Ok(res.map(|(filters, charts)| Self::from_gen(filters, charts)))
+    }
+
+    /// Constructor from the result of a filter generator.
+    #[cfg(any(test, feature = "server"))]
+    fn from_gen(filters: Filters, charts: Vec<Chart>) -> Self {
+        Self {
             charts,
             filters,
             start_time: None,
+            data_generation: 0,
             to_client_msgs: msg::to_client::Msgs::with_capacity(7),
             settings: settings::Charts::new(),
-        })
+            paused: false,
+            seqs: chart::seq::Sequencer::new(),
+        }
+    }
+
+    /// Constructor from a [session].
+    ///
+    /// Fresh UIDs never collide with the UIDs of the session.
+    ///
+    /// [session]: session/index.html (The session module)
+    pub fn from_session(session: session::Session) -> Res<Self> {
+        session.reserve_uids();
+        let session::Session {
+            everything,
+            filters: export,
+            charts,
+            settings,
+            ..
+        } = session;
+        let mut filters = Filters::new();
+        let _ = filters.update_all(everything, export.filters, export.catch_all)?;
+        let charts = charts
+            .into_iter()
+            .map(|chart| {
+                let uid = chart.spec.uid();
+                Chart::restore(&filters, chart.spec, chart.settings)
+                    .chain_err(|| format!("while restoring chart #{}", uid))
+            })
+            .collect::<Res<Vec<_>>>()?;
+        let mut slf = Self::from_gen(filters, charts);
+        slf.settings = settings;
+        Ok(slf)
+    }
+
+    /// The session of the charts, see the [`session`] module.
+    ///
+    /// [`session`]: session/index.html (The session module)
+    pub fn session(&self) -> session::Session {
+        session::Session::new(
+            self.filters.everything().clone(),
+            filter::Export::new(
+                self.filters.catch_all().clone(),
+                self.filters.filters().clone(),
+            ),
+            self.charts
+                .iter()
+                .map(|chart| session::SessionChart {
+                    spec: chart.spec().clone(),
+                    settings: chart.settings().clone(),
+                })
+                .collect(),
+            self.settings.clone(),
+        )
+    }
+
+    /// True if there is no room for a new chart in some limits.
+    pub fn chart_limit_reached(&self, limits: limits::Limits) -> bool {
+        limits.check_charts(self.charts.len() + 1).is_some()
     }
 
     /// Pushes a new chart.
@@ -138,18 +263,33 @@ impl Charts {
         }
         bail!("cannot access chart with unknown UID #{}", uid)
     }
+
+    /// Reorders the charts.
+    ///
+    /// Charts that do not appear in `order` keep their relative order, after the ones that do.
+    /// Unknown UIDs are ignored.
+    pub fn set_order(&mut self, order: &[uid::Chart]) {
+        let position = |chart: &Chart| {
+            order
+                .iter()
+                .position(|uid| *uid == chart.uid())
+                .unwrap_or(order.len())
+        };
+        // Stable, so unlisted charts keep their relative order.
+        self.charts.sort_by_key(position)
+    }
 }
 
 #[cfg(any(test, feature = "server"))]
 impl Charts {
     /// Restarts the charts and the filters if needed.
     fn restart_if_needed(&mut self) -> Res<bool> {
-        let data = data::get();
-        let start_time = data
-            .and_then(|data| data.start_time())
+        let (start_time, generation) = data::get()
+            .and_then(|data| Ok((data.start_time()?, data.generation())))
             .chain_err(|| "while checking if the charts should be restarted")?;
-        if self.start_time != Some(start_time) {
+        if self.start_time != Some(start_time) || self.data_generation != generation {
             self.start_time = Some(start_time);
+            self.data_generation = generation;
             for chart in &mut self.charts {
                 chart.reset(&self.filters)
             }
@@ -160,12 +300,37 @@ impl Charts {
         }
     }
 
+    /// Prunes the allocations dead for longer than the pruning horizon, if any.
+    ///
+    /// Yields the number of allocations pruned, see [`data::prune`].
+    ///
+    /// [`data::prune`]: data/prune/index.html (The data::prune module)
+    pub fn prune(&mut self) -> Res<usize> {
+        data::prune()
+    }
+
+    /// Forgets the filters that matched the pruned allocations.
+    ///
+    /// The charts need not reload, pruned allocations replay where their events were.
+    pub fn forget_pruned(&mut self) -> Res<()> {
+        let data = data::get()?;
+        self.filters.forget_pruned(&data);
+        Ok(())
+    }
+
     /// Extracts the new points for the different charts.
     ///
     /// The boolean indicates whether the points should overwrite existing points. It is typically
     /// true when the init file of the run has changed (the run was restarted).
+    ///
+    /// Yields no points while paused, unless `init`. The charts do not move, so that the points
+    /// ingested in the meantime are extracted when resuming.
     pub fn new_points(&mut self, init: bool) -> Res<(point::ChartPoints, bool)> {
+        if self.paused && !init {
+            return Ok((point::ChartPoints::new(), false));
+        }
         let restarted = self.restart_if_needed()?;
+        self.sync_match_cache()?;
         let mut points = point::ChartPoints::new();
         for chart in &mut self.charts {
             if let Some(chart_points) = chart.new_points(
@@ -174,9 +339,11 @@ impl Charts {
                 self.settings.time_windopt(),
             )? {
                 let prev = points.insert(chart.uid(), chart_points);
-                debug_assert!(prev.is_none())
+                debug_assert!(prev.is_none());
+                points.set_gaps(chart.uid(), chart.gaps(self.settings.time_windopt())?)
             }
         }
+        self.seqs.stamp(&mut points);
         Ok((points, restarted || init))
     }
 
@@ -184,14 +351,39 @@ impl Charts {
     pub fn handle_chart_msg(&mut self, msg: msg::to_server::ChartsMsg) -> Res<bool> {
         debug_assert!(self.to_client_msgs.is_empty());
 
+        let limits = limits::get();
         let reloaded = match msg {
-            msg::to_server::ChartsMsg::New(x_axis, y_axis) => {
-                let all_active = self.filters.fold(BTMap::new(), |mut map, uid| {
-                    let prev = map.insert(uid, true);
+            msg::to_server::ChartsMsg::New(..) if self.chart_limit_reached(limits) => {
+                let reason = msg::to_client::Rejection::ChartLimit(limits.charts);
+                self.to_client_msgs.push(msg::to_client::Msg::rejected(
+                    format!("charts({})", msg),
+                    reason,
+                ));
+                false
+            }
+            msg::to_server::ChartsMsg::New(_, y_axis, ..)
+                if data::prune::is_enabled() && !y_axis.replays_pruned() =>
+            {
+                self.reject_pruning(&msg);
+                false
+            }
+            msg::to_server::ChartsMsg::Explain
+            | msg::to_server::ChartsMsg::SnapshotDiff { .. }
+            | msg::to_server::ChartsMsg::AllocTable(_)
+                if data::prune::is_enabled() =>
+            {
+                self.reject_pruning(&msg);
+                false
+            }
+            msg::to_server::ChartsMsg::New(x_axis, y_axis, title, visible) => {
+                // Lines created while the client was setting up the chart are not in `visible`.
+                let active = self.filters.fold(BTMap::new(), |mut map, uid| {
+                    let prev = map.insert(uid, visible.get(&uid).cloned().unwrap_or(true));
                     debug_assert_eq!(prev, None);
                     map
                 });
-                let nu_chart = chart::Chart::new(&mut self.filters, x_axis, y_axis, all_active)
+                let spec = chart::ChartSpec::new(x_axis, y_axis, active);
+                let nu_chart = chart::Chart::from_spec(title, &self.filters, spec)
                     .chain_err(|| "while creating new chart")?;
 
                 // Chart creation message.
@@ -200,14 +392,6 @@ impl Charts {
                         nu_chart.spec().clone(),
                         nu_chart.settings().clone(),
                     ));
-                // // Initial points message.
-                // let points = nu_chart.new_points(&mut self.filters, true).chain_err(|| {
-                //     format!(
-                //         "while generating the initial points for new chart #{}",
-                //         nu_chart.uid()
-                //     )
-                // })?;
-                // to_client_msgs.push(msg::to_client::ChartMsg::new_points(nu_chart.uid(), points));
 
                 self.charts.push(nu_chart);
                 true
@@ -230,6 +414,104 @@ impl Charts {
                 }
             }
 
+            msg::to_server::ChartsMsg::SetScope { uid, lines } => {
+                self.get_mut(uid)?.set_scope(lines.clone());
+                // Resets the chart and regenerates its points for the new scope.
+                self.to_client_msgs
+                    .push(msg::to_client::ChartMsg::scope(uid, lines));
+                let msg = self.reload_points(Some(uid), false)?;
+                self.to_client_msgs.push(msg);
+                true
+            }
+
+            msg::to_server::ChartsMsg::Resend(uids) => {
+                for uid in uids {
+                    if self.charts.iter().any(|chart| chart.uid() == uid) {
+                        let msg = self.reload_points(Some(uid), false)?;
+                        self.to_client_msgs.push(msg);
+                    }
+                }
+                true
+            }
+
+            msg::to_server::ChartsMsg::Resync(skews) => {
+                for skew in &skews {
+                    log::warn!("client out of sync: {} ({:+.3}s)", skew, skew.secs())
+                }
+                // If the server itself went back in time, all charts are suspicious.
+                if skews.iter().any(|skew| skew.chart.is_none()) {
+                    let msg = self.reload_points(None, false)?;
+                    self.to_client_msgs.push(msg);
+                } else {
+                    for uid in skews.iter().filter_map(|skew| skew.chart) {
+                        if self.charts.iter().any(|chart| chart.uid() == uid) {
+                            let msg = self.reload_points(Some(uid), false)?;
+                            self.to_client_msgs.push(msg);
+                        }
+                    }
+                }
+                true
+            }
+
+            msg::to_server::ChartsMsg::Explain => {
+                let range = {
+                    let data = data::get()?;
+                    let now = *data.current_time();
+                    self.settings.time_windopt().to_time_window(|| now)
+                };
+                let explanation = explain::Explanation::of_range(range)?;
+                self.to_client_msgs
+                    .push(msg::to_client::Msg::explanation(explanation));
+                false
+            }
+
+            msg::to_server::ChartsMsg::SnapshotDiff { from, to } => {
+                let diff = snapshot::SnapshotDiff::of_filters(&self.filters, from, to)?;
+                self.to_client_msgs
+                    .push(msg::to_client::Msg::snapshot_diff(diff));
+                false
+            }
+
+            msg::to_server::ChartsMsg::AllocTable(request) => {
+                let table = alloc_table::AllocTable::of_filters(&self.filters, request)?;
+                self.to_client_msgs
+                    .push(msg::to_client::Msg::alloc_table(table));
+                false
+            }
+
+            msg::to_server::ChartsMsg::TogglePause => {
+                self.paused = !self.paused;
+                self.to_client_msgs
+                    .push(msg::to_client::ChartsMsg::paused(self.paused));
+                if self.paused {
+                    false
+                } else {
+                    // Catch up in one batch that overwrites the points of the client, instead of
+                    // replaying everything ingested while paused.
+                    let _ = self.restart_if_needed()?;
+                    let msg = self.reload_points(None, false)?;
+                    self.to_client_msgs.push(msg);
+                    true
+                }
+            }
+
+            msg::to_server::ChartsMsg::SetOrder(uids) => {
+                // The client already reordered its charts, this only makes the order persist.
+                self.set_order(&uids);
+                false
+            }
+
+            msg::to_server::ChartsMsg::Close(uid) => {
+                self.charts.retain(|chart| chart.uid() != uid);
+                false
+            }
+
+            msg::to_server::ChartsMsg::Rename { uid, title } => {
+                // The client already renamed its chart, this only makes the title persist.
+                self.get_mut(uid)?.rename(title);
+                false
+            }
+
             msg::to_server::ChartsMsg::Settings(settings) => {
                 let send_new_points = self.settings.overwrite(settings);
                 if send_new_points {
@@ -243,18 +525,74 @@ impl Charts {
         Ok(reloaded)
     }
 
+    /// Rejects a message that needs allocations the server may prune, see [`data::prune`].
+    ///
+    /// [`data::prune`]: data/prune/index.html (The data::prune module)
+    fn reject_pruning(&mut self, msg: &msg::to_server::ChartsMsg) {
+        self.to_client_msgs.push(msg::to_client::Msg::rejected(
+            format!("charts({})", msg),
+            msg::to_client::Rejection::Pruning,
+        ))
+    }
+
+    /// Clears the cache of the results of the filters if the data changed, see [`filter::cache`].
+    ///
+    /// [`filter::cache`]: filter/cache/index.html (The filter::cache module)
+    fn sync_match_cache(&mut self) -> Res<()> {
+        let data = data::get()?;
+        self.filters.sync_cache(&data)
+    }
+
     /// Recomputes all the points, and returns them as a message for the client.
     pub fn reload_points(
         &mut self,
         uid: Option<uid::Chart>,
         refresh_filters: bool,
     ) -> Res<msg::to_client::Msg> {
+        let new_points =
+            self.reload_charts(|chart| uid.map(|uid| chart.uid() == uid).unwrap_or(true))?;
+        Ok(msg::to_client::ChartsMsg::new_points(
+            new_points,
+            refresh_filters,
+        ))
+    }
+
+    /// Recomputes the points of the charts that show some lines, see [`Chart::shows_any`].
+    ///
+    /// Yields `None` if no chart shows these lines, since the client would wipe the points of the
+    /// other charts on an empty message. The filters still match all the allocations again so that
+    /// the statistics of the lines are up to date.
+    ///
+    /// [`Chart::shows_any`]: chart/struct.Chart.html#method.shows_any (Chart::shows_any)
+    pub fn reload_lines(
+        &mut self,
+        lines: &BTSet<uid::Line>,
+        refresh_filters: bool,
+    ) -> Res<Option<msg::to_client::Msg>> {
+        if self.charts.iter().all(|chart| !chart.shows_any(lines)) {
+            self.sync_match_cache()?;
+            self.filters.rematch(&*data::get()?)?;
+            return Ok(None);
+        }
+        let new_points = self.reload_charts(|chart| chart.shows_any(lines))?;
+        Ok(Some(msg::to_client::ChartsMsg::new_points(
+            new_points,
+            refresh_filters,
+        )))
+    }
+
+    /// Recomputes the points of the charts verifying some predicate.
+    ///
+    /// Each chart matches all the allocations again, but only the filters that changed since they
+    /// last ran on an allocation actually run, see [`filter::cache`].
+    ///
+    /// [`filter::cache`]: filter/cache/index.html (The filter::cache module)
+    fn reload_charts(&mut self, reload: impl Fn(&Chart) -> bool) -> Res<point::ChartPoints> {
+        self.sync_match_cache()?;
         let mut new_points = point::ChartPoints::new();
         for chart in &mut self.charts {
-            if let Some(uid) = uid {
-                if chart.uid() != uid {
-                    continue;
-                }
+            if !reload(chart) {
+                continue;
             }
             chart.reset(&self.filters);
             self.filters.reset();
@@ -266,12 +604,11 @@ impl Charts {
                 if prev.is_some() {
                     bail!("chart UID collision on #{}", chart.uid())
                 }
+                new_points.set_gaps(chart.uid(), chart.gaps(self.settings.time_windopt())?);
             }
         }
-        Ok(msg::to_client::ChartsMsg::new_points(
-            new_points,
-            refresh_filters,
-        ))
+        self.seqs.stamp(&mut new_points);
+        Ok(new_points)
     }
 
     /// Handles a message from the client.
@@ -284,13 +621,67 @@ impl Charts {
         let reload = match msg {
             Charts(msg) => self.handle_chart_msg(msg)?,
             Filters(msg) => {
+                // Only the charts showing the lines that change need new points.
+                let lines = match &msg {
+                    msg::to_server::FiltersMsg::UpdateAll { filters, .. } => {
+                        self.filters.changed_lines(filters)
+                    }
+                    _ => None,
+                };
                 let (mut msgs, should_reload) = self.filters.update(msg)?;
                 if should_reload {
-                    msgs.push(self.reload_points(None, true)?)
+                    if let Some(lines) = lines {
+                        msgs.extend(self.reload_lines(&lines, true)?)
+                    } else {
+                        msgs.push(self.reload_points(None, true)?)
+                    }
                 }
                 self.to_client_msgs.extend(msgs);
                 should_reload
             }
+            SetNotes { text, version } => {
+                let (notes, conflict) = notes::set(text, version)?;
+                self.to_client_msgs
+                    .push(msg::to_client::Msg::notes(notes, conflict));
+                false
+            }
+            // Charts are reloaded when the socket notices the new version of the tags.
+            Tag { tag, target } => {
+                tag::tag(&tag, &target, &self.filters)?;
+                false
+            }
+            Untag(tag) => {
+                data::untag(&tag)?;
+                false
+            }
+            LoadAll => {
+                let stats = data::load_all()?;
+                self.to_client_msgs
+                    .push(msg::to_client::Msg::alloc_stats(stats));
+                false
+            }
+            // Charts are restarted when they notice the new data. Only the requesting client hears
+            // about illegal traces, the loader registers load failures as non-fatal errors.
+            LoadTrace(path) => {
+                if let Err(e) = data::load_trace(path.map(std::path::PathBuf::from)) {
+                    self.to_client_msgs
+                        .push(msg::to_client::Msg::alert(e.to_pretty(), false))
+                }
+                false
+            }
+            ClientPanic(report) => {
+                log::error!("a client panicked:\n{}", report);
+                false
+            }
+            ClearSession => {
+                if let Err(e) = session::clear() {
+                    self.to_client_msgs
+                        .push(msg::to_client::Msg::alert(e.to_pretty(), false))
+                }
+                self.to_client_msgs
+                    .push(msg::to_client::Msg::session(session::status()?));
+                false
+            }
         };
 
         Ok((self.to_client_msgs.drain(0..), reload))
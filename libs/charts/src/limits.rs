@@ -0,0 +1,118 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Limits over the number of filters and charts.
+//!
+//! Too many filters or charts make both the server and the client unresponsive. The server rejects
+//! the creation of filters and charts beyond its [`Limits`], and filter generators truncate their
+//! output to fit. The limits are set on the command line, and sent to the clients so that they can
+//! check them before asking the server for anything.
+//!
+//! [`Limits`]: struct.Limits.html (The Limits struct)
+
+prelude! {}
+
+use msg::to_client::Rejection;
+
+/// Limits over the number of filters and charts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Limits {
+    /// Maximum number of custom filters.
+    pub filters: usize,
+    /// Maximum number of charts.
+    pub charts: usize,
+}
+impl Default for Limits {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_FILTERS, Self::DEFAULT_CHARTS)
+    }
+}
+impl Limits {
+    /// Default maximum number of custom filters.
+    pub const DEFAULT_FILTERS: usize = 200;
+    /// Default maximum number of charts.
+    pub const DEFAULT_CHARTS: usize = 40;
+
+    /// Command-line flag setting the maximum number of custom filters.
+    pub const FILTERS_FLAG: &'static str = "--max_filters";
+    /// Command-line flag setting the maximum number of charts.
+    pub const CHARTS_FLAG: &'static str = "--max_charts";
+
+    /// Constructor.
+    pub fn new(filters: usize, charts: usize) -> Self {
+        Self { filters, charts }
+    }
+
+    /// Rejection for having `count` custom filters, if any.
+    ///
+    /// ```rust
+    /// # use charts::{limits::Limits, msg::to_client::Rejection};
+    /// let limits = Limits::new(2, 1);
+    /// assert_eq!(limits.check_filters(2), None);
+    /// assert_eq!(limits.check_filters(3), Some(Rejection::FilterLimit(2)));
+    /// ```
+    pub fn check_filters(&self, count: usize) -> Option<Rejection> {
+        if count > self.filters {
+            Some(Rejection::FilterLimit(self.filters))
+        } else {
+            None
+        }
+    }
+
+    /// Rejection for having `count` charts, if any.
+    ///
+    /// ```rust
+    /// # use charts::{limits::Limits, msg::to_client::Rejection};
+    /// let limits = Limits::new(2, 1);
+    /// assert_eq!(limits.check_charts(1), None);
+    /// assert_eq!(limits.check_charts(2), Some(Rejection::ChartLimit(1)));
+    /// ```
+    pub fn check_charts(&self, count: usize) -> Option<Rejection> {
+        if count > self.charts {
+            Some(Rejection::ChartLimit(self.charts))
+        } else {
+            None
+        }
+    }
+}
+
+/// Maximum number of custom filters of the server.
+#[cfg(any(test, feature = "server"))]
+static MAX_FILTERS: sync::atomic::AtomicUsize =
+    sync::atomic::AtomicUsize::new(Limits::DEFAULT_FILTERS);
+/// Maximum number of charts of the server.
+#[cfg(any(test, feature = "server"))]
+static MAX_CHARTS: sync::atomic::AtomicUsize =
+    sync::atomic::AtomicUsize::new(Limits::DEFAULT_CHARTS);
+
+/// Limits of the server.
+#[cfg(any(test, feature = "server"))]
+pub fn get() -> Limits {
+    Limits::new(
+        MAX_FILTERS.load(sync::atomic::Ordering::Relaxed),
+        MAX_CHARTS.load(sync::atomic::Ordering::Relaxed),
+    )
+}
+
+/// Sets the limits of the server.
+#[cfg(any(test, feature = "server"))]
+pub fn set(limits: Limits) {
+    MAX_FILTERS.store(limits.filters, sync::atomic::Ordering::Relaxed);
+    MAX_CHARTS.store(limits.charts, sync::atomic::Ordering::Relaxed);
+}
@@ -22,6 +22,97 @@
 prelude! {}
 use filter::*;
 
+pub mod compress;
+pub mod config;
+pub mod wire;
+
+/// URL query parameter requesting a read-only (spectator) session.
+pub const READ_ONLY_PARAM: &str = "readonly";
+
+/// URL query parameter of the websocket accepting compressed messages, see the [`compress`] module.
+///
+/// [`compress`]: compress/index.html (The compress module)
+pub const COMPRESS_PARAM: &str = "compress";
+
+/// True if a URL query has a flag parameter.
+///
+/// The query can start with a `?`. The flag is set if the query has the parameter with no value,
+/// or with any value other than `false` and `0`.
+fn query_has_flag(query: &str, flag: &str) -> bool {
+    let query = if query.starts_with('?') {
+        &query[1..]
+    } else {
+        query
+    };
+    query.split('&').any(|param| {
+        let mut kv = param.splitn(2, '=');
+        match (kv.next(), kv.next()) {
+            (Some(key), None) => key == flag,
+            (Some(key), Some(val)) => key == flag && val != "false" && val != "0",
+            _ => false,
+        }
+    })
+}
+
+/// True if a URL query requests a read-only (spectator) session.
+///
+/// The query can start with a `?`. The session is read-only if the query has a [`READ_ONLY_PARAM`]
+/// parameter, with no value or with any value other than `false` and `0`.
+///
+/// ```rust
+/// # use charts::msg::query_is_read_only;
+/// assert!(query_is_read_only("?readonly"));
+/// assert!(query_is_read_only("tab=3&readonly=true"));
+/// assert!(query_is_read_only("readonly=1"));
+/// assert!(!query_is_read_only("readonly=false"));
+/// assert!(!query_is_read_only("?readonly=0"));
+/// assert!(!query_is_read_only("?readonlyyy"));
+/// assert!(!query_is_read_only(""));
+/// ```
+///
+/// [`READ_ONLY_PARAM`]: constant.READ_ONLY_PARAM.html (READ_ONLY_PARAM constant)
+pub fn query_is_read_only(query: &str) -> bool {
+    query_has_flag(query, READ_ONLY_PARAM)
+}
+
+/// True if a websocket URL query accepts compressed messages.
+///
+/// Same as [`query_is_read_only`], but for the [`COMPRESS_PARAM`] parameter.
+///
+/// ```rust
+/// # use charts::msg::query_accepts_compression;
+/// assert!(query_accepts_compression("?compress"));
+/// assert!(query_accepts_compression("readonly&compress=1"));
+/// assert!(!query_accepts_compression("?compress=false"));
+/// assert!(!query_accepts_compression("readonly"));
+/// ```
+///
+/// [`query_is_read_only`]: fn.query_is_read_only.html (The query_is_read_only function)
+/// [`COMPRESS_PARAM`]: constant.COMPRESS_PARAM.html (COMPRESS_PARAM constant)
+pub fn query_accepts_compression(query: &str) -> bool {
+    query_has_flag(query, COMPRESS_PARAM)
+}
+
+/// Bincode options of the messages.
+///
+/// Integers use a variable-length encoding: most of the integers in points are small, which makes
+/// them much more compact than with bincode's default fixed-size integers.
+fn bincode_options() -> impl base::bincode::Options {
+    base::bincode::DefaultOptions::new()
+}
+
+/// Encodes something as bytes, the way messages are encoded.
+pub fn encode<T: ::serde::Serialize>(value: &T) -> Res<Vec<u8>> {
+    use base::bincode::Options;
+    Ok(bincode_options().serialize(value)?)
+}
+
+/// Decodes something from bytes, the way messages are decoded.
+pub fn decode<'de, T: ::serde::Deserialize<'de>>(bytes: &'de [u8]) -> Res<T> {
+    use base::bincode::Options;
+    Ok(bincode_options().deserialize(bytes)?)
+}
+
 /// Chart settings message.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ChartSettingsMsg {
@@ -33,6 +124,28 @@ pub enum ChartSettingsMsg {
     SetDisplayMode(chart::settings::DisplayMode),
     /// Changes the resolution of a chart.
     SetResolution(chart::settings::Resolution),
+    /// Changes the quality of the points of a chart.
+    SetQuality(chart::settings::Quality),
+    /// Changes the number of points across the width of a chart, `None` to follow the quality.
+    SetMaxPoints(Option<u32>),
+    /// Changes the size percentile of a chart.
+    SetSizePercentile(chart::settings::SizePercentile),
+    /// Changes the lifetime statistic of a chart.
+    SetLifetimeStat(chart::settings::LifetimeStat),
+    /// Changes the line a chart is normalized by, `None` to stop normalizing.
+    SetNormalize(Option<uid::Line>),
+    /// Changes the transforms applied to the points of a chart.
+    SetTransforms(Vec<chart::transform::Transform>),
+    /// Changes the tick density of the axes of a chart.
+    SetTicks(chart::settings::Ticks),
+    /// Changes the time window of a chart, `None` to show all the points.
+    SetTimeWindow(Option<time::SinceStart>),
+    /// Hides or shows the everything line of a chart.
+    SetHideEverything(bool),
+    /// Changes how the x-axis of a time chart labels times.
+    SetTimeAxis(chart::settings::TimeAxis),
+    /// Hides or shows a line of a chart, from its legend.
+    SetLineHidden(uid::Line, bool),
 }
 
 impl ChartSettingsMsg {
@@ -70,6 +183,123 @@ impl ChartSettingsMsg {
     {
         (uid, Self::SetResolution(resolution.into())).into()
     }
+
+    /// Changes the quality of the points of a chart.
+    pub fn set_quality<Res>(uid: uid::Chart, quality: chart::settings::Quality) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetQuality(quality)).into()
+    }
+
+    /// Changes the number of points across the width of a chart.
+    pub fn set_max_points<Res>(uid: uid::Chart, max_points: Option<u32>) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetMaxPoints(max_points)).into()
+    }
+
+    /// Changes the size percentile of a chart.
+    pub fn set_size_percentile<Res>(
+        uid: uid::Chart,
+        size_percentile: chart::settings::SizePercentile,
+    ) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetSizePercentile(size_percentile)).into()
+    }
+
+    /// Changes the lifetime statistic of a chart.
+    pub fn set_lifetime_stat<Res>(
+        uid: uid::Chart,
+        lifetime_stat: chart::settings::LifetimeStat,
+    ) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetLifetimeStat(lifetime_stat)).into()
+    }
+
+    /// Changes the line a chart is normalized by.
+    pub fn set_normalize<Res>(uid: uid::Chart, line: Option<uid::Line>) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetNormalize(line)).into()
+    }
+
+    /// Changes the transforms applied to the points of a chart.
+    pub fn set_transforms<Res>(uid: uid::Chart, transforms: Vec<chart::transform::Transform>) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetTransforms(transforms)).into()
+    }
+
+    /// Changes the tick density of the axes of a chart.
+    pub fn set_ticks<Res>(uid: uid::Chart, ticks: chart::settings::Ticks) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetTicks(ticks)).into()
+    }
+
+    /// Changes the time window of a chart.
+    pub fn set_time_window<Res>(uid: uid::Chart, time_window: Option<time::SinceStart>) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetTimeWindow(time_window)).into()
+    }
+
+    /// Hides or shows the everything line of a chart.
+    pub fn set_hide_everything<Res>(uid: uid::Chart, hide: bool) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetHideEverything(hide)).into()
+    }
+
+    /// Changes how the x-axis of a time chart labels times.
+    pub fn set_time_axis<Res>(uid: uid::Chart, time_axis: chart::settings::TimeAxis) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetTimeAxis(time_axis)).into()
+    }
+
+    /// Hides or shows a line of a chart, from its legend.
+    pub fn set_line_hidden<Res>(uid: uid::Chart, line: uid::Line, hide: bool) -> Res
+    where
+        (uid::Chart, Self): Into<Res>,
+    {
+        (uid, Self::SetLineHidden(line, hide)).into()
+    }
+}
+
+impl ChartSettingsMsg {
+    /// True if the message modifies the chart, as opposed to just changing how it is displayed.
+    ///
+    /// Mutating messages are rejected in read-only sessions.
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            Self::ChangeTitle(_) | Self::SetSizePercentile(_) | Self::SetLifetimeStat(_) => true,
+            Self::ToggleVisible
+            | Self::SetDisplayMode(_)
+            | Self::SetResolution(_)
+            | Self::SetQuality(_)
+            | Self::SetMaxPoints(_)
+            | Self::SetNormalize(_)
+            | Self::SetTransforms(_)
+            | Self::SetTicks(_)
+            | Self::SetTimeWindow(_)
+            | Self::SetHideEverything(_)
+            | Self::SetTimeAxis(_)
+            | Self::SetLineHidden(_, _) => false,
+        }
+    }
 }
 
 impl fmt::Display for ChartSettingsMsg {
@@ -79,6 +309,36 @@ impl fmt::Display for ChartSettingsMsg {
             Self::SetDisplayMode(mode) => write!(fmt, "set display mode: {}", mode.desc()),
             Self::ChangeTitle(title) => write!(fmt, "change title: {}", title),
             Self::SetResolution(resolution) => write!(fmt, "set resolution: {}", resolution),
+            Self::SetQuality(quality) => write!(fmt, "set quality: {}", quality),
+            Self::SetMaxPoints(Some(max_points)) => {
+                write!(fmt, "set max points: {}", max_points)
+            }
+            Self::SetMaxPoints(None) => write!(fmt, "follow the quality"),
+            Self::SetSizePercentile(size_percentile) => {
+                write!(fmt, "set size percentile: {}", size_percentile)
+            }
+            Self::SetLifetimeStat(lifetime_stat) => {
+                write!(fmt, "set lifetime statistic: {}", lifetime_stat)
+            }
+            Self::SetNormalize(Some(line)) => write!(fmt, "normalize by {}", line),
+            Self::SetNormalize(None) => write!(fmt, "stop normalizing"),
+            Self::SetTransforms(transforms) => {
+                write!(fmt, "set transforms:")?;
+                for (idx, transform) in transforms.iter().enumerate() {
+                    write!(fmt, "{} {}", if idx > 0 { "," } else { "" }, transform)?
+                }
+                Ok(())
+            }
+            Self::SetTicks(ticks) => write!(fmt, "set ticks: {}", ticks),
+            Self::SetTimeWindow(Some(window)) => {
+                write!(fmt, "show the last {}", window.fmt_human())
+            }
+            Self::SetTimeWindow(None) => write!(fmt, "show all the points"),
+            Self::SetHideEverything(true) => write!(fmt, "hide the everything line"),
+            Self::SetHideEverything(false) => write!(fmt, "show the everything line"),
+            Self::SetTimeAxis(time_axis) => write!(fmt, "set time axis: {}", time_axis),
+            Self::SetLineHidden(line, true) => write!(fmt, "hide line {}", line),
+            Self::SetLineHidden(line, false) => write!(fmt, "show line {}", line),
         }
     }
 }
@@ -98,25 +358,228 @@ pub mod to_server {
 
         /// Operation over filters.
         Filters(FiltersMsg),
+
+        /// Overwrites the notes of the server.
+        SetNotes {
+            /// New content of the notes.
+            text: String,
+            /// Version of the notes the new content is based on.
+            version: u64,
+        },
+
+        /// Tags some allocations, see the [`tag`] module.
+        ///
+        /// [`tag`]: ../../tag/index.html (The tag module)
+        Tag {
+            /// Tag to add.
+            tag: String,
+            /// Allocations to tag.
+            target: tag::Target,
+        },
+        /// Removes a tag from all the allocations that have it.
+        Untag(String),
+
+        /// Loads all the allocations when the data is an estimate, see [`data::load_all`].
+        ///
+        /// [`data::load_all`]: ../../data/fn.load_all.html (The data::load_all function)
+        LoadAll,
+
+        /// Reloads the trace from scratch, or switches to the memtrace CTF file at some path, see
+        /// [`data::load_trace`].
+        ///
+        /// [`data::load_trace`]: ../../data/fn.load_trace.html (The data::load_trace function)
+        LoadTrace(Option<String>),
+
+        /// Reports a panic of the client, the server logs it.
+        ///
+        /// Sent on a best-effort basis from the panic hook of the client, through a new connection.
+        ClientPanic(String),
+
+        /// Deletes the session file of the server, see the [`session`] module.
+        ///
+        /// The server answers with a [`Session`] message.
+        ///
+        /// [`session`]: ../../session/index.html (The session module)
+        /// [`Session`]: ../to_client/enum.Msg.html#variant.Session (The Session message)
+        ClearSession,
     }
     impl fmt::Display for Msg {
         fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
             match self {
                 Self::Charts(msg) => write!(fmt, "charts({})", msg),
                 Self::Filters(msg) => write!(fmt, "filters({})", msg),
+                Self::SetNotes { version, .. } => write!(fmt, "set notes (v{})", version),
+                Self::Tag { tag, target } => write!(fmt, "tag {} as `{}`", target, tag),
+                Self::Untag(tag) => write!(fmt, "untag `{}`", tag),
+                Self::LoadAll => write!(fmt, "load all"),
+                Self::LoadTrace(None) => write!(fmt, "reload trace"),
+                Self::LoadTrace(Some(path)) => write!(fmt, "load trace `{}`", path),
+                Self::ClientPanic(_) => write!(fmt, "client panic"),
+                Self::ClearSession => write!(fmt, "clear session"),
             }
         }
     }
 
     impl Msg {
-        /// Encodes the message as bytes.
+        /// Constructor for `SetNotes`.
+        pub fn set_notes(text: impl Into<String>, version: u64) -> Self {
+            Self::SetNotes {
+                text: text.into(),
+                version,
+            }
+        }
+        /// Constructor for `Tag`.
+        pub fn tag(tag: impl Into<String>, target: tag::Target) -> Self {
+            Self::Tag {
+                tag: tag.into(),
+                target,
+            }
+        }
+        /// Constructor for `Untag`.
+        pub fn untag(tag: impl Into<String>) -> Self {
+            Self::Untag(tag.into())
+        }
+        /// Constructor for `LoadAll`.
+        pub fn load_all() -> Self {
+            Self::LoadAll
+        }
+        /// Reloads the trace from scratch.
+        pub fn reload_trace() -> Self {
+            Self::LoadTrace(None)
+        }
+        /// Switches to the memtrace CTF file at some path.
+        pub fn switch_trace(path: impl Into<String>) -> Self {
+            Self::LoadTrace(Some(path.into()))
+        }
+        /// Constructor for `ClientPanic`.
+        pub fn client_panic(report: impl Into<String>) -> Self {
+            Self::ClientPanic(report.into())
+        }
+        /// Constructor for `ClearSession`.
+        pub fn clear_session() -> Self {
+            Self::ClearSession
+        }
+
+        /// Encodes the message as bytes, with bincode.
         pub fn to_bytes(&self) -> Res<Vec<u8>> {
-            Ok(base::bincode::serialize(self)?)
+            encode(self)
         }
 
         /// Decodes the message from bytes.
+        ///
+        /// Falls back to JSON if the bytes are not a bincode-encoded message, see
+        /// [`from_json_bytes`].
+        ///
+        /// [`from_json_bytes`]: #method.from_json_bytes (The from_json_bytes method)
         pub fn from_bytes(bytes: &[u8]) -> Res<Self> {
-            Ok(base::bincode::deserialize(bytes)?)
+            match decode(bytes) {
+                Ok(msg) => Ok(msg),
+                Err(e) => Self::from_json_bytes(bytes).map_err(|_| e),
+            }
+        }
+
+        /// Encodes the message as JSON.
+        ///
+        /// JSON messages are much bigger than bincode ones, they only exist for debugging.
+        pub fn as_json(&self) -> Res<String> {
+            serde_json::to_string(self)
+                .map_err(|e| format!("failed to encode message as JSON: {}", e).into())
+        }
+
+        /// Decodes the message from JSON bytes.
+        pub fn from_json_bytes(bytes: &[u8]) -> Res<Self> {
+            serde_json::from_slice(bytes)
+                .map_err(|e| format!("failed to decode JSON message: {}", e).into())
+        }
+
+        /// True if the message modifies the charts, the filters, the notes or the tags.
+        ///
+        /// Navigation messages (time window, chart visibility, display mode...) are not mutating.
+        /// Mutating messages are rejected in read-only sessions.
+        ///
+        /// ```rust
+        /// # use charts::prelude::*;
+        /// use charts::msg::{to_server::*, ChartSettingsMsg};
+        /// let uid = uid::Chart::from(0);
+        /// assert!(FiltersMsg::request_new().is_mutating());
+        /// assert!(!FiltersMsg::revert().is_mutating());
+        /// assert!(ChartSettingsMsg::change_title::<Msg>(uid, "title").is_mutating());
+        /// assert!(!ChartSettingsMsg::toggle_visible::<Msg>(uid).is_mutating());
+        /// assert!(ChartsMsg::close(uid).is_mutating());
+        /// assert!(ChartsMsg::set_order(vec![uid]).is_mutating());
+        /// assert!(Msg::set_notes("notes", 0).is_mutating());
+        /// assert!(Msg::untag("leak").is_mutating());
+        /// assert!(Msg::reload_trace().is_mutating());
+        /// assert!(!Msg::client_panic("report").is_mutating());
+        /// ```
+        pub fn is_mutating(&self) -> bool {
+            match self {
+                Self::Charts(msg) => msg.is_mutating(),
+                Self::Filters(msg) => msg.is_mutating(),
+                Self::SetNotes { .. }
+                | Self::Tag { .. }
+                | Self::Untag(_)
+                | Self::LoadAll
+                | Self::LoadTrace(_)
+                | Self::ClearSession => true,
+                Self::ClientPanic(_) => false,
+            }
+        }
+
+        /// True if the message changes the [session] of the client.
+        ///
+        /// That is, saving the filters, creating, renaming or closing a chart, or changing the
+        /// settings of a chart or of all the charts.
+        ///
+        /// ```rust
+        /// # use charts::prelude::*;
+        /// use charts::{filter::FilterSpec, msg::{to_server::*, ChartSettingsMsg}};
+        /// let uid = uid::Chart::from(0);
+        /// assert!(ChartsMsg::close(uid).changes_session());
+        /// assert!(ChartsMsg::rename(uid, Some("heap".into())).changes_session());
+        /// assert!(ChartSettingsMsg::toggle_visible::<Msg>(uid).changes_session());
+        /// let save = FiltersMsg::update_all(
+        ///     FilterSpec::new_everything(),
+        ///     vec![],
+        ///     FilterSpec::new_catch_all(),
+        /// );
+        /// assert!(save.changes_session());
+        /// assert!(!FiltersMsg::request_new().changes_session());
+        /// assert!(!ChartsMsg::reload().changes_session());
+        /// ```
+        ///
+        /// [session]: ../../session/index.html (The session module)
+        pub fn changes_session(&self) -> bool {
+            use ChartsMsg::*;
+            match self {
+                Self::Charts(msg) => match msg {
+                    New(..)
+                    | Close(_)
+                    | ChartUpdate { .. }
+                    | Settings(_)
+                    | SetScope { .. }
+                    | SetOrder(_)
+                    | Rename { .. } => true,
+                    Reload
+                    | Resync(_)
+                    | Resend(_)
+                    | Explain
+                    | SnapshotDiff { .. }
+                    | AllocTable(_)
+                    | TogglePause => false,
+                },
+                Self::Filters(msg) => matches!(
+                    msg,
+                    FiltersMsg::UpdateAll { .. } | FiltersMsg::RequestCatchAllSite
+                ),
+                Self::SetNotes { .. }
+                | Self::Tag { .. }
+                | Self::Untag(_)
+                | Self::LoadAll
+                | Self::LoadTrace(_)
+                | Self::ClientPanic(_)
+                | Self::ClearSession => false,
+            }
         }
     }
 
@@ -130,9 +593,10 @@ pub mod to_server {
             }
 
             Into {
-                to yew::format::Text => |self| anyhow::bail!(
-                    "trying to encode a message as text, only binary is supported"
-                ),
+                to yew::format::Text => |self| match self.as_json() {
+                    Ok(json) => Ok(json),
+                    Err(e) => anyhow::bail!("{}", e),
+                },
                 to yew::format::Binary => |self| match self.to_bytes() {
                     Ok(bytes) => Ok(bytes),
                     Err(e) => anyhow::bail!("{}", e),
@@ -145,7 +609,15 @@ pub mod to_server {
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum ChartsMsg {
         /// Creates a new chart.
-        New(chart::axis::XAxis, chart::axis::YAxis),
+        ///
+        /// Also carries the title of the chart, `None` for the default title, and the visibility of
+        /// the lines. Lines missing from the visibility map are visible.
+        New(
+            chart::axis::XAxis,
+            chart::axis::YAxis,
+            Option<String>,
+            BTMap<uid::Line, bool>,
+        ),
         /// Reloads all charts.
         Reload,
         /// An update for a specific chart.
@@ -157,21 +629,110 @@ pub mod to_server {
         },
         /// New value for the global charts settings.
         Settings(settings::Charts),
+        /// Sets the lines a chart is scoped to.
+        SetScope {
+            /// UID of the chart.
+            uid: uid::Chart,
+            /// Lines of the new scope, `None` to unscope the chart.
+            lines: Option<BTSet<uid::Line>>,
+        },
+        /// Reloads the charts a client is out of sync with, see the [`skew`] module.
+        ///
+        /// Reloads all charts if the trace time of the server went backwards.
+        ///
+        /// [`skew`]: ../../chart/skew/index.html (The skew module)
+        Resync(Vec<chart::skew::Skew>),
+        /// Asks for all the points of some charts, after points went missing.
+        ///
+        /// See the [`seq`] module.
+        ///
+        /// [`seq`]: ../../chart/seq/index.html (The seq module)
+        Resend(BTSet<uid::Chart>),
+        /// Requests an explanation of the current time window.
+        ///
+        /// The server answers with an [`Explanation`] message.
+        ///
+        /// [`Explanation`]: ../to_client/enum.Msg.html#variant.Explanation
+        /// (The Explanation message)
+        Explain,
+        /// Requests a snapshot diff between two instants, see the [`snapshot`] module.
+        ///
+        /// The server answers with a [`SnapshotDiff`] message.
+        ///
+        /// [`snapshot`]: ../../snapshot/index.html (The snapshot module)
+        /// [`SnapshotDiff`]: ../to_client/enum.Msg.html#variant.SnapshotDiff
+        /// (The SnapshotDiff message)
+        SnapshotDiff {
+            /// First instant.
+            from: time::SinceStart,
+            /// Second instant.
+            to: time::SinceStart,
+        },
+        /// Requests a page of the allocation table of a line, see the [`alloc_table`] module.
+        ///
+        /// The server answers with an [`AllocTable`] message.
+        ///
+        /// [`alloc_table`]: ../../alloc_table/index.html (The alloc_table module)
+        /// [`AllocTable`]: ../to_client/enum.Msg.html#variant.AllocTable
+        /// (The AllocTable message)
+        AllocTable(alloc_table::Request),
+        /// Pauses or resumes sending points to the client.
+        ///
+        /// The server keeps ingesting the trace while paused. Resuming sends all the points in one
+        /// `NewPoints` message, overwriting the points of the client. The server answers with a
+        /// [`Paused`] message.
+        ///
+        /// [`Paused`]: ../to_client/enum.ChartsMsg.html#variant.Paused (The Paused message)
+        TogglePause,
+        /// Reorders the charts.
+        ///
+        /// Carries the UIDs of the charts in their new order. Charts that do not appear keep their
+        /// relative order, after the ones that do. Unknown UIDs are ignored.
+        SetOrder(Vec<uid::Chart>),
+        /// Closes a chart.
+        ///
+        /// Unknown UIDs are ignored.
+        Close(uid::Chart),
+        /// Sets the custom title of a chart.
+        ///
+        /// The title is trimmed, `None` or an empty title reverts to the default title of the
+        /// chart.
+        Rename {
+            /// UID of the chart.
+            uid: uid::Chart,
+            /// New title.
+            title: Option<String>,
+        },
     }
     impl fmt::Display for ChartsMsg {
         fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
             match self {
-                Self::New(_, _) => write!(fmt, "new chart"),
+                Self::New(..) => write!(fmt, "new chart"),
                 Self::Reload => write!(fmt, "reload"),
                 Self::ChartUpdate { uid, msg } => write!(fmt, "update({}, {})", uid, msg),
                 Self::Settings(_) => write!(fmt, "new settings"),
+                Self::SetScope { uid, .. } => write!(fmt, "set scope({})", uid),
+                Self::Resync(skews) => write!(fmt, "resync({} skew(s))", skews.len()),
+                Self::Resend(uids) => write!(fmt, "resend({} chart(s))", uids.len()),
+                Self::Explain => write!(fmt, "explain"),
+                Self::SnapshotDiff { from, to } => write!(fmt, "snapshot diff({}, {})", from, to),
+                Self::AllocTable(request) => write!(fmt, "alloc table({})", request),
+                Self::TogglePause => write!(fmt, "toggle pause"),
+                Self::SetOrder(uids) => write!(fmt, "set order({} chart(s))", uids.len()),
+                Self::Close(uid) => write!(fmt, "close({})", uid),
+                Self::Rename { uid, .. } => write!(fmt, "rename({})", uid),
             }
         }
     }
     impl ChartsMsg {
         /// Constructs a chart creation message.
-        pub fn new(x: chart::axis::XAxis, y: chart::axis::YAxis) -> Msg {
-            Self::New(x, y).into()
+        pub fn new(
+            x: chart::axis::XAxis,
+            y: chart::axis::YAxis,
+            title: Option<String>,
+            visible: BTMap<uid::Line, bool>,
+        ) -> Msg {
+            Self::New(x, y, title, visible).into()
         }
         /// Reloads all charts.
         pub fn reload() -> Msg {
@@ -181,6 +742,68 @@ pub mod to_server {
         pub fn settings(settings: settings::Charts) -> Msg {
             Self::Settings(settings).into()
         }
+        /// Sets the lines a chart is scoped to.
+        pub fn set_scope(uid: uid::Chart, lines: Option<BTSet<uid::Line>>) -> Msg {
+            Self::SetScope { uid, lines }.into()
+        }
+        /// Reloads the charts a client is out of sync with.
+        pub fn resync(skews: Vec<chart::skew::Skew>) -> Msg {
+            Self::Resync(skews).into()
+        }
+        /// Asks for all the points of some charts.
+        pub fn resend(uids: BTSet<uid::Chart>) -> Msg {
+            Self::Resend(uids).into()
+        }
+        /// Requests an explanation of the current time window.
+        pub fn explain() -> Msg {
+            Self::Explain.into()
+        }
+        /// Requests a snapshot diff between two instants.
+        pub fn snapshot_diff(from: time::SinceStart, to: time::SinceStart) -> Msg {
+            Self::SnapshotDiff { from, to }.into()
+        }
+        /// Requests a page of the allocation table of a line.
+        pub fn alloc_table(request: alloc_table::Request) -> Msg {
+            Self::AllocTable(request).into()
+        }
+        /// Pauses or resumes sending points to the client.
+        pub fn toggle_pause() -> Msg {
+            Self::TogglePause.into()
+        }
+        /// Reorders the charts.
+        pub fn set_order(uids: Vec<uid::Chart>) -> Msg {
+            Self::SetOrder(uids).into()
+        }
+        /// Closes a chart.
+        pub fn close(uid: uid::Chart) -> Msg {
+            Self::Close(uid).into()
+        }
+        /// Sets the custom title of a chart.
+        pub fn rename(uid: uid::Chart, title: Option<String>) -> Msg {
+            Self::Rename { uid, title }.into()
+        }
+
+        /// True if the message modifies the charts, see [`Msg::is_mutating`].
+        ///
+        /// [`Msg::is_mutating`]: enum.Msg.html#method.is_mutating (Msg::is_mutating)
+        pub fn is_mutating(&self) -> bool {
+            match self {
+                Self::New(..)
+                | Self::SetScope { .. }
+                | Self::Rename { .. }
+                | Self::SetOrder(_)
+                | Self::Close(_) => true,
+                Self::ChartUpdate { msg, .. } => msg.is_mutating(),
+                Self::Reload
+                | Self::Settings(_)
+                | Self::Resync(_)
+                | Self::Resend(_)
+                | Self::Explain
+                | Self::SnapshotDiff { .. }
+                | Self::AllocTable(_)
+                | Self::TogglePause => false,
+            }
+        }
     }
 
     base::implement! {
@@ -201,6 +824,16 @@ pub mod to_server {
         /// Settings update.
         SettingsUpdate(ChartSettingsMsg),
     }
+    impl ChartMsg {
+        /// True if the message modifies the chart, see [`Msg::is_mutating`].
+        ///
+        /// [`Msg::is_mutating`]: enum.Msg.html#method.is_mutating (Msg::is_mutating)
+        pub fn is_mutating(&self) -> bool {
+            match self {
+                Self::SettingsUpdate(msg) => msg.is_mutating(),
+            }
+        }
+    }
     impl fmt::Display for ChartMsg {
         fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
             match self {
@@ -240,9 +873,77 @@ pub mod to_server {
         /// (The Add message)
         RequestNewSub(uid::Filter),
 
+        /// Requests a new filter for an allocation site.
+        ///
+        /// Same as [`FiltersMsg::RequestNew`], but the filter only matches the allocations of the
+        /// site.
+        ///
+        /// [`FiltersMsg::RequestNew`]: #variant.RequestNew (The RequestNew message)
+        RequestSite {
+            /// File of the allocation site.
+            file: String,
+            /// Line of the allocation site.
+            line: usize,
+        },
+
         /// Requests the current server-side list of filters.
         Revert,
 
+        /// Imports filters exported in a previous session.
+        ///
+        /// The server gives fresh UIDs to the filters and sends them back (*via*
+        /// [`FiltersMsg::Import`]) so that they replace the filters of the client. As with
+        /// [`FiltersMsg::RequestNew`], the server does not register them until the user saves.
+        ///
+        /// [`FiltersMsg::Import`]: ../to_client/enum.FiltersMsg.html#variant.Import
+        /// (The Import message)
+        /// [`FiltersMsg::RequestNew`]: #variant.RequestNew (The RequestNew message)
+        Import(filter::Export),
+
+        /// Duplicates a filter.
+        ///
+        /// Carries the client's version of the filter, which may have unsaved edits. The server
+        /// sends back a copy with fresh UIDs (*via* [`FiltersMsg::Add`]), so that saving it cannot
+        /// cause UID collisions. As with [`FiltersMsg::RequestNew`], the server does not register
+        /// the copy until the user saves.
+        ///
+        /// [`FiltersMsg::Add`]: ../to_client/enum.FiltersMsg.html#variant.Add
+        /// (The Add message)
+        /// [`FiltersMsg::RequestNew`]: #variant.RequestNew (The RequestNew message)
+        Duplicate(Filter),
+
+        /// Cancels the ongoing filter generation, if any.
+        ///
+        /// Generation is atomic: if cancelled, the server falls back to having no generated
+        /// filters.
+        CancelGen,
+
+        /// Previews the live allocations a filter catches.
+        ///
+        /// Carries the client's version of the filter, which may have unsaved edits. The server
+        /// answers with [`FiltersMsg::Preview`] without registering the filter nor regenerating
+        /// any point. A preview supersedes the previews the server has not handled yet.
+        ///
+        /// [`FiltersMsg::Preview`]: ../to_client/enum.FiltersMsg.html#variant.Preview
+        /// (The Preview message)
+        Preview {
+            /// Identifier of the preview, sent back with the answer.
+            id: u64,
+            /// Filter to preview.
+            filter: Filter,
+        },
+
+        /// Requests the labels of the trace starting with some prefix.
+        ///
+        /// The server answers with [`FiltersMsg::LabelCompletions`], see the [`complete`] module. A
+        /// completion request supersedes the requests the server has not handled yet.
+        ///
+        /// [`FiltersMsg::LabelCompletions`]:
+        /// ../to_client/enum.FiltersMsg.html#variant.LabelCompletions
+        /// (The LabelCompletions message)
+        /// [`complete`]: ../../filter/complete/index.html (The complete module)
+        CompleteLabel(String),
+
         /// Updates all the filters.
         UpdateAll {
             /// New specificationfor the "everything" filter.
@@ -252,14 +953,46 @@ pub mod to_server {
             /// New specification for the "catch-all" filter.
             catch_all: filter::FilterSpec,
         },
+
+        /// Requests a new filter for the allocation site allocating the most live bytes among the
+        /// allocations of the catch-all filter.
+        ///
+        /// Unlike [`FiltersMsg::RequestSite`], the server registers the filter right away so that
+        /// the catch-all filter shrinks on the next refresh, and sends it back *via*
+        /// [`FiltersMsg::AddSaved`].
+        ///
+        /// [`FiltersMsg::RequestSite`]: #variant.RequestSite (The RequestSite message)
+        /// [`FiltersMsg::AddSaved`]: ../to_client/enum.FiltersMsg.html#variant.AddSaved
+        /// (The AddSaved message)
+        RequestCatchAllSite,
+
+        /// Appends shared filters to the filters of the client, see the [`text`] module.
+        ///
+        /// Same as [`FiltersMsg::Import`], but the server sends the filters back (*via*
+        /// [`FiltersMsg::Append`]) so that they come after the filters of the client instead of
+        /// replacing them.
+        ///
+        /// [`text`]: ../../filter/text/index.html (The text module)
+        /// [`FiltersMsg::Import`]: #variant.Import (The Import message)
+        /// [`FiltersMsg::Append`]: ../to_client/enum.FiltersMsg.html#variant.Append
+        /// (The Append message)
+        Append(Vec<Filter>),
     }
     impl fmt::Display for FiltersMsg {
         fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
             match self {
                 Self::RequestNew => write!(fmt, "request new"),
                 Self::RequestNewSub(_) => write!(fmt, "request new sub"),
+                Self::RequestSite { file, line } => write!(fmt, "request site {}:{}", file, line),
                 Self::Revert => write!(fmt, "revert"),
+                Self::Import(_) => write!(fmt, "import"),
+                Self::Duplicate(filter) => write!(fmt, "duplicate {}", filter.uid()),
+                Self::CancelGen => write!(fmt, "cancel generation"),
+                Self::Preview { id, filter } => write!(fmt, "preview #{} of {}", id, filter.uid()),
+                Self::CompleteLabel(prefix) => write!(fmt, "complete label `{}`", prefix),
                 Self::UpdateAll { .. } => write!(fmt, "update all"),
+                Self::RequestCatchAllSite => write!(fmt, "request catch-all site"),
+                Self::Append(filters) => write!(fmt, "append {} filter(s)", filters.len()),
             }
         }
     }
@@ -273,10 +1006,98 @@ pub mod to_server {
         pub fn request_new_sub(uid: uid::Filter) -> Msg {
             Self::RequestNewSub(uid).into()
         }
+        /// Requests a new filter for an allocation site.
+        pub fn request_site(file: impl Into<String>, line: usize) -> Msg {
+            Self::RequestSite {
+                file: file.into(),
+                line,
+            }
+            .into()
+        }
         /// Requests the current server-side list of filters.
         pub fn revert() -> Msg {
             Self::Revert.into()
         }
+        /// Imports filters exported in a previous session.
+        pub fn import(export: filter::Export) -> Msg {
+            Self::Import(export).into()
+        }
+        /// Duplicates a filter.
+        pub fn duplicate(filter: Filter) -> Msg {
+            Self::Duplicate(filter).into()
+        }
+        /// Cancels the ongoing filter generation.
+        pub fn cancel_gen() -> Msg {
+            Self::CancelGen.into()
+        }
+        /// Previews the live allocations a filter catches.
+        pub fn preview(id: u64, filter: Filter) -> Msg {
+            Self::Preview { id, filter }.into()
+        }
+
+        /// Requests the labels of the trace starting with some prefix.
+        pub fn complete_label(prefix: impl Into<String>) -> Msg {
+            Self::CompleteLabel(prefix.into()).into()
+        }
+        /// Requests a new filter for the top allocation site of the catch-all filter.
+        pub fn request_catch_all_site() -> Msg {
+            Self::RequestCatchAllSite.into()
+        }
+        /// Appends shared filters to the filters of the client.
+        pub fn append(filters: Vec<Filter>) -> Msg {
+            Self::Append(filters).into()
+        }
+
+        /// True if the message is a preview.
+        pub fn is_preview(&self) -> bool {
+            matches!(self, Self::Preview { .. })
+        }
+        /// True if the message is a label completion request.
+        pub fn is_label_completion(&self) -> bool {
+            matches!(self, Self::CompleteLabel(_))
+        }
+
+        /// True if the message modifies the filters, see [`Msg::is_mutating`].
+        ///
+        /// Requesting a new (sub)filter is mutating as its only purpose is to edit the filters.
+        ///
+        /// [`Msg::is_mutating`]: enum.Msg.html#method.is_mutating (Msg::is_mutating)
+        pub fn is_mutating(&self) -> bool {
+            match self {
+                Self::RequestNew
+                | Self::RequestNewSub(_)
+                | Self::RequestSite { .. }
+                | Self::Import(_)
+                | Self::Duplicate(_)
+                | Self::UpdateAll { .. }
+                | Self::RequestCatchAllSite
+                | Self::Append(_) => true,
+                Self::Revert | Self::CancelGen | Self::Preview { .. } | Self::CompleteLabel(_) => {
+                    false
+                }
+            }
+        }
+
+        /// Number of filters once the message is applied, `None` if the message adds no filters.
+        ///
+        /// `count` is the number of filters before the message is applied. Saving counts as adding
+        /// filters, since it registers the new filters of the client.
+        pub fn filter_count(&self, count: usize) -> Option<usize> {
+            match self {
+                Self::RequestNew
+                | Self::RequestSite { .. }
+                | Self::Duplicate(_)
+                | Self::RequestCatchAllSite => Some(count + 1),
+                Self::Import(export) => Some(count + export.filters.len()),
+                Self::Append(filters) => Some(count + filters.len()),
+                Self::UpdateAll { filters, .. } => Some(filters.len()),
+                Self::RequestNewSub(_)
+                | Self::Revert
+                | Self::CancelGen
+                | Self::Preview { .. }
+                | Self::CompleteLabel(_) => None,
+            }
+        }
 
         /// Updates all the filters.
         pub fn update_all(
@@ -304,8 +1125,6 @@ pub mod to_client {
     /// Messages from the server to the client.
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum Msg {
-        /// Info about the current allocation data.
-        Info,
         /// An alert.
         Alert {
             /// Alert message.
@@ -318,6 +1137,10 @@ pub mod to_client {
         /// Sent by the server when it is loading data, *i.e.* not ready to actually produce charts
         /// yet.
         LoadProgress(LoadInfo),
+        /// Filter generation progress.
+        ///
+        /// Sent by the server when it is done loading data but still generating filters.
+        GenProgress(GenProgress),
         /// Allocation statistics.
         AllocStats(AllocStats),
         /// Sent by the server when it is done loading dumps.
@@ -328,12 +1151,102 @@ pub mod to_client {
         Filters(FiltersMsg),
         /// Some filter statistics.
         FilterStats(filter::stats::AllFilterStats),
+        /// Explanation of a time range, in response to [`ChartsMsg::Explain`].
+        ///
+        /// [`ChartsMsg::Explain`]: ../to_server/enum.ChartsMsg.html#variant.Explain
+        /// (The Explain message)
+        Explanation(explain::Explanation),
+        /// Snapshot diff, in response to [`ChartsMsg::SnapshotDiff`].
+        ///
+        /// [`ChartsMsg::SnapshotDiff`]: ../to_server/enum.ChartsMsg.html#variant.SnapshotDiff
+        /// (The SnapshotDiff message)
+        SnapshotDiff(snapshot::SnapshotDiff),
+        /// Page of an allocation table, in response to [`ChartsMsg::AllocTable`].
+        ///
+        /// [`ChartsMsg::AllocTable`]: ../to_server/enum.ChartsMsg.html#variant.AllocTable
+        /// (The AllocTable message)
+        AllocTable(alloc_table::AllocTable),
+        /// The notes of the server.
+        ///
+        /// Sent on connection, when a client updates the notes, and when the notes change.
+        Notes {
+            /// Current notes.
+            notes: notes::Notes,
+            /// True if the update of this client overwrote changes it had not seen.
+            conflict: bool,
+        },
+        /// The tags of the server.
+        ///
+        /// Sent on connection, and when the tags change.
+        Tags(tag::Tags),
+        /// Information about the run the trace comes from, `None` if the trace has none.
+        ///
+        /// Sent on connection, and when the server loads a different trace.
+        TraceInfo(Option<alloc::TraceInfo>),
+        /// Sent by the server when the session is read-only.
+        ///
+        /// The server rejects all mutating messages in read-only sessions, see
+        /// [`to_server::Msg::is_mutating`].
+        ///
+        /// [`to_server::Msg::is_mutating`]: ../to_server/enum.Msg.html#method.is_mutating
+        /// (to_server::Msg::is_mutating)
+        ReadOnly,
+        /// The limits of the server over the number of filters and charts.
+        ///
+        /// Sent on connection, clients check them before asking for new filters or charts.
+        Limits(limits::Limits),
+        /// A message from the client was rejected.
+        Rejected {
+            /// Description of the rejected message.
+            msg: String,
+            /// Reason for the rejection.
+            reason: Rejection,
+        },
+        /// Status of the session file of the server, `None` if the server does not persist
+        /// sessions.
+        ///
+        /// Sent on connection, and when the session file changes.
+        Session(Option<session::Status>),
     }
-    impl Msg {
-        /// Constructor for `Info`.
-        pub fn info() -> Self {
-            Self::Info
+
+    /// Reason why the server rejected a message from the client.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Rejection {
+        /// The message is mutating and the session is read-only.
+        ReadOnlySession,
+        /// The message would take the number of filters over the limit, which it stores.
+        FilterLimit(usize),
+        /// The message would take the number of charts over the limit, which it stores.
+        ChartLimit(usize),
+        /// The message needs allocations the server may prune, see [`data::prune`].
+        ///
+        /// [`data::prune`]: ../../data/prune/index.html (The data::prune module)
+        Pruning,
+    }
+    impl fmt::Display for Rejection {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Self::ReadOnlySession => "read-only session".fmt(fmt),
+                Self::FilterLimit(max) => write!(
+                    fmt,
+                    "the server allows at most {} filters, restart it with `{} <N>` to raise the limit",
+                    max,
+                    limits::Limits::FILTERS_FLAG,
+                ),
+                Self::ChartLimit(max) => write!(
+                    fmt,
+                    "the server allows at most {} charts, restart it with `{} <N>` to raise the limit",
+                    max,
+                    limits::Limits::CHARTS_FLAG,
+                ),
+                Self::Pruning => "the server prunes old allocations (`--prune`), only the total \
+                    size, allocation count, allocation rate and churn ratio charts and filters \
+                    without lifetime subfilters can account for them"
+                    .fmt(fmt),
+            }
         }
+    }
+    impl Msg {
         /// Constructor for `Alert`.
         pub fn alert(msg: impl Into<String>, fatal: bool) -> Self {
             Self::Alert {
@@ -349,6 +1262,10 @@ pub mod to_client {
         pub fn load_progress(info: LoadInfo) -> Self {
             Self::LoadProgress(info)
         }
+        /// Constructor for a filter generation progress message.
+        pub fn gen_progress(progress: GenProgress) -> Self {
+            Self::GenProgress(progress)
+        }
         /// Constructor for an allocation-statistics message.
         pub fn alloc_stats(stats: AllocStats) -> Self {
             Self::AllocStats(stats)
@@ -357,15 +1274,100 @@ pub mod to_client {
         pub fn filter_stats(stats: filter::stats::AllFilterStats) -> Self {
             Self::FilterStats(stats)
         }
+        /// Constructor for an explanation message.
+        pub fn explanation(explanation: explain::Explanation) -> Self {
+            Self::Explanation(explanation)
+        }
+        /// Constructor for a snapshot diff message.
+        pub fn snapshot_diff(diff: snapshot::SnapshotDiff) -> Self {
+            Self::SnapshotDiff(diff)
+        }
+        /// Constructor for an allocation table message.
+        pub fn alloc_table(table: alloc_table::AllocTable) -> Self {
+            Self::AllocTable(table)
+        }
+        /// Constructor for a notes message.
+        pub fn notes(notes: notes::Notes, conflict: bool) -> Self {
+            Self::Notes { notes, conflict }
+        }
+        /// Constructor for a tags message.
+        pub fn tags(tags: tag::Tags) -> Self {
+            Self::Tags(tags)
+        }
+        /// Constructor for a trace information message.
+        pub fn trace_info(info: Option<alloc::TraceInfo>) -> Self {
+            Self::TraceInfo(info)
+        }
+        /// Constructor for a limits message.
+        pub fn limits(limits: limits::Limits) -> Self {
+            Self::Limits(limits)
+        }
+        /// Constructor for a rejection.
+        ///
+        /// `msg` describes the rejected message.
+        pub fn rejected(msg: impl Into<String>, reason: Rejection) -> Self {
+            Self::Rejected {
+                msg: msg.into(),
+                reason,
+            }
+        }
+        /// Constructor for a session status message.
+        pub fn session(status: Option<session::Status>) -> Self {
+            Self::Session(status)
+        }
+        /// Constructor for a rejection because the session is read-only.
+        pub fn read_only_reject(msg: &super::to_server::Msg) -> Self {
+            Self::Rejected {
+                msg: msg.to_string(),
+                reason: Rejection::ReadOnlySession,
+            }
+        }
 
-        /// Encodes the message as bytes.
+        /// Encodes the message as bytes, with bincode.
         pub fn to_bytes(&self) -> Res<Vec<u8>> {
-            Ok(base::bincode::serialize(self)?)
+            encode(self)
+        }
+
+        /// Encodes the message as bytes sealed in an envelope, see the [`compress`] module.
+        ///
+        /// [`compress`]: ../compress/index.html (The compress module)
+        pub fn to_envelope_bytes(&self) -> Res<Vec<u8>> {
+            compress::Envelope::seal(self.to_bytes()?).to_bytes()
+        }
+
+        /// Decodes the message from the bytes of an envelope, see the [`compress`] module.
+        ///
+        /// [`compress`]: ../compress/index.html (The compress module)
+        pub fn from_envelope_bytes(bytes: &[u8]) -> Res<Self> {
+            let bytes = compress::Envelope::from_bytes(bytes)?.open()?;
+            decode(&bytes)
         }
 
         /// Decodes the message from bytes.
+        ///
+        /// Falls back to JSON if the bytes are not a bincode-encoded message, see
+        /// [`from_json_bytes`].
+        ///
+        /// [`from_json_bytes`]: #method.from_json_bytes (The from_json_bytes method)
         pub fn from_bytes(bytes: &[u8]) -> Res<Self> {
-            Ok(base::bincode::deserialize(bytes)?)
+            match decode(bytes) {
+                Ok(msg) => Ok(msg),
+                Err(e) => Self::from_json_bytes(bytes).map_err(|_| e),
+            }
+        }
+
+        /// Encodes the message as JSON.
+        ///
+        /// JSON messages are much bigger than bincode ones, they only exist for debugging.
+        pub fn as_json(&self) -> Res<String> {
+            serde_json::to_string(self)
+                .map_err(|e| format!("failed to encode message as JSON: {}", e).into())
+        }
+
+        /// Decodes the message from JSON bytes.
+        pub fn from_json_bytes(bytes: &[u8]) -> Res<Self> {
+            serde_json::from_slice(bytes)
+                .map_err(|e| format!("failed to decode JSON message: {}", e).into())
         }
 
         /// True if the message is a minor message.
@@ -373,13 +1375,24 @@ pub mod to_client {
         /// *Minor messages* are all messages that do not act on charts or filters directly.
         pub fn is_minor(&self) -> bool {
             match self {
-                Self::Charts(_) | Self::Filters(_) => false,
-                Self::Info
-                | Self::Alert { .. }
+                Self::Charts(_)
+                | Self::Filters(_)
+                | Self::Explanation(_)
+                | Self::SnapshotDiff(_)
+                | Self::AllocTable(_)
+                | Self::Notes { .. }
+                | Self::Tags(_)
+                | Self::ReadOnly
+                | Self::Limits(_)
+                | Self::Rejected { .. } => false,
+                Self::Alert { .. }
                 | Self::LoadProgress(_)
+                | Self::GenProgress(_)
                 | Self::AllocStats(_)
                 | Self::DoneLoading
-                | Self::FilterStats(_) => true,
+                | Self::FilterStats(_)
+                | Self::TraceInfo(_)
+                | Self::Session(_) => true,
             }
         }
     }
@@ -387,14 +1400,24 @@ pub mod to_client {
     impl fmt::Display for Msg {
         fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
             match self {
-                Self::Info => "info".fmt(fmt),
                 Self::Alert { .. } => "alert".fmt(fmt),
                 Self::Charts(msg) => write!(fmt, "charts({})", msg),
                 Self::LoadProgress(_) => "load progress".fmt(fmt),
+                Self::GenProgress(_) => "generation progress".fmt(fmt),
                 Self::AllocStats(_) => "alloc stats".fmt(fmt),
                 Self::FilterStats(_) => "filter stats".fmt(fmt),
+                Self::Explanation(_) => "explanation".fmt(fmt),
+                Self::SnapshotDiff(_) => "snapshot diff".fmt(fmt),
+                Self::AllocTable(_) => "alloc table".fmt(fmt),
+                Self::Notes { notes, .. } => write!(fmt, "notes (v{})", notes.version),
+                Self::Tags(tags) => write!(fmt, "{} tag(s)", tags.len()),
+                Self::TraceInfo(_) => "trace info".fmt(fmt),
                 Self::DoneLoading => "done loading".fmt(fmt),
                 Self::Filters(_) => "filter".fmt(fmt),
+                Self::ReadOnly => "read-only session".fmt(fmt),
+                Self::Limits(_) => "limits".fmt(fmt),
+                Self::Rejected { msg, reason } => write!(fmt, "rejected {} ({})", msg, reason),
+                Self::Session(_) => "session status".fmt(fmt),
             }
         }
     }
@@ -421,6 +1444,9 @@ pub mod to_client {
             msg: ChartMsg,
         },
         /// A new collection of points, overwrites existing points.
+        ///
+        /// An empty collection wipes the points of all the charts, the server sends one when it
+        /// replaces its data.
         NewPoints {
             /// New points.
             points: point::ChartPoints,
@@ -429,6 +1455,11 @@ pub mod to_client {
         },
         /// Some points to append to existing points.
         AddPoints(point::ChartPoints),
+        /// True if the server stopped sending points, see [`TogglePause`].
+        ///
+        /// [`TogglePause`]: ../to_server/enum.ChartsMsg.html#variant.TogglePause
+        /// (The TogglePause message)
+        Paused(bool),
     }
     impl ChartsMsg {
         /// Constructor for `NewChart`.
@@ -446,6 +1477,10 @@ pub mod to_client {
         pub fn add_points(points: point::ChartPoints) -> Msg {
             Msg::charts(Self::AddPoints(points))
         }
+        /// Constructor for `Paused`.
+        pub fn paused(paused: bool) -> Msg {
+            Msg::charts(Self::Paused(paused))
+        }
 
         /// Constructs a `NewPoints` if `overwrite`, and a `AddPoints` otherwise.
         pub fn points(points: point::ChartPoints, overwrite: bool) -> Msg {
@@ -484,6 +1519,7 @@ pub mod to_client {
                     }
                     Ok(())
                 }
+                Self::Paused(paused) => write!(fmt, "paused({})", paused),
             }
         }
     }
@@ -491,27 +1527,16 @@ pub mod to_client {
     /// Messages for a specific chart in the client.
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum ChartMsg {
-        /// A brand new list of points.
-        ///
-        /// Replaces all the points in a chart.
-        NewPoints(point::Points),
-        /// Some points to append.
-        Points(point::Points),
+        /// New scope of the chart.
+        Scope(Option<BTSet<uid::Line>>),
     }
 
     impl ChartMsg {
-        /// List of points overwriting the existing points.
-        pub fn new_points(uid: uid::Chart, points: point::Points) -> Msg {
-            Msg::charts(ChartsMsg::Chart {
-                uid,
-                msg: Self::NewPoints(points),
-            })
-        }
-        /// List of points to append.
-        pub fn points(uid: uid::Chart, points: point::Points) -> Msg {
+        /// New scope of the chart.
+        pub fn scope(uid: uid::Chart, scope: Option<BTSet<uid::Line>>) -> Msg {
             Msg::charts(ChartsMsg::Chart {
                 uid,
-                msg: Self::Points(points),
+                msg: Self::Scope(scope),
             })
         }
     }
@@ -519,8 +1544,7 @@ pub mod to_client {
     impl fmt::Display for ChartMsg {
         fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
             match self {
-                Self::NewPoints(points) => write!(fmt, "{} new points", points.len()),
-                Self::Points(points) => write!(fmt, "add {} points", points.len()),
+                Self::Scope(_) => "scope".fmt(fmt),
             }
         }
     }
@@ -531,10 +1555,12 @@ pub mod to_client {
         /// Adds a filter.
         ///
         /// This message always comes in response to a [`FiltersMsg::RequestNew`] message for the
-        /// server.
+        /// server, or to a similar message such as [`FiltersMsg::Duplicate`].
         ///
         /// [`FiltersMsg::RequestNew`]: ../to_server/enum.FiltersMsg.html#variant.RequestNew
         /// (The RequestNew message)
+        /// [`FiltersMsg::Duplicate`]: ../to_server/enum.FiltersMsg.html#variant.Duplicate
+        /// (The Duplicate message)
         Add(filter::Filter),
         /// Adds a subfilter.
         ///
@@ -545,6 +1571,15 @@ pub mod to_client {
         /// (The RequestNew message)
         AddSub(uid::Filter, filter::SubFilter),
 
+        /// Replaces the filters of the client with imported ones.
+        ///
+        /// This message always comes in response to a [`FiltersMsg::Import`] message for the
+        /// server, with fresh UIDs.
+        ///
+        /// [`FiltersMsg::Import`]: ../to_server/enum.FiltersMsg.html#variant.Import
+        /// (The Import message)
+        Import(filter::Export),
+
         /// Orders the client to revert all its filters.
         Revert {
             /// Specification for the `everything` filter.
@@ -554,8 +1589,55 @@ pub mod to_client {
             /// Specification for the `catch_all` filter.
             catch_all: FilterSpec,
         },
-        // /// Updates all the specs.
-        // UpdateSpecs(BTMap<uid::Line, FilterSpec>),
+
+        /// Preview of the live allocations a filter catches.
+        ///
+        /// This message always comes in response to a [`FiltersMsg::Preview`] message for the
+        /// server.
+        ///
+        /// [`FiltersMsg::Preview`]: ../to_server/enum.FiltersMsg.html#variant.Preview
+        /// (The Preview message)
+        Preview {
+            /// Identifier of the preview the client asked for.
+            id: u64,
+            /// Filter previewed.
+            filter: uid::Filter,
+            /// Preview, or the reason the server could not compute it.
+            preview: Result<filter::preview::Preview, String>,
+        },
+
+        /// Labels of the trace starting with some prefix.
+        ///
+        /// This message always comes in response to a [`FiltersMsg::CompleteLabel`] message for
+        /// the server.
+        ///
+        /// [`FiltersMsg::CompleteLabel`]: ../to_server/enum.FiltersMsg.html#variant.CompleteLabel
+        /// (The CompleteLabel message)
+        LabelCompletions {
+            /// Prefix the client asked the completions of.
+            prefix: String,
+            /// Labels starting with the prefix, the most common first.
+            labels: Vec<String>,
+        },
+
+        /// Adds a filter the server already registered.
+        ///
+        /// This message always comes in response to a [`FiltersMsg::RequestCatchAllSite`] message
+        /// for the server.
+        ///
+        /// [`FiltersMsg::RequestCatchAllSite`]:
+        /// ../to_server/enum.FiltersMsg.html#variant.RequestCatchAllSite
+        /// (The RequestCatchAllSite message)
+        AddSaved(filter::Filter),
+
+        /// Adds shared filters after the filters of the client.
+        ///
+        /// This message always comes in response to a [`FiltersMsg::Append`] message for the
+        /// server, with fresh UIDs.
+        ///
+        /// [`FiltersMsg::Append`]: ../to_server/enum.FiltersMsg.html#variant.Append
+        /// (The Append message)
+        Append(Vec<filter::Filter>),
     }
     impl FiltersMsg {
         /// Adds a filter.
@@ -566,6 +1648,10 @@ pub mod to_client {
         pub fn add_sub(uid: uid::Filter, subfilter: filter::SubFilter) -> Msg {
             Self::AddSub(uid, subfilter).into()
         }
+        /// Replaces the filters of the client with imported ones.
+        pub fn import(export: filter::Export) -> Msg {
+            Self::Import(export).into()
+        }
 
         /// Orders the client to revert all its filters.
         pub fn revert(everything: FilterSpec, filters: Vec<Filter>, catch_all: FilterSpec) -> Msg {
@@ -577,10 +1663,33 @@ pub mod to_client {
             .into()
         }
 
-        // /// Updates all the specs.
-        // pub fn update_specs(specs: BTMap<uid::Line, FilterSpec>) -> Msg {
-        //     Self::UpdateSpecs(specs).into()
-        // }
+        /// Preview of the live allocations a filter catches.
+        pub fn preview(
+            id: u64,
+            filter: uid::Filter,
+            preview: Result<filter::preview::Preview, String>,
+        ) -> Msg {
+            Self::Preview {
+                id,
+                filter,
+                preview,
+            }
+            .into()
+        }
+
+        /// Labels of the trace starting with some prefix.
+        pub fn label_completions(prefix: String, labels: Vec<String>) -> Msg {
+            Self::LabelCompletions { prefix, labels }.into()
+        }
+
+        /// Adds a filter the server already registered.
+        pub fn add_saved(filter: filter::Filter) -> Msg {
+            Self::AddSaved(filter).into()
+        }
+        /// Adds shared filters after the filters of the client.
+        pub fn append(filters: Vec<filter::Filter>) -> Msg {
+            Self::Append(filters).into()
+        }
     }
 
     /// A raw message from the server.
@@ -588,18 +1697,18 @@ pub mod to_client {
     pub enum RawMsg {
         /// Binary version.
         Binary(Result<Vec<u8>, String>),
+        /// JSON version.
+        Text(Result<String, String>),
     }
 
     base::implement! {
         impl RawMsg {
             From {
                 from yew::format::Binary => |data| RawMsg::Binary(data.map_err(|e| e.to_string())),
-                from yew::format::Text => |_| panic!(
-                    "trying to decode a message from text, \
-                    but only decoding from binary is supported"
-                ),
+                from yew::format::Text => |data| RawMsg::Text(data.map_err(|e| e.to_string())),
             }
 
+            // The client always accepts compression, binary messages are envelopes.
             Into {
                 to Res<Msg> => |self| {
                     let res = match self {
@@ -607,7 +1716,13 @@ pub mod to_client {
                             let bytes = res_bytes
                                 .map_err(err::Error::from)
                                 .chain_err(|| "while retrieving message from the server")?;
-                            Msg::from_bytes(&bytes)
+                            Msg::from_envelope_bytes(&bytes)
+                        }
+                        RawMsg::Text(res_text) => {
+                            let text = res_text
+                                .map_err(err::Error::from)
+                                .chain_err(|| "while retrieving message from the server")?;
+                            Msg::from_json_bytes(text.as_bytes())
                         }
                     };
                     res.chain_err(|| "while parsing a message from the server")
@@ -616,3 +1731,254 @@ pub mod to_client {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Decodes an hexadecimal string.
+    fn of_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|idx| u8::from_str_radix(&hex[idx..idx + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Rebuilds a message for the server with its constructors.
+    ///
+    /// The match is exhaustive: a new variant must come with a constructor, or this fails to
+    /// compile.
+    fn rebuild_to_server(msg: to_server::Msg) -> to_server::Msg {
+        use to_server::{ChartMsg, ChartsMsg, FiltersMsg, Msg};
+        match msg {
+            Msg::Charts(msg) => match msg {
+                ChartsMsg::New(x_axis, y_axis, title, visible) => {
+                    ChartsMsg::new(x_axis, y_axis, title, visible)
+                }
+                ChartsMsg::Reload => ChartsMsg::reload(),
+                ChartsMsg::ChartUpdate {
+                    uid,
+                    msg: ChartMsg::SettingsUpdate(msg),
+                } => match msg {
+                    ChartSettingsMsg::ToggleVisible => ChartSettingsMsg::toggle_visible(uid),
+                    ChartSettingsMsg::ChangeTitle(title) => {
+                        ChartSettingsMsg::change_title(uid, title)
+                    }
+                    ChartSettingsMsg::SetDisplayMode(mode) => {
+                        ChartSettingsMsg::set_display_mode(uid, mode)
+                    }
+                    ChartSettingsMsg::SetResolution(resolution) => {
+                        ChartSettingsMsg::set_resolution(uid, resolution)
+                    }
+                    ChartSettingsMsg::SetQuality(quality) => {
+                        ChartSettingsMsg::set_quality(uid, quality)
+                    }
+                    ChartSettingsMsg::SetMaxPoints(max_points) => {
+                        ChartSettingsMsg::set_max_points(uid, max_points)
+                    }
+                    ChartSettingsMsg::SetSizePercentile(size_percentile) => {
+                        ChartSettingsMsg::set_size_percentile(uid, size_percentile)
+                    }
+                    ChartSettingsMsg::SetLifetimeStat(lifetime_stat) => {
+                        ChartSettingsMsg::set_lifetime_stat(uid, lifetime_stat)
+                    }
+                    ChartSettingsMsg::SetNormalize(line) => {
+                        ChartSettingsMsg::set_normalize(uid, line)
+                    }
+                    ChartSettingsMsg::SetTransforms(transforms) => {
+                        ChartSettingsMsg::set_transforms(uid, transforms)
+                    }
+                    ChartSettingsMsg::SetTicks(ticks) => ChartSettingsMsg::set_ticks(uid, ticks),
+                    ChartSettingsMsg::SetTimeWindow(window) => {
+                        ChartSettingsMsg::set_time_window(uid, window)
+                    }
+                    ChartSettingsMsg::SetHideEverything(hide) => {
+                        ChartSettingsMsg::set_hide_everything(uid, hide)
+                    }
+                    ChartSettingsMsg::SetTimeAxis(time_axis) => {
+                        ChartSettingsMsg::set_time_axis(uid, time_axis)
+                    }
+                    ChartSettingsMsg::SetLineHidden(line, hide) => {
+                        ChartSettingsMsg::set_line_hidden(uid, line, hide)
+                    }
+                },
+                ChartsMsg::Settings(settings) => ChartsMsg::settings(settings),
+                ChartsMsg::SetScope { uid, lines } => ChartsMsg::set_scope(uid, lines),
+                ChartsMsg::Resync(skews) => ChartsMsg::resync(skews),
+                ChartsMsg::Explain => ChartsMsg::explain(),
+                ChartsMsg::SnapshotDiff { from, to } => ChartsMsg::snapshot_diff(from, to),
+                ChartsMsg::AllocTable(request) => ChartsMsg::alloc_table(request),
+                ChartsMsg::Resend(uids) => ChartsMsg::resend(uids),
+                ChartsMsg::TogglePause => ChartsMsg::toggle_pause(),
+                ChartsMsg::SetOrder(uids) => ChartsMsg::set_order(uids),
+                ChartsMsg::Close(uid) => ChartsMsg::close(uid),
+                ChartsMsg::Rename { uid, title } => ChartsMsg::rename(uid, title),
+            },
+            Msg::Filters(msg) => match msg {
+                FiltersMsg::RequestNew => FiltersMsg::request_new(),
+                FiltersMsg::RequestNewSub(uid) => FiltersMsg::request_new_sub(uid),
+                FiltersMsg::RequestSite { file, line } => FiltersMsg::request_site(file, line),
+                FiltersMsg::Revert => FiltersMsg::revert(),
+                FiltersMsg::Import(export) => FiltersMsg::import(export),
+                FiltersMsg::Duplicate(filter) => FiltersMsg::duplicate(filter),
+                FiltersMsg::CancelGen => FiltersMsg::cancel_gen(),
+                FiltersMsg::Preview { id, filter } => FiltersMsg::preview(id, filter),
+                FiltersMsg::CompleteLabel(prefix) => FiltersMsg::complete_label(prefix),
+                FiltersMsg::UpdateAll {
+                    everything,
+                    filters,
+                    catch_all,
+                } => FiltersMsg::update_all(everything, filters, catch_all),
+                FiltersMsg::RequestCatchAllSite => FiltersMsg::request_catch_all_site(),
+                FiltersMsg::Append(filters) => FiltersMsg::append(filters),
+            },
+            Msg::SetNotes { text, version } => Msg::set_notes(text, version),
+            Msg::Tag { tag, target } => Msg::tag(tag, target),
+            Msg::Untag(tag) => Msg::untag(tag),
+            Msg::LoadAll => Msg::load_all(),
+            Msg::LoadTrace(None) => Msg::reload_trace(),
+            Msg::LoadTrace(Some(path)) => Msg::switch_trace(path),
+            Msg::ClientPanic(report) => Msg::client_panic(report),
+            Msg::ClearSession => Msg::clear_session(),
+        }
+    }
+
+    /// Rebuilds a message for the client with its constructors.
+    ///
+    /// The match is exhaustive: a new variant must come with a constructor, or this fails to
+    /// compile. Unit variants are their own constructor.
+    fn rebuild_to_client(msg: to_client::Msg) -> to_client::Msg {
+        use to_client::{ChartMsg, ChartsMsg, FiltersMsg, Msg, Rejection};
+        match msg {
+            Msg::Alert { msg, fatal } => Msg::alert(msg, fatal),
+            Msg::LoadProgress(info) => Msg::load_progress(info),
+            Msg::GenProgress(progress) => Msg::gen_progress(progress),
+            Msg::AllocStats(stats) => Msg::alloc_stats(stats),
+            Msg::DoneLoading => Msg::DoneLoading,
+            Msg::Charts(msg) => match msg {
+                ChartsMsg::NewChart(spec, settings) => ChartsMsg::new_chart(spec, settings),
+                ChartsMsg::Chart {
+                    uid,
+                    msg: ChartMsg::Scope(scope),
+                } => ChartMsg::scope(uid, scope),
+                ChartsMsg::NewPoints {
+                    points,
+                    refresh_filters,
+                } => ChartsMsg::new_points(points, refresh_filters),
+                ChartsMsg::AddPoints(points) => ChartsMsg::add_points(points),
+                ChartsMsg::Paused(paused) => ChartsMsg::paused(paused),
+            },
+            Msg::Filters(msg) => match msg {
+                FiltersMsg::Add(filter) => FiltersMsg::add(filter),
+                FiltersMsg::AddSub(uid, sub) => FiltersMsg::add_sub(uid, sub),
+                FiltersMsg::Import(export) => FiltersMsg::import(export),
+                FiltersMsg::Revert {
+                    everything,
+                    filters,
+                    catch_all,
+                } => FiltersMsg::revert(everything, filters, catch_all),
+                FiltersMsg::Preview {
+                    id,
+                    filter,
+                    preview,
+                } => FiltersMsg::preview(id, filter, preview),
+                FiltersMsg::LabelCompletions { prefix, labels } => {
+                    FiltersMsg::label_completions(prefix, labels)
+                }
+                FiltersMsg::AddSaved(filter) => FiltersMsg::add_saved(filter),
+                FiltersMsg::Append(filters) => FiltersMsg::append(filters),
+            },
+            Msg::FilterStats(stats) => Msg::filter_stats(stats),
+            Msg::Explanation(explanation) => Msg::explanation(explanation),
+            Msg::SnapshotDiff(diff) => Msg::snapshot_diff(diff),
+            Msg::AllocTable(table) => Msg::alloc_table(table),
+            Msg::Notes { notes, conflict } => Msg::notes(notes, conflict),
+            Msg::Tags(tags) => Msg::tags(tags),
+            Msg::TraceInfo(info) => Msg::trace_info(info),
+            Msg::ReadOnly => Msg::ReadOnly,
+            Msg::Limits(limits) => Msg::limits(limits),
+            Msg::Session(status) => Msg::session(status),
+            // The constructor takes the rejected message, only its description is sent.
+            Msg::Rejected {
+                msg,
+                reason: Rejection::ReadOnlySession,
+            } => match Msg::read_only_reject(&to_server::Msg::set_notes("", 0)) {
+                Msg::Rejected { reason, .. } => Msg::Rejected { msg, reason },
+                _ => panic!("`read_only_reject` does not produce a rejection"),
+            },
+            Msg::Rejected {
+                msg,
+                reason: reason @ Rejection::FilterLimit(_),
+            }
+            | Msg::Rejected {
+                msg,
+                reason: reason @ Rejection::ChartLimit(_),
+            }
+            | Msg::Rejected {
+                msg,
+                reason: reason @ Rejection::Pruning,
+            } => Msg::rejected(msg, reason),
+        }
+    }
+
+    #[test]
+    fn every_variant_round_trips() {
+        // The examples of the wire format cover all the variants of all the messages.
+        let doc = wire::Doc::generate().unwrap();
+
+        for example in &doc.to_server.examples {
+            let bytes = of_hex(&example.bytes);
+            let msg = to_server::Msg::from_bytes(&bytes).unwrap();
+            let rebuilt = rebuild_to_server(msg).to_bytes().unwrap();
+            assert_eq!(rebuilt, bytes, "{}", example.msg);
+        }
+
+        for example in &doc.to_client.examples {
+            let bytes = of_hex(&example.bytes);
+            let msg = to_client::Msg::from_bytes(&bytes).unwrap();
+            let rebuilt = rebuild_to_client(msg).to_bytes().unwrap();
+            assert_eq!(rebuilt, bytes, "{}", example.msg);
+        }
+    }
+
+    /// Points of a chart with a single line, at regular intervals.
+    fn time_size_points(count: u64) -> point::ChartPoints {
+        let points: point::TimeSizePoints = (0..count)
+            .map(|idx| {
+                let mut vals = point::PointVal::empty();
+                vals.map
+                    .insert(uid::Line::Everything, point::Size::new(idx * 1000));
+                let time = time::SinceStart::from(time::Duration::from_millis(idx * 100));
+                point::Point::new(time, vals)
+            })
+            .collect();
+        let mut chart_points = point::ChartPoints::new();
+        chart_points.insert(uid::Chart::from(0), points.into());
+        chart_points
+    }
+
+    #[test]
+    fn bincode_is_compact() {
+        let msg = to_client::ChartsMsg::add_points(time_size_points(10_000));
+        let (bytes, json) = (msg.to_bytes().unwrap(), msg.as_json().unwrap());
+        assert!(
+            3 * bytes.len() <= json.len(),
+            "bincode: {} bytes, JSON: {} bytes",
+            bytes.len(),
+            json.len(),
+        );
+    }
+
+    #[test]
+    fn json_fallback() {
+        let msg = to_client::ChartsMsg::add_points(time_size_points(10));
+        let decoded = to_client::Msg::from_bytes(msg.as_json().unwrap().as_bytes()).unwrap();
+        assert_eq!(decoded.to_bytes().unwrap(), msg.to_bytes().unwrap());
+
+        let msg = to_server::ChartsMsg::toggle_pause();
+        let decoded = to_server::Msg::from_bytes(msg.as_json().unwrap().as_bytes()).unwrap();
+        assert_eq!(decoded.to_bytes().unwrap(), msg.to_bytes().unwrap());
+
+        assert!(to_server::Msg::from_bytes(b"not a message").is_err())
+    }
+}
@@ -0,0 +1,155 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Compression of the messages from the server to the client.
+//!
+//! Clients ask for compression during the websocket handshake, with the [`COMPRESS_PARAM`] query
+//! parameter of the websocket URL. The server then seals each of its messages in an [`Envelope`]:
+//! messages of at least [`MIN_SIZE`] bytes are deflated, the others go as they are. Clients that
+//! do not ask for compression receive plain messages, so older clients keep working.
+//!
+//! Deflate comes from [miniz_oxide], which is pure Rust and compiles to wasm. The `compress_bench`
+//! example measures what compression gains on a big point payload:
+//!
+//! ```bash
+//! cargo run --release -p charts --example compress_bench
+//! ```
+//!
+//! [`COMPRESS_PARAM`]: ../constant.COMPRESS_PARAM.html (The COMPRESS_PARAM constant)
+//! [`Envelope`]: struct.Envelope.html (The Envelope struct)
+//! [`MIN_SIZE`]: constant.MIN_SIZE.html (The MIN_SIZE constant)
+//! [miniz_oxide]: https://crates.io/crates/miniz_oxide (miniz_oxide on crates.io)
+
+prelude! {}
+
+/// Minimal size of the encoding of a message for it to be compressed, in bytes.
+///
+/// Compressing small messages costs more time than it saves.
+pub const MIN_SIZE: usize = 16 * 1024;
+
+/// Deflate level, between `0` (no compression) and `10` (best compression).
+pub const LEVEL: u8 = 6;
+
+/// An encoded message, compressed or not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// True if the data is deflated.
+    pub compressed: bool,
+    /// Encoded message, deflated if `compressed`.
+    pub data: Vec<u8>,
+}
+
+impl Envelope {
+    /// Seals an encoded message, deflates it if it is big enough.
+    ///
+    /// The message is not compressed if deflating does not make it smaller.
+    ///
+    /// ```rust
+    /// # use charts::msg::compress::{Envelope, MIN_SIZE};
+    /// let small = vec![7; 10];
+    /// assert!(!Envelope::seal(small.clone()).compressed);
+    /// let big = vec![7; MIN_SIZE];
+    /// let envelope = Envelope::seal(big.clone());
+    /// assert!(envelope.compressed);
+    /// assert!(envelope.data.len() < big.len());
+    /// assert_eq!(envelope.open().unwrap(), big);
+    /// ```
+    pub fn seal(bytes: Vec<u8>) -> Self {
+        if bytes.len() >= MIN_SIZE {
+            let data = miniz_oxide::deflate::compress_to_vec(&bytes, LEVEL);
+            if data.len() < bytes.len() {
+                return Self {
+                    compressed: true,
+                    data,
+                };
+            }
+        }
+        Self {
+            compressed: false,
+            data: bytes,
+        }
+    }
+
+    /// Yields the encoded message, inflated if needed.
+    pub fn open(self) -> Res<Vec<u8>> {
+        if self.compressed {
+            miniz_oxide::inflate::decompress_to_vec(&self.data)
+                .map_err(|e| format!("failed to inflate message: {:?}", e).into())
+        } else {
+            Ok(self.data)
+        }
+    }
+
+    /// Encodes the envelope as bytes, the way messages are encoded.
+    pub fn to_bytes(&self) -> Res<Vec<u8>> {
+        super::encode(self)
+    }
+
+    /// Decodes an envelope from bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Res<Self> {
+        super::decode(bytes).chain_err(|| "while decoding a message envelope")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sealed_messages_open() {
+        let small = msg::to_client::Msg::DoneLoading;
+        let sealed = small.to_envelope_bytes().unwrap();
+        let envelope = Envelope::from_bytes(&sealed).unwrap();
+        assert!(!envelope.compressed);
+        assert_eq!(envelope.data, small.to_bytes().unwrap());
+        assert_eq!(
+            msg::to_client::Msg::from_envelope_bytes(&sealed)
+                .unwrap()
+                .to_bytes()
+                .unwrap(),
+            small.to_bytes().unwrap(),
+        );
+
+        let big = msg::to_client::Msg::Alert {
+            msg: "memthol ".repeat(MIN_SIZE / 4),
+            fatal: false,
+        };
+        let plain = big.to_bytes().unwrap();
+        let sealed = big.to_envelope_bytes().unwrap();
+        assert!(Envelope::from_bytes(&sealed).unwrap().compressed);
+        assert!(sealed.len() * 10 < plain.len());
+        assert_eq!(
+            msg::to_client::Msg::from_envelope_bytes(&sealed)
+                .unwrap()
+                .to_bytes()
+                .unwrap(),
+            plain,
+        );
+    }
+
+    #[test]
+    fn corrupted_data_fails() {
+        let envelope = Envelope {
+            compressed: true,
+            data: vec![0xff; 64],
+        };
+        assert!(envelope.open().is_err());
+        assert!(msg::to_client::Msg::from_envelope_bytes(&[0xff; 3]).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Configuration of the server, needed by the client to connect to it.
+//!
+//! The server writes its configuration in the index page it serves, as the `content` of the
+//! element with id [`META_ID`]. The index page also gets a `<base>` element so that the assets
+//! load under the [base path] of the server. The client reads the configuration back to build the
+//! URL of the websocket of the server, see [`ServerConfig::ws_url`].
+//!
+//! [`META_ID`]: constant.META_ID.html (The META_ID constant)
+//! [base path]: struct.ServerConfig.html#method.base_path (The base_path method of ServerConfig)
+//! [`ServerConfig::ws_url`]: struct.ServerConfig.html#method.ws_url
+//! (The ws_url method of ServerConfig)
+
+prelude! {}
+
+/// Id of the element of the index page holding the configuration.
+pub const META_ID: &str = "memthol_config";
+
+/// Path of the websocket endpoint, under the base path.
+pub const WS_PATH: &str = "/ws";
+
+/// Configuration of the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// Port the UI is served at.
+    pub port: usize,
+    /// Port of the websocket server.
+    pub ws_port: usize,
+    /// Base path, see [`base_path`](#method.base_path).
+    base_path: String,
+}
+
+impl ServerConfig {
+    /// Constructor, fails if the base path is illegal, see [`normalize_base_path`].
+    ///
+    /// [`normalize_base_path`]: #method.normalize_base_path (The normalize_base_path function)
+    pub fn new(port: usize, ws_port: usize, base_path: &str) -> Res<Self> {
+        Ok(Self {
+            port,
+            ws_port,
+            base_path: Self::normalize_base_path(base_path)?,
+        })
+    }
+
+    /// Normalizes a base path, fails on characters that are not legal in a base path.
+    ///
+    /// Normalized base paths are either empty or start with a `/`, and do not end with a `/`.
+    ///
+    /// ```rust
+    /// # use charts::msg::config::ServerConfig;
+    /// assert_eq!(ServerConfig::normalize_base_path("").unwrap(), "");
+    /// assert_eq!(ServerConfig::normalize_base_path("/").unwrap(), "");
+    /// assert_eq!(ServerConfig::normalize_base_path("memthol").unwrap(), "/memthol");
+    /// assert_eq!(ServerConfig::normalize_base_path(" /a/b/ ").unwrap(), "/a/b");
+    /// assert!(ServerConfig::normalize_base_path("/a b").is_err());
+    /// assert!(ServerConfig::normalize_base_path("/a?b").is_err());
+    /// assert!(ServerConfig::normalize_base_path("/a\"b").is_err());
+    /// ```
+    pub fn normalize_base_path(path: &str) -> Res<String> {
+        let path = path.trim().trim_matches('/');
+        let illegal = |c: char| {
+            !(c.is_ascii_alphanumeric() || c == '/' || c == '-' || c == '_' || c == '.' || c == '~')
+        };
+        if let Some(c) = path.chars().find(|c| illegal(*c)) {
+            bail!("illegal character `{}` in base path `{}`", c, path)
+        }
+        if path.is_empty() {
+            Ok(String::new())
+        } else {
+            Ok(format!("/{}", path))
+        }
+    }
+
+    /// URL prefix the UI and the websocket are served under.
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    /// Path of the websocket endpoint.
+    pub fn ws_path(&self) -> String {
+        format!("{}{}", self.base_path, WS_PATH)
+    }
+    /// True if a websocket request path is legal.
+    ///
+    /// Legal paths are the [websocket path](#method.ws_path), with or without a trailing `/`, and
+    /// `/` for clients that do not know about base paths.
+    ///
+    /// ```rust
+    /// # use charts::msg::config::ServerConfig;
+    /// let config = ServerConfig::new(7878, 7879, "/memthol").unwrap();
+    /// assert!(config.is_ws_path("/memthol/ws"));
+    /// assert!(config.is_ws_path("/memthol/ws/"));
+    /// assert!(config.is_ws_path("/"));
+    /// assert!(!config.is_ws_path("/ws"));
+    /// assert!(!config.is_ws_path("/memthol"));
+    /// ```
+    pub fn is_ws_path(&self, path: &str) -> bool {
+        let ws_path = self.ws_path();
+        path == "/" || path == ws_path || path.strip_suffix('/') == Some(ws_path.as_str())
+    }
+
+    /// URL of the websocket of the server, from the location of the page of the client.
+    ///
+    /// - `secure`: true if the page was loaded over `https`;
+    /// - `host`: host of the page, with its port if any;
+    /// - `hostname`: host of the page, without its port;
+    /// - `page_port`: port of the page, if any.
+    ///
+    /// If the port of the page is the port of the server, the client talks to the server directly
+    /// and connects to the port of the websocket server. Otherwise, the client talks to a reverse
+    /// proxy which is expected to forward the [websocket path](#method.ws_path) to the websocket
+    /// server, including the upgrade to a secure websocket if the page is secure.
+    ///
+    /// ```rust
+    /// # use charts::msg::config::ServerConfig;
+    /// let config = ServerConfig::new(7878, 7879, "").unwrap();
+    /// assert_eq!(
+    ///     config.ws_url(false, "localhost:7878", "localhost", Some(7878)),
+    ///     "ws://localhost:7879/ws",
+    /// );
+    /// let config = ServerConfig::new(7878, 7879, "/memthol").unwrap();
+    /// assert_eq!(
+    ///     config.ws_url(true, "host.org", "host.org", None),
+    ///     "wss://host.org/memthol/ws",
+    /// );
+    /// assert_eq!(
+    ///     config.ws_url(false, "host.org:8080", "host.org", Some(8080)),
+    ///     "ws://host.org:8080/memthol/ws",
+    /// );
+    /// ```
+    pub fn ws_url(
+        &self,
+        secure: bool,
+        host: &str,
+        hostname: &str,
+        page_port: Option<usize>,
+    ) -> String {
+        if page_port == Some(self.port) {
+            format!("ws://{}:{}{}", hostname, self.ws_port, self.ws_path())
+        } else {
+            let scheme = if secure { "wss" } else { "ws" };
+            format!("{}://{}{}", scheme, host, self.ws_path())
+        }
+    }
+
+    /// Serializes the configuration, for the index page.
+    ///
+    /// Parameters are separated by `;`, which unlike `&` needs no escaping in HTML attributes.
+    ///
+    /// ```rust
+    /// # use charts::msg::config::ServerConfig;
+    /// let config = ServerConfig::new(7878, 7879, "/memthol").unwrap();
+    /// let meta = config.to_meta();
+    /// assert_eq!(meta, "port=7878;ws_port=7879;base_path=/memthol");
+    /// assert_eq!(ServerConfig::from_meta(&meta).unwrap(), config);
+    /// ```
+    pub fn to_meta(&self) -> String {
+        format!(
+            "port={};ws_port={};base_path={}",
+            self.port, self.ws_port, self.base_path
+        )
+    }
+    /// Deserializes a configuration, see [`to_meta`](#method.to_meta).
+    pub fn from_meta(meta: &str) -> Res<Self> {
+        use std::str::FromStr;
+        let (mut port, mut ws_port, mut base_path) = (None, None, "");
+        for param in meta.split(';') {
+            let mut kv = param.splitn(2, '=');
+            let (key, val) = (kv.next().unwrap_or(""), kv.next().unwrap_or(""));
+            let parse_port = |val: &str| {
+                usize::from_str(val)
+                    .map_err(|e| err::Error::from(format!("illegal {} `{}`: {}", key, val, e)))
+            };
+            match key {
+                "port" => port = Some(parse_port(val)?),
+                "ws_port" => ws_port = Some(parse_port(val)?),
+                "base_path" => base_path = val,
+                _ => bail!("unknown server configuration parameter `{}`", key),
+            }
+        }
+        Self::new(
+            port.ok_or("no port in the server configuration")?,
+            ws_port.ok_or("no websocket port in the server configuration")?,
+            base_path,
+        )
+    }
+
+    /// Inserts the configuration in the index page.
+    ///
+    /// The index page has a `BASE_PATH` placeholder for the base path in its `<base>` element, and
+    /// a `CONFIG` placeholder for the configuration.
+    ///
+    /// ```rust
+    /// # use charts::msg::config::ServerConfig;
+    /// let config = ServerConfig::new(7878, 7879, "/memthol").unwrap();
+    /// let page = config.fill_index(r#"<base href="{{BASE_PATH}}/"/><p>{{CONFIG}}</p>"#);
+    /// assert_eq!(
+    ///     page,
+    ///     r#"<base href="/memthol/"/><p>port=7878;ws_port=7879;base_path=/memthol</p>"#,
+    /// );
+    /// ```
+    pub fn fill_index(&self, index: &str) -> String {
+        index
+            .replace("{{BASE_PATH}}", &self.base_path)
+            .replace("{{CONFIG}}", &self.to_meta())
+    }
+}
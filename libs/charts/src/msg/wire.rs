@@ -0,0 +1,989 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Description of the wire format of the client/server messages.
+//!
+//! Messages are [bincode]-encoded, so their wire format is entirely determined by the shape of
+//! their types. This module extracts this shape by *tracing* the deserialization of the messages:
+//! a [`Tracer`] is a deserializer that records what the types ask for, and produces sample values
+//! along the way. Deserialization is traced repeatedly until all the variants of all the enums
+//! reachable from the messages have been explored. The sample values serve as example payloads.
+//!
+//! The resulting [`Doc`] is committed in the repository as [`DOC_PATH`], and the tests fail when
+//! it does not match the current message types. Run the `wire_doc` binary to regenerate it.
+//!
+//! [bincode]: https://crates.io/crates/bincode (bincode on crates.io)
+//! [`Tracer`]: struct.Tracer.html (The Tracer struct)
+//! [`Doc`]: struct.Doc.html (The Doc struct)
+//! [`DOC_PATH`]: constant.DOC_PATH.html (The DOC_PATH constant)
+
+prelude! {}
+
+use ::serde::de::{self, IntoDeserializer};
+
+/// Path of the wire format description, relative to the root of the repository.
+pub const DOC_PATH: &str = "rsc/docs/wire.json";
+
+/// Maximum nesting depth of the types traced.
+const MAX_DEPTH: usize = 64;
+
+/// Format of a value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Format {
+    /// Not traced yet.
+    Unknown,
+    /// Unit value.
+    Unit,
+    /// Boolean.
+    Bool,
+    /// Signed integer, 8 bits.
+    I8,
+    /// Signed integer, 16 bits.
+    I16,
+    /// Signed integer, 32 bits.
+    I32,
+    /// Signed integer, 64 bits.
+    I64,
+    /// Signed integer, 128 bits.
+    I128,
+    /// Unsigned integer, 8 bits.
+    U8,
+    /// Unsigned integer, 16 bits.
+    U16,
+    /// Unsigned integer, 32 bits.
+    U32,
+    /// Unsigned integer, 64 bits.
+    U64,
+    /// Unsigned integer, 128 bits.
+    U128,
+    /// Float, 32 bits.
+    F32,
+    /// Float, 64 bits.
+    F64,
+    /// Character.
+    Char,
+    /// String.
+    Str,
+    /// Byte sequence.
+    Bytes,
+    /// Optional value.
+    Option(Box<Format>),
+    /// Sequence of values.
+    Seq(Box<Format>),
+    /// Map.
+    Map {
+        /// Format of the keys.
+        key: Box<Format>,
+        /// Format of the values.
+        value: Box<Format>,
+    },
+    /// Tuple.
+    Tuple(Vec<Format>),
+    /// Named type, described in the [`Registry`].
+    ///
+    /// [`Registry`]: type.Registry.html (The Registry type)
+    TypeName(String),
+}
+
+impl Format {
+    /// Applies an action to the names of the types this format mentions.
+    fn type_names_do(&self, action: &mut impl FnMut(&str)) {
+        match self {
+            Self::TypeName(name) => action(name),
+            Self::Option(format) | Self::Seq(format) => format.type_names_do(action),
+            Self::Map { key, value } => {
+                key.type_names_do(action);
+                value.type_names_do(action)
+            }
+            Self::Tuple(formats) => {
+                for format in formats {
+                    format.type_names_do(action)
+                }
+            }
+            Self::Unknown
+            | Self::Unit
+            | Self::Bool
+            | Self::I8
+            | Self::I16
+            | Self::I32
+            | Self::I64
+            | Self::I128
+            | Self::U8
+            | Self::U16
+            | Self::U32
+            | Self::U64
+            | Self::U128
+            | Self::F32
+            | Self::F64
+            | Self::Char
+            | Self::Str
+            | Self::Bytes => (),
+        }
+    }
+}
+
+/// A named format, for struct fields and enum variants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Named<T> {
+    /// Name.
+    pub name: String,
+    /// Format.
+    pub format: T,
+}
+
+/// Format of an enum variant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VariantFormat {
+    /// Not traced yet.
+    Unknown,
+    /// Unit variant.
+    Unit,
+    /// Variant with a single unnamed field.
+    NewType(Box<Format>),
+    /// Variant with unnamed fields.
+    Tuple(Vec<Format>),
+    /// Variant with named fields.
+    Struct(Vec<Named<Format>>),
+}
+
+impl VariantFormat {
+    /// Applies an action to the names of the types this format mentions.
+    fn type_names_do(&self, action: &mut impl FnMut(&str)) {
+        match self {
+            Self::Unknown | Self::Unit => (),
+            Self::NewType(format) => format.type_names_do(action),
+            Self::Tuple(formats) => {
+                for format in formats {
+                    format.type_names_do(action)
+                }
+            }
+            Self::Struct(fields) => {
+                for field in fields {
+                    field.format.type_names_do(action)
+                }
+            }
+        }
+    }
+}
+
+/// Format of a named type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContainerFormat {
+    /// Struct with no field.
+    UnitStruct,
+    /// Struct with a single unnamed field.
+    NewTypeStruct(Box<Format>),
+    /// Struct with unnamed fields.
+    TupleStruct(Vec<Format>),
+    /// Struct with named fields.
+    Struct(Vec<Named<Format>>),
+    /// Enum, the variants are indexed by their tag on the wire.
+    Enum(BTMap<u32, Named<VariantFormat>>),
+}
+
+impl ContainerFormat {
+    /// Applies an action to the names of the types this format mentions.
+    fn type_names_do(&self, action: &mut impl FnMut(&str)) {
+        match self {
+            Self::UnitStruct => (),
+            Self::NewTypeStruct(format) => format.type_names_do(action),
+            Self::TupleStruct(formats) => {
+                for format in formats {
+                    format.type_names_do(action)
+                }
+            }
+            Self::Struct(fields) => {
+                for field in fields {
+                    field.format.type_names_do(action)
+                }
+            }
+            Self::Enum(variants) => {
+                for variant in variants.values() {
+                    variant.format.type_names_do(action)
+                }
+            }
+        }
+    }
+
+    /// Merges the variants explored by another format in this one.
+    ///
+    /// Fails if the formats are not compatible, *i.e.* if they are not equal, ignoring the
+    /// variants of enums that only one of them explored.
+    fn merge(&mut self, name: &str, other: Self) -> Result<bool, TraceError> {
+        match (self, other) {
+            (Self::Enum(variants), Self::Enum(others)) => {
+                let mut new = false;
+                for (idx, other) in others {
+                    match variants.get(&idx) {
+                        Some(variant) if variant != &other => {
+                            return Err(TraceError::conflict(name));
+                        }
+                        Some(_) => (),
+                        None => {
+                            new = true;
+                            let prev = variants.insert(idx, other);
+                            debug_assert!(prev.is_none())
+                        }
+                    }
+                }
+                Ok(new)
+            }
+            (slf, other) if *slf == other => Ok(false),
+            _ => Err(TraceError::conflict(name)),
+        }
+    }
+}
+
+/// Maps type names to their format.
+pub type Registry = BTMap<String, ContainerFormat>;
+
+/// Error raised while tracing.
+#[derive(Debug, Clone)]
+pub struct TraceError {
+    /// Error message.
+    msg: String,
+}
+impl TraceError {
+    /// Conflicting-formats error.
+    fn conflict(name: &str) -> Self {
+        Self {
+            msg: format!(
+                "type name `{}` has conflicting formats, \
+                two types with the same name cannot appear in the same message",
+                name
+            ),
+        }
+    }
+}
+impl fmt::Display for TraceError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.msg.fmt(fmt)
+    }
+}
+impl std::error::Error for TraceError {}
+impl de::Error for TraceError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+/// Tracing state.
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// Formats traced by the previous passes.
+    known: Registry,
+    /// Formats traced by the current pass.
+    pass: Registry,
+    /// Number of variants of the enums encountered.
+    variant_counts: BTMap<String, usize>,
+    /// Current nesting depth.
+    depth: usize,
+    /// Names of the structs being traced, innermost last.
+    structs: Vec<&'static str>,
+    /// Number of recursive occurrences of structs being traced, see [`Tracer`].
+    ///
+    /// [`Tracer`]: struct.Tracer.html (The Tracer struct)
+    recursive: usize,
+}
+
+impl State {
+    /// Registers a format for the current pass, returns the name it is registered with.
+    ///
+    /// Different instantiations of a generic struct have the same name but different formats,
+    /// the first instantiation is registered as `Name`, the second one as `Name#2`, *etc.*
+    /// Generic enums are not supported.
+    ///
+    /// Does not register anything in a recursive occurrence of a struct, the formats traced there
+    /// are partial.
+    fn register(&mut self, name: &str, format: ContainerFormat) -> Result<String, TraceError> {
+        if self.recursive > 0 {
+            return Ok(name.into());
+        }
+        if let ContainerFormat::Enum(_) = format {
+            if let Some(prev) = self.pass.get_mut(name) {
+                prev.merge(name, format)?;
+            } else {
+                self.pass.insert(name.into(), format);
+            }
+            return Ok(name.into());
+        }
+
+        let mut cnt = 1;
+        loop {
+            let candidate = if cnt == 1 {
+                name.to_string()
+            } else {
+                format!("{}#{}", name, cnt)
+            };
+            cnt += 1;
+            match self
+                .pass
+                .get(&candidate)
+                .or_else(|| self.known.get(&candidate))
+            {
+                Some(prev) if prev != &format => continue,
+                _ => {
+                    self.pass.insert(candidate.clone(), format);
+                    return Ok(candidate);
+                }
+            }
+        }
+    }
+
+    /// Ends a pass, returns true if the pass traced anything new.
+    fn end_pass(&mut self) -> Result<bool, TraceError> {
+        let mut new = false;
+        for (name, format) in std::mem::replace(&mut self.pass, Registry::new()) {
+            if let Some(known) = self.known.get_mut(&name) {
+                new = known.merge(&name, format)? || new
+            } else {
+                new = true;
+                self.known.insert(name, format);
+            }
+        }
+        Ok(new)
+    }
+
+    /// True if all the variants of the enums reachable from a type were explored.
+    fn is_complete(&self, name: &str, visiting: &mut BTSet<String>) -> bool {
+        if !visiting.insert(name.into()) {
+            return true;
+        }
+        let format = match self.known.get(name) {
+            Some(format) => format,
+            None => return false,
+        };
+        if let ContainerFormat::Enum(variants) = format {
+            if Some(&variants.len()) != self.variant_counts.get(name) {
+                return false;
+            }
+        }
+        let mut complete = true;
+        format.type_names_do(&mut |name| complete = complete && self.is_complete(name, visiting));
+        complete
+    }
+
+    /// Chooses the variant to explore for an enum.
+    ///
+    /// Chooses the first variant never explored, if any, and the first variant leading to an
+    /// enum with unexplored variants otherwise.
+    fn choose_variant(&self, name: &str, count: usize) -> u32 {
+        let variants = match self.known.get(name) {
+            Some(ContainerFormat::Enum(variants)) => variants,
+            _ => return 0,
+        };
+        for idx in 0..(count as u32) {
+            if !variants.contains_key(&idx) {
+                return idx;
+            }
+        }
+        for (idx, variant) in variants {
+            let mut complete = true;
+            let mut visiting = BTSet::new();
+            visiting.insert(name.to_string());
+            variant.format.type_names_do(&mut |name| {
+                complete = complete && self.is_complete(name, &mut visiting)
+            });
+            if !complete {
+                return *idx;
+            }
+        }
+        0
+    }
+}
+
+/// A deserializer recording the format of the values it produces.
+///
+/// Produces sample values: zeros, empty strings, sequences and maps of length one, `Some` values.
+/// The only exception is strings which are `"0"` (UIDs are strings on the wire), or a date when
+/// the type expects a date.
+///
+/// A struct that appears inside itself, like a group of filters containing groups, is only traced
+/// once. Its recursive occurrences have empty sequences and maps, and `None` values, so that
+/// tracing terminates. Generic recursive structs are not supported.
+pub struct Tracer<'a> {
+    /// Tracing state.
+    state: &'a mut State,
+    /// Format of the value being produced.
+    format: &'a mut Format,
+}
+
+impl<'a> Tracer<'a> {
+    /// Traces a value in a sub-format.
+    fn trace<'de, T>(
+        state: &mut State,
+        format: &mut Format,
+        seed: T,
+    ) -> Result<T::Value, TraceError>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if state.depth >= MAX_DEPTH {
+            return Err(de::Error::custom(
+                "maximum depth reached while tracing, only structs can be recursive",
+            ));
+        }
+        state.depth += 1;
+        let res = seed.deserialize(Tracer { state, format });
+        state.depth -= 1;
+        res
+    }
+
+    /// Traces some values in sub-formats.
+    fn trace_seq<'de, V>(
+        state: &mut State,
+        formats: &mut [Format],
+        visitor: V,
+    ) -> Result<V::Value, TraceError>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_seq(SeqTracer {
+            state,
+            formats: formats.iter_mut(),
+        })
+    }
+}
+
+macro_rules! trace_primitives {
+    ($($fun:ident => $format:ident, $visit:ident($($val:expr)?)),* $(,)?) => {$(
+        fn $fun<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            *self.format = Format::$format;
+            visitor.$visit($($val)?)
+        }
+    )*};
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Tracer<'a> {
+    type Error = TraceError;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    trace_primitives! {
+        deserialize_bool => Bool, visit_bool(false),
+        deserialize_i8 => I8, visit_i8(0),
+        deserialize_i16 => I16, visit_i16(0),
+        deserialize_i32 => I32, visit_i32(0),
+        deserialize_i64 => I64, visit_i64(0),
+        deserialize_i128 => I128, visit_i128(0),
+        deserialize_u8 => U8, visit_u8(0),
+        deserialize_u16 => U16, visit_u16(0),
+        deserialize_u32 => U32, visit_u32(0),
+        deserialize_u64 => U64, visit_u64(0),
+        deserialize_u128 => U128, visit_u128(0),
+        deserialize_f32 => F32, visit_f32(0.),
+        deserialize_f64 => F64, visit_f64(0.),
+        deserialize_char => Char, visit_char('0'),
+        deserialize_bytes => Bytes, visit_bytes(&[]),
+        deserialize_byte_buf => Bytes, visit_byte_buf(vec![]),
+        deserialize_unit => Unit, visit_unit(),
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Str;
+        let expected = format!("{}", &visitor as &dyn de::Expected);
+        if expected.contains("date") {
+            visitor.visit_str("1970-01-01T00:00:00Z")
+        } else {
+            visitor.visit_str("0")
+        }
+    }
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.state.recursive > 0 {
+            *self.format = Format::Option(Box::new(Format::Unknown));
+            return visitor.visit_none();
+        }
+        let mut inner = Format::Unknown;
+        let res = visitor.visit_some(Tracer {
+            state: &mut *self.state,
+            format: &mut inner,
+        });
+        *self.format = Format::Option(Box::new(inner));
+        res
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let name = self.state.register(name, ContainerFormat::UnitStruct)?;
+        *self.format = Format::TypeName(name);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut inner = Format::Unknown;
+        let res = visitor.visit_newtype_struct(Tracer {
+            state: &mut *self.state,
+            format: &mut inner,
+        })?;
+        let name = self
+            .state
+            .register(name, ContainerFormat::NewTypeStruct(Box::new(inner)))?;
+        *self.format = Format::TypeName(name);
+        Ok(res)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut inner = [Format::Unknown];
+        let len = if self.state.recursive > 0 { 0 } else { 1 };
+        let res = Self::trace_seq(self.state, &mut inner[..len], visitor)?;
+        let [inner] = inner;
+        *self.format = Format::Seq(Box::new(inner));
+        Ok(res)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut formats = vec![Format::Unknown; len];
+        let res = Self::trace_seq(self.state, &mut formats, visitor)?;
+        *self.format = Format::Tuple(formats);
+        Ok(res)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut formats = vec![Format::Unknown; len];
+        let res = Self::trace_seq(self.state, &mut formats, visitor)?;
+        let name = self
+            .state
+            .register(name, ContainerFormat::TupleStruct(formats))?;
+        *self.format = Format::TypeName(name);
+        Ok(res)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut formats = [Format::Unknown, Format::Unknown];
+        let len = if self.state.recursive > 0 { 0 } else { 2 };
+        let res = visitor.visit_map(MapTracer {
+            state: &mut *self.state,
+            formats: formats[..len].iter_mut(),
+        })?;
+        let [key, value] = formats;
+        *self.format = Format::Map {
+            key: Box::new(key),
+            value: Box::new(value),
+        };
+        Ok(res)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let recursive = self.state.structs.contains(&name);
+        if recursive {
+            self.state.recursive += 1
+        }
+        self.state.structs.push(name);
+        let mut formats = vec![Format::Unknown; fields.len()];
+        let res = Self::trace_seq(self.state, &mut formats, visitor);
+        self.state.structs.pop();
+        if recursive {
+            self.state.recursive -= 1;
+            *self.format = Format::TypeName(name.into());
+            return res;
+        }
+        let res = res?;
+        let fields = fields
+            .iter()
+            .zip(formats.into_iter())
+            .map(|(name, format)| Named {
+                name: name.to_string(),
+                format,
+            })
+            .collect();
+        let name = self.state.register(name, ContainerFormat::Struct(fields))?;
+        *self.format = Format::TypeName(name);
+        Ok(res)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.state
+            .variant_counts
+            .insert(name.into(), variants.len());
+        let index = self.state.choose_variant(name, variants.len());
+        let mut format = VariantFormat::Unknown;
+        let res = visitor.visit_enum(EnumTracer {
+            state: &mut *self.state,
+            format: &mut format,
+            index,
+        })?;
+        let variant = Named {
+            name: variants[index as usize].into(),
+            format,
+        };
+        let mut map = BTMap::new();
+        map.insert(index, variant);
+        let name = self.state.register(name, ContainerFormat::Enum(map))?;
+        *self.format = Format::TypeName(name);
+        Ok(res)
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(de::Error::custom(
+            "messages cannot use self-describing deserialization, bincode does not support it",
+        ))
+    }
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+}
+
+/// Traces the elements of a sequence, one per format.
+struct SeqTracer<'a, 'f> {
+    /// Tracing state.
+    state: &'a mut State,
+    /// Formats of the elements not produced yet.
+    formats: std::slice::IterMut<'f, Format>,
+}
+impl<'de, 'a, 'f> de::SeqAccess<'de> for SeqTracer<'a, 'f> {
+    type Error = TraceError;
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.formats.next() {
+            Some(format) => Tracer::trace(self.state, format, seed).map(Some),
+            None => Ok(None),
+        }
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.formats.len())
+    }
+}
+
+/// Traces a map with one entry.
+struct MapTracer<'a, 'f> {
+    /// Tracing state.
+    state: &'a mut State,
+    /// Formats of the key and the value, if not produced yet.
+    formats: std::slice::IterMut<'f, Format>,
+}
+impl<'de, 'a, 'f> de::MapAccess<'de> for MapTracer<'a, 'f> {
+    type Error = TraceError;
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.formats.next() {
+            Some(format) => Tracer::trace(self.state, format, seed).map(Some),
+            None => Ok(None),
+        }
+    }
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        match self.formats.next() {
+            Some(format) => Tracer::trace(self.state, format, seed),
+            None => Err(de::Error::custom(
+                "map value requested after the last entry",
+            )),
+        }
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.formats.len() / 2)
+    }
+}
+
+/// Traces an enum variant.
+struct EnumTracer<'a> {
+    /// Tracing state.
+    state: &'a mut State,
+    /// Format of the variant.
+    format: &'a mut VariantFormat,
+    /// Index of the variant to produce.
+    index: u32,
+}
+impl<'de, 'a> de::EnumAccess<'de> for EnumTracer<'a> {
+    type Error = TraceError;
+    type Variant = Self;
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.index.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+impl<'de, 'a> de::VariantAccess<'de> for EnumTracer<'a> {
+    type Error = TraceError;
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        *self.format = VariantFormat::Unit;
+        Ok(())
+    }
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        let mut inner = Format::Unknown;
+        let res = Tracer::trace(self.state, &mut inner, seed)?;
+        *self.format = VariantFormat::NewType(Box::new(inner));
+        Ok(res)
+    }
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut formats = vec![Format::Unknown; len];
+        let res = Tracer::trace_seq(self.state, &mut formats, visitor)?;
+        *self.format = VariantFormat::Tuple(formats);
+        Ok(res)
+    }
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut formats = vec![Format::Unknown; fields.len()];
+        let res = Tracer::trace_seq(self.state, &mut formats, visitor)?;
+        *self.format = VariantFormat::Struct(
+            fields
+                .iter()
+                .zip(formats.into_iter())
+                .map(|(name, format)| Named {
+                    name: name.to_string(),
+                    format,
+                })
+                .collect(),
+        );
+        Ok(res)
+    }
+}
+
+/// Wire format of the messages going in one direction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Direction {
+    /// Format of the messages, a type name of the registry.
+    pub root: Format,
+    /// Formats of all the named types.
+    pub types: Registry,
+    /// Example payloads.
+    pub examples: Vec<Example>,
+}
+
+impl Direction {
+    /// Traces the deserialization of some messages.
+    fn trace<T>() -> Res<Self>
+    where
+        T: de::DeserializeOwned + ::serde::Serialize + fmt::Display,
+    {
+        let mut state = State::default();
+        let mut examples = vec![];
+        loop {
+            let mut root = Format::Unknown;
+            let sample = T::deserialize(Tracer {
+                state: &mut state,
+                format: &mut root,
+            })
+            .map_err(|e| format!("while tracing message types: {}", e))?;
+            examples.push(Example::new(&sample)?);
+
+            let new = state.end_pass().map_err(|e| e.to_string())?;
+            let complete = match &root {
+                Format::TypeName(name) => state.is_complete(name, &mut BTSet::new()),
+                _ => true,
+            };
+            if complete {
+                return Ok(Self {
+                    root,
+                    types: state.known,
+                    examples,
+                });
+            } else if !new {
+                bail!("failed to explore all the variants of the message types")
+            }
+        }
+    }
+}
+
+/// An example payload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Example {
+    /// Description of the message.
+    pub msg: String,
+    /// Bincode encoding of the message, in hexadecimal.
+    pub bytes: String,
+}
+
+impl Example {
+    /// Constructor.
+    fn new<T>(msg: &T) -> Res<Self>
+    where
+        T: ::serde::Serialize + fmt::Display,
+    {
+        let bytes = super::encode(msg)?;
+        let mut hex = String::with_capacity(bytes.len() * 2);
+        for byte in bytes {
+            hex.push_str(&format!("{:02x}", byte))
+        }
+        Ok(Self {
+            msg: msg.to_string(),
+            bytes: hex,
+        })
+    }
+}
+
+/// Description of the wire format of the client/server messages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Doc {
+    /// Encoding of the messages.
+    pub encoding: String,
+    /// Messages from the client to the server.
+    pub to_server: Direction,
+    /// Messages from the server to the client.
+    pub to_client: Direction,
+}
+
+impl Doc {
+    /// Generates the description of the current message types.
+    pub fn generate() -> Res<Self> {
+        Ok(Self {
+            encoding: "bincode (varint options): integers are little-endian and variable-length, \
+                sequences, maps and strings are prefixed by their length as a variable-length u64, \
+                enum variants by their index as a variable-length u32, options by a 0 (None) or 1 \
+                (Some) byte; a variable-length integer below 251 is a single byte, otherwise a tag \
+                byte (251 for u16, 252 for u32, 253 for u64, 254 for u128) followed by the \
+                integer, signed integers are zigzag encoded first"
+                .into(),
+            to_server: Direction::trace::<super::to_server::Msg>()
+                .chain_err(|| "while generating the client to server wire format")?,
+            to_client: Direction::trace::<super::to_client::Msg>()
+                .chain_err(|| "while generating the server to client wire format")?,
+        })
+    }
+
+    /// Pretty JSON representation.
+    pub fn to_json(&self) -> Res<String> {
+        let mut json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("while encoding the wire format description: {}", e))?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn committed_doc_is_up_to_date() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../..")
+            .join(DOC_PATH);
+        let committed = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            panic!("failed to read `{}`: {}", path.display(), e);
+        });
+        let doc = Doc::generate().unwrap().to_json().unwrap();
+        assert!(
+            committed == doc,
+            "the message types changed, the wire format description is outdated: \
+            run `cargo run -p charts --bin wire_doc -- {}`",
+            DOC_PATH,
+        );
+    }
+
+    #[test]
+    fn examples_decode() {
+        let doc = Doc::generate().unwrap();
+        for example in &doc.to_server.examples {
+            let bytes: Vec<u8> = (0..example.bytes.len())
+                .step_by(2)
+                .map(|idx| u8::from_str_radix(&example.bytes[idx..idx + 2], 16).unwrap())
+                .collect();
+            let msg = super::super::to_server::Msg::from_bytes(&bytes).unwrap();
+            assert_eq!(msg.to_string(), example.msg);
+        }
+    }
+}
@@ -0,0 +1,352 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Free-form notes shared by all the clients of a server.
+//!
+//! Notes are plain text with a few markdown-like conventions, see [`lines`]:
+//!
+//! - lines starting with `# ` are titles,
+//! - lines starting with `- ` are list items,
+//! - `[chart:Name]` and `[filter:Name]` are links to a chart or a filter.
+//!
+//! Links can be *anchored* to the UID of what they point to, as in `[chart#3:Name]`, so that they
+//! still resolve after a rename. Clients anchor links when saving, see [`anchor`].
+//!
+//! The server stores a single version of the notes. Updates are last-writer-wins: an update based
+//! on an outdated version still overwrites the notes, but the server reports the conflict to the
+//! writer.
+//!
+//! [`lines`]: fn.lines.html (The lines function)
+//! [`anchor`]: fn.anchor.html (The anchor function)
+
+prelude! {}
+
+/// Some notes and their version.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notes {
+    /// Content of the notes.
+    pub text: String,
+    /// Version of the notes, incremented by each update.
+    pub version: u64,
+}
+
+/// Kind of element a link points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkKind {
+    /// Link to a chart.
+    Chart,
+    /// Link to a filter.
+    Filter,
+}
+impl LinkKind {
+    /// Keyword of the kind in the notes.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Self::Chart => "chart",
+            Self::Filter => "filter",
+        }
+    }
+
+    /// Parses a keyword.
+    fn of_keyword(s: &str) -> Option<Self> {
+        match s {
+            "chart" => Some(Self::Chart),
+            "filter" => Some(Self::Filter),
+            _ => None,
+        }
+    }
+}
+
+/// A link to a chart or a filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Kind of element the link points to.
+    pub kind: LinkKind,
+    /// UID of the element, if the link is anchored.
+    pub uid: Option<usize>,
+    /// Name of the element when the link was written.
+    pub name: String,
+}
+impl Link {
+    /// Resolves a link given the UIDs and names of the elements of the right kind.
+    ///
+    /// Resolves by UID if the link is anchored and the UID exists, and by name otherwise.
+    ///
+    /// ```rust
+    /// # use charts::notes::*;
+    /// let elms = vec![(0, "everything"), (3, "big allocs")];
+    ///
+    /// let link = Link { kind: LinkKind::Filter, uid: None, name: "big allocs".into() };
+    /// assert_eq!(link.resolve(elms.iter().cloned()), Some(3));
+    ///
+    /// let link = Link { kind: LinkKind::Filter, uid: Some(3), name: "old name".into() };
+    /// assert_eq!(link.resolve(elms.iter().cloned()), Some(3));
+    ///
+    /// let link = Link { kind: LinkKind::Filter, uid: Some(7), name: "everything".into() };
+    /// assert_eq!(link.resolve(elms.iter().cloned()), Some(0));
+    ///
+    /// let link = Link { kind: LinkKind::Filter, uid: Some(7), name: "old name".into() };
+    /// assert_eq!(link.resolve(elms.iter().cloned()), None);
+    /// ```
+    pub fn resolve<'a>(
+        &self,
+        elms: impl Iterator<Item = (usize, &'a str)> + Clone,
+    ) -> Option<usize> {
+        if let Some(uid) = self.uid {
+            if elms.clone().any(|(elm, _)| elm == uid) {
+                return Some(uid);
+            }
+        }
+        elms.into_iter()
+            .find(|(_, name)| *name == self.name)
+            .map(|(uid, _)| uid)
+    }
+
+    /// Parses a link, without the surrounding brackets.
+    fn parse(s: &str) -> Option<Self> {
+        let mut kind_name = s.splitn(2, ':');
+        let (kind, name) = (kind_name.next()?, kind_name.next()?);
+        let mut kind_uid = kind.splitn(2, '#');
+        let kind = LinkKind::of_keyword(kind_uid.next()?)?;
+        let uid = match kind_uid.next() {
+            Some(uid) => Some(usize::from_str(uid).ok()?),
+            None => None,
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            kind,
+            uid,
+            name: name.into(),
+        })
+    }
+}
+
+base::implement! {
+    impl Link {
+        Display {
+            |&self, fmt| {
+                write!(fmt, "[{}", self.kind.keyword())?;
+                if let Some(uid) = self.uid {
+                    write!(fmt, "#{}", uid)?
+                }
+                write!(fmt, ":{}]", self.name)
+            }
+        }
+    }
+}
+
+/// A piece of a line of the notes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chunk<'a> {
+    /// Some text.
+    Text(&'a str),
+    /// A link.
+    Link(Link),
+}
+
+/// Splits a line in text and links.
+///
+/// ```rust
+/// # use charts::notes::*;
+/// let chunks = chunks("see [chart#2:Total] and [filter:big], [not:a link]");
+/// assert_eq!(chunks.len(), 5);
+/// assert_eq!(chunks[0], Chunk::Text("see "));
+/// assert_eq!(
+///     chunks[1],
+///     Chunk::Link(Link { kind: LinkKind::Chart, uid: Some(2), name: "Total".into() }),
+/// );
+/// assert_eq!(chunks[2], Chunk::Text(" and "));
+/// assert_eq!(
+///     chunks[3],
+///     Chunk::Link(Link { kind: LinkKind::Filter, uid: None, name: "big".into() }),
+/// );
+/// assert_eq!(chunks[4], Chunk::Text(", [not:a link]"));
+/// ```
+pub fn chunks(line: &str) -> Vec<Chunk<'_>> {
+    let mut res = vec![];
+    // Start of the text not pushed yet.
+    let mut text_start = 0;
+    // Where to look for the next link.
+    let mut search_start = 0;
+
+    while let Some(open) = line[search_start..].find('[').map(|idx| search_start + idx) {
+        let close = match line[open..].find(']') {
+            Some(idx) => open + idx,
+            None => break,
+        };
+        match Link::parse(&line[open + 1..close]) {
+            Some(link) => {
+                if text_start < open {
+                    res.push(Chunk::Text(&line[text_start..open]))
+                }
+                res.push(Chunk::Link(link));
+                text_start = close + 1;
+                search_start = close + 1;
+            }
+            None => search_start = open + 1,
+        }
+    }
+
+    if text_start < line.len() {
+        res.push(Chunk::Text(&line[text_start..]))
+    }
+    res
+}
+
+/// Kind of line in the notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// A title, starts with `# `.
+    Title,
+    /// A list item, starts with `- `.
+    Item,
+    /// Normal text.
+    Text,
+}
+
+/// Splits some notes in lines, with their kind and the line without its prefix.
+///
+/// ```rust
+/// # use charts::notes::*;
+/// let lines = lines("# Leak\n- starts at 2s\nsee [chart:Total]");
+/// assert_eq!(
+///     lines,
+///     vec![
+///         (LineKind::Title, "Leak"),
+///         (LineKind::Item, "starts at 2s"),
+///         (LineKind::Text, "see [chart:Total]"),
+///     ],
+/// );
+/// ```
+pub fn lines(text: &str) -> Vec<(LineKind, &str)> {
+    text.lines()
+        .map(|line| {
+            if let Some(title) = line.strip_prefix("# ") {
+                (LineKind::Title, title)
+            } else if let Some(item) = line.strip_prefix("- ") {
+                (LineKind::Item, item)
+            } else {
+                (LineKind::Text, line)
+            }
+        })
+        .collect()
+}
+
+/// Anchors the links of some notes.
+///
+/// `resolve` yields the UID and the current name of what a link points to. Links it resolves are
+/// rewritten with this UID and name, the others are left as they are.
+///
+/// ```rust
+/// # use charts::notes::*;
+/// let text = "see [chart:Total]\n- [filter#1:old] [filter:nope]";
+/// let anchored = anchor(text, |link| match (link.kind, link.uid) {
+///     (LinkKind::Chart, _) if link.name == "Total" => Some((2, "Total".into())),
+///     (LinkKind::Filter, Some(1)) => Some((1, "new".into())),
+///     _ => None,
+/// });
+/// assert_eq!(anchored, "see [chart#2:Total]\n- [filter#1:new] [filter:nope]");
+/// ```
+pub fn anchor(text: &str, resolve: impl Fn(&Link) -> Option<(usize, String)>) -> String {
+    let mut res = String::with_capacity(text.len());
+    for (idx, line) in text.split('\n').enumerate() {
+        if idx > 0 {
+            res.push('\n')
+        }
+        for chunk in chunks(line) {
+            match chunk {
+                Chunk::Text(text) => res.push_str(text),
+                Chunk::Link(mut link) => {
+                    if let Some((uid, name)) = resolve(&link) {
+                        link.uid = Some(uid);
+                        link.name = name
+                    }
+                    res.push_str(&link.to_string())
+                }
+            }
+        }
+    }
+    res
+}
+
+#[cfg(any(test, feature = "server"))]
+lazy_static! {
+    /// Notes of the server.
+    static ref NOTES: sync::RwLock<Notes> = sync::RwLock::new(Notes::default());
+}
+
+/// The notes of the server.
+#[cfg(any(test, feature = "server"))]
+pub fn get() -> Res<Notes> {
+    NOTES
+        .read()
+        .map(|notes| notes.clone())
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while reading the notes")
+}
+
+/// Version of the notes of the server.
+#[cfg(any(test, feature = "server"))]
+pub fn version() -> Res<u64> {
+    NOTES
+        .read()
+        .map(|notes| notes.version)
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while reading the notes")
+}
+
+/// Overwrites the notes of the server.
+///
+/// `version` is the version of the notes the update is based on. Returns the new notes, and a flag
+/// indicating whether the update overwrote a version more recent than `version`.
+#[cfg(any(test, feature = "server"))]
+pub fn set(text: String, version: u64) -> Res<(Notes, bool)> {
+    let mut notes = NOTES
+        .write()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while writing the notes")?;
+    let conflict = notes.version != version;
+    notes.text = text;
+    notes.version += 1;
+    Ok((notes.clone(), conflict))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn last_writer_wins() {
+        let base = get().unwrap().version;
+
+        let (notes, conflict) = set("first".into(), base).unwrap();
+        assert!(!conflict);
+        assert_eq!(notes.version, base + 1);
+
+        // Second writer still based on `base`.
+        let (notes, conflict) = set("second".into(), base).unwrap();
+        assert!(conflict);
+        assert_eq!(notes.version, base + 2);
+        assert_eq!(get().unwrap().text, "second");
+        assert_eq!(version().unwrap(), base + 2);
+    }
+}
@@ -0,0 +1,174 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Line patterns, to tell lines apart without relying on colors.
+//!
+//! In accessible mode, the client draws each line with a [`Pattern`]: a dash pattern and a marker
+//! shape, on top of the line's color. The pattern of a line only depends on its UID, so that the
+//! line looks the same in all the charts and in the legends. Patterns do not touch the colors of
+//! the filters, turning accessible mode off restores the usual look.
+//!
+//! [`Pattern`]: struct.Pattern.html (The Pattern struct)
+
+prelude! {}
+
+/// Dash pattern of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dash {
+    /// Solid line.
+    Solid,
+    /// Regular dashes.
+    Dashed,
+    /// Short dashes.
+    Short,
+    /// Long dashes.
+    Long,
+    /// Dots.
+    Dotted,
+}
+impl Dash {
+    /// All the dash patterns.
+    pub const ALL: [Self; 5] = [
+        Self::Solid,
+        Self::Dashed,
+        Self::Short,
+        Self::Long,
+        Self::Dotted,
+    ];
+
+    /// Length of the dashes and of the space between them, in pixels, `None` for solid lines.
+    pub fn lengths(self) -> Option<(f64, f64)> {
+        match self {
+            Self::Solid => None,
+            Self::Dashed => Some((12., 6.)),
+            Self::Short => Some((5., 4.)),
+            Self::Long => Some((24., 6.)),
+            Self::Dotted => Some((2., 5.)),
+        }
+    }
+
+    /// Text representation of the dash pattern, for legends.
+    pub fn swatch(self) -> &'static str {
+        match self {
+            Self::Solid => "────",
+            Self::Dashed => "╌╌╌╌",
+            Self::Short => "┄┄┄┄",
+            Self::Long => "── ──",
+            Self::Dotted => "····",
+        }
+    }
+}
+
+/// Marker drawn on some of the points of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Marker {
+    /// Empty circle.
+    Circle,
+    /// Filled circle.
+    Disk,
+    /// Filled triangle.
+    Triangle,
+    /// Cross.
+    Cross,
+}
+impl Marker {
+    /// All the markers.
+    pub const ALL: [Self; 4] = [Self::Circle, Self::Disk, Self::Triangle, Self::Cross];
+
+    /// Text representation of the marker, for legends.
+    pub fn glyph(self) -> char {
+        match self {
+            Self::Circle => '○',
+            Self::Disk => '●',
+            Self::Triangle => '▲',
+            Self::Cross => '✕',
+        }
+    }
+}
+
+/// Dash pattern and marker of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    /// Dash pattern.
+    pub dash: Dash,
+    /// Marker.
+    pub marker: Marker,
+}
+impl Pattern {
+    /// Number of distinct patterns.
+    pub const COUNT: usize = Dash::ALL.len() * Marker::ALL.len();
+    /// Minimal distance between two markers of the same line, in pixels.
+    pub const MARKER_SPACING: f64 = 80.;
+    /// Size of the markers, in pixels.
+    pub const MARKER_SIZE: u32 = 6;
+
+    /// Pattern of the `index`-th line.
+    ///
+    /// Consecutive indices yield different dash patterns and different markers, and the first
+    /// [`COUNT`](#associatedconstant.COUNT) patterns are all different.
+    ///
+    /// ```rust
+    /// # use charts::pattern::*;
+    /// let (fst, snd) = (Pattern::nth(0), Pattern::nth(1));
+    /// assert_eq!(fst.dash, Dash::Solid);
+    /// assert_ne!(fst.dash, snd.dash);
+    /// assert_ne!(fst.marker, snd.marker);
+    /// assert_eq!(Pattern::nth(Pattern::COUNT), fst);
+    /// ```
+    pub fn nth(index: usize) -> Self {
+        Self {
+            dash: Dash::ALL[index % Dash::ALL.len()],
+            marker: Marker::ALL[index % Marker::ALL.len()],
+        }
+    }
+
+    /// Pattern of a line.
+    pub fn of_line(line: uid::Line) -> Self {
+        let index = match line {
+            uid::Line::Everything => 0,
+            uid::Line::CatchAll => 1,
+            uid::Line::Filter(uid) => 2 + uid.get(),
+            uid::Line::Segment(uid) => uid.get(),
+        };
+        Self::nth(index)
+    }
+
+    /// Text representation of the pattern, for legends.
+    ///
+    /// ```rust
+    /// # use charts::pattern::*;
+    /// assert_eq!(Pattern::nth(1).swatch(), "╌╌╌╌●");
+    /// ```
+    pub fn swatch(&self) -> String {
+        format!("{}{}", self.dash.swatch(), self.marker.glyph())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distinct_patterns() {
+        let patterns: HSet<_> = (0..Pattern::COUNT).map(Pattern::nth).collect();
+        assert_eq!(patterns.len(), Pattern::COUNT);
+        let swatches: HSet<_> = patterns.iter().map(Pattern::swatch).collect();
+        assert_eq!(swatches.len(), Pattern::COUNT);
+    }
+}
@@ -21,13 +21,23 @@
 
 prelude! {}
 
+pub mod churn;
+pub mod heap;
+pub mod line_map;
+pub mod scatter;
+
+pub use churn::Churn;
+pub use heap::HeapSize;
+pub use line_map::LineMap;
+pub use scatter::ScatterPoints;
+
 /// A point value.
 ///
 /// Stores a value for each filter, and the value for the catch-all filter.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PointVal<Val> {
     /// Values for filter lines.
-    pub map: BTMap<uid::Line, Val>,
+    pub map: LineMap<Val>,
 }
 impl<Val> PointVal<Val> {
     /// Constructor.
@@ -35,7 +45,7 @@ impl<Val> PointVal<Val> {
     where
         Val: Clone,
     {
-        let mut map = BTMap::new();
+        let mut map = LineMap::new();
         map.insert(uid::Line::CatchAll, default.clone());
         map.insert(uid::Line::Everything, default.clone());
         for filter in filters.filters() {
@@ -46,7 +56,9 @@ impl<Val> PointVal<Val> {
 
     /// Empty constructor.
     pub fn empty() -> Self {
-        Self { map: BTMap::new() }
+        Self {
+            map: LineMap::new(),
+        }
     }
 
     /// True if the inner map is empty.
@@ -56,7 +68,7 @@ impl<Val> PointVal<Val> {
 
     /// Immutable ref over some value.
     pub fn get_mut_or(&mut self, uid: uid::Line, default: Val) -> &mut Val {
-        self.map.entry(uid).or_insert(default)
+        self.map.get_or_insert_with(uid, || default)
     }
 
     /// Mutable ref over some value.
@@ -80,7 +92,7 @@ impl<Val> PointVal<Val> {
 
     /// Map over all values.
     pub fn map<Out>(self, mut f: impl FnMut(uid::Line, Val) -> Res<Out>) -> Res<PointVal<Out>> {
-        let mut map = BTMap::new();
+        let mut map = LineMap::new();
         for (uid, val) in self.map {
             map.insert(uid, f(uid, val)?);
         }
@@ -93,7 +105,7 @@ impl<Val> PointVal<Val> {
         self,
         mut f: impl FnMut(uid::Line, Val) -> Res<Option<Out>>,
     ) -> Res<PointVal<Out>> {
-        let mut map = BTMap::new();
+        let mut map = LineMap::new();
         for (uid, val) in self.map {
             if let Some(res) = f(uid, val)? {
                 let prev = map.insert(uid, res);
@@ -189,6 +201,49 @@ base::implement! {
     }
 }
 
+/// A percentage, used by normalized charts.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default, Serialize, Deserialize)]
+pub struct Percent {
+    /// Actual percentage, `100.0` means 100%.
+    pub percent: f32,
+}
+impl Percent {
+    /// Constructor.
+    pub fn new(percent: f32) -> Self {
+        Self { percent }
+    }
+
+    /// Percentage of `val` with respect to `total`.
+    ///
+    /// Returns `None` if the percentage is not defined, *i.e.* when `total` is zero but `val` is
+    /// not. When both are zero, the percentage is zero.
+    ///
+    /// ```rust
+    /// # use charts::point::Percent;
+    /// assert_eq!(Percent::of(1.0, 4.0), Some(Percent::new(25.0)));
+    /// assert_eq!(Percent::of(0.0, 0.0), Some(Percent::new(0.0)));
+    /// assert_eq!(Percent::of(1.0, 0.0), None);
+    /// ```
+    pub fn of(val: f64, total: f64) -> Option<Self> {
+        if total == 0.0 {
+            if val == 0.0 {
+                Some(Self::new(0.0))
+            } else {
+                None
+            }
+        } else {
+            Some(Self::new((val * 100.0 / total) as f32))
+        }
+    }
+}
+base::implement! {
+    impl Percent {
+        Display {
+            |&self, fmt| write!(fmt, "{:.2}%", self.percent),
+        }
+    }
+}
+
 /// Extension trait for coordinates.
 ///
 /// Note that the type of the values appearing in a point are not necessarily the same type expected
@@ -304,6 +359,46 @@ impl CoordExt for Size {
     }
 }
 
+impl CoordExt for Churn {
+    type Coord = f32;
+    type Range = coord::RangedCoordf32;
+    fn default_val() -> Self {
+        Self::default()
+    }
+    fn zero() -> f32 {
+        0.0
+    }
+    fn is_zero(val: &f32) -> bool {
+        *val == 0.0
+    }
+    fn default_min() -> f32 {
+        0.0
+    }
+    fn default_max() -> f32 {
+        1.0
+    }
+}
+
+impl CoordExt for Percent {
+    type Coord = f32;
+    type Range = coord::RangedCoordf32;
+    fn default_val() -> Self {
+        Self::default()
+    }
+    fn zero() -> f32 {
+        0.0
+    }
+    fn is_zero(val: &f32) -> bool {
+        *val == 0.0
+    }
+    fn default_min() -> f32 {
+        0.0
+    }
+    fn default_max() -> f32 {
+        100.0
+    }
+}
+
 impl CoordExt for f32 {
     type Coord = f32;
     type Range = coord::RangedCoordf32;
@@ -376,11 +471,25 @@ where
     fn val_coord_processor(range: &Range<Val>, x: &Val) -> Val::Coord;
     /// Formatter for the axis labels.
     fn val_label_formatter(val: &Val::Coord) -> String;
+    /// Formatter for the axis labels of a chart with some style.
+    ///
+    /// Defaults to [`val_label_formatter`].
+    ///
+    /// [`val_label_formatter`]: #tymethod.val_label_formatter (The val_label_formatter method)
+    fn val_styled_label_formatter(val: &Val::Coord, _style_conf: &impl StyleExt) -> String {
+        Self::val_label_formatter(val)
+    }
+    /// True if a line with no value in a point should not be drawn over that point.
+    ///
+    /// By default, points with no value for a line are just skipped.
+    fn missing_is_gap() -> bool {
+        false
+    }
 }
 
 impl<X, Y> ChartRender<X, Y> for PolyPoints<X, Y>
 where
-    X: CoordExt,
+    X: CoordExt + PartialOrd,
     Y: CoordExt,
     Self: RangesExt<X, Y> + PointValExt<X> + PointValExt<Y>,
 {
@@ -402,12 +511,36 @@ pub trait StyleExt {
 
     /// Creates a shape style.
     fn shape_conf(&self, color: &Color) -> plotters::style::ShapeStyle;
+
+    /// True if lines should be dashed.
+    ///
+    /// Used when the data is an estimate. Only applies to the normal display mode, stacked areas
+    /// are filled.
+    fn dashed(&self) -> bool {
+        false
+    }
+
+    /// Pattern of a line, if lines should have patterns.
+    ///
+    /// Used in accessible mode, so that lines can be told apart without relying on their color. In
+    /// the normal display mode, the pattern's dashes override the dashes of estimates, if any. In
+    /// stacked-area modes, the upper border of the areas is drawn with the pattern.
+    fn pattern(&self, _line: uid::Line) -> Option<pattern::Pattern> {
+        None
+    }
+
+    /// Label of a time on the x-axis of a time chart.
+    ///
+    /// Defaults to the time since the start of the run.
+    fn time_label(&self, time: time::SinceStart) -> String {
+        settings::TimeAxis::SinceStart.label(time, None)
+    }
 }
 
 /// Chart-rendering trait.
 pub trait ChartRender<X, Y>
 where
-    X: CoordExt,
+    X: CoordExt + PartialOrd,
     Y: CoordExt,
     Self: RangesExt<X, Y> + PointValExt<X> + PointValExt<Y>,
 {
@@ -437,8 +570,8 @@ where
     }
 
     /// X-axis label formatter.
-    fn x_label_formatter(val: &X::Coord) -> String {
-        <Self as PointValExt<X>>::val_label_formatter(val)
+    fn x_label_formatter(val: &X::Coord, style_conf: &impl StyleExt) -> String {
+        <Self as PointValExt<X>>::val_styled_label_formatter(val, style_conf)
     }
     /// Y-axis label formatter.
     fn y_label_formatter(val: &Y::Coord) -> String {
@@ -456,6 +589,7 @@ where
         style_conf: &impl StyleExt,
         is_active: impl Fn(uid::Line) -> bool,
         active_filters: impl Iterator<Item = &'spec filter::FilterSpec> + Clone,
+        gaps: &[Range<X>],
     ) -> Res<()>
     where
         DB: plotters::prelude::DrawingBackend,
@@ -477,6 +611,7 @@ where
                 style_conf,
                 is_active,
                 active_filters,
+                gaps,
             ),
             DisplayMode::StackedArea => self.chart_render_stacked_area(
                 settings,
@@ -484,6 +619,7 @@ where
                 style_conf,
                 is_active,
                 active_filters,
+                gaps,
             ),
             DisplayMode::StackedAreaPercent => self.chart_render_stacked_area_percent(
                 settings,
@@ -491,6 +627,7 @@ where
                 style_conf,
                 is_active,
                 active_filters,
+                gaps,
             ),
         }
     }
@@ -503,6 +640,7 @@ where
         style_conf: &impl StyleExt,
         is_active: impl Fn(uid::Line) -> bool,
         active_filters: impl Iterator<Item = &'spec filter::FilterSpec>,
+        gaps: &[Range<X>],
     ) -> Res<()>
     where
         DB: plotters::prelude::DrawingBackend,
@@ -529,30 +667,57 @@ where
             style_conf.mesh_conf::<X, Y, DB>(&mut mesh);
 
             // Set x/y formatters and draw this thing.
-            mesh.x_label_formatter(&Self::x_label_formatter)
+            mesh.x_label_formatter(&|x| Self::x_label_formatter(x, style_conf))
                 .y_label_formatter(&Self::y_label_formatter)
                 .draw()
                 .map_err(|e| e.to_string())?;
         }
 
+        let missing_is_gap = <Self as PointValExt<Y>>::missing_is_gap();
+
         // Time to add some points.
         for filter_spec in active_filters {
             let f_uid = filter_spec.uid();
 
-            let points = self.points().filter_map(|point| {
-                point.vals.map.get(&f_uid).map(|val| {
-                    (
-                        Self::x_coord_processor(&raw_ranges.x, &point.key),
-                        Self::y_coord_processor(&raw_ranges.y, val),
-                    )
-                })
-            });
+            // Runs of consecutive points, split where a missing value is a gap.
+            let mut runs = vec![vec![]];
+            for point in self.points() {
+                match point.vals.map.get(&f_uid) {
+                    Some(val) => runs.last_mut().expect("runs cannot be empty").push((
+                        &point.key,
+                        (
+                            Self::x_coord_processor(&raw_ranges.x, &point.key),
+                            Self::y_coord_processor(&raw_ranges.y, val),
+                        ),
+                    )),
+                    None if missing_is_gap && runs.last().map(Vec::is_empty) == Some(false) => {
+                        runs.push(vec![])
+                    }
+                    None => (),
+                }
+            }
 
             let style = style_conf.shape_conf(filter_spec.color());
-
-            chart_cxt
-                .draw_series(LineSeries::new(points, style))
-                .map_err(|e| e.to_string())?;
+            let pattern = style_conf.pattern(f_uid);
+            let dash = match pattern.and_then(|pattern| pattern.dash.lengths()) {
+                None if style_conf.dashed() => Some(DASH_PATTERN),
+                dash => dash,
+            };
+
+            for run in runs {
+                for segment in split_at_gaps(run.into_iter(), gaps) {
+                    if let Some(pattern) = pattern {
+                        draw_markers(&chart_cxt, &segment, &style, pattern.marker)?
+                    }
+                    if let Some(dash) = dash {
+                        draw_dashed(&chart_cxt, &segment, &style, dash)?
+                    } else {
+                        chart_cxt
+                            .draw_series(LineSeries::new(segment, style.clone()))
+                            .map_err(|e| e.to_string())?;
+                    }
+                }
+            }
         }
 
         Ok(())
@@ -565,6 +730,7 @@ where
         style_conf: &impl StyleExt,
         is_active: impl Fn(uid::Line) -> bool,
         active_filters: impl Iterator<Item = &'spec filter::FilterSpec> + Clone,
+        gaps: &[Range<X>],
     ) -> Res<()>
     where
         DB: plotters::prelude::DrawingBackend,
@@ -584,6 +750,7 @@ where
             style_conf,
             is_active,
             active_filters,
+            gaps,
             Y::zero,
             |y_val, _y_max| y_val,
             |lbound, ubound| (lbound..ubound).into(),
@@ -600,6 +767,7 @@ where
         style_conf: &impl StyleExt,
         is_active: impl Fn(uid::Line) -> bool,
         active_filters: impl Iterator<Item = &'spec filter::FilterSpec> + Clone,
+        gaps: &[Range<X>],
     ) -> Res<()>
     where
         DB: plotters::prelude::DrawingBackend,
@@ -619,6 +787,7 @@ where
             style_conf,
             is_active,
             active_filters,
+            gaps,
             || 0.0f32,
             |y_val, y_max| {
                 if Y::is_zero(y_max) {
@@ -646,6 +815,7 @@ where
         style_conf: &impl StyleExt,
         is_active: impl Fn(uid::Line) -> bool,
         active_filters: impl Iterator<Item = &'spec filter::FilterSpec> + Clone,
+        gaps: &[Range<X>],
         zero: impl Fn() -> RealY::Coord,
         compute_from_val_and_max: impl Fn(Y::Coord, &Y::Coord) -> RealY::Coord,
         range_do: impl Fn(Y::Coord, Y::Coord) -> RealY::Range,
@@ -727,7 +897,7 @@ where
             style_conf.mesh_conf::<X, RealY, DB>(&mut mesh);
 
             // Set x/y formatters and draw this thing.
-            mesh.x_label_formatter(&Self::x_label_formatter)
+            mesh.x_label_formatter(&|x| Self::x_label_formatter(x, style_conf))
                 .y_label_formatter(&label_formatter)
                 .draw()
                 .map_err(|e| e.to_string())?;
@@ -760,22 +930,210 @@ where
                 assert!(*max >= y_val);
                 *sum = sum.clone() + compute_from_val_and_max(y_val, max);
                 (
-                    Self::x_coord_processor(&raw_ranges.x, &point.key),
-                    sum.clone(),
+                    &point.key,
+                    (
+                        Self::x_coord_processor(&raw_ranges.x, &point.key),
+                        sum.clone(),
+                    ),
                 )
             });
 
             let style = style_conf.shape_conf(filter_spec.color()).filled();
-
-            chart_cxt
-                .draw_series(LineSeries::new(points, style))
-                .map_err(|e| e.to_string())?;
+            let pattern = style_conf.pattern(f_uid);
+
+            for segment in split_at_gaps(points, gaps) {
+                match pattern {
+                    None => {
+                        chart_cxt
+                            .draw_series(LineSeries::new(segment, style.clone()))
+                            .map_err(|e| e.to_string())?;
+                    }
+                    // Plotters cannot fill areas with hatches, the upper border of the area
+                    // carries the pattern instead.
+                    Some(pattern) => {
+                        chart_cxt
+                            .draw_series(LineSeries::new(segment.iter().cloned(), style.clone()))
+                            .map_err(|e| e.to_string())?;
+                        let border = style_conf.shape_conf(&crate::color::Color::BLACK);
+                        draw_markers(&chart_cxt, &segment, &border, pattern.marker)?;
+                        match pattern.dash.lengths() {
+                            Some(dash) => draw_dashed(&chart_cxt, &segment, &border, dash)?,
+                            None => {
+                                chart_cxt
+                                    .draw_series(LineSeries::new(segment, border))
+                                    .map_err(|e| e.to_string())?;
+                            }
+                        }
+                    }
+                }
+            }
         }
 
         Ok(())
     }
 }
 
+/// Splits some keyed coordinates into segments that do not span over any gap.
+///
+/// Used to avoid drawing lines over ranges for which no data is available.
+fn split_at_gaps<'a, X, C>(
+    coords: impl Iterator<Item = (&'a X, C)>,
+    gaps: &[Range<X>],
+) -> Vec<Vec<C>>
+where
+    X: PartialOrd + 'a,
+{
+    let mut segments = vec![];
+    let mut current = vec![];
+    let mut prev: Option<&X> = None;
+    for (key, coord) in coords {
+        if let Some(prev) = prev {
+            if gaps
+                .iter()
+                .any(|gap| prev <= &gap.lbound && &gap.ubound <= key)
+            {
+                segments.push(std::mem::replace(&mut current, vec![]))
+            }
+        }
+        prev = Some(key);
+        current.push(coord)
+    }
+    if !current.is_empty() {
+        segments.push(current)
+    }
+    segments
+}
+
+/// Length of the dashes of estimated lines, and of the space between them, in pixels.
+const DASH_PATTERN: (f64, f64) = (12., 6.);
+
+/// Draws a dashed line through some coordinates.
+///
+/// `dash` is the length of the dashes and of the space between them, in pixels.
+fn draw_dashed<DB, XR, YR>(
+    chart_cxt: &plotters::prelude::ChartContext<'_, DB, coord::Cartesian2d<XR, YR>>,
+    coords: &[(XR::ValueType, YR::ValueType)],
+    style: &plotters::style::ShapeStyle,
+    dash: (f64, f64),
+) -> Res<()>
+where
+    DB: plotters::prelude::DrawingBackend,
+    XR: coord::Ranged,
+    YR: coord::Ranged,
+{
+    let area = chart_cxt.plotting_area().use_screen_coord();
+    let pixels: Vec<_> = coords
+        .iter()
+        .map(|coord| {
+            let (x, y) = chart_cxt.backend_coord(coord);
+            (x as f64, y as f64)
+        })
+        .collect();
+    for dash in dashes(&pixels, dash) {
+        let dash: Vec<_> = dash
+            .into_iter()
+            .map(|(x, y)| (x.round() as i32, y.round() as i32))
+            .collect();
+        area.draw(&plotters::element::PathElement::new(dash, style.clone()))
+            .map_err(|e| e.to_string())?
+    }
+    Ok(())
+}
+
+/// Draws markers on some coordinates, skipping the ones too close to the previous marker.
+///
+/// See [`Pattern::MARKER_SPACING`](../pattern/struct.Pattern.html#associatedconstant.MARKER_SPACING).
+fn draw_markers<DB, XR, YR>(
+    chart_cxt: &plotters::prelude::ChartContext<'_, DB, coord::Cartesian2d<XR, YR>>,
+    coords: &[(XR::ValueType, YR::ValueType)],
+    style: &plotters::style::ShapeStyle,
+    marker: pattern::Marker,
+) -> Res<()>
+where
+    DB: plotters::prelude::DrawingBackend,
+    XR: coord::Ranged,
+    YR: coord::Ranged,
+{
+    use pattern::Pattern;
+
+    let area = chart_cxt.plotting_area().use_screen_coord();
+    let mut last: Option<(i32, i32)> = None;
+    for coord in coords {
+        let (x, y) = chart_cxt.backend_coord(coord);
+        if let Some((last_x, last_y)) = last {
+            let dist = ((x - last_x) as f64).hypot((y - last_y) as f64);
+            if dist < Pattern::MARKER_SPACING {
+                continue;
+            }
+        }
+        last = Some((x, y));
+        draw_marker(&area, (x, y), style, marker, Pattern::MARKER_SIZE)?
+    }
+    Ok(())
+}
+
+/// Draws a marker of some size at some screen coordinates.
+fn draw_marker<DB>(
+    area: &plotters::drawing::DrawingArea<DB, plotters::coord::Shift>,
+    pixel: (i32, i32),
+    style: &plotters::style::ShapeStyle,
+    marker: pattern::Marker,
+    size: u32,
+) -> Res<()>
+where
+    DB: plotters::prelude::DrawingBackend,
+{
+    use pattern::Marker;
+    use plotters::element::{Circle, Cross, TriangleMarker};
+
+    let res = match marker {
+        Marker::Circle => area.draw(&Circle::new(pixel, size, style.clone())),
+        Marker::Disk => area.draw(&Circle::new(pixel, size, style.filled())),
+        Marker::Triangle => area.draw(&TriangleMarker::new(pixel, size, style.filled())),
+        Marker::Cross => area.draw(&Cross::new(pixel, size, style.clone())),
+    };
+    res.map_err(|e| e.to_string().into())
+}
+
+/// Splits a line into dashes, given the length of the dashes and of the space between them.
+fn dashes(points: &[(f64, f64)], (dash_len, space_len): (f64, f64)) -> Vec<Vec<(f64, f64)>> {
+    let mut res = vec![];
+    let mut dash = vec![];
+    // True if drawing a dash, false if in a space.
+    let mut drawing = true;
+    // Length left before the end of the current dash or space.
+    let mut left = dash_len;
+
+    for pair in points.windows(2) {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        let len = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        if drawing && dash.is_empty() {
+            dash.push((x1, y1))
+        }
+        // Length of the segment already handled.
+        let mut done = 0.;
+        while len - done > left {
+            done += left;
+            let ratio = done / len;
+            dash.push((x1 + (x2 - x1) * ratio, y1 + (y2 - y1) * ratio));
+            if drawing {
+                res.push(std::mem::replace(&mut dash, vec![]))
+            }
+            drawing = !drawing;
+            left = if drawing { dash_len } else { space_len };
+        }
+        left -= len - done;
+        if drawing {
+            dash.push((x2, y2))
+        }
+    }
+
+    if dash.len() > 1 {
+        res.push(dash)
+    }
+    res
+}
+
 /// A list of points.
 pub type PolyPoints<X, Y> = Vec<Point<X, Y>>;
 
@@ -903,6 +1261,12 @@ impl<Y> PointValExt<time::SinceStart> for PolyPoints<time::SinceStart, Y> {
         res.push('s');
         res
     }
+    fn val_styled_label_formatter(
+        date: &<time::SinceStart as CoordExt>::Coord,
+        style_conf: &impl StyleExt,
+    ) -> String {
+        style_conf.time_label(time::SinceStart::from(date.to_std().unwrap()))
+    }
 }
 
 impl<X> PointValExt<Size> for PolyPoints<X, Size> {
@@ -953,19 +1317,415 @@ impl<X> PointValExt<u64> for PolyPoints<X, u64> {
     }
 }
 
+impl<X> PointValExt<Churn> for PolyPoints<X, Churn> {
+    fn val_range_processor(range: Range<Option<Churn>>) -> Res<Range<Churn>> {
+        Ok(range.unwrap_or_else(Churn::default, Churn::default))
+    }
+    fn val_coord_range_processor(_range: &Range<Churn>) -> Res<Range<<Churn as CoordExt>::Coord>> {
+        // Churn ratios always live in `[0, 1]`.
+        Ok(Range::new(Churn::default_min(), Churn::default_max()))
+    }
+    fn val_coord_processor(_range: &Range<Churn>, x: &Churn) -> <Churn as CoordExt>::Coord {
+        x.ratio()
+    }
+    fn val_label_formatter(val: &<Churn as CoordExt>::Coord) -> String {
+        format!("{:.2}", val)
+    }
+}
+
+impl<X> PointValExt<Percent> for PolyPoints<X, Percent> {
+    fn val_range_processor(range: Range<Option<Percent>>) -> Res<Range<Percent>> {
+        Ok(range.unwrap_or_else(Percent::default, || Percent::new(Percent::default_max())))
+    }
+    fn val_coord_range_processor(
+        range: &Range<Percent>,
+    ) -> Res<Range<<Percent as CoordExt>::Coord>> {
+        // Always show at least `[0, 100]`.
+        Ok(Range::new(
+            range.lbound.percent.min(Percent::default_min()),
+            range.ubound.percent.max(Percent::default_max()),
+        ))
+    }
+    fn val_coord_processor(_range: &Range<Percent>, x: &Percent) -> <Percent as CoordExt>::Coord {
+        x.percent
+    }
+    fn val_label_formatter(val: &<Percent as CoordExt>::Coord) -> String {
+        format!("{:.2}%", val)
+    }
+    fn missing_is_gap() -> bool {
+        true
+    }
+}
+
+impl<X> PointValExt<f32> for PolyPoints<X, f32> {
+    fn val_range_processor(range: Range<Option<f32>>) -> Res<Range<f32>> {
+        Ok(range.unwrap_or_else(f32::default_min, f32::default_max))
+    }
+    fn val_coord_range_processor(range: &Range<f32>) -> Res<Range<<f32 as CoordExt>::Coord>> {
+        // Transformed values can be negative, always show zero.
+        Ok(Range::new(
+            range.lbound.min(f32::default_min()),
+            range.ubound.max(f32::default_max()),
+        ))
+    }
+    fn val_coord_processor(_range: &Range<f32>, x: &f32) -> <f32 as CoordExt>::Coord {
+        *x
+    }
+    fn val_label_formatter(val: &<f32 as CoordExt>::Coord) -> String {
+        if *val < 0.0 {
+            format!("-{}", num_fmt::str_do(-*val as f64, base::identity))
+        } else {
+            num_fmt::str_do(*val as f64, base::identity)
+        }
+    }
+}
+
+/// Keeps the last `n` values of each line of some points.
+///
+/// Points left with no value are dropped.
+fn last_vals<X: Clone, Y: Clone>(points: &[Point<X, Y>], n: usize) -> PolyPoints<X, Y> {
+    let mut counts: BTMap<uid::Line, usize> = BTMap::new();
+    let mut res = vec![];
+    for point in points.iter().rev() {
+        let mut map = LineMap::new();
+        for (uid, val) in point.vals.map.iter() {
+            let count = counts.entry(*uid).or_insert(0);
+            if *count < n {
+                *count += 1;
+                map.insert(*uid, val.clone());
+            }
+        }
+        if !map.is_empty() {
+            res.push(Point::new(point.key.clone(), PointVal { map }))
+        }
+    }
+    res.reverse();
+    res
+}
+
+/// Removes the points before `start`, except for the last value of each line before `start`.
+///
+/// These values move to a point at `start`, so that the lines start with the value they have at
+/// `start`. Returns the number of x-axis ticks removed.
+fn drop_before<Y: Clone>(
+    points: &mut PolyPoints<time::SinceStart, Y>,
+    start: time::SinceStart,
+) -> usize {
+    let count = points.iter().take_while(|point| point.key < start).count();
+    if count == 0 {
+        return 0;
+    }
+    let mut anchor = LineMap::new();
+    for point in points.drain(0..count) {
+        for (uid, val) in point.vals.map.iter() {
+            let _ = anchor.insert(*uid, val.clone());
+        }
+    }
+    match points.first_mut() {
+        Some(first) if first.key == start => {
+            for (uid, val) in anchor.iter() {
+                if !first.vals.map.contains_key(uid) {
+                    let _ = first.vals.map.insert(*uid, val.clone());
+                }
+            }
+            count
+        }
+        _ => {
+            points.insert(0, Point::new(start, PointVal { map: anchor }));
+            count - 1
+        }
+    }
+}
+
+/// Removes the points after `end`.
+///
+/// If some points were removed, the last value of each line moves to a point at `end`, so that the
+/// lines end with the value they have at `end`. Returns the number of x-axis ticks removed.
+fn drop_after<Y: Clone>(
+    points: &mut PolyPoints<time::SinceStart, Y>,
+    end: time::SinceStart,
+) -> usize {
+    let keep = points.iter().take_while(|point| point.key <= end).count();
+    let count = points.len() - keep;
+    if count == 0 {
+        return 0;
+    }
+    points.truncate(keep);
+    let mut anchor = LineMap::new();
+    for point in points.iter() {
+        for (uid, val) in point.vals.map.iter() {
+            let _ = anchor.insert(*uid, val.clone());
+        }
+    }
+    match points.last_mut() {
+        Some(last) if last.key == end => {
+            for (uid, val) in anchor.iter() {
+                if !last.vals.map.contains_key(uid) {
+                    let _ = last.vals.map.insert(*uid, val.clone());
+                }
+            }
+            count
+        }
+        Some(_) => {
+            points.push(Point::new(end, PointVal { map: anchor }));
+            count - 1
+        }
+        None => count,
+    }
+}
+
+/// Variation per second of each line between its consecutive values.
+///
+/// The first value of each line has no predecessor, and thus no rate.
+fn rate<Y>(points: PolyPoints<time::SinceStart, Y>, to_f64: impl Fn(&Y) -> f64) -> TimeFloatPoints {
+    let mut prev: BTMap<uid::Line, (f64, f64)> = BTMap::new();
+    points
+        .into_iter()
+        .map(|Point { key, vals }| {
+            let secs = key.as_secs_f64();
+            let mut map = LineMap::new();
+            for (uid, val) in vals.map.iter() {
+                let val = to_f64(val);
+                if let Some((prev_secs, prev_val)) = prev.insert(*uid, (secs, val)) {
+                    if prev_secs < secs {
+                        map.insert(*uid, ((val - prev_val) / (secs - prev_secs)) as f32);
+                    }
+                }
+            }
+            Point::new(key, PointVal { map })
+        })
+        .collect()
+}
+
+/// Mean of each value of each line and the values of this line preceding it, `width` values in
+/// total at most.
+fn smooth<X, Y>(
+    points: PolyPoints<X, Y>,
+    width: usize,
+    to_f64: impl Fn(&Y) -> f64,
+) -> PolyPoints<X, f32> {
+    let mut windows: BTMap<uid::Line, std::collections::VecDeque<f64>> = BTMap::new();
+    points
+        .into_iter()
+        .map(|Point { key, vals }| {
+            let mut map = LineMap::new();
+            for (uid, val) in vals.map.iter() {
+                let window = windows.entry(*uid).or_insert_with(Default::default);
+                window.push_back(to_f64(val));
+                while window.len() > width {
+                    window.pop_front();
+                }
+                let mean = window.iter().sum::<f64>() / (window.len() as f64);
+                map.insert(*uid, mean as f32);
+            }
+            Point::new(key, PointVal { map })
+        })
+        .collect()
+}
+
+/// Removes the values and the points that do not change how the lines are drawn.
+///
+/// `memory` is the last value of each line and the last point of the points preceding `points`, if
+/// any. See [`TimePoints::dedup`] for details. Returns the number of values removed, the last value
+/// of each line and the last point.
+///
+/// [`TimePoints::dedup`]: enum.TimePoints.html#method.dedup (TimePoints::dedup)
+fn dedup<Y>(
+    points: &mut PolyPoints<time::SinceStart, Y>,
+    memory: Option<(&[Point<time::SinceStart, Y>], &[Point<time::SinceStart, Y>])>,
+    gaps: &[TimeWindow],
+) -> (
+    usize,
+    PolyPoints<time::SinceStart, Y>,
+    PolyPoints<time::SinceStart, Y>,
+)
+where
+    Y: CoordExt + PartialEq + Clone,
+    PolyPoints<time::SinceStart, Y>: PointValExt<Y>,
+{
+    let count = |points: &[Point<time::SinceStart, Y>]| {
+        points
+            .iter()
+            .fold(0, |acc, point| acc + point.vals.map.len())
+    };
+    let in_gap = |from: &time::SinceStart, to: &time::SinceStart| {
+        gaps.iter()
+            .any(|gap| from <= &gap.lbound && &gap.ubound <= to)
+    };
+    let (last_vals, last_tick) = memory.unwrap_or((&[], &[]));
+    let before = count(points);
+
+    // Values on a flat part of their line, only if missing values are just skipped.
+    if !<PolyPoints<time::SinceStart, Y> as PointValExt<Y>>::missing_is_gap() {
+        // Last value kept for each line, and its time.
+        let mut last: BTMap<uid::Line, (time::SinceStart, Y)> = BTMap::new();
+        for point in last_vals {
+            for (uid, val) in point.vals.map.iter() {
+                last.insert(*uid, (point.key, val.clone()));
+            }
+        }
+        // Indices of the points mentioning each line.
+        let mut indices: BTMap<uid::Line, Vec<usize>> = BTMap::new();
+        for (idx, point) in points.iter().enumerate() {
+            for uid in point.vals.map.keys() {
+                indices.entry(*uid).or_insert_with(Vec::new).push(idx)
+            }
+        }
+        let mut elided = vec![];
+        for (uid, indices) in indices {
+            let mut prev = last.remove(&uid);
+            for pair in indices.windows(2) {
+                let (current, next) = (&points[pair[0]], &points[pair[1]]);
+                let (val, next_val) = (&current.vals.map[&uid], &next.vals.map[&uid]);
+                let flat = prev.as_ref().map_or(false, |(prev_key, prev_val)| {
+                    prev_val == val && val == next_val && !in_gap(prev_key, &next.key)
+                });
+                if flat {
+                    elided.push((pair[0], uid))
+                } else {
+                    prev = Some((current.key, val.clone()))
+                }
+            }
+        }
+        for (idx, uid) in elided {
+            points[idx].vals.map.remove(&uid);
+        }
+    }
+
+    // Points with the same values as their neighbors.
+    let len = points.len();
+    let mut keep = vec![true; len];
+    // Index of the last point kept, `None` for the last point of the previous points.
+    let mut last_kept: Option<usize> = None;
+    for idx in 0..len {
+        let prev = match (last_kept, last_tick.last()) {
+            (Some(prev), _) => &points[prev],
+            (None, Some(prev)) => prev,
+            (None, None) => {
+                last_kept = Some(idx);
+                continue;
+            }
+        };
+        let elide = points.get(idx + 1).map_or(false, |next| {
+            prev.vals.map == points[idx].vals.map
+                && points[idx].vals.map == next.vals.map
+                && !in_gap(&prev.key, &next.key)
+        });
+        if elide {
+            keep[idx] = false
+        } else {
+            last_kept = Some(idx)
+        }
+    }
+    let mut keep = keep.into_iter();
+    points.retain(|_| keep.next().unwrap_or(true));
+
+    // Last value of each line, one point per line.
+    let mut last: BTMap<uid::Line, Point<time::SinceStart, Y>> = BTMap::new();
+    for point in last_vals.iter().chain(points.iter()) {
+        for (uid, val) in point.vals.map.iter() {
+            let mut vals = PointVal::empty();
+            vals.map.insert(*uid, val.clone());
+            last.insert(*uid, Point::new(point.key, vals));
+        }
+    }
+    let last_tick = points
+        .last()
+        .or_else(|| last_tick.last())
+        .cloned()
+        .into_iter()
+        .collect();
+
+    (
+        before - count(points),
+        last.into_iter().map(|(_, point)| point).collect(),
+        last_tick,
+    )
+}
+
+/// Normalizes some points by a line.
+///
+/// Each value becomes the percentage it represents with respect to the value of `line` in the same
+/// point, see [`Percent::of`]. Values for which the percentage is not defined are dropped, and so
+/// are all the values of points that have no value for `line`. The values for `line` itself are
+/// always `100%`.
+///
+/// [`Percent::of`]: struct.Percent.html#method.of (Percent::of)
+fn normalize<X, Y>(
+    points: PolyPoints<X, Y>,
+    line: uid::Line,
+    to_f64: impl Fn(&Y) -> f64,
+) -> PolyPoints<X, Percent> {
+    points
+        .into_iter()
+        .map(|Point { key, vals }| {
+            let mut map = LineMap::new();
+            if let Some(total) = vals.map.get(&line).map(&to_f64) {
+                for (uid, val) in vals.map.iter() {
+                    let percent = if *uid == line {
+                        Some(Percent::new(100.0))
+                    } else {
+                        Percent::of(to_f64(val), total)
+                    };
+                    if let Some(percent) = percent {
+                        map.insert(*uid, percent);
+                    }
+                }
+            }
+            Point::new(key, PointVal { map })
+        })
+        .collect()
+}
+
 /// Points representing size over time.
 pub type TimeSizePoints = PolyPoints<time::SinceStart, Size>;
+/// Points representing churn over time.
+pub type TimeChurnPoints = PolyPoints<time::SinceStart, Churn>;
+/// Points representing a number of allocations over time.
+pub type TimeCountPoints = PolyPoints<time::SinceStart, u64>;
+/// Points representing percentages over time.
+pub type TimePercentPoints = PolyPoints<time::SinceStart, Percent>;
+/// Points representing arbitrary values over time.
+pub type TimeFloatPoints = PolyPoints<time::SinceStart, f32>;
+/// Points representing the live size on the minor and major heaps over time.
+pub type TimeHeapPoints = PolyPoints<time::SinceStart, HeapSize>;
+
+/// What deduplication remembers of the points it processed, see [`TimePoints::dedup`].
+///
+/// [`TimePoints::dedup`]: enum.TimePoints.html#method.dedup (TimePoints::dedup)
+#[derive(Debug, Clone)]
+pub struct DedupMemory {
+    /// Last value of each line, one point per line.
+    last_vals: TimePoints,
+    /// Last point, if any.
+    last_tick: TimePoints,
+}
 
 /// Some points for a time chart.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TimePoints {
     /// Size over time.
     Size(TimeSizePoints),
+    /// Churn over time.
+    Churn(TimeChurnPoints),
+    /// Allocation count over time.
+    Count(TimeCountPoints),
+    /// Percentages over time, for normalized charts.
+    Percent(TimePercentPoints),
+    /// Arbitrary values over time, for transformed charts and lifetime charts.
+    Float(TimeFloatPoints),
+    /// Minor and major heap sizes over time.
+    Heap(TimeHeapPoints),
 }
 
 base::implement! {
     impl From for TimePoints {
-        from TimeSizePoints => |points| Self::Size(points)
+        from TimeSizePoints => |points| Self::Size(points),
+        from TimeChurnPoints => |points| Self::Churn(points),
+        from TimeCountPoints => |points| Self::Count(points),
+        from TimePercentPoints => |points| Self::Percent(points),
+        from TimeFloatPoints => |points| Self::Float(points),
+        from TimeHeapPoints => |points| Self::Heap(points),
     }
 }
 
@@ -974,6 +1734,11 @@ impl TimePoints {
     pub fn is_empty(&self) -> bool {
         match self {
             Self::Size(points) => points.is_empty(),
+            Self::Churn(points) => points.is_empty(),
+            Self::Count(points) => points.is_empty(),
+            Self::Percent(points) => points.is_empty(),
+            Self::Float(points) => points.is_empty(),
+            Self::Heap(points) => points.is_empty(),
         }
     }
 
@@ -983,6 +1748,11 @@ impl TimePoints {
     pub fn len(&self) -> usize {
         match self {
             Self::Size(points) => points.len(),
+            Self::Churn(points) => points.len(),
+            Self::Count(points) => points.len(),
+            Self::Percent(points) => points.len(),
+            Self::Float(points) => points.len(),
+            Self::Heap(points) => points.len(),
         }
     }
     /// Total number of points.
@@ -991,11 +1761,449 @@ impl TimePoints {
             Self::Size(points) => points
                 .iter()
                 .fold(0, |acc, point| acc + point.vals.map.len()),
+            Self::Churn(points) => points
+                .iter()
+                .fold(0, |acc, point| acc + point.vals.map.len()),
+            Self::Count(points) => points
+                .iter()
+                .fold(0, |acc, point| acc + point.vals.map.len()),
+            Self::Percent(points) => points
+                .iter()
+                .fold(0, |acc, point| acc + point.vals.map.len()),
+            Self::Float(points) => points
+                .iter()
+                .fold(0, |acc, point| acc + point.vals.map.len()),
+            Self::Heap(points) => points
+                .iter()
+                .fold(0, |acc, point| acc + point.vals.map.len()),
+        }
+    }
+
+    /// Time of the first point, if any.
+    pub fn first_time(&self) -> Option<time::SinceStart> {
+        match self {
+            Self::Size(points) => points.first().map(|point| point.key),
+            Self::Churn(points) => points.first().map(|point| point.key),
+            Self::Count(points) => points.first().map(|point| point.key),
+            Self::Percent(points) => points.first().map(|point| point.key),
+            Self::Float(points) => points.first().map(|point| point.key),
+            Self::Heap(points) => points.first().map(|point| point.key),
+        }
+    }
+
+    /// Time of the last point, if any.
+    pub fn last_time(&self) -> Option<time::SinceStart> {
+        match self {
+            Self::Size(points) => points.last().map(|point| point.key),
+            Self::Churn(points) => points.last().map(|point| point.key),
+            Self::Count(points) => points.last().map(|point| point.key),
+            Self::Percent(points) => points.last().map(|point| point.key),
+            Self::Float(points) => points.last().map(|point| point.key),
+            Self::Heap(points) => points.last().map(|point| point.key),
+        }
+    }
+
+    /// Description of the value of the last point for some line, if any.
+    pub fn last_val_desc(&self, uid: uid::Line) -> Option<String> {
+        match self {
+            Self::Size(points) => points
+                .last()
+                .and_then(|point| point.vals.map.get(&uid))
+                .map(|val| val.to_string()),
+            Self::Churn(points) => points
+                .last()
+                .and_then(|point| point.vals.map.get(&uid))
+                .map(|val| val.to_string()),
+            Self::Count(points) => points
+                .last()
+                .and_then(|point| point.vals.map.get(&uid))
+                .map(|val| val.to_string()),
+            Self::Percent(points) => points
+                .last()
+                .and_then(|point| point.vals.map.get(&uid))
+                .map(|val| val.to_string()),
+            Self::Float(points) => points
+                .last()
+                .and_then(|point| point.vals.map.get(&uid))
+                .map(|val| num_fmt::str_do(*val as f64, base::identity)),
+            Self::Heap(points) => points
+                .last()
+                .and_then(|point| point.vals.map.get(&uid))
+                .map(|val| val.to_string()),
+        }
+    }
+
+    /// Value of the last point for some line, if any, as displayed by [`series`].
+    ///
+    /// [`series`]: #method.series (The series method)
+    pub fn last_val(&self, uid: uid::Line) -> Option<f64> {
+        fn last_val<Val>(
+            points: &[Point<time::SinceStart, Val>],
+            uid: uid::Line,
+            val: impl Fn(&Val) -> f64,
+        ) -> Option<f64> {
+            points
+                .last()
+                .and_then(|point| point.vals.map.get(&uid))
+                .map(val)
+        }
+        match self {
+            Self::Size(points) => last_val(points, uid, |val| val.size as f64),
+            Self::Churn(points) => last_val(points, uid, |val| val.ratio() as f64),
+            Self::Count(points) => last_val(points, uid, |val| *val as f64),
+            Self::Percent(points) => last_val(points, uid, |val| val.percent as f64),
+            Self::Float(points) => last_val(points, uid, |val| *val as f64),
+            Self::Heap(points) => last_val(points, uid, |val| val.total() as f64),
+        }
+    }
+
+    /// Flattens the points to one list of `(seconds, value)` pairs per line.
+    ///
+    /// Values are the ones the chart displays: bytes, churn ratio, count, percentage or
+    /// transformed value. Heap sizes are flattened to their total, see [`heap::split`] to keep the
+    /// two heaps apart, and churn values to their ratio, see [`churn::split`] for their spread.
+    ///
+    /// [`heap::split`]: heap/fn.split.html (The heap::split function)
+    /// [`churn::split`]: churn/fn.split.html (The churn::split function)
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, point::*};
+    /// let point = |secs, size: u64| {
+    ///     let mut vals = PointVal::empty();
+    ///     vals.map.insert(uid::Line::Everything, Size::new(size));
+    ///     Point::new(time::SinceStart::from_secs(secs), vals)
+    /// };
+    /// let points: TimeSizePoints = vec![point(0, 3), point(2, 7)];
+    /// let series = TimePoints::from(points).series();
+    /// assert_eq!(series[&uid::Line::Everything], vec![(0.0, 3.0), (2.0, 7.0)]);
+    /// ```
+    pub fn series(&self) -> BTMap<uid::Line, Vec<(f64, f64)>> {
+        fn series<Val>(
+            points: &[Point<time::SinceStart, Val>],
+            val: impl Fn(&Val) -> f64,
+        ) -> BTMap<uid::Line, Vec<(f64, f64)>> {
+            let mut res = BTMap::new();
+            for point in points {
+                let secs = point.key.as_secs_f64();
+                for (uid, v) in point.vals.map.iter() {
+                    res.entry(*uid)
+                        .or_insert_with(Vec::new)
+                        .push((secs, val(v)))
+                }
+            }
+            res
+        }
+        match self {
+            Self::Size(points) => series(points, |val| val.size as f64),
+            Self::Churn(points) => series(points, |val| val.ratio() as f64),
+            Self::Count(points) => series(points, |val| *val as f64),
+            Self::Percent(points) => series(points, |val| val.percent as f64),
+            Self::Float(points) => series(points, |val| *val as f64),
+            Self::Heap(points) => series(points, |val| val.total() as f64),
+        }
+    }
+
+    /// Lines that have at least one value.
+    pub fn lines(&self) -> BTSet<uid::Line> {
+        fn lines<Val>(points: &[Point<time::SinceStart, Val>]) -> BTSet<uid::Line> {
+            points
+                .iter()
+                .flat_map(|point| point.vals.map.keys().cloned())
+                .collect()
+        }
+        match self {
+            Self::Size(points) => lines(points),
+            Self::Churn(points) => lines(points),
+            Self::Count(points) => lines(points),
+            Self::Percent(points) => lines(points),
+            Self::Float(points) => lines(points),
+            Self::Heap(points) => lines(points),
+        }
+    }
+
+    /// Only keeps the values of the lines verifying some predicate.
+    pub fn retain_lines(&mut self, keep: impl Fn(uid::Line) -> bool) {
+        match self {
+            Self::Size(points) => points
+                .iter_mut()
+                .for_each(|point| point.vals.map.retain(|line, _| keep(*line))),
+            Self::Churn(points) => points
+                .iter_mut()
+                .for_each(|point| point.vals.map.retain(|line, _| keep(*line))),
+            Self::Count(points) => points
+                .iter_mut()
+                .for_each(|point| point.vals.map.retain(|line, _| keep(*line))),
+            Self::Percent(points) => points
+                .iter_mut()
+                .for_each(|point| point.vals.map.retain(|line, _| keep(*line))),
+            Self::Float(points) => points
+                .iter_mut()
+                .for_each(|point| point.vals.map.retain(|line, _| keep(*line))),
+            Self::Heap(points) => points
+                .iter_mut()
+                .for_each(|point| point.vals.map.retain(|line, _| keep(*line))),
+        }
+    }
+
+    /// Normalizes the points by a line, see [`Percent::of`].
+    ///
+    /// The values of the points become the percentage they represent with respect to the value of
+    /// `line` in the same point. Undefined percentages are dropped, which renders as gaps.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, point::*};
+    /// let (everything, catch_all) = (uid::Line::Everything, uid::Line::CatchAll);
+    /// let point = |secs, total, caught| {
+    ///     let mut vals = PointVal::empty();
+    ///     vals.map.insert(everything, total);
+    ///     vals.map.insert(catch_all, caught);
+    ///     Point::new(time::SinceStart::from_secs(secs), vals)
+    /// };
+    /// let points: TimeCountPoints = vec![point(0, 4, 1), point(1, 0, 0), point(2, 0, 3)];
+    /// let normalized = match TimePoints::from(points).normalize(everything) {
+    ///     TimePoints::Percent(points) => points,
+    ///     _ => panic!("expected percentages"),
+    /// };
+    /// let catch_all_vals: Vec<_> = normalized
+    ///     .iter()
+    ///     .map(|point| point.vals.map.get(&catch_all).map(|val| val.percent))
+    ///     .collect();
+    /// assert_eq!(catch_all_vals, vec![Some(25.0), Some(0.0), None]);
+    /// assert!(normalized.iter().all(|point| point.vals.map[&everything].percent == 100.0));
+    /// ```
+    ///
+    /// [`Percent::of`]: struct.Percent.html#method.of (Percent::of)
+    pub fn normalize(self, line: uid::Line) -> Self {
+        match self {
+            Self::Size(points) => normalize(points, line, |val| val.size as f64).into(),
+            Self::Churn(points) => normalize(points, line, |val| val.ratio() as f64).into(),
+            Self::Count(points) => normalize(points, line, |val| *val as f64).into(),
+            Self::Percent(points) => normalize(points, line, |val| val.percent as f64).into(),
+            Self::Float(points) => normalize(points, line, |val| *val as f64).into(),
+            Self::Heap(points) => normalize(points, line, |val| val.total() as f64).into(),
+        }
+    }
+
+    /// Variation per second of each line between its consecutive values.
+    ///
+    /// The first value of each line has no rate and is dropped.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, point::*};
+    /// let point = |secs, size: u64| {
+    ///     let mut vals = PointVal::empty();
+    ///     vals.map.insert(uid::Line::Everything, Size::new(size));
+    ///     Point::new(time::SinceStart::from_secs(secs), vals)
+    /// };
+    /// let points: TimeSizePoints = vec![point(0, 3), point(2, 7), point(3, 6)];
+    /// let series = TimePoints::from(points).rate().series();
+    /// assert_eq!(series[&uid::Line::Everything], vec![(2.0, 2.0), (3.0, -1.0)]);
+    /// ```
+    pub fn rate(self) -> Self {
+        match self {
+            Self::Size(points) => rate(points, |val| val.size as f64).into(),
+            Self::Churn(points) => rate(points, |val| val.ratio() as f64).into(),
+            Self::Count(points) => rate(points, |val| *val as f64).into(),
+            Self::Percent(points) => rate(points, |val| val.percent as f64).into(),
+            Self::Float(points) => rate(points, |val| *val as f64).into(),
+            Self::Heap(points) => rate(points, |val| val.total() as f64).into(),
+        }
+    }
+
+    /// Replaces each value of each line by its mean with the `width - 1` values of this line
+    /// preceding it.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, point::*};
+    /// let point = |secs, size: u64| {
+    ///     let mut vals = PointVal::empty();
+    ///     vals.map.insert(uid::Line::Everything, Size::new(size));
+    ///     Point::new(time::SinceStart::from_secs(secs), vals)
+    /// };
+    /// let points: TimeSizePoints = vec![point(0, 3), point(2, 7), point(3, 2)];
+    /// let series = TimePoints::from(points).smooth(2).series();
+    /// assert_eq!(series[&uid::Line::Everything], vec![(0.0, 3.0), (2.0, 5.0), (3.0, 4.5)]);
+    /// ```
+    pub fn smooth(self, width: usize) -> Self {
+        match self {
+            Self::Size(points) => smooth(points, width, |val| val.size as f64).into(),
+            Self::Churn(points) => smooth(points, width, |val| val.ratio() as f64).into(),
+            Self::Count(points) => smooth(points, width, |val| *val as f64).into(),
+            Self::Percent(points) => smooth(points, width, |val| val.percent as f64).into(),
+            Self::Float(points) => smooth(points, width, |val| *val as f64).into(),
+            Self::Heap(points) => smooth(points, width, |val| val.total() as f64).into(),
+        }
+    }
+
+    /// Points with the last `n` values of each line, points with no such value are dropped.
+    pub fn last_vals(&self, n: usize) -> Self {
+        match self {
+            Self::Size(points) => last_vals(points, n).into(),
+            Self::Churn(points) => last_vals(points, n).into(),
+            Self::Count(points) => last_vals(points, n).into(),
+            Self::Percent(points) => last_vals(points, n).into(),
+            Self::Float(points) => last_vals(points, n).into(),
+            Self::Heap(points) => last_vals(points, n).into(),
+        }
+    }
+
+    /// Removes the values and the points that do not change how the lines are drawn.
+    ///
+    /// A value is removed when it is on a flat part of its line: the previous and next values of
+    /// the line are the same as this value. A point is removed when it is on a flat part of all the
+    /// lines: it has the same values as the points right before and after it. Points whose values
+    /// are missing are gaps in percentages, so only whole points are removed for them.
+    ///
+    /// Values and points whose neighbors have a gap between them are kept, and so is the last
+    /// point since the points after it are not known yet. `memory` is what deduplication
+    /// remembers of the previous batches of points, it is updated for the next batch.
+    ///
+    /// Returns the number of values removed, see [`point_count`].
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, point::*};
+    /// let point = |secs, size: u64| {
+    ///     let mut vals = PointVal::empty();
+    ///     vals.map.insert(uid::Line::Everything, Size::new(size));
+    ///     Point::new(time::SinceStart::from_secs(secs), vals)
+    /// };
+    /// let mut memory = None;
+    /// let points: TimeSizePoints = (0..5).map(|secs| point(secs, 7)).collect();
+    /// let mut points = TimePoints::from(points);
+    /// assert_eq!(points.dedup(&mut memory, &[]), 3);
+    /// let secs: Vec<_> = points.series()[&uid::Line::Everything].iter().map(|(x, _)| *x).collect();
+    /// assert_eq!(secs, vec![0.0, 4.0]);
+    ///
+    /// // The first point of the next batch follows the last point of this one.
+    /// let next: TimeSizePoints = vec![point(5, 7), point(6, 7), point(7, 3)];
+    /// let mut next = TimePoints::from(next);
+    /// assert_eq!(next.dedup(&mut memory, &[]), 1);
+    /// let secs: Vec<_> = next.series()[&uid::Line::Everything].iter().map(|(x, _)| *x).collect();
+    /// assert_eq!(secs, vec![6.0, 7.0]);
+    /// ```
+    ///
+    /// [`point_count`]: #method.point_count (The point_count method)
+    pub fn dedup(&mut self, memory: &mut Option<DedupMemory>, gaps: &[TimeWindow]) -> usize {
+        macro_rules! dedup {
+            ($variant:ident, $points:expr) => {{
+                let (removed, last_vals, last_tick) = match memory.as_ref() {
+                    Some(DedupMemory {
+                        last_vals: Self::$variant(last_vals),
+                        last_tick: Self::$variant(last_tick),
+                    }) => dedup($points, Some((last_vals, last_tick)), gaps),
+                    _ => dedup($points, None, gaps),
+                };
+                *memory = Some(DedupMemory {
+                    last_vals: Self::$variant(last_vals),
+                    last_tick: Self::$variant(last_tick),
+                });
+                removed
+            }};
+        }
+        match self {
+            Self::Size(points) => dedup!(Size, points),
+            Self::Churn(points) => dedup!(Churn, points),
+            Self::Count(points) => dedup!(Count, points),
+            Self::Percent(points) => dedup!(Percent, points),
+            Self::Float(points) => dedup!(Float, points),
+            // Heap charts opt out of deduplication, see `YAxis::can_dedup`.
+            Self::Heap(_) => 0,
+        }
+    }
+
+    /// Removes the points before `start`, except for the last value of each line before `start`.
+    ///
+    /// These values move to a point at `start`, so that the lines start with the value they have
+    /// at `start` instead of ramping up from nothing. Returns the number of x-axis ticks removed.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, point::*};
+    /// let point = |secs, vals: &[(uid::Line, u64)]| {
+    ///     let mut map = PointVal::empty();
+    ///     for (line, size) in vals {
+    ///         map.map.insert(*line, Size::new(*size));
+    ///     }
+    ///     Point::new(time::SinceStart::from_secs(secs), map)
+    /// };
+    /// let (all, catch_all) = (uid::Line::Everything, uid::Line::CatchAll);
+    /// let points: TimeSizePoints = vec![
+    ///     point(0, &[(all, 1), (catch_all, 1)]),
+    ///     point(2, &[(all, 3)]),
+    ///     point(4, &[(all, 4), (catch_all, 2)]),
+    /// ];
+    /// let mut points = TimePoints::from(points);
+    /// assert_eq!(points.drop_before(time::SinceStart::from_secs(3)), 1);
+    /// let series = points.series();
+    /// assert_eq!(series[&all], vec![(3.0, 3.0), (4.0, 4.0)]);
+    /// assert_eq!(series[&catch_all], vec![(3.0, 1.0), (4.0, 2.0)]);
+    /// ```
+    pub fn drop_before(&mut self, start: time::SinceStart) -> usize {
+        match self {
+            Self::Size(points) => drop_before(points, start),
+            Self::Churn(points) => drop_before(points, start),
+            Self::Count(points) => drop_before(points, start),
+            Self::Percent(points) => drop_before(points, start),
+            Self::Float(points) => drop_before(points, start),
+            Self::Heap(points) => drop_before(points, start),
+        }
+    }
+
+    /// Removes the points after `end`, see [`drop_before`].
+    ///
+    /// If some points were removed, the lines end with the value they have at `end`.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, point::*};
+    /// let point = |secs, vals: &[(uid::Line, u64)]| {
+    ///     let mut map = PointVal::empty();
+    ///     for (line, size) in vals {
+    ///         map.map.insert(*line, Size::new(*size));
+    ///     }
+    ///     Point::new(time::SinceStart::from_secs(secs), map)
+    /// };
+    /// let (all, catch_all) = (uid::Line::Everything, uid::Line::CatchAll);
+    /// let points: TimeSizePoints = vec![
+    ///     point(0, &[(all, 1), (catch_all, 1)]),
+    ///     point(2, &[(all, 3)]),
+    ///     point(4, &[(all, 4), (catch_all, 2)]),
+    /// ];
+    /// let mut points = TimePoints::from(points);
+    /// assert_eq!(points.drop_after(time::SinceStart::from_secs(5)), 0);
+    /// assert_eq!(points.drop_after(time::SinceStart::from_secs(3)), 0);
+    /// let series = points.series();
+    /// assert_eq!(series[&all], vec![(0.0, 1.0), (2.0, 3.0), (3.0, 3.0)]);
+    /// assert_eq!(series[&catch_all], vec![(0.0, 1.0), (3.0, 1.0)]);
+    /// ```
+    ///
+    /// [`drop_before`]: #method.drop_before (The drop_before method)
+    pub fn drop_after(&mut self, end: time::SinceStart) -> usize {
+        match self {
+            Self::Size(points) => drop_after(points, end),
+            Self::Churn(points) => drop_after(points, end),
+            Self::Count(points) => drop_after(points, end),
+            Self::Percent(points) => drop_after(points, end),
+            Self::Float(points) => drop_after(points, end),
+            Self::Heap(points) => drop_after(points, end),
+        }
+    }
+
+    /// Removes the first `n` x-axis ticks.
+    pub fn drop_first(&mut self, n: usize) {
+        match self {
+            Self::Size(points) => drop(points.drain(0..n.min(points.len()))),
+            Self::Churn(points) => drop(points.drain(0..n.min(points.len()))),
+            Self::Count(points) => drop(points.drain(0..n.min(points.len()))),
+            Self::Percent(points) => drop(points.drain(0..n.min(points.len()))),
+            Self::Float(points) => drop(points.drain(0..n.min(points.len()))),
+            Self::Heap(points) => drop(points.drain(0..n.min(points.len()))),
         }
     }
 
     /// Extends some points with other points, returns `true` iff new points were added.
     ///
+    /// The last churn point is the bucket the chart is still filling, so a churn point with the
+    /// same key replaces its values.
+    ///
     /// Fails if the two kinds of points are not compatible.
     pub fn extend(&mut self, other: &mut Self) -> Res<bool> {
         let new_stuff = match (self, other) {
@@ -1004,6 +2212,51 @@ impl TimePoints {
                 self_points.extend(points.drain(0..));
                 new_stuff
             }
+            (Self::Churn(self_points), Self::Churn(points)) => {
+                let new_stuff = !points.is_empty();
+                let replaced = match (self_points.last(), points.first()) {
+                    (Some(last), Some(first)) => last.key == first.key,
+                    (None, _) | (_, None) => false,
+                };
+                if replaced {
+                    let last = self_points.pop().expect("replaced point cannot be missing");
+                    // Deduplication may have dropped the values that did not change.
+                    let first = &mut points[0];
+                    for (line, val) in last.vals.map {
+                        let _ = first.vals.map.get_or_insert_with(line, || val);
+                    }
+                }
+                self_points.extend(points.drain(0..));
+                new_stuff
+            }
+            (Self::Count(self_points), Self::Count(points)) => {
+                let new_stuff = !points.is_empty();
+                self_points.extend(points.drain(0..));
+                new_stuff
+            }
+            (Self::Percent(self_points), Self::Percent(points)) => {
+                let new_stuff = !points.is_empty();
+                self_points.extend(points.drain(0..));
+                new_stuff
+            }
+            (Self::Float(self_points), Self::Float(points)) => {
+                let new_stuff = !points.is_empty();
+                self_points.extend(points.drain(0..));
+                new_stuff
+            }
+            (Self::Heap(self_points), Self::Heap(points)) => {
+                let new_stuff = !points.is_empty();
+                self_points.extend(points.drain(0..));
+                new_stuff
+            }
+            (Self::Size(_), _)
+            | (Self::Churn(_), _)
+            | (Self::Count(_), _)
+            | (Self::Percent(_), _)
+            | (Self::Float(_), _)
+            | (Self::Heap(_), _) => {
+                bail!("cannot extend time points with incompatible time points")
+            }
         };
         Ok(new_stuff)
     }
@@ -1016,6 +2269,7 @@ impl TimePoints {
         style_conf: &impl StyleExt,
         is_active: impl Fn(uid::Line) -> bool,
         active_filters: impl Iterator<Item = &'spec filter::FilterSpec> + Clone,
+        gaps: &[TimeWindow],
     ) -> Res<()>
     where
         DB: plotters::prelude::DrawingBackend,
@@ -1027,6 +2281,48 @@ impl TimePoints {
                 style_conf,
                 is_active,
                 active_filters,
+                gaps,
+            ),
+            Self::Count(points) => points.render(
+                settings,
+                chart_builder,
+                style_conf,
+                is_active,
+                active_filters,
+                gaps,
+            ),
+            // Percentages and transformed values cannot be stacked, always use normal rendering.
+            Self::Percent(points) => points.chart_render(
+                settings,
+                chart_builder,
+                style_conf,
+                is_active,
+                active_filters,
+                gaps,
+            ),
+            Self::Float(points) => points.chart_render(
+                settings,
+                chart_builder,
+                style_conf,
+                is_active,
+                active_filters,
+                gaps,
+            ),
+            Self::Churn(points) => churn::render(
+                points,
+                chart_builder,
+                style_conf,
+                is_active,
+                active_filters,
+                gaps,
+            ),
+            Self::Heap(points) => heap::render(
+                points,
+                chart_builder,
+                style_conf,
+                is_active,
+                active_filters,
+                gaps,
             ),
         }
     }
@@ -1037,6 +2333,8 @@ impl TimePoints {
 pub enum Points {
     /// Points for a time chart.
     Time(TimePoints),
+    /// Points for a scatter chart.
+    Scatter(ScatterPoints),
 }
 
 impl Points {
@@ -1044,30 +2342,194 @@ impl Points {
     pub fn is_empty(&self) -> bool {
         match self {
             Self::Time(points) => points.is_empty(),
+            Self::Scatter(points) => points.is_empty(),
         }
     }
 
     /// Number of x-axis ticks.
     ///
-    /// Each x-axis tick can have several y-axis filter points.
+    /// Each x-axis tick can have several y-axis filter points. Scatter charts have no ticks, this
+    /// is their number of points.
     pub fn len(&self) -> usize {
         match self {
             Self::Time(points) => points.len(),
+            Self::Scatter(points) => points.len(),
         }
     }
     /// Total number of points.
     pub fn point_count(&self) -> usize {
         match self {
             Self::Time(points) => points.point_count(),
+            Self::Scatter(points) => points.len(),
         }
     }
 
     /// Extends some points with other points, returns `true` iff new points were added.
     ///
-    /// Fails if the two kinds of points are not compatible.
+    /// Fails if the two kinds of points are not compatible. Scatter points replace the previous
+    /// ones, see [`ScatterPoints::extend`].
+    ///
+    /// [`ScatterPoints::extend`]: scatter/struct.ScatterPoints.html#method.extend
+    /// (ScatterPoints::extend)
     pub fn extend(&mut self, other: &mut Self) -> Res<bool> {
         match (self, other) {
             (Self::Time(self_points), Self::Time(points)) => self_points.extend(points),
+            (Self::Scatter(self_points), Self::Scatter(points)) => Ok(self_points.extend(points)),
+            (Self::Time(_), Self::Scatter(_)) | (Self::Scatter(_), Self::Time(_)) => {
+                bail!("cannot extend time points with scatter points, or the other way around")
+            }
+        }
+    }
+
+    /// Description of the value of the last point for some line, if any.
+    pub fn last_val_desc(&self, uid: uid::Line) -> Option<String> {
+        match self {
+            Self::Time(points) => points.last_val_desc(uid),
+            Self::Scatter(points) => points.last_val_desc(uid),
+        }
+    }
+
+    /// Value of the last point for some line, if any.
+    ///
+    /// Always `None` for scatter points, which have no last point.
+    pub fn last_val(&self, uid: uid::Line) -> Option<f64> {
+        match self {
+            Self::Time(points) => points.last_val(uid),
+            Self::Scatter(_) => None,
+        }
+    }
+
+    /// Time of the last point, if any.
+    pub fn last_time(&self) -> Option<time::SinceStart> {
+        match self {
+            Self::Time(points) => points.last_time(),
+            Self::Scatter(_) => None,
+        }
+    }
+
+    /// Only keeps the values of the lines verifying some predicate.
+    pub fn retain_lines(&mut self, keep: impl Fn(uid::Line) -> bool) {
+        match self {
+            Self::Time(points) => points.retain_lines(keep),
+            Self::Scatter(points) => points.retain_lines(keep),
+        }
+    }
+
+    /// Flattens the points to one list of `(x, y)` pairs per line, see [`TimePoints::series`].
+    ///
+    /// [`TimePoints::series`]: enum.TimePoints.html#method.series (TimePoints::series)
+    pub fn series(&self) -> BTMap<uid::Line, Vec<(f64, f64)>> {
+        match self {
+            Self::Time(points) => points.series(),
+            Self::Scatter(points) => points.series(),
+        }
+    }
+
+    /// Lines that have at least one value.
+    pub fn lines(&self) -> BTSet<uid::Line> {
+        match self {
+            Self::Time(points) => points.lines(),
+            Self::Scatter(points) => points.lines(),
+        }
+    }
+
+    /// Normalizes the points by a line, see [`TimePoints::normalize`].
+    ///
+    /// [`TimePoints::normalize`]: enum.TimePoints.html#method.normalize (TimePoints::normalize)
+    pub fn normalize(self, line: uid::Line) -> Self {
+        match self {
+            Self::Time(points) => Self::Time(points.normalize(line)),
+            // Scatter charts have no lines to transform.
+            points @ Self::Scatter(_) => points,
+        }
+    }
+
+    /// Variation per second of each line, see [`TimePoints::rate`].
+    ///
+    /// [`TimePoints::rate`]: enum.TimePoints.html#method.rate (TimePoints::rate)
+    pub fn rate(self) -> Self {
+        match self {
+            Self::Time(points) => Self::Time(points.rate()),
+            // Scatter charts have no lines to transform.
+            points @ Self::Scatter(_) => points,
+        }
+    }
+
+    /// Moving average of each line, see [`TimePoints::smooth`].
+    ///
+    /// [`TimePoints::smooth`]: enum.TimePoints.html#method.smooth (TimePoints::smooth)
+    pub fn smooth(self, width: usize) -> Self {
+        match self {
+            Self::Time(points) => Self::Time(points.smooth(width)),
+            // Scatter charts have no lines to transform.
+            points @ Self::Scatter(_) => points,
+        }
+    }
+
+    /// Points with the last `n` values of each line, see [`TimePoints::last_vals`].
+    ///
+    /// [`TimePoints::last_vals`]: enum.TimePoints.html#method.last_vals (TimePoints::last_vals)
+    pub fn last_vals(&self, n: usize) -> Self {
+        match self {
+            Self::Time(points) => Self::Time(points.last_vals(n)),
+            Self::Scatter(points) => Self::Scatter(points.clone()),
+        }
+    }
+
+    /// Removes the first `n` x-axis ticks.
+    pub fn drop_first(&mut self, n: usize) {
+        match self {
+            Self::Time(points) => points.drop_first(n),
+            Self::Scatter(_) => (),
+        }
+    }
+
+    /// Removes the points before `start`, see [`TimePoints::drop_before`].
+    ///
+    /// Scatter points are samples of the whole time window, they are left untouched.
+    ///
+    /// [`TimePoints::drop_before`]: enum.TimePoints.html#method.drop_before
+    /// (TimePoints::drop_before)
+    pub fn drop_before(&mut self, start: time::SinceStart) -> usize {
+        match self {
+            Self::Time(points) => points.drop_before(start),
+            Self::Scatter(_) => 0,
+        }
+    }
+
+    /// Only keeps the points of a time window, for charts zooming on it.
+    ///
+    /// The lines start and end with the values they have at the bounds of the window, see
+    /// [`TimePoints::drop_before`] and [`TimePoints::drop_after`]. Scatter points are left
+    /// untouched.
+    ///
+    /// [`TimePoints::drop_before`]: enum.TimePoints.html#method.drop_before
+    /// (TimePoints::drop_before)
+    /// [`TimePoints::drop_after`]: enum.TimePoints.html#method.drop_after
+    /// (TimePoints::drop_after)
+    pub fn zoom(&mut self, window: &TimeWindow) {
+        if let Self::Time(points) = self {
+            let _ = points.drop_before(window.lbound);
+            let _ = points.drop_after(window.ubound);
+        }
+    }
+
+    /// Time range of the points of a time chart, `None` for scatter charts and empty points.
+    pub fn time_range(&self) -> Option<TimeWindow> {
+        match self {
+            Self::Time(points) => Some(Range::new(points.first_time()?, points.last_time()?)),
+            Self::Scatter(_) => None,
+        }
+    }
+
+    /// Removes the values and the points that do not change how the lines are drawn, see
+    /// [`TimePoints::dedup`].
+    ///
+    /// [`TimePoints::dedup`]: enum.TimePoints.html#method.dedup (TimePoints::dedup)
+    pub fn dedup(&mut self, memory: &mut Option<DedupMemory>, gaps: &[TimeWindow]) -> usize {
+        match self {
+            Self::Time(points) => points.dedup(memory, gaps),
+            Self::Scatter(_) => 0,
         }
     }
 
@@ -1079,6 +2541,7 @@ impl Points {
         style_conf: &impl StyleExt,
         is_active: impl Fn(uid::Line) -> bool,
         active_filters: impl Iterator<Item = &'spec filter::FilterSpec> + Clone,
+        gaps: &[TimeWindow],
     ) -> Res<()>
     where
         DB: plotters::prelude::DrawingBackend,
@@ -1090,6 +2553,14 @@ impl Points {
                 style_conf,
                 is_active,
                 active_filters,
+                gaps,
+            ),
+            Self::Scatter(points) => points.render(
+                settings,
+                chart_builder,
+                style_conf,
+                is_active,
+                active_filters,
             ),
         }
     }
@@ -1109,15 +2580,47 @@ where
 pub struct ChartPoints {
     /// The actual points.
     points: BTMap<uid::Chart, Points>,
+    /// Time ranges with no data, for each chart.
+    ///
+    /// These are always the full list of gaps for the chart, not only the new ones.
+    gaps: BTMap<uid::Chart, Vec<TimeWindow>>,
+    /// Sequence numbers of the points of each chart, see the [`seq`] module.
+    ///
+    /// [`seq`]: ../chart/seq/index.html (The seq module)
+    seqs: BTMap<uid::Chart, chart::seq::Seq>,
 }
 impl ChartPoints {
     /// Constructor.
     pub fn new() -> Self {
         Self {
             points: BTMap::new(),
+            gaps: BTMap::new(),
+            seqs: BTMap::new(),
         }
     }
 
+    /// Sets the sequence number of the points of a chart.
+    pub fn set_seq(&mut self, uid: uid::Chart, seq: chart::seq::Seq) {
+        let _ = self.seqs.insert(uid, seq);
+    }
+    /// Sequence number of the points of a chart, if any.
+    pub fn seq(&self, uid: uid::Chart) -> Option<chart::seq::Seq> {
+        self.seqs.get(&uid).cloned()
+    }
+
+    /// Sets the gaps of a chart.
+    pub fn set_gaps(&mut self, uid: uid::Chart, gaps: Vec<TimeWindow>) {
+        if !gaps.is_empty() {
+            let _ = self.gaps.insert(uid, gaps);
+        }
+    }
+    /// Removes the gaps of a chart.
+    ///
+    /// Returns the empty list if the chart has no gaps.
+    pub fn remove_gaps(&mut self, uid: uid::Chart) -> Vec<TimeWindow> {
+        self.gaps.remove(&uid).unwrap_or_else(Vec::new)
+    }
+
     /// True if there are no points.
     pub fn is_empty(&self) -> bool {
         self.points.iter().all(|(_uid, points)| points.is_empty())
@@ -1134,3 +2637,130 @@ base::implement! {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Size points for two lines from `(seconds, everything, catch-all)` triples.
+    fn size_points(vals: &[(u64, u64, u64)]) -> TimePoints {
+        let points: TimeSizePoints = vals
+            .iter()
+            .map(|(secs, everything, catch_all)| {
+                let mut vals = PointVal::empty();
+                vals.map
+                    .insert(uid::Line::Everything, Size::new(*everything));
+                vals.map.insert(uid::Line::CatchAll, Size::new(*catch_all));
+                Point::new(time::SinceStart::from_secs(*secs), vals)
+            })
+            .collect();
+        points.into()
+    }
+
+    /// Value of a line at some time, interpolated linearly.
+    fn interpolate(series: &[(f64, f64)], x: f64) -> f64 {
+        let idx = series.iter().position(|(key, _)| x <= *key).unwrap();
+        let (x_1, y_1) = series[idx];
+        if idx == 0 || x == x_1 {
+            return y_1;
+        }
+        let (x_0, y_0) = series[idx - 1];
+        y_0 + (y_1 - y_0) * (x - x_0) / (x_1 - x_0)
+    }
+
+    /// Checks that deduplicated points draw the same lines as the original points.
+    ///
+    /// Points have no missing values, so the lines have no gaps.
+    fn assert_same_lines(original: &TimePoints, deduped: &TimePoints) {
+        let (original, deduped) = (original.series(), deduped.series());
+        assert_eq!(original.len(), deduped.len());
+        for (line, series) in original.iter() {
+            let deduped = &deduped[line];
+            for (x, y) in series {
+                assert_eq!(interpolate(deduped, *x), *y, "line {} at {}s", line, x)
+            }
+        }
+    }
+
+    /// Points with runs of identical values, including a run where only one line changes.
+    const RUNS: &[(u64, u64, u64)] = &[
+        (0, 1, 1),
+        (1, 1, 1),
+        (2, 1, 1),
+        (3, 1, 1),
+        (4, 5, 1),
+        (5, 5, 2),
+        (6, 5, 2),
+        (7, 5, 2),
+        (8, 5, 3),
+        (9, 0, 0),
+        (10, 0, 0),
+        (11, 0, 0),
+    ];
+
+    #[test]
+    fn dedup_preserves_lines() {
+        let original = size_points(RUNS);
+        let mut deduped = original.clone();
+        assert_eq!(deduped.dedup(&mut None, &[]), 11);
+        assert_eq!(deduped.point_count(), 2 * RUNS.len() - 11);
+        assert_same_lines(&original, &deduped);
+
+        // Percentages cannot lose values, only whole points.
+        let original = original.normalize(uid::Line::Everything);
+        let mut deduped = original.clone();
+        assert_eq!(deduped.dedup(&mut None, &[]), 8);
+        assert_eq!(deduped.len(), RUNS.len() - 4);
+        assert_same_lines(&original, &deduped);
+    }
+
+    #[test]
+    fn batched_dedup_preserves_lines() {
+        let original = size_points(RUNS);
+        let mut elided = 0;
+        let mut memory = None;
+        let mut all: Option<TimePoints> = None;
+        for batch in RUNS.chunks(3) {
+            let mut batch = size_points(batch);
+            elided += batch.dedup(&mut memory, &[]);
+            match all.as_mut() {
+                Some(all) => {
+                    all.extend(&mut batch).unwrap();
+                }
+                None => all = Some(batch),
+            }
+        }
+        let all = all.unwrap();
+        // Last points of the batches are always kept.
+        assert_eq!(elided, 8);
+        assert_same_lines(&original, &all);
+    }
+
+    #[test]
+    fn dedup_keeps_points_around_gaps() {
+        let original = size_points(RUNS);
+        let gap = Range::new(
+            time::SinceStart::from_secs(1),
+            time::SinceStart::from_secs(2),
+        );
+        let mut deduped = original.clone();
+        // The values of both lines at `1s` and `2s` are kept.
+        assert_eq!(deduped.dedup(&mut None, &[gap]), 7);
+        assert_same_lines(&original, &deduped);
+    }
+
+    #[test]
+    fn dashes_follow_the_line() {
+        let dashes = dashes(&[(0., 0.), (10., 0.), (10., 10.)], (3., 2.));
+        assert_eq!(
+            dashes,
+            vec![
+                vec![(0., 0.), (3., 0.)],
+                vec![(5., 0.), (8., 0.)],
+                // Goes around the corner.
+                vec![(10., 0.), (10., 3.)],
+                vec![(10., 5.), (10., 8.)],
+            ],
+        );
+    }
+}
@@ -0,0 +1,299 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Points of churn charts.
+//!
+//! Each line of a churn chart has two sub-series, both between `0` and `1`: the churn ratio and
+//! the size spread, see [`Churn`]. Both are drawn with the color of the line, the ratio with a
+//! plain stroke and the spread with a dashed one.
+//!
+//! [`Churn`]: struct.Churn.html (The Churn struct)
+
+prelude! {}
+
+use super::{
+    draw_dashed, draw_markers, split_at_gaps, ChartRender, CoordExt, RangesExt, StyleExt,
+    TimeChurnPoints, TimeFloatPoints, DASH_PATTERN,
+};
+
+/// Number of size classes, see [`SizeClasses`].
+///
+/// [`SizeClasses`]: struct.SizeClasses.html (The SizeClasses struct)
+const CLASS_COUNT: usize = 32;
+
+/// Churn quantity: bytes allocated and freed in some time bucket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Churn {
+    /// Bytes allocated.
+    pub alloc: u64,
+    /// Bytes freed.
+    pub freed: u64,
+    /// Spread between the sizes of the allocations freed and the sizes of the allocations created,
+    /// between `0` and `1`, see [`SizeClasses::spread`].
+    ///
+    /// [`SizeClasses::spread`]: struct.SizeClasses.html#method.spread
+    /// (The spread method of SizeClasses)
+    #[serde(default)]
+    pub spread: f32,
+}
+impl Churn {
+    /// Constructor, with no spread.
+    pub fn new(alloc: u64, freed: u64) -> Self {
+        Self {
+            alloc,
+            freed,
+            spread: 0.0,
+        }
+    }
+
+    /// Ratio of the bytes freed to the bytes allocated, between `0` and `1`.
+    ///
+    /// The ratio is capped at `1`, which is also the value when nothing was allocated but some
+    /// bytes were freed.
+    ///
+    /// ```rust
+    /// # use charts::point::Churn;
+    /// assert_eq!(Churn::new(0, 0).ratio(), 0.0);
+    /// assert_eq!(Churn::new(100, 25).ratio(), 0.25);
+    /// assert_eq!(Churn::new(100, 250).ratio(), 1.0);
+    /// assert_eq!(Churn::new(0, 7).ratio(), 1.0);
+    /// ```
+    pub fn ratio(&self) -> f32 {
+        if self.alloc == 0 {
+            if self.freed == 0 {
+                0.0
+            } else {
+                1.0
+            }
+        } else if self.freed >= self.alloc {
+            1.0
+        } else {
+            (self.freed as f32) / (self.alloc as f32)
+        }
+    }
+}
+base::implement! {
+    impl Churn {
+        Display {
+            |&self, fmt| {
+                let (alloc, freed) = (
+                    num_fmt::bin_str_do(self.alloc as f64, base::identity),
+                    num_fmt::bin_str_do(self.freed as f64, base::identity),
+                );
+                write!(
+                    fmt,
+                    "{:.2} (+{}B, -{}B), spread {:.2}",
+                    self.ratio(),
+                    alloc,
+                    freed,
+                    self.spread,
+                )
+            },
+        }
+    }
+}
+
+/// Number of allocations created and freed in each size class.
+///
+/// The class of a size is its base-2 logarithm, sizes of `4GiB` and more share the last class.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeClasses {
+    /// Allocations created by class.
+    alloc: Vec<u64>,
+    /// Allocations freed by class.
+    freed: Vec<u64>,
+}
+impl SizeClasses {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self {
+            alloc: vec![0; CLASS_COUNT],
+            freed: vec![0; CLASS_COUNT],
+        }
+    }
+
+    /// Class of a size.
+    fn class_of(size: u64) -> usize {
+        let log = (64 - size.leading_zeros()).saturating_sub(1) as usize;
+        log.min(CLASS_COUNT - 1)
+    }
+
+    /// Records the creation or the death of `count` allocations totaling `bytes` bytes.
+    ///
+    /// Aggregated allocations, *e.g.* pruned ones, are all put in the class of their average size.
+    pub fn record(&mut self, add: bool, count: u64, bytes: u64) {
+        if count == 0 {
+            return;
+        }
+        let class = Self::class_of(bytes / count);
+        let classes = if add {
+            &mut self.alloc
+        } else {
+            &mut self.freed
+        };
+        classes[class] += count
+    }
+
+    /// Spread between the sizes of the allocations freed and created, between `0` and `1`.
+    ///
+    /// This is the total variation distance between the distributions of the size classes of the
+    /// allocations freed and created: `0` when both have the same distribution, `1` when no
+    /// allocation freed is in the class of an allocation created. The spread is `0` when nothing
+    /// was created or nothing was freed.
+    ///
+    /// ```rust
+    /// # use charts::point::churn::SizeClasses;
+    /// let mut classes = SizeClasses::new();
+    /// assert_eq!(classes.spread(), 0.0);
+    /// classes.record(true, 2, 16);
+    /// classes.record(true, 2, 1024);
+    /// // Same classes as the allocations created.
+    /// classes.record(false, 1, 8);
+    /// classes.record(false, 1, 512);
+    /// assert_eq!(classes.spread(), 0.0);
+    /// // Only small allocations freed.
+    /// classes.record(false, 2, 16);
+    /// assert_eq!(classes.spread(), 0.25);
+    /// ```
+    pub fn spread(&self) -> f32 {
+        let (alloc, freed): (u64, u64) = (self.alloc.iter().sum(), self.freed.iter().sum());
+        if alloc == 0 || freed == 0 {
+            return 0.0;
+        }
+        let distance: f64 = self
+            .alloc
+            .iter()
+            .zip(self.freed.iter())
+            .map(|(a, f)| ((*a as f64) / (alloc as f64) - (*f as f64) / (freed as f64)).abs())
+            .sum();
+        (distance / 2.0).min(1.0) as f32
+    }
+
+    /// Forgets all the allocations recorded.
+    pub fn clear(&mut self) {
+        self.alloc.iter_mut().for_each(|count| *count = 0);
+        self.freed.iter_mut().for_each(|count| *count = 0);
+    }
+}
+
+/// Splits churn points into churn ratio and size spread points.
+///
+/// ```rust
+/// # use charts::{prelude::*, point::*};
+/// let mut vals = PointVal::empty();
+/// let mut churn = Churn::new(100, 25);
+/// churn.spread = 0.5;
+/// vals.map.insert(uid::Line::Everything, churn);
+/// let points: TimeChurnPoints = vec![Point::new(time::SinceStart::from_secs(1), vals)];
+/// let (ratio, spread) = churn::split(&points);
+/// assert_eq!(ratio[0].vals.map[&uid::Line::Everything], 0.25);
+/// assert_eq!(spread[0].vals.map[&uid::Line::Everything], 0.5);
+/// ```
+pub fn split(points: &TimeChurnPoints) -> (TimeFloatPoints, TimeFloatPoints) {
+    let sub = |spread: bool| -> TimeFloatPoints {
+        points
+            .iter()
+            .map(|point| {
+                let mut vals = PointVal::empty();
+                for (line, churn) in point.vals.map.iter() {
+                    let val = if spread { churn.spread } else { churn.ratio() };
+                    vals.map.insert(*line, val);
+                }
+                Point::new(point.key, vals)
+            })
+            .collect()
+    };
+    (sub(false), sub(true))
+}
+
+/// Renders churn points.
+///
+/// Churn charts have no stacked-area modes: both sub-series of all the lines are always drawn as
+/// lines.
+pub fn render<'spec, DB>(
+    points: &TimeChurnPoints,
+    mut chart_builder: plotters::prelude::ChartBuilder<DB>,
+    style_conf: &impl StyleExt,
+    is_active: impl Fn(uid::Line) -> bool,
+    active_filters: impl Iterator<Item = &'spec filter::FilterSpec>,
+    gaps: &[TimeWindow],
+) -> Res<()>
+where
+    DB: plotters::prelude::DrawingBackend,
+{
+    use plotters::prelude::*;
+
+    type XRange = <time::SinceStart as CoordExt>::Range;
+    type YRange = <Churn as CoordExt>::Range;
+
+    let ranges = points.ranges(&is_active);
+    let raw_ranges = <TimeChurnPoints as ChartRender<_, _>>::ranges_processor(ranges)?;
+    let ranges = <TimeChurnPoints as ChartRender<_, _>>::coord_ranges_processor(&raw_ranges)?;
+
+    let x_range: XRange = (ranges.x.lbound..ranges.x.ubound).into();
+    let y_range: YRange = (ranges.y.lbound..ranges.y.ubound).into();
+
+    let mut chart_cxt: ChartContext<DB, coord::Cartesian2d<XRange, YRange>> = chart_builder
+        .build_cartesian_2d(x_range, y_range)
+        .map_err(|e| e.to_string())?;
+
+    {
+        let mut mesh = chart_cxt.configure_mesh();
+        style_conf.mesh_conf::<time::SinceStart, Churn, DB>(&mut mesh);
+        let x_label_formatter =
+            |x: &_| <TimeChurnPoints as ChartRender<_, _>>::x_label_formatter(x, style_conf);
+        mesh.x_label_formatter(&x_label_formatter)
+            .y_label_formatter(&<TimeChurnPoints as ChartRender<_, _>>::y_label_formatter)
+            .draw()
+            .map_err(|e| e.to_string())?;
+    }
+
+    for filter_spec in active_filters {
+        let f_uid = filter_spec.uid();
+        let style = style_conf.shape_conf(filter_spec.color());
+        let pattern = style_conf.pattern(f_uid);
+
+        for dashed in [false, true].iter() {
+            let coords = points.iter().filter_map(|point| {
+                point.vals.map.get(&f_uid).map(|val| {
+                    let x = <TimeChurnPoints as ChartRender<_, _>>::x_coord_processor(
+                        &raw_ranges.x,
+                        &point.key,
+                    );
+                    let y = if *dashed { val.spread } else { val.ratio() };
+                    (&point.key, (x, y))
+                })
+            });
+            for segment in split_at_gaps(coords, gaps) {
+                if let Some(pattern) = pattern {
+                    draw_markers(&chart_cxt, &segment, &style, pattern.marker)?
+                }
+                if *dashed {
+                    draw_dashed(&chart_cxt, &segment, &style, DASH_PATTERN)?
+                } else {
+                    chart_cxt
+                        .draw_series(LineSeries::new(segment, style.clone()))
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
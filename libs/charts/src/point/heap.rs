@@ -0,0 +1,190 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Points of heap charts.
+//!
+//! Each line of a heap chart has two sub-series: the live size on the minor heap and the live size
+//! on the major heap, see [`HeapSize`]. Both are drawn with the color of the line, the major heap
+//! with a plain stroke and the minor heap with a dashed one.
+//!
+//! [`HeapSize`]: struct.HeapSize.html (The HeapSize struct)
+
+prelude! {}
+
+use super::{
+    draw_dashed, draw_markers, split_at_gaps, ChartRender, CoordExt, Ranges, RangesExt, Size,
+    StyleExt, TimeHeapPoints, TimeSizePoints, DASH_PATTERN,
+};
+
+/// Live size on the minor and major heaps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct HeapSize {
+    /// Live size on the minor heap.
+    pub minor: u64,
+    /// Live size on the major heap.
+    pub major: u64,
+}
+
+impl HeapSize {
+    /// Constructor.
+    pub fn new(minor: u64, major: u64) -> Self {
+        Self { minor, major }
+    }
+
+    /// Live size on both heaps.
+    pub fn total(&self) -> u64 {
+        self.minor + self.major
+    }
+
+    /// Mutable reference to the live size on one of the heaps.
+    pub fn heap_mut(&mut self, major: bool) -> &mut u64 {
+        if major {
+            &mut self.major
+        } else {
+            &mut self.minor
+        }
+    }
+}
+
+base::implement! {
+    impl HeapSize {
+        Display {
+            |&self, fmt| write!(fmt, "{} (minor), {} (major)", self.minor, self.major),
+        }
+    }
+}
+
+/// Splits heap points into minor-heap and major-heap size points.
+///
+/// ```rust
+/// # use charts::{prelude::*, point::{*, heap::*}};
+/// let mut vals = PointVal::empty();
+/// vals.map.insert(uid::Line::Everything, HeapSize::new(3, 7));
+/// let points: TimeHeapPoints = vec![Point::new(time::SinceStart::from_secs(1), vals)];
+/// let (minor, major) = split(&points);
+/// assert_eq!(minor[0].vals.map[&uid::Line::Everything], Size::new(3u64));
+/// assert_eq!(major[0].vals.map[&uid::Line::Everything], Size::new(7u64));
+/// ```
+pub fn split(points: &TimeHeapPoints) -> (TimeSizePoints, TimeSizePoints) {
+    let heap = |major: bool| -> TimeSizePoints {
+        points
+            .iter()
+            .map(|point| {
+                let mut vals = PointVal::empty();
+                for (line, size) in point.vals.map.iter() {
+                    let size = if major { size.major } else { size.minor };
+                    vals.map.insert(*line, Size::new(size));
+                }
+                Point::new(point.key, vals)
+            })
+            .collect()
+    };
+    (heap(false), heap(true))
+}
+
+/// Renders heap points.
+///
+/// Heap charts have no stacked-area modes: both heaps of all the lines are always drawn as lines.
+pub fn render<'spec, DB>(
+    points: &TimeHeapPoints,
+    mut chart_builder: plotters::prelude::ChartBuilder<DB>,
+    style_conf: &impl StyleExt,
+    is_active: impl Fn(uid::Line) -> bool,
+    active_filters: impl Iterator<Item = &'spec filter::FilterSpec>,
+    gaps: &[TimeWindow],
+) -> Res<()>
+where
+    DB: plotters::prelude::DrawingBackend,
+{
+    use plotters::prelude::*;
+
+    type XRange = <time::SinceStart as CoordExt>::Range;
+    type YRange = <Size as CoordExt>::Range;
+
+    let (minor, major) = split(points);
+
+    let ranges = {
+        let (minor, major) = (minor.ranges(&is_active), major.ranges(&is_active));
+        let y = Range::new(
+            minor.y.lbound.into_iter().chain(major.y.lbound).min(),
+            minor.y.ubound.into_iter().chain(major.y.ubound).max(),
+        );
+        Ranges::new(minor.x, y)
+    };
+    let raw_ranges = <TimeSizePoints as ChartRender<_, _>>::ranges_processor(ranges)?;
+    let ranges = <TimeSizePoints as ChartRender<_, _>>::coord_ranges_processor(&raw_ranges)?;
+
+    let x_range: XRange = (ranges.x.lbound..ranges.x.ubound).into();
+    let y_range: YRange = (ranges.y.lbound..ranges.y.ubound).into();
+
+    let mut chart_cxt: ChartContext<DB, coord::Cartesian2d<XRange, YRange>> = chart_builder
+        .build_cartesian_2d(x_range, y_range)
+        .map_err(|e| e.to_string())?;
+
+    {
+        let mut mesh = chart_cxt.configure_mesh();
+        style_conf.mesh_conf::<time::SinceStart, Size, DB>(&mut mesh);
+        let x_label_formatter =
+            |x: &_| <TimeSizePoints as ChartRender<_, _>>::x_label_formatter(x, style_conf);
+        mesh.x_label_formatter(&x_label_formatter)
+            .y_label_formatter(&<TimeSizePoints as ChartRender<_, _>>::y_label_formatter)
+            .draw()
+            .map_err(|e| e.to_string())?;
+    }
+
+    for filter_spec in active_filters {
+        let f_uid = filter_spec.uid();
+        let style = style_conf.shape_conf(filter_spec.color());
+        let pattern = style_conf.pattern(f_uid);
+
+        for (heap, dashed) in [(&major, false), (&minor, true)].iter() {
+            let coords = heap.iter().filter_map(|point| {
+                point.vals.map.get(&f_uid).map(|val| {
+                    (
+                        &point.key,
+                        (
+                            <TimeSizePoints as ChartRender<_, _>>::x_coord_processor(
+                                &raw_ranges.x,
+                                &point.key,
+                            ),
+                            <TimeSizePoints as ChartRender<_, _>>::y_coord_processor(
+                                &raw_ranges.y,
+                                val,
+                            ),
+                        ),
+                    )
+                })
+            });
+            for segment in split_at_gaps(coords, gaps) {
+                if let Some(pattern) = pattern {
+                    draw_markers(&chart_cxt, &segment, &style, pattern.marker)?
+                }
+                if *dashed {
+                    draw_dashed(&chart_cxt, &segment, &style, DASH_PATTERN)?
+                } else {
+                    chart_cxt
+                        .draw_series(LineSeries::new(segment, style.clone()))
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,431 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Maps from line UIDs to values.
+//!
+//! Most sessions have zero or one custom filter, in which case a point only has values for the
+//! filter, the catch-all line and the *everything* line. A [`LineMap`] stores these values in
+//! fixed slots, and only switches to an actual map when it needs to store values for two custom
+//! filters or more. Going back to slots is automatic when a map is [rebuilt] for at most one
+//! filter, which is what charts do when the filters change.
+//!
+//! Both representations serialize exactly as a `BTMap<uid::Line, Val>` would, so that the messages
+//! the server sends do not depend on the representation.
+//!
+//! ```rust
+//! # use charts::point::LineMap;
+//! # use base::prelude::*;
+//! let (f_1, f_2) = (uid::Line::Filter(uid::Filter::fresh()), uid::Line::Filter(uid::Filter::fresh()));
+//! let mut map = LineMap::new();
+//! map.insert(uid::Line::Everything, 3);
+//! map.insert(f_1, 1);
+//! map.insert(uid::Line::CatchAll, 2);
+//! assert!(map.is_small());
+//! assert_eq!(map.iter().map(|(_, val)| *val).collect::<Vec<_>>(), vec![1, 2, 3]);
+//!
+//! map.insert(f_2, 7);
+//! assert!(!map.is_small());
+//! assert_eq!(map.len(), 4);
+//!
+//! map.remove(&f_1);
+//! let rebuilt: LineMap<_> = map.into_iter().collect();
+//! assert!(rebuilt.is_small());
+//! assert_eq!(rebuilt[&f_2], 7);
+//! ```
+//!
+//! [`LineMap`]: struct.LineMap.html (The LineMap struct)
+//! [rebuilt]: struct.LineMap.html#impl-FromIterator%3C(Line%2C%20Val)%3E (FromIterator for LineMap)
+
+prelude! {}
+
+use std::collections::btree_map;
+
+/// Number of slots of the small representation.
+const SLOT_COUNT: usize = 3;
+
+/// Slot of a line in the small representation, if any.
+///
+/// Slots follow the order of line UIDs: filters, then the catch-all line, then *everything*.
+/// Segment lines have no slot, maps with segment lines always use the general representation.
+fn slot_of(line: &uid::Line) -> Option<usize> {
+    match line {
+        uid::Line::Filter(_) => Some(0),
+        uid::Line::CatchAll => Some(1),
+        uid::Line::Everything => Some(2),
+        uid::Line::Segment(_) => None,
+    }
+}
+
+/// Actual representation of a line map.
+#[derive(Debug, Clone)]
+enum Repr<Val> {
+    /// At most one custom filter, values are in their line's slot.
+    Small([Option<(uid::Line, Val)>; SLOT_COUNT]),
+    /// Any number of custom filters.
+    Large(BTMap<uid::Line, Val>),
+}
+
+/// A map from line UIDs to values.
+///
+/// See the [module-level documentation] for details.
+///
+/// [module-level documentation]: index.html (The line_map module)
+#[derive(Debug, Clone)]
+pub struct LineMap<Val> {
+    /// Representation.
+    repr: Repr<Val>,
+}
+
+impl<Val> Default for LineMap<Val> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Val> LineMap<Val> {
+    /// Empty constructor.
+    pub fn new() -> Self {
+        Self {
+            repr: Repr::Small([None, None, None]),
+        }
+    }
+
+    /// Constructor for the general representation, regardless of the number of filters.
+    pub fn new_large() -> Self {
+        Self {
+            repr: Repr::Large(BTMap::new()),
+        }
+    }
+
+    /// True if the map uses the representation for at most one filter.
+    pub fn is_small(&self) -> bool {
+        match self.repr {
+            Repr::Small(_) => true,
+            Repr::Large(_) => false,
+        }
+    }
+
+    /// Number of values in the map.
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Small(slots) => slots.iter().filter(|slot| slot.is_some()).count(),
+            Repr::Large(map) => map.len(),
+        }
+    }
+
+    /// True if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        match &self.repr {
+            Repr::Small(slots) => slots.iter().all(Option::is_none),
+            Repr::Large(map) => map.is_empty(),
+        }
+    }
+
+    /// Switches to the general representation.
+    fn make_large(&mut self) -> &mut BTMap<uid::Line, Val> {
+        if let Repr::Small(slots) = &mut self.repr {
+            let map = slots.iter_mut().filter_map(Option::take).collect();
+            self.repr = Repr::Large(map)
+        }
+        match &mut self.repr {
+            Repr::Large(map) => map,
+            Repr::Small(_) => unreachable!("small line map right after switching to large"),
+        }
+    }
+
+    /// Retrieves the value for a line.
+    pub fn get(&self, line: &uid::Line) -> Option<&Val> {
+        match &self.repr {
+            Repr::Small(slots) => match slot_of(line).and_then(|slot| slots[slot].as_ref()) {
+                Some((key, val)) if key == line => Some(val),
+                _ => None,
+            },
+            Repr::Large(map) => map.get(line),
+        }
+    }
+
+    /// Retrieves the value for a line, mutable version.
+    pub fn get_mut(&mut self, line: &uid::Line) -> Option<&mut Val> {
+        match &mut self.repr {
+            Repr::Small(slots) => match slot_of(line).and_then(move |slot| slots[slot].as_mut()) {
+                Some((key, val)) if key == line => Some(val),
+                _ => None,
+            },
+            Repr::Large(map) => map.get_mut(line),
+        }
+    }
+
+    /// True if the map has a value for some line.
+    pub fn contains_key(&self, line: &uid::Line) -> bool {
+        self.get(line).is_some()
+    }
+
+    /// Inserts a value for a line, returns the previous value if any.
+    pub fn insert(&mut self, line: uid::Line, val: Val) -> Option<Val> {
+        if let (Repr::Small(slots), Some(slot)) = (&mut self.repr, slot_of(&line)) {
+            let slot = &mut slots[slot];
+            match slot {
+                Some((key, _)) if *key != line => (),
+                _ => return slot.replace((line, val)).map(|(_, prev)| prev),
+            }
+        }
+        self.make_large().insert(line, val)
+    }
+
+    /// Retrieves the value for a line, inserting one if there is none.
+    pub fn get_or_insert_with(
+        &mut self,
+        line: uid::Line,
+        default: impl FnOnce() -> Val,
+    ) -> &mut Val {
+        if !self.contains_key(&line) {
+            let prev = self.insert(line, default());
+            debug_assert!(prev.is_none());
+        }
+        self.get_mut(&line)
+            .expect("line map cannot lack a value right after its insertion")
+    }
+
+    /// Removes the value for a line.
+    pub fn remove(&mut self, line: &uid::Line) -> Option<Val> {
+        match &mut self.repr {
+            Repr::Small(slots) => match slot_of(line).map(|slot| &mut slots[slot]) {
+                Some(slot) => match slot {
+                    Some((key, _)) if key == line => slot.take().map(|(_, val)| val),
+                    _ => None,
+                },
+                None => None,
+            },
+            Repr::Large(map) => map.remove(line),
+        }
+    }
+
+    /// Only keeps the values verifying some predicate.
+    pub fn retain(&mut self, mut keep: impl FnMut(&uid::Line, &mut Val) -> bool) {
+        match &mut self.repr {
+            Repr::Small(slots) => {
+                for slot in slots.iter_mut() {
+                    if let Some((line, val)) = slot {
+                        if !keep(line, val) {
+                            *slot = None
+                        }
+                    }
+                }
+            }
+            Repr::Large(map) => map.retain(keep),
+        }
+    }
+
+    /// Iterator over the lines and their values, in increasing line order.
+    pub fn iter(&self) -> Iter<'_, Val> {
+        match &self.repr {
+            Repr::Small(slots) => Iter::Small(slots.iter()),
+            Repr::Large(map) => Iter::Large(map.iter()),
+        }
+    }
+
+    /// Iterator over the lines, in increasing order.
+    pub fn keys(&self) -> impl Iterator<Item = &uid::Line> {
+        self.iter().map(|(line, _)| line)
+    }
+
+    /// Iterator over the values, in increasing line order.
+    pub fn values(&self) -> impl Iterator<Item = &Val> {
+        self.iter().map(|(_, val)| val)
+    }
+}
+
+impl<Val: PartialEq> PartialEq for LineMap<Val> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl<Val: Eq> Eq for LineMap<Val> {}
+
+impl<'a, Val> std::ops::Index<&'a uid::Line> for LineMap<Val> {
+    type Output = Val;
+    fn index(&self, line: &'a uid::Line) -> &Val {
+        self.get(line)
+            .unwrap_or_else(|| panic!("unknown line uid `{}`", line))
+    }
+}
+
+impl<Val> std::iter::FromIterator<(uid::Line, Val)> for LineMap<Val> {
+    fn from_iter<I: IntoIterator<Item = (uid::Line, Val)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (line, val) in iter {
+            map.insert(line, val);
+        }
+        map
+    }
+}
+
+impl<Val> From<BTMap<uid::Line, Val>> for LineMap<Val> {
+    fn from(map: BTMap<uid::Line, Val>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+/// Iterator over the lines and values of a line map.
+pub enum Iter<'a, Val> {
+    /// Small representation.
+    Small(std::slice::Iter<'a, Option<(uid::Line, Val)>>),
+    /// General representation.
+    Large(btree_map::Iter<'a, uid::Line, Val>),
+}
+impl<'a, Val> Iterator for Iter<'a, Val> {
+    type Item = (&'a uid::Line, &'a Val);
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Small(slots) => slots
+                .find_map(|slot| slot.as_ref())
+                .map(|(line, val)| (line, val)),
+            Self::Large(iter) => iter.next(),
+        }
+    }
+}
+
+/// Owning iterator over the lines and values of a line map.
+pub enum IntoIter<Val> {
+    /// Small representation.
+    Small(std::array::IntoIter<Option<(uid::Line, Val)>, SLOT_COUNT>),
+    /// General representation.
+    Large(btree_map::IntoIter<uid::Line, Val>),
+}
+impl<Val> Iterator for IntoIter<Val> {
+    type Item = (uid::Line, Val);
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Small(slots) => slots.find_map(|slot| slot),
+            Self::Large(iter) => iter.next(),
+        }
+    }
+}
+
+impl<Val> IntoIterator for LineMap<Val> {
+    type Item = (uid::Line, Val);
+    type IntoIter = IntoIter<Val>;
+    fn into_iter(self) -> IntoIter<Val> {
+        match self.repr {
+            Repr::Small(slots) => IntoIter::Small(IntoIterator::into_iter(slots)),
+            Repr::Large(map) => IntoIter::Large(map.into_iter()),
+        }
+    }
+}
+impl<'a, Val> IntoIterator for &'a LineMap<Val> {
+    type Item = (&'a uid::Line, &'a Val);
+    type IntoIter = Iter<'a, Val>;
+    fn into_iter(self) -> Iter<'a, Val> {
+        self.iter()
+    }
+}
+
+impl<Val: ::serde::Serialize> ::serde::Serialize for LineMap<Val> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        use ::serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (line, val) in self.iter() {
+            map.serialize_entry(line, val)?
+        }
+        map.end()
+    }
+}
+impl<'de, Val: ::serde::Deserialize<'de>> ::serde::Deserialize<'de> for LineMap<Val> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        <BTMap<uid::Line, Val> as ::serde::Deserialize>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Applies the same operations to a small map and a large one, checks they agree.
+    #[test]
+    fn golden() {
+        let filters: Vec<_> = (0..3)
+            .map(|_| uid::Line::Filter(uid::Filter::fresh()))
+            .collect();
+        let (mut small, mut large) = (LineMap::new(), LineMap::new_large());
+        let check = |small: &LineMap<u64>, large: &LineMap<u64>| {
+            assert_eq!(small, large);
+            assert_eq!(msg::encode(small).unwrap(), msg::encode(large).unwrap());
+            assert_eq!(
+                serde_json::to_string(small).unwrap(),
+                serde_json::to_string(large).unwrap(),
+            );
+        };
+
+        for (cnt, line) in vec![uid::Line::Everything, uid::Line::CatchAll, filters[0]]
+            .into_iter()
+            .enumerate()
+        {
+            small.insert(line, cnt as u64);
+            large.insert(line, cnt as u64);
+            check(&small, &large)
+        }
+        *small.get_or_insert_with(filters[0], || 0) += 7;
+        *large.get_or_insert_with(filters[0], || 0) += 7;
+        check(&small, &large);
+        assert!(small.is_small());
+
+        // A second filter, the small map switches representation.
+        small.insert(filters[1], 42);
+        large.insert(filters[1], 42);
+        check(&small, &large);
+        assert!(!small.is_small());
+
+        small.retain(|line, _| *line != filters[0]);
+        large.retain(|line, _| *line != filters[0]);
+        check(&small, &large);
+
+        // Rebuilding goes back to slots.
+        let (small, large): (LineMap<_>, LineMap<_>) = (small.into_iter().collect(), large.clone());
+        assert!(small.is_small());
+        check(&small, &large);
+
+        // And the serialized map decodes as either.
+        let bytes = msg::encode(&large).unwrap();
+        let decoded: LineMap<u64> = msg::decode(&bytes).unwrap();
+        assert!(decoded.is_small());
+        check(&decoded, &large);
+        let map: BTMap<uid::Line, u64> = msg::decode(&bytes).unwrap();
+        assert_eq!(msg::encode(&map).unwrap(), bytes);
+
+        // Segment lines have no slot.
+        let (mut small, mut large) = (decoded, large);
+        let segment = uid::Line::Segment(uid::Segment::from(3));
+        small.insert(segment, 5);
+        large.insert(segment, 5);
+        assert!(!small.is_small());
+        check(&small, &large);
+        assert!(serde_json::to_string(&small)
+            .unwrap()
+            .contains("\"segment_3\":5"));
+        let decoded: LineMap<u64> =
+            serde_json::from_str(&serde_json::to_string(&small).unwrap()).unwrap();
+        assert_eq!(decoded[&segment], 5);
+    }
+}
@@ -0,0 +1,514 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Points of scatter charts.
+//!
+//! Scatter charts do not draw lines, each point is a dead allocation with its lifetime on the
+//! x-axis and its size on the y-axis, both logarithmic. The server only sends a bounded [`Sample`]
+//! of the dead allocations of each line, and always sends whole samples: new [`ScatterPoints`]
+//! replace the previous ones instead of extending them.
+//!
+//! [`Sample`]: struct.Sample.html (The Sample struct)
+//! [`ScatterPoints`]: struct.ScatterPoints.html (The ScatterPoints struct)
+
+prelude! {}
+
+use point::{CoordExt, Ranges, StyleExt};
+
+/// Logarithmic coordinates, built from a plain range.
+///
+/// Plotters' logarithmic coordinates cannot be built from a range directly, which [`CoordExt`]
+/// requires.
+///
+/// [`CoordExt`]: ../trait.CoordExt.html (The CoordExt trait)
+pub struct LogAxis(coord::LogCoord<f64>);
+impl From<std::ops::Range<f64>> for LogAxis {
+    fn from(range: std::ops::Range<f64>) -> Self {
+        Self(coord::LogRange(range).into())
+    }
+}
+impl coord::Ranged for LogAxis {
+    type FormatOption = coord::DefaultFormatting;
+    type ValueType = f64;
+    fn map(&self, value: &f64, limit: (i32, i32)) -> i32 {
+        self.0.map(value, limit)
+    }
+    fn key_points<Hint: coord::KeyPointHint>(&self, hint: Hint) -> Vec<f64> {
+        self.0.key_points(hint)
+    }
+    fn range(&self) -> std::ops::Range<f64> {
+        self.0.range()
+    }
+}
+
+/// Values of a logarithmic axis.
+#[derive(Debug, Clone, Copy)]
+pub struct LogScale;
+impl CoordExt for LogScale {
+    type Coord = f64;
+    type Range = LogAxis;
+    fn default_val() -> Self {
+        LogScale
+    }
+    fn zero() -> f64 {
+        0.
+    }
+    fn is_zero(val: &f64) -> bool {
+        *val == 0.
+    }
+    fn default_min() -> f64 {
+        1.
+    }
+    fn default_max() -> f64 {
+        10.
+    }
+}
+
+/// A sampled dead allocation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScatterPoint {
+    /// UID of the allocation.
+    pub alloc: uid::Alloc,
+    /// Lifetime of the allocation.
+    pub lifetime: time::Lifetime,
+    /// Size of the allocation, in bytes.
+    pub size: u32,
+    /// Top frame of the allocation-site callstack, if any.
+    pub top_frame: Option<String>,
+}
+impl ScatterPoint {
+    /// Smallest lifetime shown, in seconds, lifetimes of `0` would be infinitely far on a log scale.
+    const MIN_SECS: f64 = 1e-9;
+
+    /// Constructor.
+    pub fn new(
+        alloc: uid::Alloc,
+        lifetime: time::Lifetime,
+        size: u32,
+        top_frame: Option<String>,
+    ) -> Self {
+        Self {
+            alloc,
+            lifetime,
+            size,
+            top_frame,
+        }
+    }
+
+    /// Constructor from an allocation and its time of death.
+    pub fn of_dead(alloc: &Alloc, tod: time::SinceStart) -> Self {
+        let lifetime = (tod - alloc.toc).to_lifetime();
+        let top_frame = alloc.alloc_site_do(|site| site.map(|site| site.loc.to_string()));
+        Self::new(alloc.uid, lifetime, alloc.real_size, top_frame)
+    }
+
+    /// X-axis coordinate: lifetime in seconds.
+    pub fn x(&self) -> f64 {
+        self.lifetime.as_secs_f64().max(Self::MIN_SECS)
+    }
+    /// Y-axis coordinate: size in bytes.
+    pub fn y(&self) -> f64 {
+        (self.size as f64).max(1.)
+    }
+
+    /// Multi-line description, for tooltips.
+    ///
+    /// ```rust
+    /// # use charts::point::scatter::ScatterPoint;
+    /// # use base::prelude::*;
+    /// let lifetime = time::Duration::new(0, 1_500_000).into();
+    /// let point = ScatterPoint::new(uid::Alloc::from(7usize), lifetime, 2048, None);
+    /// assert_eq!(
+    ///     point.desc(),
+    ///     "allocation #7\nsize: 2.00KiB\nlifetime: 1.5ms\ntop frame: unknown",
+    /// );
+    /// ```
+    pub fn desc(&self) -> String {
+        format!(
+            "allocation #{}\nsize: {}B\nlifetime: {}\ntop frame: {}",
+            self.alloc,
+            num_fmt::bin_str_do(self.size, base::identity),
+            self.lifetime.fmt_human(),
+            self.top_frame.as_deref().unwrap_or("unknown"),
+        )
+    }
+}
+
+/// Sample of the dead allocations of a line.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// Number of dead allocations the sample was drawn from.
+    pub seen: u64,
+    /// Sampled allocations.
+    pub points: Vec<ScatterPoint>,
+}
+impl Sample {
+    /// Empty sample.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Offers an allocation to the sample.
+    ///
+    /// Reservoir sampling: once `capacity` allocations have been offered, each allocation offered
+    /// replaces a random sampled allocation with probability `capacity / seen`. So, the sample is
+    /// always a uniform sample of the allocations offered so far.
+    #[cfg(any(test, feature = "server"))]
+    pub fn offer(&mut self, point: ScatterPoint, capacity: usize, rng: &mut impl base::rand::Rng) {
+        self.seen += 1;
+        if self.points.len() < capacity {
+            self.points.push(point)
+        } else {
+            let idx = rng.gen_range(0, self.seen);
+            if idx < capacity as u64 {
+                self.points[idx as usize] = point
+            }
+        }
+    }
+}
+
+/// Points for a scatter chart: a sample of dead allocations for each line.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScatterPoints {
+    /// Samples of the lines.
+    samples: BTMap<uid::Line, Sample>,
+}
+impl ScatterPoints {
+    /// Radius around a point in which the mouse hovers it, in pixels.
+    pub const HOVER_RADIUS: f64 = 8.;
+    /// Radius of the points, in pixels.
+    const POINT_SIZE: u32 = 3;
+
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sample of a line, created if needed.
+    pub fn sample_mut(&mut self, line: uid::Line) -> &mut Sample {
+        self.samples.entry(line).or_insert_with(Sample::new)
+    }
+    /// Sample of a line, if any.
+    pub fn sample(&self, line: uid::Line) -> Option<&Sample> {
+        self.samples.get(&line)
+    }
+
+    /// True if there are no points.
+    pub fn is_empty(&self) -> bool {
+        self.samples.values().all(|sample| sample.points.is_empty())
+    }
+    /// Total number of points.
+    pub fn len(&self) -> usize {
+        self.samples
+            .values()
+            .map(|sample| sample.points.len())
+            .sum()
+    }
+
+    /// Lines that have at least one point.
+    pub fn lines(&self) -> BTSet<uid::Line> {
+        self.samples
+            .iter()
+            .filter(|(_, sample)| !sample.points.is_empty())
+            .map(|(line, _)| *line)
+            .collect()
+    }
+
+    /// Only keeps the samples of the lines verifying some predicate.
+    pub fn retain_lines(&mut self, keep: impl Fn(uid::Line) -> bool) {
+        let samples = std::mem::replace(&mut self.samples, BTMap::new());
+        self.samples = samples
+            .into_iter()
+            .filter(|(line, _)| keep(*line))
+            .collect()
+    }
+
+    /// Replaces the points by some new points, returns `true` if they changed.
+    ///
+    /// Scatter points are always sent whole, see the [module-level documentation](index.html).
+    pub fn extend(&mut self, other: &mut Self) -> bool {
+        if self == other {
+            false
+        } else {
+            std::mem::swap(&mut self.samples, &mut other.samples);
+            true
+        }
+    }
+
+    /// Description of the sample of some line, if any.
+    pub fn last_val_desc(&self, line: uid::Line) -> Option<String> {
+        self.samples.get(&line).map(|sample| {
+            format!(
+                "{} sampled out of {} dead allocation(s)",
+                sample.points.len(),
+                sample.seen
+            )
+        })
+    }
+
+    /// Flattens the points to one list of `(lifetime, size)` pairs per line, sorted.
+    ///
+    /// Lifetimes are in seconds, sizes in bytes.
+    pub fn series(&self) -> BTMap<uid::Line, Vec<(f64, f64)>> {
+        self.samples
+            .iter()
+            .map(|(line, sample)| {
+                let mut series: Vec<_> = sample
+                    .points
+                    .iter()
+                    .map(|point| (point.lifetime.as_secs_f64(), point.size as f64))
+                    .collect();
+                series
+                    .sort_by(|lft, rgt| lft.partial_cmp(rgt).unwrap_or(std::cmp::Ordering::Equal));
+                (*line, series)
+            })
+            .collect()
+    }
+
+    /// Ranges of the axes for the active lines.
+    ///
+    /// Ranges are padded by a factor of `2` on each side, so that no point is on an axis.
+    ///
+    /// ```rust
+    /// # use charts::point::scatter::*;
+    /// # use base::prelude::*;
+    /// let mut points = ScatterPoints::new();
+    /// for (secs, size) in vec![(1, 8), (4, 64)] {
+    ///     let lifetime = time::Duration::new(secs, 0).into();
+    ///     let point = ScatterPoint::new(uid::Alloc::from(0usize), lifetime, size, None);
+    ///     points.sample_mut(uid::Line::Everything).points.push(point);
+    /// }
+    /// let ranges = points.ranges(|_| true);
+    /// assert_eq!((ranges.x.lbound, ranges.x.ubound), (0.5, 8.));
+    /// assert_eq!((ranges.y.lbound, ranges.y.ubound), (4., 128.));
+    /// ```
+    pub fn ranges(&self, is_active: impl Fn(uid::Line) -> bool) -> Ranges<f64, f64> {
+        let mut x: Option<(f64, f64)> = None;
+        let mut y: Option<(f64, f64)> = None;
+        let points = self
+            .samples
+            .iter()
+            .filter(|(line, _)| is_active(**line))
+            .flat_map(|(_, sample)| sample.points.iter());
+        for point in points {
+            let update = |range: &mut Option<(f64, f64)>, val: f64| {
+                *range = Some(match *range {
+                    Some((min, max)) => (min.min(val), max.max(val)),
+                    None => (val, val),
+                })
+            };
+            update(&mut x, point.x());
+            update(&mut y, point.y())
+        }
+        let pad = |range: Option<(f64, f64)>| {
+            let (min, max) = range.unwrap_or((LogScale::default_min(), LogScale::default_max()));
+            Range::new(min / 2., max * 2.)
+        };
+        Ranges::new(pad(x), pad(y))
+    }
+
+    /// Pixel position of a point, given the ranges of the axes and the pixels of the plotting area.
+    ///
+    /// Matches how plotters maps logarithmic coordinates, up to rounding.
+    fn pixel_of(
+        ranges: &Ranges<f64, f64>,
+        area: &Ranges<f64, f64>,
+        point: &ScatterPoint,
+    ) -> (f64, f64) {
+        let ratio = |range: &Range<f64>, val: f64| {
+            (val.ln() - range.lbound.ln()) / (range.ubound.ln() - range.lbound.ln())
+        };
+        (
+            area.x.lbound + ratio(&ranges.x, point.x()) * (area.x.ubound - area.x.lbound),
+            area.y.ubound - ratio(&ranges.y, point.y()) * (area.y.ubound - area.y.lbound),
+        )
+    }
+
+    /// Point closest to a pixel position, if any is in the [hover radius].
+    ///
+    /// `area` is the pixels of the plotting area, the part of the chart inside the axes.
+    ///
+    /// [hover radius]: #associatedconstant.HOVER_RADIUS (HOVER_RADIUS constant)
+    pub fn closest(
+        &self,
+        is_active: impl Fn(uid::Line) -> bool,
+        area: &Ranges<f64, f64>,
+        (x, y): (f64, f64),
+    ) -> Option<(uid::Line, &ScatterPoint)> {
+        let ranges = self.ranges(&is_active);
+        let mut closest = None;
+        let mut best = Self::HOVER_RADIUS;
+        for (line, sample) in self.samples.iter() {
+            if !is_active(*line) {
+                continue;
+            }
+            for point in &sample.points {
+                let (p_x, p_y) = Self::pixel_of(&ranges, area, point);
+                let dist = (p_x - x).hypot(p_y - y);
+                if dist <= best {
+                    best = dist;
+                    closest = Some((*line, point))
+                }
+            }
+        }
+        closest
+    }
+
+    /// X-axis label formatter.
+    ///
+    /// Rounds to the nanosecond first, so that ticks such as `0.7` do not show as `699.999999ms`.
+    fn x_label_formatter(secs: &f64) -> String {
+        time::Duration::from_nanos((secs * 1e9).round() as u64)
+            .fmt_human()
+            .to_string()
+    }
+    /// Y-axis label formatter.
+    fn y_label_formatter(size: &f64) -> String {
+        let mut s = num_fmt::bin_str_do(*size, base::identity);
+        s.push('B');
+        s
+    }
+
+    /// Renders the points on a graph.
+    ///
+    /// In accessible mode, points are drawn with the marker of their line's pattern.
+    pub fn render<'spec, DB>(
+        &self,
+        _settings: &settings::Chart,
+        mut chart_builder: plotters::prelude::ChartBuilder<DB>,
+        style_conf: &impl StyleExt,
+        is_active: impl Fn(uid::Line) -> bool,
+        active_filters: impl Iterator<Item = &'spec filter::FilterSpec>,
+    ) -> Res<()>
+    where
+        DB: plotters::prelude::DrawingBackend,
+    {
+        use plotters::prelude::*;
+
+        let ranges = self.ranges(&is_active);
+        let x_range = LogAxis::from(ranges.x.lbound..ranges.x.ubound);
+        let y_range = LogAxis::from(ranges.y.lbound..ranges.y.ubound);
+
+        let mut chart_cxt: ChartContext<DB, coord::Cartesian2d<LogAxis, LogAxis>> = chart_builder
+            .build_cartesian_2d(x_range, y_range)
+            .map_err(|e| e.to_string())?;
+
+        {
+            let mut mesh = chart_cxt.configure_mesh();
+            style_conf.mesh_conf::<LogScale, LogScale, DB>(&mut mesh);
+            mesh.x_label_formatter(&Self::x_label_formatter)
+                .y_label_formatter(&Self::y_label_formatter)
+                .draw()
+                .map_err(|e| e.to_string())?;
+        }
+
+        let area = chart_cxt.plotting_area().use_screen_coord();
+        for filter_spec in active_filters {
+            let line = filter_spec.uid();
+            let sample = match self.samples.get(&line) {
+                Some(sample) => sample,
+                None => continue,
+            };
+            let style = style_conf.shape_conf(filter_spec.color());
+            let marker = style_conf
+                .pattern(line)
+                .map(|pattern| pattern.marker)
+                .unwrap_or(pattern::Marker::Disk);
+            for point in &sample.points {
+                let pixel = chart_cxt.backend_coord(&(point.x(), point.y()));
+                super::draw_marker(&area, pixel, &style, marker, Self::POINT_SIZE)?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use base::rand::{rngs::SmallRng, SeedableRng};
+
+    fn point(idx: u64) -> ScatterPoint {
+        ScatterPoint::new(
+            uid::Alloc::from(idx),
+            time::Duration::new(idx, 0).into(),
+            8,
+            None,
+        )
+    }
+
+    #[test]
+    fn samples_are_bounded() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let mut sample = Sample::new();
+        for idx in 0..3 {
+            sample.offer(point(idx), 5, &mut rng)
+        }
+        assert_eq!(sample.seen, 3);
+        assert_eq!(sample.points, (0..3).map(point).collect::<Vec<_>>());
+
+        for idx in 3..1000 {
+            sample.offer(point(idx), 5, &mut rng)
+        }
+        assert_eq!(sample.seen, 1000);
+        assert_eq!(sample.points.len(), 5);
+        let uids: BTSet<_> = sample.points.iter().map(|point| point.alloc).collect();
+        assert_eq!(uids.len(), 5);
+        // A uniform sample of 5 out of 1000 is very unlikely to only contain the first allocations.
+        assert!(uids.iter().any(|uid| *uid >= uid::Alloc::from(5u64)));
+    }
+
+    #[test]
+    fn samples_are_uniform() {
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut hits = vec![0; 10];
+        for _ in 0..2000 {
+            let mut sample = Sample::new();
+            for idx in 0..10 {
+                sample.offer(point(idx), 2, &mut rng)
+            }
+            for point in &sample.points {
+                hits[point.lifetime.as_secs() as usize] += 1
+            }
+        }
+        // Each allocation is sampled with probability 1/5, 400 times out of 2000 on average.
+        for count in hits {
+            assert!(300 < count && count < 500, "{}", count)
+        }
+    }
+
+    #[test]
+    fn closest_point() {
+        let mut points = ScatterPoints::new();
+        points
+            .sample_mut(uid::Line::Everything)
+            .points
+            .extend(vec![point(1), point(100)]);
+        let area = Ranges::new(Range::new(0., 100.), Range::new(0., 50.));
+        let ranges = points.ranges(|_| true);
+        let (x, y) = ScatterPoints::pixel_of(&ranges, &area, &point(100));
+        assert_eq!(
+            points.closest(|_| true, &area, (x + 2., y - 2.)),
+            Some((uid::Line::Everything, &point(100)))
+        );
+        assert_eq!(points.closest(|_| true, &area, (x - 20., y)), None);
+        assert_eq!(points.closest(|_| false, &area, (x, y)), None);
+    }
+}
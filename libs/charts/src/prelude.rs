@@ -32,7 +32,8 @@ pub mod alloc {
 pub mod coord {
     pub use plotters::coord::{
         cartesian::Cartesian2d,
-        ranged1d::{AsRangedCoord, Ranged, ValueFormatter},
+        combinators::{LogCoord, LogRange},
+        ranged1d::{AsRangedCoord, DefaultFormatting, KeyPointHint, Ranged, ValueFormatter},
         types::{RangedCoordf32, RangedCoordu32, RangedCoordu64, RangedDuration},
     };
 }
@@ -58,11 +59,14 @@ base::cfg_item! {
 }
 
 pub use crate::{
+    alloc_table,
     chart::{self, settings},
     color::Color,
+    explain,
     filter::{self, Filter, Filters},
-    msg,
+    limits, msg, notes, pattern,
     point::{self, Point, PointVal, Points},
+    session, snapshot, tag,
 };
 
 /// Number pretty formatting.
@@ -126,12 +130,14 @@ pub type AllocUidSet = BTSet<uid::Alloc>;
 /// Dump-loading information.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LoadInfo {
-    /// Number of dumps loaded so far.
+    /// Number of dumps, or bytes of a CTF file, loaded so far.
     pub loaded: usize,
-    /// Total number of dumps.
+    /// Total number of dumps, or bytes of a CTF file.
     ///
     /// Can be `0`, in which case the progress is considered to be `0` as well.
     pub total: usize,
+    /// True if `loaded` and `total` are numbers of bytes of a CTF file.
+    pub bytes: bool,
 }
 impl LoadInfo {
     /// Unknown info, `loaded` and `total` are set to `0`.
@@ -139,6 +145,28 @@ impl LoadInfo {
         Self {
             loaded: 0,
             total: 0,
+            bytes: false,
+        }
+    }
+    /// Description of the progress.
+    ///
+    /// ```rust
+    /// # use charts::prelude::LoadInfo;
+    /// let mut info = LoadInfo::unknown();
+    /// info.loaded = 3;
+    /// info.total = 10;
+    /// assert_eq!(info.desc(), "3 / 10 dumps");
+    /// info.loaded = 104_857_600;
+    /// info.total = 2_147_483_648;
+    /// info.bytes = true;
+    /// assert_eq!(info.desc(), "100.00MiB / 2.00GiB");
+    /// ```
+    pub fn desc(&self) -> String {
+        if self.bytes {
+            let bytes = |n: usize| num_fmt::bin_str_do(n as f64, |n| format!("{}B", n));
+            format!("{} / {}", bytes(self.loaded), bytes(self.total))
+        } else {
+            format!("{} / {} dumps", self.loaded, self.total)
         }
     }
     /// Percent version of the progress.
@@ -151,6 +179,118 @@ impl LoadInfo {
     }
 }
 
+/// Filter generation progress.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenProgress {
+    /// Progress, between `0` and `1`.
+    pub fraction: f32,
+    /// Description of the current phase of the generation.
+    pub phase: String,
+}
+impl GenProgress {
+    /// Constructor.
+    ///
+    /// Clamps `fraction` between `0` and `1`.
+    pub fn new(fraction: f32, phase: impl Into<String>) -> Self {
+        let fraction = if fraction.is_nan() || fraction < 0. {
+            0.
+        } else if fraction > 1. {
+            1.
+        } else {
+            fraction
+        };
+        Self {
+            fraction,
+            phase: phase.into(),
+        }
+    }
+    /// Percent version of the progress.
+    pub fn percent(&self) -> f64 {
+        (self.fraction as f64) * 100.
+    }
+}
+
+/// Sampling of the packets of a trace, for quick looks at big traces.
+///
+/// Only the allocations of one packet in `sampling` are loaded, see
+/// `data::set_packet_sampling`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Estimate {
+    /// Only the allocations of one packet in `sampling` are loaded.
+    pub sampling: usize,
+    /// True if all the allocations are being loaded in the background.
+    pub loading_all: bool,
+}
+impl Estimate {
+    /// Constructor, `None` if `sampling` loads everything.
+    ///
+    /// ```rust
+    /// # use charts::prelude::*;
+    /// assert_eq!(Estimate::new(1), None);
+    /// let estimate = Estimate::new(8).unwrap();
+    /// assert_eq!(estimate.to_string(), "estimated from 12.5% of packets");
+    /// ```
+    pub fn new(sampling: usize) -> Option<Self> {
+        if sampling > 1 {
+            Some(Self {
+                sampling,
+                loading_all: false,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Percentage of the packets the data comes from.
+    pub fn percent(&self) -> f64 {
+        100. / (self.sampling as f64)
+    }
+}
+base::implement! {
+    impl Estimate {
+        Display {
+            |&self, fmt| {
+                write!(fmt, "estimated from {}% of packets", (self.percent() * 10.).round() / 10.)?;
+                if self.loading_all {
+                    write!(fmt, ", loading everything")?
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Status of the parse cache of a trace, see `data::cache`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheStatus {
+    /// The cache is disabled.
+    Disabled,
+    /// The data was loaded from the cache, with the size of the cache in bytes.
+    Loaded(u64),
+    /// The cache is being written.
+    Writing,
+    /// The cache was written, with its size in bytes.
+    Written(u64),
+    /// The cache could not be written.
+    Failed(String),
+}
+base::implement! {
+    impl CacheStatus {
+        Display {
+            |&self, fmt| {
+                let size_str = |size: &u64| num_fmt::str_do(*size as f64, |s| format!("{}B", s));
+                match self {
+                    Self::Disabled => write!(fmt, "disabled"),
+                    Self::Loaded(size) => write!(fmt, "loaded from cache ({})", size_str(size)),
+                    Self::Writing => write!(fmt, "writing"),
+                    Self::Written(size) => write!(fmt, "written ({})", size_str(size)),
+                    Self::Failed(e) => write!(fmt, "failed: {}", e),
+                }
+            }
+        }
+    }
+}
+
 /// Allocation statistics.
 ///
 /// Sent to the client so that it can display basic informations (run date, allocation count...).
@@ -166,6 +306,20 @@ pub struct AllocStats {
     pub start_date: time::Date,
     /// Duration of the run.
     pub duration: time::SinceStart,
+    /// Number of allocations with unresolved locations.
+    pub unresolved_locs: usize,
+    /// How location filters treat allocations with unresolved locations.
+    pub unresolved_policy: filter::loc::UnresolvedPolicy,
+    /// Sampling of the packets, if the data is an estimate.
+    ///
+    /// Sizes and allocation counts are scaled accordingly.
+    pub estimate: Option<Estimate>,
+    /// Format of the trace, if known.
+    pub trace_format: Option<alloc_data::TraceFormat>,
+    /// Status of the parse cache of the trace, if the trace has one.
+    pub cache: Option<CacheStatus>,
+    /// Number of events the trace parser skipped because it does not know their kind.
+    pub skipped_events: usize,
 }
 #[cfg(any(test, feature = "server"))]
 impl AllocStats {
@@ -179,6 +333,12 @@ impl AllocStats {
             total_size: 0,
             start_date,
             duration: time::SinceStart::zero(),
+            unresolved_locs: 0,
+            unresolved_policy: filter::loc::unresolved_policy(),
+            estimate: None,
+            trace_format: None,
+            cache: None,
+            skipped_events: 0,
         }
     }
 
@@ -0,0 +1,397 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Sessions: the charts and filters of a client, persisted by the server.
+//!
+//! Once [started], the server writes the [`Session`] of a client to a JSON file each time the
+//! client saves its filters, creates or closes a chart, or changes the settings of a chart. The
+//! last writer wins. Read-only clients never write the session.
+//!
+//! If the server restores sessions, clients that connect start from the last session saved instead
+//! of the charts and filters of the filter generator. Session files that do not parse, or that
+//! come from another [`VERSION`] of the format, are ignored with a warning.
+//!
+//! [started]: fn.start.html (The start function)
+//! [`Session`]: struct.Session.html (The Session struct)
+//! [`VERSION`]: constant.VERSION.html (The VERSION constant)
+
+prelude! {}
+
+use chart::ChartSpec;
+use filter::FilterSpec;
+
+/// Version of the format of the session files.
+///
+/// Must change each time the types a session mentions change in a way that breaks
+/// deserialization.
+pub const VERSION: u32 = 1;
+
+/// A chart of a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionChart {
+    /// Specification of the chart.
+    pub spec: ChartSpec,
+    /// Settings of the chart.
+    pub settings: settings::Chart,
+}
+
+/// The charts and filters of a client.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Version of the format, see [`VERSION`].
+    ///
+    /// [`VERSION`]: constant.VERSION.html (The VERSION constant)
+    pub version: u32,
+    /// Specification of the everything filter.
+    pub everything: FilterSpec,
+    /// The custom filters and the catch-all filter.
+    pub filters: filter::Export,
+    /// The charts, in order.
+    pub charts: Vec<SessionChart>,
+    /// Settings of all the charts.
+    pub settings: settings::Charts,
+}
+
+impl Session {
+    /// Constructor.
+    pub fn new(
+        everything: FilterSpec,
+        filters: filter::Export,
+        charts: Vec<SessionChart>,
+        settings: settings::Charts,
+    ) -> Self {
+        Self {
+            version: VERSION,
+            everything,
+            filters,
+            charts,
+            settings,
+        }
+    }
+
+    /// JSON version of the session.
+    pub fn to_json(&self) -> Res<String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("while encoding the session: {}", e))?;
+        Ok(json)
+    }
+
+    /// Parses and checks a JSON session.
+    ///
+    /// Fails if the session comes from another [`VERSION`], or if its filters are not consistent.
+    ///
+    /// ```rust
+    /// use charts::{filter::{Export, FilterSpec}, chart::settings, session::*};
+    /// let session = Session::new(
+    ///     FilterSpec::new_everything(),
+    ///     Export::new(FilterSpec::new_catch_all(), vec![]),
+    ///     vec![],
+    ///     settings::Charts::new(),
+    /// );
+    /// let json = session.to_json().unwrap();
+    /// assert_eq!(Session::from_json(&json).unwrap().version, VERSION);
+    ///
+    /// let mut old = session.clone();
+    /// old.version = VERSION + 1;
+    /// assert!(Session::from_json(&old.to_json().unwrap()).is_err());
+    /// assert!(Session::from_json(&json[0..json.len() / 2]).is_err());
+    /// ```
+    ///
+    /// [`VERSION`]: constant.VERSION.html (The VERSION constant)
+    pub fn from_json(json: &str) -> Res<Self> {
+        let slf: Self =
+            serde_json::from_str(json).map_err(|e| format!("illegal session: {}", e))?;
+        if slf.version != VERSION {
+            bail!(
+                "session has version {}, expected version {}",
+                slf.version,
+                VERSION
+            )
+        }
+        if !slf.everything.uid().is_everything() {
+            bail!(
+                "illegal session: expected everything specification, found `{}`",
+                slf.everything.name()
+            )
+        }
+        slf.filters.check()?;
+        Ok(slf)
+    }
+
+    /// Makes sure fresh UIDs do not collide with the UIDs of the session.
+    pub fn reserve_uids(&self) {
+        for filter in &self.filters.filters {
+            uid::Filter::reserve(filter.uid());
+            for sub in filter.iter() {
+                uid::SubFilter::reserve(sub.uid())
+            }
+        }
+        for chart in &self.charts {
+            uid::Chart::reserve(chart.spec.uid())
+        }
+    }
+}
+
+/// Status of the session file of the server, as shown to the clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Status {
+    /// Path to the session file.
+    pub path: String,
+    /// True if new clients start from the saved session.
+    pub restore: bool,
+    /// True if a session is saved.
+    pub saved: bool,
+}
+
+/// Configuration of the session persistence of the server.
+#[cfg(any(test, feature = "server"))]
+struct Conf {
+    /// Path to the session file, `None` if sessions are not persisted.
+    path: Option<std::path::PathBuf>,
+    /// True if new clients start from the saved session.
+    restore: bool,
+    /// Last session saved or loaded, if restoring sessions.
+    session: Option<Session>,
+}
+
+#[cfg(any(test, feature = "server"))]
+lazy_static! {
+    /// Session persistence of the server.
+    static ref CONF: sync::RwLock<Conf> = sync::RwLock::new(Conf {
+        path: None,
+        restore: false,
+        session: None,
+    });
+}
+
+/// Loads a session file.
+///
+/// Yields `None` if there is no such file. Files that cannot be read or that are not legal
+/// sessions are ignored with a warning.
+#[cfg(any(test, feature = "server"))]
+pub fn load(path: &std::path::Path) -> Option<Session> {
+    if !path.exists() {
+        return None;
+    }
+    let session = std::fs::read_to_string(path)
+        .map_err(|e| err::Error::from(e.to_string()))
+        .and_then(|json| Session::from_json(&json))
+        .chain_err(|| format!("while loading session file `{}`", path.display()));
+    match session {
+        Ok(session) => Some(session),
+        Err(e) => {
+            log::warn!("{}", e.to_pretty());
+            log::warn!("ignoring the session file, starting fresh");
+            None
+        }
+    }
+}
+
+/// Starts persisting sessions in some file.
+///
+/// If `restore`, loads the session of the file if any: new clients start from it.
+#[cfg(any(test, feature = "server"))]
+pub fn start(path: impl Into<std::path::PathBuf>, restore: bool) -> Res<()> {
+    let path = path.into();
+    let session = if restore { load(&path) } else { None };
+    if let Some(session) = session.as_ref() {
+        log::info!(
+            "restoring session with {} filter(s) and {} chart(s)",
+            session.filters.filters.len(),
+            session.charts.len()
+        );
+        session.reserve_uids()
+    }
+    let mut conf = CONF
+        .write()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while starting session persistence")?;
+    *conf = Conf {
+        path: Some(path),
+        restore,
+        session,
+    };
+    Ok(())
+}
+
+/// The session new clients start from, if any.
+#[cfg(any(test, feature = "server"))]
+pub fn restored() -> Res<Option<Session>> {
+    CONF.read()
+        .map(|conf| conf.session.clone())
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while reading the session")
+}
+
+/// Status of the session file, `None` if sessions are not persisted.
+#[cfg(any(test, feature = "server"))]
+pub fn status() -> Res<Option<Status>> {
+    let conf = CONF
+        .read()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while reading the session status")?;
+    Ok(conf.path.as_ref().map(|path| Status {
+        path: path.display().to_string(),
+        restore: conf.restore,
+        saved: path.exists(),
+    }))
+}
+
+/// Writes a session file.
+///
+/// The session is written in a temporary file first, so that an interrupted write does not leave a
+/// broken session file.
+#[cfg(any(test, feature = "server"))]
+fn write(path: &std::path::Path, session: &Session) -> Res<()> {
+    let json = session.to_json()?;
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, json)
+        .chain_err(|| format!("while writing session file `{}`", path.display()))?;
+    std::fs::rename(&tmp, path)
+        .chain_err(|| format!("while writing session file `{}`", path.display()))?;
+    Ok(())
+}
+
+/// Writes a session to the session file, if sessions are persisted.
+#[cfg(any(test, feature = "server"))]
+pub fn save(session: Session) -> Res<()> {
+    let mut conf = CONF
+        .write()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while saving the session")?;
+    let path = match conf.path.as_ref() {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    write(path, &session)?;
+    if conf.restore {
+        conf.session = Some(session)
+    }
+    Ok(())
+}
+
+/// Deletes the session file, new clients start from the filter generator again.
+#[cfg(any(test, feature = "server"))]
+pub fn clear() -> Res<()> {
+    let mut conf = CONF
+        .write()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while clearing the session")?;
+    conf.session = None;
+    if let Some(path) = conf.path.as_ref() {
+        if path.exists() {
+            std::fs::remove_file(path)
+                .chain_err(|| format!("while deleting session file `{}`", path.display()))?
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A session with a filter and a chart.
+    fn session() -> Session {
+        let mut filter = Filter::new(FilterSpec::new(Color::random())).unwrap();
+        filter.insert(filter::SubFilter::default()).unwrap();
+        let filters = Filters::new_with(vec![filter.clone()]);
+        let chart = chart::Chart::new(
+            &filters,
+            chart::axis::XAxis::Time,
+            chart::axis::YAxis::TotalSize,
+            filters.uid_map(true),
+        )
+        .unwrap();
+        Session::new(
+            FilterSpec::new_everything(),
+            filter::Export::new(FilterSpec::new_catch_all(), vec![filter]),
+            vec![SessionChart {
+                spec: chart.spec().clone(),
+                settings: chart.settings().clone(),
+            }],
+            settings::Charts::new(),
+        )
+    }
+
+    #[test]
+    fn corrupt_files_are_ignored() {
+        let dir = std::env::temp_dir().join(format!("memthol_session_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("session.json");
+
+        assert!(load(&path).is_none());
+
+        let session = session();
+        std::fs::write(&path, session.to_json().unwrap()).unwrap();
+        let loaded = load(&path).expect("failed to load a legal session");
+        assert_eq!(loaded.charts.len(), 1);
+        assert_eq!(loaded.filters, session.filters);
+
+        let mut json = session.to_json().unwrap();
+        json.truncate(json.len() / 3);
+        std::fs::write(&path, json).unwrap();
+        assert!(load(&path).is_none());
+
+        let mut other = session;
+        other.version = VERSION + 1;
+        std::fs::write(&path, other.to_json().unwrap()).unwrap();
+        assert!(load(&path).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap()
+    }
+
+    #[test]
+    fn writes_replace_the_file() {
+        let dir =
+            std::env::temp_dir().join(format!("memthol_session_write_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("session.json");
+        let session = session();
+
+        // A write interrupted before the rename leaves the previous session untouched.
+        std::fs::write(&path, session.to_json().unwrap()).unwrap();
+        std::fs::write(dir.join("session.json.tmp"), "{ \"version\": ").unwrap();
+        assert_eq!(load(&path).unwrap().filters, session.filters);
+
+        let mut other = session.clone();
+        other.charts.clear();
+        write(&path, &other).unwrap();
+        assert!(load(&path).unwrap().charts.is_empty());
+        let files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, vec![std::ffi::OsString::from("session.json")]);
+
+        std::fs::remove_dir_all(&dir).unwrap()
+    }
+
+    #[test]
+    fn charts_round_trip() {
+        let session = session();
+        let charts = crate::Charts::from_session(session.clone()).unwrap();
+        let restored = charts.session();
+        assert_eq!(restored.filters, session.filters);
+        assert_eq!(restored.charts.len(), 1);
+        assert_eq!(restored.charts[0].spec.uid(), session.charts[0].spec.uid());
+        assert!(uid::Chart::fresh() > session.charts[0].spec.uid());
+    }
+}
@@ -0,0 +1,254 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Snapshot diffs: the live memory that appeared between two instants.
+//!
+//! A snapshot diff between `from` and `to` answers *what allocated after `from` and is still alive
+//! at `to`*. Allocation sites are ranked, for each line, by how much their live size grew between
+//! the two instants:
+//!
+//! - allocations created after `from` and still alive at `to` contribute their size;
+//! - allocations created after `from` and dead at `to` do not contribute;
+//! - allocations created at or before `from` do not contribute, whether they are still alive at `to`
+//!   or not.
+
+prelude! {}
+
+/// Maximum number of allocation sites per line in a snapshot diff.
+pub const MAX_SITES: usize = 20;
+
+/// Live size growth of an allocation site.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteGrowth {
+    /// File of the allocation site.
+    pub file: String,
+    /// Line of the allocation site.
+    pub line: usize,
+    /// Number of live allocations of the site at `to` that were created after `from`.
+    pub count: u64,
+    /// Live bytes of the site at `to` that were allocated after `from`.
+    pub bytes: u64,
+}
+
+base::implement! {
+    impl SiteGrowth {
+        Display {
+            |&self, fmt| write!(fmt, "`{}`:{}", self.file, self.line)
+        }
+    }
+}
+
+/// Live size growth of the allocation sites of each line between two instants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    /// First instant.
+    pub from: time::SinceStart,
+    /// Second instant.
+    pub to: time::SinceStart,
+    /// Sites of each line, largest growth first.
+    ///
+    /// Lines are in the order of the filters, the everything line first and the catch-all line
+    /// last.
+    pub lines: Vec<(uid::Line, Vec<SiteGrowth>)>,
+}
+
+impl SnapshotDiff {
+    /// Sites of a line.
+    pub fn sites(&self, line: uid::Line) -> Option<&Vec<SiteGrowth>> {
+        self.lines
+            .iter()
+            .find(|(uid, _)| *uid == line)
+            .map(|(_, sites)| sites)
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+impl SnapshotDiff {
+    /// Constructor, see the [module-level documentation](index.html).
+    ///
+    /// - `lines`: the lines to compute the diff of, in order;
+    /// - `line_of`: the line of an allocation, besides the everything line.
+    pub fn new(
+        data: &data::Data,
+        from: time::SinceStart,
+        to: time::SinceStart,
+        lines: Vec<uid::Line>,
+        line_of: impl Fn(&uid::Alloc) -> uid::Line,
+    ) -> Res<Self> {
+        if to < from {
+            bail!(
+                "illegal snapshot diff, second instant {} is before the first one {}",
+                to,
+                from,
+            )
+        }
+
+        // Maps lines to their allocation sites, and sites to their count and bytes.
+        let mut sites: BTMap<uid::Line, HMap<alloc::Loc, (u64, u64)>> =
+            lines.iter().map(|line| (*line, HMap::new())).collect();
+
+        // Allocations are sorted by time of creation.
+        for alloc in data
+            .iter_allocs()
+            .skip_while(|alloc| alloc.toc <= from)
+            .take_while(|alloc| alloc.toc <= to)
+        {
+            let alive = alloc.tod.map(|tod| tod > to).unwrap_or(true);
+            if !alive {
+                continue;
+            }
+            let site = match alloc.trace().last() {
+                Some(site) => site.loc.clone(),
+                None => continue,
+            };
+            let line = line_of(alloc.uid());
+            let lines = Some(uid::Line::Everything)
+                .into_iter()
+                .chain(Some(line).filter(|line| *line != uid::Line::Everything));
+            for line in lines {
+                if let Some(sites) = sites.get_mut(&line) {
+                    let (count, bytes) = sites.entry(site.clone()).or_insert((0, 0));
+                    *count += 1;
+                    *bytes += alloc.real_size as u64;
+                }
+            }
+        }
+
+        // Sizes are already scaled by the parser.
+        let sampling = data.sampling() as u64;
+
+        let lines = lines
+            .into_iter()
+            .map(|line| {
+                let mut growth: Vec<_> = sites
+                    .remove(&line)
+                    .unwrap_or_else(HMap::new)
+                    .into_iter()
+                    .map(|(site, (count, bytes))| SiteGrowth {
+                        file: site.file.to_string(),
+                        line: site.line,
+                        count: count * sampling,
+                        bytes,
+                    })
+                    .collect();
+                growth.sort_by(|lft, rgt| {
+                    rgt.bytes
+                        .cmp(&lft.bytes)
+                        .then_with(|| rgt.count.cmp(&lft.count))
+                        .then_with(|| (&lft.file, lft.line).cmp(&(&rgt.file, rgt.line)))
+                });
+                growth.truncate(MAX_SITES);
+                (line, growth)
+            })
+            .collect();
+
+        Ok(Self { from, to, lines })
+    }
+
+    /// Snapshot diff of the lines of some filters, using the global data.
+    pub fn of_filters(
+        filters: &filter::Filters,
+        from: time::SinceStart,
+        to: time::SinceStart,
+    ) -> Res<Self> {
+        let data = data::get().chain_err(|| "while computing a snapshot diff")?;
+        let lines = filters.fold(vec![], |mut lines, line| {
+            lines.push(line);
+            lines
+        });
+        Self::new(&data, from, to, lines, |alloc| filters.line_of(alloc))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixture::{self, millis};
+
+    /// Builds some data with one 8-byte allocation per millisecond for 100 milliseconds.
+    ///
+    /// Allocations alternate between `leak.ml`, which never frees, and `temp.ml`, which frees each
+    /// allocation 10 milliseconds after creating it.
+    fn data() -> data::Data {
+        let mut synth = fixture::Synth::new();
+        let trace = |file: &str| fixture::trace(&[("main.ml", 1), (file, 3)]);
+        let (leak, temp) = (trace("leak.ml"), trace("temp.ml"));
+        let labels = fixture::labels(&[]);
+
+        for ms in 0..110u64 {
+            if ms >= 10 && ms % 2 == 1 {
+                synth.dead(ms, ms - 10)
+            }
+            if ms < 100 {
+                let trace = if ms % 2 == 0 { &leak } else { &temp };
+                synth.alloc(ms, 8, trace, &labels);
+            }
+        }
+
+        synth.done()
+    }
+
+    /// Puts the allocations of `leak.ml` in the catch-all line, the others in a filter line.
+    fn line_of(alloc: &uid::Alloc) -> uid::Line {
+        if alloc.get() % 2 == 0 {
+            uid::Line::CatchAll
+        } else {
+            uid::Line::Filter(uid::Filter::from(0))
+        }
+    }
+
+    #[test]
+    fn growth() {
+        let data = data();
+        let lines = vec![uid::Line::Everything, uid::Line::CatchAll];
+        let diff = SnapshotDiff::new(&data, millis(30), millis(45), lines, line_of).unwrap();
+
+        // Created in `(30, 45]`: `leak.ml` at 32, 34, ..., 44; `temp.ml` at 31, 33, ..., 45, only
+        // the ones created after 35 are alive at 45.
+        let everything = diff.sites(uid::Line::Everything).unwrap();
+        assert_eq!(everything.len(), 2);
+        let summary = |site: &SiteGrowth| (site.file.clone(), site.count, site.bytes);
+        assert_eq!(summary(&everything[0]), ("leak.ml".into(), 7, 7 * 8));
+        assert_eq!(summary(&everything[1]), ("temp.ml".into(), 5, 5 * 8));
+
+        let catch_all = diff.sites(uid::Line::CatchAll).unwrap();
+        assert_eq!(catch_all.len(), 1);
+        assert_eq!(catch_all[0], everything[0]);
+    }
+
+    #[test]
+    fn older_allocations_do_not_contribute() {
+        let data = data();
+        // Nothing is created in `(99, 200]`, everything alive at 200 was created before 99.
+        let diff = SnapshotDiff::new(
+            &data,
+            millis(99),
+            millis(200),
+            vec![uid::Line::Everything],
+            line_of,
+        )
+        .unwrap();
+        assert_eq!(diff.sites(uid::Line::Everything), Some(&vec![]));
+
+        let diff = SnapshotDiff::new(&data, millis(0), millis(0), vec![], line_of).unwrap();
+        assert!(diff.lines.is_empty());
+
+        assert!(SnapshotDiff::new(&data, millis(45), millis(30), vec![], line_of).is_err())
+    }
+}
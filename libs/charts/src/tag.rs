@@ -0,0 +1,150 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Tags on allocations.
+//!
+//! Users can tag the allocations matching a filter, or the allocations of a site, from the UI. A
+//! tag is a label appended to the labels of these allocations, so that label filters see it as any
+//! other label, see [`label_filter`]. Charts are recomputed when tags change, so tags apply to the
+//! whole run retroactively.
+//!
+//! Tags only apply to the allocations that exist when tagging, and live in the memory of the
+//! server.
+//!
+//! [`label_filter`]: fn.label_filter.html (The label_filter function)
+
+prelude! {}
+
+use filter::label::{LabelFilter, LabelSpec};
+
+/// Number of allocations of each tag.
+pub type Tags = BTMap<String, usize>;
+
+/// Allocations to tag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Target {
+    /// Allocations matching a filter.
+    Filter(uid::Filter),
+    /// Allocations of an allocation site.
+    Site {
+        /// File of the site.
+        file: String,
+        /// Line of the site.
+        line: usize,
+    },
+}
+
+base::implement! {
+    impl Target {
+        Display {
+            |&self, fmt| match self {
+                Self::Filter(uid) => write!(fmt, "filter #{}", uid),
+                Self::Site { file, line } => write!(fmt, "{}:{}", file, line),
+            }
+        }
+    }
+}
+
+/// Checks a tag, yields it without leading and trailing whitespaces.
+///
+/// ```rust
+/// # use charts::tag::*;
+/// assert_eq!(check(" leak ").unwrap(), "leak");
+/// assert!(check("  ").is_err());
+/// assert!(check("**").is_err());
+/// ```
+pub fn check(tag: &str) -> Res<String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        bail!("tags cannot be empty")
+    }
+    // Would be parsed as something else than a label in label filters.
+    if tag == "**" || tag.starts_with("#\"") {
+        bail!("illegal tag `{}`", tag)
+    }
+    Ok(tag.into())
+}
+
+/// Label filter matching the allocations with some tag.
+pub fn label_filter(tag: impl Into<String>) -> LabelFilter {
+    LabelFilter::new(
+        filter::string_like::Pred::Contain,
+        vec![
+            LabelSpec::Anything,
+            LabelSpec::Value(tag.into()),
+            LabelSpec::Anything,
+        ],
+    )
+}
+
+/// The tag a label filter matches, if it is a [`label_filter`].
+///
+/// ```rust
+/// # use charts::tag::*;
+/// assert_eq!(tag_of(&label_filter("leak")), Some("leak"));
+/// assert_eq!(tag_of(&Default::default()), Some("my label"));
+/// ```
+///
+/// [`label_filter`]: fn.label_filter.html (The label_filter function)
+pub fn tag_of(filter: &LabelFilter) -> Option<&str> {
+    if filter.pred() != filter::string_like::Pred::Contain {
+        return None;
+    }
+    match filter.specs().as_slice() {
+        [LabelSpec::Anything, LabelSpec::Value(tag), LabelSpec::Anything] => Some(tag),
+        _ => None,
+    }
+}
+
+/// UIDs of the allocations of a target.
+#[cfg(any(test, feature = "server"))]
+pub fn allocs_of(target: &Target, filters: &filter::Filters) -> Res<Vec<uid::Alloc>> {
+    let data = data::get()?;
+    let now = *data.current_time();
+    let allocs = data.iter_allocs();
+    let res = match target {
+        Target::Filter(uid) => {
+            let filter = filters
+                .filters()
+                .iter()
+                .find(|filter| filter.uid() == *uid)
+                .ok_or_else(|| format!("unknown filter #{}", uid))?;
+            allocs
+                .filter(|alloc| filter.apply(&now, alloc))
+                .map(|alloc| *alloc.uid())
+                .collect()
+        }
+        Target::Site { file, line } => {
+            let site = explain::site_subfilter(file, *line);
+            allocs
+                .filter(|alloc| site.apply(&now, alloc))
+                .map(|alloc| *alloc.uid())
+                .collect()
+        }
+    };
+    Ok(res)
+}
+
+/// Tags the allocations of a target, returns the number of allocations tagged.
+#[cfg(any(test, feature = "server"))]
+pub fn tag(tag: &str, target: &Target, filters: &filter::Filters) -> Res<usize> {
+    let tag = check(tag)?;
+    let allocs = allocs_of(target, filters)?;
+    data::tag(&tag, allocs).chain_err(|| format!("while tagging {} as `{}`", target, tag))
+}
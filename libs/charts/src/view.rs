@@ -0,0 +1,321 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! View state, used to share what a client is looking at.
+//!
+//! A [`ViewState`] is a compact subset of the client's state that can be encoded in the fragment
+//! of the URL (the part after `#`), so that users can share links to a specific view. It stores
+//!
+//! - the active footer tab,
+//! - the focused chart, *i.e.* the chart with its settings open,
+//! - the global time window, and
+//! - shared filters, that the client offers to import, see [`filter::text::share`].
+//!
+//! The fragment is a `&`-separated list of `key=value` pairs:
+//!
+//! ```rust
+//! # use charts::{prelude::*, view::ViewState};
+//! let state = ViewState {
+//!     tab: Some(uid::Line::CatchAll),
+//!     chart: Some(uid::Chart::from(3)),
+//!     time_windopt: TimeWindopt::new(Some(time::SinceStart::from_secs(2)), None),
+//!     filters: None,
+//! };
+//! let fragment = state.to_fragment();
+//! assert_eq!(fragment, "tab=catch_all&chart=3&tw=2.000000..");
+//! assert_eq!(ViewState::from_fragment(&fragment), state);
+//! ```
+//!
+//! Decoding is lenient: unknown keys and illegal values are ignored, so that old or hand-edited
+//! links restore whatever they can.
+//!
+//! [`ViewState`]: struct.ViewState.html (The ViewState struct)
+//! [`filter::text::share`]: ../filter/text/fn.share.html (The share function)
+
+prelude! {}
+
+/// Key for the active footer tab.
+const TAB_KEY: &str = "tab";
+/// Key for the focused chart.
+const CHART_KEY: &str = "chart";
+/// Key for the time window.
+const TIME_WINDOW_KEY: &str = "tw";
+/// Separator between the bounds of the time window.
+const TIME_WINDOW_SEP: &str = "..";
+/// Key for the shared filters.
+const FILTERS_KEY: &str = "filters";
+
+/// Subset of the client's state that can be encoded in a URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewState {
+    /// Active footer tab, if any.
+    pub tab: Option<uid::Line>,
+    /// Focused chart, if any.
+    pub chart: Option<uid::Chart>,
+    /// Global time window.
+    pub time_windopt: TimeWindopt,
+    /// Shared filters, as encoded by [`filter::text::share`].
+    ///
+    /// [`filter::text::share`]: ../filter/text/fn.share.html (The share function)
+    pub filters: Option<String>,
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        Self {
+            tab: None,
+            chart: None,
+            time_windopt: TimeWindopt::new(None, None),
+            filters: None,
+        }
+    }
+}
+
+impl ViewState {
+    /// True if the state is the default state.
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Encodes the state as a URL fragment, without the leading `#`.
+    pub fn to_fragment(&self) -> String {
+        let mut pairs = vec![];
+        if let Some(tab) = self.tab {
+            pairs.push(format!("{}={}", TAB_KEY, tab))
+        }
+        if let Some(chart) = self.chart {
+            pairs.push(format!("{}={}", CHART_KEY, chart))
+        }
+        let Range { lbound, ubound } = &self.time_windopt;
+        if lbound.is_some() || ubound.is_some() {
+            let bound = |b: &Option<time::SinceStart>| {
+                b.map(|b| b.display_micros().to_string())
+                    .unwrap_or_else(String::new)
+            };
+            pairs.push(format!(
+                "{}={}{}{}",
+                TIME_WINDOW_KEY,
+                bound(lbound),
+                TIME_WINDOW_SEP,
+                bound(ubound)
+            ))
+        }
+        if let Some(filters) = &self.filters {
+            pairs.push(format!("{}={}", FILTERS_KEY, filters))
+        }
+        pairs.join("&")
+    }
+
+    /// Decodes a URL fragment, with or without the leading `#`.
+    ///
+    /// Unknown keys and illegal values are ignored.
+    ///
+    /// ```rust
+    /// # use charts::{prelude::*, view::ViewState};
+    /// let state = ViewState::from_fragment("#tab=7&chart=oops&tw=..1.5&theme=dark");
+    /// assert_eq!(state.tab, Some(uid::Line::Filter(uid::Filter::from(7))));
+    /// assert_eq!(state.chart, None);
+    /// assert_eq!(state.time_windopt.lbound, None);
+    /// assert_eq!(
+    ///     state.time_windopt.ubound,
+    ///     Some(time::SinceStart::from_nano_timestamp(1, 500_000_000)),
+    /// );
+    /// ```
+    pub fn from_fragment(fragment: &str) -> Self {
+        let fragment = fragment.trim_start_matches('#');
+        let mut state = Self::default();
+        for pair in fragment.split('&') {
+            let mut key_val = pair.splitn(2, '=');
+            let (key, val) = match (key_val.next(), key_val.next()) {
+                (Some(key), Some(val)) => (key, val),
+                _ => continue,
+            };
+            match key {
+                TAB_KEY => state.tab = Self::parse_line(val),
+                CHART_KEY => state.chart = usize::from_str(val).ok().map(uid::Chart::from),
+                TIME_WINDOW_KEY => {
+                    if let Some(time_windopt) = Self::parse_time_windopt(val) {
+                        state.time_windopt = time_windopt
+                    }
+                }
+                FILTERS_KEY => state.filters = Self::parse_filters(val),
+                _ => (),
+            }
+        }
+        state
+    }
+
+    /// Parses a line UID.
+    fn parse_line(s: &str) -> Option<uid::Line> {
+        match s {
+            "catch_all" => Some(uid::Line::CatchAll),
+            "everything" => Some(uid::Line::Everything),
+            s => usize::from_str(s)
+                .ok()
+                .map(|idx| uid::Line::Filter(uid::Filter::from(idx))),
+        }
+    }
+
+    /// Parses a time window, `None` if illegal.
+    fn parse_time_windopt(s: &str) -> Option<TimeWindopt> {
+        let mut bounds = s.splitn(2, TIME_WINDOW_SEP);
+        let (lbound, ubound) = (bounds.next()?, bounds.next()?);
+        let bound = |b: &str| -> Option<Option<time::SinceStart>> {
+            if b.is_empty() {
+                Some(None)
+            } else {
+                time::SinceStart::parse_secs(b).ok().map(Some)
+            }
+        };
+        let time_windopt = TimeWindopt::new(bound(lbound)?, bound(ubound)?);
+        match time_windopt {
+            Range {
+                lbound: Some(lbound),
+                ubound: Some(ubound),
+            } if lbound > ubound => None,
+            time_windopt => Some(time_windopt),
+        }
+    }
+
+    /// Parses shared filters, `None` if they are not URL-safe base64.
+    ///
+    /// Only checks the characters, decoding happens when the client offers to import the filters.
+    fn parse_filters(s: &str) -> Option<String> {
+        let legal = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        Some(s.to_string()).filter(|s| !s.is_empty() && s.chars().all(legal))
+    }
+
+    /// Drops the references to filters and charts that do not exist.
+    ///
+    /// Used when restoring a state from a link that might be outdated.
+    pub fn restrict(
+        mut self,
+        line_exists: impl Fn(uid::Line) -> bool,
+        chart_exists: impl Fn(uid::Chart) -> bool,
+    ) -> Self {
+        if self.tab.map(|tab| !line_exists(tab)).unwrap_or(false) {
+            self.tab = None
+        }
+        if self
+            .chart
+            .map(|chart| !chart_exists(chart))
+            .unwrap_or(false)
+        {
+            self.chart = None
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn secs(secs: u64) -> Option<time::SinceStart> {
+        Some(time::SinceStart::from_secs(secs))
+    }
+
+    #[test]
+    fn round_trip() {
+        let states = vec![
+            ViewState::default(),
+            ViewState {
+                tab: Some(uid::Line::Everything),
+                chart: None,
+                time_windopt: TimeWindopt::new(None, secs(10)),
+                filters: None,
+            },
+            ViewState {
+                tab: Some(uid::Line::Filter(uid::Filter::from(12))),
+                chart: Some(uid::Chart::from(0)),
+                time_windopt: TimeWindopt::new(
+                    Some(time::SinceStart::from_nano_timestamp(1, 250_000)),
+                    secs(3),
+                ),
+                filters: Some(filter::text::share(&[])),
+            },
+        ];
+        for state in states {
+            let fragment = state.to_fragment();
+            assert_eq!(ViewState::from_fragment(&fragment), state, "{:?}", fragment);
+            assert_eq!(
+                ViewState::from_fragment(&format!("#{}", fragment)),
+                state,
+                "{:?}",
+                fragment
+            );
+        }
+        assert_eq!(ViewState::default().to_fragment(), "");
+    }
+
+    #[test]
+    fn illegal_values() {
+        for fragment in &[
+            "",
+            "#",
+            "tab",
+            "tab=",
+            "tab=-1",
+            "chart=catch_all",
+            "tw=3..1",
+            "tw=1",
+            "tw=a..b",
+            "unknown=3",
+            "filters=",
+            "filters=a+b",
+            "&&=",
+        ] {
+            assert_eq!(
+                ViewState::from_fragment(fragment),
+                ViewState::default(),
+                "{:?}",
+                fragment
+            )
+        }
+    }
+
+    #[test]
+    fn partial_restore() {
+        let state = ViewState::from_fragment("tab=3&chart=5&tw=1..2");
+        let time_windopt = TimeWindopt::new(secs(1), secs(2));
+
+        // Filter `3` and chart `5` were deleted.
+        let restricted = state.clone().restrict(
+            |line| line != uid::Line::Filter(uid::Filter::from(3)),
+            |chart| chart != uid::Chart::from(5),
+        );
+        assert_eq!(
+            restricted,
+            ViewState {
+                tab: None,
+                chart: None,
+                time_windopt,
+                filters: None,
+            }
+        );
+
+        // Only chart `5` was deleted.
+        let restricted = state
+            .clone()
+            .restrict(|_| true, |chart| chart != uid::Chart::from(5));
+        assert_eq!(restricted.tab, state.tab);
+        assert_eq!(restricted.chart, None);
+        assert_eq!(restricted.time_windopt, time_windopt);
+    }
+}
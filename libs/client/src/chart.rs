@@ -23,9 +23,16 @@ use plotters::prelude::*;
 
 pub use charts::chart::{settings, ChartSpec};
 
+use charts::chart::{
+    seq::{SeqCheck, Tracker},
+    skew::Skew,
+};
+use settings::{LabelAngle, Resolution, TickConf};
+
 prelude! {}
 
 pub mod axis;
+pub mod dom;
 pub mod new;
 
 /// The collection of charts.
@@ -36,8 +43,34 @@ pub struct Charts {
     new_chart: new::NewChart,
     /// Name of the DOM node containing all the charts.
     dom_node_id: &'static str,
+    /// DOM identifiers of the charts.
+    dom_ids: dom::Registry,
     /// Link to the model.
     link: Link,
+    /// True if the data is an estimate, see [`Chart::estimate`].
+    ///
+    /// [`Chart::estimate`]: struct.Chart.html#structfield.estimate (The estimate field of Chart)
+    estimate: bool,
+    /// True in accessible mode, see [`Chart::accessible`].
+    ///
+    /// [`Chart::accessible`]: struct.Chart.html#structfield.accessible (The accessible field of Chart)
+    accessible: bool,
+    /// Start date and trace time of the server in the last statistics received.
+    server_time: Option<(time::Date, time::SinceStart)>,
+    /// Divergences between the charts and the server, see [`check_sync`].
+    ///
+    /// [`check_sync`]: #method.check_sync (The check_sync method)
+    skews: Vec<Skew>,
+    /// True if the server stopped sending points.
+    paused: bool,
+    /// Sequence numbers of the points applied, see [`charts::chart::seq`].
+    ///
+    /// [`charts::chart::seq`]: ../../charts/chart/seq/index.html (The seq module)
+    seqs: Tracker,
+    /// Time window the charts with a linked zoom zoom on, if any.
+    ///
+    /// New charts and charts linking their zoom again start with this zoom.
+    zoom: Option<TimeWindow>,
 }
 
 impl Charts {
@@ -48,6 +81,14 @@ impl Charts {
             link,
             new_chart: new::NewChart::new(),
             dom_node_id: "charts_list",
+            dom_ids: dom::Registry::new(),
+            estimate: false,
+            accessible: false,
+            server_time: None,
+            skews: vec![],
+            paused: false,
+            seqs: Tracker::new(),
+            zoom: None,
         }
     }
 
@@ -56,6 +97,94 @@ impl Charts {
         &self.dom_node_id
     }
 
+    /// Sets the estimate flag of all the charts.
+    ///
+    /// Charts whose flag changes are redrawn.
+    pub fn set_estimate(&mut self, estimate: bool) {
+        self.estimate = estimate;
+        for chart in &mut self.charts {
+            if chart.estimate != estimate {
+                chart.estimate = estimate;
+                chart.redraw = true
+            }
+        }
+    }
+
+    /// Sets the accessible flag of all the charts.
+    ///
+    /// Charts whose flag changes are redrawn.
+    pub fn set_accessible(&mut self, accessible: bool) {
+        self.accessible = accessible;
+        for chart in &mut self.charts {
+            if chart.accessible != accessible {
+                chart.accessible = accessible;
+                chart.redraw = true
+            }
+        }
+    }
+
+    /// True if the server stopped sending points.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// True if at least one chart is zoomed.
+    pub fn is_zoomed(&self) -> bool {
+        self.charts.iter().any(|chart| chart.zoom().is_some())
+    }
+
+    /// Checks the charts against the trace time of the server, see [`charts::chart::skew`].
+    ///
+    /// `bounded` is true if the time window has an upper bound. The server going back in time is
+    /// remembered until the next resync. Charts lagging behind the server are expected while
+    /// paused, and are not checked. Returns `true` if the skews changed.
+    ///
+    /// [`charts::chart::skew`]: ../../charts/chart/skew/index.html (The skew module)
+    pub fn check_sync(&mut self, stats: &AllocStats, bounded: bool) -> bool {
+        let now = stats.duration;
+        let mut skews = vec![];
+        if let Some((start, previous)) = self.server_time.as_ref() {
+            if *start == stats.start_date {
+                let server_skew = Skew::check_server(*previous, now)
+                    .or_else(|| self.skews.iter().find(|skew| skew.chart.is_none()).cloned());
+                skews.extend(server_skew)
+            }
+        }
+        if self.server_time.map(|(start, _)| start) != Some(stats.start_date) {
+            for chart in &mut self.charts {
+                chart.start_date = Some(stats.start_date);
+                chart.redraw = true
+            }
+        }
+        self.server_time = Some((stats.start_date, now));
+
+        if !self.paused {
+            for chart in &self.charts {
+                if let Some(last) = chart.points.as_ref().and_then(|points| points.last_time()) {
+                    skews.extend(Skew::check_chart(chart.uid(), last, now, bounded))
+                }
+            }
+        }
+
+        for skew in &skews {
+            if self.skews.iter().all(|known| known.chart != skew.chart) {
+                log::warn!(
+                    "out of sync with the server: {} ({:+.3}s)",
+                    skew,
+                    skew.secs()
+                )
+            }
+        }
+        let changed = self.skews != skews;
+        self.skews = skews;
+        changed
+    }
+
+    /// Divergences between the charts and the server.
+    pub fn skews(&self) -> &[Skew] {
+        &self.skews
+    }
+
     /// Number of charts.
     pub fn len(&self) -> usize {
         self.charts.len()
@@ -83,13 +212,43 @@ impl Charts {
         bail!("unknown chart UID #{}", uid)
     }
 
+    /// Iterator over the charts.
+    pub fn iter(&self) -> impl Iterator<Item = &Chart> + Clone {
+        self.charts.iter()
+    }
+
+    /// True if a chart with this UID exists.
+    pub fn contains(&self, uid: uid::Chart) -> bool {
+        self.charts.iter().any(|chart| chart.uid() == uid)
+    }
+
+    /// The focused chart, *i.e.* the first chart with its settings open.
+    pub fn focused(&self) -> Option<uid::Chart> {
+        self.charts
+            .iter()
+            .find(|chart| chart.is_settings_visible())
+            .map(Chart::uid)
+    }
+    /// Focuses on a chart by opening its settings.
+    pub fn focus(&mut self, uid: uid::Chart) -> Res<ShouldRender> {
+        let (_, chart) = self.get_mut(uid)?;
+        let changed = !chart.settings_visible;
+        chart.settings_visible = true;
+        Ok(changed)
+    }
+
     /// Destroys a chart.
+    ///
+    /// The server forgets the chart too, so that it leaves the session of the client.
     fn destroy(&mut self, uid: uid::Chart) -> Res<ShouldRender> {
         let (index, _) = self
             .get_mut(uid)
             .chain_err(|| format!("while destroying chart"))?;
         let chart = self.charts.remove(index);
+        self.dom_ids.unregister(uid);
+        self.seqs.forget(uid);
         chart.destroy();
+        self.send(msg::to_server::ChartsMsg::close(uid).into());
         Ok(true)
     }
 }
@@ -97,34 +256,84 @@ impl Charts {
 /// # Internal message handling
 impl Charts {
     /// Applies an operation.
+    ///
+    /// The start date of the run, if known, is only used by CSV exports.
     pub fn update(
         &mut self,
         filters: filter::Reference,
+        start_date: Option<&time::Date>,
         action: msg::ChartsMsg,
     ) -> Res<ShouldRender> {
         use msg::ChartsMsg::*;
 
         match action {
             Move { uid, up } => self.move_chart(uid, up),
+            SortByValue => self.sort_by_value(),
+            SetAllVisible(visible) => Ok(self.set_all_visible(visible)),
+            ZoomSync { uid, window } => self.zoom_sync(uid, window),
+            ResetZoom => Ok(self.reset_zoom()),
+            ToggleZoomLink(uid) => {
+                let zoom = self.zoom.clone();
+                let (_, chart) = self.get_mut(uid)?;
+                chart.zoom_linked = !chart.zoom_linked;
+                if chart.zoom_linked {
+                    let _ = chart.set_zoom(zoom);
+                }
+                Ok(true)
+            }
             Destroy(uid) => self.destroy(uid),
+            Export(uid) => {
+                let (_, chart) = self.get_mut(uid)?;
+                let (file_name, html) = chart.export_html(filters)?;
+                js::download_html(&file_name, &html)?;
+                Ok(false)
+            }
+            ExportCsv(uid) => {
+                let (_, chart) = self.get_mut(uid)?;
+                let (file_name, csv) = chart.export_csv(filters, start_date)?;
+                js::download(&file_name, "text/csv", &csv)?;
+                Ok(false)
+            }
 
             RefreshFilters => self.refresh_filters(filters),
 
+            Resync => {
+                let skews = std::mem::replace(&mut self.skews, vec![]);
+                if !skews.is_empty() {
+                    self.send(msg::to_server::ChartsMsg::resync(skews).into())
+                }
+                Ok(true)
+            }
+
             NewChartSetX(x_axis) => self.new_chart.set_x_axis(x_axis),
             NewChartSetY(y_axis) => self.new_chart.set_y_axis(y_axis),
+            NewChartOpen(open) => Ok(self.new_chart.set_open(open)),
+            NewChartSetTitle(title) => Ok(self.new_chart.set_title(title)),
+            NewChartToggleLine(line) => Ok(self.new_chart.toggle_line(line)),
+            NewChartSubmit => {
+                let msg = self.new_chart.submit();
+                self.send(msg);
+                Ok(true)
+            }
 
             ChartMsg { uid, msg } => {
                 let (_, chart) = self.get_mut(uid)?;
-                chart.update(msg)
+                chart.update(filters, msg)
             }
         }
     }
 
     /// Runs post-rendering actions.
+    ///
+    /// A chart failing to do so is disabled, see [`Chart::fail`].
+    ///
+    /// [`Chart::fail`]: struct.Chart.html#method.fail (The fail method of Chart)
     pub fn rendered(&mut self, filters: filter::Reference, stats: &AllFilterStats) {
         for chart in &mut self.charts {
             if let Err(e) = chart.rendered(filters, stats) {
-                alert!("error while running `rendered`: {}", e)
+                chart.fail(e);
+                // Rendering is over, ask for another one to show the error badge.
+                self.link.send_message(msg::ChartMsg::failed(chart.uid()))
             }
         }
     }
@@ -166,10 +375,89 @@ impl Charts {
             )
         })?;
 
+        if changed {
+            self.send_order()
+        }
+        Ok(changed)
+    }
+
+    /// Sends the order of the charts to the server, so that it persists in the session.
+    fn send_order(&self) {
+        let order = self.charts.iter().map(Chart::uid).collect();
+        self.send(msg::to_server::ChartsMsg::set_order(order).into())
+    }
+
+    /// Sorts the charts by decreasing value of the last point of their everything line.
+    ///
+    /// Charts without such a value come last, in their current order.
+    fn sort_by_value(&mut self) -> Res<ShouldRender> {
+        let value = |chart: &Chart| {
+            chart
+                .points
+                .as_ref()
+                .and_then(|points| points.last_val(uid::Line::Everything))
+        };
+        let old_order: Vec<_> = self.charts.iter().map(Chart::uid).collect();
+        // Stable, so charts with the same value keep their relative order.
+        self.charts
+            .sort_by(|lft, rgt| match (value(lft), value(rgt)) {
+                (Some(lft), Some(rgt)) => {
+                    rgt.partial_cmp(&lft).unwrap_or(std::cmp::Ordering::Equal)
+                }
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            });
+
+        let changed = self.charts.iter().map(Chart::uid).ne(old_order.into_iter());
+        if changed {
+            self.send_order()
+        }
         Ok(changed)
     }
 
+    /// Shows or hides all the charts, in one go.
+    fn set_all_visible(&mut self, visible: bool) -> ShouldRender {
+        let mut changed = false;
+        for chart in &mut self.charts {
+            if chart.is_visible() != visible {
+                chart.toggle_visible();
+                changed = true
+            }
+        }
+        changed
+    }
+
+    /// Zooms chart `uid` on a time window, and the charts with a linked zoom if `uid` has one.
+    fn zoom_sync(&mut self, uid: uid::Chart, window: Option<TimeWindow>) -> Res<ShouldRender> {
+        let (_, chart) = self.get_mut(uid)?;
+        if !chart.zoom_linked {
+            return Ok(chart.set_zoom(window));
+        }
+        self.zoom = window;
+        let mut changed = false;
+        for chart in self.charts.iter_mut().filter(|chart| chart.zoom_linked) {
+            changed = chart.set_zoom(self.zoom.clone()) || changed
+        }
+        Ok(changed)
+    }
+
+    /// Resets the zoom of all the charts, linked or not.
+    fn reset_zoom(&mut self) -> ShouldRender {
+        self.zoom = None;
+        let mut changed = false;
+        for chart in &mut self.charts {
+            changed = chart.set_zoom(None) || changed
+        }
+        changed
+    }
+
     /// Tries to move a chart. If the move is illegal, returns `false`.
+    ///
+    /// Moved charts are the same objects: they keep their points, their DOM identifiers and their
+    /// canvas, which [`Chart::rendered`] binds back to the chart's container after rendering.
+    ///
+    /// [`Chart::rendered`]: struct.Chart.html#method.rendered (The rendered method of Chart)
     fn try_move_chart_up(&mut self, index: usize) -> Res<bool> {
         // Make sure the move is legal.
         let did_something = if index == 0 || index >= self.charts.len() {
@@ -190,6 +478,7 @@ impl Charts {
 
         html! {
             <>
+                {layout::chart::render_toolbar(model)}
                 <div
                     id = model.charts().dom_node_id()
                 >
@@ -200,7 +489,13 @@ impl Charts {
                         )
                     ) }
                 </div>
-                { self.new_chart.render(model) }
+                {
+                    if model.is_read_only() {
+                        html! {}
+                    } else {
+                        self.new_chart.render(model)
+                    }
+                }
             </>
         }
     }
@@ -220,7 +515,12 @@ impl Charts {
         let should_render = match action {
             ChartsMsg::NewChart(spec, settings) => {
                 log::info!("creating new chart");
-                let chart = Chart::new(spec, settings, self.link.clone())?;
+                let dom_ids = self.dom_ids.register(spec.uid())?;
+                let mut chart = Chart::new(spec, settings, dom_ids, self.link.clone())?;
+                chart.estimate = self.estimate;
+                chart.accessible = self.accessible;
+                chart.start_date = self.server_time.map(|(start, _)| start);
+                let _ = chart.set_zoom(self.zoom.clone());
                 self.charts.push(chart);
                 true
             }
@@ -229,9 +529,21 @@ impl Charts {
                 mut points,
                 refresh_filters,
             } => {
+                if points.is_empty() {
+                    log::info!("the server replaced its data, wiping all points");
+                    for chart in &mut self.charts {
+                        chart.clear_points()
+                    }
+                }
                 for chart in &mut self.charts {
-                    if let Some(points) = points.remove(&chart.uid()) {
-                        chart.overwrite_points(points)?
+                    if let Some(chart_points) = points.remove(&chart.uid()) {
+                        let seq = points.seq(chart.uid());
+                        if self.seqs.check(chart.uid(), seq, true) != SeqCheck::Apply {
+                            log::debug!("ignoring outdated points for chart #{}", chart.uid());
+                            continue;
+                        }
+                        chart.set_gaps(points.remove_gaps(chart.uid()));
+                        chart.overwrite_points(chart_points)?
                     }
                 }
                 if refresh_filters {
@@ -240,26 +552,51 @@ impl Charts {
                 true
             }
             ChartsMsg::AddPoints(mut points) => {
+                let mut failed = false;
+                let mut resend = BTSet::new();
                 for chart in &mut self.charts {
-                    if let Some(points) = points.remove(&chart.uid()) {
-                        chart.add_points(points, filters, stats)?
+                    if let Some(chart_points) = points.remove(&chart.uid()) {
+                        match self.seqs.check(chart.uid(), points.seq(chart.uid()), false) {
+                            SeqCheck::Apply => (),
+                            SeqCheck::Ignore => {
+                                log::debug!("ignoring outdated points for chart #{}", chart.uid());
+                                continue;
+                            }
+                            SeqCheck::Resend => {
+                                log::warn!(
+                                    "some points of chart #{} are missing, asking for all of them",
+                                    chart.uid()
+                                );
+                                let _ = resend.insert(chart.uid());
+                                continue;
+                            }
+                        }
+                        chart.set_gaps(points.remove_gaps(chart.uid()));
+                        // Only disable this chart, the others are fine.
+                        if let Err(e) = chart.add_points(chart_points, filters, stats) {
+                            chart.fail(e);
+                            failed = true
+                        }
                     }
                 }
-                false
+                if !resend.is_empty() {
+                    self.send(msg::to_server::ChartsMsg::resend(resend).into())
+                }
+                failed
+            }
+            ChartsMsg::Paused(paused) => {
+                let changed = self.paused != paused;
+                self.paused = paused;
+                changed
             }
 
             ChartsMsg::Chart { uid, msg } => {
                 let (_index, chart) = self.get_mut(uid)?;
                 match msg {
-                    ChartMsg::NewPoints(points) => chart.overwrite_points(points)?,
-                    ChartMsg::Points(points) => chart.add_points(points, filters, stats)?,
+                    ChartMsg::Scope(scope) => chart.set_scope(scope),
                 }
                 true
             }
-            // msg => bail!(
-            //     "unsupported message from server: {}",
-            //     msg.as_json().unwrap_or_else(|_| format!("{:?}", msg))
-            // ),
         };
         Ok(should_render)
     }
@@ -289,6 +626,8 @@ pub struct Chart {
     )>,
     /// The points.
     points: Option<point::Points>,
+    /// Time ranges for which no data is available.
+    gaps: Vec<TimeWindow>,
     /// Previous filter map, used when updating filters to keep track of those that are hidden.
     prev_active: BTMap<uid::Line, bool>,
 
@@ -301,14 +640,51 @@ pub struct Chart {
     redraw: bool,
     /// True if the chart settings are visible.
     settings_visible: bool,
+    /// True if the points are an estimate, in which case lines are dashed.
+    estimate: bool,
+    /// True in accessible mode, in which case lines have patterns on top of their color.
+    accessible: bool,
+    /// Start date of the run, if known, used to label the x-axis with wall-clock times.
+    start_date: Option<time::Date>,
+    /// Observes the size of the canvas, only set once the chart is built.
+    resize_observer: Option<js::ResizeObserver>,
+    /// Error that disabled the chart, if any, see [`fail`](#method.fail).
+    failure: Option<String>,
+    /// Point of a scatter chart the mouse is over, if any, and its line.
+    hovered: Option<(uid::Line, point::scatter::ScatterPoint)>,
+    /// Point of a scatter chart whose details are pinned under the chart, if any, and its line.
+    pinned: Option<(uid::Line, point::scatter::ScatterPoint)>,
+    /// Time window the chart zooms on, if any.
+    zoom: Option<TimeWindow>,
+    /// True if the zoom of the chart follows the zoom of the other charts with a linked zoom.
+    zoom_linked: bool,
+    /// X pixel position where the selection of a time window to zoom on started, if any.
+    zoom_start: Option<i32>,
+    /// Time range of the x-axis of the last drawing of a time chart, if any.
+    x_range: Option<TimeWindow>,
+    /// True while the title of the chart is being edited.
+    editing_title: bool,
+    /// True if the title input should get the focus after rendering.
+    focus_title: bool,
 }
 impl Chart {
     /// Constructor.
-    pub fn new(spec: ChartSpec, settings: settings::Chart, link: Link) -> Res<Self> {
-        let top_container = format!("chart_container_{}", spec.uid().get());
-        let container = format!("chart_canvas_container_{}", spec.uid().get());
-        let canvas = format!("chart_canvas_{}", spec.uid().get());
-        let collapsed_canvas = format!("{}_collapsed", canvas);
+    ///
+    /// The DOM identifiers should come from the [`dom::Registry`] of the charts.
+    ///
+    /// [`dom::Registry`]: dom/struct.Registry.html (The Registry struct)
+    pub fn new(
+        spec: ChartSpec,
+        settings: settings::Chart,
+        dom_ids: dom::DomIds,
+        link: Link,
+    ) -> Res<Self> {
+        let dom::DomIds {
+            top_container,
+            container,
+            canvas,
+            collapsed_canvas,
+        } = dom_ids;
 
         Ok(Self {
             spec,
@@ -320,23 +696,263 @@ impl Chart {
             collapsed_canvas,
             chart: None,
             points: None,
+            gaps: vec![],
             prev_active: BTMap::new(),
             settings_visible: false,
             redraw: true,
+            estimate: false,
+            accessible: false,
+            start_date: None,
+            resize_observer: None,
+            failure: None,
+            hovered: None,
+            pinned: None,
+            zoom: None,
+            zoom_linked: true,
+            zoom_start: None,
+            x_range: None,
+            editing_title: false,
+            focus_title: false,
         })
     }
 
     /// Handles a message for this chart.
-    pub fn update(&mut self, msg: msg::ChartMsg) -> Res<ShouldRender> {
+    pub fn update(&mut self, filters: filter::Reference, msg: msg::ChartMsg) -> Res<ShouldRender> {
         use msg::ChartMsg::*;
         match msg {
             SettingsToggleVisible => self.toggle_settings_visible(),
             FilterToggleVisible(l_uid) => self.filter_toggle_visible(l_uid)?,
-            SettingsUpdate(msg) => self.settings.update(msg),
+            SettingsUpdate(msg) => {
+                use charts::msg::ChartSettingsMsg::*;
+                // The server needs the size percentile, the lifetime statistic and the transforms to
+                // generate the points.
+                match &msg {
+                    SetSizePercentile(size_percentile) => self.link.send_message(Msg::ToServer(
+                        charts::msg::ChartSettingsMsg::set_size_percentile(
+                            self.uid(),
+                            *size_percentile,
+                        ),
+                    )),
+                    SetLifetimeStat(lifetime_stat) => self.link.send_message(Msg::ToServer(
+                        charts::msg::ChartSettingsMsg::set_lifetime_stat(
+                            self.uid(),
+                            *lifetime_stat,
+                        ),
+                    )),
+                    SetNormalize(line) => self.link.send_message(Msg::ToServer(
+                        charts::msg::ChartSettingsMsg::set_normalize(self.uid(), *line),
+                    )),
+                    SetTransforms(transforms) => self.link.send_message(Msg::ToServer(
+                        charts::msg::ChartSettingsMsg::set_transforms(
+                            self.uid(),
+                            transforms.clone(),
+                        ),
+                    )),
+                    SetQuality(quality) => self.link.send_message(Msg::ToServer(
+                        charts::msg::ChartSettingsMsg::set_quality(self.uid(), *quality),
+                    )),
+                    SetMaxPoints(max_points) => self.link.send_message(Msg::ToServer(
+                        charts::msg::ChartSettingsMsg::set_max_points(self.uid(), *max_points),
+                    )),
+                    SetTimeWindow(window) => self.link.send_message(Msg::ToServer(
+                        charts::msg::ChartSettingsMsg::set_time_window(self.uid(), *window),
+                    )),
+                    // Only remembered by the server, the points do not change.
+                    SetHideEverything(hide) => {
+                        self.link.send_message(Msg::ToServer(
+                            charts::msg::ChartSettingsMsg::set_hide_everything(self.uid(), *hide),
+                        ));
+                        self.redraw = true
+                    }
+                    SetTimeAxis(time_axis) => {
+                        self.link.send_message(Msg::ToServer(
+                            charts::msg::ChartSettingsMsg::set_time_axis(self.uid(), *time_axis),
+                        ));
+                        self.redraw = true
+                    }
+                    SetLineHidden(line, hide) => {
+                        self.link.send_message(Msg::ToServer(
+                            charts::msg::ChartSettingsMsg::set_line_hidden(
+                                self.uid(),
+                                *line,
+                                *hide,
+                            ),
+                        ));
+                        self.redraw = true
+                    }
+                    ToggleVisible | ChangeTitle(_) | SetDisplayMode(_) | SetResolution(_)
+                    | SetTicks(_) => (),
+                }
+                self.settings.update(msg)
+            }
+            Resized => {
+                let resized = self
+                    .chart
+                    .as_ref()
+                    .map(|(_, canvas)| {
+                        Self::client_size(canvas) != (canvas.width(), canvas.height())
+                    })
+                    .unwrap_or(false);
+                self.redraw = self.redraw || resized;
+                return Ok(resized);
+            }
+            Failed => return Ok(true),
+            Hover(pos) => {
+                let hovered = pos.and_then(|pos| self.point_at(filters, pos));
+                let changed = hovered != self.hovered;
+                self.hovered = hovered;
+                return Ok(changed);
+            }
+            Pin(x, y) => self.pinned = self.point_at(filters, (x, y)),
+            Unpin => self.pinned = None,
+            ZoomStart(x) => {
+                self.zoom_start = Some(x);
+                return Ok(false);
+            }
+            ZoomEnd(x) => {
+                let window = self
+                    .zoom_start
+                    .take()
+                    .and_then(|start| self.window_between(start, x));
+                if let Some(window) = window {
+                    self.link
+                        .send_message(msg::ChartsMsg::zoom_sync(self.uid(), Some(window)))
+                }
+                return Ok(false);
+            }
+            EditTitle(editing) => {
+                let changed = editing != self.editing_title;
+                self.editing_title = editing;
+                self.focus_title = editing;
+                return Ok(changed);
+            }
+            Rename(title) => self.rename(title),
         }
         Ok(true)
     }
 
+    /// True while the title of the chart is being edited.
+    pub fn is_editing_title(&self) -> bool {
+        self.editing_title
+    }
+    /// DOM identifier of the input editing the title of the chart.
+    pub fn title_input_id(&self) -> String {
+        format!("{}_title", self.top_container)
+    }
+    /// Sets the custom title of the chart and stops editing it.
+    ///
+    /// An empty title reverts to the default title. The server remembers the title, so that it
+    /// persists in the session.
+    fn rename(&mut self, title: String) {
+        self.editing_title = false;
+        self.focus_title = false;
+        if self.spec.set_title(Some(title)) {
+            self.link.send_message(msg::to_server::ChartsMsg::rename(
+                self.uid(),
+                self.spec.title().map(String::from),
+            ))
+        }
+        self.settings.set_title(self.spec.display_title())
+    }
+
+    /// Time window the chart zooms on, if any.
+    pub fn zoom(&self) -> Option<&TimeWindow> {
+        self.zoom.as_ref()
+    }
+
+    /// True if the zoom of the chart follows the zoom of the other charts.
+    pub fn is_zoom_linked(&self) -> bool {
+        self.zoom_linked
+    }
+
+    /// Sets the time window the chart zooms on, returns `true` if it changed.
+    ///
+    /// Scatter charts have no time axis, they ignore zooms.
+    fn set_zoom(&mut self, zoom: Option<TimeWindow>) -> bool {
+        if self.is_scatter() || self.zoom == zoom {
+            return false;
+        }
+        self.zoom = zoom;
+        self.redraw = true;
+        true
+    }
+
+    /// Time window between two x pixel positions of a time chart.
+    ///
+    /// `None` if the positions are too close, which is a click rather than a selection, or if the
+    /// chart was not drawn yet.
+    fn window_between(&self, start: i32, end: i32) -> Option<TimeWindow> {
+        /// Minimal width of a selection, in pixels.
+        const MIN_WIDTH: i32 = 5;
+        if (end - start).abs() < MIN_WIDTH {
+            return None;
+        }
+        let range = self.x_range.as_ref()?;
+        let area = self.plot_area()?;
+        let (min, max) = (range.lbound.as_secs_f64(), range.ubound.as_secs_f64());
+        let time_at = |x: i32| {
+            let ratio = (x as f64 - area.x.lbound) / (area.x.ubound - area.x.lbound);
+            let secs = min + ratio.max(0.0).min(1.0) * (max - min);
+            time::SinceStart::from(time::Duration::from_secs_f64(secs))
+        };
+        let window = Range::new(time_at(start.min(end)), time_at(start.max(end)));
+        if window.lbound < window.ubound {
+            Some(window)
+        } else {
+            None
+        }
+    }
+
+    /// True if the chart is a scatter chart.
+    pub fn is_scatter(&self) -> bool {
+        *self.spec.x_axis() == axis::XAxis::Lifetime
+    }
+
+    /// Point of a scatter chart whose details are pinned, if any, and its line.
+    pub fn pinned(&self) -> Option<&(uid::Line, point::scatter::ScatterPoint)> {
+        self.pinned.as_ref()
+    }
+
+    /// Pixels of the plotting area, the part of the canvas inside the axes.
+    fn plot_area(&self) -> Option<point::Ranges<f64, f64>> {
+        let (width, height) = self
+            .chart
+            .as_ref()
+            .map(|(_, canvas)| (canvas.width(), canvas.height()))?;
+        let x_angle = self
+            .settings
+            .ticks()
+            .conf(Self::plot_resolution(width, height, LabelAngle::Flat))
+            .x_angle;
+        Some(point::Ranges::new(
+            Range::new(
+                Self::Y_LABEL_AREA as f64,
+                width.saturating_sub(Self::RIGHT_MARGIN) as f64,
+            ),
+            Range::new(
+                Self::TOP_MARGIN as f64,
+                height.saturating_sub(Self::x_label_area(x_angle)) as f64,
+            ),
+        ))
+    }
+
+    /// Point of a scatter chart at some pixel position, if any, and its line.
+    fn point_at(
+        &self,
+        filters: filter::Reference,
+        (x, y): (i32, i32),
+    ) -> Option<(uid::Line, point::scatter::ScatterPoint)> {
+        let points = match &self.points {
+            Some(point::Points::Scatter(points)) => points,
+            Some(point::Points::Time(_)) | None => return None,
+        };
+        let area = self.plot_area()?;
+        let is_active = |line: uid::Line| self.is_line_visible(line) && !filters.is_muted(line);
+        points
+            .closest(is_active, &area, (x as f64, y as f64))
+            .map(|(line, point)| (line, point.clone()))
+    }
+
     /// UID accessor.
     pub fn uid(&self) -> uid::Chart {
         self.spec.uid()
@@ -369,11 +985,136 @@ impl Chart {
         self.settings().title()
     }
 
+    /// Error that disabled the chart, if any.
+    pub fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+
+    /// Disables the chart because of an error.
+    ///
+    /// The chart ignores new points and stops drawing, until the server sends all its points again
+    /// (see [`overwrite_points`](#method.overwrite_points)).
+    pub fn fail(&mut self, e: err::Error) {
+        let e = e.to_pretty();
+        log::error!("disabling chart #{}: {}", self.uid(), e);
+        self.failure = Some(e);
+        self.points = None;
+    }
+
     /// Chart specification.
     pub fn spec(&self) -> &ChartSpec {
         &self.spec
     }
 
+    /// Chart tooltip: y-axis documentation, and value of the last point for each visible line.
+    ///
+    /// Describes the point the mouse is over instead, if any.
+    pub fn tooltip(&self, filters: filter::Reference) -> String {
+        if let Some((line, point)) = &self.hovered {
+            let name = filters
+                .get(*line)
+                .map(|(_, spec)| spec.name().to_string())
+                .unwrap_or_else(|_| line.to_string());
+            return format!("{}\n{}\n\nclick to pin the details", name, point.desc());
+        }
+        let mut res = self.spec.y_axis().doc().to_string();
+        if let Some(points) = &self.points {
+            let mut first = true;
+            for spec in filters.specs_iter() {
+                let uid = spec.uid();
+                if spec.is_muted() || !self.is_line_visible(uid) {
+                    continue;
+                }
+                if let Some(desc) = points.last_val_desc(uid) {
+                    if first {
+                        res.push_str("\n\nlast point:");
+                        first = false
+                    }
+                    res.push_str(&format!("\n- {}: {}", spec.name(), desc))
+                }
+            }
+        }
+        if !self.gaps.is_empty() {
+            res.push_str("\n\nno data available:");
+            for gap in &self.gaps {
+                res.push_str(&format!(
+                    "\n- from {} to {} ({})",
+                    gap.lbound,
+                    gap.ubound,
+                    gap.ubound - gap.lbound
+                ))
+            }
+        }
+        if !self.is_scatter() {
+            if let Some(zoom) = &self.zoom {
+                res.push_str(&format!("\n\nzoomed on [{}, {}]", zoom.lbound, zoom.ubound))
+            }
+            res.push_str("\n\ndrag to zoom, double-click to reset the zoom")
+        }
+        res
+    }
+
+    /// Exports the chart as a standalone HTML page, yields the file name and the page.
+    ///
+    /// Only exports the visible lines that are not muted, with the points the chart currently has.
+    pub fn export_html(&self, filters: filter::Reference) -> Res<(String, String)> {
+        let export = self.export(filters)?;
+        let html = export
+            .to_html()
+            .chain_err(|| format!("while exporting chart `{}`", self.title()))?;
+        Ok((export.file_name(), html))
+    }
+
+    /// Exports the points of the chart as CSV, yields the file name and the CSV.
+    ///
+    /// Exports the same lines as [`export_html`], `start_date` is the start date of the run if
+    /// known.
+    ///
+    /// [`export_html`]: #method.export_html (The export_html method)
+    pub fn export_csv(
+        &self,
+        filters: filter::Reference,
+        start_date: Option<&time::Date>,
+    ) -> Res<(String, String)> {
+        let export = self.export(filters)?;
+        Ok((export.csv_file_name(), export.to_csv(start_date)))
+    }
+
+    /// Visible lines that are not muted, with the points the chart currently has.
+    fn export(&self, filters: filter::Reference) -> Res<charts::chart::export::Export> {
+        let points = match &self.points {
+            Some(point::Points::Scatter(_)) => bail!(
+                "chart `{}` is a scatter chart, only time charts can be exported",
+                self.title()
+            ),
+            Some(points) => points,
+            None => bail!("chart `{}` has no points to export yet", self.title()),
+        };
+        let segments = self.spec.y_axis().has_segment_lines();
+        let segment_specs = self.segment_specs();
+        let lines = filters
+            .specs_iter()
+            .filter(|spec| !segments || spec.is_everything())
+            .filter(|spec| !spec.is_muted() && self.is_line_visible(spec.uid()))
+            .chain(segment_specs.iter())
+            .map(|spec| (spec.uid(), spec.name().clone(), spec.color().clone()));
+        Ok(charts::chart::export::Export::new(
+            &self.spec,
+            self.title(),
+            points,
+            &self.gaps,
+            lines,
+        ))
+    }
+
+    /// Sets the time ranges for which no data is available.
+    pub fn set_gaps(&mut self, gaps: Vec<TimeWindow>) {
+        if self.gaps != gaps {
+            self.gaps = gaps;
+            self.redraw = true
+        }
+    }
+
     /// DOM identifier for the chart's top container.
     pub fn top_container_id(&self) -> &str {
         &self.top_container
@@ -404,6 +1145,25 @@ impl Chart {
     pub fn filter_visibility(&self) -> &BTMap<uid::Line, bool> {
         &self.spec.active()
     }
+    /// True if a line is drawn: it is active and, if it is the everything line, not hidden.
+    ///
+    /// Does not take muting into account.
+    pub fn is_line_visible(&self, line: uid::Line) -> bool {
+        self.spec.active().get(&line).cloned().unwrap_or(false) && self.settings.shows_line(line)
+    }
+
+    /// Specifications of the lines of the chart that are not filters.
+    ///
+    /// Only charts with segment lines have such lines, they are the segments of the points of the
+    /// chart. They are always visible.
+    pub fn segment_specs(&self) -> Vec<filter::FilterSpec> {
+        match &self.points {
+            Some(points) if self.spec.y_axis().has_segment_lines() => {
+                charts::chart::time::segment::line_specs(points.lines())
+            }
+            Some(_) | None => vec![],
+        }
+    }
 
     /// Destroys the chart.
     pub fn destroy(self) {}
@@ -411,19 +1171,54 @@ impl Chart {
 
 /// # Features that (can) trigger a re-draw.
 impl Chart {
-    /// Toggles the visibility of a filter for the chart.
+    /// Toggles the visibility of a filter for the chart, from its legend.
+    ///
+    /// Toggling the everything line while it is hidden by the settings shows it. The lines hidden
+    /// are part of the settings of the chart, so that the server remembers them.
     pub fn filter_toggle_visible(&mut self, uid: uid::Line) -> Res<()> {
-        if let Some(is_visible) = self.spec.active_mut().get_mut(&uid) {
-            *is_visible = !*is_visible;
+        if !self.spec.in_scope(uid) {
+            bail!(
+                "line {} is not in the scope of this chart, edit the scope in the chart's settings",
+                uid
+            )
+        }
+        if uid.is_everything() && self.settings.hide_everything() {
+            self.settings.set_hide_everything(false);
+            self.link.send_message(Msg::ToServer(
+                charts::msg::ChartSettingsMsg::set_hide_everything(self.uid(), false),
+            ));
+            self.spec.active_mut().insert(uid, true);
             self.redraw = true;
-        } else {
-            bail!("cannot toggle visibility of unknown filter {}", uid)
+            return Ok(());
         }
+        let active = self
+            .spec
+            .active_mut()
+            .get_mut(&uid)
+            .ok_or_else(|| format!("cannot toggle visibility of unknown filter {}", uid))?;
+        // Inactive lines become visible.
+        let hide = *active && !self.settings.hidden().contains(&uid);
+        *active = true;
+        self.settings.set_line_hidden(uid, hide);
+        self.link.send_message(Msg::ToServer(
+            charts::msg::ChartSettingsMsg::set_line_hidden(self.uid(), uid, hide),
+        ));
+        self.redraw = true;
         Ok(())
     }
 
+    /// Sets the scope of the chart.
+    pub fn set_scope(&mut self, scope: Option<BTSet<uid::Line>>) {
+        self.spec.set_scope(scope);
+        self.redraw = true
+    }
+
     /// Replaces the filters of the chart.
+    ///
+    /// Lines outside of the scope of the chart are inactive, in particular new lines are inactive in
+    /// scoped charts.
     pub fn replace_filters(&mut self, filters: filter::Reference) -> Res<()> {
+        let scope = self.spec.scope().cloned();
         self.prev_active.clear();
         let active = self.spec.active_mut();
         let prev_active = &mut self.prev_active;
@@ -433,7 +1228,11 @@ impl Chart {
 
         filters.specs_apply(|spec| {
             let spec_uid = spec.uid();
-            let visible = prev_active.get(&spec_uid).cloned().unwrap_or(true);
+            let in_scope = scope
+                .as_ref()
+                .map(|scope| scope.contains(&spec_uid))
+                .unwrap_or(true);
+            let visible = in_scope && prev_active.get(&spec_uid).cloned().unwrap_or(true);
             let prev = active.insert(spec_uid, visible);
             debug_assert!(prev.is_none());
             Ok(())
@@ -450,9 +1249,20 @@ impl Chart {
         filters: filter::Reference,
         stats: &AllFilterStats,
     ) -> Res<()> {
+        if self.failure.is_some() {
+            return Ok(());
+        }
         let mut redraw = false;
+        let settings = &self.settings;
         if let Some(my_points) = &mut self.points {
-            let changed = my_points.extend(&mut points)?;
+            let mut changed = my_points.extend(&mut points)?;
+            // Forget the points that are out of the time window of the chart, if any.
+            let start = my_points
+                .last_time()
+                .and_then(|now| settings.window_start(now));
+            if let Some(start) = start {
+                changed = my_points.drop_before(start) > 0 || changed
+            }
             if changed {
                 self.draw(filters, stats)?
             }
@@ -468,11 +1278,21 @@ impl Chart {
     }
 
     /// Overwrites the points in a chart.
+    ///
+    /// Re-enables the chart if it was disabled by an error.
     pub fn overwrite_points(&mut self, points: point::Points) -> Res<()> {
+        self.failure = None;
         self.points = Some(points);
         self.redraw = true;
         Ok(())
     }
+
+    /// Drops all the points, until the server sends new ones.
+    pub fn clear_points(&mut self) {
+        self.failure = None;
+        self.points = None;
+        self.redraw = true
+    }
 }
 
 /// # Canvas Handling.
@@ -538,15 +1358,16 @@ impl Chart {
             }
 
             use wasm_bindgen::JsCast;
-            let canvas: web_sys::HtmlCanvasElement = self
-                .get_canvas()?
-                .clone()
-                .dyn_into()
-                .expect("failed to retrieve chart canvas");
-            let width = canvas.client_width();
-            let height = canvas.client_height();
-            let width = if width >= 0 { width as u32 } else { 0 };
-            let height = if height >= 0 { height as u32 } else { 0 };
+            let canvas: web_sys::HtmlCanvasElement =
+                self.get_canvas()?.dyn_into().map_err(|element| {
+                    format!(
+                        "DOM element `{}` for chart {} is not a canvas: {:?}",
+                        self.canvas,
+                        self.uid(),
+                        element
+                    )
+                })?;
+            let (width, height) = Self::client_size(&canvas);
             log::info!(
                 "original width/height: {}/{}",
                 canvas.width(),
@@ -555,34 +1376,15 @@ impl Chart {
             canvas.set_width(width);
             canvas.set_height(height);
 
-            {
-                let res_width = if width <= Self::CHART_X_DIFF {
-                    width
-                } else {
-                    width - Self::CHART_X_DIFF
-                };
-                let res_height = if height <= Self::CHART_Y_DIFF {
-                    height
-                } else {
-                    height - Self::CHART_Y_DIFF
-                };
-                log::info!(
-                    "sending new resolution: {}x{} ({}x{})",
-                    res_width,
-                    res_height,
-                    width,
-                    height
-                );
-                self.link.send_message(Msg::ToServer(
-                    charts::msg::ChartSettingsMsg::set_resolution(
-                        self.spec.uid(),
-                        (res_width, res_height),
-                    ),
-                ))
-            }
+            self.update_resolution(width, height);
+
+            let (uid, link) = (self.uid(), self.link.clone());
+            self.resize_observer = Some(js::ResizeObserver::new(&canvas, move || {
+                link.send_message(msg::ChartMsg::resized(uid))
+            }));
 
-            let backend: plotters::CanvasBackend =
-                plotters::CanvasBackend::new(&self.canvas).expect("could not find canvas");
+            let backend: plotters::CanvasBackend = plotters::CanvasBackend::new(&self.canvas)
+                .ok_or_else(|| format!("could not find canvas for chart {}", self.uid()))?;
 
             let chart: plotters::prelude::DrawingArea<
                 plotters::CanvasBackend,
@@ -595,10 +1397,68 @@ impl Chart {
 
         Ok(())
     }
+
+    /// Size of a canvas in the page.
+    fn client_size(canvas: &web_sys::HtmlCanvasElement) -> (u32, u32) {
+        let (width, height) = (canvas.client_width(), canvas.client_height());
+        (
+            if width >= 0 { width as u32 } else { 0 },
+            if height >= 0 { height as u32 } else { 0 },
+        )
+    }
+
+    /// Resolution of the plotting area of a canvas of some size.
+    fn plot_resolution(width: u32, height: u32, x_angle: LabelAngle) -> Resolution {
+        Resolution::from((
+            width.checked_sub(Self::CHART_X_DIFF).unwrap_or(width),
+            height
+                .checked_sub(Self::chart_y_diff(x_angle))
+                .unwrap_or(height),
+        ))
+    }
+
+    /// Updates the resolution of the chart for a canvas of some size, yields the tick
+    /// configuration for this size.
+    ///
+    /// Sends the resolution to the server if it changed, since the server uses it to compress the
+    /// points.
+    fn update_resolution(&mut self, width: u32, height: u32) -> TickConf {
+        let ticks =
+            self.settings
+                .ticks()
+                .conf(Self::plot_resolution(width, height, LabelAngle::Flat));
+        let resolution = Self::plot_resolution(width, height, ticks.x_angle);
+        if self.settings.resolution() != Some(resolution) {
+            log::info!(
+                "sending new resolution: {} ({}x{})",
+                resolution,
+                width,
+                height
+            );
+            self.settings.set_resolution(resolution);
+            self.link.send_message(Msg::ToServer(
+                charts::msg::ChartSettingsMsg::set_resolution(self.uid(), resolution),
+            ))
+        }
+        ticks
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
-struct Styler;
+struct Styler {
+    /// True if lines should be dashed.
+    dashed: bool,
+    /// True if lines should have patterns.
+    accessible: bool,
+    /// Tick configuration.
+    ticks: TickConf,
+    /// Resolution of the plotting area.
+    resolution: Resolution,
+    /// How the x-axis labels times.
+    time_axis: charts::chart::settings::TimeAxis,
+    /// Start date of the run, if known.
+    start_date: Option<time::Date>,
+}
 impl charts::point::StyleExt for Styler {
     fn mesh_conf<X, Y, DB>(&self, mesh: &mut plotters::chart::MeshStyle<X::Range, Y::Range, DB>)
     where
@@ -607,6 +1467,8 @@ impl charts::point::StyleExt for Styler {
         DB: plotters::prelude::DrawingBackend,
     {
         mesh.disable_x_mesh()
+            .x_labels(self.ticks.x_labels(self.resolution.width))
+            .y_labels(self.ticks.y_labels(self.resolution.height))
             .label_style(("sans-serif", 20).into_font())
             .axis_style(&plotters::prelude::BLACK)
             .bold_line_style(
@@ -614,17 +1476,45 @@ impl charts::point::StyleExt for Styler {
                     .stroke_width(1),
             )
             .light_line_style(&plotters::prelude::BLACK.mix(0.0));
+        match self.ticks.x_angle {
+            LabelAngle::Flat => (),
+            LabelAngle::Vertical => {
+                mesh.x_label_style(
+                    ("sans-serif", 20)
+                        .into_font()
+                        .transform(plotters::style::FontTransform::Rotate90),
+                );
+            }
+        }
     }
 
     fn shape_conf(&self, color: &charts::color::Color) -> plotters::style::ShapeStyle {
         let style = color.stroke_width(3);
         style
     }
+
+    fn dashed(&self) -> bool {
+        self.dashed
+    }
+
+    fn pattern(&self, line: uid::Line) -> Option<charts::pattern::Pattern> {
+        if self.accessible {
+            Some(charts::pattern::Pattern::of_line(line))
+        } else {
+            None
+        }
+    }
+
+    fn time_label(&self, time: time::SinceStart) -> String {
+        self.time_axis.label(time, self.start_date.as_ref())
+    }
 }
 
 impl Chart {
     /// Size of the x-axis label area.
     const X_LABEL_AREA: u32 = 30;
+    /// Size of the x-axis label area when labels are vertical.
+    const VERTICAL_X_LABEL_AREA: u32 = 100;
     /// Size of the y-axis label area.
     const Y_LABEL_AREA: u32 = 120;
     /// Size of the top margin.
@@ -634,8 +1524,17 @@ impl Chart {
 
     /// Difference between the chart's canvas x-size and the chart's x-size.
     const CHART_X_DIFF: u32 = Self::Y_LABEL_AREA + Self::RIGHT_MARGIN;
+    /// Size of the x-axis label area for some label angle.
+    fn x_label_area(x_angle: LabelAngle) -> u32 {
+        match x_angle {
+            LabelAngle::Flat => Self::X_LABEL_AREA,
+            LabelAngle::Vertical => Self::VERTICAL_X_LABEL_AREA,
+        }
+    }
     /// Difference between the chart's canvas y-size and the chart's y-size.
-    const CHART_Y_DIFF: u32 = Self::X_LABEL_AREA + Self::TOP_MARGIN;
+    fn chart_y_diff(x_angle: LabelAngle) -> u32 {
+        Self::x_label_area(x_angle) + Self::TOP_MARGIN
+    }
 
     /// Draws the chart, **takes care of updating `self.redraw`**.
     ///
@@ -648,13 +1547,41 @@ impl Chart {
             return Ok(());
         }
 
+        let (width, height) = match self.chart.as_ref() {
+            Some((_, canvas)) => Self::client_size(canvas),
+            None => return Ok(()),
+        };
+        let ticks = self.update_resolution(width, height);
+        let styler = Styler {
+            dashed: self.estimate,
+            accessible: self.accessible,
+            ticks,
+            resolution: Self::plot_resolution(width, height, ticks.x_angle),
+            time_axis: self.settings.time_axis(),
+            start_date: self.start_date,
+        };
+
         let visible_filters = self.spec.active();
+        let settings = &self.settings;
+        let segments = self.spec.y_axis().has_segment_lines();
+        let segment_specs = self.segment_specs();
+
+        let zoomed = match (&self.zoom, &self.points) {
+            (Some(window), Some(points)) => {
+                let mut points = points.clone();
+                points.zoom(window);
+                Some(points)
+            }
+            (None, _) | (_, None) => None,
+        };
+        // Only borrow the points, the x-range is assigned below.
+        let own_points = &self.points;
+        let points = zoomed.as_ref().or_else(|| own_points.as_ref());
+        self.x_range = points.and_then(point::Points::time_range);
 
         if let Some((chart, canvas)) = &mut self.chart {
-            let width = canvas.client_width();
-            canvas.set_width(if width >= 0 { width as u32 } else { 0 });
-            let height = canvas.client_height();
-            canvas.set_height(if height >= 0 { height as u32 } else { 0 });
+            canvas.set_width(width);
+            canvas.set_height(height);
 
             let (chart_w, chart_h) = (canvas.width(), canvas.height());
 
@@ -668,14 +1595,14 @@ impl Chart {
                 html_canvas.set_height(chart_h)
             }
 
-            if let Some(points) = &self.points {
+            if let Some(points) = points {
                 chart.fill(&plotters::style::colors::WHITE).unwrap();
 
                 let mut builder = plotters::prelude::ChartBuilder::on(&chart);
                 builder
                     .margin_top(Self::TOP_MARGIN)
                     .margin_right(Self::RIGHT_MARGIN)
-                    .x_label_area_size(Self::X_LABEL_AREA)
+                    .x_label_area_size(Self::x_label_area(ticks.x_angle))
                     .y_label_area_size(Self::Y_LABEL_AREA);
 
                 let is_catch_all_active = stats
@@ -683,16 +1610,26 @@ impl Chart {
                     .map(|stats| stats.alloc_count > 0)
                     .unwrap_or(true);
                 let is_active = |f_uid: uid::Line| {
+                    if segments && !f_uid.is_everything() {
+                        return true;
+                    }
                     visible_filters.get(&f_uid).cloned().unwrap_or(false)
+                        && settings.shows_line(f_uid)
                         && (!f_uid.is_catch_all() || is_catch_all_active)
+                        && !filters.is_muted(f_uid)
                 };
 
                 points.render(
-                    &self.settings,
+                    settings,
                     builder,
-                    &Styler,
+                    &styler,
                     is_active,
-                    filters.specs_iter().filter(|spec| is_active(spec.uid())),
+                    filters
+                        .specs_iter()
+                        .filter(|spec| !segments || spec.is_everything())
+                        .chain(segment_specs.iter())
+                        .filter(|spec| is_active(spec.uid())),
+                    &self.gaps,
                 )?;
 
                 chart
@@ -709,6 +1646,18 @@ impl Chart {
 impl Chart {
     /// Runs post-rendering actions.
     pub fn rendered(&mut self, filters: filter::Reference, stats: &AllFilterStats) -> Res<()> {
+        if self.focus_title {
+            self.focus_title = false;
+            use wasm_bindgen::JsCast;
+            if let Some(input) = js::try_get_element_by_id(&self.title_input_id())? {
+                if let Ok(input) = input.dyn_into::<web_sys::HtmlElement>() {
+                    input.focus().map_err(error_from_js_val)?
+                }
+            }
+        }
+        if self.failure.is_some() {
+            return Ok(());
+        }
         self.rebind_canvas()?;
 
         if self.chart.is_none() {
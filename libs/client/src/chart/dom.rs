@@ -0,0 +1,103 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! DOM identifiers of the charts.
+//!
+//! The DOM identifiers of a chart only depend on its UID, they do not change when the chart moves.
+//! Charts register their identifiers in a [`Registry`] when they are created, so that a collision
+//! is an error instead of two charts silently drawing in the same canvas.
+//!
+//! [`Registry`]: struct.Registry.html (The Registry struct)
+
+prelude! {}
+
+/// DOM identifiers of a chart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomIds {
+    /// Top container of the chart, with its tabs.
+    pub top_container: String,
+    /// Direct container of the canvas.
+    pub container: String,
+    /// Canvas.
+    pub canvas: String,
+    /// Collapsed version of the canvas.
+    pub collapsed_canvas: String,
+}
+impl DomIds {
+    /// Identifiers of a chart.
+    pub fn of_uid(uid: uid::Chart) -> Self {
+        let canvas = format!("chart_canvas_{}", uid.get());
+        Self {
+            top_container: format!("chart_container_{}", uid.get()),
+            container: format!("chart_canvas_container_{}", uid.get()),
+            collapsed_canvas: format!("{}_collapsed", canvas),
+            canvas,
+        }
+    }
+
+    /// Iterator over all the identifiers.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        vec![
+            self.top_container.as_str(),
+            self.container.as_str(),
+            self.canvas.as_str(),
+            self.collapsed_canvas.as_str(),
+        ]
+        .into_iter()
+    }
+}
+
+/// DOM identifiers in use, and the chart they belong to.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    /// Maps identifiers to the chart using them.
+    ids: HMap<String, uid::Chart>,
+}
+impl Registry {
+    /// Empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the identifiers of a chart.
+    ///
+    /// Fails without registering anything if any identifier is already in use.
+    pub fn register(&mut self, uid: uid::Chart) -> Res<DomIds> {
+        let ids = DomIds::of_uid(uid);
+        for id in ids.iter() {
+            if let Some(owner) = self.ids.get(id) {
+                bail!(
+                    "cannot create chart #{}, DOM identifier `{}` is already used by chart #{}",
+                    uid,
+                    id,
+                    owner
+                )
+            }
+        }
+        for id in ids.iter() {
+            let _prev = self.ids.insert(id.to_string(), uid);
+        }
+        Ok(ids)
+    }
+
+    /// Unregisters the identifiers of a chart.
+    pub fn unregister(&mut self, uid: uid::Chart) {
+        self.ids.retain(|_, owner| *owner != uid)
+    }
+}
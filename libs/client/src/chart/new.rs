@@ -18,6 +18,9 @@
 */
 
 //! Part of the BUI that handles chart creation.
+//!
+//! Chart creation goes through a dialog listing the axes, incompatible axis pairs are greyed out.
+//! Closing the dialog, by creating the chart or cancelling, discards its state.
 
 prelude! {}
 
@@ -31,6 +34,14 @@ pub struct NewChart {
     pub x_axis: XAxis,
     /// Y-axis selection.
     pub y_axis: YAxis,
+    /// True if the creation dialog is open.
+    pub open: bool,
+    /// Title of the chart, the default title if empty.
+    pub title: String,
+    /// Lines that will not be visible initially.
+    ///
+    /// Lines are visible by default, which includes lines created while the dialog is open.
+    pub hidden: BTSet<uid::Line>,
 }
 
 impl NewChart {
@@ -56,7 +67,13 @@ impl NewChart {
         let x_axis = XAxis::default();
         let y_axis =
             Self::default_y_axis_for(x_axis, None).expect("cannot construct new chart DOM element");
-        Self { x_axis, y_axis }
+        Self {
+            x_axis,
+            y_axis,
+            open: false,
+            title: String::new(),
+            hidden: BTSet::new(),
+        }
     }
 
     /// Sets the x-axis.
@@ -70,10 +87,61 @@ impl NewChart {
 
     /// Sets the y-axis.
     pub fn set_y_axis(&mut self, y_axis: YAxis) -> Res<ShouldRender> {
+        if !self.x_axis.accepts(y_axis) {
+            bail!(
+                "{} axis is not compatible with {} axis",
+                y_axis.desc(),
+                self.x_axis.desc()
+            )
+        }
         self.y_axis = y_axis;
         Ok(true)
     }
 
+    /// Opens or closes the dialog.
+    ///
+    /// Closing the dialog discards its state.
+    pub fn set_open(&mut self, open: bool) -> ShouldRender {
+        if open {
+            self.open = true
+        } else {
+            *self = Self::new()
+        }
+        true
+    }
+
+    /// Sets the title.
+    pub fn set_title(&mut self, title: String) -> ShouldRender {
+        self.title = title;
+        true
+    }
+
+    /// Toggles the initial visibility of a line.
+    pub fn toggle_line(&mut self, line: uid::Line) -> ShouldRender {
+        if !self.hidden.remove(&line) {
+            self.hidden.insert(line);
+        }
+        true
+    }
+
+    /// Title the chart will have, the default title if none was given.
+    fn title_or_default(&self) -> String {
+        if self.title.trim().is_empty() {
+            format!("{} over {}", self.y_axis.desc(), self.x_axis.desc())
+        } else {
+            self.title.trim().to_string()
+        }
+    }
+
+    /// Chart creation message, closes the dialog.
+    pub fn submit(&mut self) -> Msg {
+        let title = Some(self.title.trim().to_string()).filter(|title| !title.is_empty());
+        let visible = self.hidden.iter().map(|line| (*line, false)).collect();
+        let msg = msg::to_server::ChartsMsg::new(self.x_axis, self.y_axis, title, visible);
+        self.set_open(false);
+        msg.into()
+    }
+
     /// Renders itself.
     pub fn render(&self, model: &Model) -> Html {
         define_style! {
@@ -86,32 +154,132 @@ impl NewChart {
             };
         }
 
-        let (x_axis, y_axis) = (self.x_axis, self.y_axis);
+        if !self.open {
+            return html! {
+                <center class="chart_header">
+                    <h2>
+                        <div
+                            style = CREATE_STYLE
+                            onclick = model.link.callback(|_| msg::ChartsMsg::new_chart_open())
+                        >
+                            {"create chart"}
+                        </div>
+                    </h2>
+                </center>
+            };
+        }
 
         html! {
             <center class="chart_header">
-                <h2>
-                    <div
-                        style = CREATE_STYLE
-                        onclick = model.link.callback(
-                            move |_| msg::to_server::ChartsMsg::new(x_axis, y_axis)
-                        )
-                    >
-                        {"create chart"}
-                    </div>
-                    <Select<XAxis>
-                        selected = Some(x_axis)
-                        options = XAxis::all()
-                        on_change = model.link.callback(msg::ChartsMsg::new_chart_set_x)
-                    />
-                    { "    /    " }
-                    <Select<YAxis>
-                        selected = Some(y_axis)
-                        options = x_axis.y_axes()
-                        on_change = model.link.callback(msg::ChartsMsg::new_chart_set_y)
-                    />
-                </h2>
+                <h2>{"new chart"}</h2>
+                {self.render_dialog(model)}
             </center>
         }
     }
+
+    /// Renders the dialog.
+    fn render_dialog(&self, model: &Model) -> Html {
+        define_style! {
+            DIALOG_STYLE = {
+                text_align(left),
+            };
+            DISABLED_STYLE = {
+                fg(gray),
+            };
+        }
+
+        let (x_axis, y_axis) = (self.x_axis, self.y_axis);
+
+        let x_axes = html! {
+            <>{for XAxis::all().into_iter().map(|x| {
+                layout::input::radio(
+                    x == x_axis,
+                    format!("new_chart_x_{}", x.desc()),
+                    x.desc(),
+                    model.link.callback(move |_| msg::ChartsMsg::new_chart_set_x(x)),
+                    model.link.callback(move |_| msg::ChartsMsg::new_chart_set_x(x)),
+                    true,
+                )
+            })}</>
+        };
+
+        let y_axes = html! {
+            <>{for YAxis::all().into_iter().map(|y| {
+                if !x_axis.accepts(y) {
+                    return html! {
+                        <span
+                            style = DISABLED_STYLE
+                            title = format!("not compatible with {} axis", x_axis.desc())
+                        >
+                            {format!(" {} ", y.desc())}
+                        </span>
+                    };
+                }
+                layout::input::radio(
+                    y == y_axis,
+                    format!("new_chart_y_{}", y.desc()),
+                    y.desc(),
+                    model.link.callback(move |_| msg::ChartsMsg::new_chart_set_y(y)),
+                    model.link.callback(move |_| msg::ChartsMsg::new_chart_set_y(y)),
+                    true,
+                )
+            })}</>
+        };
+
+        let filters = model.filters();
+        let lines = html! {
+            <>{for filters.specs_iter().map(|spec| {
+                let line = spec.uid();
+                layout::input::checkbox(
+                    !self.hidden.contains(&line),
+                    format!("new_chart_line_{}", line),
+                    spec.name().to_string(),
+                    model
+                        .link
+                        .callback(move |_| msg::ChartsMsg::new_chart_toggle_line(line)),
+                )
+            })}</>
+        };
+
+        html! {
+            <div style = DIALOG_STYLE>
+                <table>
+                    <tr>
+                        <td>{"title"}</td>
+                        <td>
+                            {layout::input::string_input(model, &self.title, |res| match res {
+                                Ok(title) => msg::ChartsMsg::new_chart_set_title(title),
+                                Err(e) => msg::Msg::err(e),
+                            })}
+                            {format!(" \"{}\"", self.title_or_default())}
+                        </td>
+                    </tr>
+                    <tr>
+                        <td>{"x-axis"}</td>
+                        <td>{x_axes}</td>
+                    </tr>
+                    <tr>
+                        <td>{"y-axis"}</td>
+                        <td>{y_axes}</td>
+                    </tr>
+                    <tr>
+                        <td>{"visible lines"}</td>
+                        <td>{lines}</td>
+                    </tr>
+                </table>
+                {layout::button::text::render_default_button(
+                    "new_chart_create",
+                    "create",
+                    Some(model.link.callback(|_| msg::ChartsMsg::new_chart_submit())),
+                    false,
+                )}
+                {layout::button::text::render_default_button(
+                    "new_chart_cancel",
+                    "cancel",
+                    Some(model.link.callback(|_| msg::ChartsMsg::new_chart_cancel())),
+                    false,
+                )}
+            </div>
+        }
+    }
 }
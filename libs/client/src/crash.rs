@@ -0,0 +1,146 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Panic handling.
+//!
+//! Panics abort in WASM: once the client panics, the model is gone and nothing can be done from
+//! the update loop. The panic hook [installed] at startup replaces the page with a recovery screen
+//! showing a diagnostic report, and sends this report to the server so that it ends up in its log.
+//!
+//! The report mentions the last messages the model handled, which the model [records] as it goes.
+//!
+//! Reloading the page does not lose much: the server keeps the charts, the filters and the notes,
+//! and the URL fragment keeps the view state.
+//!
+//! [installed]: fn.install.html (The install function)
+//! [records]: fn.record.html (The record function)
+
+prelude! {}
+
+use std::{cell::RefCell, collections::VecDeque};
+use wasm::*;
+
+/// Number of messages the report mentions.
+pub const HISTORY_LEN: usize = 32;
+
+thread_local! {
+    /// Last messages handled by the model, most recent last.
+    static HISTORY: RefCell<VecDeque<String>> = RefCell::new(VecDeque::with_capacity(HISTORY_LEN));
+}
+
+/// Records a message handled by the model.
+pub fn record(msg: &impl fmt::Display) {
+    HISTORY.with(|history| {
+        if let Ok(mut history) = history.try_borrow_mut() {
+            if history.len() >= HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(msg.to_string())
+        }
+    })
+}
+
+/// Installs the panic hook.
+pub fn install() {
+    std::panic::set_hook(Box::new(|info| {
+        let report = report(&info.to_string());
+        log::error!("{}", report);
+        if let Err(e) = render_recovery(&report) {
+            log::error!("failed to render the recovery screen: {}", e)
+        }
+        if let Err(e) = send_report(&report) {
+            log::error!("failed to report the panic to the server: {}", e)
+        }
+    }))
+}
+
+/// Builds the diagnostic report for a panic.
+fn report(panic: &str) -> String {
+    let mut report = format!(
+        "memthol client v{} {}\n\nstack:\n{}\n\nlast messages, most recent last:",
+        env!("CARGO_PKG_VERSION"),
+        panic,
+        js::stack_trace(),
+    );
+    HISTORY.with(|history| match history.try_borrow() {
+        Ok(history) if history.is_empty() => report.push_str("\n(none)"),
+        Ok(history) => {
+            for msg in history.iter() {
+                report.push_str("\n- ");
+                report.push_str(msg)
+            }
+        }
+        Err(_) => report.push_str("\n(unavailable)"),
+    });
+    report
+}
+
+/// Replaces the whole page with the recovery screen.
+///
+/// The buttons only run plain JS: the model is gone, calling back into the client is not an option.
+fn render_recovery(report: &str) -> Res<()> {
+    let body = web_sys::window()
+        .ok_or("could not retrieve window")?
+        .document()
+        .ok_or("could not retrieve document from window")?
+        .body()
+        .ok_or("could not retrieve document body")?;
+    body.set_inner_html(&format!(
+        r#"<div style="margin: 2% 5%; font-family: sans-serif;">
+    <h2>The client crashed</h2>
+    <p>
+        The server is still running and keeps the charts, the filters and the notes. Reloading the
+        page restores them.
+    </p>
+    <p>
+        <button onclick="window.location.reload()">reload the UI</button>
+        <button onclick="navigator.clipboard.writeText(document.getElementById('crash_report').value)">
+            copy diagnostic report
+        </button>
+    </p>
+    <textarea id="crash_report" readonly style="width: 100%; height: 60vh;">{}</textarea>
+</div>"#,
+        charts::chart::export::escape(report)
+    ));
+    Ok(())
+}
+
+/// Sends the report to the server, on a best-effort basis.
+///
+/// The socket of the model is gone with it, the report goes through a new read-only connection.
+fn send_report(report: &str) -> Res<()> {
+    use wasm_bindgen::JsCast;
+
+    let bytes = msg::to_server::Msg::client_panic(report).to_bytes()?;
+    let socket =
+        web_sys::WebSocket::new(&js::server::ws_address(true)?).map_err(error_from_js_val)?;
+    let on_open = {
+        let socket = socket.clone();
+        Closure::wrap(Box::new(move || {
+            if let Err(e) = socket.send_with_u8_array(&bytes) {
+                log::error!("failed to send panic report: {}", error_from_js_val(e))
+            }
+            let _ = socket.close();
+        }) as Box<dyn FnMut()>)
+    };
+    socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+    // The closure must outlive this function, the page is not going anywhere anyway.
+    on_open.forget();
+    Ok(())
+}
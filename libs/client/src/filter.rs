@@ -21,7 +21,9 @@
 
 prelude! {}
 
-pub use charts::filter::{stats::AllFilterStats, Filter, FilterSpec, SubFilter};
+pub use charts::filter::{
+    preview::Preview, stats::AllFilterStats, Filter, FilterName, FilterSpec, GroupOp, SubFilter,
+};
 
 /// Stores filter states.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -43,6 +45,17 @@ impl Default for FilterStates {
     }
 }
 impl FilterStates {
+    /// The filters as a collection, for diffing.
+    pub fn collection(&self) -> charts::filter::diff::Collection {
+        charts::filter::diff::Collection::new(&self.everything, &self.filters, &self.catch_all)
+    }
+
+    /// True if some filters do not contrast enough with the chart background.
+    pub fn has_low_contrast(&self) -> bool {
+        self.specs_iter()
+            .any(|spec| spec.color().has_low_contrast())
+    }
+
     /// Returns the current index and state for a filter from its UID.
     pub fn get_filter(&self, uid: uid::Filter) -> Res<(usize, &Filter)> {
         self.filters
@@ -72,6 +85,13 @@ impl FilterStates {
             .ok_or_else(|| format!("unknown filter uid #{}", uid).into())
     }
 
+    /// True if the line of a filter is muted, false if the filter is unknown.
+    pub fn is_muted(&self, uid: uid::Line) -> bool {
+        self.get(uid)
+            .map(|(_, spec)| spec.is_muted())
+            .unwrap_or(false)
+    }
+
     /// Returns the current index and state for a filter from its UID.
     pub fn get(&self, uid: uid::Line) -> Res<(Option<usize>, &FilterSpec)> {
         match uid {
@@ -80,6 +100,7 @@ impl FilterStates {
             uid::Line::Filter(uid) => self
                 .get_filter(uid)
                 .map(|(idx, filter)| (Some(idx), filter.spec())),
+            uid::Line::Segment(_) => bail!("line `{}` is not a filter", uid),
         }
     }
     /// Returns the current index and mutable state for a filter from its UID.
@@ -90,6 +111,7 @@ impl FilterStates {
             uid::Line::Filter(uid) => self
                 .get_filter_mut(uid)
                 .map(|(idx, filter)| (Some(idx), filter.spec_mut())),
+            uid::Line::Segment(_) => bail!("line `{}` is not a filter", uid),
         }
     }
     /// The filters to render.
@@ -106,6 +128,8 @@ impl FilterStates {
     }
 
     /// (In)active filters to render.
+    ///
+    /// Muted filters are never rendered.
     fn inner_filters_to_render<'me>(
         &'me self,
         is_active: &'me impl Fn(&FilterSpec) -> bool,
@@ -118,23 +142,24 @@ impl FilterStates {
         let has_user_filters = !self.filters.is_empty();
         (
             // Everything.
-            if is_active(&self.everything) == active {
+            if !self.everything.is_muted() && is_active(&self.everything) == active {
                 Some(&self.everything)
             } else {
                 None
             },
             // Custom filters.
             if has_user_filters {
-                Some(
-                    self.filters
-                        .iter()
-                        .filter(move |filter| is_active(filter.spec()) == active),
-                )
+                Some(self.filters.iter().filter(move |filter| {
+                    !filter.spec().is_muted() && is_active(filter.spec()) == active
+                }))
             } else {
                 None
             },
             // Catch-all.
-            if has_user_filters && is_active(&self.catch_all) == active {
+            if has_user_filters
+                && !self.catch_all.is_muted()
+                && is_active(&self.catch_all) == active
+            {
                 Some(&self.catch_all)
             } else {
                 None
@@ -217,14 +242,63 @@ pub enum FReference {}
 /// Reference filter states.
 pub type Reference<'a> = F<'a, FReference>;
 
+/// Maximal number of edits that can be undone.
+pub const HISTORY_LEN: usize = 50;
+
+/// Delay between the last edit of a subfilter and the preview request, in milliseconds.
+pub const PREVIEW_DELAY_MS: u64 = 500;
+
+/// Delay between the last keystroke in a label and the completion request, in milliseconds.
+pub const COMPLETION_DELAY_MS: u64 = 250;
+
+/// Identifier of the list of completions of the label being typed.
+pub const LABEL_COMPLETIONS_ID: &str = "label_completions";
+
+/// Preview of the subfilter being edited.
+#[derive(Debug, Clone)]
+pub struct SubPreview {
+    /// Filter the subfilter belongs to.
+    pub filter: uid::Filter,
+    /// Subfilter being edited.
+    pub sub: uid::SubFilter,
+    /// Identifier of the preview, a reply with a different identifier is outdated.
+    id: u64,
+    /// Preview, or the reason it failed; `None` while waiting for the server.
+    pub res: Option<Result<Preview, String>>,
+}
+
 /// Stores the current/old filter states, as well as the filter statistics.
 pub struct FilterInfo {
     /// Link to the model.
     pub link: Link,
     /// Current and reference states.
     pub states: Memory<FilterStates>,
+    /// Current states before the last edits, most recent last, see [`HISTORY_LEN`].
+    ///
+    /// [`HISTORY_LEN`]: constant.HISTORY_LEN.html (The HISTORY_LEN constant)
+    undo: Vec<FilterStates>,
+    /// Current states before the last undos, most recent last.
+    redo: Vec<FilterStates>,
     /// Filter statistics for the reference filter states.
     pub reference_stats: AllFilterStats,
+    /// Text of the import area, `None` if the area is closed.
+    pub import: Option<String>,
+    /// Reason why the filters of the import area are illegal, if any.
+    pub import_error: Option<String>,
+    /// Preview of the subfilter being edited, if any.
+    pub preview: Option<SubPreview>,
+    /// Pending preview request, dropping it cancels the request.
+    preview_task: Option<TimeoutTask>,
+    /// Identifier of the last preview.
+    preview_id: u64,
+    /// Completions of the label being typed, as label specifications.
+    pub label_completions: Vec<String>,
+    /// Prefix of the last completion request, if any.
+    completion_prefix: Option<String>,
+    /// Pending completion request, dropping it cancels the request.
+    completion_task: Option<TimeoutTask>,
+    /// Groups the subfilters requested from the server go to, in the order of the requests.
+    new_sub_groups: Vec<(uid::Filter, Vec<usize>)>,
 }
 
 impl FilterInfo {
@@ -234,7 +308,18 @@ impl FilterInfo {
         Self {
             link,
             states,
+            undo: vec![],
+            redo: vec![],
             reference_stats: AllFilterStats::new(),
+            import: None,
+            import_error: None,
+            preview: None,
+            preview_task: None,
+            preview_id: 0,
+            label_completions: vec![],
+            completion_prefix: None,
+            completion_task: None,
+            new_sub_groups: vec![],
         }
     }
 
@@ -275,6 +360,7 @@ impl FilterInfo {
         match uid {
             uid::Line::Everything => current.everything != reference.everything,
             uid::Line::CatchAll => current.catch_all != reference.catch_all,
+            uid::Line::Segment(_) => false,
             uid::Line::Filter(uid) => {
                 let (ref_index, ref_filter) = if let Ok((idx, filter)) = reference.get_filter(uid) {
                     (idx, filter)
@@ -292,6 +378,12 @@ impl FilterInfo {
         }
     }
 
+    /// Changes from the reference filter states to the current ones.
+    pub fn changes(&self) -> Vec<charts::filter::diff::Change> {
+        let (current, reference) = (self.states.get(), self.states.reference());
+        reference.collection().diff(&current.collection())
+    }
+
     /// Returns the current index and state for a filter from its UID.
     fn get_filter(&self, uid: uid::Filter) -> Res<(usize, &Filter)> {
         self.states
@@ -339,6 +431,7 @@ impl FilterInfo {
             uid::Line::Filter(uid) => self
                 .get_filter(uid)
                 .map(|(idx, filter)| (Some(idx), filter.spec())),
+            uid::Line::Segment(_) => bail!("line `{}` is not a filter", uid),
         }
     }
     /// Returns the current index and mutable state for a filter from its UID.
@@ -349,6 +442,7 @@ impl FilterInfo {
             uid::Line::Filter(uid) => self
                 .get_filter_mut(uid)
                 .map(|(idx, filter)| (Some(idx), filter.spec_mut())),
+            uid::Line::Segment(_) => bail!("line `{}` is not a filter", uid),
         }
     }
 }
@@ -380,6 +474,18 @@ impl FilterInfo {
         Ok(true)
     }
 
+    /// Asks the server for a copy of a filter.
+    ///
+    /// The copy is made from the current version of the filter, unsaved edits included. The server
+    /// gives it fresh UIDs, since the client cannot know which UIDs are free, and sends it back as
+    /// a new filter. The copy then shows as edited until the user saves it.
+    fn duplicate(&mut self, uid: uid::Filter) -> Res<ShouldRender> {
+        let (_, filter) = self.get_filter(uid)?;
+        self.link
+            .send_message(msg::to_server::FiltersMsg::duplicate(filter.clone()));
+        Ok(false)
+    }
+
     /// Changes the name of a filter.
     fn change_name(&mut self, uid: uid::Line, new_name: ChangeData) -> Res<()> {
         let new_name = match new_name {
@@ -388,9 +494,7 @@ impl FilterInfo {
                 bail!("unexpected text field update {:?}", err)
             }
         };
-        if new_name.is_empty() {
-            bail!("filter names cannot be empty")
-        }
+        let new_name = filter::FilterName::new(new_name)?;
         let (_, spec) = self
             .get_mut(uid)
             .chain_err(|| "while updating a filter's name")?;
@@ -400,10 +504,20 @@ impl FilterInfo {
     }
 
     /// Changes the color of a filter.
+    ///
+    /// Illegal colors raise a warning listing the accepted formats, and leave the color of the
+    /// filter unchanged.
     fn change_color(&mut self, uid: uid::Line, new_color: ChangeData) -> Res<()> {
         let new_color = match new_color {
-            yew::html::ChangeData::Value(new_color) => charts::color::Color::from_str(new_color)
-                .chain_err(|| "while changing the color of a filter")?,
+            yew::html::ChangeData::Value(new_color) => {
+                match charts::color::Color::from_str(new_color) {
+                    Ok(color) => color,
+                    Err(e) => {
+                        self.link.send_message(msg::Msg::warn(e.to_string()));
+                        return Ok(());
+                    }
+                }
+            }
             err @ yew::html::ChangeData::Select(_) | err @ yew::html::ChangeData::Files(_) => {
                 bail!("unexpected text field update {:?}", err)
             }
@@ -417,6 +531,254 @@ impl FilterInfo {
         Ok(())
     }
 
+    /// Undoes a single change from the reference filter states to the current ones.
+    ///
+    /// Leaves the other changes alone, *e.g.* reverting a move puts the filter back at its old
+    /// index even if other filters were added or removed since.
+    fn revert_change(&mut self, change: charts::filter::diff::Change) -> Res<()> {
+        use charts::filter::diff::Change::*;
+        match change {
+            Added { uid, .. } => {
+                let _ = self.rm_filter(uid)?;
+            }
+            Removed { index, filter } => {
+                let filters = &mut self.states.get_mut().filters;
+                let index = std::cmp::min(index, filters.len());
+                filters.insert(index, filter)
+            }
+            Renamed { uid, old, .. } => {
+                let (_, spec) = self.get_mut(uid)?;
+                spec.set_name(old)
+            }
+            Recolored { uid, old, .. } => {
+                let (_, spec) = self.get_mut(uid)?;
+                spec.set_color(old)
+            }
+            SubAdded { uid, sub } => {
+                let (_, filter) = self.get_filter_mut(uid)?;
+                filter.remove(sub.uid())?
+            }
+            SubRemoved { uid, sub } => {
+                let (_, filter) = self.get_filter_mut(uid)?;
+                filter.insert(sub)?
+            }
+            SubModified { uid, old, .. } => {
+                let (_, filter) = self.get_filter_mut(uid)?;
+                filter.replace(old)?
+            }
+            Regrouped { uid, mut old, .. } => {
+                let (_, filter) = self.get_filter_mut(uid)?;
+                // Forget the subfilters removed since.
+                old.rename_subs(&|sub| {
+                    Some(sub).filter(|sub| filter.iter().any(|s| s.uid() == *sub))
+                });
+                filter.set_root(old)?
+            }
+            SubsReordered { uid, old } => {
+                let (_, filter) = self.get_filter_mut(uid)?;
+                filter.reorder(&old)
+            }
+            Moved { uid, old, .. } => {
+                let (_, filter) = self.rm_filter(uid)?;
+                let filters = &mut self.states.get_mut().filters;
+                let index = std::cmp::min(old, filters.len());
+                filters.insert(index, filter)
+            }
+        }
+        Ok(())
+    }
+
+    /// Remembers the current states before an edit, if the edit changed them.
+    ///
+    /// Forgets the undos, since they do not apply to the new states.
+    fn record(&mut self, before: FilterStates) {
+        if &before == self.states.get() {
+            return;
+        }
+        self.undo.push(before);
+        if self.undo.len() > HISTORY_LEN {
+            let _ = self.undo.remove(0);
+        }
+        self.redo.clear()
+    }
+
+    /// Undoes the last edit, or the last undo if `redo`.
+    ///
+    /// Edits are undone on the current states only, the filters show as edited until the user
+    /// saves them. Returns false if there is nothing to undo.
+    fn undo(&mut self, redo: bool) -> ShouldRender {
+        let (from, to) = if redo {
+            (&mut self.redo, &mut self.undo)
+        } else {
+            (&mut self.undo, &mut self.redo)
+        };
+        let states = if let Some(states) = from.pop() {
+            states
+        } else {
+            return false;
+        };
+
+        // Focus on the filters that come back, leave the ones that disappear.
+        let current = self.states.get();
+        let is_new = |filter: &&Filter| current.get_filter(filter.uid()).is_err();
+        if let Some(filter) = states.filters.iter().find(is_new) {
+            self.link
+                .send_message(msg::FooterMsg::toggle_tab(uid::Line::Filter(filter.uid())))
+        } else if current.filters.len() > states.filters.len() {
+            self.link
+                .send_message(msg::FooterMsg::toggle_tab(uid::Line::Everything))
+        }
+
+        let previous = std::mem::replace(self.states.get_mut(), states);
+        to.push(previous);
+        true
+    }
+
+    /// Fixes the color of the filters that do not contrast enough with the chart background.
+    ///
+    /// Returns true if some colors changed.
+    fn fix_contrast(&mut self) -> bool {
+        let current = self.states.get_mut();
+        let specs = Some(&mut current.everything)
+            .into_iter()
+            .chain(current.filters.iter_mut().map(Filter::spec_mut))
+            .chain(Some(&mut current.catch_all));
+        let mut changed = false;
+        for spec in specs {
+            if spec.color().has_low_contrast() {
+                let color = spec.color().contrasting();
+                spec.set_color(color);
+                changed = true
+            }
+        }
+        changed
+    }
+
+    /// Mutes or unmutes a filter.
+    ///
+    /// Muting only hides a line, it does not change what the filter catches: unlike other
+    /// modifications, it applies to the reference filters directly and is sent to the server right
+    /// away so that it is persisted with the filters.
+    fn toggle_mute(&mut self, uid: uid::Line) -> Res<ShouldRender> {
+        let muted = {
+            let (_, spec) = self
+                .states
+                .get()
+                .get(uid)
+                .chain_err(|| "while (un)muting a filter")?;
+            !spec.is_muted()
+        };
+        self.states.do_both(|states| {
+            if let Ok((_, spec)) = states.get_mut(uid) {
+                spec.set_muted(muted)
+            }
+        });
+
+        let reference = self.states.reference();
+        self.link
+            .send_message(msg::to_server::FiltersMsg::update_all(
+                reference.everything.clone(),
+                reference.filters.clone(),
+                reference.catch_all.clone(),
+            ));
+        self.link.send_message(msg::ChartsMsg::refresh_filters());
+
+        Ok(true)
+    }
+
+    /// Starts a new preview for a subfilter, superseding the previous one.
+    ///
+    /// If `res` is `None`, the preview is requested from the server after [`PREVIEW_DELAY_MS`]
+    /// milliseconds without edits. Otherwise `res` is the result of the preview, typically the
+    /// reason why the subfilter is illegal.
+    ///
+    /// [`PREVIEW_DELAY_MS`]: constant.PREVIEW_DELAY_MS.html (The PREVIEW_DELAY_MS constant)
+    fn start_preview(
+        &mut self,
+        filter: uid::Filter,
+        sub: uid::SubFilter,
+        res: Option<Result<Preview, String>>,
+    ) {
+        self.preview_id += 1;
+        let id = self.preview_id;
+        self.preview_task = if res.is_none() {
+            Some(TimeoutService::spawn(
+                std::time::Duration::from_millis(PREVIEW_DELAY_MS),
+                self.link.callback(move |()| Msg::send_preview(id)),
+            ))
+        } else {
+            None
+        };
+        self.preview = Some(SubPreview {
+            filter,
+            sub,
+            id,
+            res,
+        });
+    }
+
+    /// Drops the current preview, if any.
+    fn clear_preview(&mut self) {
+        self.preview = None;
+        self.preview_task = None;
+    }
+
+    /// Sends the preview request for the current version of a filter.
+    ///
+    /// Does nothing if the preview was superseded.
+    fn send_preview(&mut self, id: u64) -> Res<ShouldRender> {
+        self.preview_task = None;
+        let filter = match self.preview.as_ref() {
+            Some(preview) if preview.id == id && preview.res.is_none() => preview.filter,
+            _ => return Ok(false),
+        };
+        let (_, filter) = self.get_filter(filter)?;
+        self.link
+            .send_message(msg::to_server::FiltersMsg::preview(id, filter.clone()));
+        Ok(false)
+    }
+
+    /// Requests the completions of a label being typed, superseding the previous request.
+    ///
+    /// The request is sent after [`COMPLETION_DELAY_MS`] milliseconds without keystrokes. Only
+    /// values and prefixes (`foo*`) have completions.
+    ///
+    /// [`COMPLETION_DELAY_MS`]: constant.COMPLETION_DELAY_MS.html
+    /// (The COMPLETION_DELAY_MS constant)
+    fn complete_label(&mut self, text: String) -> ShouldRender {
+        use charts::filter::label::LabelSpec;
+        let prefix = match LabelSpec::new(text) {
+            Ok(LabelSpec::Value(prefix)) | Ok(LabelSpec::Prefix(prefix)) => prefix,
+            _ => {
+                self.completion_prefix = None;
+                self.completion_task = None;
+                let render = !self.label_completions.is_empty();
+                self.label_completions.clear();
+                return render;
+            }
+        };
+        if self.completion_prefix.as_ref() == Some(&prefix) {
+            return false;
+        }
+        self.completion_prefix = Some(prefix.clone());
+        self.completion_task = Some(TimeoutService::spawn(
+            std::time::Duration::from_millis(COMPLETION_DELAY_MS),
+            self.link
+                .callback(move |()| Msg::send_completion(prefix.clone())),
+        ));
+        false
+    }
+
+    /// Sends a completion request, unless it was superseded.
+    fn send_completion(&mut self, prefix: String) -> ShouldRender {
+        self.completion_task = None;
+        if self.completion_prefix.as_ref() == Some(&prefix) {
+            self.link
+                .send_message(msg::to_server::FiltersMsg::complete_label(prefix))
+        }
+        false
+    }
+
     /// Applies an update to a filter.
     fn filter_update(filter: &mut Filter, msg: FilterMsg) -> Res<ShouldRender> {
         match msg {
@@ -428,13 +790,47 @@ impl FilterInfo {
                 filter.remove(uid)?;
                 Ok(true)
             }
+            FilterMsg::RequestSub(_) => bail!("new subfilters come from the server"),
+            FilterMsg::AddGroup(path) => {
+                filter.add_group(&path, filter::GroupOp::And)?;
+                Ok(true)
+            }
+            FilterMsg::Ungroup(path) => {
+                filter.ungroup(&path)?;
+                Ok(true)
+            }
+            FilterMsg::SetGroupOp(path, op) => {
+                filter.set_group_op(&path, op)?;
+                Ok(true)
+            }
+            FilterMsg::MoveSub { uid, up } => filter.shift_sub(uid, up),
         }
     }
 }
 
 impl FilterInfo {
     /// Handles a message.
+    ///
+    /// Edits of the current states can be undone, see [`Msg::Undo`].
+    ///
+    /// [`Msg::Undo`]: enum.Msg.html#variant.Undo (The Undo message)
     pub fn update(&mut self, msg: Msg) -> Res<ShouldRender> {
+        let before = if msg.is_edit() {
+            Some(self.states.get().clone())
+        } else {
+            None
+        };
+        let res = self.inner_update(msg);
+        if let Some(before) = before {
+            self.record(before)
+        }
+        res
+    }
+
+    /// Handles a message, see [`update`].
+    ///
+    /// [`update`]: #method.update (The update method)
+    fn inner_update(&mut self, msg: Msg) -> Res<ShouldRender> {
         match msg {
             Msg::Save => {
                 if !self.states.has_changed() {
@@ -459,8 +855,60 @@ impl FilterInfo {
                 Ok(true)
             }
 
+            Msg::Undo => {
+                self.clear_preview();
+                Ok(self.undo(false))
+            }
+            Msg::Redo => {
+                self.clear_preview();
+                Ok(self.undo(true))
+            }
+
             Msg::Rm(uid) => self.remove(uid),
 
+            Msg::Duplicate(uid) => self.duplicate(uid),
+
+            Msg::FixContrast => Ok(self.fix_contrast()),
+
+            Msg::RevertChange(change) => {
+                self.revert_change(change)
+                    .chain_err(|| "while reverting a change to the filters")?;
+                Ok(true)
+            }
+
+            Msg::ToggleMute(uid) => self.toggle_mute(uid),
+
+            Msg::Export => {
+                self.export()?;
+                Ok(false)
+            }
+            Msg::Import(ImportMsg::Toggle) => {
+                self.import = match self.import {
+                    Some(_) => None,
+                    None => Some(String::new()),
+                };
+                self.import_error = None;
+                Ok(true)
+            }
+            Msg::Import(ImportMsg::Edit(text)) => {
+                self.import = Some(text);
+                Ok(self.import_error.take().is_some())
+            }
+            Msg::Import(ImportMsg::Submit) => self.import(),
+            Msg::CopyText(uid) => {
+                let (_, filter) = self.get_filter(uid)?;
+                js::copy_to_clipboard(&charts::filter::text::to_text(filter))?;
+                self.link.send_message(msg::Msg::info(format!(
+                    "copied filter `{}` as text",
+                    filter.name()
+                )));
+                Ok(false)
+            }
+            Msg::Share => {
+                self.share()?;
+                Ok(false)
+            }
+
             Msg::FilterSpec {
                 uid,
                 msg: SpecMsg::ChangeName(new_name),
@@ -475,9 +923,39 @@ impl FilterInfo {
                 self.change_color(uid, new_color)?;
                 Ok(true)
             }
+            Msg::Filter {
+                uid,
+                msg: FilterMsg::RequestSub(path),
+            } => {
+                self.new_sub_groups.push((uid, path));
+                self.link
+                    .send_message(msg::to_server::FiltersMsg::request_new_sub(uid));
+                Ok(false)
+            }
             Msg::Filter { uid, msg } => {
                 let (_index, filter) = self.get_filter_mut(uid)?;
-                Self::filter_update(filter, msg)
+                let edited = match &msg {
+                    FilterMsg::Sub(sub) => Some(sub.uid()),
+                    FilterMsg::RmSub(_)
+                    | FilterMsg::RequestSub(_)
+                    | FilterMsg::AddGroup(_)
+                    | FilterMsg::Ungroup(_)
+                    | FilterMsg::SetGroupOp(..)
+                    | FilterMsg::MoveSub { .. } => None,
+                };
+                let render = Self::filter_update(filter, msg)?;
+                match edited {
+                    Some(sub) => self.start_preview(uid, sub, None),
+                    None => self.clear_preview(),
+                }
+                Ok(render)
+            }
+            Msg::SendPreview(id) => self.send_preview(id),
+            Msg::CompleteLabel(text) => Ok(self.complete_label(text)),
+            Msg::SendCompletion(prefix) => Ok(self.send_completion(prefix)),
+            Msg::PreviewError { uid, sub, error } => {
+                self.start_preview(uid, sub, Some(Err(error)));
+                Ok(true)
             }
             Msg::Move { uid, left } => {
                 let (index, _) = self.get_filter(uid)?;
@@ -508,13 +986,112 @@ impl FilterInfo {
         }
     }
 
+    /// Downloads the current filters as JSON, also logs them.
+    fn export(&self) -> Res<()> {
+        let current = self.states.get();
+        let json = charts::filter::Export::new(current.catch_all.clone(), current.filters.clone())
+            .to_json()?;
+        log::info!("exported filters:\n{}", json);
+        js::download("memthol_filters.json", "application/json", &json)
+    }
+
+    /// Checks the filters of the import area and sends them to the server.
+    ///
+    /// The area takes exported filters (JSON), that replace the current filters, or filters in the
+    /// textual syntax of the [`text`] module, one per line, that come after the current filters.
+    /// The server gives them fresh UIDs and sends them back, see [`server_update`]. The area stays
+    /// open if the filters are illegal, with the reason why, so that the user can fix them.
+    ///
+    /// [`text`]: ../../charts/filter/text/index.html (The text module)
+    /// [`server_update`]: #method.server_update (The server_update method)
+    fn import(&mut self) -> Res<ShouldRender> {
+        let text = self
+            .import
+            .as_ref()
+            .ok_or("the filter import area is closed")?;
+        let res = if text.trim_start().starts_with('{') {
+            charts::filter::Export::from_json(text)
+                .map(msg::to_server::FiltersMsg::import)
+                .chain_err(|| "while importing filters")
+        } else {
+            charts::filter::text::from_texts(text)
+                .and_then(|filters| {
+                    if filters.is_empty() {
+                        bail!("there are no filters to import")
+                    }
+                    Ok(msg::to_server::FiltersMsg::append(filters))
+                })
+                .chain_err(|| "while importing filters")
+        };
+        match res {
+            Ok(msg) => {
+                self.link.send_message(msg);
+                self.import = None;
+                self.import_error = None
+            }
+            Err(e) => self.import_error = Some(e.to_pretty()),
+        }
+        Ok(true)
+    }
+
+    /// Opens the import area with filters shared *via* a link, see [`text::share`].
+    ///
+    /// Filters that cannot be decoded still open the area, with the reason why.
+    ///
+    /// [`text::share`]: ../../charts/filter/text/fn.share.html (The share function)
+    pub fn offer_shared(&mut self, shared: &str) {
+        match charts::filter::text::unshare(shared) {
+            Ok(text) => {
+                self.import = Some(text);
+                self.import_error = None
+            }
+            Err(e) => {
+                self.import = Some(String::new());
+                self.import_error = Some(e.to_pretty())
+            }
+        }
+    }
+
+    /// Copies a link sharing the current filters to the clipboard.
+    ///
+    /// Opening the link on a client of the same server offers to import the filters.
+    fn share(&self) -> Res<()> {
+        let filters = &self.states.get().filters;
+        let view = charts::view::ViewState {
+            filters: Some(charts::filter::text::share(filters)),
+            ..charts::view::ViewState::default()
+        };
+        let link = format!("{}#{}", js::url::without_fragment()?, view.to_fragment());
+        js::copy_to_clipboard(&link)?;
+        self.link.send_message(msg::Msg::info(format!(
+            "copied a link sharing {} filter(s)",
+            filters.len()
+        )));
+        Ok(())
+    }
+
     /// Applies an operation from the server.
+    ///
+    /// New filters and imports can be undone. New filters get the next palette color their
+    /// siblings do not use. Reverts overwrite the whole states and forget the
+    /// history. Filters the server already registered go to both states, so that they do not show
+    /// as edited. Outdated previews and completions are ignored.
     pub fn server_update(&mut self, msg: msg::from_server::FiltersMsg) -> Res<ShouldRender> {
         use msg::from_server::FiltersMsg::*;
+        let before = self.states.get().clone();
         match msg {
-            Add(filter) => {
+            Add(mut filter) => {
+                // The server does not know the unsaved filters, pick the color again.
+                let color = charts::color::Color::next_palette(
+                    self.current()
+                        .filters
+                        .iter()
+                        .map(|filter| filter.spec().color()),
+                );
+                filter.spec_mut().set_color(color);
                 let uid = filter.uid();
                 self.states.get_mut().filters.push(filter);
+                self.record(before);
                 self.link
                     .send_message(msg::FooterMsg::toggle_tab(footer::FooterTab::filter(
                         uid::Line::Filter(uid),
@@ -522,8 +1099,47 @@ impl FilterInfo {
                 Ok(true)
             }
             AddSub(uid, subfilter) => {
+                let group = self
+                    .new_sub_groups
+                    .iter()
+                    .position(|(filter, _)| *filter == uid)
+                    .map(|index| self.new_sub_groups.remove(index).1);
+                let sub_uid = subfilter.uid();
                 let (_, filter) = self.states.get_mut().get_filter_mut(uid)?;
                 filter.insert(subfilter)?;
+                // The group may have been removed since the request, the subfilter stays in the
+                // root group then.
+                if let Some(path) = group.filter(|path| filter.root().get(path).is_some()) {
+                    filter.move_sub(sub_uid, &path)?
+                }
+                self.record(before);
+                Ok(true)
+            }
+            Import(export) => {
+                // Only the current states change: the imported filters show as edited until the
+                // user saves them.
+                let current = self.states.get_mut();
+                current.catch_all = export.catch_all;
+                current.filters = export.filters;
+                let active = current
+                    .filters
+                    .first()
+                    .map(|filter| uid::Line::Filter(filter.uid()))
+                    .unwrap_or(uid::Line::Everything);
+                self.record(before);
+                self.link.send_message(msg::FooterMsg::toggle_tab(active));
+                Ok(true)
+            }
+            Append(filters) => {
+                // Same as imports, but the filters come after the current ones.
+                let active = filters
+                    .first()
+                    .map(|filter| uid::Line::Filter(filter.uid()));
+                self.states.get_mut().filters.extend(filters);
+                self.record(before);
+                if let Some(active) = active {
+                    self.link.send_message(msg::FooterMsg::toggle_tab(active))
+                }
                 Ok(true)
             }
             Revert {
@@ -543,8 +1159,43 @@ impl FilterInfo {
                     filters,
                     catch_all,
                 });
+                self.undo.clear();
+                self.redo.clear();
+                self.clear_preview();
+                Ok(true)
+            }
+            Preview {
+                id,
+                filter,
+                preview,
+            } => match self.preview.as_mut() {
+                Some(current) if current.id == id && current.filter == filter => {
+                    current.res = Some(preview);
+                    Ok(true)
+                }
+                // Superseded.
+                _ => Ok(false),
+            },
+            LabelCompletions { prefix, labels } => {
+                if self.completion_prefix.as_ref() != Some(&prefix) {
+                    return Ok(false);
+                }
+                self.label_completions = labels
+                    .into_iter()
+                    .map(|label| charts::filter::label::LabelSpec::Value(label).to_string())
+                    .collect();
+                Ok(true)
+            }
+            AddSaved(filter) => {
+                let uid = filter.uid();
+                self.states
+                    .do_both(|states| states.filters.push(filter.clone()));
+                self.link
+                    .send_message(msg::FooterMsg::toggle_tab(footer::FooterTab::filter(
+                        uid::Line::Filter(uid),
+                    )));
                 Ok(true)
-            } // UpdateSpecs(specs) => self.update_specs(specs),
+            }
         }
     }
 }
@@ -556,6 +1207,32 @@ pub enum Msg {
     Save,
     /// Removes a filter.
     Rm(uid::Filter),
+    /// Duplicates a filter.
+    Duplicate(uid::Filter),
+    /// Fixes the color of the filters that do not contrast enough with the chart background.
+    FixContrast,
+    /// Undoes the last edit of the filters, see [`HISTORY_LEN`].
+    ///
+    /// [`HISTORY_LEN`]: constant.HISTORY_LEN.html (The HISTORY_LEN constant)
+    Undo,
+    /// Redoes the last undo.
+    Redo,
+    /// Undoes a single change to the filters, see [`FilterInfo::changes`].
+    ///
+    /// [`FilterInfo::changes`]: struct.FilterInfo.html#method.changes (The changes method)
+    RevertChange(charts::filter::diff::Change),
+    /// Mutes or unmutes a filter.
+    ToggleMute(uid::Line),
+    /// Downloads the current filters as JSON.
+    Export,
+    /// Filter import.
+    Import(ImportMsg),
+    /// Copies a filter to the clipboard, in the textual syntax of the [`text`] module.
+    ///
+    /// [`text`]: ../../charts/filter/text/index.html (The text module)
+    CopyText(uid::Filter),
+    /// Copies a link sharing the current filters to the clipboard.
+    Share,
     /// A message for a specific filter specification.
     FilterSpec {
         /// Uid of the filter.
@@ -577,6 +1254,22 @@ pub enum Msg {
         /// Move left iff true.
         left: bool,
     },
+    /// Requests a preview from the server, unless the preview was superseded.
+    SendPreview(u64),
+    /// Reports an illegal subfilter edit in the preview of the subfilter.
+    PreviewError {
+        /// Filter UID.
+        uid: uid::Filter,
+        /// Subfilter UID.
+        sub: uid::SubFilter,
+        /// Error message.
+        error: String,
+    },
+    /// Requests the completions of a label being typed, after a delay.
+    CompleteLabel(String),
+    /// Requests the completions of a label prefix from the server, unless the request was
+    /// superseded.
+    SendCompletion(String),
 }
 
 impl Msg {
@@ -584,10 +1277,50 @@ impl Msg {
     pub fn save() -> Msg {
         Self::Save.into()
     }
+    /// Undoes the last edit of the filters.
+    pub fn undo() -> Msg {
+        Self::Undo.into()
+    }
+    /// Redoes the last undo.
+    pub fn redo() -> Msg {
+        Self::Redo.into()
+    }
     /// Removes a filter.
     pub fn rm(uid: uid::Filter) -> Msg {
         Self::Rm(uid).into()
     }
+    /// Duplicates a filter.
+    pub fn duplicate(uid: uid::Filter) -> Msg {
+        Self::Duplicate(uid).into()
+    }
+    /// Fixes the color of the filters that do not contrast enough with the chart background.
+    pub fn fix_contrast() -> Msg {
+        Self::FixContrast.into()
+    }
+    /// Undoes a single change to the filters.
+    pub fn revert_change(change: charts::filter::diff::Change) -> Msg {
+        Self::RevertChange(change).into()
+    }
+    /// Mutes or unmutes a filter.
+    pub fn toggle_mute(uid: uid::Line) -> Msg {
+        Self::ToggleMute(uid).into()
+    }
+    /// Downloads the current filters as JSON.
+    pub fn export() -> Msg {
+        Self::Export.into()
+    }
+    /// Filter import.
+    pub fn import(msg: ImportMsg) -> Msg {
+        Self::Import(msg).into()
+    }
+    /// Copies a filter to the clipboard, as text.
+    pub fn copy_text(uid: uid::Filter) -> Msg {
+        Self::CopyText(uid).into()
+    }
+    /// Copies a link sharing the current filters to the clipboard.
+    pub fn share() -> Msg {
+        Self::Share.into()
+    }
     /// A message for a specific filter specification.
     pub fn filter_spec(uid: uid::Line, msg: SpecMsg) -> Msg {
         Self::FilterSpec { uid, msg }.into()
@@ -600,6 +1333,58 @@ impl Msg {
     pub fn move_filter(uid: uid::Filter, left: bool) -> Msg {
         Self::Move { uid, left }.into()
     }
+    /// Requests a preview from the server.
+    pub fn send_preview(id: u64) -> Msg {
+        Self::SendPreview(id).into()
+    }
+    /// Reports an illegal subfilter edit in the preview of the subfilter.
+    pub fn preview_error(uid: uid::Filter, sub: uid::SubFilter, error: String) -> Msg {
+        Self::PreviewError { uid, sub, error }.into()
+    }
+    /// Requests the completions of a label being typed, after a delay.
+    pub fn complete_label(text: String) -> Msg {
+        Self::CompleteLabel(text).into()
+    }
+    /// Requests the completions of a label prefix from the server.
+    pub fn send_completion(prefix: String) -> Msg {
+        Self::SendCompletion(prefix).into()
+    }
+
+    /// True if the message edits the current filter states, *i.e.* it can be undone.
+    pub fn is_edit(&self) -> bool {
+        match self {
+            Self::Rm(_)
+            | Self::FixContrast
+            | Self::RevertChange(_)
+            | Self::FilterSpec { .. }
+            | Self::Filter { .. }
+            | Self::Move { .. } => true,
+            Self::Save
+            | Self::Undo
+            | Self::Redo
+            | Self::Duplicate(_)
+            | Self::ToggleMute(_)
+            | Self::Export
+            | Self::Import(_)
+            | Self::CopyText(_)
+            | Self::Share
+            | Self::SendPreview(_)
+            | Self::PreviewError { .. }
+            | Self::CompleteLabel(_)
+            | Self::SendCompletion(_) => false,
+        }
+    }
+}
+
+/// An action over the filter import area.
+#[derive(Debug)]
+pub enum ImportMsg {
+    /// Opens or closes the area.
+    Toggle,
+    /// Changes the text of the area.
+    Edit(String),
+    /// Imports the filters of the area.
+    Submit,
 }
 
 /// An action over the specification of a filter.
@@ -628,6 +1413,21 @@ pub enum FilterMsg {
     Sub(filter::SubFilter),
     /// Removes a subfilter.
     RmSub(uid::SubFilter),
+    /// Requests a new subfilter from the server, for the group at some path.
+    RequestSub(Vec<usize>),
+    /// Adds a nested group to the group at some path.
+    AddGroup(Vec<usize>),
+    /// Removes the nested group at some path, its members go to its parent group.
+    Ungroup(Vec<usize>),
+    /// Sets the operator of the group at some path.
+    SetGroupOp(Vec<usize>, filter::GroupOp),
+    /// Moves a subfilter up or down among the subfilters of its group.
+    MoveSub {
+        /// UID of the subfilter.
+        uid: uid::SubFilter,
+        /// True if the subfilter goes up.
+        up: bool,
+    },
 }
 impl FilterMsg {
     /// Updates a subfilter.
@@ -638,6 +1438,26 @@ impl FilterMsg {
     pub fn rm_sub(uid: uid::Filter, sub_uid: uid::SubFilter) -> msg::Msg {
         Msg::filter(uid, Self::RmSub(sub_uid)).into()
     }
+    /// Requests a new subfilter from the server, for the group at some path.
+    pub fn request_sub(uid: uid::Filter, path: Vec<usize>) -> msg::Msg {
+        Msg::filter(uid, Self::RequestSub(path)).into()
+    }
+    /// Adds a nested group to the group at some path.
+    pub fn add_group(uid: uid::Filter, path: Vec<usize>) -> msg::Msg {
+        Msg::filter(uid, Self::AddGroup(path)).into()
+    }
+    /// Removes the nested group at some path.
+    pub fn ungroup(uid: uid::Filter, path: Vec<usize>) -> msg::Msg {
+        Msg::filter(uid, Self::Ungroup(path)).into()
+    }
+    /// Sets the operator of the group at some path.
+    pub fn set_group_op(uid: uid::Filter, path: Vec<usize>, op: filter::GroupOp) -> msg::Msg {
+        Msg::filter(uid, Self::SetGroupOp(path, op)).into()
+    }
+    /// Moves a subfilter up or down among the subfilters of its group.
+    pub fn move_sub(uid: uid::Filter, sub_uid: uid::SubFilter, up: bool) -> msg::Msg {
+        Msg::filter(uid, Self::MoveSub { uid: sub_uid, up }).into()
+    }
 }
 
 base::implement! {
@@ -652,9 +1472,32 @@ base::implement! {
             |&self, fmt| match self {
                 Self::Save => write!(fmt, "save"),
                 Self::Rm(f_uid) => write!(fmt, "rm {}", f_uid),
+                Self::Duplicate(f_uid) => write!(fmt, "duplicate {}", f_uid),
+                Self::FixContrast => write!(fmt, "fix contrast"),
+                Self::Undo => write!(fmt, "undo"),
+                Self::Redo => write!(fmt, "redo"),
+                Self::RevertChange(change) => write!(fmt, "revert change, {}", change),
+                Self::ToggleMute(uid) => write!(fmt, "toggle mute {}", uid),
+                Self::Export => write!(fmt, "export"),
+                Self::Import(msg) => write!(fmt, "import, {}", msg),
+                Self::CopyText(uid) => write!(fmt, "copy {} as text", uid),
+                Self::Share => write!(fmt, "share"),
                 Self::FilterSpec { uid, msg } => write!(fmt, "filter spec {}, {}", uid, msg),
                 Self::Filter { uid, msg } => write!(fmt, "filter {}, {}", uid, msg),
                 Self::Move { uid, left } => write!(fmt, "move {} ({})", uid, left),
+                Self::SendPreview(id) => write!(fmt, "send preview #{}", id),
+                Self::PreviewError { uid, sub, .. } => write!(fmt, "preview error {}/{}", uid, sub),
+                Self::CompleteLabel(text) => write!(fmt, "complete label `{}`", text),
+                Self::SendCompletion(prefix) => write!(fmt, "send completion `{}`", prefix),
+            }
+        }
+    }
+    impl ImportMsg {
+        Display {
+            |&self, fmt| match self {
+                Self::Toggle => write!(fmt, "toggle"),
+                Self::Edit(_) => write!(fmt, "edit"),
+                Self::Submit => write!(fmt, "submit"),
             }
         }
     }
@@ -671,6 +1514,13 @@ base::implement! {
             |&self, fmt| match self {
                 Self::Sub(_) => write!(fmt, "subfilter update"),
                 Self::RmSub(_) => write!(fmt, "remove subfilter"),
+                Self::RequestSub(path) => write!(fmt, "request subfilter in {:?}", path),
+                Self::AddGroup(path) => write!(fmt, "add group in {:?}", path),
+                Self::Ungroup(path) => write!(fmt, "ungroup {:?}", path),
+                Self::SetGroupOp(path, op) => write!(fmt, "set operator of {:?} to {}", path, op),
+                Self::MoveSub { uid, up } => {
+                    write!(fmt, "move subfilter {} {}", uid, if *up { "up" } else { "down" })
+                }
             }
         }
     }
@@ -45,6 +45,89 @@ use wasm::*;
 extern "C" {
     /// Issues an alert.
     pub fn alert(s: &str);
+
+    /// Asks the user for some text, `None` if the user cancelled.
+    pub fn prompt(s: &str, default: &str) -> Option<String>;
+
+    /// Percent-encodes a string for use in a URI.
+    #[wasm_bindgen(js_name = encodeURIComponent)]
+    fn encode_uri_component(s: &str) -> String;
+
+    /// JS resize observer.
+    #[wasm_bindgen(js_name = ResizeObserver)]
+    type JsResizeObserver;
+
+    /// Creates a resize observer calling a JS function.
+    #[wasm_bindgen(constructor, js_class = "ResizeObserver")]
+    fn new(callback: &JsValue) -> JsResizeObserver;
+
+    /// Starts observing an element.
+    #[wasm_bindgen(method, js_class = "ResizeObserver")]
+    fn observe(this: &JsResizeObserver, target: &web_sys::Element);
+
+    /// Stops observing all elements.
+    #[wasm_bindgen(method, js_class = "ResizeObserver")]
+    fn disconnect(this: &JsResizeObserver);
+
+    /// JS error, only used to retrieve stack traces.
+    #[wasm_bindgen(js_name = Error)]
+    type JsError;
+
+    /// Creates an error, capturing the current stack.
+    #[wasm_bindgen(constructor, js_class = "Error")]
+    fn new() -> JsError;
+
+    /// Stack of an error.
+    #[wasm_bindgen(method, getter, js_class = "Error")]
+    fn stack(this: &JsError) -> String;
+
+    /// JS navigator, only used for its clipboard which web-sys only exposes as an unstable API.
+    #[wasm_bindgen(js_name = Navigator)]
+    type JsNavigator;
+
+    /// Clipboard of the browser, undefined outside of secure contexts.
+    #[wasm_bindgen(method, getter, js_class = "Navigator")]
+    fn clipboard(this: &JsNavigator) -> JsClipboard;
+
+    /// JS clipboard.
+    #[wasm_bindgen(js_name = Clipboard)]
+    type JsClipboard;
+
+    /// Writes some text to the clipboard, asynchronously.
+    #[wasm_bindgen(catch, method, js_name = writeText, js_class = "Clipboard")]
+    fn write_text(this: &JsClipboard, text: &str) -> Result<JsValue, JsValue>;
+}
+
+/// Current JS stack trace.
+pub fn stack_trace() -> String {
+    JsError::new().stack()
+}
+
+/// Calls a function each time an element is resized, until dropped.
+pub struct ResizeObserver {
+    /// Actual JS observer.
+    observer: JsResizeObserver,
+    /// Function called by the observer, must live as long as the observer.
+    _callback: Closure<dyn FnMut()>,
+}
+impl ResizeObserver {
+    /// Constructor.
+    ///
+    /// Note that observers also call `callback` when they start observing.
+    pub fn new(target: &web_sys::Element, callback: impl FnMut() + 'static) -> Self {
+        let callback = Closure::wrap(Box::new(callback) as Box<dyn FnMut()>);
+        let observer = JsResizeObserver::new(callback.as_ref());
+        observer.observe(target);
+        Self {
+            observer,
+            _callback: callback,
+        }
+    }
+}
+impl Drop for ResizeObserver {
+    fn drop(&mut self) {
+        self.observer.disconnect()
+    }
 }
 
 /// Alias type for `wasm_bindgen`'s `JsValue`.
@@ -67,6 +150,109 @@ pub fn get_element_by_id(id: &str) -> Res<web_sys::Element> {
     Ok(res)
 }
 
+/// Makes the browser download some HTML content as a file.
+pub fn download_html(file_name: &str, html: &str) -> Res<()> {
+    download(file_name, "text/html", html)
+}
+
+/// Makes the browser download some content as a file.
+pub fn download(file_name: &str, mime: &str, content: &str) -> Res<()> {
+    use wasm_bindgen::JsCast;
+    let document = web_sys::window()
+        .ok_or("could not retrieve window")?
+        .document()
+        .ok_or("could not retrieve document from window")?;
+    let link: web_sys::HtmlElement = document
+        .create_element("a")
+        .map_err(error_from_js_val)?
+        .dyn_into()
+        .map_err(|_| "could not create download link")?;
+    let href = format!(
+        "data:{};charset=utf-8,{}",
+        mime,
+        encode_uri_component(content)
+    );
+    link.set_attribute("href", &href)
+        .map_err(error_from_js_val)?;
+    link.set_attribute("download", file_name)
+        .map_err(error_from_js_val)?;
+    link.click();
+    Ok(())
+}
+
+/// Copies some text to the clipboard.
+///
+/// Browsers only expose the clipboard in secure contexts, *e.g.* over HTTPS or on localhost.
+/// Elsewhere, the text goes in a prompt so that users can copy it themselves.
+pub fn copy_to_clipboard(text: &str) -> Res<()> {
+    use wasm_bindgen::JsCast;
+    let navigator: JsNavigator = web_sys::window()
+        .ok_or("could not retrieve window")?
+        .navigator()
+        .unchecked_into();
+    let clipboard = navigator.clipboard();
+    if clipboard.is_undefined() {
+        let _ = prompt("Copy the text below.", text);
+    } else {
+        clipboard.write_text(text).map_err(error_from_js_val)?;
+    }
+    Ok(())
+}
+
+/// URL fragment handling.
+pub mod url {
+    prelude! {}
+
+    /// Current URL fragment, including the leading `#` if any.
+    pub fn fragment() -> Res<String> {
+        let res = web_sys::window()
+            .ok_or("could not retrieve window")?
+            .location()
+            .hash()
+            .map_err(error_from_js_val)?;
+        Ok(res)
+    }
+
+    /// Replaces the URL fragment, without creating a new history entry.
+    pub fn replace_fragment(fragment: &str) -> Res<()> {
+        let window = web_sys::window().ok_or("could not retrieve window")?;
+        let location = window.location();
+        let url = format!(
+            "{}{}#{}",
+            location.pathname().map_err(error_from_js_val)?,
+            location.search().map_err(error_from_js_val)?,
+            fragment,
+        );
+        window
+            .history()
+            .map_err(error_from_js_val)?
+            .replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url))
+            .map_err(error_from_js_val)?;
+        Ok(())
+    }
+
+    /// Current URL without its fragment, to build links to the page.
+    pub fn without_fragment() -> Res<String> {
+        let location = web_sys::window()
+            .ok_or("could not retrieve window")?
+            .location();
+        let url = format!(
+            "{}{}{}",
+            location.origin().map_err(error_from_js_val)?,
+            location.pathname().map_err(error_from_js_val)?,
+            location.search().map_err(error_from_js_val)?,
+        );
+        Ok(url)
+    }
+
+    /// Current time in milliseconds, used for throttling.
+    pub fn now_ms() -> Option<f64> {
+        web_sys::window()
+            .and_then(|window| window.performance())
+            .map(|performance| performance.now())
+    }
+}
+
 /// Server info.
 pub mod server {
     prelude! {}
@@ -77,21 +263,50 @@ pub mod server {
             .ok_or_else(|| err::Error::from("could not retrieve (window) JS location"))
     }
 
-    /// Retrievs the address and the port of the server.
-    pub fn address() -> Res<(String, usize)> {
-        location()
-            .and_then(|loc| {
-                Ok((
-                    loc.hostname()
-                        .map_err(|js_val| err::Error::from(format!("{:?}", js_val)))?,
-                    usize::from_str_radix(
-                        &loc.port()
-                            .map_err(|js_val| err::Error::from(format!("{:?}", js_val)))?,
-                        10,
-                    )
-                    .map_err(|e| e.to_string())?,
-                ))
-            })
-            .chain_err(|| "while retrieving server's address and port")
+    /// True if the URL of the page requests a read-only session.
+    ///
+    /// This is only a request, the server decides whether the session is actually read-only.
+    pub fn read_only_requested() -> Res<bool> {
+        let search = location()?
+            .search()
+            .map_err(|js_val| err::Error::from(format!("{:?}", js_val)))?;
+        Ok(charts::msg::query_is_read_only(&search))
+    }
+
+    /// Configuration of the server, written by the server in the index page.
+    pub fn config() -> Res<charts::msg::config::ServerConfig> {
+        use charts::msg::config::{ServerConfig, META_ID};
+        let meta = super::try_get_element_by_id(META_ID)?
+            .ok_or_else(|| format!("no server configuration (`{}`) in the page", META_ID))?;
+        let content = meta
+            .get_attribute("content")
+            .ok_or("the server configuration of the page has no content")?;
+        ServerConfig::from_meta(&content).chain_err(|| "while reading the server configuration")
+    }
+
+    /// Address of the websocket of the server, see [`ServerConfig::ws_url`].
+    ///
+    /// Accepts compressed messages, and requests a read-only session if `read_only`.
+    ///
+    /// [`ServerConfig::ws_url`]: ../../../charts/msg/config/struct.ServerConfig.html#method.ws_url
+    /// (The ws_url method of ServerConfig)
+    pub fn ws_address(read_only: bool) -> Res<String> {
+        let config = config()?;
+        let loc = location()?;
+        let js_err = |js_val| err::Error::from(format!("{:?}", js_val));
+        let secure = loc.protocol().map_err(js_err)? == "https:";
+        let host = loc.host().map_err(js_err)?;
+        let hostname = loc.hostname().map_err(js_err)?;
+        // Empty when the page uses the default port of its protocol.
+        let page_port = usize::from_str_radix(&loc.port().map_err(js_err)?, 10).ok();
+
+        let mut addr = config.ws_url(secure, &host, &hostname, page_port);
+        addr.push('?');
+        addr.push_str(charts::msg::COMPRESS_PARAM);
+        if read_only {
+            addr.push('&');
+            addr.push_str(charts::msg::READ_ONLY_PARAM)
+        }
+        Ok(addr)
     }
 }
@@ -23,14 +23,21 @@
 
 prelude! {}
 
+pub mod alloc_table;
 pub mod button;
+pub mod changes;
 pub mod chart;
 pub mod footer;
 pub mod header;
+pub mod heap;
+pub mod help;
+pub mod info;
 pub mod input;
 pub mod progress;
+pub mod snapshot;
 pub mod table;
 pub mod tabs;
+pub mod tags;
 
 /// Dark grey background.
 pub const DARK_GREY_BG: &'static str = "#313131";
@@ -90,12 +97,16 @@ pub fn render(model: &Model) -> Html {
                 {
                     if let Some(load_info) = model.progress.as_ref() {
                         progress::render(load_info)
+                    } else if let Some(gen_progress) = model.gen_progress.as_ref() {
+                        progress::render_gen(model, gen_progress)
                     } else {
                         model.charts.render(model)
                     }
                 }
             </div>
             { model.footer.render(model) }
+            { model.footer.render_tab_menu(model) }
+            { model.toasts.render(model) }
         </>
     }
 }
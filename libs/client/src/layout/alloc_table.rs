@@ -0,0 +1,201 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Allocation table rendering.
+//!
+//! Shows a page of the allocations of a line, see [`charts::alloc_table`]. The line follows the
+//! filter tab last selected, and can also be picked in the tab directly. Sorting and paging happen
+//! on the server.
+//!
+//! [`charts::alloc_table`]: ../../../charts/alloc_table/index.html (The charts::alloc_table module)
+
+prelude! {}
+
+use charts::alloc_table::SortBy;
+
+/// Pretty string for a size in bytes.
+fn size_str(size: u64) -> String {
+    format!("{}B", num_fmt::bin_str_do(size as f64, base::identity))
+}
+
+/// A line option, used by the line selector.
+#[derive(Clone, PartialEq)]
+struct LineOpt {
+    /// Line.
+    line: uid::Line,
+    /// Name of the line.
+    name: String,
+}
+impl fmt::Display for LineOpt {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.name.fmt(fmt)
+    }
+}
+
+/// Renders the header of a sortable column.
+fn sortable_header(model: &Model, sort_by: SortBy, label: &str) -> Html {
+    define_style! {
+        HEADER_STYLE = {
+            pointer,
+        };
+    }
+
+    let request = &model.footer.alloc_request;
+    let arrow = if request.sort_by != sort_by {
+        ""
+    } else if request.descending {
+        " ▼"
+    } else {
+        " ▲"
+    };
+    html! {
+        <th
+            style = HEADER_STYLE
+            title = format!("sort by {}", sort_by)
+            onclick = model.link.callback(move |_| msg::FooterMsg::alloc_sort(sort_by))
+        >
+            {format!("{}{}", label, arrow)}
+        </th>
+    }
+}
+
+/// Renders the allocation table tab in the menu part of the footer.
+pub fn render(model: &Model) -> Html {
+    define_style! {
+        FRAME_STYLE = {
+            font(code),
+        };
+    }
+
+    let request = &model.footer.alloc_request;
+    let options: Vec<LineOpt> = model
+        .filters()
+        .specs_iter()
+        .map(|spec| LineOpt {
+            line: spec.uid(),
+            name: spec.name().to_string(),
+        })
+        .collect();
+    let selected = options.iter().find(|opt| opt.line == request.line).cloned();
+    let selector = html! {
+        <>
+            {"allocations of "}
+            <Select<LineOpt>
+                selected = selected
+                options = options
+                on_change = model.link.callback(
+                    |opt: LineOpt| msg::FooterMsg::alloc_line(opt.line)
+                )
+            />
+        </>
+    };
+
+    // The page of the server may be for another request while the new one is pending.
+    let table = match model.alloc_table.as_ref() {
+        Some(table) if table.request.line == request.line => table,
+        Some(_) | None => {
+            return html! {
+                <>
+                    <br/>
+                    {layout::section_title("Allocations")}
+                    <br/>
+                    {selector}
+                    <br/>
+                    {"loading..."}
+                </>
+            }
+        }
+    };
+
+    let (page, page_count) = (table.request.page, table.page_count());
+    let prev = if page > 0 {
+        Some(
+            model
+                .link
+                .callback(move |_| msg::FooterMsg::alloc_page(page - 1)),
+        )
+    } else {
+        None
+    };
+    let next = if page + 1 < page_count {
+        Some(
+            model
+                .link
+                .callback(move |_| msg::FooterMsg::alloc_page(page + 1)),
+        )
+    } else {
+        None
+    };
+    let prev_dimmed = prev.is_none();
+    let next_dimmed = next.is_none();
+    let pager = html! {
+        <>
+            {layout::button::text::render_default_button(
+                "alloc_table_prev",
+                "previous",
+                prev,
+                prev_dimmed,
+            )}
+            {format!(" page {} of {}, {} allocation(s) ", page + 1, page_count, table.total)}
+            {layout::button::text::render_default_button(
+                "alloc_table_next",
+                "next",
+                next,
+                next_dimmed,
+            )}
+        </>
+    };
+
+    html! {
+        <>
+            <br/>
+            {layout::section_title("Allocations")}
+            <br/>
+            {selector}
+            {" | "}
+            {pager}
+            <table id = "alloc_table">
+                <tr>
+                    {sortable_header(model, SortBy::Uid, "uid")}
+                    {sortable_header(model, SortBy::Size, "size")}
+                    {sortable_header(model, SortBy::Toc, "allocated at")}
+                    {sortable_header(model, SortBy::Lifetime, "died at (lifetime)")}
+                    <th>{"top frame"}</th>
+                </tr>
+                {for table.rows.iter().map(|row| {
+                    let death = match row.tod {
+                        Some(tod) => format!("{} s ({} s)", tod, tod - row.toc),
+                        None => "live".into(),
+                    };
+                    html! {
+                        <tr>
+                            <td>{row.uid.to_string()}</td>
+                            <td>{size_str(row.size as u64)}</td>
+                            <td>{format!("{} s", row.toc)}</td>
+                            <td>{death}</td>
+                            <td style = FRAME_STYLE>
+                                {row.frame.clone().unwrap_or_else(|| "none".into())}
+                            </td>
+                        </tr>
+                    }
+                })}
+            </table>
+        </>
+    }
+}
@@ -60,6 +60,8 @@ pub struct BoxProps<'color> {
     top: bool,
     /// (De)activates wrapping of the buttons' text.
     no_wrap: bool,
+    /// Colors of the text and of its outline, white text with a black outline if `None`.
+    text_color: Option<(&'color str, &'color str)>,
 }
 
 impl<'color> BoxProps<'color> {
@@ -77,6 +79,7 @@ impl<'color> BoxProps<'color> {
             no_wrap: false,
             gradient_top: "#c1c1c1",
             gradient_bot: "black",
+            text_color: None,
         }
     }
 
@@ -94,6 +97,7 @@ impl<'color> BoxProps<'color> {
             no_wrap: false,
             gradient_top: "#c1c1c1",
             gradient_bot: "black",
+            text_color: None,
         }
     }
 
@@ -111,6 +115,7 @@ impl<'color> BoxProps<'color> {
             no_wrap: true,
             gradient_top: "#c1c1c1",
             gradient_bot: "black",
+            text_color: None,
         }
     }
 
@@ -178,6 +183,12 @@ impl<'color> BoxProps<'color> {
         self
     }
 
+    /// Sets the colors of the text and of its outline.
+    pub const fn with_text_color(mut self, text: &'color str, outline: &'color str) -> Self {
+        self.text_color = Some((text, outline));
+        self
+    }
+
     /// Indicates whether the button should be "on top", *i.e.* is a footer button.
     pub const fn for_footer(mut self, top: bool) -> Self {
         self.top = top;
@@ -326,10 +337,21 @@ pub mod text {
         onclick: Option<OnClickAction>,
         dimmed: bool,
     ) -> Html {
+        let text_color = props.as_ref().and_then(|props| props.text_color);
+        let txt = match text_color {
+            Some((text, outline)) if !dimmed => html! {
+                <span
+                    style = inline_css!(fg({text}), font_outline({outline}))
+                >
+                    {txt}
+                </span>
+            },
+            _ => html! {{txt}},
+        };
         let mut inner = if props.as_ref().map(|props| props.top).unwrap_or(false) {
-            top_centered(html! {{txt}}, dimmed)
+            top_centered(txt, dimmed)
         } else {
-            centered(html! {{txt}}, dimmed)
+            centered(txt, dimmed)
         };
         if let Some(props) = props {
             inner = html! {
@@ -460,6 +482,8 @@ pub mod img {
         Plus,
         /// Minus image.
         Minus,
+        /// Copy image.
+        Copy,
         /// Undo image.
         Undo,
         /// Check image.
@@ -484,6 +508,7 @@ pub mod img {
                 Self::ArrowDown => arrow_down(dimension_px, id, onclick, desc),
                 Self::Plus => plus(dimension_px, id, onclick, desc),
                 Self::Minus => minus(dimension_px, id, onclick, desc),
+                Self::Copy => copy(dimension_px, id, onclick, desc),
                 Self::Undo => undo(dimension_px, id, onclick, desc),
                 Self::Check => check(dimension_px, id, onclick, desc),
                 Self::Dots => dots(dimension_px, id, onclick, desc),
@@ -785,6 +810,39 @@ pub mod img {
         }
     }
 
+    /// Copy button.
+    ///
+    /// Inline SVG for https://icons.getbootstrap.com/icons/files.
+    pub fn copy(
+        dimension_px: Option<usize>,
+        id: impl fmt::Display,
+        onclick: Option<OnClickAction>,
+        desc: impl fmt::Display,
+    ) -> Html {
+        raw_render(dimension_px, id, copy_img(), onclick, desc)
+    }
+    fn copy_img() -> Html {
+        html! {
+            <svg
+                fill = "currentColor"
+                height = "100%"
+                viewBox = "0 0 16 16"
+                xmlns = "http://www.w3.org/2000/svg"
+            >
+                <path
+                    fill-rule = "evenodd"
+                    d = "\
+                        M4 2h7a2 2 0 0 1 2 2v10a2 2 0 0 1-2 2H4a2 2 0 0 1-2-2V4a2 2 0 0 1 2-2zm0 \
+                        1a1 1 0 0 0-1 1v10a1 1 0 0 0 1 1h7a1 1 0 0 0 1-1V4a1 1 0 0 0-1-1H4z\
+                    "
+                />
+                <path
+                    d = "M6 0h7a2 2 0 0 1 2 2v10a2 2 0 0 1-2 2v-1a1 1 0 0 0 1-1V2a1 1 0 0 0-1-1H6a1 1 0 0 0-1 1H4a2 2 0 0 1 2-2z"
+                />
+            </svg>
+        }
+    }
+
     /// Undo button.
     ///
     /// Inline SVG for https://icons.getbootstrap.com/icons/arrow-counterclockwise.
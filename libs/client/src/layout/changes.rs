@@ -0,0 +1,86 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Unsaved filter changes rendering.
+//!
+//! Lists the changes between the filters the server knows and the ones being edited, see
+//! [`charts::filter::diff`], so that users can check (and revert) them before saving.
+//!
+//! [`charts::filter::diff`]: ../../../charts/filter/diff/index.html (The charts::filter::diff module)
+
+prelude! {}
+
+/// Renders the unsaved filter changes in the menu part of the footer.
+pub fn render(model: &Model) -> Html {
+    let changes = model.filters.changes();
+
+    html! {
+        <>
+            <br/>
+            {layout::section_title("Unsaved changes")}
+            <br/>
+            {
+                if changes.is_empty() {
+                    html! {
+                        "no unsaved changes"
+                    }
+                } else {
+                    html! {
+                        <table id = "changes_table">
+                            {for changes.into_iter().enumerate().map(|(idx, change)| {
+                                html! {
+                                    <tr>
+                                        <td>{render_line(model, change.line())}</td>
+                                        <td>{change.to_string()}</td>
+                                        <td>{
+                                            layout::button::text::render_default_button(
+                                                format!("changes_revert_{}", idx),
+                                                "revert",
+                                                Some(model.link.callback(move |_| {
+                                                    msg::filter::Msg::revert_change(change.clone())
+                                                })),
+                                                false,
+                                            )
+                                        }</td>
+                                    </tr>
+                                }
+                            })}
+                        </table>
+                    }
+                }
+            }
+        </>
+    }
+}
+
+/// Renders the name of the line a change is about.
+///
+/// Uses the current name if the line still exists, and the reference one otherwise.
+fn render_line(model: &Model, uid: uid::Line) -> Html {
+    let name = model
+        .filters
+        .current()
+        .get(uid)
+        .or_else(|_| model.filters.reference().get(uid))
+        .map(|(_, spec)| spec.name().to_string())
+        .unwrap_or_else(|_| uid.to_string());
+    html! {
+        <>{name}</>
+    }
+}
@@ -88,6 +88,58 @@ pub fn render(model: &Model, chart: &Chart, pos: ChartPos) -> Html {
     }
 }
 
+/// Renders the toolbar above the charts, with operations over all the charts.
+pub fn render_toolbar(model: &Model) -> Html {
+    define_style! {
+        TOOLBAR_STYLE = {
+            block,
+            text_align(center),
+            margin(0%, 1%, 1%, 1%),
+        };
+    }
+
+    fn button(model: &Model, id: &str, txt: &str, msg: impl Fn() -> Msg + 'static) -> Html {
+        layout::button::text::render_default_button(
+            format!("charts_toolbar_{}", id),
+            txt,
+            Some(model.link.callback(move |_| msg())),
+            false,
+        )
+    }
+    let paused = model.charts().is_paused();
+
+    html! {
+        <div
+            id = "charts_toolbar"
+            style = TOOLBAR_STYLE
+        >
+            {button(model, "collapse_all", "collapse all", msg::ChartsMsg::collapse_all)}
+            {" "}
+            {button(model, "expand_all", "expand all", msg::ChartsMsg::expand_all)}
+            {" | "}
+            {button(model, "reload_all", "reload all", || {
+                msg::to_server::ChartsMsg::reload().into()
+            })}
+            {" "}
+            {button(
+                model,
+                "toggle_pause",
+                if paused { "▶ resume all" } else { "⏸ pause all" },
+                || msg::to_server::ChartsMsg::toggle_pause().into(),
+            )}
+            {" | "}
+            {button(model, "sort_by_value", "sort by value", msg::ChartsMsg::sort_by_value)}
+            {" | "}
+            {layout::button::text::render_default_button(
+                "charts_toolbar_reset_zoom",
+                "reset zoom (all)",
+                Some(model.link.callback(|_| msg::ChartsMsg::reset_zoom())),
+                !model.charts().is_zoomed(),
+            )}
+        </div>
+    }
+}
+
 define_style! {
     CHART_CONTAINER_STYLE = {
         width(100%),
@@ -131,7 +183,7 @@ define_style! {
 }
 
 /// Renders a chart.
-fn render_chart(_model: &Model, chart: &Chart) -> Html {
+fn render_chart(model: &Model, chart: &Chart) -> Html {
     let visible = chart.is_visible();
     let canvas_id = chart.canvas_id();
     // let collapsed_canvas_id = chart.collapsed_canvas_id();
@@ -151,16 +203,97 @@ fn render_chart(_model: &Model, chart: &Chart) -> Html {
             />
         }
     };
+    let style = if visible {
+        &*CHART_CONTAINER_STYLE
+    } else {
+        &*COLLAPSED_CHART_CONTAINER_STYLE
+    };
+    // Time charts zoom on the time window selected with the mouse.
+    if !chart.is_scatter() {
+        let uid = chart.uid();
+        return html! {
+            <div
+                id = chart.container_id()
+                title = chart.tooltip(model.filters())
+                style = style
+                onmousedown = model.link.callback(move |e: yew::events::MouseEvent| {
+                    msg::ChartMsg::zoom_start(uid, e.offset_x())
+                })
+                onmouseup = model.link.callback(move |e: yew::events::MouseEvent| {
+                    msg::ChartMsg::zoom_end(uid, e.offset_x())
+                })
+                ondblclick = model.link.callback(move |_| msg::ChartsMsg::zoom_sync(uid, None))
+            >
+                {inner}
+            </div>
+        };
+    }
+
+    // Scatter charts describe the point under the mouse, and pin its details on click.
+    let uid = chart.uid();
+    html! {
+        <>
+            <div
+                id = chart.container_id()
+                title = chart.tooltip(model.filters())
+                style = style
+                onmousemove = model.link.callback(move |e: yew::events::MouseEvent| {
+                    msg::ChartMsg::hover(uid, Some((e.offset_x(), e.offset_y())))
+                })
+                onmouseleave = model.link.callback(move |_| msg::ChartMsg::hover(uid, None))
+                onclick = model.link.callback(move |e: yew::events::MouseEvent| {
+                    msg::ChartMsg::pin(uid, e.offset_x(), e.offset_y())
+                })
+            >
+                {inner}
+            </div>
+            {render_pinned(model, chart)}
+        </>
+    }
+}
+
+/// Renders the details of the pinned point of a scatter chart, if any.
+fn render_pinned(model: &Model, chart: &Chart) -> Html {
+    define_style! {
+        PINNED_STYLE = {
+            width(100%),
+            padding(0 px, 2%),
+            white_space(pre),
+            font_size(120%),
+        };
+        BUTTON_CONTAINER = {
+            float(right),
+            height(30 px),
+            width(30 px),
+        };
+    }
+
+    let (line, point) = match chart.pinned() {
+        Some(pinned) => pinned,
+        None => return html! {},
+    };
+    let uid = chart.uid();
+    let name = model
+        .filters()
+        .get(*line)
+        .map(|(_, spec)| spec.name().to_string())
+        .unwrap_or_else(|_| line.to_string());
+
     html! {
         <div
-            id = chart.container_id()
-            style = if visible {
-                &*CHART_CONTAINER_STYLE
-            } else {
-                &*COLLAPSED_CHART_CONTAINER_STYLE
-            }
+            style = PINNED_STYLE
         >
-            {inner}
+            <div
+                style = BUTTON_CONTAINER
+            >
+                {layout::button::img::close(
+                    None,
+                    format!("unpin_point_button_{}", uid),
+                    Some(model.link.callback(move |_| msg::ChartMsg::unpin(uid))),
+                    "hide the details of this allocation",
+                )}
+            </div>
+            {format!("{}\n{}", name, point.desc())}
         </div>
     }
 }
@@ -238,9 +371,222 @@ pub mod tiles {
         }
     }
 
+    use charts::chart::settings::{Quality, MAX_POINTS_BOUNDS};
+
+    /// A time window option, used by the time window selector.
+    #[derive(Clone, PartialEq)]
+    enum ShowLast {
+        /// All the points.
+        All,
+        /// The points of the last amount of time.
+        Last(time::SinceStart),
+        /// Asks for an amount of time.
+        Custom,
+    }
+    impl fmt::Display for ShowLast {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Self::All => "off".fmt(fmt),
+                Self::Last(window) => window.fmt_human().fmt(fmt),
+                Self::Custom => "custom".fmt(fmt),
+            }
+        }
+    }
+
+    /// Renders the checkbox linking the zoom of a time chart with the zoom of the other charts.
+    fn render_zoom_link(model: &Model, chart: &Chart) -> Html {
+        if chart.is_scatter() {
+            return html! {};
+        }
+        define_style! {
+            ZOOM_LINK_STYLE = {
+                inline block,
+            };
+        }
+        let chart_uid = chart.uid();
+        html! {
+            <>
+                {" | "}
+                <span
+                    style = ZOOM_LINK_STYLE
+                    title = "uncheck to zoom this chart on its own"
+                >
+                    {layout::input::checkbox(
+                        chart.is_zoom_linked(),
+                        format!("chart_{}_zoom_link", chart_uid),
+                        "linked zoom",
+                        model.link.callback(move |_| msg::ChartsMsg::toggle_zoom_link(chart_uid)),
+                    )}
+                </span>
+            </>
+        }
+    }
+
+    /// Renders the title of a chart.
+    ///
+    /// Clicking the title swaps it for an input, which renames the chart when validated. An empty
+    /// title reverts to the default title. Read-only sessions cannot rename charts.
+    fn render_title(model: &Model, chart: &Chart) -> Html {
+        define_style! {
+            EDITABLE_STYLE = {
+                pointer,
+            };
+            INPUT_STYLE = {
+                font_size(100%),
+                width(50%),
+            };
+        }
+        let chart_uid = chart.uid();
+
+        if model.is_read_only() {
+            return html! { chart.title() };
+        }
+        if !chart.is_editing_title() {
+            return html! {
+                <span
+                    style = EDITABLE_STYLE
+                    title = "click to rename the chart"
+                    onclick = model.link.callback(
+                        move |_| msg::ChartMsg::edit_title(chart_uid, true)
+                    )
+                >
+                    {chart.title()}
+                </span>
+            };
+        }
+        html! {
+            <input
+                type = "text"
+                id = chart.title_input_id()
+                style = INPUT_STYLE
+                value = chart.title()
+                placeholder = chart.spec().desc()
+                onchange = model.link.callback(move |data| match data {
+                    yew::html::ChangeData::Value(title) => {
+                        msg::ChartMsg::rename(chart_uid, title).into()
+                    }
+                    _ => Msg::err("unexpected data for the title of a chart"),
+                })
+                onblur = model.link.callback(
+                    move |_| msg::ChartMsg::edit_title(chart_uid, false)
+                )
+            />
+        }
+    }
+
+    /// Renders the time window selector of a chart.
+    fn render_time_window(model: &Model, chart: &Chart) -> Html {
+        use charts::chart::settings::TIME_WINDOW_PRESETS;
+
+        let chart_uid = chart.uid();
+        let current = chart
+            .settings()
+            .time_window()
+            .map(ShowLast::Last)
+            .unwrap_or(ShowLast::All);
+
+        let mut options = vec![ShowLast::All];
+        options.extend(
+            TIME_WINDOW_PRESETS
+                .iter()
+                .map(|secs| ShowLast::Last(time::SinceStart::from_secs(*secs))),
+        );
+        if !options.contains(&current) {
+            options.push(current.clone())
+        }
+        options.push(ShowLast::Custom);
+
+        let on_change = model.link.callback(move |opt: ShowLast| {
+            let window = match opt {
+                ShowLast::All => None,
+                ShowLast::Last(window) => Some(window),
+                ShowLast::Custom => match js::prompt("only show the points of the last...", "1m") {
+                    Some(window) => match time::SinceStart::parse_human(&window) {
+                        Ok(window) => Some(window),
+                        Err(e) => return msg::Msg::err(e),
+                    },
+                    None => return msg::Msg::Noop,
+                },
+            };
+            msg::ChartSettingsMsg::set_time_window::<msg::ChartsMsg>(chart_uid, window).into()
+        });
+
+        html! {
+            <>
+                {" | last "}
+                <Select<ShowLast>
+                    selected = Some(current)
+                    options = options
+                    on_change = on_change
+                />
+            </>
+        }
+    }
+
+    /// Renders the time axis selector of a chart, if it is a time chart.
+    fn render_time_axis(model: &Model, chart: &Chart) -> Html {
+        use charts::chart::settings::TimeAxis;
+
+        if chart.is_scatter() {
+            return html! {};
+        }
+        let chart_uid = chart.uid();
+
+        html! {
+            <>
+                {" | time "}
+                <Select<TimeAxis>
+                    selected = Some(chart.settings().time_axis())
+                    options = TimeAxis::all()
+                    on_change = model.link.callback(
+                        move |time_axis| msg::ChartSettingsMsg::set_time_axis::<msg::ChartsMsg>(
+                            chart_uid, time_axis
+                        )
+                    )
+                />
+            </>
+        }
+    }
+
+    /// Renders the lifetime statistic selector of a chart, if it has one.
+    fn render_lifetime_stat(model: &Model, chart: &Chart) -> Html {
+        use charts::chart::settings::LifetimeStat;
+
+        let current = match chart.settings().lifetime_stat() {
+            Some(current) => current,
+            None => return html! {},
+        };
+        if model.is_read_only() {
+            return html! { format!(" | lifetime {}", current) };
+        }
+        let chart_uid = chart.uid();
+
+        html! {
+            <>
+                {" | lifetime "}
+                <Select<LifetimeStat>
+                    selected = Some(current)
+                    options = LifetimeStat::all()
+                    on_change = model.link.callback(
+                        move |stat| msg::ChartSettingsMsg::set_lifetime_stat::<msg::ChartsMsg>(
+                            chart_uid, stat
+                        )
+                    )
+                />
+            </>
+        }
+    }
+
     /// Renders the top/center tabs of the tile.
-    pub fn render_center_tabs(_model: &Model, chart: &Chart) -> Html {
-        // let chart_uid = chart.uid();
+    ///
+    /// Clicking the title renames the chart. The tooltip of the title shows the resolution of the
+    /// points, which the quality selector next to it scales. The number of points, if set,
+    /// overrides the quality. The time window selector restricts the points to the last few
+    /// seconds, the time axis selector switches between times since start and wall-clock times,
+    /// and the everything checkbox hides the everything line. Lifetime charts also have a selector
+    /// for their statistic.
+    pub fn render_center_tabs(model: &Model, chart: &Chart) -> Html {
+        let chart_uid = chart.uid();
 
         define_style! {
             TITLE_CONTAINER = {
@@ -254,13 +600,96 @@ pub mod tiles {
                 table cell,
                 overflow(x: auto),
             };
+            FAILURE_STYLE = {
+                fg(red),
+            };
+            QUALITY_STYLE = {
+                font_size(60%),
+            };
+            MAX_POINTS_STYLE = {
+                inline block,
+                width(5 em),
+            };
+            EVERYTHING_STYLE = {
+                inline block,
+            };
         }
 
-        let mut title = chart.title().to_string();
-        if !chart.settings().display_mode().is_normal() {
-            title.push_str(" | ");
-            title.push_str(chart.settings().display_mode().desc());
+        let mode = if chart.settings().display_mode().is_normal() {
+            String::new()
+        } else {
+            format!(" | {}", chart.settings().display_mode().desc())
+        };
+
+        let quality = chart.settings().quality();
+        let hide_everything = chart.settings().hide_everything();
+        let mut tooltip = chart
+            .settings()
+            .resolution_desc()
+            .unwrap_or_else(|| format!("{} quality, resolution not known yet", quality));
+        if let Some(warning) = quality.warning() {
+            tooltip.push_str("\n");
+            tooltip.push_str(warning)
         }
+        let quality_selector = html! {
+            <span
+                style = QUALITY_STYLE
+                title = tooltip.clone()
+            >
+                {" | quality "}
+                <Select<Quality>
+                    selected = Some(quality)
+                    options = Quality::all()
+                    on_change = model.link.callback(
+                        move |quality| msg::ChartSettingsMsg::set_quality::<msg::ChartsMsg>(
+                            chart_uid, quality
+                        )
+                    )
+                />
+                {" | points "}
+                <span
+                    style = MAX_POINTS_STYLE
+                    title = format!(
+                        "number of points across the width of the chart, between {} and {}, \
+                        leave empty to follow the quality",
+                        MAX_POINTS_BOUNDS.0, MAX_POINTS_BOUNDS.1,
+                    )
+                >
+                    {layout::input::u32_opt_input(
+                        model,
+                        MAX_POINTS_BOUNDS.0,
+                        chart.settings().max_points(),
+                        move |max_points_res| max_points_res.map(|max_points| {
+                            msg::ChartSettingsMsg::set_max_points::<msg::ChartsMsg>(
+                                chart_uid, max_points
+                            )
+                        }).into(),
+                    )}
+                </span>
+                {render_time_window(model, chart)}
+                {render_zoom_link(model, chart)}
+                {render_time_axis(model, chart)}
+                {render_lifetime_stat(model, chart)}
+                {" | "}
+                <span
+                    style = EVERYTHING_STYLE
+                    title = "uncheck to hide the everything line, \
+                    the y-axis then fits the other lines"
+                >
+                    {layout::input::checkbox(
+                        !hide_everything,
+                        format!("chart_{}_everything", chart_uid),
+                        "everything",
+                        model.link.callback(move |_| {
+                            msg::ChartSettingsMsg::set_hide_everything::<msg::ChartsMsg>(
+                                chart_uid,
+                                !hide_everything,
+                            )
+                        }),
+                    )}
+                </span>
+            </span>
+        };
 
         html! {
             <center
@@ -268,8 +697,25 @@ pub mod tiles {
             >
                 <div
                     style = TITLE_CELL
+                    title = tooltip
                 >
-                    {title}
+                    {render_title(model, chart)}
+                    {mode}
+                    {quality_selector}
+                    {
+                        if let Some(failure) = chart.failure() {
+                            html! {
+                                <span
+                                    style = FAILURE_STYLE
+                                    title = failure
+                                >
+                                    {" | error, chart disabled"}
+                                </span>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
                 </div>
             </center>
         }
@@ -451,20 +897,55 @@ pub mod settings {
 
                 { title(model, chart) }
                 { options(model, chart) }
+                { size_percentile(model, chart) }
+                { normalize(model, chart) }
+                { transforms(model, chart) }
+                { ticks(model, chart) }
+                { scope(model, chart) }
+                { export(model, chart) }
             </div>
         }
     }
 
+    /// Renders the chart's export row.
+    pub fn export(model: &Model, chart: &Chart) -> Html {
+        let uid = chart.uid();
+        let mut row = layout::table::TableRow::new_menu(false, html! { "export" })
+            .black_sep()
+            .height_px(LINE_HEIGHT_PX);
+        row.push_single_value(layout::button::text::render_default_button(
+            format!("chart_{}_export_html", uid),
+            "interactive HTML",
+            Some(model.link.callback(move |_| msg::ChartsMsg::export(uid))),
+            false,
+        ));
+        row.push_single_value(layout::button::text::render_default_button(
+            format!("chart_{}_export_csv", uid),
+            "CSV",
+            Some(
+                model
+                    .link
+                    .callback(move |_| msg::ChartsMsg::export_csv(uid)),
+            ),
+            false,
+        ));
+        row.render()
+    }
+
     /// Renders the chart's title setting row.
     pub fn title(model: &Model, chart: &Chart) -> Html {
         let mut title = layout::table::TableRow::new_menu(true, html! { "title" })
             .black_sep()
             .height_px(LINE_HEIGHT_PX);
+        if model.is_read_only() {
+            title.push_single_value(html! { chart.title() });
+            return title.render();
+        }
         title.push_single_value({
             let uid = chart.uid();
             layout::input::string_input(model, chart.title(), move |new_title_res| {
                 new_title_res
-                    .map(|new_title| msg::ChartSettingsMsg::change_title(uid, new_title))
+                    .map(|new_title| msg::ChartMsg::rename(uid, new_title))
                     .into()
             })
         });
@@ -512,6 +993,373 @@ pub mod settings {
             html!()
         }
     }
+
+    /// Renders the chart's size percentile setting, if it has one.
+    pub fn size_percentile(model: &Model, chart: &Chart) -> Html {
+        let current = match chart.settings().size_percentile() {
+            Some(current) => current,
+            None => return html!(),
+        };
+        let uid = chart.uid();
+
+        let mut row = layout::table::TableRow::new_menu(false, html! { "size percentile" })
+            .black_sep()
+            .height_px(LINE_HEIGHT_PX);
+        if model.is_read_only() {
+            row.push_single_value(html! { current.to_string() });
+            return row.render();
+        }
+        row.push_value(layout::input::u32_input(
+            model,
+            current.percentile as u32,
+            move |percentile_res| {
+                percentile_res
+                    .and_then(|percentile| {
+                        let new = charts::chart::settings::SizePercentile {
+                            percentile: if percentile > 100 {
+                                0
+                            } else {
+                                percentile as u8
+                            },
+                            ..current
+                        };
+                        if let Some(err) = new.is_legal() {
+                            bail!(err)
+                        }
+                        Ok(msg::ChartSettingsMsg::set_size_percentile(uid, new))
+                    })
+                    .into()
+            },
+        ));
+        row.push_sep(html! { "over the last" });
+        row.push_value(layout::input::since_start_opt_input(
+            model,
+            Some(current.window),
+            move |window_res| {
+                window_res
+                    .and_then(|window_opt| {
+                        let new = charts::chart::settings::SizePercentile {
+                            window: window_opt.unwrap_or_else(time::SinceStart::zero),
+                            ..current
+                        };
+                        if let Some(err) = new.is_legal() {
+                            bail!(err)
+                        }
+                        Ok(msg::ChartSettingsMsg::set_size_percentile(uid, new))
+                    })
+                    .into()
+            },
+        ));
+        row.render()
+    }
+
+    /// A normalization option, used by the normalization selector.
+    #[derive(Clone, PartialEq)]
+    struct NormalizeBy {
+        /// Line to normalize by, `None` for no normalization.
+        line: Option<uid::Line>,
+        /// Name of the line.
+        name: String,
+    }
+    impl fmt::Display for NormalizeBy {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            if self.line.is_some() {
+                self.name.fmt(fmt)
+            } else {
+                "nothing".fmt(fmt)
+            }
+        }
+    }
+
+    /// Renders the chart's normalization setting.
+    pub fn normalize(model: &Model, chart: &Chart) -> Html {
+        let uid = chart.uid();
+        let options: Vec<NormalizeBy> = Some(NormalizeBy {
+            line: None,
+            name: String::new(),
+        })
+        .into_iter()
+        .chain(model.filters().specs_iter().map(|spec| NormalizeBy {
+            line: Some(spec.uid()),
+            name: spec.name().to_string(),
+        }))
+        .collect();
+        let current = chart.settings().normalize();
+        let selected = options.iter().find(|opt| opt.line == current).cloned();
+
+        let mut row = layout::table::TableRow::new_menu(false, html! { "normalize by" })
+            .black_sep()
+            .height_px(LINE_HEIGHT_PX);
+        if model.is_read_only() {
+            row.push_single_value(html! {
+                selected.map(|opt| opt.to_string()).unwrap_or_else(String::new)
+            });
+            return row.render();
+        }
+        row.push_selector(html! {
+            <Select<NormalizeBy>
+                selected = selected
+                options = options
+                on_change = model.link.callback(
+                    move |opt: NormalizeBy| msg::ChartSettingsMsg::set_normalize::<msg::ChartsMsg>(
+                        uid, opt.line
+                    )
+                )
+            />
+        });
+        if current.is_some() {
+            row.push_sep(html! { "values are percentages of this line" })
+        }
+        row.render()
+    }
+
+    /// Renders the transforms applied to the chart's points.
+    ///
+    /// Transforms are applied in the order they were added, the normalization is shown in its
+    /// own row but also appears here.
+    pub fn transforms(model: &Model, chart: &Chart) -> Html {
+        use charts::chart::transform::Transform;
+
+        let uid = chart.uid();
+        let current = chart.settings().transforms().to_vec();
+
+        let mut row = layout::table::TableRow::new_menu(false, html! { "transforms" })
+            .black_sep()
+            .height_px(LINE_HEIGHT_PX);
+        if model.is_read_only() {
+            let descs: Vec<String> = current.iter().map(Transform::to_string).collect();
+            row.push_single_value(html! {
+                if descs.is_empty() { "none".to_string() } else { descs.join(", then ") }
+            });
+            return row.render();
+        }
+
+        let set_transforms = move |transforms: Vec<Transform>| {
+            msg::ChartSettingsMsg::set_transforms::<msg::ChartsMsg>(uid, transforms)
+        };
+
+        for (idx, transform) in current.iter().enumerate() {
+            if idx > 0 {
+                row.push_sep(html! { "then" })
+            }
+            match *transform {
+                Transform::Smooth(width) => {
+                    row.push_sep(html! { "smooth over" });
+                    let current = current.clone();
+                    row.push_value(layout::input::u32_input(model, width as u32, move |res| {
+                        res.and_then(|width| {
+                            let mut transforms = current.clone();
+                            transforms[idx] = Transform::Smooth(width as usize);
+                            if let Some(err) = transforms[idx].is_legal() {
+                                bail!(err)
+                            }
+                            Ok(set_transforms(transforms))
+                        })
+                        .into()
+                    }));
+                    row.push_sep(html! { "values" })
+                }
+                Transform::Normalize(_) | Transform::Rate => {
+                    row.push_sep(html! { transform.to_string() })
+                }
+            }
+            let mut transforms = current.clone();
+            transforms.remove(idx);
+            row.push_value(layout::button::text::render_default_button(
+                format!("chart_{}_transform_{}_remove", uid, idx),
+                "remove",
+                Some(
+                    model
+                        .link
+                        .callback(move |_| set_transforms(transforms.clone())),
+                ),
+                false,
+            ));
+        }
+
+        let add = |name: &str, txt: &str, transform: Transform| {
+            let mut transforms = current.clone();
+            transforms.push(transform);
+            layout::button::text::render_default_button(
+                format!("chart_{}_transform_add_{}", uid, name),
+                txt,
+                Some(
+                    model
+                        .link
+                        .callback(move |_| set_transforms(transforms.clone())),
+                ),
+                false,
+            )
+        };
+        row.push_value(html! {
+            <>
+                {add("rate", "add rate", Transform::Rate)}
+                {add("smooth", "add smoothing", Transform::Smooth(Transform::DEFAULT_SMOOTH))}
+            </>
+        });
+        row.render()
+    }
+
+    /// Renders the chart's tick density setting.
+    ///
+    /// Ticks only change how the chart is drawn, so read-only sessions can change them.
+    pub fn ticks(model: &Model, chart: &Chart) -> Html {
+        use charts::chart::settings::{LabelAngle, TickConf, Ticks};
+
+        let uid = chart.uid();
+        let current = chart.settings().ticks();
+        let set_ticks =
+            move |ticks: Ticks| msg::ChartSettingsMsg::set_ticks::<msg::ChartsMsg>(uid, ticks);
+        // Manual mode starts from what auto mode currently does.
+        let auto_conf = chart
+            .settings()
+            .resolution()
+            .map(TickConf::auto)
+            .unwrap_or_default();
+
+        let mut row = layout::table::TableRow::new_menu(false, html! { "ticks" })
+            .black_sep()
+            .height_px(LINE_HEIGHT_PX);
+        let mode = html! {
+            <>
+                {layout::input::radio(
+                    current.is_auto(),
+                    format!("chart_{}_ticks_auto", uid),
+                    "auto",
+                    model.link.callback(move |_| set_ticks(Ticks::Auto)),
+                    model.link.callback(move |_| set_ticks(Ticks::Auto)),
+                    false,
+                )}
+                {layout::input::radio(
+                    !current.is_auto(),
+                    format!("chart_{}_ticks_manual", uid),
+                    "manual",
+                    model.link.callback(move |_| set_ticks(Ticks::Manual(auto_conf))),
+                    model.link.callback(move |_| set_ticks(Ticks::Manual(auto_conf))),
+                    true,
+                )}
+            </>
+        };
+
+        let conf = match current {
+            Ticks::Auto => {
+                row.push_single_value(mode);
+                return row.render();
+            }
+            Ticks::Manual(conf) => conf,
+        };
+        row.push_value(mode);
+        let set_conf = move |res: Res<TickConf>| -> Msg {
+            res.and_then(|conf| {
+                if let Some(err) = conf.is_legal() {
+                    bail!(err)
+                }
+                Ok(set_ticks(Ticks::Manual(conf)))
+            })
+            .into()
+        };
+
+        row.push_sep(html! { "x every" });
+        row.push_value(layout::input::u32_input(
+            model,
+            conf.x_spacing,
+            move |res| set_conf(res.map(|x_spacing| TickConf { x_spacing, ..conf })),
+        ));
+        row.push_sep(html! { "px, y every" });
+        row.push_value(layout::input::u32_input(
+            model,
+            conf.y_spacing,
+            move |res| set_conf(res.map(|y_spacing| TickConf { y_spacing, ..conf })),
+        ));
+        row.push_sep(html! { "px, x labels at" });
+        row.push_value(html! {
+            <>
+                {for [LabelAngle::Flat, LabelAngle::Vertical].iter().enumerate().map(
+                    |(idx, &x_angle)| {
+                        let new = TickConf { x_angle, ..conf };
+                        layout::input::radio(
+                            conf.x_angle == x_angle,
+                            format!("chart_{}_ticks_angle_{}", uid, x_angle.degrees()),
+                            format!("{}°", x_angle.degrees()),
+                            model.link.callback(move |_| set_ticks(Ticks::Manual(new))),
+                            model.link.callback(move |_| set_ticks(Ticks::Manual(new))),
+                            idx > 0,
+                        )
+                    }
+                )}
+            </>
+        });
+        row.render()
+    }
+
+    /// Renders the chart's scope setting: the lines the chart shows.
+    pub fn scope(model: &Model, chart: &Chart) -> Html {
+        let uid = chart.uid();
+        let spec = chart.spec();
+        let lines: Vec<&filter::FilterSpec> = model.filters().specs_iter().collect();
+
+        let mut row = layout::table::TableRow::new_menu(false, html! { "scope" })
+            .black_sep()
+            .height_px(LINE_HEIGHT_PX);
+        if model.is_read_only() {
+            let names: Vec<String> = lines
+                .iter()
+                .filter(|line| spec.in_scope(line.uid()))
+                .map(|line| line.name().to_string())
+                .collect();
+            row.push_single_value(html! { names.join(", ") });
+            return row.render();
+        }
+
+        let all: BTSet<uid::Line> = lines.iter().map(|line| line.uid()).collect();
+        let checklist = html! {
+            <>
+                {for lines.iter().map(|line| {
+                    let line_uid = line.uid();
+                    let in_scope = spec.in_scope(line_uid);
+                    let mut scope: BTSet<uid::Line> = all
+                        .iter()
+                        .cloned()
+                        .filter(|line| spec.in_scope(*line))
+                        .collect();
+                    if in_scope {
+                        scope.remove(&line_uid);
+                    } else {
+                        scope.insert(line_uid);
+                    }
+                    // Scoping a chart to all the lines unscopes it.
+                    let scope = if scope == all { None } else { Some(scope) };
+                    layout::input::checkbox(
+                        in_scope,
+                        format!("chart_{}_scope_{}", uid, line_uid),
+                        line.name().to_string(),
+                        model.link.callback(move |_| {
+                            msg::to_server::ChartsMsg::set_scope(uid, scope.clone())
+                        }),
+                    )
+                })}
+            </>
+        };
+        row.push_single_value(checklist);
+
+        let mut policy = layout::table::TableRow::new_menu(false, html! {})
+            .black_sep()
+            .height_px(LINE_HEIGHT_PX);
+        policy.push_single_value(html! {
+            if spec.scope().is_some() {
+                "scoped chart: lines created later will not be shown"
+            } else {
+                "unscoped chart: lines created later will be shown"
+            }
+        });
+
+        html! {
+            <>
+                {row.render()}
+                {policy.render()}
+            </>
+        }
+    }
 }
 
 /// Filter tabs (bottom) rendering.
@@ -544,13 +1392,7 @@ pub mod filter_toggles {
 
         let chart_uid = chart.uid();
 
-        let is_active = |spec: &filter::FilterSpec| {
-            chart
-                .filter_visibility()
-                .get(&spec.uid())
-                .cloned()
-                .unwrap_or(false)
-        };
+        let is_active = |spec: &filter::FilterSpec| chart.is_line_visible(spec.uid());
 
         let callback = |spec: &filter::FilterSpec| {
             let uid = spec.uid();
@@ -613,10 +1455,17 @@ pub mod filter_toggles {
 
             (@push($tabs:expr) $filter_spec:expr, $active:expr) => {{
                 let tabs = $tabs.get_or_insert_with(Tabs::new);
+                let name = if model.settings.is_accessible() {
+                    let pattern = charts::pattern::Pattern::of_line($filter_spec.uid());
+                    format!("{} {}", pattern.swatch(), $filter_spec.name())
+                } else {
+                    $filter_spec.name().to_string()
+                };
                 tabs.push_tab(
                     model,
-                    $filter_spec.name(),
+                    &name,
                     TabProps::new($filter_spec.color().to_string())
+                        .with_contrast_text($filter_spec.color())
                         .set_dimmed(!$active)
                         .set_rev()
                         .set_round(!$active),
@@ -26,6 +26,22 @@ prelude! {}
 pub enum FooterTab {
     /// Filters tab.
     Filter(uid::Line),
+    /// Unsaved filter changes tab.
+    Changes,
+    /// Notes tab.
+    Notes,
+    /// Heap composition tab.
+    Heap,
+    /// Snapshot diff tab.
+    Snapshot,
+    /// Allocation table tab.
+    Allocations,
+    /// Tags tab.
+    Tags,
+    /// Trace info tab.
+    Info,
+    /// Help tab.
+    Help,
 }
 
 impl FooterTab {
@@ -38,6 +54,14 @@ impl FooterTab {
     pub fn get_filter(self) -> Option<uid::Line> {
         match self {
             Self::Filter(uid) => Some(uid),
+            Self::Changes
+            | Self::Notes
+            | Self::Heap
+            | Self::Snapshot
+            | Self::Allocations
+            | Self::Tags
+            | Self::Info
+            | Self::Help => None,
         }
     }
 }
@@ -46,6 +70,14 @@ impl fmt::Display for FooterTab {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
             FooterTab::Filter(uid) => write!(fmt, "Filter({})", uid),
+            FooterTab::Changes => write!(fmt, "Changes"),
+            FooterTab::Notes => write!(fmt, "Notes"),
+            FooterTab::Heap => write!(fmt, "Heap"),
+            FooterTab::Snapshot => write!(fmt, "Snapshot"),
+            FooterTab::Allocations => write!(fmt, "Allocations"),
+            FooterTab::Tags => write!(fmt, "Tags"),
+            FooterTab::Info => write!(fmt, "Info"),
+            FooterTab::Help => write!(fmt, "Help"),
         }
     }
 }
@@ -57,16 +89,38 @@ impl From<uid::Line> for FooterTab {
 }
 
 /// Footer state.
-#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq)]
 pub struct Footer {
     /// Active footer tab, if any.
     pub active: Option<FooterTab>,
+    /// Search string of the help tab.
+    pub help_search: String,
+    /// First instant of the snapshot diff tab.
+    pub snapshot_from: Option<time::SinceStart>,
+    /// Second instant of the snapshot diff tab.
+    pub snapshot_to: Option<time::SinceStart>,
+    /// Page of the allocation table tab.
+    ///
+    /// Follows the filter tab last selected.
+    pub alloc_request: charts::alloc_table::Request,
+    /// True if the allocation table tab must ask the server for its page.
+    alloc_request_stale: bool,
+    /// Filter tab whose context menu is open, if any, with the horizontal position of the menu.
+    pub tab_menu: Option<(uid::Line, i32)>,
 }
 
 impl Footer {
     /// Constructor.
     pub fn new() -> Self {
-        Self { active: None }
+        Self {
+            active: None,
+            help_search: String::new(),
+            snapshot_from: None,
+            snapshot_to: None,
+            alloc_request: charts::alloc_table::Request::new(uid::Line::Everything),
+            alloc_request_stale: true,
+            tab_menu: None,
+        }
     }
 
     /// Applies a footer action.
@@ -74,13 +128,85 @@ impl Footer {
         use msg::FooterMsg::*;
         match msg {
             ToggleTab(tab) => {
+                self.tab_menu = None;
                 if self.active == Some(tab) {
                     self.active = None
                 } else {
-                    self.active = Some(tab)
+                    self.open(tab)
                 }
                 Ok(true)
             }
+            HelpSearch(search) => {
+                self.help_search = search;
+                Ok(true)
+            }
+            SnapshotFrom(from) => {
+                let redraw = self.snapshot_from != from;
+                self.snapshot_from = from;
+                Ok(redraw)
+            }
+            SnapshotTo(to) => {
+                let redraw = self.snapshot_to != to;
+                self.snapshot_to = to;
+                Ok(redraw)
+            }
+            AllocLine(line) => {
+                self.alloc_request.line = line;
+                self.alloc_request.page = 0;
+                self.alloc_request_stale = true;
+                Ok(true)
+            }
+            AllocSort(sort_by) => {
+                self.alloc_request.sort(sort_by);
+                self.alloc_request_stale = true;
+                Ok(true)
+            }
+            AllocPage(page) => {
+                self.alloc_request.page = page;
+                self.alloc_request_stale = true;
+                Ok(true)
+            }
+            TabMenu(menu) => {
+                let redraw = self.tab_menu != menu;
+                self.tab_menu = menu;
+                Ok(redraw)
+            }
+        }
+    }
+
+    /// Opens a tab.
+    ///
+    /// Opening a filter tab changes the line of the allocation table tab, opening the allocation
+    /// table tab refreshes its page.
+    pub fn open(&mut self, tab: FooterTab) {
+        self.active = Some(tab);
+        match tab {
+            FooterTab::Filter(line) if line != self.alloc_request.line => {
+                self.alloc_request.line = line;
+                self.alloc_request.page = 0;
+                self.alloc_request_stale = true
+            }
+            FooterTab::Allocations => self.alloc_request_stale = true,
+            FooterTab::Filter(_)
+            | FooterTab::Changes
+            | FooterTab::Notes
+            | FooterTab::Heap
+            | FooterTab::Snapshot
+            | FooterTab::Tags
+            | FooterTab::Info
+            | FooterTab::Help => (),
+        }
+    }
+
+    /// The page the allocation table tab must ask the server for, if any.
+    ///
+    /// Only yields something while the allocation table tab is active.
+    pub fn take_alloc_request(&mut self) -> Option<charts::alloc_table::Request> {
+        if self.alloc_request_stale && self.active == Some(FooterTab::Allocations) {
+            self.alloc_request_stale = false;
+            Some(self.alloc_request.clone())
+        } else {
+            None
         }
     }
 
@@ -119,6 +245,57 @@ impl Footer {
         }
     }
 
+    /// Renders the context menu of a filter tab, if one is open.
+    ///
+    /// The menu sits right above the footer and closes as soon as it is clicked or left.
+    pub fn render_tab_menu(&self, model: &Model) -> Html {
+        define_style! {
+            ITEM_STYLE = {
+                padding(5 px, 10 px),
+                pointer,
+            };
+        }
+
+        let (uid, left) = match self.tab_menu {
+            Some(menu) => menu,
+            None => return html! {},
+        };
+        let filter = match model.footer_filters().get(uid) {
+            Ok((_, filter)) => filter,
+            Err(_) => return html! {},
+        };
+        let menu_style = inline_css! {
+            pos(fixed),
+            left({left} px),
+            bottom({self.height_px()} px),
+            z_index(700),
+            fg({layout::LIGHT_BLUE_FG}),
+            bg({layout::DARK_GREY_BG}),
+            border_radius(5 px),
+        };
+        let item = if filter.is_muted() {
+            "🔊 unmute (show the line in all charts)"
+        } else {
+            "🔇 mute (hide the line in all charts)"
+        };
+
+        html! {
+            <div
+                id = "tab_menu"
+                style = menu_style
+                onclick = model.link.callback(|_| msg::FooterMsg::close_tab_menu())
+                onmouseleave = model.link.callback(|_| msg::FooterMsg::close_tab_menu())
+            >
+                <div
+                    style = ITEM_STYLE
+                    onclick = model.link.callback(move |_| msg::filter::Msg::toggle_mute(uid))
+                >
+                    {item}
+                </div>
+            </div>
+        }
+    }
+
     /// Renders the footer.
     pub fn render(&self, model: &Model) -> Html {
         define_style! {
@@ -215,6 +392,174 @@ impl Footer {
                     </footer>
                 }
             }
+            Some(footer::FooterTab::Changes) => {
+                html! {
+                    <footer
+                        id = "expanded_footer"
+                        style = EXPANDED_STYLE
+                    >
+                        <div
+                            id = "expanded_tabs_tile"
+                            style = TABS_STYLE
+                        >
+                            { tabs::render(model, None) }
+                        </div>
+                        <div
+                            id = "expanded_menu_tile"
+                            style = EXPANDED_MENU_STYLE
+                        >
+                            { menu::render_changes(model) }
+                        </div>
+                    </footer>
+                }
+            }
+            Some(footer::FooterTab::Notes) => {
+                html! {
+                    <footer
+                        id = "expanded_footer"
+                        style = EXPANDED_STYLE
+                    >
+                        <div
+                            id = "expanded_tabs_tile"
+                            style = TABS_STYLE
+                        >
+                            { tabs::render(model, None) }
+                        </div>
+                        <div
+                            id = "expanded_menu_tile"
+                            style = EXPANDED_MENU_STYLE
+                        >
+                            { menu::render_notes(model) }
+                        </div>
+                    </footer>
+                }
+            }
+            Some(footer::FooterTab::Heap) => {
+                html! {
+                    <footer
+                        id = "expanded_footer"
+                        style = EXPANDED_STYLE
+                    >
+                        <div
+                            id = "expanded_tabs_tile"
+                            style = TABS_STYLE
+                        >
+                            { tabs::render(model, None) }
+                        </div>
+                        <div
+                            id = "expanded_menu_tile"
+                            style = EXPANDED_MENU_STYLE
+                        >
+                            { menu::render_heap(model) }
+                        </div>
+                    </footer>
+                }
+            }
+            Some(footer::FooterTab::Snapshot) => {
+                html! {
+                    <footer
+                        id = "expanded_footer"
+                        style = EXPANDED_STYLE
+                    >
+                        <div
+                            id = "expanded_tabs_tile"
+                            style = TABS_STYLE
+                        >
+                            { tabs::render(model, None) }
+                        </div>
+                        <div
+                            id = "expanded_menu_tile"
+                            style = EXPANDED_MENU_STYLE
+                        >
+                            { menu::render_snapshot(model) }
+                        </div>
+                    </footer>
+                }
+            }
+            Some(footer::FooterTab::Allocations) => {
+                html! {
+                    <footer
+                        id = "expanded_footer"
+                        style = EXPANDED_STYLE
+                    >
+                        <div
+                            id = "expanded_tabs_tile"
+                            style = TABS_STYLE
+                        >
+                            { tabs::render(model, None) }
+                        </div>
+                        <div
+                            id = "expanded_menu_tile"
+                            style = EXPANDED_MENU_STYLE
+                        >
+                            { menu::render_alloc_table(model) }
+                        </div>
+                    </footer>
+                }
+            }
+            Some(footer::FooterTab::Tags) => {
+                html! {
+                    <footer
+                        id = "expanded_footer"
+                        style = EXPANDED_STYLE
+                    >
+                        <div
+                            id = "expanded_tabs_tile"
+                            style = TABS_STYLE
+                        >
+                            { tabs::render(model, None) }
+                        </div>
+                        <div
+                            id = "expanded_menu_tile"
+                            style = EXPANDED_MENU_STYLE
+                        >
+                            { menu::render_tags(model) }
+                        </div>
+                    </footer>
+                }
+            }
+            Some(footer::FooterTab::Info) => {
+                html! {
+                    <footer
+                        id = "expanded_footer"
+                        style = EXPANDED_STYLE
+                    >
+                        <div
+                            id = "expanded_tabs_tile"
+                            style = TABS_STYLE
+                        >
+                            { tabs::render(model, None) }
+                        </div>
+                        <div
+                            id = "expanded_menu_tile"
+                            style = EXPANDED_MENU_STYLE
+                        >
+                            { menu::render_info(model) }
+                        </div>
+                    </footer>
+                }
+            }
+            Some(footer::FooterTab::Help) => {
+                html! {
+                    <footer
+                        id = "expanded_footer"
+                        style = EXPANDED_STYLE
+                    >
+                        <div
+                            id = "expanded_tabs_tile"
+                            style = TABS_STYLE
+                        >
+                            { tabs::render(model, None) }
+                        </div>
+                        <div
+                            id = "expanded_menu_tile"
+                            style = EXPANDED_MENU_STYLE
+                        >
+                            { menu::render_help(model) }
+                        </div>
+                    </footer>
+                }
+            }
         }
     }
 }
@@ -276,7 +621,8 @@ pub mod menu {
                     let empty = || html! { <></> };
                     match filter.uid() {
                         uid::Line::CatchAll |
-                        uid::Line::Everything => empty(),
+                        uid::Line::Everything |
+                        uid::Line::Segment(_) => builtin::render(model, filter),
                         uid::Line::Filter(uid) => if let Ok(
                             (_index, filter)
                         ) = model.footer_filters().get_filter(uid) {
@@ -297,6 +643,94 @@ pub mod menu {
         }
     }
 
+    /// Renders the unsaved filter changes in the menu-part of the footer.
+    pub fn render_changes(model: &Model) -> Html {
+        html! {
+            <>
+                { render_left_tile() }
+                { render_center_tile(layout::changes::render(model)) }
+                { render_right_tile(html! {}) }
+            </>
+        }
+    }
+
+    /// Renders the notes in the menu-part of the footer.
+    pub fn render_notes(model: &Model) -> Html {
+        html! {
+            <>
+                { render_left_tile() }
+                { render_center_tile(model.notes.render(model)) }
+                { render_right_tile(html! {}) }
+            </>
+        }
+    }
+
+    /// Renders the heap composition in the menu-part of the footer.
+    pub fn render_heap(model: &Model) -> Html {
+        html! {
+            <>
+                { render_left_tile() }
+                { render_center_tile(layout::heap::render(model)) }
+                { render_right_tile(html! {}) }
+            </>
+        }
+    }
+
+    /// Renders the snapshot diff tab.
+    pub fn render_snapshot(model: &Model) -> Html {
+        html! {
+            <>
+                { render_left_tile() }
+                { render_center_tile(layout::snapshot::render(model)) }
+                { render_right_tile(html! {}) }
+            </>
+        }
+    }
+
+    /// Renders the allocation table tab.
+    pub fn render_alloc_table(model: &Model) -> Html {
+        html! {
+            <>
+                { render_left_tile() }
+                { render_center_tile(layout::alloc_table::render(model)) }
+                { render_right_tile(html! {}) }
+            </>
+        }
+    }
+
+    /// Renders the tags in the menu-part of the footer.
+    pub fn render_tags(model: &Model) -> Html {
+        html! {
+            <>
+                { render_left_tile() }
+                { render_center_tile(layout::tags::render(model)) }
+                { render_right_tile(html! {}) }
+            </>
+        }
+    }
+
+    /// Renders the trace info.
+    pub fn render_info(model: &Model) -> Html {
+        html! {
+            <>
+                { render_left_tile() }
+                { render_center_tile(layout::info::render(model)) }
+                { render_right_tile(html! {}) }
+            </>
+        }
+    }
+
+    /// Renders the help.
+    pub fn render_help(model: &Model) -> Html {
+        html! {
+            <>
+                { render_left_tile() }
+                { render_center_tile(layout::help::render(model)) }
+                { render_right_tile(html! {}) }
+            </>
+        }
+    }
+
     /// Renders the left tile of the menu.
     pub fn render_left_tile() -> Html {
         html! {
@@ -324,8 +758,172 @@ pub mod menu {
         use super::*;
 
         /// Renders the right tile of the menu for some filter.
-        pub fn render(_model: &Model, _uid: uid::Line) -> Html {
-            html! {}
+        ///
+        /// User filters can tag the allocations they match, as long as they are saved since the
+        /// server tags using its own version of the filter.
+        pub fn render(model: &Model, uid: uid::Line) -> Html {
+            if model.is_read_only() {
+                return render_export_import(model, uid);
+            }
+            let tag = match uid {
+                uid::Line::Filter(filter_uid) => {
+                    let edited = model.filters.is_filter_edited(uid);
+                    html! {
+                        <>
+                            <br/>
+                            {layout::button::text::render_default_button(
+                                "filter_tag",
+                                "tag matching allocations",
+                                if edited {
+                                    None
+                                } else {
+                                    Some(layout::tags::tag_callback(
+                                        model,
+                                        charts::tag::Target::Filter(filter_uid),
+                                    ))
+                                },
+                                edited,
+                            )}
+                        </>
+                    }
+                }
+                uid::Line::Everything | uid::Line::CatchAll | uid::Line::Segment(_) => html! {},
+            };
+            let contrast = if model.footer_filters().has_low_contrast() {
+                html! {
+                    <>
+                        <br/>
+                        {layout::button::text::render_default_button(
+                            "filters_fix_contrast",
+                            "fix all low-contrast colors",
+                            Some(model.link.callback(|_| msg::filter::Msg::fix_contrast())),
+                            false,
+                        )}
+                    </>
+                }
+            } else {
+                html! {}
+            };
+            html! {
+                <>
+                    {tag}
+                    {contrast}
+                    {render_export_import(model, uid)}
+                </>
+            }
+        }
+
+        /// Renders the filter sharing, export and import buttons, and the import area if it is
+        /// open.
+        ///
+        /// Read-only sessions can only share and export.
+        fn render_export_import(model: &Model, uid: uid::Line) -> Html {
+            define_style! {
+                IMPORT_AREA_STYLE = {
+                    width(90%),
+                    height(10 em),
+                };
+                IMPORT_ERROR_STYLE = {
+                    fg(red),
+                    width(90%),
+                    overflow(x: auto),
+                };
+            }
+
+            let copy = match uid {
+                uid::Line::Filter(uid) => html! {
+                    <>
+                        <br/>
+                        {layout::button::text::render_default_button(
+                            "filter_copy_text",
+                            "copy filter as text",
+                            Some(model.link.callback(move |_| msg::filter::Msg::copy_text(uid))),
+                            false,
+                        )}
+                    </>
+                },
+                uid::Line::Everything | uid::Line::CatchAll | uid::Line::Segment(_) => html! {},
+            };
+            let export = html! {
+                <>
+                    {copy}
+                    <br/>
+                    {layout::button::text::render_default_button(
+                        "filters_share",
+                        "copy link sharing all filters",
+                        Some(model.link.callback(|_| msg::filter::Msg::share())),
+                        false,
+                    )}
+                    <br/>
+                    {layout::button::text::render_default_button(
+                        "filters_export",
+                        "export filters",
+                        Some(model.link.callback(|_| msg::filter::Msg::export())),
+                        false,
+                    )}
+                </>
+            };
+            if model.is_read_only() {
+                return export;
+            }
+
+            let import_error = match model.filters.import_error.as_ref() {
+                None => html! {},
+                Some(e) => html! {
+                    <>
+                        <br/>
+                        <pre style = IMPORT_ERROR_STYLE>{e}</pre>
+                    </>
+                },
+            };
+            let import_area = match model.filters.import.as_ref() {
+                None => html! {},
+                Some(text) => html! {
+                    <>
+                        <br/>
+                        <textarea
+                            id = "filters_import"
+                            style = IMPORT_AREA_STYLE
+                            value = text
+                            placeholder = "paste exported filters, or filters as text, one per line"
+                            oninput = model.link.callback(
+                                |data: yew::events::InputData| msg::filter::Msg::import(
+                                    msg::filter::ImportMsg::Edit(data.value)
+                                )
+                            )
+                        />
+                        <br/>
+                        {import_error}
+                        {layout::button::text::render_default_button(
+                            "filters_import_submit",
+                            "replace (export) or add (text) filters",
+                            Some(model.link.callback(|_| {
+                                msg::filter::Msg::import(msg::filter::ImportMsg::Submit)
+                            })),
+                            false,
+                        )}
+                    </>
+                },
+            };
+            html! {
+                <>
+                    {export}
+                    <br/>
+                    {layout::button::text::render_default_button(
+                        "filters_import",
+                        if model.filters.import.is_some() {
+                            "cancel import"
+                        } else {
+                            "import filters"
+                        },
+                        Some(model.link.callback(|_| {
+                            msg::filter::Msg::import(msg::filter::ImportMsg::Toggle)
+                        })),
+                        false,
+                    )}
+                    {import_area}
+                </>
+            }
         }
     }
 
@@ -364,8 +962,9 @@ pub mod menu {
             let mut table_row = layout::table::TableRow::new_menu(true, html! { "name" });
             table_row.push_single_value({
                 let uid = filter.uid();
-                layout::input::text_input(
+                layout::input::bounded_text_input(
                     filter.name(),
+                    filter::FilterName::MAX_LEN,
                     model
                         .link
                         .callback(move |data| msg::filter::SpecMsg::change_name(uid, data)),
@@ -375,27 +974,134 @@ pub mod menu {
         }
 
         /// Renders the filter's color row.
+        ///
+        /// Warns if the color does not contrast enough with the chart background, and suggests a
+        /// color that does.
         pub fn render_color_row(model: &Model, filter: &filter::FilterSpec) -> Html {
             let mut table_row = layout::table::TableRow::new_menu(false, html! { "color" });
-            table_row.push_single_value({
-                let uid = filter.uid();
-                layout::input::color_input(
-                    filter.color(),
-                    model
-                        .link
-                        .callback(move |data| msg::filter::SpecMsg::change_color(uid, data)),
-                )
-            });
+            let uid = filter.uid();
+            table_row.push_value(layout::input::color_input(
+                &filter.color().opaque(),
+                model
+                    .link
+                    .callback(move |data| msg::filter::SpecMsg::change_color(uid, data)),
+            ));
+            let color = filter.color();
+            if color.has_low_contrast() {
+                let suggested = color.contrasting();
+                table_row.push_value(html! {
+                    format!(
+                        "low contrast ({:.1}:1, needs {:.0}:1)",
+                        color.contrast(&charts::color::Color::CHART_BACKGROUND),
+                        charts::color::Color::MIN_CONTRAST,
+                    )
+                });
+                if !model.is_read_only() {
+                    table_row.push_value(layout::button::text::render_default_button(
+                        "filter_color_fix",
+                        format!("use {}", suggested),
+                        Some(model.link.callback(move |_| {
+                            msg::filter::SpecMsg::change_color(
+                                uid,
+                                ChangeData::Value(suggested.to_string()),
+                            )
+                        })),
+                        false,
+                    ))
+                }
+            }
             table_row.render()
         }
     }
 
+    /// Built-in line (everything, catch-all) rendering.
+    pub mod builtin {
+        use super::*;
+
+        /// Renders the description of a built-in line.
+        ///
+        /// The catch-all line also shows its live allocations, and can generate a filter for its
+        /// top allocation site.
+        pub fn render(model: &Model, filter: &filter::FilterSpec) -> Html {
+            let desc = match filter.builtin_desc() {
+                Some(desc) => desc,
+                None => return html! {},
+            };
+            let mut table_row = layout::table::TableRow::new_menu(true, html! { "catches" });
+            table_row.push_single_value(html! { desc });
+            let catch_all = if filter.uid() == uid::Line::CatchAll {
+                render_catch_all(model)
+            } else {
+                html! {}
+            };
+            html! {
+                <>
+                    <br/>
+                    {layout::section_title("Built-in line")}
+                    <br/>
+
+                    {table_row.render()}
+                    {catch_all}
+                </>
+            }
+        }
+
+        /// Renders the live allocations of the catch-all line and the top-site button.
+        ///
+        /// The statistics are the ones of the saved filters, which the server pushes with the
+        /// points.
+        fn render_catch_all(model: &Model) -> Html {
+            let mut live = layout::table::TableRow::new_menu(true, html! { "live" });
+            live.push_single_value(match model.filters.ref_stats().get(uid::Line::CatchAll) {
+                Some(stats) => html! {
+                    format!(
+                        "{} allocation(s), {}B",
+                        num_fmt::str_do(stats.live_count as f64, identity),
+                        num_fmt::bin_str_do(stats.live_size as f64, identity),
+                    )
+                },
+                None => html! { "no statistics yet" },
+            });
+            let generate = if model.is_read_only() {
+                html! {}
+            } else {
+                html! {
+                    <>
+                        <br/>
+                        {layout::button::text::render_default_button(
+                            "catch_all_site",
+                            "generate filter from catch-all top site",
+                            Some(model.link.callback(
+                                |_| msg::to_server::FiltersMsg::request_catch_all_site()
+                            )),
+                            false,
+                        )}
+                    </>
+                }
+            };
+            html! {
+                <>
+                    {live.render()}
+                    {generate}
+                </>
+            }
+        }
+    }
+
     /// Sub-filter rendering.
     pub mod subfilters {
         use super::*;
         use charts::filter::{sub::RawSubFilter, LifetimeFilter, SizeFilter, SubFilter};
 
+        define_style! {
+            GROUP_STYLE = {
+                margin_left(30 px),
+            };
+        }
+
         /// Renders the sub-filters of a filter.
+        ///
+        /// Subfilters in no nested group come first, then the nested groups.
         pub fn render(model: &Model, filter: &filter::Filter) -> Html {
             let uid = filter.uid();
 
@@ -405,24 +1111,119 @@ pub mod menu {
                     {layout::section_title("Catch allocation if ...")}
                     <br/>
 
+                    {render_group_header(model, uid, filter.root(), vec![])}
+                    {render_subs(model, uid, filter.ungrouped().collect())}
                     {
-                        for filter.iter().enumerate().map(
-                            |(index, sub)| render_sub(model, uid, index == 0, sub)
+                        for filter.root().groups().iter().enumerate().map(
+                            |(index, group)| render_group(model, filter, group, vec![index])
                         )
                     }
 
                     <br/>
 
                     {add_subfilter(model, uid)}
+                    {layout::input::datalist(
+                        filter::LABEL_COMPLETIONS_ID,
+                        &model.filters.label_completions,
+                    )}
                 </>
             }
         }
 
-        /// Button for adding sub-filters.
+        /// Renders a nested group of subfilters, indented w.r.t. its parent.
+        ///
+        /// - `path`: path of the group, see the `charts::filter::group` module.
+        fn render_group(
+            model: &Model,
+            filter: &filter::Filter,
+            group: &charts::filter::Group,
+            path: Vec<usize>,
+        ) -> Html {
+            let uid = filter.uid();
+            html! {
+                <div
+                    style = GROUP_STYLE
+                >
+                    {render_group_header(model, uid, group, path.clone())}
+                    {render_subs(model, uid, filter.group_subs(group).collect())}
+                    {
+                        for group.groups().iter().enumerate().map(|(index, nested)| {
+                            let mut nested_path = path.clone();
+                            nested_path.push(index);
+                            render_group(model, filter, nested, nested_path)
+                        })
+                    }
+                </div>
+            }
+        }
+
+        /// Renders the header of a group: its operator, and buttons to add subfilters and groups.
+        ///
+        /// The root group has no *add subfilter* button, see [`add_subfilter`], and cannot be
+        /// ungrouped.
+        ///
+        /// [`add_subfilter`]: fn.add_subfilter.html (The add_subfilter function)
+        fn render_group_header(
+            model: &Model,
+            uid: uid::Filter,
+            group: &charts::filter::Group,
+            path: Vec<usize>,
+        ) -> Html {
+            let is_root = path.is_empty();
+            let mut table_row = layout::table::TableRow::new_menu(
+                is_root,
+                html! { {if is_root { "subfilters" } else { "group" }} },
+            );
+
+            let selector = {
+                let path = path.clone();
+                html! {
+                    <Select<filter::GroupOp>
+                        selected = Some(group.op())
+                        options = filter::GroupOp::all()
+                        on_change = model.link.callback(
+                            move |op| msg::filter::FilterMsg::set_group_op(uid, path.clone(), op)
+                        )
+                    />
+                }
+            };
+            table_row.push_selector(selector);
+
+            if !is_root {
+                let path = path.clone();
+                table_row.push_button(
+                    "add subfilter",
+                    model
+                        .link
+                        .callback(move |_| msg::filter::FilterMsg::request_sub(uid, path.clone())),
+                );
+            }
+            if path.len() < charts::filter::group::MAX_DEPTH {
+                let path = path.clone();
+                table_row.push_button(
+                    "add group",
+                    model
+                        .link
+                        .callback(move |_| msg::filter::FilterMsg::add_group(uid, path.clone())),
+                );
+            }
+            if !is_root {
+                table_row.push_button(
+                    "ungroup",
+                    model
+                        .link
+                        .callback(move |_| msg::filter::FilterMsg::ungroup(uid, path.clone())),
+                );
+            }
+
+            table_row.render()
+        }
+
+        /// Button for adding sub-filters to the root group.
         pub fn add_subfilter(model: &Model, uid: uid::Filter) -> Html {
             let action = model
                 .link
-                .callback(move |_| msg::to_server::FiltersMsg::request_new_sub(uid));
+                .callback(move |_| msg::filter::FilterMsg::request_sub(uid, vec![]));
             layout::button::img::plus(
                 Some(TAB_HEIGHT_PX),
                 "add_subfilter",
@@ -431,59 +1232,126 @@ pub mod menu {
             )
         }
 
+        /// Renders the subfilters of a group, in order.
+        ///
+        /// Each subfilter has buttons to move it up or down among the subfilters of the group.
+        fn render_subs(model: &Model, uid: uid::Filter, subs: Vec<&filter::SubFilter>) -> Html {
+            let last = subs.len().saturating_sub(1);
+            html! {
+                {
+                    for subs.into_iter().enumerate().map(|(index, sub)| {
+                        render_sub(model, uid, false, sub, (index > 0, index < last))
+                    })
+                }
+            }
+        }
+
         /// Renders a sub-filter for a filter.
+        ///
+        /// - `can_move`: whether the subfilter can move up, and whether it can move down.
         pub fn render_sub(
             model: &Model,
             uid: uid::Filter,
             is_first: bool,
             sub: &filter::SubFilter,
+            can_move: (bool, bool),
         ) -> Html {
             let key = render_key(model, uid, sub);
             let mut table_row = layout::table::TableRow::new_menu(is_first, key);
             let sub_uid = sub.uid();
             match sub.raw() {
-                RawSubFilter::Size(sub) => {
-                    size::render(&mut table_row, model, sub, move |size_sub_filter_res| {
-                        msg_of_res(size_sub_filter_res.map(|size| {
-                            msg::filter::FilterMsg::update_sub(
-                                uid,
-                                filter::SubFilter::new(sub_uid, RawSubFilter::Size(size)),
-                            )
-                        }))
-                    })
-                }
+                RawSubFilter::Size(sub) => size::render(&mut table_row, model, sub, move |res| {
+                    update_sub(uid, sub_uid, res.map(RawSubFilter::Size))
+                }),
                 RawSubFilter::Lifetime(sub) => {
-                    lifetime::render(&mut table_row, model, sub, move |lifetime_sub_filter_res| {
-                        msg_of_res(lifetime_sub_filter_res.map(|lifetime| {
-                            msg::filter::FilterMsg::update_sub(
-                                uid,
-                                filter::SubFilter::new(sub_uid, RawSubFilter::Lifetime(lifetime)),
-                            )
-                        }))
+                    lifetime::render(&mut table_row, model, sub, move |res| {
+                        update_sub(uid, sub_uid, res.map(RawSubFilter::Lifetime))
                     })
                 }
-                RawSubFilter::Label(sub) => {
-                    label::render(&mut table_row, model, sub, move |label_sub_filter_res| {
-                        msg_of_res(label_sub_filter_res.map(|label| {
-                            msg::filter::FilterMsg::update_sub(
-                                uid,
-                                filter::SubFilter::new(sub_uid, RawSubFilter::Label(label)),
-                            )
-                        }))
+                RawSubFilter::Label(sub) => label::render(&mut table_row, model, sub, move |res| {
+                    update_sub(uid, sub_uid, res.map(RawSubFilter::Label))
+                }),
+                RawSubFilter::Loc(sub) => {
+                    location::render(&mut table_row, model, sub, move |res| {
+                        update_sub(uid, sub_uid, res.map(RawSubFilter::Loc))
                     })
                 }
-                RawSubFilter::Loc(sub) => {
-                    location::render(&mut table_row, model, sub, move |loc_sub_filter_res| {
-                        msg_of_res(loc_sub_filter_res.map(|loc| {
-                            msg::filter::FilterMsg::update_sub(
-                                uid,
-                                filter::SubFilter::new(sub_uid, RawSubFilter::Loc(loc)),
-                            )
-                        }))
+                RawSubFilter::Frame(sub) => frame::render(&mut table_row, model, sub, move |res| {
+                    update_sub(uid, sub_uid, res.map(RawSubFilter::Frame))
+                }),
+                RawSubFilter::NSamples(sub) => {
+                    nsamples::render(&mut table_row, model, sub, move |res| {
+                        update_sub(uid, sub_uid, res.map(RawSubFilter::NSamples))
                     })
                 }
             };
 
+            let (can_move_up, can_move_down) = can_move;
+            let move_action = |can_move: bool, up: bool| {
+                if can_move {
+                    Some(
+                        model
+                            .link
+                            .callback(move |_| msg::filter::FilterMsg::move_sub(uid, sub_uid, up)),
+                    )
+                } else {
+                    None
+                }
+            };
+            table_row.push_tiny_value(layout::button::img::arrow_up(
+                None,
+                format!("move_sub_up_{}", sub_uid),
+                move_action(can_move_up, true),
+                "move this subfilter up",
+            ));
+            table_row.push_tiny_value(layout::button::img::arrow_down(
+                None,
+                format!("move_sub_down_{}", sub_uid),
+                move_action(can_move_down, false),
+                "move this subfilter down",
+            ));
+
+            html! {
+                <>
+                    {table_row.render()}
+                    {render_preview(model, uid, sub_uid)}
+                </>
+            }
+        }
+
+        /// Message updating a subfilter.
+        ///
+        /// Errors, *e.g.* an illegal regex in a label, show in the preview of the subfilter.
+        fn update_sub(uid: uid::Filter, sub_uid: uid::SubFilter, res: Res<RawSubFilter>) -> Msg {
+            match res {
+                Ok(raw) => {
+                    msg::filter::FilterMsg::update_sub(uid, filter::SubFilter::new(sub_uid, raw))
+                }
+                Err(e) => msg::filter::Msg::preview_error(uid, sub_uid, e.to_pretty()).into(),
+            }
+        }
+
+        /// Renders the preview of a subfilter, if it is the one being edited.
+        fn render_preview(model: &Model, uid: uid::Filter, sub_uid: uid::SubFilter) -> Html {
+            let preview = match model.filters.preview.as_ref() {
+                Some(preview) if preview.filter == uid && preview.sub == sub_uid => preview,
+                _ => return html! {},
+            };
+            let text = match preview.res.as_ref() {
+                None => "previewing...".to_string(),
+                Some(Err(e)) => e.clone(),
+                Some(Ok(preview)) if preview.examples.is_empty() => preview.to_string(),
+                Some(Ok(preview)) => {
+                    let examples: Vec<String> = preview
+                        .examples
+                        .iter()
+                        .map(|labels| format!("[{}]", labels.join(", ")))
+                        .collect();
+                    format!("{}, e.g. {}", preview, examples.join(" "))
+                }
+            };
+            let mut table_row = layout::table::TableRow::new_menu(false, html! { "preview" });
+            table_row.push_single_value(html! { text });
             table_row.render()
         }
 
@@ -491,17 +1359,23 @@ pub mod menu {
         enum SubKey {
             Remove,
             Change(charts::filter::FilterKind),
+            /// Label filter matching a tag.
+            Tag,
         }
         impl SubKey {
-            pub fn all() -> Vec<SubKey> {
+            pub fn all(with_tag: bool) -> Vec<SubKey> {
                 charts::filter::FilterKind::all()
                     .into_iter()
                     .map(Self::Change)
+                    .chain(if with_tag { Some(Self::Tag) } else { None })
                     .chain(Some(Self::Remove))
                     .collect()
             }
             pub fn from_kind(kind: charts::filter::FilterKind) -> Self {
-                Self::Change(kind)
+                match kind {
+                    charts::filter::FilterKind::Label => Self::Tag,
+                    kind => Self::Change(kind),
+                }
             }
         }
         impl fmt::Display for SubKey {
@@ -509,15 +1383,20 @@ pub mod menu {
                 match self {
                     Self::Remove => write!(fmt, "remove"),
                     Self::Change(kind) => kind.fmt(fmt),
+                    Self::Tag => write!(fmt, "tag"),
                 }
             }
         }
 
         /// Renders the action selector for a subfilter.
+        ///
+        /// Labels are not a filter kind of the UI, but label filters matching a tag are.
         pub fn render_key(model: &Model, uid: uid::Filter, sub: &SubFilter) -> Html {
             let sub_uid = sub.uid();
-            let options = SubKey::all();
+            let is_label = sub.kind() == charts::filter::FilterKind::Label;
+            let options = SubKey::all(is_label || !model.tags.is_empty());
             let selected = Some(SubKey::from_kind(sub.kind()));
+            let first_tag = model.tags.keys().next().cloned().unwrap_or_default();
             let sub_clone = sub.clone();
             html! {
                 <Select<SubKey>
@@ -530,6 +1409,15 @@ pub mod menu {
                                 sub.change_kind(kind);
                                 msg::filter::FilterMsg::update_sub(uid, sub)
                             }
+                            SubKey::Tag => msg::filter::FilterMsg::update_sub(
+                                uid,
+                                filter::SubFilter::new(
+                                    sub_uid,
+                                    RawSubFilter::Label(charts::tag::label_filter(
+                                        first_tag.clone(),
+                                    )),
+                                ),
+                            ),
                             SubKey::Remove => {
                                 msg::filter::FilterMsg::rm_sub(uid, sub_uid)
                             }
@@ -542,7 +1430,7 @@ pub mod menu {
         /// Size sub-filter rendering.
         pub mod size {
             use super::*;
-            use charts::filter::ord::Pred;
+            use charts::filter::ord::{OrdFilter, Pred};
 
             /// Renders a size sub-filter.
             pub fn render<Update>(
@@ -552,6 +1440,19 @@ pub mod menu {
                 msg: Update,
             ) where
                 Update: Fn(Res<SizeFilter>) -> Msg + 'static + Clone,
+            {
+                render_count(table_row, model, sub, "machine word(s)", msg)
+            }
+
+            /// Renders a sub-filter over some count, `unit` is the unit of the count.
+            pub fn render_count<Update>(
+                table_row: &mut layout::table::TableRow,
+                model: &Model,
+                sub: &OrdFilter<u32>,
+                unit: &'static str,
+                msg: Update,
+            ) where
+                Update: Fn(Res<OrdFilter<u32>>) -> Msg + 'static + Clone,
             {
                 let selector = {
                     let selected = Some(sub.cmp_kind());
@@ -573,23 +1474,21 @@ pub mod menu {
                 table_row.push_selector(selector);
 
                 match *sub {
-                    SizeFilter::Cmp { cmp, val } => {
-                        table_row.push_value(layout::input::u32_input(
+                    OrdFilter::Cmp { cmp, val } => {
+                        table_row.push_value(layout::input::size_input(
                             model,
                             val,
-                            move |usize_res| msg(usize_res.map(|val| SizeFilter::Cmp { cmp, val })),
+                            move |usize_res| msg(usize_res.map(|val| OrdFilter::Cmp { cmp, val })),
                         ));
-                        table_row.push_value(html! {
-                            "machine word(s)"
-                        })
+                        table_row.push_value(html! { unit })
                     }
-                    SizeFilter::In { lb, ub } => {
+                    OrdFilter::In { lb, ub } => {
                         let msg_fn = msg.clone();
-                        let lb_html = layout::input::u32_input(model, lb, move |usize_res| {
-                            msg_fn(usize_res.map(|lb| SizeFilter::In { lb, ub }))
+                        let lb_html = layout::input::size_input(model, lb, move |usize_res| {
+                            msg_fn(usize_res.map(|lb| OrdFilter::In { lb, ub }))
                         });
-                        let ub_html = layout::input::u32_input(model, ub, move |usize_res| {
-                            msg(usize_res.map(|ub| SizeFilter::In { lb, ub }))
+                        let ub_html = layout::input::size_input(model, ub, move |usize_res| {
+                            msg(usize_res.map(|ub| OrdFilter::In { lb, ub }))
                         });
                         table_row.push_sep(html! {"["});
                         table_row.push_value(lb_html);
@@ -601,6 +1500,22 @@ pub mod menu {
             }
         }
 
+        /// Sample count sub-filter rendering.
+        pub mod nsamples {
+            use super::*;
+            use charts::filter::NSamplesFilter;
+
+            /// Renders a sample count sub-filter, same as a size sub-filter but for the unit.
+            pub fn render(
+                table_row: &mut layout::table::TableRow,
+                model: &Model,
+                sub: &NSamplesFilter,
+                msg: impl Fn(Res<NSamplesFilter>) -> Msg + 'static + Clone,
+            ) {
+                size::render_count(table_row, model, sub, "sample(s)", msg)
+            }
+        }
+
         /// Lifetime sub-filter rendering.
         pub mod lifetime {
             use super::*;
@@ -715,18 +1630,25 @@ pub mod menu {
                     push_add_button!(idx);
 
                     let value = spec.to_string();
-                    let inner = layout::input::string_input(model, &value, {
-                        let msg = msg.clone();
-                        let sub = sub.clone();
+                    let inner = layout::input::completed_string_input(
+                        model,
+                        &value,
+                        filter::LABEL_COMPLETIONS_ID,
+                        &model.filters.label_completions,
+                        |text| msg::filter::Msg::complete_label(text).into(),
+                        {
+                            let msg = msg.clone();
+                            let sub = sub.clone();
 
-                        move |str_res| {
-                            msg(str_res.and_then(LabelSpec::new).map(|spec| {
-                                let mut sub = sub.clone();
-                                sub.replace(idx, spec);
-                                sub
-                            }))
-                        }
-                    });
+                            move |str_res| {
+                                msg(str_res.and_then(LabelSpec::new).map(|spec| {
+                                    let mut sub = sub.clone();
+                                    sub.replace(idx, spec);
+                                    sub
+                                }))
+                            }
+                        },
+                    );
                     if spec.matches_anything() {
                         table_row.push_tiny_value(inner)
                     } else {
@@ -808,6 +1730,107 @@ pub mod menu {
                 }
 
                 push_add_button!(sub.specs().len());
+
+                // Indicate how allocations with unresolved locations are treated, if any.
+                if let Some(stats) = model
+                    .alloc_stats
+                    .as_ref()
+                    .filter(|stats| stats.unresolved_locs > 0)
+                {
+                    table_row.push_value(html! {
+                        format!(
+                            "{} unresolved: {}",
+                            stats.unresolved_locs, stats.unresolved_policy
+                        )
+                    })
+                }
+            }
+        }
+
+        /// Frame sub-filter rendering.
+        pub mod frame {
+            use super::*;
+            use charts::filter::{
+                frame::{FramePred, PathSpec},
+                FrameFilter,
+            };
+
+            /// Parses an optional line bound, empty or `_` for no bound.
+            fn parse_line(s: String) -> Res<Option<usize>> {
+                let s = s.trim();
+                if s.is_empty() || s == "_" {
+                    Ok(None)
+                } else {
+                    let line = usize::from_str_radix(s, 10)
+                        .map_err(|e| err::Error::from(e.to_string()))
+                        .chain_err(|| format!("illegal line number `{}`", s))?;
+                    Ok(Some(line))
+                }
+            }
+
+            /// Renders a frame sub-filter.
+            pub fn render(
+                table_row: &mut layout::table::TableRow,
+                model: &Model,
+                sub: &FrameFilter,
+                msg: impl Fn(Res<FrameFilter>) -> Msg + 'static + Clone,
+            ) {
+                let selector = {
+                    let selected = Some(sub.pred);
+                    let sub = sub.clone();
+                    let msg = msg.clone();
+                    html! {
+                        <Select<FramePred>
+                            selected = selected
+                            options = FramePred::all()
+                            on_change = model.link.callback(
+                                move |pred| msg(Ok(FrameFilter { pred, ..sub.clone() }))
+                            )
+                        />
+                    }
+                };
+                table_row.push_selector(selector);
+
+                table_row.push_value(layout::input::string_input(model, &sub.path.to_string(), {
+                    let msg = msg.clone();
+                    let sub = sub.clone();
+                    move |str_res| {
+                        msg(str_res.and_then(PathSpec::new).map(|path| FrameFilter {
+                            path,
+                            ..sub.clone()
+                        }))
+                    }
+                }));
+
+                let line_to_string = |line: Option<usize>| {
+                    line.map(|line| line.to_string())
+                        .unwrap_or_else(|| "_".into())
+                };
+                let lb_html = layout::input::string_input(model, &line_to_string(sub.line_lb), {
+                    let msg = msg.clone();
+                    let sub = sub.clone();
+                    move |str_res| {
+                        msg(str_res.and_then(parse_line).map(|line_lb| FrameFilter {
+                            line_lb,
+                            ..sub.clone()
+                        }))
+                    }
+                });
+                let ub_html = layout::input::string_input(model, &line_to_string(sub.line_ub), {
+                    let sub = sub.clone();
+                    move |str_res| {
+                        msg(str_res.and_then(parse_line).map(|line_ub| FrameFilter {
+                            line_ub,
+                            ..sub.clone()
+                        }))
+                    }
+                });
+                table_row.push_value(html! { "at line" });
+                table_row.push_sep(html! {"["});
+                table_row.push_tiny_value(lb_html);
+                table_row.push_sep(html! {","});
+                table_row.push_tiny_value(ub_html);
+                table_row.push_sep(html! {"]"});
             }
         }
     }
@@ -846,7 +1869,7 @@ pub mod tabs {
         pub fn render(model: &Model) -> Html {
             let mut tabs = layout::tabs::Tabs::new();
 
-            let edited = model.filters.has_changed();
+            let edited = !model.is_read_only() && model.filters.has_changed();
 
             tabs.push_img_tab(
                 IMG_DIM_PX,
@@ -874,9 +1897,85 @@ pub mod tabs {
                 layout::button::img::Img::Check,
                 "save all modifications",
             );
+            if edited {
+                tabs.push_tab(
+                    model,
+                    "changes",
+                    TabProps::new_footer_gray()
+                        .set_active(model.footer.active == Some(footer::FooterTab::Changes)),
+                    model
+                        .link
+                        .callback(|_| msg::FooterMsg::toggle_tab(footer::FooterTab::Changes)),
+                );
+            }
 
             tabs.push_sep_right();
 
+            tabs.push_tab(
+                model,
+                "notes",
+                TabProps::new_footer_gray()
+                    .set_active(model.footer.active == Some(footer::FooterTab::Notes))
+                    .set_edited(model.notes.has_changed()),
+                model
+                    .link
+                    .callback(|_| msg::FooterMsg::toggle_tab(footer::FooterTab::Notes)),
+            );
+            tabs.push_tab(
+                model,
+                "heap",
+                TabProps::new_footer_gray()
+                    .set_active(model.footer.active == Some(footer::FooterTab::Heap)),
+                model
+                    .link
+                    .callback(|_| msg::FooterMsg::toggle_tab(footer::FooterTab::Heap)),
+            );
+            tabs.push_tab(
+                model,
+                "snapshot",
+                TabProps::new_footer_gray()
+                    .set_active(model.footer.active == Some(footer::FooterTab::Snapshot)),
+                model
+                    .link
+                    .callback(|_| msg::FooterMsg::toggle_tab(footer::FooterTab::Snapshot)),
+            );
+            tabs.push_tab(
+                model,
+                "allocations",
+                TabProps::new_footer_gray()
+                    .set_active(model.footer.active == Some(footer::FooterTab::Allocations)),
+                model
+                    .link
+                    .callback(|_| msg::FooterMsg::toggle_tab(footer::FooterTab::Allocations)),
+            );
+            tabs.push_tab(
+                model,
+                "tags",
+                TabProps::new_footer_gray()
+                    .set_active(model.footer.active == Some(footer::FooterTab::Tags)),
+                model
+                    .link
+                    .callback(|_| msg::FooterMsg::toggle_tab(footer::FooterTab::Tags)),
+            );
+            tabs.push_tab(
+                model,
+                "info",
+                TabProps::new_footer_gray()
+                    .set_active(model.footer.active == Some(footer::FooterTab::Info)),
+                model
+                    .link
+                    .callback(|_| msg::FooterMsg::toggle_tab(footer::FooterTab::Info)),
+            );
+            tabs.push_tab(
+                model,
+                "help",
+                TabProps::new_footer_gray()
+                    .set_active(model.footer.active == Some(footer::FooterTab::Help)),
+                model
+                    .link
+                    .callback(|_| msg::FooterMsg::toggle_tab(footer::FooterTab::Help)),
+            );
+
             html! {
                 <div
                     id = "left_tabs_tile"
@@ -906,6 +2005,17 @@ pub mod tabs {
 
             tabs.push_sep();
 
+            if model.is_read_only() {
+                return html! {
+                    <div
+                        id = "right_tabs_tile"
+                        style = RIGHT_STYLE
+                    >
+                        {tabs.render()}
+                    </div>
+                };
+            }
+
             tabs.push_img_tab(
                 IMG_DIM_PX,
                 TabProps::new_footer_gray(),
@@ -917,6 +2027,17 @@ pub mod tabs {
                 layout::button::img::Img::Plus,
                 "add a new filter",
             );
+            tabs.push_img_tab(
+                IMG_DIM_PX,
+                TabProps::new_footer_gray(),
+                current_filter.map(|uid| {
+                    model
+                        .link
+                        .callback(move |_| msg::filter::Msg::duplicate(uid))
+                }),
+                layout::button::img::Img::Copy,
+                "duplicate current filter",
+            );
             tabs.push_img_tab(
                 IMG_DIM_PX,
                 TabProps::new_footer_gray(),
@@ -967,6 +2088,12 @@ pub mod tabs {
                     .callback(move |_| msg::FooterMsg::toggle_tab(footer::FooterTab::filter(uid)))
             };
 
+            let context_menu = |uid: uid::Line| {
+                model.link.callback(move |event: yew::events::MouseEvent| {
+                    msg::FooterMsg::open_tab_menu(uid, event.client_x())
+                })
+            };
+
             let is_edited =
                 |filter: &filter::FilterSpec| model.filters.is_filter_edited(filter.uid());
 
@@ -976,7 +2103,13 @@ pub mod tabs {
                  index_uid_opt: Option<(usize, uid::Filter)>| {
                     let edited = is_edited(filter);
 
-                    let name = match model.filters.ref_stats().get(filter.uid()) {
+                    let mut name = match model.filters.ref_stats().get(filter.uid()) {
+                        Some(stats) if !edited && filter.uid() == uid::Line::CatchAll => format!(
+                            "{} ({} live, {}B)",
+                            filter.name(),
+                            num_fmt::str_do(stats.live_count as f64, identity),
+                            num_fmt::bin_str_do(stats.live_size as f64, identity),
+                        ),
                         Some(stats) if !edited => format!(
                             "{} ({})",
                             filter.name(),
@@ -985,12 +2118,23 @@ pub mod tabs {
                                 identity,
                             )
                         ),
-                        _ => filter.name().into(),
+                        _ => filter.name().to_string(),
                     };
+                    if filter.is_muted() {
+                        name = format!("🔇 {}", name)
+                    }
 
                     let props = TabProps::new_footer(filter.color().to_string())
+                        .with_contrast_text(filter.color())
                         .set_active(is_active(filter))
-                        .set_edited(edited);
+                        .set_edited(edited)
+                        .set_dimmed(filter.is_muted())
+                        .set_catch_all(filter.uid() == uid::Line::CatchAll);
+                    let props = if model.is_read_only() {
+                        props
+                    } else {
+                        props.with_context_menu(context_menu(filter.uid()))
+                    };
                     let props = if let Some((index, f_uid)) = index_uid_opt {
                         props.with_first_last_uid(|| {
                             (
@@ -255,6 +255,83 @@ impl Header {
         }
     }
 
+    /// Pause/resume button, and paused indicator.
+    fn format_pause(&self, model: &Model) -> Html {
+        define_style! {
+            PAUSED_STYLE = {
+                fg(orange),
+            };
+        }
+
+        let paused = model.charts.is_paused();
+        let onclick = Some(
+            self.link
+                .callback(|_| msg::Msg::from(msg::to_server::ChartsMsg::toggle_pause())),
+        );
+        html! {
+            <>
+                {" | "}
+                {
+                    if paused {
+                        html! {
+                            <>
+                                <span
+                                    style = PAUSED_STYLE
+                                    title = "the server keeps reading the trace, \
+                                        resuming catches up in one go"
+                                >
+                                    {"paused"}
+                                </span>
+                                {" "}
+                            </>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                {layout::button::text::render_default_button(
+                    "toggle_pause",
+                    if paused { "▶ resume" } else { "⏸ pause" },
+                    onclick,
+                    false,
+                )}
+            </>
+        }
+    }
+
+    /// Warning and resync button when the charts are out of sync with the server.
+    fn format_skews(&self, model: &Model) -> Html {
+        define_style! {
+            SKEW_STYLE = {
+                fg(orange),
+            };
+        }
+
+        let skews = model.charts.skews();
+        if skews.is_empty() {
+            return html! {};
+        }
+        let desc: Vec<String> = skews.iter().map(|skew| skew.to_string()).collect();
+        html! {
+            <>
+                {" | "}
+                <span
+                    style = SKEW_STYLE
+                    title = desc.join("\n")
+                >
+                    {format!("out of sync with the server ({})", skews.len())}
+                </span>
+                {" "}
+                {layout::button::text::render_default_button(
+                    "resync",
+                    "resync",
+                    Some(self.link.callback(|_| msg::ChartsMsg::resync())),
+                    false,
+                )}
+            </>
+        }
+    }
+
     /// Formats the statistics, if any.
     fn format_stats(&self, model: &Model) -> Html {
         static LOCAL: time::chrono::Local = time::chrono::Local;
@@ -284,8 +361,65 @@ impl Header {
                     {emph(num_fmt::str_do(stats.alloc_count as f64, identity))}
                     {" allocations, "}
                     {emph(num_fmt::bin_str_do(stats.total_size as f64, |mut s| {s.push('B') ; s}))}
+                    {
+                        if stats.unresolved_locs > 0 {
+                            html! {
+                                <>
+                                    {" ("}
+                                    {emph(num_fmt::str_do(stats.unresolved_locs as f64, identity))}
+                                    {" with unresolved locations, "}
+                                    {stats.unresolved_policy}
+                                    {" by callstack filters)"}
+                                </>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
                     {" | "}
                     {code(stats.dump_dir.display())}
+                    {
+                        if let Some(estimate) = stats.estimate.as_ref() {
+                            html! {
+                                <>
+                                    {" | "}
+                                    {emph(estimate)}
+                                    {
+                                        if model.is_read_only() || estimate.loading_all {
+                                            html! {}
+                                        } else {
+                                            html! {
+                                                <>
+                                                    {" "}
+                                                    {layout::button::text::render_default_button(
+                                                        "load_all",
+                                                        "load everything",
+                                                        Some(self.link.callback(|_| {
+                                                            msg::Msg::from(
+                                                                msg::to_server::Msg::load_all(),
+                                                            )
+                                                        })),
+                                                        false,
+                                                    )}
+                                                </>
+                                            }
+                                        }
+                                    }
+                                </>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {self.format_pause(model)}
+                    {self.format_skews(model)}
+                    {
+                        if model.is_read_only() {
+                            html! { <>{" | "}{emph("read-only")}</> }
+                        } else {
+                            html! {}
+                        }
+                    }
                 </p>
             }
         } else {
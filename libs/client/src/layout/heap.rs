@@ -0,0 +1,163 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Heap composition rendering.
+//!
+//! A donut of the live heap by filter, drawn as inline SVG from the filter statistics the server
+//! sends with the allocation statistics. The everything line is not a slice since it overlaps with
+//! all the others, the catch-all line is.
+
+prelude! {}
+
+/// Radius of the donut, the SVG is `100` by `100`.
+const RADIUS: f64 = 35.0;
+/// Width of the ring of the donut.
+const RING_WIDTH: f64 = 20.0;
+
+/// Pretty string for a size in bytes.
+fn size_str(size: u64) -> String {
+    format!("{}B", num_fmt::bin_str_do(size as f64, base::identity))
+}
+
+/// Renders the heap composition in the menu part of the footer.
+pub fn render(model: &Model) -> Html {
+    define_style! {
+        DONUT_STYLE = {
+            height(200 px),
+            width(200 px),
+            float(left),
+        };
+        SLICE_STYLE = {
+            pointer,
+        };
+        TABLE_STYLE = {
+            float(left),
+            padding(0%, 2%),
+        };
+    }
+
+    let filters = model.filters();
+    let composition = model.filters.ref_stats().composition();
+    let total: u64 = composition.iter().map(|(_, size, _)| size).sum();
+
+    // Spec of each slice, the catch-all line is not listed when there are no user filters, in
+    // which case it is the same as the everything line. Muted lines have no slice.
+    let slices: Vec<_> = composition
+        .into_iter()
+        .filter_map(|(line, size, share)| {
+            let spec = filters
+                .specs_iter()
+                .find(|spec| spec.uid() == line)
+                .or_else(|| match line {
+                    uid::Line::CatchAll => filters.specs_iter().next(),
+                    _ => None,
+                })?;
+            if spec.is_muted() {
+                return None;
+            }
+            Some((spec, size, share))
+        })
+        .collect();
+
+    let desc = |spec: &filter::FilterSpec, size: u64, share: f64| {
+        format!(
+            "{}: {} ({:.2}%)",
+            spec.name(),
+            size_str(size),
+            share * 100.0
+        )
+    };
+    let open = |line: uid::Line| {
+        model
+            .link
+            .callback(move |_| msg::FooterMsg::toggle_tab(footer::FooterTab::filter(line)))
+    };
+
+    let mut start = 0.0;
+    let donut = html! {
+        <svg
+            id = "heap_donut"
+            style = DONUT_STYLE
+            viewBox = "0 0 100 100"
+        >
+            <g transform = "rotate(-90 50 50)">
+                {for slices.iter().map(|(spec, size, share)| {
+                    let offset = -start * 100.0;
+                    start += share;
+                    html! {
+                        <circle
+                            style = SLICE_STYLE
+                            cx = "50"
+                            cy = "50"
+                            r = RADIUS.to_string()
+                            fill = "none"
+                            stroke = spec.color().to_string()
+                            stroke-width = RING_WIDTH.to_string()
+                            pathLength = "100"
+                            stroke-dasharray = format!("{} {}", share * 100.0, 100)
+                            stroke-dashoffset = offset.to_string()
+                            onclick = open(spec.uid())
+                        >
+                            <title>{desc(spec, *size, *share)}</title>
+                        </circle>
+                    }
+                })}
+            </g>
+        </svg>
+    };
+
+    let table = html! {
+        <table
+            id = "heap_table"
+            style = TABLE_STYLE
+        >
+            {for slices.iter().map(|(spec, size, share)| html! {
+                <tr
+                    style = SLICE_STYLE
+                    onclick = open(spec.uid())
+                >
+                    <td style = format!("color: {}", spec.color())>{"■"}</td>
+                    <td>{spec.name().to_string()}</td>
+                    <td>{size_str(*size)}</td>
+                    <td>{format!("{:.2}%", share * 100.0)}</td>
+                </tr>
+            })}
+        </table>
+    };
+
+    html! {
+        <>
+            <br/>
+            {layout::section_title(&format!("Live heap: {}", size_str(total)))}
+            <br/>
+            {
+                if slices.is_empty() {
+                    html! { "no live allocation" }
+                } else {
+                    html! {
+                        <>
+                            {donut}
+                            {table}
+                        </>
+                    }
+                }
+            }
+        </>
+    }
+}
@@ -0,0 +1,163 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Help rendering.
+//!
+//! The entries of the help are not written by hand: they come from the sub-filter kinds of
+//! [`charts::filter::FilterKind`], and from the features of the trace format the server sends,
+//! which it builds from the schema of the trace parser. So the help cannot go stale.
+//!
+//! [`charts::filter::FilterKind`]: ../../../charts/filter/enum.FilterKind.html
+//! (The FilterKind enum)
+
+prelude! {}
+
+use charts::filter::FilterKind;
+
+define_style! {
+    SEARCH_STYLE = {
+        width(50%),
+    };
+    UNAVAILABLE_STYLE = {
+        fg(gray),
+    };
+}
+
+/// A help entry.
+struct Entry {
+    /// Name of the entry.
+    key: String,
+    /// Description of the entry.
+    desc: String,
+    /// True if the entry is unavailable, *e.g.* a feature the trace format does not support.
+    unavailable: bool,
+}
+impl Entry {
+    /// True if the entry matches a search string.
+    ///
+    /// The search is case-insensitive, and the empty search matches everything.
+    fn matches(&self, search: &str) -> bool {
+        search.is_empty()
+            || self.key.to_lowercase().contains(search)
+            || self.desc.to_lowercase().contains(search)
+    }
+}
+
+/// Sub-filter entries.
+fn filter_entries() -> Vec<Entry> {
+    FilterKind::all()
+        .into_iter()
+        .map(|kind| Entry {
+            key: kind.to_string(),
+            desc: kind.help().into(),
+            unavailable: false,
+        })
+        .collect()
+}
+
+/// Trace feature entries, empty if the format of the trace is unknown.
+fn feature_entries(model: &Model) -> Vec<Entry> {
+    model
+        .alloc_stats
+        .as_ref()
+        .and_then(|stats| stats.trace_format.as_ref())
+        .map(|format| {
+            format
+                .features
+                .iter()
+                .map(|feature| Entry {
+                    key: feature.name.clone(),
+                    desc: feature
+                        .unavailable
+                        .clone()
+                        .unwrap_or_else(|| feature.desc.clone()),
+                    unavailable: feature.unavailable.is_some(),
+                })
+                .collect()
+        })
+        .unwrap_or_else(Vec::new)
+}
+
+/// Renders the help in the menu part of the footer.
+pub fn render(model: &Model) -> Html {
+    let search = model.footer.help_search.to_lowercase();
+    html! {
+        <>
+            <br/>
+            <input
+                id = "help_search"
+                type = "text"
+                style = SEARCH_STYLE
+                value = &model.footer.help_search
+                placeholder = "search the help"
+                oninput = model.link.callback(
+                    |data: yew::events::InputData| msg::FooterMsg::help_search(data.value)
+                )
+            />
+            <br/>
+            {render_section("Sub-filters", "help_filters_table", filter_entries(), &search)}
+            {render_section(
+                "Trace features",
+                "help_features_table",
+                feature_entries(model),
+                &search,
+            )}
+        </>
+    }
+}
+
+/// Renders the entries of a section matching a search string.
+fn render_section(title: &str, id: &str, entries: Vec<Entry>, search: &str) -> Html {
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter(|entry| entry.matches(search))
+        .collect();
+    if entries.is_empty() {
+        return html! {};
+    }
+    html! {
+        <>
+            <br/>
+            {layout::section_title(title)}
+            <br/>
+            <table id = id>
+                {for entries.iter().map(render_entry)}
+            </table>
+        </>
+    }
+}
+
+/// Renders an entry.
+fn render_entry(entry: &Entry) -> Html {
+    if entry.unavailable {
+        html! {
+            <tr style = UNAVAILABLE_STYLE>
+                <td>{&entry.key}</td>
+                <td>{format!("unavailable: {}", entry.desc)}</td>
+            </tr>
+        }
+    } else {
+        html! {
+            <tr>
+                <td>{&entry.key}</td>
+                <td>{&entry.desc}</td>
+            </tr>
+        }
+    }
+}
@@ -0,0 +1,372 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Trace info rendering.
+//!
+//! Shows the statistics of the filters, the number of filters and charts against the limits of the
+//! server, and the format of the trace the server loaded with the features it cannot support given
+//! its version. The server builds the trace information from the
+//! schema of the trace parser, and sends the filter statistics each time it sends new points. Also
+//! shows the run the trace comes from, and the status of the parse cache of the trace, with its
+//! size. Events of unknown kind the parser skipped are reported with the format of the trace.
+//!
+//! Users can also reload the trace from scratch, or switch to another CTF file. Every client sees
+//! the switch, and failures are reported to the requesting client only.
+//!
+//! If the server persists sessions, users can clear the session file: the next clients then start
+//! from the filter generator, until someone saves a session again.
+
+prelude! {}
+
+use alloc::{FormatFeature, TraceFormat};
+use charts::filter::stats::FilterStats;
+
+define_style! {
+    UNAVAILABLE_STYLE = {
+        fg(gray),
+    };
+}
+
+/// Renders the trace info in the menu part of the footer.
+pub fn render(model: &Model) -> Html {
+    html! {
+        <>
+            {render_filter_stats(model)}
+            <br/>
+            {layout::section_title("Limits")}
+            <br/>
+            {render_limits(model)}
+            <br/>
+            {layout::section_title("Run")}
+            <br/>
+            {
+                match model.trace_info.as_ref() {
+                    Some(info) => render_trace_info(model, info),
+                    None => html! { "the trace has no information about its run" },
+                }
+            }
+            <br/>
+            {layout::section_title("Trace")}
+            <br/>
+            {render_file(model)}
+            <br/>
+            {
+                match model.alloc_stats.as_ref().and_then(|stats| stats.trace_format.as_ref()) {
+                    Some(format) => render_format(format),
+                    None => html! { "the format of the trace is unknown" },
+                }
+            }
+            {render_skipped_events(model)}
+            <br/>
+            {layout::section_title("Cache")}
+            <br/>
+            {
+                match model.alloc_stats.as_ref().and_then(|stats| stats.cache.as_ref()) {
+                    Some(status) => html! { <>{status.to_string()}</> },
+                    None => html! { "no parse cache for this trace" },
+                }
+            }
+            <br/>
+            {layout::section_title("Session")}
+            <br/>
+            {render_session(model)}
+        </>
+    }
+}
+
+/// Renders the status of the session file of the server, with a button to clear it.
+fn render_session(model: &Model) -> Html {
+    let status = match model.session.as_ref() {
+        Some(status) => status,
+        None => return html! { "the server does not save sessions" },
+    };
+    let desc = match (status.saved, status.restore) {
+        (false, _) => "no session saved",
+        (true, false) => "session saved, new clients do not restore it",
+        (true, true) => "session saved, new clients restore it",
+    };
+    html! {
+        <>
+            {"file: "}
+            {layout::header::code(&status.path)}
+            <br/>
+            {desc}
+            {
+                if model.is_read_only() || !status.saved {
+                    html! {}
+                } else {
+                    html! {
+                        <>
+                            {" "}
+                            {layout::button::text::render_default_button(
+                                "session_clear",
+                                "clear session",
+                                Some(model.link.callback(|_| msg::to_server::Msg::clear_session())),
+                                false,
+                            )}
+                        </>
+                    }
+                }
+            }
+        </>
+    }
+}
+
+/// Renders the file the trace comes from, with the inputs to reload it or to switch to another
+/// file.
+fn render_file(model: &Model) -> Html {
+    let path = model
+        .alloc_stats
+        .as_ref()
+        .map(|stats| stats.dump_dir.display().to_string())
+        .unwrap_or_default();
+    if model.is_read_only() {
+        return html! { <>{"file: "}{layout::header::code(&path)}</> };
+    }
+    html! {
+        <>
+            {"file: "}
+            {layout::input::string_input(model, &path, |res| match res {
+                Ok(path) => msg::to_server::Msg::switch_trace(path.trim()).into(),
+                Err(e) => msg::Msg::err(e),
+            })}
+            {" "}
+            {layout::button::text::render_default_button(
+                "trace_reload",
+                "reload",
+                Some(model.link.callback(|_| msg::to_server::Msg::reload_trace())),
+                false,
+            )}
+        </>
+    }
+}
+
+/// Renders the number of events the trace parser skipped, if any.
+fn render_skipped_events(model: &Model) -> Html {
+    match model.alloc_stats.as_ref().map(|stats| stats.skipped_events) {
+        Some(count) if count > 0 => html! {
+            <>
+                <br/>
+                {format!(
+                    "skipped {} event(s) of unknown kind, and the rest of their packets",
+                    num_fmt::str_do(count as f64, identity),
+                )}
+            </>
+        },
+        _ => html! {},
+    }
+}
+
+/// Pretty string for a size in bytes.
+fn size_str(size: u64) -> String {
+    num_fmt::str_do(size as f64, |size| format!("{}B", size))
+}
+
+/// Renders the statistics of the everything line, the user filters, and the catch-all line.
+///
+/// Only mentions the current filters, so removed filters do not appear.
+fn render_filter_stats(model: &Model) -> Html {
+    let (filters, stats) = (model.filters(), model.filters.ref_stats());
+    html! {
+        <>
+            <br/>
+            {layout::section_title("Filters")}
+            <br/>
+            <table id = "filter_stats_table">
+                <tr>
+                    <th>{"filter"}</th>
+                    <th>{"live size"}</th>
+                    <th>{"live allocations"}</th>
+                    <th>{"allocations"}</th>
+                    <th>{"peak live size"}</th>
+                </tr>
+                {for filters.specs_iter().map(|spec| render_stats(spec, stats.get(spec.uid())))}
+            </table>
+        </>
+    }
+}
+
+/// Renders the statistics of a filter, if any.
+fn render_stats(spec: &filter::FilterSpec, stats: Option<&FilterStats>) -> Html {
+    let cells = match stats {
+        Some(stats) => html! {
+            <>
+                <td>{size_str(stats.live_size)}</td>
+                <td>{num_fmt::str_do(stats.live_count as f64, identity)}</td>
+                <td>{num_fmt::str_do(stats.alloc_count as f64, identity)}</td>
+                <td>{
+                    match stats.peak_time {
+                        Some(time) => format!("{} at {}", size_str(stats.peak_live_size), time),
+                        None => "none".into(),
+                    }
+                }</td>
+            </>
+        },
+        None => html! {
+            <td colspan = "4">{"no statistics yet"}</td>
+        },
+    };
+    html! {
+        <tr>
+            <td style = format!("color: {}", spec.color())>{spec.name().to_string()}</td>
+            {cells}
+        </tr>
+    }
+}
+
+/// Renders the number of filters and charts against the limits of the server.
+///
+/// The number of filters includes the unsaved ones.
+fn render_limits(model: &Model) -> Html {
+    let limits = model.limits();
+    let row = |what: &str, count: usize, max: usize, flag: &str| {
+        html! {
+            <tr title = format!("the server limit can be raised with `{}`", flag)>
+                <td>{what}</td>
+                <td>{format!("{} / {}", count, max)}</td>
+            </tr>
+        }
+    };
+    html! {
+        <table id = "limits_table">
+            {row(
+                "filters",
+                model.footer_filters().filters.len(),
+                limits.filters,
+                charts::limits::Limits::FILTERS_FLAG,
+            )}
+            {row(
+                "charts",
+                model.charts().len(),
+                limits.charts,
+                charts::limits::Limits::CHARTS_FLAG,
+            )}
+        </table>
+    }
+}
+
+/// Renders the information about the run a trace comes from.
+///
+/// Sizes are extrapolated from the samples, the scaling factor tells by how much.
+fn render_trace_info(model: &Model, info: &alloc::TraceInfo) -> Html {
+    let row = |key: &str, val: String| {
+        html! {
+            <tr>
+                <td>{key}</td>
+                <td>{val}</td>
+            </tr>
+        }
+    };
+    let duration = model
+        .alloc_stats
+        .as_ref()
+        .map(|stats| stats.duration.to_string())
+        .unwrap_or_else(|| "unknown".into());
+    html! {
+        <table id = "run_info_table">
+            {row("executable", info.exe_name.clone())}
+            {row(
+                "arguments",
+                if info.exe_params.is_empty() {
+                    "none".into()
+                } else {
+                    info.exe_params.clone()
+                },
+            )}
+            {row("host", info.host_name.clone())}
+            {row("PID", info.pid.to_string())}
+            {row("word size", format!("{} bits", info.word_size))}
+            {row("sampling rate", info.sample_rate.to_string())}
+            <tr title = "sizes are extrapolated from the samples, by this factor">
+                <td>{"scaling factor"}</td>
+                <td>{format!("×{}", info.scaling_factor())}</td>
+            </tr>
+            {row("duration so far", duration)}
+        </table>
+    }
+}
+
+/// Renders a trace format.
+fn render_format(format: &TraceFormat) -> Html {
+    html! {
+        <table id = "trace_info_table">
+            <tr>
+                <td>{"format"}</td>
+                <td>{layout::header::emph(format)}</td>
+            </tr>
+            <tr>
+                <td>{"events"}</td>
+                <td>{format.events.join(", ")}</td>
+            </tr>
+            <tr>
+                <td>{"quirks"}</td>
+                <td>{
+                    if format.quirks.is_empty() {
+                        "none".into()
+                    } else {
+                        format.quirks.join(", ")
+                    }
+                }</td>
+            </tr>
+            {
+                match format.context.as_ref() {
+                    Some(context) => html! {
+                        <tr>
+                            <td>{"context"}</td>
+                            <td>{
+                                if context.is_empty() {
+                                    html! { "none" }
+                                } else {
+                                    layout::header::code(context)
+                                }
+                            }</td>
+                        </tr>
+                    },
+                    None => html! {},
+                }
+            }
+            {for format.features.iter().map(render_feature)}
+        </table>
+    }
+}
+
+/// Renders a feature depending on the format.
+///
+/// Unavailable features are greyed out, with the reason as a tooltip.
+fn render_feature(feature: &FormatFeature) -> Html {
+    match feature.unavailable.as_ref() {
+        Some(reason) => html! {
+            <tr
+                style = UNAVAILABLE_STYLE
+                title = reason
+            >
+                <td>{&feature.name}</td>
+                <td>{"unavailable"}</td>
+            </tr>
+        },
+        None => html! {
+            <tr
+                title = &feature.desc
+            >
+                <td>{&feature.name}</td>
+                <td>{"available"}</td>
+            </tr>
+        },
+    }
+}
@@ -55,6 +55,36 @@ pub fn text_input(value: &str, onchange: OnChangeAction) -> Html {
         />
     }
 }
+/// Generates HTML for a text input field with a maximum length.
+///
+/// Displays a counter next to the field showing the length of `value` w.r.t. `max_len`.
+pub fn bounded_text_input(value: &str, max_len: usize, onchange: OnChangeAction) -> Html {
+    define_style! {
+        COUNTER_STYLE = {
+            font_size(70%),
+            fg(gray),
+            margin(0%, 0%, 0%, 1%),
+        };
+    }
+    let len = value.chars().count();
+    html! {
+        <>
+            <input
+                type = "text"
+                class = "text_input"
+                style = TEXT_INPUT_STYLE
+                value = value
+                maxlength = max_len.to_string()
+                onchange = onchange
+            />
+            <span
+                style = COUNTER_STYLE
+            >
+                {format!("{}/{}", len, max_len)}
+            </span>
+        </>
+    }
+}
 /// Generates HTML for an input field with steps.
 pub fn step_input(value: &str, step: impl fmt::Display, onchange: OnChangeAction) -> Html {
     html! {
@@ -95,6 +125,62 @@ pub fn string_input(
     )
 }
 
+/// Generates a text-input field with completions, expecting a string result.
+///
+/// The completions are the options of the datalist with identifier `list`. Each keystroke yields
+/// `oninput`, except when the text is one of the `completions`: picking a completion yields `msg`
+/// right away, as validating the field does.
+pub fn completed_string_input(
+    model: &Model,
+    value: &str,
+    list: &str,
+    completions: &[String],
+    oninput: impl Fn(String) -> Msg + 'static,
+    msg: impl Fn(Res<String>) -> Msg + 'static + Clone,
+) -> Html {
+    let onchange = {
+        let msg = msg.clone();
+        model.link.callback(move |data| {
+            msg(parse_text_data(data)
+                .map_err(err::Error::from)
+                .chain_err(|| "while parsing string value"))
+        })
+    };
+    let completions = completions.to_vec();
+    let oninput = model.link.callback(move |data: yew::events::InputData| {
+        if completions.contains(&data.value) {
+            msg(Ok(data.value))
+        } else {
+            oninput(data.value)
+        }
+    });
+    html! {
+        <input
+            type = "text"
+            class = "text_input"
+            style = TEXT_INPUT_STYLE
+            value = value
+            list = list
+            autocomplete = "off"
+            onchange = onchange
+            oninput = oninput
+        />
+    }
+}
+
+/// Generates a datalist of completions for text-input fields.
+pub fn datalist(id: &str, completions: &[String]) -> Html {
+    html! {
+        <datalist id = id>
+            {
+                for completions.iter().map(
+                    |completion| html! { <option value = completion.clone()/> }
+                )
+            }
+        </datalist>
+    }
+}
+
 /// Parses a modification from a text-input field as a usize.
 fn parse_usize_data(data: ChangeData) -> Res<usize> {
     use alloc::parser::Parseable;
@@ -113,16 +199,19 @@ pub fn usize_input(model: &Model, value: usize, msg: impl Fn(Res<usize>) -> Msg
 }
 
 /// Generates a text-input field expecting a lifetime-like value.
+///
+/// The value is a human-readable duration such as `1.5s` or `2m30s`, see `parse_human` in
+/// `DurationExt`.
 pub fn lifetime_input(
     model: &Model,
     value: time::Lifetime,
     msg: impl Fn(Res<time::Lifetime>) -> Msg + 'static,
 ) -> Html {
     text_input(
-        &value.to_string(),
+        &value.fmt_human().to_string(),
         model.link.callback(move |data| {
             let lifetime = parse_text_data(data).and_then(|txt| {
-                time::Lifetime::parse_secs(&txt).chain_err(|| "while parsing lifetime value")
+                time::Lifetime::parse_human(&txt).chain_err(|| "while parsing lifetime value")
             });
             msg(lifetime)
         }),
@@ -130,45 +219,57 @@ pub fn lifetime_input(
 }
 
 /// Generates a text-input field expecting an optional time-like (SinceStart) value.
+///
+/// The value is a human-readable duration, like for [`lifetime_input`]. An empty field yields
+/// `None`.
+///
+/// [`lifetime_input`]: fn.lifetime_input.html (The lifetime_input function)
 pub fn since_start_opt_input(
     model: &Model,
-    step: impl fmt::Display,
     value: Option<time::SinceStart>,
     msg: impl Fn(Res<Option<time::SinceStart>>) -> Msg + 'static,
 ) -> Html {
-    step_input(
+    text_input(
         &value
-            .map(|t| {
-                let mut s = t.to_string();
-                loop {
-                    match s.pop() {
-                        Some('0') => (),
-                        Some(c) => {
-                            if c != '.' {
-                                s.push(c)
-                            }
-                            break;
-                        }
-                        None => {
-                            s.push('0');
-                            break;
-                        }
-                    }
-                }
-                s
-            })
+            .map(|time| time.fmt_human().to_string())
             .unwrap_or_else(|| "".into()),
-        step,
         model.link.callback(move |data| {
-            let time_opt = parse_text_data(data).and_then(|txt| match &txt as &str {
+            let time_opt = parse_text_data(data).and_then(|txt| match txt.trim() {
                 "" => Ok(None),
-                txt => Ok(time::SinceStart::parse_secs(txt).ok()),
+                txt => time::SinceStart::parse_human(txt)
+                    .map(Some)
+                    .chain_err(|| "while parsing time value"),
             });
             msg(time_opt)
         }),
     )
 }
 
+/// Generates a number-input field expecting an optional integer (`u32`) value.
+///
+/// An empty field yields `None`.
+pub fn u32_opt_input(
+    model: &Model,
+    step: impl fmt::Display,
+    value: Option<u32>,
+    msg: impl Fn(Res<Option<u32>>) -> Msg + 'static,
+) -> Html {
+    step_input(
+        &value.map(|n| n.to_string()).unwrap_or_else(|| "".into()),
+        step,
+        model.link.callback(move |data| {
+            let opt = parse_text_data(data).and_then(|txt| match txt.trim() {
+                "" => Ok(None),
+                txt => txt
+                    .parse::<u32>()
+                    .map(Some)
+                    .map_err(|e| format!("illegal integer `{}`: {}", txt, e).into()),
+            });
+            msg(opt.chain_err(|| "while parsing integer value"))
+        }),
+    )
+}
+
 fn parse_u32_data(data: ChangeData) -> Res<u32> {
     use alloc::parser::Parseable;
     parse_text_data(data).and_then(|txt| u32::parse(txt).map_err(|e| e.into()))
@@ -185,6 +286,28 @@ pub fn u32_input(model: &Model, value: u32, msg: impl Fn(Res<u32>) -> Msg + 'sta
     )
 }
 
+/// Parses a modification from a text-input field as a size.
+///
+/// See [`charts::filter::ord::parse_size`] for the units accepted.
+///
+/// [`charts::filter::ord::parse_size`]: ../../../charts/filter/ord/fn.parse_size.html (The parse_size function)
+fn parse_size_data(data: ChangeData) -> Res<u32> {
+    parse_text_data(data).and_then(|txt| charts::filter::ord::parse_size(&txt))
+}
+/// Generates a text-input field expecting a size, *i.e.* a number of machine words.
+///
+/// The size is displayed in human-readable form if it can be done without losing precision.
+pub fn size_input(model: &Model, value: u32, msg: impl Fn(Res<u32>) -> Msg + 'static) -> Html {
+    text_input(
+        &charts::filter::ord::size_to_string(value),
+        model.link.callback(move |data| {
+            msg(parse_size_data(data)
+                .map_err(|e| err::Error::from(e))
+                .chain_err(|| "while parsing size value"))
+        }),
+    )
+}
+
 /// Generates HTML for a color selector.
 pub fn color_input(value: &impl fmt::Display, onchange: OnChangeAction) -> Html {
     html! {
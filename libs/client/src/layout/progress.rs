@@ -19,7 +19,7 @@
 
 //! Progress-bar rendering.
 //!
-//! This is used when the server is still parsing the dumps.
+//! This is used when the server is still parsing the dumps, or generating filters.
 
 prelude! {}
 
@@ -48,10 +48,7 @@ pub fn render(info: &LoadInfo) -> Html {
             </div>
             <br/>
             <div>
-                {format!(
-                    "{} / {}",
-                    info.loaded, info.total,
-                )}
+                {format!("{} ({:.0}%)", info.desc(), percent)}
             </div>
             <br/>
             <progress
@@ -64,3 +61,59 @@ pub fn render(info: &LoadInfo) -> Html {
         </center>
     }
 }
+
+/// Renders the filter generation progress bar, with a cancel button.
+///
+/// This is used when the server is done parsing the dumps, but is still generating filters.
+pub fn render_gen(model: &Model, info: &GenProgress) -> Html {
+    define_style! {
+        BIG = {
+            font_size(180%),
+        };
+        PROGRESS = {
+            width(70%),
+        };
+        CANCEL = {
+            width(20%),
+        };
+    }
+
+    let percent = info.percent();
+
+    html! {
+        <center
+            style = BIG
+        >
+            <br/>
+            <div>
+                {"Please wait, memthol is generating filters..."}
+            </div>
+            <br/>
+            <div>
+                {format!("{} ({:.0}%)", info.phase, percent)}
+            </div>
+            <br/>
+            <progress
+                value = percent
+                max = 100
+                style = PROGRESS
+            >
+                { format!("{:.0}%", percent) }
+            </progress>
+            <br/>
+            <br/>
+            <div
+                style = CANCEL
+            >
+                {layout::button::text::render_default_button(
+                    "cancel_filter_gen",
+                    "cancel",
+                    Some(model.link.callback(
+                        |_| msg::to_server::FiltersMsg::cancel_gen()
+                    )),
+                    false,
+                )}
+            </div>
+        </center>
+    }
+}
@@ -0,0 +1,131 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Snapshot diff rendering.
+//!
+//! Users pick two instants, the server answers with the allocation sites of each line whose live
+//! size grew between them, see [`charts::snapshot`].
+//!
+//! [`charts::snapshot`]: ../../../charts/snapshot/index.html (The charts::snapshot module)
+
+prelude! {}
+
+/// Pretty string for a size in bytes.
+fn size_str(size: u64) -> String {
+    format!("{}B", num_fmt::bin_str_do(size as f64, base::identity))
+}
+
+/// Renders the snapshot diff tab in the menu part of the footer.
+pub fn render(model: &Model) -> Html {
+    define_style! {
+        SITE_STYLE = {
+            font(code),
+        };
+    }
+
+    let (from, to) = (model.footer.snapshot_from, model.footer.snapshot_to);
+    let compare = match (from, to) {
+        (Some(from), Some(to)) if from <= to => Some(
+            model
+                .link
+                .callback(move |_| msg::to_server::ChartsMsg::snapshot_diff(from, to)),
+        ),
+        _ => None,
+    };
+    let dimmed = compare.is_none();
+
+    let inputs = html! {
+        <>
+            {"live allocations created after "}
+            {layout::input::since_start_opt_input(model, from, |res| match res {
+                Ok(from) => msg::FooterMsg::snapshot_from(from),
+                Err(e) => msg::Msg::err(e),
+            })}
+            {" that are still alive at "}
+            {layout::input::since_start_opt_input(model, to, |res| match res {
+                Ok(to) => msg::FooterMsg::snapshot_to(to),
+                Err(e) => msg::Msg::err(e),
+            })}
+            {" "}
+            {layout::button::text::render_default_button(
+                "snapshot_compare",
+                "compare",
+                compare,
+                dimmed,
+            )}
+        </>
+    };
+
+    let filters = model.filters();
+    let result = match model.snapshot_diff.as_ref() {
+        None => html! {},
+        Some(diff) => html! {
+            <>
+                <br/>
+                {format!("growth between {} s and {} s", diff.from, diff.to)}
+                <table id = "snapshot_table">
+                    <tr>
+                        <th/>
+                        <th>{"filter"}</th>
+                        <th>{"site"}</th>
+                        <th>{"count delta"}</th>
+                        <th>{"bytes delta"}</th>
+                    </tr>
+                    {for diff.lines.iter().filter_map(|(line, sites)| {
+                        let spec = filters.specs_iter().find(|spec| spec.uid() == *line)?;
+                        let color = format!("color: {}", spec.color());
+                        let name = spec.name().to_string();
+                        if sites.is_empty() {
+                            return Some(html! {
+                                <tr>
+                                    <td style = color>{"■"}</td>
+                                    <td>{name}</td>
+                                    <td colspan = "3">{"no growth"}</td>
+                                </tr>
+                            });
+                        }
+                        Some(html! {
+                            <>
+                                {for sites.iter().map(|site| html! {
+                                    <tr>
+                                        <td style = color.clone()>{"■"}</td>
+                                        <td>{name.clone()}</td>
+                                        <td style = SITE_STYLE>{site.to_string()}</td>
+                                        <td>{format!("+{}", site.count)}</td>
+                                        <td>{format!("+{}", size_str(site.bytes))}</td>
+                                    </tr>
+                                })}
+                            </>
+                        })
+                    })}
+                </table>
+            </>
+        },
+    };
+
+    html! {
+        <>
+            <br/>
+            {layout::section_title("Snapshot diff")}
+            <br/>
+            {inputs}
+            {result}
+        </>
+    }
+}
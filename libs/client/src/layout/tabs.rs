@@ -50,6 +50,12 @@ pub struct TabProps {
     top: bool,
     /// Makes the whole tab round (*i.e.*, not oriented).
     round: bool,
+    /// Colors of the text and of its outline, if not the default ones.
+    text_color: Option<(String, String)>,
+    /// True if the tab is for the catch-all line, which is not a normal filter.
+    catch_all: bool,
+    /// Action on right-click, replaces the context menu of the browser.
+    context_menu: Option<OnClickAction>,
 }
 impl TabProps {
     /// Creates a tab with some color.
@@ -62,6 +68,9 @@ impl TabProps {
             rev: false,
             top: false,
             round: false,
+            text_color: None,
+            catch_all: false,
+            context_menu: None,
         }
     }
 
@@ -75,6 +84,9 @@ impl TabProps {
             rev: false,
             top: true,
             round: false,
+            text_color: None,
+            catch_all: false,
+            context_menu: None,
         }
     }
     /// Creates a new gray footer tab.
@@ -88,10 +100,20 @@ impl TabProps {
         self
     }
 
+    /// Makes the text readable over a color, see [`Color::contrast_text`].
+    ///
+    /// [`Color::contrast_text`]: ../../../charts/color/struct.Color.html#method.contrast_text
+    /// (The contrast_text method of Color)
+    pub fn with_contrast_text(mut self, color: &charts::color::Color) -> Self {
+        let text = color.contrast_text();
+        self.text_color = Some((text.to_string(), text.contrast_text().to_string()));
+        self
+    }
+
     /// Turns itself into button box properties
     pub fn to_box_props(&self) -> layout::button::BoxProps {
         let active = self.active.to_bool();
-        layout::button::BoxProps::new_tab("black")
+        let props = layout::button::BoxProps::new_tab("black")
             .with_gradient_top(if active {
                 layout::DARK_GREY_BG
             } else {
@@ -102,7 +124,11 @@ impl TabProps {
             .with_radius_px(10)
             .revert_if(self.rev)
             .for_footer(self.top)
-            .round_if(self.round)
+            .round_if(self.round);
+        match self.text_color.as_ref() {
+            Some((text, outline)) => props.with_text_color(text, outline),
+            None => props,
+        }
     }
 
     /// Activates the tab.
@@ -137,6 +163,18 @@ impl TabProps {
         self
     }
 
+    /// Sets whether the tab is for the catch-all line, which has a dashed outline.
+    pub fn set_catch_all(mut self, is_catch_all: bool) -> Self {
+        self.catch_all = is_catch_all;
+        self
+    }
+
+    /// Sets the action triggered by right-clicking the tab.
+    pub fn with_context_menu(mut self, action: OnClickAction) -> Self {
+        self.context_menu = Some(action);
+        self
+    }
+
     /// Sets whether the tab is reverse-order.
     pub fn set_rev(mut self) -> Self {
         self.rev = true;
@@ -237,9 +275,23 @@ pub fn style(props: &TabProps) -> String {
             props.edited,
             italic,
         ),
+
+        if(
+            props.catch_all,
+            outline(dashed),
+        ),
     )
 }
 
+/// Tooltip of the button moving a filter left.
+///
+/// Allocations go to the first filter they match, so the order of the filters is their priority.
+const MOVE_LEFT_TITLE: &str =
+    "raise priority: allocations matching several filters go to the left-most one";
+/// Tooltip of the button moving a filter right.
+const MOVE_RIGHT_TITLE: &str =
+    "lower priority: allocations matching several filters go to the left-most one";
+
 /// A list of tabs.
 pub struct Tabs {
     /// The list of tabs.
@@ -269,9 +321,9 @@ impl Tabs {
     ) {
         let edited = props.edited;
         let mut res = if edited {
-            Self::raw_tab(&props, onclick, format!("*{}*", text))
+            Self::raw_tab(&props, onclick, format!("*{}*", text), None)
         } else {
-            Self::raw_tab(&props, onclick, text)
+            Self::raw_tab(&props, onclick, text, None)
         };
 
         if let IsActive::YesWith {
@@ -288,7 +340,8 @@ impl Tabs {
                             model.link.callback(
                                 move |_| msg::filter::Msg::move_filter(uid, true)
                             ),
-                            "<"
+                            "<",
+                            Some(MOVE_LEFT_TITLE),
                         )}
                         {res}
                     </>
@@ -303,7 +356,8 @@ impl Tabs {
                             model.link.callback(
                                 move |_| msg::filter::Msg::move_filter(uid, false)
                             ),
-                            ">"
+                            ">",
+                            Some(MOVE_RIGHT_TITLE),
                         )}
                     </>
                 }
@@ -313,26 +367,52 @@ impl Tabs {
         self.tabs.push(res)
     }
 
-    /// Displays a raw tab.
-    fn raw_tab(props: &TabProps, onclick: OnClickAction, content: impl fmt::Display) -> Html {
-        html! {
+    /// Displays a raw tab, with an optional tooltip.
+    fn raw_tab(
+        props: &TabProps,
+        onclick: OnClickAction,
+        content: impl fmt::Display,
+        title: Option<&str>,
+    ) -> Html {
+        let tab = html! {
             <div
-                id = "filter_tab_cell"
-                style = OUTTER_CELL_STYLE
+                id = "filter_tab"
+                style = style(props)
             >
+                {layout::button::text::render(
+                    Some(props.to_box_props()),
+                    "filter_content",
+                    content,
+                    Some(onclick),
+                    props.dimmed,
+                )}
+            </div>
+        };
+        if let Some(action) = props.context_menu.clone() {
+            let oncontextmenu = Callback::from(move |event: yew::events::MouseEvent| {
+                event.prevent_default();
+                action.emit(event)
+            });
+            html! {
                 <div
-                    id = "filter_tab"
-                    style = style(props)
+                    id = "filter_tab_cell"
+                    style = OUTTER_CELL_STYLE
+                    title = title.unwrap_or("")
+                    oncontextmenu = oncontextmenu
                 >
-                    {layout::button::text::render(
-                        Some(props.to_box_props()),
-                        "filter_content",
-                        content,
-                        Some(onclick),
-                        props.dimmed,
-                    )}
+                    {tab}
                 </div>
-            </div>
+            }
+        } else {
+            html! {
+                <div
+                    id = "filter_tab_cell"
+                    style = OUTTER_CELL_STYLE
+                    title = title.unwrap_or("")
+                >
+                    {tab}
+                </div>
+            }
         }
     }
 
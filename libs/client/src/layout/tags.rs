@@ -0,0 +1,91 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Tags rendering.
+//!
+//! Tags are labels users add to allocations from the UI, see [`charts::tag`]. Filters select tagged
+//! allocations with a `tag` sub-filter.
+//!
+//! [`charts::tag`]: ../../../charts/tag/index.html (The charts::tag module)
+
+prelude! {}
+
+use charts::tag::Target;
+
+/// Callback asking the user for a tag, and tagging the allocations of a target with it.
+pub fn tag_callback(model: &Model, target: Target) -> OnClickAction {
+    model.link.callback(move |_| {
+        match js::prompt(&format!("tag the allocations of {} as", target), "") {
+            Some(tag) => match charts::tag::check(&tag) {
+                Ok(tag) => msg::to_server::Msg::tag(tag, target.clone()).into(),
+                Err(e) => msg::Msg::err(e),
+            },
+            None => msg::Msg::Noop,
+        }
+    })
+}
+
+/// Renders the tags in the menu part of the footer.
+pub fn render(model: &Model) -> Html {
+    let read_only = model.is_read_only();
+
+    html! {
+        <>
+            <br/>
+            {layout::section_title("Tags")}
+            <br/>
+            {
+                if model.tags.is_empty() {
+                    html! {
+                        "no tags, tag the allocations of a filter or of an explanation's \
+                        allocation site to create one"
+                    }
+                } else {
+                    html! {
+                        <table id = "tags_table">
+                            {for model.tags.iter().enumerate().map(|(idx, (tag, count))| {
+                                let tag = tag.clone();
+                                html! {
+                                    <tr>
+                                        <td>{&tag}</td>
+                                        <td>{format!("{} allocation(s)", count)}</td>
+                                        <td>{
+                                            if read_only {
+                                                html! {}
+                                            } else {
+                                                layout::button::text::render_default_button(
+                                                    format!("tags_remove_{}", idx),
+                                                    "remove",
+                                                    Some(model.link.callback(move |_| {
+                                                        msg::to_server::Msg::untag(tag.clone())
+                                                    })),
+                                                    false,
+                                                )
+                                            }
+                                        }</td>
+                                    </tr>
+                                }
+                            })}
+                        </table>
+                    }
+                }
+            }
+        </>
+    }
+}
@@ -32,9 +32,12 @@ pub mod js;
 pub mod layout;
 
 pub mod model;
+pub mod notes;
 pub mod settings;
+pub mod toast;
 
 pub mod chart;
+pub mod crash;
 pub mod cst;
 pub mod filter;
 pub mod msg;
@@ -49,5 +52,6 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 #[wasm_bindgen(start)]
 pub fn main() {
     wasm_logger::init(wasm_logger::Config::default());
+    crash::install();
     yew::start_app::<Model>();
 }
@@ -559,10 +559,32 @@ macro_rules! css {
     (@($str:expr) top) => (
         write!($str, "top: 0;");
     );
+    (@($str:expr) top($val:tt $unit:tt)) => {{
+        write!($str, "top: ");
+        write!($str, "{}", $crate::css!(@arg $val));
+        write!($str, concat!(stringify!($unit), "; "));
+    }};
+    // #right
+    (@($str:expr) right($val:tt $unit:tt)) => {{
+        write!($str, "right: ");
+        write!($str, "{}", $crate::css!(@arg $val));
+        write!($str, concat!(stringify!($unit), "; "));
+    }};
     // #bottom
     (@($str:expr) bottom) => (
         write!($str, "bottom: 0;");
     );
+    (@($str:expr) bottom($val:tt $unit:tt)) => {{
+        write!($str, "bottom: ");
+        write!($str, "{}", $crate::css!(@arg $val));
+        write!($str, concat!(stringify!($unit), "; "));
+    }};
+    // #left
+    (@($str:expr) left($val:tt $unit:tt)) => {{
+        write!($str, "left: ");
+        write!($str, "{}", $crate::css!(@arg $val));
+        write!($str, concat!(stringify!($unit), "; "));
+    }};
 
     // #vertical_align
     (@($str:expr) vertical_align($pos:tt)) => (
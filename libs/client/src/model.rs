@@ -27,8 +27,12 @@ pub struct Model {
     pub link: Link,
     /// Socket task for receiving/sending messages from/to the server.
     pub socket_task: Option<WebSocketTask>,
-    /// Errors.
-    pub errors: Vec<err::Error>,
+    /// Keyboard listener for the shortcuts, see [`key_down`].
+    ///
+    /// [`key_down`]: #method.key_down (The key_down method)
+    pub key_listener: Option<KeyListenerHandle>,
+    /// Toast notifications, for errors, warnings and server alerts.
+    pub toasts: toast::Toasts,
     /// Collection of charts.
     pub charts: Charts,
 
@@ -42,11 +46,35 @@ pub struct Model {
 
     /// If not `None`, then the server is currently loading the dumps.
     pub progress: Option<LoadInfo>,
+    /// If not `None`, then the server is currently generating filters.
+    pub gen_progress: Option<GenProgress>,
     /// Allocation statistics, for the header.
     pub alloc_stats: Option<AllocStats>,
 
     /// Global chart settings.
     pub settings: settings::Settings,
+    /// Notes shared with the other clients.
+    pub notes: notes::Notes,
+    /// Tags of the server and the number of allocations they tag.
+    pub tags: charts::tag::Tags,
+    /// Last snapshot diff from the server, if any.
+    pub snapshot_diff: Option<charts::snapshot::SnapshotDiff>,
+    /// Last page of the allocation table from the server, if any.
+    pub alloc_table: Option<charts::alloc_table::AllocTable>,
+    /// Information about the run the trace comes from, if any.
+    pub trace_info: Option<alloc::TraceInfo>,
+    /// Status of the session file of the server, `None` if the server does not persist sessions.
+    pub session: Option<charts::session::Status>,
+
+    /// True if the server told us the session is read-only.
+    read_only: bool,
+    /// Limits of the server over the number of filters and charts.
+    limits: charts::limits::Limits,
+
+    /// View state from the URL, restored once the server has sent the charts.
+    view_to_restore: Option<ViewState>,
+    /// Last view state written in the URL, and when.
+    view_in_url: (ViewState, Option<f64>),
 }
 
 impl Model {
@@ -54,6 +82,30 @@ impl Model {
     pub fn filters(&self) -> filter::Reference {
         self.filters.reference()
     }
+    /// True if the session is read-only, *i.e.* filters and charts cannot be edited.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+    /// Limits of the server over the number of filters and charts.
+    pub fn limits(&self) -> charts::limits::Limits {
+        self.limits
+    }
+    /// Reason why the server would reject a message because of its limits, if any.
+    ///
+    /// Filters are checked against the client-side filters, which include the unsaved ones.
+    pub fn reject_over_limit(
+        &self,
+        to_server: &msg::to_server::Msg,
+    ) -> Option<msg::from_server::Rejection> {
+        use msg::to_server::{ChartsMsg, Msg};
+        match to_server {
+            Msg::Filters(filters_msg) => filters_msg
+                .filter_count(self.filters.current().filters.len())
+                .and_then(|count| self.limits.check_filters(count)),
+            Msg::Charts(ChartsMsg::New(..)) => self.limits.check_charts(self.charts.len() + 1),
+            _ => None,
+        }
+    }
     /// Client-side filters accessor.
     pub fn footer_filters(&self) -> filter::Current {
         self.filters.current()
@@ -77,8 +129,7 @@ impl Model {
     /// Activates the websocket to receive data from the server.
     fn activate_ws(link: &mut Link) -> Res<WebSocketTask> {
         log::info!("fetching server's websocket info");
-        let (addr, port) = js::server::address()?;
-        let addr = format!("ws://{}:{}", addr, port + 1);
+        let addr = js::server::ws_address(js::server::read_only_requested()?)?;
         log::info!("websocket: {:?}", addr);
         let callback = link.callback(|msg| Msg::FromServer(msg));
         let notification = link.callback(|status| Msg::ConnectionStatus(status));
@@ -86,6 +137,154 @@ impl Model {
         log::info!("connection established successfully");
         Ok(task)
     }
+
+    /// Listens to the keyboard for the shortcuts.
+    fn activate_keyboard(link: &Link) -> Res<KeyListenerHandle> {
+        let window = web_sys::window().ok_or("could not retrieve window")?;
+        let callback = link.callback(Msg::KeyDown);
+        Ok(KeyboardService::register_key_down(&window, callback))
+    }
+
+    /// Handles keyboard shortcuts.
+    ///
+    /// While a filter tab is active, `ctrl+z` undoes the last filter edit and `ctrl+shift+z`
+    /// redoes it.
+    fn key_down(&mut self, event: web_sys::KeyboardEvent) -> ShouldRender {
+        let on_filters = self
+            .footer
+            .active
+            .and_then(footer::FooterTab::get_filter)
+            .is_some();
+        let ctrl = event.ctrl_key() || event.meta_key();
+        if on_filters && ctrl && event.key().to_lowercase() == "z" {
+            // Filter edits are not text edits, do not let the browser undo anything else.
+            event.prevent_default();
+            self.link.send_message(if event.shift_key() {
+                msg::filter::Msg::redo()
+            } else {
+                msg::filter::Msg::undo()
+            })
+        }
+        false
+    }
+}
+
+/// # View state and URL handling
+impl Model {
+    /// Minimal time between two URL updates, in milliseconds.
+    const URL_UPDATE_SPAN_MS: f64 = 1_000.;
+
+    /// Current view state.
+    pub fn view_state(&self) -> ViewState {
+        ViewState {
+            tab: self.footer.active.and_then(footer::FooterTab::get_filter),
+            chart: self.charts.focused(),
+            time_windopt: self.settings.time_windopt().clone(),
+            filters: None,
+        }
+    }
+
+    /// Restores the view state from the URL, if needed and if the server sent the charts.
+    ///
+    /// References to filters or charts that do not exist anymore are ignored. Shared filters open
+    /// the import area, on the tab of the first filter if no tab is open, unless the session is
+    /// read-only.
+    fn try_restore_view(&mut self) -> Res<ShouldRender> {
+        if self.charts.len() == 0 {
+            return Ok(false);
+        }
+        let state = if let Some(state) = self.view_to_restore.take() {
+            state
+        } else {
+            return Ok(false);
+        };
+        let filters = self.filters.reference();
+        let charts = &self.charts;
+        let state = state.restrict(
+            |line| filters.specs_iter().any(|spec| spec.uid() == line),
+            |chart| charts.contains(chart),
+        );
+        log::info!("restoring view state {:?}", state.to_fragment());
+
+        let mut should_render = false;
+        if let Some(tab) = state.tab {
+            self.footer.open(footer::FooterTab::filter(tab));
+            should_render = true
+        }
+        if let Some(chart) = state.chart {
+            should_render = self.charts.focus(chart)? || should_render
+        }
+        if let Some(shared) = state.filters.as_ref() {
+            if self.read_only {
+                self.toasts
+                    .warn("ignoring the shared filters of the link, the session is read-only")
+            } else {
+                self.filters.offer_shared(shared);
+                if self.footer.active.is_none() {
+                    let tab = self
+                        .filters
+                        .reference()
+                        .filters
+                        .first()
+                        .map(|filter| uid::Line::Filter(filter.uid()))
+                        .unwrap_or(uid::Line::Everything);
+                    self.footer.open(footer::FooterTab::filter(tab))
+                }
+            }
+            should_render = true
+        }
+        if &state.time_windopt != self.settings.time_windopt() {
+            let Range { lbound, ubound } = state.time_windopt;
+            self.link.send_message(settings::Msg::TimeWindowLb(lbound));
+            self.link.send_message(settings::Msg::TimeWindowUb(ubound));
+            self.link.send_message(settings::Msg::Save);
+        }
+        Ok(should_render)
+    }
+
+    /// Writes the view state in the URL, if it changed.
+    ///
+    /// Throttled: does nothing if the URL was updated less than `URL_UPDATE_SPAN_MS` ago, the
+    /// update will happen on a later call.
+    fn update_url(&mut self) -> Res<()> {
+        // Do not overwrite the URL before it has been restored.
+        if self.view_to_restore.is_some() {
+            return Ok(());
+        }
+        let state = self.view_state();
+        if state == self.view_in_url.0 {
+            return Ok(());
+        }
+        let now = js::url::now_ms();
+        if let (Some(now), Some(last)) = (now, self.view_in_url.1) {
+            if now - last < Self::URL_UPDATE_SPAN_MS {
+                return Ok(());
+            }
+        }
+        js::url::replace_fragment(&state.to_fragment())?;
+        self.view_in_url = (state, now);
+        Ok(())
+    }
+}
+
+/// # Notes
+impl Model {
+    /// Goes to the chart or filter a link of the notes points to.
+    ///
+    /// Opens the settings of a chart, or the footer tab of a filter.
+    fn follow_link(&mut self, link: charts::notes::Link) -> Res<ShouldRender> {
+        use charts::notes::LinkKind;
+        let (uid, _) = notes::Notes::resolve(&link, &self.charts, self.filters.reference())
+            .ok_or_else(|| format!("{} does not point to anything", link))?;
+        match link.kind {
+            LinkKind::Chart => self.charts.focus(uid::Chart::from(uid)),
+            LinkKind::Filter => {
+                let tab = footer::FooterTab::filter(uid::Line::Filter(uid::Filter::from(uid)));
+                self.footer.open(tab);
+                Ok(true)
+            }
+        }
+    }
 }
 
 /// # Communication with the server
@@ -105,14 +304,22 @@ impl Model {
         let msg = msg?;
         log::info!("received message from server: {}", msg);
         match msg {
-            Msg::Info => Ok(false),
             Msg::Alert { msg, fatal } => {
-                alert!("{}{}", if fatal { "[fatal] " } else { "" }, msg);
-                Ok(false)
+                let severity = if fatal {
+                    toast::Severity::Error
+                } else {
+                    toast::Severity::Warning
+                };
+                self.toasts.push(severity, msg, None);
+                Ok(true)
             }
             Msg::Charts(msg) => {
-                self.charts
-                    .server_update(self.filters.reference(), self.filters.ref_stats(), msg)
+                let should_render = self.charts.server_update(
+                    self.filters.reference(),
+                    self.filters.ref_stats(),
+                    msg,
+                )?;
+                Ok(self.try_restore_view()? || should_render)
             }
             Msg::Filters(msg) => self.filters.server_update(msg),
 
@@ -123,7 +330,38 @@ impl Model {
                     .map(|s| s != &stats)
                     .unwrap_or(true);
                 self.settings.set_run_duration(stats.duration);
+                self.charts.set_estimate(stats.estimate.is_some());
+                let bounded = self.settings.time_windopt().ubound.is_some();
+                let skewed = self.charts.check_sync(&stats, bounded);
                 self.alloc_stats = Some(stats);
+                Ok(redraw || skewed)
+            }
+            Msg::Explanation(explanation) => {
+                self.settings.set_explanation(explanation);
+                Ok(true)
+            }
+            Msg::SnapshotDiff(diff) => {
+                self.snapshot_diff = Some(diff);
+                Ok(true)
+            }
+            Msg::AllocTable(table) => {
+                self.alloc_table = Some(table);
+                Ok(true)
+            }
+            Msg::Notes { notes, conflict } => self.notes.server_update(notes, conflict),
+            Msg::Tags(tags) => {
+                let redraw = self.tags != tags;
+                self.tags = tags;
+                Ok(redraw)
+            }
+            Msg::TraceInfo(info) => {
+                let redraw = self.trace_info != info;
+                self.trace_info = info;
+                Ok(redraw)
+            }
+            Msg::Session(status) => {
+                let redraw = self.session != status;
+                self.session = status;
                 Ok(redraw)
             }
             Msg::FilterStats(stats) => {
@@ -137,9 +375,37 @@ impl Model {
                 self.progress = Some(info);
                 Ok(redraw)
             }
+            Msg::GenProgress(progress) => {
+                let redraw = self.progress.is_some()
+                    || self
+                        .gen_progress
+                        .as_ref()
+                        .map(|p| p != &progress)
+                        .unwrap_or(true);
+                self.progress = None;
+                self.gen_progress = Some(progress);
+                Ok(redraw)
+            }
+            Msg::ReadOnly => {
+                log::info!("session is read-only");
+                let redraw = !self.read_only;
+                self.read_only = true;
+                Ok(redraw)
+            }
+            Msg::Limits(limits) => {
+                let redraw = self.limits != limits;
+                self.limits = limits;
+                Ok(redraw)
+            }
+            Msg::Rejected { msg, reason } => {
+                self.toasts
+                    .warn(format!("the server rejected {}: {}", msg, reason));
+                Ok(true)
+            }
             Msg::DoneLoading => {
-                let redraw = self.progress.is_some();
+                let redraw = self.progress.is_some() || self.gen_progress.is_some();
                 self.progress = None;
+                self.gen_progress = None;
                 Ok(redraw)
             }
         }
@@ -163,18 +429,38 @@ impl Component for Model {
     type Properties = ();
 
     fn create(_: Self::Properties, mut link: Link) -> Self {
-        let (socket_task, errors) = match Self::activate_ws(&mut link) {
-            Ok(res) => (Some(res), vec![]),
-            Err(e) => (None, vec![e]),
+        let mut toasts = toast::Toasts::new(link.clone());
+        let socket_task = match Self::activate_ws(&mut link) {
+            Ok(res) => Some(res),
+            Err(e) => {
+                toasts.error(&e);
+                None
+            }
+        };
+        let key_listener = match Self::activate_keyboard(&link) {
+            Ok(listener) => Some(listener),
+            Err(e) => {
+                log::warn!("keyboard shortcuts are unavailable: {}", e);
+                None
+            }
         };
         let charts = Charts::new(link.clone());
         let filters = filter::FilterInfo::new(link.clone());
         let settings = settings::Settings::new(link.clone());
+        let notes = notes::Notes::new(link.clone());
         let header = header::Header::new(link.clone());
+        let view_to_restore = match js::url::fragment() {
+            Ok(fragment) => Some(ViewState::from_fragment(&fragment)).filter(|s| !s.is_default()),
+            Err(e) => {
+                log::warn!("failed to retrieve URL fragment: {}", e);
+                None
+            }
+        };
         Model {
             link,
             socket_task,
-            errors,
+            key_listener,
+            toasts,
             charts,
 
             filters,
@@ -183,48 +469,112 @@ impl Component for Model {
             header,
 
             progress: Some(LoadInfo::unknown()),
+            gen_progress: None,
             alloc_stats: None,
             settings,
+            notes,
+            tags: charts::tag::Tags::new(),
+            snapshot_diff: None,
+            alloc_table: None,
+            trace_info: None,
+            session: None,
+
+            read_only: false,
+            limits: charts::limits::Limits::default(),
+
+            view_to_restore,
+            view_in_url: (ViewState::default(), None),
         }
     }
 
     fn update(&mut self, msg: Msg) -> ShouldRender {
         log::debug!("handling message {}", msg);
-        match msg {
+        crash::record(&msg);
+        let should_render = match msg {
             // Messages to/from the server.
             Msg::FromServer(msg) => {
                 let msg: Res<charts::msg::to_client::Msg> = msg.into();
                 unwrap_or_send_err!(self.handle_server_msg(msg) => self default false)
             }
             Msg::ToServer(msg) => {
-                log::info!("propagating message to server {}", msg);
-                self.server_send(msg);
-                false
+                if self.read_only && msg.is_mutating() {
+                    log::warn!("ignoring mutating message in read-only session: {}", msg);
+                    false
+                } else if let Some(reason) = self.reject_over_limit(&msg) {
+                    self.toasts
+                        .warn(format!("cannot send {} to the server: {}", msg, reason));
+                    true
+                } else {
+                    log::info!("propagating message to server {}", msg);
+                    self.server_send(msg);
+                    false
+                }
             }
 
             // Dealing with status changes in the connection with the server.
             Msg::ConnectionStatus(status) => {
                 use WebSocketStatus::*;
                 match status {
-                    Opened => log::debug!("successfully established connection with the server"),
-                    Closed => log::warn!("connection with the server was closed"),
-                    Error => alert!("failed to connect with the server"),
+                    Opened => {
+                        log::debug!("successfully established connection with the server");
+                        false
+                    }
+                    Closed => {
+                        log::warn!("connection with the server was closed");
+                        false
+                    }
+                    Error => {
+                        self.toasts.push(
+                            toast::Severity::Error,
+                            "failed to connect with the server",
+                            None,
+                        );
+                        true
+                    }
                 }
-                false
             }
 
             // Internal operations.
             Msg::Charts(msg) => unwrap_or_send_err!(
-                self.charts.update(self.filters.reference(), msg) => self default false
+                self.charts.update(
+                    self.filters.reference(),
+                    self.alloc_stats.as_ref().map(|stats| &stats.start_date),
+                    msg,
+                ) => self default false
             ),
-            Msg::Footer(msg) => unwrap_or_send_err!(
-                self.footer.update(msg) => self default false
+            Msg::Footer(msg) => {
+                let should_render = unwrap_or_send_err!(
+                    self.footer.update(msg) => self default false
+                );
+                if let Some(request) = self.footer.take_alloc_request() {
+                    self.link
+                        .send_message(msg::to_server::ChartsMsg::alloc_table(request))
+                }
+                should_render
+            }
+            // Exporting does not change the filters.
+            Msg::Filter(msg @ filter::Msg::Export) => unwrap_or_send_err!(
+                self.filters.update(msg) => self default false
             ),
+            Msg::Filter(msg) if self.read_only => {
+                log::warn!("ignoring filter edition in read-only session: {}", msg);
+                false
+            }
             Msg::Filter(msg) => unwrap_or_send_err!(
                 self.filters.update(msg) => self default false
             ),
-            Msg::Settings(msg) => unwrap_or_send_err!(
-                self.settings.update(msg) => self default false
+            Msg::Settings(msg) => {
+                let should_render = unwrap_or_send_err!(
+                    self.settings.update(msg) => self default false
+                );
+                self.charts.set_accessible(self.settings.is_accessible());
+                should_render
+            }
+            Msg::Notes(notes::Msg::Follow(link)) => unwrap_or_send_err!(
+                self.follow_link(link) => self default false
+            ),
+            Msg::Notes(msg) => unwrap_or_send_err!(
+                self.notes.update(msg, &self.charts, self.filters.reference()) => self default false
             ),
 
             // Basic communication messages.
@@ -232,17 +582,31 @@ impl Component for Model {
                 log::debug!("{}", s);
                 false
             }
+            Msg::Toast(msg) => self.toasts.update(msg),
+            Msg::Info(s) => {
+                log::info!("{}", s);
+                self.toasts.info(s);
+                true
+            }
             Msg::Warn(s) => {
                 log::warn!("{}", s);
-                false
+                self.toasts.warn(s);
+                true
             }
             Msg::Err(e) => {
-                alert!("{}", e.to_pretty());
+                log::error!("{}", e.to_pretty());
+                self.toasts.error(&e);
                 true
             }
 
+            Msg::KeyDown(event) => self.key_down(event),
+
             Msg::Noop => false,
+        };
+        if let Err(e) = self.update_url() {
+            log::warn!("failed to update URL: {}", e)
         }
+        should_render
     }
     fn view(&self) -> Html {
         layout::render(self)
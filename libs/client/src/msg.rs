@@ -25,12 +25,16 @@ pub use charts::msg::{to_client as from_server, to_server, ChartSettingsMsg};
 
 /// Filter messages.
 pub mod filter {
-    pub use crate::filter::{FilterMsg, Msg, SpecMsg};
+    pub use crate::filter::{FilterMsg, ImportMsg, Msg, SpecMsg};
 }
 /// Settings messages.
 pub mod settings {
     pub use crate::settings::Msg;
 }
+/// Notes messages.
+pub mod notes {
+    pub use crate::notes::Msg;
+}
 
 /// Internal model messages.
 ///
@@ -53,9 +57,18 @@ pub enum Msg {
     Filter(filter::Msg),
     /// Settings operations.
     Settings(settings::Msg),
+    /// Notes operations.
+    Notes(notes::Msg),
+    /// Toast notification operations.
+    Toast(toast::Msg),
+
+    /// A key was pressed, for keyboard shortcuts.
+    KeyDown(web_sys::KeyboardEvent),
 
     /// A message to print in the JS console.
     Msg(String),
+    /// An information notification.
+    Info(String),
     /// A warning to print in the JS console.
     Warn(String),
     /// An error.
@@ -70,6 +83,10 @@ impl Msg {
     pub fn msg<S: Into<String>>(txt: S) -> Msg {
         Self::Msg(txt.into())
     }
+    /// Information notification constructor.
+    pub fn info<S: Into<String>>(txt: S) -> Msg {
+        Self::Info(txt.into())
+    }
     /// Warning message constructor.
     pub fn warn<S: Into<String>>(txt: S) -> Msg {
         Self::Warn(txt.into())
@@ -99,16 +116,54 @@ pub enum ChartsMsg {
         msg: ChartMsg,
     },
 
+    /// Sorts the charts by decreasing value of the last point of their everything line.
+    ///
+    /// Charts without such a value come last, in their current order.
+    SortByValue,
+    /// Shows or hides all the charts.
+    SetAllVisible(bool),
+
+    /// Zooms the time charts on a time window, or resets their zoom if `None`.
+    ///
+    /// If chart `uid` has a linked zoom, all the charts with a linked zoom zoom on the window.
+    /// Otherwise, only chart `uid` does.
+    ZoomSync {
+        /// UID of the chart the zoom comes from.
+        uid: uid::Chart,
+        /// Time window to zoom on.
+        window: Option<TimeWindow>,
+    },
+    /// Resets the zoom of all the charts, linked or not.
+    ResetZoom,
+    /// Links or unlinks the zoom of a chart with the zoom of the other charts.
+    ToggleZoomLink(uid::Chart),
+
     /// Destroys a chart.
     Destroy(uid::Chart),
 
+    /// Exports a chart as a standalone HTML page.
+    Export(uid::Chart),
+    /// Exports the points of a chart as CSV.
+    ExportCsv(uid::Chart),
+
     /// Forces to refresh the filters.
     RefreshFilters,
 
+    /// Asks the server to reload the charts that are out of sync.
+    Resync,
+
     /// Sets the x-axis in the new chart element.
     NewChartSetX(chart::axis::XAxis),
     /// Sets the y-axis in the new chart element.
     NewChartSetY(chart::axis::YAxis),
+    /// Opens or closes the chart creation dialog, closing it discards its state.
+    NewChartOpen(bool),
+    /// Sets the title in the new chart element.
+    NewChartSetTitle(String),
+    /// Toggles the initial visibility of a line in the new chart element.
+    NewChartToggleLine(uid::Line),
+    /// Asks the server to create the chart of the new chart element.
+    NewChartSubmit,
 }
 impl ChartsMsg {
     /// Constructs a message to move a chart up.
@@ -119,16 +174,53 @@ impl ChartsMsg {
     pub fn move_down(uid: uid::Chart) -> Msg {
         Self::Move { uid, up: false }.into()
     }
+    /// Constructs a message to sort the charts by value.
+    pub fn sort_by_value() -> Msg {
+        Self::SortByValue.into()
+    }
+    /// Constructs a message to hide all the charts.
+    pub fn collapse_all() -> Msg {
+        Self::SetAllVisible(false).into()
+    }
+    /// Constructs a message to show all the charts.
+    pub fn expand_all() -> Msg {
+        Self::SetAllVisible(true).into()
+    }
+    /// Constructs a message zooming the charts on a time window, or resetting their zoom.
+    pub fn zoom_sync(uid: uid::Chart, window: Option<TimeWindow>) -> Msg {
+        Self::ZoomSync { uid, window }.into()
+    }
+    /// Constructs a message to reset the zoom of all the charts.
+    pub fn reset_zoom() -> Msg {
+        Self::ResetZoom.into()
+    }
+    /// Constructs a message to link or unlink the zoom of a chart.
+    pub fn toggle_zoom_link(uid: uid::Chart) -> Msg {
+        Self::ToggleZoomLink(uid).into()
+    }
     /// Constructs a message to destroy a chart.
     pub fn destroy(uid: uid::Chart) -> Msg {
         Self::Destroy(uid).into()
     }
+    /// Constructs a message to export a chart as a standalone HTML page.
+    pub fn export(uid: uid::Chart) -> Msg {
+        Self::Export(uid).into()
+    }
+    /// Constructs a message to export the points of a chart as CSV.
+    pub fn export_csv(uid: uid::Chart) -> Msg {
+        Self::ExportCsv(uid).into()
+    }
 
     /// Forces to refresh all the filters.
     pub fn refresh_filters() -> Msg {
         Self::RefreshFilters.into()
     }
 
+    /// Asks the server to reload the charts that are out of sync.
+    pub fn resync() -> Msg {
+        Self::Resync.into()
+    }
+
     /// Sets the x-axis in the new chart element.
     pub fn new_chart_set_x(x: chart::axis::XAxis) -> Msg {
         Self::NewChartSetX(x).into()
@@ -137,6 +229,26 @@ impl ChartsMsg {
     pub fn new_chart_set_y(y: chart::axis::YAxis) -> Msg {
         Self::NewChartSetY(y).into()
     }
+    /// Opens the chart creation dialog.
+    pub fn new_chart_open() -> Msg {
+        Self::NewChartOpen(true).into()
+    }
+    /// Closes the chart creation dialog, discarding its state.
+    pub fn new_chart_cancel() -> Msg {
+        Self::NewChartOpen(false).into()
+    }
+    /// Sets the title in the new chart element.
+    pub fn new_chart_set_title(title: String) -> Msg {
+        Self::NewChartSetTitle(title).into()
+    }
+    /// Toggles the initial visibility of a line in the new chart element.
+    pub fn new_chart_toggle_line(line: uid::Line) -> Msg {
+        Self::NewChartToggleLine(line).into()
+    }
+    /// Asks the server to create the chart of the new chart element.
+    pub fn new_chart_submit() -> Msg {
+        Self::NewChartSubmit.into()
+    }
 }
 
 /// A message for a specific chart.
@@ -148,6 +260,32 @@ pub enum ChartMsg {
     FilterToggleVisible(uid::Line),
     /// Updates the chart's settings.
     SettingsUpdate(ChartSettingsMsg),
+    /// The canvas of the chart was resized.
+    Resized,
+    /// The chart was disabled by an error, renders its error badge.
+    Failed,
+    /// The mouse moved over the canvas of a scatter chart, at some pixel position.
+    ///
+    /// `None` if the mouse left the canvas.
+    Hover(Option<(i32, i32)>),
+    /// Pins the details of the point of a scatter chart at some pixel position, if any.
+    Pin(i32, i32),
+    /// Unpins the details of a point of a scatter chart.
+    Unpin,
+    /// The mouse button went down over the canvas of a time chart, at some x pixel position.
+    ///
+    /// Starts the selection of a time window to zoom on.
+    ZoomStart(i32),
+    /// The mouse button went up over the canvas of a time chart, at some x pixel position.
+    ///
+    /// Ends the selection of a time window to zoom on, see [`ChartsMsg::ZoomSync`].
+    ///
+    /// [`ChartsMsg::ZoomSync`]: enum.ChartsMsg.html#variant.ZoomSync (The ZoomSync message)
+    ZoomEnd(i32),
+    /// Starts or stops editing the title of the chart.
+    EditTitle(bool),
+    /// Sets the custom title of the chart, an empty title reverts to the default title.
+    Rename(String),
 }
 
 impl ChartMsg {
@@ -159,6 +297,42 @@ impl ChartMsg {
     pub fn filter_toggle_visible(uid: uid::Chart, line: uid::Line) -> ChartsMsg {
         (uid, Self::FilterToggleVisible(line)).into()
     }
+    /// The canvas of a chart was resized.
+    pub fn resized(uid: uid::Chart) -> ChartsMsg {
+        (uid, Self::Resized).into()
+    }
+    /// A chart was disabled by an error.
+    pub fn failed(uid: uid::Chart) -> ChartsMsg {
+        (uid, Self::Failed).into()
+    }
+    /// The mouse moved over the canvas of a scatter chart.
+    pub fn hover(uid: uid::Chart, pos: Option<(i32, i32)>) -> ChartsMsg {
+        (uid, Self::Hover(pos)).into()
+    }
+    /// Pins the details of the point of a scatter chart at some position.
+    pub fn pin(uid: uid::Chart, x: i32, y: i32) -> ChartsMsg {
+        (uid, Self::Pin(x, y)).into()
+    }
+    /// Unpins the details of a point of a scatter chart.
+    pub fn unpin(uid: uid::Chart) -> ChartsMsg {
+        (uid, Self::Unpin).into()
+    }
+    /// Starts the selection of a time window to zoom on.
+    pub fn zoom_start(uid: uid::Chart, x: i32) -> ChartsMsg {
+        (uid, Self::ZoomStart(x)).into()
+    }
+    /// Ends the selection of a time window to zoom on.
+    pub fn zoom_end(uid: uid::Chart, x: i32) -> ChartsMsg {
+        (uid, Self::ZoomEnd(x)).into()
+    }
+    /// Starts or stops editing the title of a chart.
+    pub fn edit_title(uid: uid::Chart, editing: bool) -> ChartsMsg {
+        (uid, Self::EditTitle(editing)).into()
+    }
+    /// Sets the custom title of a chart.
+    pub fn rename(uid: uid::Chart, title: String) -> ChartsMsg {
+        (uid, Self::Rename(title)).into()
+    }
 }
 
 /// Footer operation.
@@ -166,18 +340,58 @@ impl ChartMsg {
 pub enum FooterMsg {
     /// Toggles a tab.
     ToggleTab(footer::FooterTab),
-    // /// Lets the footer know a filter was removed.
-    // Removed(uid::Filter),
+    /// Changes the search string of the help tab.
+    HelpSearch(String),
+    /// Changes the first instant of the snapshot diff tab.
+    SnapshotFrom(Option<time::SinceStart>),
+    /// Changes the second instant of the snapshot diff tab.
+    SnapshotTo(Option<time::SinceStart>),
+    /// Changes the line of the allocation table tab.
+    AllocLine(uid::Line),
+    /// Sorts the allocation table tab by some column, or flips the direction of the sort.
+    AllocSort(charts::alloc_table::SortBy),
+    /// Changes the page of the allocation table tab.
+    AllocPage(usize),
+    /// Opens the context menu of a filter tab at some horizontal position (pixels), or closes it.
+    TabMenu(Option<(uid::Line, i32)>),
 }
 impl FooterMsg {
     /// Toggles a tab.
     pub fn toggle_tab(tab: impl Into<footer::FooterTab>) -> Msg {
         Self::ToggleTab(tab.into()).into()
     }
-    // /// Lets the footer know a filter was removed.
-    // pub fn removed(uid: uid::Filter) -> Msg {
-    //     Self::Removed(uid).into()
-    // }
+    /// Changes the search string of the help tab.
+    pub fn help_search(search: impl Into<String>) -> Msg {
+        Self::HelpSearch(search.into()).into()
+    }
+    /// Changes the first instant of the snapshot diff tab.
+    pub fn snapshot_from(from: Option<time::SinceStart>) -> Msg {
+        Self::SnapshotFrom(from).into()
+    }
+    /// Changes the second instant of the snapshot diff tab.
+    pub fn snapshot_to(to: Option<time::SinceStart>) -> Msg {
+        Self::SnapshotTo(to).into()
+    }
+    /// Changes the line of the allocation table tab.
+    pub fn alloc_line(line: uid::Line) -> Msg {
+        Self::AllocLine(line).into()
+    }
+    /// Sorts the allocation table tab by some column, or flips the direction of the sort.
+    pub fn alloc_sort(sort_by: charts::alloc_table::SortBy) -> Msg {
+        Self::AllocSort(sort_by).into()
+    }
+    /// Changes the page of the allocation table tab.
+    pub fn alloc_page(page: usize) -> Msg {
+        Self::AllocPage(page).into()
+    }
+    /// Opens the context menu of a filter tab at some horizontal position (pixels).
+    pub fn open_tab_menu(line: uid::Line, left: i32) -> Msg {
+        Self::TabMenu(Some((line, left))).into()
+    }
+    /// Closes the context menu of the filter tabs.
+    pub fn close_tab_menu() -> Msg {
+        Self::TabMenu(None).into()
+    }
 }
 
 base::implement! {
@@ -191,7 +405,11 @@ base::implement! {
                 Self::Footer(footer_msg) => write!(fmt, "footer, {}", footer_msg),
                 Self::Filter(filter_msg) => write!(fmt, "filter, {}", filter_msg),
                 Self::Settings(settings_msg) => write!(fmt, "settings, {}", settings_msg),
+                Self::Notes(notes_msg) => write!(fmt, "notes, {}", notes_msg),
+                Self::Toast(toast_msg) => write!(fmt, "toast, {}", toast_msg),
+                Self::KeyDown(event) => write!(fmt, "key down {:?}", event.key()),
                 Self::Msg(_) => write!(fmt, "info"),
+                Self::Info(_) => write!(fmt, "notification"),
                 Self::Warn(_) => write!(fmt, "warning"),
                 Self::Err(_) => write!(fmt, "error"),
                 Self::Noop => write!(fmt, "noop"),
@@ -209,6 +427,8 @@ base::implement! {
             },
             from FooterMsg => |msg| Self::Footer(msg),
             from settings::Msg => |msg| Self::Settings(msg),
+            from notes::Msg => |msg| Self::Notes(msg),
+            from toast::Msg => |msg| Self::Toast(msg),
         }
     }
 
@@ -216,10 +436,25 @@ base::implement! {
         Display {
             |&self, fmt| match self {
                 Self::Move { uid, up } => write!(fmt, "move {}/{}", uid, up),
+                Self::SortByValue => write!(fmt, "sort by value"),
+                Self::SetAllVisible(visible) => write!(fmt, "set all visible {}", visible),
+                Self::ZoomSync { uid, window: Some(window) } => {
+                    write!(fmt, "zoom sync {} [{}, {}]", uid, window.lbound, window.ubound)
+                }
+                Self::ZoomSync { uid, window: None } => write!(fmt, "zoom sync {} reset", uid),
+                Self::ResetZoom => write!(fmt, "reset zoom"),
+                Self::ToggleZoomLink(c_uid) => write!(fmt, "toggle zoom link {}", c_uid),
                 Self::Destroy(c_uid) => write!(fmt, "destroy {}", c_uid),
+                Self::Export(c_uid) => write!(fmt, "export {}", c_uid),
+                Self::ExportCsv(c_uid) => write!(fmt, "export csv {}", c_uid),
                 Self::RefreshFilters => write!(fmt, "refresh filters"),
+                Self::Resync => write!(fmt, "resync"),
                 Self::NewChartSetX(_) => write!(fmt, "new-chart-set-x"),
                 Self::NewChartSetY(_) => write!(fmt, "new-chart-set-y"),
+                Self::NewChartOpen(open) => write!(fmt, "new-chart-open {}", open),
+                Self::NewChartSetTitle(_) => write!(fmt, "new-chart-set-title"),
+                Self::NewChartToggleLine(line) => write!(fmt, "new-chart-toggle-line {}", line),
+                Self::NewChartSubmit => write!(fmt, "new-chart-submit"),
                 Self::ChartMsg { uid, msg } => write!(fmt, "chart[{}]: {}", uid, msg),
             }
         }
@@ -239,6 +474,16 @@ base::implement! {
                 Self::SettingsToggleVisible => write!(fmt, "settings toggle visible"),
                 Self::FilterToggleVisible(l_uid) => write!(fmt, "filter toggle visible {}", l_uid),
                 Self::SettingsUpdate(msg) => write!(fmt, "{}", msg),
+                Self::Resized => write!(fmt, "resized"),
+                Self::Failed => write!(fmt, "failed"),
+                Self::Hover(Some((x, y))) => write!(fmt, "hover {}x{}", x, y),
+                Self::Hover(None) => write!(fmt, "hover none"),
+                Self::Pin(x, y) => write!(fmt, "pin {}x{}", x, y),
+                Self::Unpin => write!(fmt, "unpin"),
+                Self::ZoomStart(x) => write!(fmt, "zoom start {}", x),
+                Self::ZoomEnd(x) => write!(fmt, "zoom end {}", x),
+                Self::EditTitle(editing) => write!(fmt, "edit title {}", editing),
+                Self::Rename(title) => write!(fmt, "rename {}", title),
             }
         }
 
@@ -251,7 +496,14 @@ base::implement! {
         Display {
             |&self, fmt| match self {
                 Self::ToggleTab(_) => write!(fmt, "toggle tab"),
-                // Self::Removed(f_uid) => write!(fmt, "remove {}", f_uid),
+                Self::HelpSearch(_) => write!(fmt, "help search"),
+                Self::SnapshotFrom(_) => write!(fmt, "snapshot from"),
+                Self::SnapshotTo(_) => write!(fmt, "snapshot to"),
+                Self::AllocLine(line) => write!(fmt, "alloc line {}", line),
+                Self::AllocSort(sort_by) => write!(fmt, "alloc sort {}", sort_by),
+                Self::AllocPage(page) => write!(fmt, "alloc page {}", page),
+                Self::TabMenu(Some((line, _))) => write!(fmt, "open tab menu {}", line),
+                Self::TabMenu(None) => write!(fmt, "close tab menu"),
             }
         }
     }
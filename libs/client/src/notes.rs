@@ -0,0 +1,326 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Notes part of the client.
+//!
+//! See [`charts::notes`] for the syntax of the notes.
+//!
+//! [`charts::notes`]: ../../charts/notes/index.html (The charts::notes module)
+
+prelude! {}
+
+use charts::notes::{self as cnotes, Link as NoteLink, LinkKind};
+
+/// Notes state.
+pub struct Notes {
+    /// Link to the model, to send messages.
+    link: Link,
+    /// Notes on the server.
+    notes: cnotes::Notes,
+    /// Content being edited, if different from the server's.
+    draft: Option<String>,
+    /// Version of the notes the draft is based on.
+    draft_version: u64,
+}
+
+impl Notes {
+    /// Constructor.
+    pub fn new(link: Link) -> Self {
+        Self {
+            link,
+            notes: cnotes::Notes::default(),
+            draft: None,
+            draft_version: 0,
+        }
+    }
+
+    /// Current content of the notes, including the changes not saved yet.
+    pub fn text(&self) -> &str {
+        self.draft.as_ref().unwrap_or(&self.notes.text)
+    }
+
+    /// True if the notes have changes that are not saved.
+    pub fn has_changed(&self) -> bool {
+        self.draft.is_some()
+    }
+
+    /// True if the notes changed on the server since the draft was started.
+    pub fn is_outdated(&self) -> bool {
+        self.has_changed() && self.draft_version != self.notes.version
+    }
+
+    /// Handles notes from the server.
+    pub fn server_update(&mut self, notes: cnotes::Notes, conflict: bool) -> Res<ShouldRender> {
+        if conflict {
+            self.link.send_message(msg::Msg::warn(
+                "the notes were changed by another client since you started editing them, \
+                your version overwrote theirs",
+            ))
+        }
+        if self.draft.as_ref() == Some(&notes.text) {
+            self.draft = None
+        }
+        let redraw = self.notes != notes;
+        self.notes = notes;
+        Ok(redraw)
+    }
+
+    /// Resolves a link, yields the UID and the current name of what it points to.
+    pub fn resolve(
+        link: &NoteLink,
+        charts: &Charts,
+        filters: filter::Reference,
+    ) -> Option<(usize, String)> {
+        match link.kind {
+            LinkKind::Chart => {
+                let uid = link.resolve(
+                    charts
+                        .iter()
+                        .map(|chart| (chart.uid().get(), chart.title())),
+                )?;
+                charts
+                    .iter()
+                    .find(|chart| chart.uid().get() == uid)
+                    .map(|chart| (uid, chart.title().to_string()))
+            }
+            LinkKind::Filter => {
+                let uid = link.resolve(
+                    filters
+                        .filters
+                        .iter()
+                        .map(|filter| (filter.uid().get(), &**filter.name())),
+                )?;
+                filters
+                    .get_filter(uid::Filter::from(uid))
+                    .ok()
+                    .map(|(_, filter)| (uid, filter.name().to_string()))
+            }
+        }
+    }
+
+    /// Applies a notes action.
+    pub fn update(
+        &mut self,
+        msg: Msg,
+        charts: &Charts,
+        filters: filter::Reference,
+    ) -> Res<ShouldRender> {
+        match msg {
+            Msg::Edit(text) => {
+                if text == self.notes.text {
+                    Ok(self.draft.take().is_some())
+                } else {
+                    if self.draft.is_none() {
+                        self.draft_version = self.notes.version
+                    }
+                    self.draft = Some(text);
+                    Ok(true)
+                }
+            }
+            Msg::InsertTime(time) => {
+                let mut text = self.text().to_string();
+                if !text.is_empty() && !text.ends_with(char::is_whitespace) {
+                    text.push(' ')
+                }
+                text.push_str(&format!("@{}s", time.display_millis()));
+                self.update(Msg::Edit(text), charts, filters)
+            }
+            Msg::Revert => Ok(self.draft.take().is_some()),
+            Msg::Save => {
+                if let Some(draft) = self.draft.as_ref() {
+                    let text = cnotes::anchor(draft, |link| Self::resolve(link, charts, filters));
+                    self.link
+                        .send_message(msg::Msg::ToServer(msg::to_server::Msg::set_notes(
+                            text,
+                            self.draft_version,
+                        )));
+                }
+                Ok(false)
+            }
+            Msg::Follow(link) => bail!("the model should follow links, not the notes: {}", link),
+        }
+    }
+
+    /// Renders the notes in the menu part of the footer.
+    pub fn render(&self, model: &Model) -> Html {
+        define_style! {
+            AREA_STYLE = {
+                width(95%),
+                height(30%),
+                border_radius(5 px),
+                margin(none),
+                padding(0%, 1%),
+                border(none),
+                bg({"#3a3a3a"}),
+                fg(orange),
+                font(code),
+            };
+            PREVIEW_STYLE = {
+                width(95%),
+                padding(0%, 1%),
+            };
+            LINK_STYLE = {
+                fg(orange),
+                underline,
+                pointer,
+            };
+            BROKEN_LINK_STYLE = {
+                fg(gray),
+            };
+        }
+
+        let read_only = model.is_read_only();
+        let link = |link: NoteLink| match Self::resolve(&link, &model.charts, model.filters()) {
+            Some((_, name)) => html! {
+                <a
+                    style = LINK_STYLE
+                    title = format!("go to {} `{}`", link.kind.keyword(), name)
+                    onclick = self.link.callback(move |_| msg::Msg::from(Msg::Follow(link.clone())))
+                >
+                    {name}
+                </a>
+            },
+            None => html! {
+                <span
+                    style = BROKEN_LINK_STYLE
+                    title = format!("no {} named `{}`", link.kind.keyword(), link.name)
+                >
+                    {link.to_string()}
+                </span>
+            },
+        };
+        let line = |line: &str| {
+            html! {
+                <>
+                    {for cnotes::chunks(line).into_iter().map(|chunk| match chunk {
+                        cnotes::Chunk::Text(text) => html! { text },
+                        cnotes::Chunk::Link(l) => link(l),
+                    })}
+                </>
+            }
+        };
+
+        let now = model
+            .settings
+            .time_windopt()
+            .ubound
+            .or_else(|| model.alloc_stats.as_ref().map(|stats| stats.duration));
+
+        html! {
+            <>
+                <br/>
+                {layout::section_title(if self.is_outdated() {
+                    "Notes (changed on the server since you started editing)"
+                } else {
+                    "Notes"
+                })}
+                <br/>
+                {
+                    if read_only {
+                        html! {}
+                    } else {
+                        html! {
+                            <>
+                                <textarea
+                                    id = "notes_input"
+                                    style = AREA_STYLE
+                                    value = self.text()
+                                    placeholder = "# title, - item, [chart:Name], [filter:Name]"
+                                    oninput = self.link.callback(
+                                        |data: yew::events::InputData| msg::Msg::from(
+                                            Msg::Edit(data.value)
+                                        )
+                                    )
+                                />
+                                <br/>
+                                {layout::button::text::render_default_button(
+                                    "notes_insert_time",
+                                    "insert time",
+                                    now.map(|time| self.link.callback(
+                                        move |_| msg::Msg::from(Msg::InsertTime(time))
+                                    )),
+                                    now.is_none(),
+                                )}
+                                {layout::button::text::render_default_button(
+                                    "notes_revert",
+                                    "revert",
+                                    if self.has_changed() {
+                                        Some(self.link.callback(|_| msg::Msg::from(Msg::Revert)))
+                                    } else {
+                                        None
+                                    },
+                                    !self.has_changed(),
+                                )}
+                                {layout::button::text::render_default_button(
+                                    "notes_save",
+                                    "save",
+                                    if self.has_changed() {
+                                        Some(self.link.callback(|_| msg::Msg::from(Msg::Save)))
+                                    } else {
+                                        None
+                                    },
+                                    !self.has_changed(),
+                                )}
+                            </>
+                        }
+                    }
+                }
+                <div
+                    id = "notes_preview"
+                    style = PREVIEW_STYLE
+                >
+                    {for cnotes::lines(self.text()).into_iter().map(|(kind, text)| match kind {
+                        cnotes::LineKind::Title => html! { <h3>{line(text)}</h3> },
+                        cnotes::LineKind::Item => html! { <li>{line(text)}</li> },
+                        cnotes::LineKind::Text => html! { <>{line(text)}<br/></> },
+                    })}
+                </div>
+            </>
+        }
+    }
+}
+
+/// Notes messages.
+#[derive(Debug)]
+pub enum Msg {
+    /// Changes the content of the notes.
+    Edit(String),
+    /// Appends a time to the content of the notes.
+    InsertTime(time::SinceStart),
+    /// Discards the changes.
+    Revert,
+    /// Sends the changes to the server.
+    Save,
+    /// Goes to the chart or filter a link points to.
+    Follow(NoteLink),
+}
+
+base::implement! {
+    impl Msg {
+        Display {
+            |&self, fmt| match self {
+                Self::Edit(_) => write!(fmt, "edit"),
+                Self::InsertTime(time) => write!(fmt, "insert time {}", time),
+                Self::Revert => write!(fmt, "revert"),
+                Self::Save => write!(fmt, "save"),
+                Self::Follow(link) => write!(fmt, "follow {}", link),
+            }
+        }
+    }
+}
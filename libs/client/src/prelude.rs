@@ -21,7 +21,11 @@
 pub use yew::{
     html,
     html::ChangeData,
-    services::websocket::{WebSocketService, WebSocketStatus, WebSocketTask},
+    services::{
+        keyboard::{KeyListenerHandle, KeyboardService},
+        timeout::{TimeoutService, TimeoutTask},
+        websocket::{WebSocketService, WebSocketStatus, WebSocketTask},
+    },
     Callback, Component, Renderable, ShouldRender,
 };
 pub use yew_components::Select;
@@ -31,8 +35,10 @@ pub use base::prelude::*;
 pub use charts::{
     palette,
     prelude::{
-        alloc, filter::stats::AllFilterStats, num_fmt, time, Alloc, AllocStats, LoadInfo, Regex,
+        alloc, filter::stats::AllFilterStats, num_fmt, time, Alloc, AllocStats, GenProgress,
+        LoadInfo, Regex, TimeWindopt, TimeWindow,
     },
+    view::ViewState,
 };
 
 /// Re-exports from `plotters`, `plotters_canvas`, and `palette`.
@@ -73,17 +79,17 @@ pub fn msg_of_res(res: Res<Msg>) -> Msg {
 
 /// Re-exports from `charts::point`.
 pub mod point {
-    pub use charts::point::{Point, Points, TimePoints};
+    pub use charts::point::{scatter, Point, Points, Ranges, TimePoints};
 }
 pub use point::Point;
 
 pub use crate::{
     chart::{self, Chart, Charts},
-    cst, filter, js,
+    crash, cst, filter, js,
     layout::{self, footer, header},
     model::Model,
     msg::{self, Msg},
-    settings,
+    notes, settings, toast,
 };
 
 /// Component link to the model, can send messages to the model.
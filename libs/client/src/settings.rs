@@ -115,6 +115,14 @@ pub struct Settings {
 
     /// Global charts settings.
     charts_settings: Memory<charts::chart::settings::Charts>,
+
+    /// Explanation of the time window, if requested.
+    explanation: Option<charts::explain::Explanation>,
+
+    /// True in accessible mode, where lines have patterns on top of their color.
+    ///
+    /// Client-side only, does not change the colors of the filters.
+    accessible: bool,
 }
 
 impl Settings {
@@ -125,9 +133,16 @@ impl Settings {
             charts_settings: Memory::default(),
             link,
             run_duration: time::SinceStart::zero(),
+            explanation: None,
+            accessible: false,
         }
     }
 
+    /// True in accessible mode, where lines have patterns on top of their color.
+    pub fn is_accessible(&self) -> bool {
+        self.accessible
+    }
+
     /// True if the settings menu can be expanded.
     pub fn can_expand(&self) -> bool {
         self.display_mode.can_inc()
@@ -139,7 +154,19 @@ impl Settings {
 
     /// Number of header lines for the current display mode.
     pub fn line_count(&self) -> usize {
-        self.display_mode.line_count()
+        match (self.display_mode, self.explanation.as_ref()) {
+            (DisplayMode::Expanded(_), Some(explanation)) => {
+                self.display_mode.line_count() + 1 + explanation.contributors.len()
+            }
+            (DisplayMode::Collapsed, _) | (DisplayMode::Expanded(_), None) => {
+                self.display_mode.line_count()
+            }
+        }
+    }
+
+    /// Time window the charts currently use, *i.e.* the one saved on the server.
+    pub fn time_windopt(&self) -> &TimeWindopt {
+        self.charts_settings.reference().time_windopt()
     }
 
     /// Update the current time since the run started.
@@ -147,6 +174,11 @@ impl Settings {
         self.run_duration = run_duration
     }
 
+    /// Sets the explanation of the time window, received from the server.
+    pub fn set_explanation(&mut self, explanation: charts::explain::Explanation) {
+        self.explanation = Some(explanation)
+    }
+
     /// Renders the settings menu.
     pub fn render(&self, model: &Model) -> Html {
         match self.display_mode {
@@ -160,6 +192,7 @@ impl Settings {
         html! {
             <>
                 {self.time_window_line(model)}
+                {self.explanation_lines(model)}
             </>
         }
     }
@@ -173,6 +206,8 @@ impl Settings {
             display_mode: _,
             link: _,
             run_duration: _,
+            explanation: _,
+            accessible: _,
 
             charts_settings,
         } = self;
@@ -188,6 +223,8 @@ impl Settings {
             display_mode: _,
             link: _,
             run_duration: _,
+            explanation: _,
+            accessible: _,
 
             charts_settings,
         } = self;
@@ -267,18 +304,29 @@ impl Settings {
                 .ubound
                 .unwrap_or_else(|| self.run_duration),
         );
-        let step = self.run_duration / 10;
 
         header::Header::three_part_line_with(
             &*SETTINGS_LINE,
-            html! {},
+            header::Header::center(layout::button::text::render_default_button(
+                "header_settings_accessible",
+                if self.accessible {
+                    "accessible: on"
+                } else {
+                    "accessible: off"
+                },
+                Some(
+                    self.link
+                        .callback(|_| msg::Msg::from(Msg::ToggleAccessible)),
+                ),
+                false,
+            )),
             header::Header::center(html! {
                 <div>
                     <div
                         style = LEFT
                     >
                         { layout::header::emph("time window") }
-                        { " (seconds) " }
+                        { " " }
                         { layout::header::code("[ ") }
                     </div>
 
@@ -287,7 +335,6 @@ impl Settings {
                     >
                         { layout::input::since_start_opt_input(
                             model,
-                            step,
                             Some(lb),
                             |since_start_opt| msg_of_res(
                                 since_start_opt.map(|lb| Msg::TimeWindowLb(lb).into())
@@ -306,7 +353,6 @@ impl Settings {
                     >
                         { layout::input::since_start_opt_input(
                             model,
-                            step,
                             Some(ub),
                             |since_start_opt| msg_of_res(
                                 since_start_opt.map(|ub| Msg::TimeWindowUb(ub).into())
@@ -321,10 +367,143 @@ impl Settings {
                     </div>
                 </div>
             }),
-            html! {},
+            header::Header::center(layout::button::text::render_default_button(
+                "header_settings_explain",
+                "explain",
+                if self.has_changed() {
+                    None
+                } else {
+                    Some(self.link.callback(|_| msg::to_server::ChartsMsg::explain()))
+                },
+                self.has_changed(),
+            )),
         )
     }
 
+    /// Generates the lines of the explanation of the time window, if any.
+    pub fn explanation_lines(&self, model: &Model) -> Html {
+        const BORDER_HEIGHT_PX: usize = 2;
+        const LINE_HEIGHT_PX: usize = header::HEADER_LINE_HEIGHT_PX - BORDER_HEIGHT_PX;
+        define_style! {
+            EXPLANATION_LINE = {
+                border(bottom, {BORDER_HEIGHT_PX}px, {layout::LIGHT_BLUE_FG}),
+                height({LINE_HEIGHT_PX}px),
+            };
+        }
+
+        let explanation = match self.explanation.as_ref() {
+            Some(explanation) => explanation,
+            None => return html! {},
+        };
+        let bytes = |bytes: u64| {
+            num_fmt::bin_str_do(bytes as f64, |mut s| {
+                s.push('B');
+                s
+            })
+        };
+        let window = |window: &TimeWindow| {
+            layout::header::code(format!("[{}, {}]", window.lbound, window.ubound))
+        };
+
+        let title = header::Header::three_part_line_with(
+            &*EXPLANATION_LINE,
+            html! {},
+            header::Header::center(html! {
+                <>
+                    { layout::header::emph("explaining") }
+                    { " " }
+                    { window(&explanation.range) }
+                    { " against " }
+                    { window(&explanation.baseline) }
+                    {
+                        if explanation.contributors.is_empty() {
+                            html! { ": no allocation site allocates more than in the baseline" }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {
+                        if explanation.complete {
+                            html! {}
+                        } else {
+                            html! { " (partial, ran out of time)" }
+                        }
+                    }
+                </>
+            }),
+            header::Header::center(layout::button::img::close(
+                Some(header::HEADER_INFO_LINE_BUTTON_HEIGHT_PX),
+                "header_explanation_close",
+                Some(
+                    self.link
+                        .callback(|_| msg::Msg::from(Msg::DismissExplanation)),
+                ),
+                "dismiss explanation",
+            )),
+        );
+
+        html! {
+            <>
+                {title}
+                {for explanation.contributors.iter().enumerate().map(|(idx, contributor)| {
+                    let relative = contributor
+                        .relative_increase()
+                        .map(|rel| format!(", +{:.0}%", rel * 100.))
+                        .unwrap_or_else(String::new);
+                    let (file, line) = (contributor.file.clone(), contributor.line);
+                    let site = charts::tag::Target::Site {
+                        file: file.clone(),
+                        line,
+                    };
+                    header::Header::three_part_line_with(
+                        &*EXPLANATION_LINE,
+                        html! {},
+                        header::Header::center(html! {
+                            <div
+                                title = contributor.example.join("\n")
+                            >
+                                { layout::header::code(contributor) }
+                                { " +" }
+                                { layout::header::emph(bytes(contributor.increase())) }
+                                { format!(
+                                    " ({} → {}{})",
+                                    bytes(contributor.baseline),
+                                    bytes(contributor.bytes),
+                                    relative,
+                                ) }
+                            </div>
+                        }),
+                        if model.is_read_only() {
+                            html! {}
+                        } else {
+                            header::Header::center(html! {
+                                <>
+                                    {layout::button::text::render_default_button(
+                                        format!("header_explanation_filter_{}", idx),
+                                        "create filter",
+                                        Some(self.link.callback(move |_| {
+                                            msg::to_server::FiltersMsg::request_site(
+                                                file.clone(),
+                                                line,
+                                            )
+                                        })),
+                                        false,
+                                    )}
+                                    {layout::button::text::render_default_button(
+                                        format!("header_explanation_tag_{}", idx),
+                                        "tag",
+                                        Some(layout::tags::tag_callback(model, site)),
+                                        false,
+                                    )}
+                                </>
+                            })
+                        },
+                    )
+                })}
+            </>
+        }
+    }
+
     /// Updates itself given a settings message.
     pub fn update(&mut self, msg: Msg) -> Res<ShouldRender> {
         let res = match msg {
@@ -369,6 +548,11 @@ impl Settings {
                 self.charts_settings.reset();
                 Ok(true)
             }
+            Msg::DismissExplanation => Ok(self.explanation.take().is_some()),
+            Msg::ToggleAccessible => {
+                self.accessible = !self.accessible;
+                Ok(true)
+            }
             Msg::Save => {
                 if self.has_changed() {
                     if let Some(mut errors) = self.is_legal() {
@@ -407,6 +591,12 @@ pub enum Msg {
     Expand,
     /// Collapses the settings.
     Collapse,
+    /// Dismisses the explanation of the time window.
+    DismissExplanation,
+    /// Toggles accessible mode, see [`Settings::is_accessible`].
+    ///
+    /// [`Settings::is_accessible`]: struct.Settings.html#method.is_accessible (The is_accessible method of Settings)
+    ToggleAccessible,
 }
 base::implement! {
     impl Msg {
@@ -430,6 +620,8 @@ base::implement! {
                 Self::Save => write!(fmt, "save"),
                 Self::Expand => write!(fmt, "expand"),
                 Self::Collapse => write!(fmt, "collapse"),
+                Self::DismissExplanation => write!(fmt, "dismiss explanation"),
+                Self::ToggleAccessible => write!(fmt, "toggle accessible mode"),
             }
         }
     }
@@ -0,0 +1,361 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Toast notifications.
+//!
+//! Errors, warnings and server alerts show up as a stack of notifications in the top-right corner
+//! of the page. Notifications stay until clicked, except for information notifications which go
+//! away after [`INFO_DELAY_MS`]. A notification identical to the last one is not repeated, the last
+//! one counts how many times it was raised instead.
+//!
+//! [`INFO_DELAY_MS`]: constant.INFO_DELAY_MS.html (The INFO_DELAY_MS constant)
+
+prelude! {}
+
+/// Number of milliseconds information notifications stay visible.
+pub const INFO_DELAY_MS: u64 = 5_000;
+
+/// Maximum number of notifications visible at the same time, the most recent ones.
+pub const MAX_VISIBLE: usize = 5;
+
+/// Severity of a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Information, dismissed automatically.
+    Info,
+    /// Warning.
+    Warning,
+    /// Error.
+    Error,
+}
+
+impl Severity {
+    /// Description of a severity.
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+
+    /// Background color of the notifications of a severity.
+    fn bg(self) -> &'static str {
+        match self {
+            Self::Info => "#3a6ea5",
+            Self::Warning => "#b7791f",
+            Self::Error => "#b83232",
+        }
+    }
+}
+
+/// A notification.
+pub struct Toast {
+    /// Identifier, unique over the lifetime of the client.
+    id: u64,
+    /// Severity.
+    severity: Severity,
+    /// Message.
+    text: String,
+    /// Details, *e.g.* the whole chain of an error, if any.
+    details: Option<String>,
+    /// Number of times the notification was raised in a row.
+    count: usize,
+    /// True if the details are visible.
+    expanded: bool,
+    /// Timer dismissing the notification, for information notifications.
+    _timeout: Option<TimeoutTask>,
+}
+
+/// Toast notifications state.
+pub struct Toasts {
+    /// Link to the model, to send messages.
+    link: Link,
+    /// Notifications, from the oldest to the most recent.
+    toasts: Vec<Toast>,
+    /// Identifier of the next notification.
+    next_id: u64,
+}
+
+impl Toasts {
+    /// Constructor.
+    pub fn new(link: Link) -> Self {
+        Self {
+            link,
+            toasts: vec![],
+            next_id: 0,
+        }
+    }
+
+    /// Raises a notification.
+    ///
+    /// If the last notification is the same, counts it once more instead, which also restarts its
+    /// timer if it is an information notification.
+    pub fn push(&mut self, severity: Severity, text: impl Into<String>, details: Option<String>) {
+        let text = text.into();
+        let (id, count) = match self.toasts.last() {
+            Some(last)
+                if last.severity == severity && last.text == text && last.details == details =>
+            {
+                (last.id, last.count + 1)
+            }
+            _ => {
+                let id = self.next_id;
+                self.next_id += 1;
+                (id, 1)
+            }
+        };
+        if count > 1 {
+            self.toasts.pop();
+        }
+
+        let _timeout = if severity == Severity::Info {
+            Some(TimeoutService::spawn(
+                std::time::Duration::from_millis(INFO_DELAY_MS),
+                self.link
+                    .callback(move |_| msg::Msg::from(Msg::Dismiss(id))),
+            ))
+        } else {
+            None
+        };
+        self.toasts.push(Toast {
+            id,
+            severity,
+            text,
+            details,
+            count,
+            expanded: false,
+            _timeout,
+        })
+    }
+
+    /// Raises an information notification.
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Severity::Info, text, None)
+    }
+    /// Raises a warning notification.
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.push(Severity::Warning, text, None)
+    }
+    /// Raises an error notification.
+    ///
+    /// The message is the error that caused the others, the details are the whole chain.
+    pub fn error(&mut self, e: &err::Error) {
+        let text = e
+            .iter()
+            .last()
+            .map(|cause| cause.to_string())
+            .unwrap_or_else(|| e.to_string());
+        let details = if e.iter().nth(1).is_some() {
+            Some(e.to_pretty())
+        } else {
+            None
+        };
+        self.push(Severity::Error, text, details)
+    }
+
+    /// Applies a notification action.
+    pub fn update(&mut self, msg: Msg) -> ShouldRender {
+        match msg {
+            Msg::Dismiss(id) => {
+                let len = self.toasts.len();
+                self.toasts.retain(|toast| toast.id != id);
+                len != self.toasts.len()
+            }
+            Msg::DismissHidden => {
+                let hidden = self.toasts.len().saturating_sub(MAX_VISIBLE);
+                self.toasts.drain(0..hidden);
+                hidden > 0
+            }
+            Msg::ToggleDetails(id) => match self.toasts.iter_mut().find(|toast| toast.id == id) {
+                Some(toast) => {
+                    toast.expanded = !toast.expanded;
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Renders the notifications, below the header.
+    pub fn render(&self, model: &Model) -> Html {
+        define_style! {
+            MORE_STYLE = {
+                text_align(right),
+                fg({layout::LIGHT_BLUE_FG}),
+                bg({layout::DARK_GREY_BG}),
+                border_radius(5 px),
+                padding(5 px, 10 px),
+                pointer,
+            };
+        }
+
+        if self.toasts.is_empty() {
+            return html! {};
+        }
+
+        let container_style = inline_css! {
+            pos(fixed),
+            top({model.header.height_px(model) + 10} px),
+            right(10 px),
+            width(30%),
+            z_index(700),
+        };
+        let hidden = self.toasts.len().saturating_sub(MAX_VISIBLE);
+
+        html! {
+            <div
+                id = "toasts"
+                style = container_style
+            >
+                {for self.toasts[hidden..].iter().rev().map(|toast| self.render_toast(toast))}
+                {
+                    if hidden > 0 {
+                        html! {
+                            <div
+                                style = MORE_STYLE
+                                title = "click to dismiss the notifications that are not visible"
+                                onclick = self.link.callback(
+                                    |_| msg::Msg::from(Msg::DismissHidden)
+                                )
+                            >
+                                {format!("{} more", hidden)}
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+    /// Renders a notification.
+    fn render_toast(&self, toast: &Toast) -> Html {
+        define_style! {
+            TOAST_STYLE = {
+                width(100%),
+                margin_bottom(5 px),
+                padding(5 px, 10 px),
+                border_radius(5 px),
+                fg(white),
+                box_shadow(2 px, 2 px, 5 px, 0 px, (0, 0, 0, 0.4)),
+                white_space(pre wrap),
+                pointer,
+            };
+            COUNT_STYLE = {
+                bold,
+                margin_left(10 px),
+            };
+            DETAILS_BUTTON_STYLE = {
+                underline,
+                margin_left(10 px),
+            };
+            DETAILS_STYLE = {
+                font(code),
+                font_size(80%),
+                margin(5 px, 0 px, 0 px, 0 px),
+            };
+        }
+
+        let id = toast.id;
+        let style = format!(
+            "{} background-color: {};",
+            *TOAST_STYLE,
+            toast.severity.bg()
+        );
+        let title = match toast.details.as_ref() {
+            Some(details) => format!("{}, click to dismiss\n\n{}", toast.severity.desc(), details),
+            None => format!("{}, click to dismiss", toast.severity.desc()),
+        };
+
+        html! {
+            <div
+                style = style
+                title = title
+                onclick = self.link.callback(move |_| msg::Msg::from(Msg::Dismiss(id)))
+            >
+                {&toast.text}
+                {
+                    if toast.count > 1 {
+                        html! {
+                            <span style = COUNT_STYLE>
+                                {format!("×{}", toast.count)}
+                            </span>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                {
+                    match toast.details.as_ref() {
+                        Some(details) => html! {
+                            <>
+                                <span
+                                    style = DETAILS_BUTTON_STYLE
+                                    onclick = self.link.callback(move |event: yew::events::MouseEvent| {
+                                        event.stop_propagation();
+                                        msg::Msg::from(Msg::ToggleDetails(id))
+                                    })
+                                >
+                                    {if toast.expanded { "hide details" } else { "details" }}
+                                </span>
+                                {
+                                    if toast.expanded {
+                                        html! {
+                                            <pre style = DETAILS_STYLE>
+                                                {details}
+                                            </pre>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                            </>
+                        },
+                        None => html! {},
+                    }
+                }
+            </div>
+        }
+    }
+}
+
+/// Toast notification messages.
+#[derive(Debug)]
+pub enum Msg {
+    /// Dismisses a notification.
+    Dismiss(u64),
+    /// Dismisses the notifications that are not visible.
+    DismissHidden,
+    /// Shows or hides the details of a notification.
+    ToggleDetails(u64),
+}
+
+base::implement! {
+    impl Msg {
+        Display {
+            |&self, fmt| match self {
+                Self::Dismiss(id) => write!(fmt, "dismiss {}", id),
+                Self::DismissHidden => write!(fmt, "dismiss hidden"),
+                Self::ToggleDetails(id) => write!(fmt, "toggle details {}", id),
+            }
+        }
+    }
+}
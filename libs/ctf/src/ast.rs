@@ -21,80 +21,6 @@
 
 prelude! {}
 
-// /// A span for some type.
-// pub type Span<T> {
-//     /// Beginning of the span.
-//     pub begin: T,
-//     /// End of the span.
-//     pub end: T,
-// }
-
-// impl<T> Span<T>
-// where
-//     T: PartialOrd + Ord,
-// {
-//     /// Constructor.
-//     ///
-//     /// Fails if `begin > end`.
-//     pub fn new(begin: T, end: T) -> Res<Self> {
-//         if begin > end {
-//             bail!("non-monotonous values")
-//         }
-//         Ok(Self { begin, end })
-//     }
-
-//     /// Checks whether the input value is in the span.
-//     pub fn contains(&self, value: T) -> bool {
-//         (self.begin <= value) && (value <= self.end)
-//     }
-
-//     /// Checks whether the input value reference is in the span.
-//     pub fn contains_ref(&self, value: impl AsRef<T>) -> bool
-//     where
-//         for<'a> &'a T: PartialOrd + Ord,
-//     {
-//         let value = value.as_ref();
-//         (&self.begin <= value) && (value <= &self.end)
-//     }
-// }
-
-// impl<T> Span<T> {
-//     /// Map over the bounds of the span.
-//     pub fn map<U>(self, f: impl Fn(T) -> U) -> Span<U> {
-//         Span {
-//             begin: f(self.begin),
-//             end: f(self.end),
-//         }
-//     }
-
-//     /// Reference version of a span.
-//     pub fn as_ref(&self) -> Span<&T> {
-//         Span {
-//             begin: &self.begin,
-//             end: &self.end,
-//         }
-//     }
-// }
-
-// impl Span<Clock> {
-//     /// Pretty printer for a span of `u64` durations.
-//     pub fn pretty_time(&self) -> Span<time::Duration> {
-//         Span {
-//             begin: time::Duration::from_millis(self.begin),
-//             end: time::Duration::from_millis(self.end),
-//         }
-//     }
-// }
-
-// impl<T> fmt::Display for Span<T>
-// where
-//     T: fmt::Display,
-// {
-//     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-//         write!(fmt, "[{}, {}]", self.begin, self.end)
-//     }
-// }
-
 /// Header-related AST types.
 pub mod header {
     prelude! {}
@@ -117,13 +43,17 @@ pub mod header {
         pub pid: Pid,
         /// Memtrace version in use.
         pub version: u16,
+        /// Schema of the version in use.
+        pub schema: &'static schema::Schema,
     }
     impl Header {
         /// True if the element this header is for has a context.
         ///
-        /// Only true in memtrace `v2.*` and above.
+        /// Decided by the [schema] of the version.
+        ///
+        /// [schema]: ../../schema/index.html (The schema module)
         pub fn has_context(&self) -> bool {
-            self.version >= 2
+            self.schema.has_field(schema::Field::Context)
         }
     }
 
@@ -224,7 +154,6 @@ pub mod header {
 /// Event-related types.
 pub mod event {
     use super::*;
-    // prelude! {}
 
     /// Code for info events.
     const INFO_CODE: u32 = 0;
@@ -263,16 +192,25 @@ pub mod event {
         Collection,
         /// Stores a value between `1` and `16`.
         SmallAlloc(u32),
+        /// Event this parser does not know, stores its code.
+        ///
+        /// Typically an event added by a version of memtrace more recent than this parser. Events
+        /// do not store their size, so the parser cannot skip just the event: it skips the rest of
+        /// the packet instead.
+        Unknown(u32),
     }
     impl Kind {
         /// Checks the invariant for the `SmallAlloc` variant.
-        fn small_alloc_invariant(code: u32) {
+        fn small_alloc_invariant(code: u32) -> Res<()> {
             if !SMALL_ALLOC_REDUCED_CODES.contains(code) {
-                panic!(
+                bail!(
                     "illegal small allocation reduced code, expected {} <= {} <= {}",
-                    SMALL_ALLOC_REDUCED_CODES.lbound, code, SMALL_ALLOC_REDUCED_CODES.ubound
+                    SMALL_ALLOC_REDUCED_CODES.lbound,
+                    code,
+                    SMALL_ALLOC_REDUCED_CODES.ubound
                 )
             }
+            Ok(())
         }
 
         /// True if the event is an info event.
@@ -281,6 +219,19 @@ pub mod event {
         }
 
         /// Constructor from an event code.
+        ///
+        /// Codes this parser does not know yield [`Unknown`].
+        ///
+        /// ```rust
+        /// # use ctf::ast::event::Kind;
+        /// assert_eq!(Kind::from_code(2).unwrap(), Kind::Alloc);
+        /// assert_eq!(Kind::from_code(103).unwrap(), Kind::SmallAlloc(3));
+        /// assert_eq!(Kind::from_code(42).unwrap(), Kind::Unknown(42));
+        /// assert_eq!(Kind::Unknown(42).code().unwrap(), 42);
+        /// assert!(Kind::SmallAlloc(42).code().is_err());
+        /// ```
+        ///
+        /// [`Unknown`]: #variant.Unknown (The Unknown variant)
         pub fn from_code(code: u32) -> Res<Self> {
             let res = if code == INFO_CODE {
                 Self::Info
@@ -294,43 +245,49 @@ pub mod event {
                 Self::Collection
             } else if SMALL_ALLOC_CODES.contains(code) {
                 let reduced_code = code - SMALL_ALLOC_OFFSET;
-                Self::small_alloc_invariant(reduced_code);
+                Self::small_alloc_invariant(reduced_code)?;
                 Self::SmallAlloc(reduced_code)
             } else {
-                bail!("unexpected event code `{}`", code)
+                Self::Unknown(code)
             };
             Ok(res)
         }
 
         /// Event code of an event kind.
-        pub fn code(self) -> u32 {
-            match self {
+        pub fn code(self) -> Res<u32> {
+            let code = match self {
                 Self::Info => INFO_CODE,
                 Self::Locs => LOCS_CODE,
                 Self::Alloc => ALLOC_CODE,
                 Self::Promotion => PROMOTION_CODE,
                 Self::Collection => COLLECTION_CODE,
                 Self::SmallAlloc(n) => {
-                    Self::small_alloc_invariant(n);
-                    n + 100
+                    Self::small_alloc_invariant(n)?;
+                    n + SMALL_ALLOC_OFFSET
                 }
-            }
+                Self::Unknown(code) => code,
+            };
+            Ok(code)
         }
     }
 
     /// An event, decoded version.
     #[derive(Debug, Clone)]
-    pub enum Event<'data> {
+    pub enum Event {
         /// Location event.
-        Locs(Locs<'data>),
+        Locs(Locs),
         /// Allocation event.
         Alloc(Alloc),
         /// Promotion event.
         Promotion(u64),
         /// Collection event.
         Collection(u64),
+        /// Event this parser does not know, stores its code, see [`Kind::Unknown`].
+        ///
+        /// [`Kind::Unknown`]: enum.Kind.html#variant.Unknown (The Unknown kind)
+        Unknown(u32),
     }
-    impl<'data> Event<'data> {
+    impl Event {
         /// One-word description of the event.
         pub fn name(&self) -> &'static str {
             match self {
@@ -338,6 +295,7 @@ pub mod event {
                 Self::Alloc(_) => "allocation",
                 Self::Promotion(_) => "promotion",
                 Self::Collection(_) => "collection",
+                Self::Unknown(_) => "unknown",
             }
         }
 
@@ -353,6 +311,7 @@ pub mod event {
                 ),
                 Self::Collection(id) => format!("{}({})", name, id),
                 Self::Promotion(id) => format!("{}({})", name, id),
+                Self::Unknown(code) => format!("{}(code {})", name, code),
                 _ => name.into(),
             }
         }
@@ -398,6 +357,19 @@ pub mod event {
                 false,
             )
             .sample_rate(self.sample_rate)
+            .trace_info(self.to_trace_info())
+        }
+
+        /// Information about the run, for the UI.
+        pub fn to_trace_info(&self) -> alloc_data::TraceInfo {
+            alloc_data::TraceInfo {
+                exe_name: self.exe_name.clone(),
+                exe_params: self.exe_params.clone(),
+                host_name: self.host_name.clone(),
+                pid: self.pid,
+                sample_rate: self.sample_rate,
+                word_size: self.word_size,
+            }
         }
     }
 
@@ -473,11 +445,11 @@ pub mod event {
 
 /// A collection of locations.
 #[derive(Debug, Clone)]
-pub struct Locs<'data> {
+pub struct Locs {
     /// ID of the locations.
     pub id: u64,
     /// Locations.
-    pub locs: Vec<loc::Location<'data>>,
+    pub locs: Vec<loc::Location>,
 }
 
 /// Cache-check data.
@@ -36,6 +36,8 @@ pub mod ast;
 pub mod btrace;
 pub mod loc;
 pub mod parse;
+pub mod schema;
+pub mod stream;
 
 prelude! {}
 
@@ -49,16 +51,11 @@ const DEBUG_VERB: bool = false;
 use ast::{event::Event, *};
 
 /// Shorthand trait for the signature of event-handling functions.
-pub trait EventAction<'data>:
-    FnMut(Option<&ast::header::Packet>, Clock, Event<'data>) -> err::Res<()>
-{
-}
-impl<'data, T> EventAction<'data> for T where
-    T: FnMut(Option<&ast::header::Packet>, Clock, Event<'data>) -> err::Res<()>
-{
-}
+pub trait EventAction: FnMut(Option<&ast::header::Packet>, Clock, Event) -> err::Res<()> {}
+impl<T> EventAction for T where T: FnMut(Option<&ast::header::Packet>, Clock, Event) -> err::Res<()> {}
 
-pub use diff_parse::parse;
+pub use diff_parse::{parse, Decoder};
+pub use stream::StreamParser;
 
 mod diff_parse {
     use alloc_data::prelude::*;
@@ -72,18 +69,139 @@ mod diff_parse {
         last_trace: Vec<CLoc>,
         last_trace_len: usize,
         last_trace_cached: Option<Trace>,
+        /// Location codes of the last trace.
+        last_codes: Vec<EncodedLoc>,
+        /// True if the last trace mentions an unresolved location.
+        last_trace_unresolved: bool,
         cursor: usize,
         cursor_count_minus: usize,
+        /// If true, unknown location codes are fatal errors.
+        strict: bool,
+        /// Location codes that could not be resolved.
+        unresolved: HSet<EncodedLoc>,
     }
     impl TraceBuilder {
-        fn new() -> Self {
+        fn new(strict: bool) -> Self {
             Self {
                 last_trace: Vec::with_capacity(32),
                 last_trace_cached: None,
                 last_trace_len: 0,
+                last_codes: Vec::with_capacity(32),
+                last_trace_unresolved: false,
                 cursor: 0,
                 cursor_count_minus: 0,
+                strict,
+                unresolved: HSet::new(),
+            }
+        }
+
+        /// Placeholder location for an unknown location code.
+        fn placeholder(factory: &mut mem::Factory, code: EncodedLoc) -> Vec<Loc> {
+            let file = factory.register_str(&format!("<unresolved location #{}>", code));
+            vec![Loc::new(
+                file,
+                0,
+                Span {
+                    lbound: 0,
+                    ubound: 0,
+                },
+            )]
+        }
+
+        /// Resolves a location code.
+        ///
+        /// In non-strict mode, unknown codes are registered as placeholder locations so that
+        /// subsequent traces mentioning the same code remain consistent. The boolean is true if
+        /// the code is unresolved.
+        fn resolve<'map>(
+            &mut self,
+            factory: &mut mem::Factory,
+            loc_map: &'map mut LocMap,
+            code: EncodedLoc,
+        ) -> Res<(&'map Vec<Loc>, bool)> {
+            if !loc_map.contains_key(&code) {
+                if self.strict {
+                    bail!("[ctf parser] unknown location code `{}`", code)
+                }
+                base::log::warn!("[ctf parser] unknown location code `{}`", code);
+                loc_map.insert(code, Self::placeholder(factory, code));
+                self.unresolved.insert(code);
             }
+            let unresolved = self.unresolved.contains(&code);
+            let locs = loc_map
+                .get(&code)
+                .ok_or_else(|| format!("[ctf parser] unknown location code `{}`", code))?;
+            Ok((locs, unresolved))
+        }
+
+        /// Registers some locations for a code.
+        ///
+        /// Registering different locations for the same code, or registering locations for a code
+        /// that was previously treated as unresolved, is an error in strict mode. In non-strict
+        /// mode, the first locations registered are kept, except for the placeholder of an
+        /// unresolved code which the actual locations replace. Traces built after that mention the
+        /// actual locations, traces built before keep the placeholder.
+        fn register_locs(
+            &mut self,
+            loc_map: &mut LocMap,
+            code: EncodedLoc,
+            locs: Vec<Loc>,
+        ) -> Res<()> {
+            use std::collections::hash_map::Entry;
+            match loc_map.entry(code) {
+                Entry::Vacant(entry) => {
+                    entry.insert(locs);
+                }
+                Entry::Occupied(mut entry) => {
+                    if self.unresolved.contains(&code) {
+                        let msg = format!(
+                            "[ctf parser] locations #{} were used before being registered",
+                            code
+                        );
+                        if self.strict {
+                            bail!(msg)
+                        }
+                        base::log::warn!("{}, replacing the placeholder", msg);
+                        entry.insert(locs);
+                        self.unresolved.remove(&code);
+                        if self.last_codes.contains(&code) {
+                            self.rebuild_last_trace(loc_map)?
+                        }
+                    } else if entry.get() != &locs {
+                        let msg =
+                            format!("[ctf parser] trying to register locations #{} twice", code);
+                        if self.strict {
+                            bail!(msg)
+                        }
+                        base::log::warn!("{}, ignoring new locations", msg)
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// Rebuilds the last trace from its location codes, after some of its locations changed.
+        ///
+        /// Subsequent traces are built relatively to the last one, which must mention the current
+        /// locations of its codes.
+        fn rebuild_last_trace(&mut self, loc_map: &LocMap) -> Res<()> {
+            self.last_trace.clear();
+            self.last_trace_cached = None;
+            self.last_trace_unresolved = false;
+            for code in &self.last_codes {
+                let locs = loc_map
+                    .get(code)
+                    .ok_or_else(|| format!("[ctf parser] unknown location code `{}`", code))?;
+                self.last_trace_unresolved =
+                    self.last_trace_unresolved || self.unresolved.contains(code);
+                for loc in locs {
+                    match self.last_trace.last_mut() {
+                        Some(cloc) if loc == &cloc.loc => cloc.cnt += 1,
+                        _ => self.last_trace.push(CLoc::new(loc.clone(), 1)),
+                    }
+                }
+            }
+            Ok(())
         }
         #[inline]
         fn reset(&mut self) {
@@ -95,10 +213,10 @@ mod diff_parse {
         fn build_trace(
             &mut self,
             factory: &mut mem::Factory,
-            loc_map: &LocMap,
+            loc_map: &mut LocMap,
             common_pref_len: usize,
             trace: Vec<usize>,
-        ) -> Res<Trace> {
+        ) -> Res<(Trace, bool)> {
             debug_assert_eq!(self.cursor, 0);
             debug_assert_eq!(self.cursor_count_minus, 0);
 
@@ -107,7 +225,7 @@ mod diff_parse {
             let trace = if common_pref_len == trace_len && trace_len == self.last_trace_len {
                 if let Some(trace) = self.last_trace_cached.clone() {
                     trace
-                } else if common_pref_len == 0 {
+                } else if self.last_codes.len() == trace_len {
                     let mut trace = self.last_trace.clone();
                     trace.shrink_to_fit();
                     let trace = factory.register_trace(trace);
@@ -117,10 +235,16 @@ mod diff_parse {
                     bail!("[build_trace] illegal internal state: no previous trace exists")
                 }
             } else {
+                self.last_trace_unresolved = false;
+                self.last_codes.truncate(common_pref_len);
+                self.last_codes.extend(
+                    trace[common_pref_len..]
+                        .iter()
+                        .map(|code| *code as EncodedLoc),
+                );
                 'drain_trace: for (idx, code) in trace.into_iter().enumerate() {
-                    let sub_trace = loc_map
-                        .get(&(code as u64))
-                        .ok_or_else(|| format!("[ctf parser] unknown location code `{}`", code))?;
+                    let (sub_trace, unresolved) = self.resolve(factory, loc_map, code as u64)?;
+                    self.last_trace_unresolved = self.last_trace_unresolved || unresolved;
 
                     match idx.cmp(&common_pref_len) {
                         std::cmp::Ordering::Less => {
@@ -185,7 +309,85 @@ mod diff_parse {
                 trace
             };
 
-            Ok(trace)
+            Ok((trace, self.last_trace_unresolved))
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn unresolved_locs() {
+            let mut factory = mem::Factory::new(false);
+            let mut loc_map = LocMap::new();
+            let file = factory.register_str("file.ml");
+            loc_map.insert(
+                0,
+                vec![Loc::new(
+                    file,
+                    7,
+                    Span {
+                        lbound: 3,
+                        ubound: 5,
+                    },
+                )],
+            );
+
+            let mut builder = TraceBuilder::new(true);
+            let res = builder.build_trace(&mut factory, &mut loc_map, 0, vec![0, 1]);
+            assert!(res.is_err());
+
+            let mut builder = TraceBuilder::new(false);
+            let (_, unresolved) = builder
+                .build_trace(&mut factory, &mut loc_map, 0, vec![0])
+                .unwrap();
+            assert!(!unresolved);
+            let (trace, unresolved) = builder
+                .build_trace(&mut factory, &mut loc_map, 1, vec![0, 1])
+                .unwrap();
+            assert!(unresolved);
+
+            // The actual locations replace the placeholder when not strict, including in the
+            // prefix of the next trace.
+            let real = factory.register_str("real.ml");
+            let real_locs: Vec<_> = (1..3)
+                .map(|line| {
+                    Loc::new(
+                        real.clone(),
+                        line,
+                        Span {
+                            lbound: 0,
+                            ubound: 1,
+                        },
+                    )
+                })
+                .collect();
+            builder.register_locs(&mut loc_map, 1, real_locs).unwrap();
+            let (real_trace, unresolved) = builder
+                .build_trace(&mut factory, &mut loc_map, 2, vec![0, 1, 0])
+                .unwrap();
+            assert!(!unresolved);
+            let (_, unresolved) = builder
+                .build_trace(&mut factory, &mut loc_map, 0, vec![1])
+                .unwrap();
+            assert!(!unresolved);
+
+            let mut builder = TraceBuilder::new(true);
+            let locs = loc_map[&0].clone();
+            builder.register_locs(&mut loc_map, 0, locs).unwrap();
+            assert!(builder.register_locs(&mut loc_map, 0, vec![]).is_err());
+
+            // Releases the factory locks, needed to access traces and strings.
+            drop(factory);
+            let trace = trace.get();
+            assert_eq!(trace.len(), 2);
+            assert_eq!(trace[1].loc.file.to_string(), "<unresolved location #1>");
+            let real_trace = real_trace.get();
+            assert_eq!(real_trace.len(), 4);
+            assert_eq!(real_trace[1].loc.file.to_string(), "real.ml");
+            assert_eq!(real_trace[2].loc.line, 2);
+            assert_eq!(real_trace[3].loc.file.to_string(), "file.ml");
         }
     }
 
@@ -193,15 +395,280 @@ mod diff_parse {
         time::Date::from_micros(convert(date, "date_from_microsecs"))
     }
 
+    /// Init info of a trace, from its CTF header and its trace info.
+    pub fn init(header: &crate::ast::header::Ctf, info: &crate::ast::event::Info) -> Init {
+        // Start time of the run, used for init and to compute the time-since-start of all events.
+        let start_time = date_from_microsecs(header.timestamp.lbound);
+        info.to_init(start_time)
+            .trace_format(header.schema.trace_format(info))
+    }
+
+    base::new_time_stats! {
+        struct DecoderProf {
+            pub trace_building => "building traces",
+            pub locations => "registering locations",
+            pub dead => "handling collections",
+            pub promotion => "handling promotions",
+            pub alloc => "handling allocations",
+            pub alloc_action => "allocation action",
+        }
+    }
+
+    /// Turns the events of the packets of a trace into allocations and collections.
+    ///
+    /// Stores what must survive from one packet to the next: the last trace built, the locations
+    /// registered, the end of the last packet to detect gaps, and the index of the packet for
+    /// sampling. Used by [`parse`] on whole traces, and on the packets of a [`StreamParser`] for
+    /// traces that are still being written.
+    ///
+    /// [`parse`]: fn.parse.html (The parse function)
+    /// [`StreamParser`]: stream/struct.StreamParser.html (The StreamParser struct)
+    pub struct Decoder {
+        /// Builds the traces of the allocations.
+        trace_builder: TraceBuilder,
+        /// Maps location encoded identifiers to actual locations.
+        loc_id_to_loc: LocMap,
+        /// Start time of the run, used to compute the time-since-start of all events.
+        start_time: time::Date,
+        /// End of the previous packet, used to detect gaps between packets.
+        last_packet_end: Option<time::SinceStart>,
+        /// Index of the next packet, used for sampling.
+        packet_idx: usize,
+        /// Packet sampling, see [`parse`].
+        ///
+        /// [`parse`]: fn.parse.html (The parse function)
+        sampling: usize,
+        /// Number of events skipped because their kind is unknown.
+        skipped_events: usize,
+        /// Profiling.
+        prof: DecoderProf,
+    }
+
+    impl Decoder {
+        /// Constructor, `header` is the CTF header of the trace.
+        ///
+        /// See [`parse`] for `strict` and `sampling`.
+        ///
+        /// [`parse`]: fn.parse.html (The parse function)
+        pub fn new(header: &crate::ast::header::Ctf, strict: bool, sampling: usize) -> Self {
+            Self {
+                trace_builder: TraceBuilder::new(strict),
+                loc_id_to_loc: LocMap::with_capacity(1001),
+                start_time: date_from_microsecs(header.timestamp.lbound),
+                last_packet_end: None,
+                packet_idx: 0,
+                sampling,
+                skipped_events: 0,
+                prof: DecoderProf::new(),
+            }
+        }
+
+        /// Number of events skipped so far because their kind is unknown.
+        ///
+        /// Events do not store their size, so the events following an unknown event in the same
+        /// packet are lost too and are not counted.
+        pub fn skipped_events(&self) -> usize {
+            self.skipped_events
+        }
+
+        /// Logs the profiling information, if any.
+        pub fn log_prof(&self) {
+            self.prof
+                .all_do(|| (), |desc, sw| base::log::info!("| {:>25}: {}", desc, sw))
+        }
+
+        /// Handles the events of a packet.
+        ///
+        /// Packets must be handled in the order of the trace. See [`parse`] for the actions.
+        ///
+        /// [`parse`]: fn.parse.html (The parse function)
+        pub fn packet<'a, F>(
+            &mut self,
+            factory: &mut F,
+            header: &crate::ast::header::Packet,
+            events: impl Iterator<Item = Res<(crate::prelude::Clock, crate::ast::event::Event)>>,
+            mut new_action: impl FnMut(&mut F, alloc_data::Builder),
+            mut dead_action: impl FnMut(&mut F, time::SinceStart, uid::Alloc),
+            mut promotion_action: impl FnMut(&mut F, time::SinceStart, uid::Alloc),
+            mut mark_timestamp: impl FnMut(&mut F, time::SinceStart),
+            mut mark_gap: impl FnMut(&mut F, Range<time::SinceStart>),
+        ) -> Res<()>
+        where
+            F: std::ops::DerefMut<Target = mem::Factory<'a>>,
+        {
+            use crate::ast::event::Event;
+
+            let Self {
+                trace_builder,
+                loc_id_to_loc,
+                start_time,
+                last_packet_end,
+                packet_idx,
+                sampling,
+                skipped_events,
+                prof,
+            } = self;
+            let (start_time, sampling) = (*start_time, *sampling);
+
+            let keep_allocs = sampling <= 1 || *packet_idx % sampling == 0;
+            *packet_idx += 1;
+
+            let packet_start = date_from_microsecs(header.timestamp.lbound) - start_time;
+            if let Some(last_packet_end) = *last_packet_end {
+                if last_packet_end < packet_start {
+                    mark_gap(factory, Range::new(last_packet_end, packet_start))
+                }
+            }
+
+            for event in events {
+                let (clock, event) = event?;
+                match event {
+                    Event::Alloc(crate::ast::event::Alloc {
+                        id: uid,
+                        backtrace,
+                        len,
+                        common_pref_len,
+                        nsamples,
+                        is_major,
+                        ..
+                    }) => {
+                        let (trace, unresolved_locs) = {
+                            prof.trace_building.time(|| {
+                                trace_builder.build_trace(
+                                    factory,
+                                    loc_id_to_loc,
+                                    common_pref_len,
+                                    backtrace,
+                                )
+                            })?
+                        };
+
+                        if !keep_allocs {
+                            continue;
+                        }
+
+                        prof.alloc.start();
+
+                        // Build the allocation.
+                        let alloc = {
+                            let time_since_start = date_from_microsecs(clock) - start_time;
+                            let labels = factory.empty_labels();
+                            let nsamples = nsamples * std::cmp::max(sampling, 1);
+                            let kind = if is_major {
+                                AllocKind::Major
+                            } else {
+                                AllocKind::Minor
+                            };
+                            alloc_data::Builder::new(
+                                Some(uid.into()),
+                                kind,
+                                convert(len, "ctf parser: alloc size"),
+                                trace,
+                                labels,
+                                time_since_start,
+                                None,
+                            )
+                            .nsamples(nsamples as u32)
+                            .unresolved_locs(unresolved_locs)
+                        };
+
+                        prof.alloc.stop();
+
+                        prof.alloc_action.time(|| new_action(factory, alloc))
+                    }
+
+                    Event::Collection(alloc_uid) => {
+                        prof.dead.start();
+
+                        let uid = uid::Alloc::from(alloc_uid);
+                        let timestamp = date_from_microsecs(clock) - start_time;
+
+                        dead_action(factory, timestamp, uid);
+
+                        prof.dead.stop();
+                    }
+                    Event::Locs(crate::ast::Locs { id, locs }) => {
+                        prof.locations.start();
+
+                        let locs = locs
+                            .into_iter()
+                            .map(|loc| {
+                                let file = factory.register_str(&loc.file_path);
+                                let line = loc.line;
+                                let col = loc.col;
+
+                                Loc::new(
+                                    file,
+                                    line,
+                                    Span {
+                                        lbound: col.lbound,
+                                        ubound: col.ubound,
+                                    },
+                                )
+                            })
+                            .collect();
+
+                        let res = trace_builder.register_locs(loc_id_to_loc, id, locs);
+                        prof.locations.stop();
+                        res?
+                    }
+                    Event::Promotion(alloc_uid) => {
+                        prof.promotion.start();
+
+                        let uid = uid::Alloc::from(alloc_uid);
+                        let timestamp = date_from_microsecs(clock) - start_time;
+
+                        promotion_action(factory, timestamp, uid);
+
+                        prof.promotion.stop();
+                    }
+                    Event::Unknown(code) => {
+                        *skipped_events += 1;
+                        base::log::warn!(
+                            "[ctf parser] skipping unknown event code `{}` and the rest of packet #{}",
+                            code,
+                            header.id(),
+                        )
+                    }
+                }
+            }
+
+            let packet_end = date_from_microsecs(header.timestamp.ubound) - start_time;
+            mark_timestamp(factory, packet_end);
+            *last_packet_end = Some(packet_end);
+
+            Ok(())
+        }
+    }
+
     /// Parses a CTF file (memtrace format).
+    ///
+    /// Function `mark_gap` is called on the time ranges between two packets where no data is
+    /// available, *i.e.* when a packet starts strictly after the previous packet ended.
+    ///
+    /// In `strict` mode, unknown location codes and conflicting location registrations are fatal
+    /// errors. Otherwise, the allocations mentioning them are still built, with placeholder
+    /// locations and their `unresolved_locs` flag set.
+    ///
+    /// If `sampling > 1`, only the allocations of one packet in `sampling` are built, and their
+    /// number of samples is multiplied by `sampling` so that sizes are estimates of the real ones.
+    /// The UID hints of the allocations are their UIDs in the trace, which are not consecutive
+    /// anymore. Collections and promotions are all reported, including those of allocations that
+    /// were not built.
+    /// All packets are still decoded since backtraces and locations are encoded relatively to the
+    /// previous packets.
     pub fn parse<'a, F>(
         bytes: &[u8],
-        mut factory: &mut F,
+        strict: bool,
+        sampling: usize,
+        factory: &mut F,
         mut bytes_progress: impl FnMut(usize),
         init_action: impl FnOnce(&mut F, Init),
         mut new_action: impl FnMut(&mut F, alloc_data::Builder),
         mut dead_action: impl FnMut(&mut F, time::SinceStart, uid::Alloc),
+        mut promotion_action: impl FnMut(&mut F, time::SinceStart, uid::Alloc),
         mut mark_timestamp: impl FnMut(&mut F, time::SinceStart),
+        mut mark_gap: impl FnMut(&mut F, Range<time::SinceStart>),
     ) -> Res<()>
     where
         F: std::ops::DerefMut<Target = mem::Factory<'a>>,
@@ -212,36 +679,17 @@ mod diff_parse {
                 pub basic_parsing => "basic parsing",
                 pub event_parsing => "event parsing",
                 pub packet_parsing => "packet parsing",
-                pub trace_building => "building traces",
-                pub locations => "registering locations",
-                pub dead => "handling collections",
-                pub alloc => "handling allocations",
-                pub alloc_action => "allocation action",
             }
         }
         let mut prof = Prof::new();
         prof.total.start();
 
-        let mut trace_builder = TraceBuilder::new();
-
-        // Maps location encoded identifiers to actual locations.
-        let mut loc_id_to_loc = LocMap::with_capacity(1001);
-
         parse! {
             bytes => |mut parser| {
                 prof.basic_parsing.start();
-
-                let header = parser.header();
-
-                // Start time of the run, used for init and to compute the time-since-start of all
-                // events.
-                let start_time = date_from_microsecs(header.timestamp.lbound);
-                // let end_time = date_from_microsecs(header.header.timestamp.end).sub(start_time)?;
-
-                // Init info.
-                let init = parser.trace_info().to_init(start_time);
-
+                let init = init(parser.header(), parser.trace_info());
                 init_action(factory, init);
+                let mut decoder = Decoder::new(parser.header(), strict, sampling);
                 prof.basic_parsing.stop();
 
                 // Iterate over the packet of the trace.
@@ -252,102 +700,34 @@ mod diff_parse {
                         bytes_progress(packet_parser.real_position().0);
                     }
 
-                    // Iterate over the events of the packet.
-                    while let Some((clock, event)) = prof.event_parsing.time(
-                        || packet_parser.next_event()
-                    )? {
-                        use crate::ast::event::Event;
-
-                        match event {
-                            Event::Alloc(crate::ast::event::Alloc {
-                                id: uid, backtrace, len, common_pref_len, nsamples, ..
-                            }) => {
-                                let trace = {
-                                    prof.trace_building.time(|| trace_builder.build_trace(
-                                        factory,
-                                        &loc_id_to_loc,
-                                        common_pref_len,
-                                        backtrace,
-                                    ))?
-                                };
-
-                                prof.alloc.start();
-
-                                // Build the allocation.
-                                let alloc = {
-                                    let time_since_start =
-                                        date_from_microsecs(clock) - start_time;
-                                    let labels = factory.empty_labels();
-                                    let alloc = alloc_data::Builder::new(
-                                        Some(uid.into()),
-                                        AllocKind::Minor,
-                                        convert(len, "ctf parser: alloc size"),
-                                        trace,
-                                        labels,
-                                        time_since_start,
-                                        None
-                                    ).nsamples(nsamples as u32);
-                                    alloc
-                                };
-
-                                prof.alloc.stop();
-
-                                prof.alloc_action.time(|| new_action(factory, alloc))
-                            },
-
-                            Event::Collection(alloc_uid) => {
-                                prof.dead.start();
-
-                                let uid = uid::Alloc::from(alloc_uid);
-                                let timestamp = date_from_microsecs(clock) - start_time;
-
-                                dead_action(&mut factory, timestamp, uid);
-
-                                prof.dead.stop();
-                            },
-                            Event::Locs(crate::ast::Locs { id, locs }) => {
-                                prof.locations.start();
-
-                                let locs = locs.into_iter().map(|loc| {
-                                    let file = factory.register_str(loc.file_path);
-                                    let line = loc.line;
-                                    let col = loc.col;
-
-                                    Loc::new(
-                                        file,
-                                        line,
-                                        Span {
-                                            lbound: col.lbound,
-                                            ubound: col.ubound,
-                                        },
-                                    )
-                                }).collect();
-
-                                let prev = loc_id_to_loc.insert(id, locs);
-                                prof.locations.stop();
-                                if prev.is_some() && prev.as_ref() != loc_id_to_loc.get(&id) {
-                                    bail!("[ctf parser] trying to register locations #{} twice", id)
-                                }
-                            },
-                            Event::Promotion(_) => {
-                                ()
-                            },
-                        }
-                    }
-
-                    let packet_end = date_from_microsecs(
-                        packet_parser.header().timestamp.ubound
-                    ) - start_time;
-                    mark_timestamp(
+                    let header = packet_parser.header().clone();
+                    let event_parsing = &mut prof.event_parsing;
+                    let events = std::iter::from_fn(
+                        || event_parsing.time(|| packet_parser.next_event()).transpose()
+                    );
+                    decoder.packet(
                         factory,
-                        packet_end,
-                    )
+                        &header,
+                        events,
+                        &mut new_action,
+                        &mut dead_action,
+                        &mut promotion_action,
+                        &mut mark_timestamp,
+                        &mut mark_gap,
+                    )?;
                 }
 
                 prof.all_do(
                     || base::log::info!("done parsing"),
                     |desc, sw| base::log::info!("| {:>25}: {}", desc, sw),
                 );
+                decoder.log_prof();
+                if decoder.skipped_events() > 0 {
+                    base::log::warn!(
+                        "[ctf parser] skipped {} unknown event(s)",
+                        decoder.skipped_events(),
+                    )
+                }
 
                 Ok(())
             }
@@ -21,12 +21,17 @@
 
 prelude! {}
 
-/// A string slice and some value.
-pub type Data<'data, T> = (&'data str, T);
+/// A string and some value.
+///
+/// The string is owned so that the MTF map does not borrow the input, which lets the context
+/// survive the bytes of the packets it was built from, see [`StreamParser`].
+///
+/// [`StreamParser`]: ../stream/struct.StreamParser.html (The StreamParser struct)
+pub type Data<T> = (Arc<str>, T);
 /// An optional [`Data`] value.
 ///
 /// [`Data`]: type.Data.html (Data type alias)
-pub type Entry<'data, T> = Option<Data<'data, T>>;
+pub type Entry<T> = Option<Data<T>>;
 
 /// Last legal index in the MTF table.
 const LAST_IDX: u8 = 30;
@@ -62,14 +67,14 @@ impl fmt::Display for Idx {
 
 /// MTF (Move-To-Front) map.
 #[derive(Debug, Clone)]
-pub struct MtfMap<'data, T> {
+pub struct MtfMap<T> {
     /// Actual MTF map.
     ///
     /// **Always has length `MAX_IDX`.**
-    vec: Vec<Entry<'data, T>>,
+    vec: Vec<Entry<T>>,
 }
 
-impl<'data, T> MtfMap<'data, T> {
+impl<T> MtfMap<T> {
     /// Creates an empty MTF map.
     pub fn new() -> Self
     where
@@ -81,7 +86,7 @@ impl<'data, T> MtfMap<'data, T> {
     }
 
     /// Removes the last entry in the MTF map.
-    pub fn remove_last(&mut self) -> Entry<'data, T> {
+    pub fn remove_last(&mut self) -> Entry<T> {
         if let Some(last) = self.vec.last_mut() {
             std::mem::replace(last, None)
         } else {
@@ -170,7 +175,7 @@ impl<'data, T> MtfMap<'data, T> {
     /// Pushes an element at the front of the MTF map.
     ///
     /// Slides all elements in the map to the right.
-    fn push(&mut self, key: &'data str, val: T) -> Res<()> {
+    fn push(&mut self, key: Arc<str>, val: T) -> Res<()> {
         self.check("before pushing")?;
         let mut tmp = Some((key, val));
         for entry in &mut self.vec {
@@ -183,12 +188,12 @@ impl<'data, T> MtfMap<'data, T> {
     }
 
     /// Decodes a location at the current position in the input parser.
-    pub fn decode<Out, Parser>(
+    pub fn decode<'data, Out, Parser>(
         &mut self,
         parser: &mut Parser,
         idx: Idx,
-        if_absent: impl FnOnce(&mut Parser, Entry<'data, T>) -> Res<(&'data str, T)>,
-        binding_do: impl FnOnce(&mut Parser, &'data str, &mut T) -> Res<Out>,
+        if_absent: impl FnOnce(&mut Parser, Entry<T>) -> Res<(Arc<str>, T)>,
+        binding_do: impl FnOnce(&mut Parser, &Arc<str>, &mut T) -> Res<Out>,
     ) -> Res<Out>
     where
         Parser: CanParse<'data>,
@@ -198,13 +203,13 @@ impl<'data, T> MtfMap<'data, T> {
             pinfo!(parser, "index {} is not found", idx.idx);
             let last = self.remove_last();
             let (key, mut val) = if_absent(parser, last)?;
-            let res = binding_do(parser, key, &mut val);
+            let res = binding_do(parser, &key, &mut val);
             self.push(key, val)?;
             res
         } else {
             pinfo!(parser, "index {} is NOT not found", idx.idx);
             let res = match &mut self[idx] {
-                Some((key, val)) => binding_do(parser, *key, val),
+                Some((key, val)) => binding_do(parser, key, val),
                 None => bail!("[fatal] trying to decode an empty entry at {}", idx),
             };
             self.move_to_front(idx.idx)?;
@@ -213,14 +218,14 @@ impl<'data, T> MtfMap<'data, T> {
     }
 }
 
-impl<'data, T> std::ops::Index<Idx> for MtfMap<'data, T> {
-    type Output = Entry<'data, T>;
-    fn index(&self, idx: Idx) -> &Entry<'data, T> {
+impl<T> std::ops::Index<Idx> for MtfMap<T> {
+    type Output = Entry<T>;
+    fn index(&self, idx: Idx) -> &Entry<T> {
         &self.vec[idx.idx as usize]
     }
 }
-impl<'data, T> std::ops::IndexMut<Idx> for MtfMap<'data, T> {
-    fn index_mut(&mut self, idx: Idx) -> &mut Entry<'data, T> {
+impl<T> std::ops::IndexMut<Idx> for MtfMap<T> {
+    fn index_mut(&mut self, idx: Idx) -> &mut Entry<T> {
         &mut self.vec[idx.idx as usize]
     }
 }
@@ -228,11 +233,11 @@ impl<'data, T> std::ops::IndexMut<Idx> for MtfMap<'data, T> {
 /// Location parsing context.
 ///
 /// Wrapper around an MTF map.
-pub struct Cxt<'data> {
+pub struct Cxt {
     /// The MTF map.
-    map: MtfMap<'data, MtfMap<'data, ()>>,
+    map: MtfMap<MtfMap<()>>,
 }
-impl<'data> Cxt<'data> {
+impl Cxt {
     /// Constructs an empty context.
     pub fn new() -> Self {
         Self { map: MtfMap::new() }
@@ -276,15 +281,15 @@ impl<'data> Cxt<'data> {
 }
 
 /// A list of locations.
-pub type Locs<'data> = Vec<Location<'data>>;
+pub type Locs = Vec<Location>;
 
 /// A location.
 #[derive(Debug, Clone)]
-pub struct Location<'data> {
+pub struct Location {
     /// Encoded binary version of the location.
     pub encoded: usize,
     /// Path to the allocation-site file.
-    pub file_path: &'data str,
+    pub file_path: Arc<str>,
     /// Line index (from zero).
     pub line: usize,
     /// Column span (from zero).
@@ -292,11 +297,11 @@ pub struct Location<'data> {
     /// Definition name.
     ///
     /// Currently unused in memthol proper.
-    pub def_name: &'data str,
+    pub def_name: Arc<str>,
 }
-impl<'data> Location<'data> {
+impl Location {
     /// Parses a location at the current position in the input parser.
-    pub fn parse(parser: &mut impl CanParse<'data>, cxt: &mut Cxt<'data>) -> Res<Self> {
+    pub fn parse<'data>(parser: &mut impl CanParse<'data>, cxt: &mut Cxt) -> Res<Self> {
         let low: u64 = convert(parser.u32()?, "loc: low");
         let high: u64 = convert(parser.u16()?, "loc: high");
         pinfo!(parser, "    loc {{ low: {}, high: {} }}", low, high);
@@ -354,7 +359,7 @@ impl<'data> Location<'data> {
                             MtfMap::new()
                         };
                         pinfo!(parser, "        parsing file path");
-                        Ok((parser.string()?, map))
+                        Ok((parser.string()?.into(), map))
                     },
                     // given the parser and the file path/map binding, do this
                     |parser, file_path, map| {
@@ -364,11 +369,11 @@ impl<'data> Location<'data> {
                             // if absent, parse a string and bind it to unit
                             |parser, _| {
                                 pinfo!(parser, "        parsing def name");
-                                Ok((parser.string()?, ()))
+                                Ok((parser.string()?.into(), ()))
                             },
                             // given the parser and the def name/unit binding, return file path and def
                             // name
-                            |_, def_name, _| Ok((file_path, def_name)),
+                            |_, def_name, _| Ok((file_path.clone(), def_name.clone())),
                         )
                         .chain_err(|| {
                             format!(
@@ -392,7 +397,7 @@ impl<'data> Location<'data> {
     }
 }
 
-impl fmt::Display for Location<'_> {
+impl fmt::Display for Location {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         write!(
             fmt,
@@ -425,7 +430,7 @@ mod test {
         let data = vec!["0", "1", "2", "3", "4"];
         let mut mtf = MtfMap::new();
         for idx in 0..data.len() {
-            mtf.vec[idx] = Some((data[idx], '🙀'));
+            mtf.vec[idx] = Some((data[idx].into(), '🙀'));
         }
         check(
             &mtf,
@@ -518,7 +523,7 @@ mod test {
 
         check(&mtf, "{}");
 
-        mtf.push(data[0], '🙀').unwrap();
+        mtf.push(data[0].into(), '🙀').unwrap();
         check(
             &mtf,
             "\
@@ -528,7 +533,7 @@ mod test {
             ",
         );
 
-        mtf.push(data[1], '🙀').unwrap();
+        mtf.push(data[1].into(), '🙀').unwrap();
         check(
             &mtf,
             "\
@@ -539,7 +544,7 @@ mod test {
             ",
         );
 
-        mtf.push(data[2], '🙀').unwrap();
+        mtf.push(data[2].into(), '🙀').unwrap();
         check(
             &mtf,
             "\
@@ -551,7 +556,7 @@ mod test {
             ",
         );
 
-        mtf.push(data[3], '🙀').unwrap();
+        mtf.push(data[3].into(), '🙀').unwrap();
         check(
             &mtf,
             "\
@@ -564,7 +569,7 @@ mod test {
             ",
         );
 
-        mtf.push(data[4], '🙀').unwrap();
+        mtf.push(data[4].into(), '🙀').unwrap();
         check(
             &mtf,
             "\
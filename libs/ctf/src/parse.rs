@@ -48,6 +48,14 @@ prelude! {}
 /// Memtrace CTF magic number.
 const MAGIC: u32 = 0xc1fc1fc1;
 
+/// Length of a packet header in bytes, magic number included.
+///
+/// All the fields of a header have a fixed size. The CTF header has the same length: it has a magic
+/// number too, parsed by [`try_magic`].
+///
+/// [`try_magic`]: struct.RawParser.html#method.try_magic (try_magic method on RawParser)
+pub const HEADER_LEN: usize = 66;
+
 /// A position in the parser (zero-cost wrapper around a usize).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Pos {
@@ -87,13 +95,13 @@ base::new_time_stats! {
 /// - the location context,
 /// - the backtrace context, and
 /// - the allocation UID counter.
-pub struct Cxt<'data> {
-    loc: loc::Cxt<'data>,
+pub struct Cxt {
+    loc: loc::Cxt,
     btrace: btrace::Cxt,
     alloc_count: u64,
     prof: Prof,
 }
-impl<'data> Cxt<'data> {
+impl Cxt {
     /// Constructor.
     pub fn new() -> Self {
         Self {
@@ -118,6 +126,12 @@ impl<'data> Cxt<'data> {
     pub fn peek_next_alloc_id(&self) -> u64 {
         self.alloc_count
     }
+    /// Moves the allocation UID counter forward to some UID, if it is behind it.
+    ///
+    /// Used when skipping events, to keep the UIDs of the next packets consistent.
+    pub fn skip_alloc_ids_to(&mut self, next: u64) {
+        self.alloc_count = std::cmp::max(self.alloc_count, next)
+    }
 }
 
 /// Raw parser.
@@ -583,11 +597,13 @@ impl<'data> RawParser<'data> {
         let start = self.pos();
         let magic = self.u32_be()?;
         if magic == MAGIC {
+            self.big_endian = true;
             Ok(Either::Left(BeParser::from_raw(self)))
         } else {
             self.backtrack(start);
             let magic = self.u32_le()?;
             if magic == MAGIC {
+                self.big_endian = false;
                 Ok(Either::Right(LeParser::from_raw(self)))
             } else {
                 bail!(parse_error!(expected format!("magic number {}", MAGIC), found magic))
@@ -765,7 +781,7 @@ decl_impl_trait! {
         ///
         /// Context-sensitive.
         fn alloc(
-            &mut self, timestamp: u64, cxt: &mut Cxt<'data>, short: Option<usize>
+            &mut self, timestamp: u64, cxt: &mut Cxt, short: Option<usize>
         ) -> Res<ast::event::Alloc> {
             pinfo!(self, "parsing alloc");
             let alloc_id = cxt.next_alloc_id();
@@ -829,7 +845,7 @@ decl_impl_trait! {
         ///
         /// > In debug, the code actually `debug_assert`s this. In release, the panic will be an
         /// > arithmetic underflow.
-        fn alloc_uid_from_delta(&mut self, cxt: &Cxt<'data>) -> Res<u64> {
+        fn alloc_uid_from_delta(&mut self, cxt: &Cxt) -> Res<u64> {
             let next_alloc_id = cxt.peek_next_alloc_id();
             debug_assert!(next_alloc_id > 0);
             let id_delta = self.v_usize()? as u64;
@@ -839,7 +855,7 @@ decl_impl_trait! {
         /// Parses some new locations.
         ///
         /// Context-sensitive.
-        fn locs(&mut self, cxt: &mut Cxt<'data>) -> Res<ast::Locs<'data>> {
+        fn locs(&mut self, cxt: &mut Cxt) -> Res<ast::Locs> {
             pinfo!(self, "    parsing locations");
             let id = convert(self.u64()?, "locs: id");
             let len = convert(self.u8()?, "locs: len");
@@ -900,6 +916,7 @@ decl_impl_trait! {
             let ev_code = code >> EVENT_HEADER_TIME_LEN;
             pinfo!(self, "ev code: {}, time: {}", ev_code, time);
             let ev = event::Kind::from_code(ev_code)?;
+            header.schema.check_event(ev)?;
             Ok((ev, time))
         }
 
@@ -920,7 +937,7 @@ decl_impl_trait! {
         fn ctf_header(&mut self) -> Res<header::Ctf> {
             pinfo!(self, "parsing ctf header");
             self.raw_package_header(false)
-                .map(|(header, _)| header::Ctf::new(header, self.big_endian))
+                .map(|(header, _)| header::Ctf::new(header, self.is_big_endian()))
                 .chain_err(|| "while parsing ctf header")
         }
 
@@ -965,14 +982,7 @@ decl_impl_trait! {
             );
             let alloc_id = Range::new(alloc_begin, alloc_end);
 
-            if VERSION == version {
-                ()
-            } else {
-                match (VERSION, version) {
-                    (2, 1) => (),
-                    _ => bail!("found trace format v{}, expected v{}", version, VERSION),
-                }
-            }
+            let schema = schema::get(version)?;
 
             let header_size: u32 = convert(self.pos() - start, "raw_package_header: header_size");
 
@@ -998,6 +1008,7 @@ decl_impl_trait! {
                     alloc_id,
                     pid,
                     version,
+                    schema,
                 },
                 cache_check,
             ))
@@ -1064,7 +1075,7 @@ pub struct CtfParser<'data, Endian> {
     parser: Parser<'data, Endian>,
     header: header::Ctf,
     trace_info: ast::event::Info<'data>,
-    cxt: Cxt<'data>,
+    cxt: Cxt,
     packet_count: usize,
 }
 impl<'data> CtfParser<'data, ()> {
@@ -1174,6 +1185,43 @@ where
     }
 }
 
+/// Pseudo-parsers over an input that may end with an incomplete packet.
+impl<'data, Endian> Parser<'data, Endian>
+where
+    Parser<'data, Endian>: CanParse<'data>,
+{
+    /// Yields a [`PacketParser`] for the packet starting the input, if the input contains all of
+    /// it.
+    ///
+    /// Returns `None` if the header or some of the events of the packet are missing, in which case
+    /// `cxt` is left untouched. Otherwise, also yields the number of bytes of the packet, header
+    /// included.
+    ///
+    /// [`PacketParser`]: struct.PacketParser.html (PacketParser struct)
+    pub fn complete_packet<'cxt>(
+        mut self,
+        id: usize,
+        cxt: &'cxt mut Cxt,
+    ) -> Res<Option<(usize, PacketParser<'cxt, 'data, Endian>)>> {
+        if self.data().len() < HEADER_LEN {
+            return Ok(None);
+        }
+        let packet_header = self.packet_header(id)?;
+        debug_assert_eq!(*self.pos(), HEADER_LEN);
+
+        let content_len: usize =
+            convert(packet_header.content_size, "complete_packet: content_len");
+        if HEADER_LEN + content_len > self.data().len() {
+            return Ok(None);
+        }
+
+        let offset = self.real_position().0;
+        let event_bytes = self.take(content_len);
+        let parser = PacketParser::<Endian>::new(event_bytes, offset, packet_header, cxt);
+        Ok(Some((HEADER_LEN + content_len, parser)))
+    }
+}
+
 /// Packet parser.
 ///
 /// Thin wrapper around a [`RawParser`] over the bytes of the events of the packet. Also stores the
@@ -1191,7 +1239,7 @@ pub struct PacketParser<'cxt, 'data, Endian> {
     /// Event counter.
     event_cnt: usize,
     /// Parsing context.
-    cxt: &'cxt mut Cxt<'data>,
+    cxt: &'cxt mut Cxt,
 }
 
 impl<'cxt, 'data, Endian> std::ops::Deref for PacketParser<'cxt, 'data, Endian> {
@@ -1224,12 +1272,7 @@ where
     /// - `cxt`: parsing context, borrowed from the [`CtfParser`].
     ///
     /// [`CtfParser`]: struct.CtfParser.html (CtfParser struct)
-    fn new(
-        input: &'data [u8],
-        offset: usize,
-        header: header::Packet,
-        cxt: &'cxt mut Cxt<'data>,
-    ) -> Self {
+    fn new(input: &'data [u8], offset: usize, header: header::Packet, cxt: &'cxt mut Cxt) -> Self {
         Self {
             parser: Parser::new(input, offset),
             header,
@@ -1243,8 +1286,17 @@ where
         &self.header
     }
 
+    /// Parses all the remaining events of the packet, yields them with the packet header.
+    pub fn into_events(mut self) -> Res<(header::Packet, Vec<(Clock, Event)>)> {
+        let mut events = Vec::new();
+        while let Some(event) = self.next_event()? {
+            events.push(event)
+        }
+        Ok((self.header, events))
+    }
+
     /// Returns the next event of the packet, if any.
-    pub fn next_event(&mut self) -> Res<Option<(Clock, Event<'data>)>> {
+    pub fn next_event(&mut self) -> Res<Option<(Clock, Event)>> {
         if self.is_eof() {
             return Ok(None);
         }
@@ -1296,6 +1348,14 @@ where
             event::Kind::Info => bail!(
                 parse_error!(expected "non-info event: having more than two info events is illegal")
             ),
+
+            // Events do not store their size, the rest of the packet cannot be parsed.
+            event::Kind::Unknown(code) => {
+                let rest = parser.data().len() - *parser.pos();
+                let _ = parser.take(rest);
+                cxt.skip_alloc_ids_to(self.header.alloc_id.ubound);
+                Event::Unknown(code)
+            }
         };
 
         pinfo!(parser, "    {:?}", event);
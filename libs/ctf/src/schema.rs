@@ -0,0 +1,539 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Schema of the memtrace versions supported by the parser.
+//!
+//! The [`SCHEMAS`] table is the reference for what each version of memtrace contains: the events
+//! it can have, the fields whose presence depends on the version, and its quirks. The parser
+//! consults it instead of comparing versions itself, and the UI uses it, through
+//! [`Schema::trace_format`], to show which features the trace it loaded can support.
+//!
+//! Supporting a new version of memtrace starts by adding an entry to this table.
+//!
+//! ```rust
+//! use ctf::schema::{self, Event, Field};
+//! let v1 = schema::get(1).unwrap();
+//! assert!(v1.has_event(Event::SmallAlloc));
+//! assert!(!v1.has_field(Field::Context));
+//! let v2 = schema::get(2).unwrap();
+//! assert!(v2.has_field(Field::Context));
+//! assert_eq!(schema::latest().version, ctf::VERSION);
+//! assert!(schema::get(3).is_err());
+//! ```
+//!
+//! [`SCHEMAS`]: constant.SCHEMAS.html (The SCHEMAS constant)
+//! [`Schema::trace_format`]: struct.Schema.html#method.trace_format (The trace_format method)
+
+prelude! {}
+
+use ast::event::Kind;
+
+/// Kinds of events, regardless of their payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Trace info event.
+    Info,
+    /// Locations event.
+    Locs,
+    /// Allocation event.
+    Alloc,
+    /// Short allocation event.
+    SmallAlloc,
+    /// Promotion event.
+    Promotion,
+    /// Collection event.
+    Collection,
+}
+impl Event {
+    /// Event of an event kind, `None` for unknown kinds.
+    pub fn of(kind: Kind) -> Option<Self> {
+        let event = match kind {
+            Kind::Info => Self::Info,
+            Kind::Locs => Self::Locs,
+            Kind::Alloc => Self::Alloc,
+            Kind::SmallAlloc(_) => Self::SmallAlloc,
+            Kind::Promotion => Self::Promotion,
+            Kind::Collection => Self::Collection,
+            Kind::Unknown(_) => return None,
+        };
+        Some(event)
+    }
+
+    /// Short description of the event.
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::Info => "trace info",
+            Self::Locs => "locations",
+            Self::Alloc => "allocation",
+            Self::SmallAlloc => "short allocation",
+            Self::Promotion => "promotion",
+            Self::Collection => "collection",
+        }
+    }
+}
+
+/// Fields that only some versions have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// Context string of the trace info, after the PID.
+    Context,
+}
+impl Field {
+    /// Short description of the field.
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::Context => "trace info context",
+        }
+    }
+}
+
+/// A memthol feature that needs a field of the trace.
+#[derive(Debug, Clone, Copy)]
+pub struct Feature {
+    /// Name of the feature.
+    pub name: &'static str,
+    /// Description of the feature.
+    pub desc: &'static str,
+    /// Field the feature needs.
+    pub needs: Field,
+}
+
+/// Memthol features that depend on the version of the trace.
+pub const FEATURES: &[Feature] = &[Feature {
+    name: "run context",
+    desc: "context string the program attached to its trace",
+    needs: Field::Context,
+}];
+
+/// Schema of a memtrace version.
+#[derive(Debug, Clone, Copy)]
+pub struct Schema {
+    /// Version.
+    pub version: u16,
+    /// Events the version can contain.
+    pub events: &'static [Event],
+    /// Fields the version has, among the ones that depend on the version.
+    pub fields: &'static [Field],
+    /// Quirks of the version.
+    pub quirks: &'static [&'static str],
+}
+
+/// Events common to all versions.
+const ALL_EVENTS: &[Event] = &[
+    Event::Info,
+    Event::Locs,
+    Event::Alloc,
+    Event::SmallAlloc,
+    Event::Promotion,
+    Event::Collection,
+];
+
+/// Schemas of the supported versions, by increasing version.
+pub const SCHEMAS: &[Schema] = &[
+    Schema {
+        version: 1,
+        events: ALL_EVENTS,
+        fields: &[],
+        quirks: &["the trace info ends with the PID, there is no context string"],
+    },
+    Schema {
+        version: 2,
+        events: ALL_EVENTS,
+        fields: &[Field::Context],
+        quirks: &[],
+    },
+];
+
+/// Retrieves the schema of a version.
+///
+/// Fails if the version is not supported.
+pub fn get(version: u16) -> Res<&'static Schema> {
+    SCHEMAS
+        .iter()
+        .find(|schema| schema.version == version)
+        .ok_or_else(|| {
+            format!(
+                "found trace format v{}, expected {}",
+                version,
+                SCHEMAS
+                    .iter()
+                    .map(|schema| format!("v{}", schema.version))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            )
+            .into()
+        })
+}
+
+/// Schema of the latest supported version.
+pub fn latest() -> &'static Schema {
+    SCHEMAS
+        .last()
+        .expect("the table of schemas cannot be empty")
+}
+
+impl Schema {
+    /// True if the version can contain some event.
+    pub fn has_event(&self, event: Event) -> bool {
+        self.events.contains(&event)
+    }
+
+    /// True if the version has some field.
+    pub fn has_field(&self, field: Field) -> bool {
+        self.fields.contains(&field)
+    }
+
+    /// Fails if the version cannot contain some event kind.
+    ///
+    /// Unknown kinds pass the check, the packet parser skips them.
+    pub fn check_event(&self, kind: Kind) -> Res<()> {
+        let event = match Event::of(kind) {
+            Some(event) => event,
+            None => return Ok(()),
+        };
+        if self.has_event(event) {
+            Ok(())
+        } else {
+            bail!(
+                "found {} event, which memtrace v{} cannot contain",
+                event.desc(),
+                self.version
+            )
+        }
+    }
+
+    /// Describes the format of a trace of this version.
+    pub fn trace_format(&self, info: &ast::event::Info) -> alloc_data::TraceFormat {
+        alloc_data::TraceFormat {
+            name: "memtrace".into(),
+            version: self.version,
+            events: self
+                .events
+                .iter()
+                .map(|event| event.desc().into())
+                .collect(),
+            quirks: self.quirks.iter().map(|quirk| quirk.to_string()).collect(),
+            features: FEATURES
+                .iter()
+                .map(|feature| alloc_data::FormatFeature {
+                    name: feature.name.into(),
+                    desc: feature.desc.into(),
+                    unavailable: if self.has_field(feature.needs) {
+                        None
+                    } else {
+                        Some(format!(
+                            "memtrace v{} has no {}",
+                            self.version,
+                            feature.needs.desc()
+                        ))
+                    },
+                })
+                .collect(),
+            context: info.context.map(String::from),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::task::Poll;
+
+    /// Writes a number to some bytes, big-endian iff `be` is true.
+    macro_rules! put {
+        ($bytes:expr, $be:expr, $n:expr) => {
+            if $be {
+                $bytes.extend(&$n.to_be_bytes())
+            } else {
+                $bytes.extend(&$n.to_le_bytes())
+            }
+        };
+    }
+
+    /// Writes a CTF header and trace info for a version, big-endian iff `be` is true.
+    ///
+    /// The trace info has a context string iff `context` is true, whatever the version.
+    fn trace_start(be: bool, version: u16, context: bool) -> Vec<u8> {
+        let mut bytes = vec![];
+        put!(bytes, be, 0xc1fc1fc1u32);
+        // Packet size in bits, irrelevant here.
+        put!(bytes, be, 1024u32 * 8);
+        // Begin and end times.
+        put!(bytes, be, 0u64);
+        put!(bytes, be, 1u64);
+        // Flush duration.
+        put!(bytes, be, 0u32);
+        put!(bytes, be, version);
+        // PID.
+        put!(bytes, be, 7u64);
+        // Cache check.
+        put!(bytes, be, 0u16);
+        put!(bytes, be, 0u16);
+        put!(bytes, be, 0u64);
+        // Allocation UID range.
+        put!(bytes, be, 0u64);
+        put!(bytes, be, 0u64);
+
+        // Info event header, code `0` and time `0`.
+        put!(bytes, be, 0u32);
+        // Sample rate and word size.
+        put!(bytes, be, 0.5f64);
+        bytes.push(8);
+        for string in &["exe", "host", "params"] {
+            bytes.extend(string.as_bytes());
+            bytes.push(0)
+        }
+        // PID.
+        put!(bytes, be, 7u64);
+        if context {
+            bytes.extend(b"context");
+            bytes.push(0)
+        }
+        bytes
+    }
+
+    #[test]
+    fn decoder_follows_table() {
+        for schema in SCHEMAS {
+            let context = schema.has_field(Field::Context);
+            let bytes = trace_start(false, schema.version, context);
+            let parser = parse::CtfParser::new(&bytes).unwrap().right().unwrap();
+            assert_eq!(parser.header().version, schema.version);
+            assert_eq!(parser.trace_info().context.is_some(), context);
+            // Everything was read, and nothing more.
+            assert!(parser.is_eof());
+
+            let format = schema.trace_format(parser.trace_info());
+            for feature in &format.features {
+                assert_eq!(feature.is_available(), context);
+            }
+        }
+    }
+
+    #[test]
+    fn decoder_never_reads_absent_fields() {
+        for schema in SCHEMAS {
+            if schema.has_field(Field::Context) {
+                continue;
+            }
+            // Trailing bytes that look like a context must not be read as one.
+            let bytes = trace_start(false, schema.version, true);
+            let parser = parse::CtfParser::new(&bytes).unwrap().right().unwrap();
+            assert!(parser.trace_info().context.is_none());
+            assert!(!parser.is_eof());
+        }
+    }
+
+    /// Writes a packet for a version, big-endian iff `be` is true.
+    ///
+    /// - `alloc_ids`: range of the allocation UIDs created in the packet;
+    /// - `events`: event codes and payloads, events all happen at time `0`.
+    fn packet(be: bool, version: u16, alloc_ids: (u64, u64), events: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut content: Vec<u8> = vec![];
+        for (code, payload) in events {
+            put!(content, be, code << 25);
+            content.extend(*payload)
+        }
+
+        let mut bytes = vec![];
+        put!(bytes, be, 0xc1fc1fc1u32);
+        let size = parse::HEADER_LEN + content.len();
+        put!(bytes, be, size as u32 * 8);
+        // Begin and end times.
+        put!(bytes, be, 0u64);
+        put!(bytes, be, 1u64);
+        // Flush duration.
+        put!(bytes, be, 0u32);
+        put!(bytes, be, version);
+        // PID.
+        put!(bytes, be, 7u64);
+        // Cache check.
+        put!(bytes, be, 0u16);
+        put!(bytes, be, 0u16);
+        put!(bytes, be, 0u64);
+        put!(bytes, be, alloc_ids.0);
+        put!(bytes, be, alloc_ids.1);
+        assert_eq!(bytes.len(), parse::HEADER_LEN);
+
+        bytes.extend(content);
+        bytes
+    }
+
+    #[test]
+    fn unknown_events_are_skipped() {
+        let schema = latest();
+        let context = schema.has_field(Field::Context);
+        // Small allocation of size `1`, with an empty backtrace.
+        let small_alloc: (u32, &[u8]) = (101, &[0, 0]);
+
+        let mut bytes = trace_start(false, schema.version, context);
+        // The unknown event is sandwiched between two allocations, the second one is lost with the
+        // rest of the packet.
+        bytes.extend(packet(
+            false,
+            schema.version,
+            (0, 2),
+            &[small_alloc, (42, &[1, 2, 3]), small_alloc],
+        ));
+        bytes.extend(packet(false, schema.version, (2, 3), &[small_alloc]));
+
+        let mut parser = parse::CtfParser::new(&bytes).unwrap().right().unwrap();
+        let mut decoder = crate::Decoder::new(parser.header(), true, 1);
+        let mut factory = Box::new(alloc_data::mem::Factory::new(false));
+        let mut uids = vec![];
+        while let Some(packet_parser) = parser.next_packet().unwrap() {
+            let (header, events) = packet_parser.into_events().unwrap();
+            decoder
+                .packet(
+                    &mut factory,
+                    &header,
+                    events.into_iter().map(Ok),
+                    |_, builder| uids.push(builder.uid_hint),
+                    |_, _, _| (),
+                    |_, _, _| (),
+                    |_, _| (),
+                    |_, _| (),
+                )
+                .unwrap()
+        }
+
+        // The UIDs of the second packet are still consistent with its header.
+        assert_eq!(
+            uids,
+            vec![Some(uid::Alloc::new(0)), Some(uid::Alloc::new(2))]
+        );
+        assert_eq!(decoder.skipped_events(), 1);
+    }
+
+    /// Writes a packet with an allocation with a three-frame backtrace, and then its collection.
+    fn alloc_and_collection(be: bool, version: u16) -> Vec<u8> {
+        let mut alloc = vec![];
+        // Length `1_000`, as a variable-length `u16`.
+        alloc.push(253);
+        put!(alloc, be, 1_000u16);
+        // One sample, major allocation, nothing in common with the previous backtrace.
+        alloc.extend(&[1, 1, 0]);
+        // Two cache misses with their location, and then a cache hit on the first one.
+        put!(alloc, be, 3u16);
+        put!(alloc, be, (1u16 << 2) | 3);
+        put!(alloc, be, 0x1122_3344_5566_7788u64);
+        put!(alloc, be, (2u16 << 2) | 3);
+        put!(alloc, be, 0x0102_0304_0506_0708u64);
+        put!(alloc, be, 1u16 << 2);
+        // The collection refers to the most recent allocation.
+        let collection = [0];
+
+        packet(
+            be,
+            version,
+            (0, 1),
+            &[
+                (ast::event::Alloc::event_code(), alloc.as_slice()),
+                (ast::event::Collection::event_code(), &collection[..]),
+            ],
+        )
+    }
+
+    /// Parses a trace, yields its endianness and a description of everything in it.
+    fn describe(bytes: &[u8]) -> Res<(bool, Vec<String>)> {
+        let mut desc = vec![];
+        let be = parse! {
+            bytes => |mut parser| {
+                desc.push(format!("{:?}", parser.header().header()));
+                desc.push(format!("{:?}", parser.trace_info()));
+                while let Some(packet_parser) = parser.next_packet()? {
+                    let (header, events) = packet_parser.into_events()?;
+                    desc.push(format!("{:?}", header));
+                    desc.push(format!("{:?}", events))
+                }
+                parser.header().is_be()
+            }
+        };
+        Ok((be, desc))
+    }
+
+    #[test]
+    fn big_endian_round_trip() {
+        let schema = latest();
+        let context = schema.has_field(Field::Context);
+        let trace = |be| {
+            let mut bytes = trace_start(be, schema.version, context);
+            bytes.extend(alloc_and_collection(be, schema.version));
+            // The stream parser waits for the whole packet, its size must be the actual one.
+            let size = 8 * bytes.len() as u32;
+            let size = if be {
+                size.to_be_bytes()
+            } else {
+                size.to_le_bytes()
+            };
+            bytes[4..8].copy_from_slice(&size);
+            bytes
+        };
+        let (le_bytes, be_bytes) = (trace(false), trace(true));
+
+        let (le, expected) = describe(&le_bytes).unwrap();
+        assert!(!le);
+        let (be, desc) = describe(&be_bytes).unwrap();
+        assert!(be);
+        assert_eq!(desc, expected);
+
+        let mut parser = parse::CtfParser::new(&be_bytes).unwrap().left().unwrap();
+        let (_, events) = parser
+            .next_packet()
+            .unwrap()
+            .unwrap()
+            .into_events()
+            .unwrap();
+        match events.as_slice() {
+            [(_, ast::event::Event::Alloc(alloc)), (_, ast::event::Event::Collection(id))] => {
+                assert_eq!(alloc.len, 1_000);
+                assert!(alloc.is_major);
+                assert_eq!(
+                    alloc.backtrace,
+                    vec![
+                        0x1122_3344_5566_7788,
+                        0x0102_0304_0506_0708,
+                        0x1122_3344_5566_7788
+                    ],
+                );
+                assert_eq!(*id, alloc.id)
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+
+        // The stream parser picks the same endianness.
+        let mut parser = crate::stream::StreamParser::new(be_bytes.as_slice());
+        assert!(parser.poll_init().unwrap().is_ready());
+        assert!(parser.header().unwrap().is_be());
+        match parser.poll_packet().unwrap() {
+            Poll::Ready(packet) => {
+                assert_eq!(format!("{:?}", packet.events), expected[3])
+            }
+            Poll::Pending => panic!("the packet is complete"),
+        }
+    }
+
+    #[test]
+    fn unsupported_versions() {
+        let bytes = trace_start(false, latest().version + 1, true);
+        assert!(parse::CtfParser::new(&bytes).is_err());
+        assert!(get(0).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Streaming parser, for traces that are still being written.
+//!
+//! Memtrace writes its traces packet by packet. So, a trace that is still being written is a
+//! sequence of complete packets, possibly followed by the beginning of a packet. A
+//! [`StreamParser`] reads whatever is available, yields the complete packets, and keeps the bytes
+//! of the incomplete packet until the rest of it shows up.
+//!
+//! [`StreamParser`]: struct.StreamParser.html (The StreamParser struct)
+
+prelude! {}
+
+use std::{io::Read, task::Poll};
+
+use crate::parse::{BigEndian, Cxt, LowEndian, Parser, RawParser, HEADER_LEN};
+
+/// Number of bytes read at once from the input.
+const CHUNK_LEN: usize = 1 << 16;
+
+/// A packet and its events.
+#[derive(Debug, Clone)]
+pub struct Packet {
+    /// Header of the packet.
+    pub header: ast::header::Packet,
+    /// Events of the packet, in order.
+    pub events: Vec<(Clock, ast::event::Event)>,
+}
+
+/// Streaming CTF parser.
+///
+/// Reads its input incrementally, and yields the packets as they become complete. The parsing
+/// context (locations and backtrace caches) is maintained across packets, exactly as in the
+/// [`CtfParser`].
+///
+/// Polling the parser yields `Pending` when the input has no more bytes for now, as is the case at
+/// the end of a file that is still being written. The bytes of a trailing truncated packet are kept
+/// until the next poll, which reads the rest of the packet if it is available.
+///
+/// [`CtfParser`]: ../parse/struct.CtfParser.html (The CtfParser struct)
+pub struct StreamParser<R> {
+    /// Input.
+    reader: R,
+    /// Bytes read from the input, only the ones after `start` are not parsed yet.
+    buf: Vec<u8>,
+    /// Index in `buf` of the first byte not parsed yet.
+    start: usize,
+    /// Offset in the input of the first byte of `buf`.
+    offset: usize,
+    /// CTF header, available once the trace info is parsed.
+    header: Option<ast::header::Ctf>,
+    /// Parsing context.
+    cxt: Cxt,
+    /// Number of packets parsed.
+    packet_count: usize,
+}
+
+impl<R: Read> StreamParser<R> {
+    /// Constructor, `reader` must be at the start of the trace.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::with_capacity(CHUNK_LEN),
+            start: 0,
+            offset: 0,
+            header: None,
+            cxt: Cxt::new(),
+            packet_count: 0,
+        }
+    }
+
+    /// Number of bytes of the input consumed.
+    ///
+    /// The bytes of an incomplete packet are not consumed.
+    pub fn consumed(&self) -> usize {
+        self.offset + self.start
+    }
+
+    /// CTF header of the trace, available once [`poll_init`] is ready.
+    ///
+    /// [`poll_init`]: #method.poll_init (The poll_init method)
+    pub fn header(&self) -> Option<&ast::header::Ctf> {
+        self.header.as_ref()
+    }
+
+    /// Replaces the input, *e.g.* after reopening the file.
+    ///
+    /// The new reader must be positioned at [`consumed`]: the bytes read but not consumed are
+    /// dropped, and read again from the new reader. The parsing context is kept.
+    ///
+    /// [`consumed`]: #method.consumed (The consumed method)
+    pub fn reopen(&mut self, reader: R) {
+        self.reader = reader;
+        self.offset = self.consumed();
+        self.start = 0;
+        self.buf.clear();
+    }
+
+    /// Reads from the input, yields the number of bytes read.
+    ///
+    /// Drops the bytes already parsed first, so that the buffer does not grow with the input.
+    fn read(&mut self) -> Res<usize> {
+        self.buf.drain(..self.start);
+        self.offset += self.start;
+        self.start = 0;
+
+        let len = self.buf.len();
+        self.buf.resize(len + CHUNK_LEN, 0);
+        let res = loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                res => break res,
+            }
+        };
+        let read = res.as_ref().map(|read| *read).unwrap_or(0);
+        self.buf.truncate(len + read);
+        res.map_err(|e| format!("while reading ctf input: {}", e).into())
+    }
+
+    /// Parses the CTF header and the trace info, yields the init info of the trace.
+    ///
+    /// Must be polled until it is ready before polling packets.
+    pub fn poll_init(&mut self) -> Res<Poll<alloc_data::Init>> {
+        if self.header.is_some() {
+            bail!("[ctf stream parser] trying to parse the trace info twice")
+        }
+        loop {
+            if let Some(init) = self.try_init()? {
+                return Ok(Poll::Ready(init));
+            }
+            if self.read()? == 0 {
+                return Ok(Poll::Pending);
+            }
+        }
+    }
+
+    /// Parses the CTF header and the trace info, if all their bytes are available.
+    fn try_init(&mut self) -> Res<Option<alloc_data::Init>> {
+        let bytes = &self.buf[self.start..];
+        if bytes.len() < HEADER_LEN {
+            return Ok(None);
+        }
+
+        // The CTF header gives the size of the first packet, which contains the trace info.
+        let info_len: usize = {
+            let parser_disj = RawParser::new(bytes, self.consumed()).try_magic()?;
+            let header = parser_do! {
+                parser_disj => join |mut parser| {
+                    parser.ctf_header()?
+                }
+            };
+            convert(header.total_content_size, "stream parser: info_len")
+        };
+        if bytes.len() < info_len {
+            return Ok(None);
+        }
+
+        let (header, init, len) = parse! {
+            bytes => |parser| {
+                let header = parser.header().clone();
+                let init = crate::diff_parse::init(&header, parser.trace_info());
+                (header, init, parser.real_position().0)
+            }
+        };
+        self.start += len;
+        self.header = Some(header);
+        Ok(Some(init))
+    }
+
+    /// Yields the next packet, if it is complete.
+    ///
+    /// Reads from the input until the packet is complete, or until the input has no more bytes in
+    /// which case the packet is pending.
+    pub fn poll_packet(&mut self) -> Res<Poll<Packet>> {
+        let big_endian = match self.header.as_ref() {
+            Some(header) => header.is_be(),
+            None => bail!("[ctf stream parser] trying to parse a packet before the trace info"),
+        };
+        loop {
+            let packet = if big_endian {
+                self.try_packet::<BigEndian>()?
+            } else {
+                self.try_packet::<LowEndian>()?
+            };
+            if let Some(packet) = packet {
+                return Ok(Poll::Ready(packet));
+            }
+            if self.read()? == 0 {
+                return Ok(Poll::Pending);
+            }
+        }
+    }
+
+    /// Parses the next packet, if all its bytes are available.
+    fn try_packet<Endian>(&mut self) -> Res<Option<Packet>>
+    where
+        for<'data> Parser<'data, Endian>: CanParse<'data>,
+    {
+        let offset = self.consumed();
+        let parser = Parser::<Endian>::new(&self.buf[self.start..], offset);
+        let (len, packet_parser) = match parser.complete_packet(self.packet_count, &mut self.cxt)? {
+            Some(res) => res,
+            None => return Ok(None),
+        };
+        let (header, events) = packet_parser.into_events()?;
+        self.start += len;
+        self.packet_count += 1;
+        Ok(Some(Packet { header, events }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A trace.
+    const TRACE: &[u8] = include_bytes!("../rsc/lists_and_sets.ctf");
+
+    /// A trace that is being written, grows by `step` bytes at a time.
+    struct Growing {
+        /// Number of bytes written.
+        available: usize,
+        /// Number of bytes read.
+        pos: usize,
+        /// Number of bytes written at a time.
+        step: usize,
+    }
+    impl Growing {
+        fn grow(&mut self) {
+            assert!(self.available < TRACE.len(), "the whole trace is available");
+            self.available = std::cmp::min(self.available + self.step, TRACE.len())
+        }
+    }
+    impl Read for Growing {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let end = std::cmp::min(self.available, self.pos + buf.len());
+            let len = end - self.pos;
+            buf[..len].copy_from_slice(&TRACE[self.pos..end]);
+            self.pos = end;
+            Ok(len)
+        }
+    }
+
+    /// Describes the packets of the trace, parsed at once.
+    fn batch_packets() -> Res<Vec<String>> {
+        let mut packets = vec![];
+        parse! {
+            TRACE => |mut parser| {
+                while let Some(packet_parser) = parser.next_packet()? {
+                    let (header, events) = packet_parser.into_events()?;
+                    packets.push(format!("{} {:?}", header.id(), events))
+                }
+            }
+        }
+        Ok(packets)
+    }
+
+    #[test]
+    fn stream_like_batch() {
+        let expected = batch_packets().unwrap();
+        assert!(!expected.is_empty());
+
+        for step in [1, 1000, 4096, TRACE.len()].iter() {
+            let reader = Growing {
+                available: 0,
+                pos: 0,
+                step: *step,
+            };
+            let mut parser = StreamParser::new(reader);
+
+            while parser.poll_init().unwrap().is_pending() {
+                parser.reader.grow()
+            }
+
+            let mut packets = vec![];
+            while packets.len() < expected.len() {
+                match parser.poll_packet().unwrap() {
+                    Poll::Ready(Packet { header, events }) => {
+                        packets.push(format!("{} {:?}", header.id(), events))
+                    }
+                    Poll::Pending => {
+                        // Reopening must not lose the incomplete packet nor the parsing context.
+                        let available = parser.reader.available;
+                        parser.reopen(Growing {
+                            available,
+                            pos: parser.consumed(),
+                            step: *step,
+                        });
+                        parser.reader.grow()
+                    }
+                }
+            }
+
+            assert_eq!(packets, expected);
+            assert_eq!(parser.consumed(), TRACE.len());
+            assert!(parser.poll_packet().unwrap().is_pending());
+        }
+    }
+}
@@ -0,0 +1,320 @@
+/*<LICENSE>
+    This file is part of Memthol.
+
+    Copyright (C) 2020 OCamlPro.
+
+    Memthol is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    Memthol is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with Memthol.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Read-only HTTP API, to extract the data of the charts without a browser.
+//!
+//! The API is only served with `--api`, under the base path of the UI:
+//!
+//! - `GET /api/charts`: the specs of the charts;
+//! - `GET /api/charts/<uid>/points?since=<secs>`: the points of a chart, optionally only the ones
+//!   at least `<secs>` seconds after the start of the run; `null` if the chart has no points;
+//! - `GET /api/filters`: the specs of the filters, the everything and catch-all filters included;
+//! - `GET /api/stats`: the statistics of each filter.
+//!
+//! Responses are JSON, with the same types as the messages of the websocket protocol. The API has
+//! its own charts and filters, the ones a new client starts with, *i.e.* the ones of the filter
+//! generator. Until the trace is loaded, all requests fail with status `503`.
+
+use gotham::{
+    hyper::{
+        header::{self, HeaderValue},
+        Body, Response, StatusCode, Uri,
+    },
+    state::{FromState, State},
+};
+
+use crate::prelude::*;
+
+lazy_static! {
+    /// Charts of the API, created by the first request once the trace is loaded.
+    static ref CHARTS: sync::Mutex<Option<Charts>> = sync::Mutex::new(None);
+}
+
+/// Error of a request, with the status of the response.
+struct Failure {
+    /// Status of the response.
+    status: StatusCode,
+    /// Description of the error.
+    error: err::Error,
+}
+impl Failure {
+    /// Constructor.
+    fn new(status: StatusCode, error: impl Into<err::Error>) -> Self {
+        Self {
+            status,
+            error: error.into(),
+        }
+    }
+}
+impl From<err::Error> for Failure {
+    fn from(error: err::Error) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, error)
+    }
+}
+
+/// Result of a request.
+type ApiRes<T> = Result<T, Failure>;
+
+/// Applies an action to the charts of the API, creating them if needed.
+fn charts_do<T>(action: impl FnOnce(&mut Charts) -> Res<T>) -> ApiRes<T> {
+    if charts::data::progress::get()?.is_some() {
+        return Err(Failure::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "the trace is still loading",
+        ));
+    }
+    let mut api_charts = CHARTS
+        .lock()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while accessing the charts of the API")?;
+    if api_charts.is_none() {
+        let gen = Charts::auto_gen_shared(&mut charts::filter::gen::GenCxt::silent())?
+            .ok_or_else(|| err::Error::from("filter generation was cancelled"))?;
+        *api_charts = Some(gen)
+    }
+    let api_charts = api_charts.as_mut().expect("charts were just created");
+    Ok(action(api_charts)?)
+}
+
+/// Parses the UID of the chart in the path of a points request.
+fn chart_uid(uri: &Uri) -> ApiRes<uid::Chart> {
+    let segment = uri.path().trim_end_matches('/').rsplit('/').nth(1);
+    segment
+        .and_then(|uid| usize::from_str(uid).ok())
+        .map(uid::Chart::from)
+        .ok_or_else(|| {
+            Failure::new(
+                StatusCode::NOT_FOUND,
+                format!("illegal chart UID in `{}`", uri.path()),
+            )
+        })
+}
+
+/// Parses the `since` parameter of the query of a points request, if any.
+fn since(uri: &Uri) -> ApiRes<Option<time::SinceStart>> {
+    let query = uri.query().unwrap_or("");
+    for param in query.split('&') {
+        let mut key_val = param.splitn(2, '=');
+        if key_val.next() != Some("since") {
+            continue;
+        }
+        let val = key_val.next().unwrap_or("");
+        return match f64::from_str(val) {
+            Ok(secs) if secs.is_finite() && secs >= 0.0 => Ok(Some(time::SinceStart::from(
+                time::Duration::from_secs_f64(secs),
+            ))),
+            _ => Err(Failure::new(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "expected a positive number of seconds for `since`, got `{}`",
+                    val
+                ),
+            )),
+        };
+    }
+    Ok(None)
+}
+
+/// Specs of the charts.
+fn chart_specs() -> ApiRes<Vec<charts::chart::ChartSpec>> {
+    charts_do(|charts| {
+        Ok(charts
+            .charts()
+            .iter()
+            .map(|chart| chart.spec().clone())
+            .collect())
+    })
+}
+
+/// Points of a chart, at least `since` seconds after the start of the run if any.
+fn chart_points(
+    uid: uid::Chart,
+    since: Option<time::SinceStart>,
+) -> ApiRes<Option<charts::point::Points>> {
+    let (known, points) = charts_do(|charts| {
+        if charts.charts().iter().all(|chart| chart.uid() != uid) {
+            return Ok((false, None));
+        }
+        let (mut points, _) = charts.new_points(true)?;
+        Ok((true, points.remove(&uid)))
+    })?;
+    if !known {
+        return Err(Failure::new(
+            StatusCode::NOT_FOUND,
+            format!("unknown chart #{}", uid),
+        ));
+    }
+    Ok(points.map(|mut points| {
+        if let Some(since) = since {
+            let _ = points.drop_before(since);
+        }
+        points
+    }))
+}
+
+/// Specs of the filters, the everything filter first and the catch-all filter last.
+fn filter_specs() -> ApiRes<Vec<charts::filter::FilterSpec>> {
+    charts_do(|charts| {
+        let filters = charts.filters();
+        let mut specs = vec![filters.everything().clone()];
+        specs.extend(filters.filters().iter().map(|filter| filter.spec().clone()));
+        specs.push(filters.catch_all().clone());
+        Ok(specs)
+    })
+}
+
+/// Statistics of the filters.
+///
+/// Filters only know about the allocations the charts went through, so the charts first catch up
+/// with the data.
+fn filter_stats() -> ApiRes<charts::filter::stats::AllFilterStats> {
+    charts_do(|charts| {
+        let _ = charts.new_points(false)?;
+        charts.filters().filter_stats()
+    })
+}
+
+/// Builds the response to a request.
+///
+/// The body of failed requests is the description of the error, as plain text.
+fn respond<T: ::serde::Serialize>(state: State, res: ApiRes<T>) -> (State, Response<Body>) {
+    let json = res.and_then(|val| {
+        serde_json::to_string(&val)
+            .chain_err(|| "while serializing the response")
+            .map_err(Failure::from)
+    });
+    let (status, body, content_type) = match json {
+        Ok(json) => (StatusCode::OK, json, "application/json"),
+        Err(Failure { status, error }) => {
+            log::warn!("api request `{}` failed", Uri::borrow_from(&state));
+            (status, error.to_pretty(), "text/plain; charset=utf-8")
+        }
+    };
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    (state, response)
+}
+
+/// Functions answering API requests.
+pub mod handlers {
+    use super::*;
+
+    /// Answers `GET /api/charts`.
+    pub fn charts(state: State) -> (State, Response<Body>) {
+        let res = chart_specs();
+        respond(state, res)
+    }
+    /// Answers `GET /api/charts/<uid>/points?since=<secs>`.
+    pub fn points(state: State) -> (State, Response<Body>) {
+        let res = {
+            let uri = Uri::borrow_from(&state);
+            chart_uid(uri).and_then(|uid| chart_points(uid, since(uri)?))
+        };
+        respond(state, res)
+    }
+    /// Answers `GET /api/filters`.
+    pub fn filters(state: State) -> (State, Response<Body>) {
+        let res = filter_specs();
+        respond(state, res)
+    }
+    /// Answers `GET /api/stats`.
+    pub fn stats(state: State) -> (State, Response<Body>) {
+        let res = filter_stats();
+        respond(state, res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use gotham::test::TestServer;
+
+    use super::*;
+
+    /// Loads the test trace, once for all the tests.
+    fn load_trace() {
+        static LOAD: std::sync::Once = std::sync::Once::new();
+        LOAD.call_once(|| {
+            let trace = concat!(env!("CARGO_MANIFEST_DIR"), "/../rsc/dumps/ctf/mini_ae.ctf");
+            charts::data::start(trace).unwrap()
+        });
+        while charts::data::progress::get().unwrap().is_some() {
+            std::thread::sleep(time::Duration::from_millis(50))
+        }
+    }
+
+    /// Queries the API, yields the status and the body of the response.
+    fn get(path: &str) -> (StatusCode, String) {
+        load_trace();
+        let server = TestServer::new(crate::router::new("/memthol", true)).unwrap();
+        let response = server
+            .client()
+            .get(format!("http://localhost/memthol/api/{}", path))
+            .perform()
+            .unwrap();
+        let status = response.status();
+        let body = response.read_utf8_body().unwrap();
+        (status, body)
+    }
+
+    #[test]
+    fn charts_and_points() {
+        let (status, body) = get("charts");
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let specs: Vec<charts::chart::ChartSpec> = serde_json::from_str(&body).unwrap();
+        let uid = specs.first().expect("the API has no charts").uid();
+
+        let (status, body) = get(&format!("charts/{}/points", uid));
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let all: Option<charts::point::Points> = serde_json::from_str(&body).unwrap();
+        let all = all.expect("the chart has no points");
+        assert!(!all.is_empty());
+
+        let (status, body) = get(&format!("charts/{}/points?since=1000000", uid));
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let late: Option<charts::point::Points> = serde_json::from_str(&body).unwrap();
+        assert!(late.map(|points| points.is_empty()).unwrap_or(true));
+
+        let (status, _) = get(&format!("charts/{}/points?since=yesterday", uid));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get("charts/7777777/points");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get("charts/chart/points");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn filters_and_stats() {
+        let (status, body) = get("filters");
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let specs: Vec<charts::filter::FilterSpec> = serde_json::from_str(&body).unwrap();
+        assert!(specs.len() >= 2);
+        assert!(specs[0].uid().is_everything());
+
+        let (status, body) = get("stats");
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let stats: charts::filter::stats::AllFilterStats = serde_json::from_str(&body).unwrap();
+        let everything = stats
+            .get(uid::Line::Everything)
+            .expect("no statistics for the everything filter");
+        assert!(everything.alloc_count > 0);
+    }
+}
@@ -36,3 +36,34 @@ pub static CLIENT_JS: &[u8] = include_bytes!(concat!(
     base::client_wasm_build_dir!(),
     "/client.js"
 ));
+
+base::prelude::lazy_static! {
+    /// Landing page with the configuration of the server, see [`set_config`].
+    ///
+    /// [`set_config`]: fn.set_config.html (The set_config function)
+    static ref INDEX_PAGE: std::sync::RwLock<String> = std::sync::RwLock::new(String::new());
+}
+
+/// Fills the landing page with the configuration of the server, see [`ServerConfig::fill_index`].
+///
+/// Must be called before the UI is served.
+///
+/// [`ServerConfig::fill_index`]: ../../charts/msg/config/struct.ServerConfig.html#method.fill_index
+/// (The fill_index method of ServerConfig)
+pub fn set_config(config: &charts::msg::config::ServerConfig) -> base::prelude::Res<()> {
+    use base::prelude::*;
+    let index = std::str::from_utf8(INDEX).chain_err(|| "the landing page is not legal utf-8")?;
+    *INDEX_PAGE
+        .write()
+        .map_err(|e| err::Error::from(e.to_string()))
+        .chain_err(|| "while setting the landing page")? = config.fill_index(index);
+    Ok(())
+}
+
+/// Landing page, with the configuration of the server.
+pub fn index_page() -> String {
+    match INDEX_PAGE.read() {
+        Ok(page) => page.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
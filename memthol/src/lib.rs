@@ -27,7 +27,8 @@
 //! - monitoring the files in the user-provided dump directory;
 //! - organising the data from the diffs;
 //! - answer browser's queries by sending them the client;
-//! - maintain one session per client that performs whatever treatment the user requests.
+//! - maintain one session per client that performs whatever treatment the user requests;
+//! - optionally, serve a read-only [HTTP API] for scripts.
 //!
 //! The documentation for the server is the present document. The client's crate is in the
 //! `./libs/client` from the root of the repository.
@@ -44,12 +45,14 @@
 //!
 //! [`alloc_data` crate]: ../alloc_data/index.html (Memthol's alloc_data crate)
 //! [`charts` crate]: ../charts/index.html (Memthol's charts crate)
+//! [HTTP API]: api/index.html (The api module)
 
 #![deny(missing_docs)]
 
 #[macro_use]
 pub mod prelude;
 
+pub mod api;
 pub mod assets;
 pub mod msg;
 pub mod router;
@@ -118,6 +121,31 @@ impl ErrorHandler {
     }
 }
 
+/// Runs the active filter generator as soon as the data is loaded, in a separate thread.
+///
+/// Clients then receive the generated filters right away, instead of waiting for generation to
+/// run when they connect. Failures are not fatal: clients run the generator themselves then.
+pub fn spawn_filter_gen() {
+    std::thread::spawn(|| {
+        let res = (|| -> Res<()> {
+            while charts::data::progress::get()?.is_some() {
+                std::thread::sleep(time::Duration::from_millis(200))
+            }
+            let charts = time! {
+                Charts::auto_gen_shared(&mut charts::filter::gen::GenCxt::silent())?,
+                |time| log::info!("done with startup filter generation in {}", time)
+            };
+            if let Some(charts) = charts {
+                log::info!("generated {} filter(s)", charts.filters().len())
+            }
+            Ok(())
+        })();
+        if let Err(e) = res.chain_err(|| "during startup filter generation") {
+            err::register_non_fatal(e)
+        }
+    });
+}
+
 /// CLAP-related actions.
 pub mod clap {
     use crate::prelude::*;
@@ -145,4 +173,13 @@ pub mod clap {
             std::process::exit(code)
         }
     }
+
+    /// Handles the CLAs related to location-resolution failures.
+    pub fn unresolved_locs(strict: bool, policy: &str) {
+        charts::data::set_strict(strict);
+        match charts::filter::loc::UnresolvedPolicy::from_cla(policy) {
+            Ok(policy) => charts::filter::loc::set_unresolved_policy(policy),
+            Err(e) => err::register_fatal(e),
+        }
+    }
 }
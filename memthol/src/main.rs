@@ -29,6 +29,15 @@ mod default {
     /// Default filter gen parameter.
     pub const FILTER_GEN: &str = "alloc_site";
 
+    /// Default unresolved-location policy.
+    pub const UNRESOLVED_LOCS: &str = "never";
+
+    /// Default input format.
+    pub const FORMAT: &str = "auto";
+
+    /// Default color palette.
+    pub const PALETTE: &str = "hues";
+
     /// Default address.
     pub const ADDR: &str = "localhost";
     /// Default port.
@@ -36,6 +45,9 @@ mod default {
 
     /// Default directory.
     pub const INPUT: &str = ".";
+
+    /// Default session file.
+    pub const SESSION_FILE: &str = "session.json";
 }
 
 /// Fails if the input string is not a `usize`.
@@ -48,6 +60,13 @@ fn usize_validator(s: String) -> Result<(), String> {
     }
 }
 
+/// Fails if the input string is not a legal base path.
+fn base_path_validator(s: String) -> Result<(), String> {
+    charts::msg::config::ServerConfig::normalize_base_path(&s)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
 /// Initializes the logger.
 fn init_logger(verb: u64) {
     let mut builder = pretty_env_logger::formatted_timed_builder();
@@ -88,14 +107,95 @@ pub fn main() {
             -l --log !required
             "activates (separate) socket logging"
         )
+        (@arg READ_ONLY:
+            --readonly !required
+            "makes all sessions read-only: clients cannot edit filters or charts"
+        )
+        (@arg MAX_FILTERS:
+            --max_filters +takes_value !required
+            { usize_validator }
+            "maximum number of filters, creating more filters is rejected (default 200)"
+        )
+        (@arg MAX_CHARTS:
+            --max_charts +takes_value !required
+            { usize_validator }
+            "maximum number of charts, creating more charts is rejected (default 40)"
+        )
+        (@arg SCATTER_SAMPLE:
+            --scatter_sample +takes_value !required
+            { usize_validator }
+            "maximum number of allocations scatter charts show for each filter (default 500)"
+        )
+
+        (@arg SESSION_FILE:
+            --("session-file") +takes_value !required
+            default_value(default::SESSION_FILE)
+            "file the charts and filters of the clients are saved to, \
+            each time a client saves its filters or changes its charts"
+        )
+        (@arg RESTORE_SESSION:
+            --("restore-session") !required
+            "clients start from the charts and filters of <SESSION_FILE> if any, \
+            instead of the ones of the filter generator"
+        )
+
+        (@arg PALETTE:
+            --palette +takes_value !required
+            possible_value[hues okabe_ito viridis]
+            default_value(default::PALETTE)
+            "palette the colors of new filters are picked from, \
+            `okabe_ito` and `viridis` are colorblind-safe"
+        )
 
         // Filter-gen stuff.
         (@arg FILTER_GEN:
             --filter_gen +takes_value !required
+            visible_alias("filter-gen")
             default_value(default::FILTER_GEN)
             "filter generation heuristic, get help with `--filter_gen help`"
         )
 
+        // Parsing stuff.
+        (@arg STRICT:
+            --strict !required
+            "fail on unknown or conflicting location codes instead of flagging allocations"
+        )
+        (@arg UNRESOLVED_LOCS:
+            --unresolved_locs +takes_value !required
+            possible_value[never all]
+            default_value(default::UNRESOLVED_LOCS)
+            "whether location filters match allocations with unresolved locations"
+        )
+        (@arg FORMAT:
+            --format +takes_value !required
+            possible_value[auto ctf massif]
+            default_value(default::FORMAT)
+            "format of <INPUT> when it is a file, `auto` loads files named `massif.out.*` or \
+            `*.massif` as massif profiles and the others as memtrace CTF files"
+        )
+        (@arg QUICK:
+            --quick +takes_value !required
+            { usize_validator }
+            "only loads the allocations of one packet in <QUICK> of a CTF file, \
+            everything can be loaded later from the UI"
+        )
+        (@arg PROGRESS_PACKETS:
+            --progress_packets +takes_value !required
+            { usize_validator }
+            "number of CTF packets parsed between two loading progress reports (default 1024)"
+        )
+        (@arg NO_CACHE:
+            --("no-cache") !required
+            "neither reads nor writes the parse cache of CTF files"
+        )
+        (@arg PRUNE:
+            --prune +takes_value !required
+            { usize_validator }
+            "drops the allocations dead for more than <PRUNE> seconds to bound memory usage, \
+            only the total size, count, rate and churn charts are available, lifetime filters, \
+            explanations, allocation tables and snapshot diffs are refused"
+        )
+
         // Server-related stuff.
 
         (@arg ADDR:
@@ -107,7 +207,18 @@ pub fn main() {
             -p --port +takes_value !required
             default_value(default::PORT)
             { usize_validator }
-            "the port to serve the UI at"
+            "the port to serve the UI at, the websocket is served at <PORT> + 1"
+        )
+        (@arg API:
+            --api !required
+            "serves a read-only JSON API under <BASE_PATH>/api, \
+            to extract the data of the charts without a browser"
+        )
+        (@arg BASE_PATH:
+            --("base-path") +takes_value !required
+            { base_path_validator }
+            "URL prefix to serve the UI and the websocket under, for reverse proxies: \
+            the websocket is at <BASE_PATH>/ws"
         )
 
         // Directory or CTF file.
@@ -115,7 +226,8 @@ pub fn main() {
         (@arg INPUT:
             !required
             default_value(default::INPUT)
-            "path to either a directory containing memthol's dump files, or a memtrace CTF file"
+            "path to either a directory containing memthol's dump files, a memtrace CTF file, \
+            or a massif profile"
         )
     )
     .get_matches();
@@ -126,8 +238,19 @@ pub fn main() {
         let port = matches.value_of("PORT").expect("argument with default");
         usize::from_str(port).expect("argument with validator")
     };
+    let config = {
+        let base_path = matches.value_of("BASE_PATH").unwrap_or("");
+        charts::msg::config::ServerConfig::new(port, port + 1, base_path)
+            .expect("argument with validator")
+    };
     let log = matches.occurrences_of("LOG") > 0;
     let open = matches.occurrences_of("OPEN") > 0;
+    let read_only = matches.occurrences_of("READ_ONLY") > 0;
+    let api = matches.occurrences_of("API") > 0;
+    let session_file = matches
+        .value_of("SESSION_FILE")
+        .expect("argument with default");
+    let restore_session = matches.occurrences_of("RESTORE_SESSION") > 0;
 
     let verb = matches.occurrences_of("VERB");
     init_logger(verb);
@@ -139,33 +262,162 @@ pub fn main() {
         .expect("argument with default");
     memthol::clap::filter_gen(filter_gen_args);
 
+    let strict = matches.occurrences_of("STRICT") > 0;
+    let unresolved_locs = matches
+        .value_of("UNRESOLVED_LOCS")
+        .expect("argument with default");
+    memthol::clap::unresolved_locs(strict, unresolved_locs);
+
+    let format = matches.value_of("FORMAT").expect("argument with default");
+    let format =
+        charts::data::InputFormat::from_name(format).expect("argument with possible values");
+    charts::data::set_input_format(format);
+
+    let quick = matches.value_of("QUICK").map(|sampling| {
+        use std::str::FromStr;
+        usize::from_str(sampling).expect("argument with validator")
+    });
+    if let Some(sampling) = quick {
+        charts::data::set_packet_sampling(sampling)
+    }
+
+    let progress_packets = matches.value_of("PROGRESS_PACKETS").map(|packets| {
+        use std::str::FromStr;
+        usize::from_str(packets).expect("argument with validator")
+    });
+    if let Some(packets) = progress_packets {
+        charts::data::progress::set_every_packets(packets)
+    }
+
+    let no_cache = matches.occurrences_of("NO_CACHE") > 0;
+    charts::data::cache::set_enabled(!no_cache);
+
+    let prune = matches
+        .value_of("PRUNE")
+        .map(|secs| {
+            use std::str::FromStr;
+            u64::from_str(secs).expect("argument with validator")
+        })
+        .filter(|secs| *secs > 0);
+    charts::data::prune::set_horizon(prune.map(base::time::SinceStart::from_secs));
+
+    let limits = {
+        use std::str::FromStr;
+        let max = |arg: &str, default: usize| {
+            matches
+                .value_of(arg)
+                .map(|max| usize::from_str(max).expect("argument with validator"))
+                .unwrap_or(default)
+        };
+        charts::limits::Limits::new(
+            max("MAX_FILTERS", charts::limits::Limits::DEFAULT_FILTERS),
+            max("MAX_CHARTS", charts::limits::Limits::DEFAULT_CHARTS),
+        )
+    };
+    charts::limits::set(limits);
+
+    let scatter_sample = matches.value_of("SCATTER_SAMPLE").map(|size| {
+        use std::str::FromStr;
+        usize::from_str(size).expect("argument with validator")
+    });
+    if let Some(size) = scatter_sample {
+        charts::chart::scatter::set_sample_size(size)
+    }
+
+    let palette = matches.value_of("PALETTE").expect("argument with default");
+    let palette =
+        charts::color::Palette::from_name(palette).expect("argument with possible values");
+    charts::color::set_palette(palette);
+
     let path = format!("{}:{}", addr, port);
+    let url = format!("http://{}{}/", path, config.base_path());
     println!("|===| Starting");
-    println!("| url: http://{}", path);
+    println!("| url: {}", url);
     println!("| target: `{}`", target);
+    if read_only {
+        println!("| read-only sessions");
+    }
+    if format != charts::data::InputFormat::Auto {
+        println!("| {} input", format.name());
+    }
+    if strict {
+        println!("| strict location resolution");
+    }
+    if limits != charts::limits::Limits::default() {
+        println!(
+            "| at most {} filters and {} charts",
+            limits.filters, limits.charts
+        );
+    }
+    if restore_session {
+        println!("| restoring session from `{}`", session_file);
+    }
+    if let Some(size) = scatter_sample {
+        println!("| scatter charts sample {} allocations per filter", size);
+    }
+    if palette != charts::color::Palette::default() {
+        println!("| {} palette", palette);
+    }
+    if let Some(estimate) = quick.and_then(charts::prelude::Estimate::new) {
+        println!("| quick look, {}", estimate);
+    }
+    if no_cache {
+        println!("| parse cache disabled");
+    }
+    if let Some(secs) = prune {
+        println!("| pruning allocations dead for more than {} s", secs);
+    }
+    if !config.base_path().is_empty() {
+        println!("| websocket path: {}", config.ws_path());
+    }
+    if api {
+        println!("| api: {}api", url);
+    }
     println!("|===|");
     println!();
 
     error_handler.handle_new_errors();
 
-    let router = memthol::router::new();
+    base::unwrap_or! {
+        memthol::assets::set_config(&config), exit
+    }
+
+    let router = memthol::router::new(config.base_path(), api);
 
     log::info!("starting data monitoring");
     base::unwrap_or! {
         charts::data::start(target), exit
     }
 
+    base::unwrap_or! {
+        charts::session::start(session_file, restore_session), exit
+    }
+
+    log::info!("starting filter generation");
+    memthol::spawn_filter_gen();
+
     error_handler.handle_new_errors();
 
     log::info!("starting socket listeners");
     base::unwrap_or! {
-        memthol::socket::spawn_server(addr, port + 1, log), exit
+        memthol::socket::spawn_server(addr, &config, log, read_only), exit
+    }
+
+    // Gotham panics when it cannot bind, check the address is available for a clear error. The
+    // listener is dropped right away so that gotham can bind.
+    base::unwrap_or! {
+        std::net::TcpListener::bind(&path)
+            .map(|_listener| ())
+            .map_err(|e| {
+                base::err::Error::from(format!("while binding the UI server at `{}`: {}", path, e))
+            }),
+        exit
     }
 
     error_handler.handle_new_errors();
 
     if open {
-        open_in_background(&path)
+        open_in_background(&url)
     }
 
     log::info!("starting gotham server");
@@ -174,8 +426,8 @@ pub fn main() {
     error_handler.error_watch_loop()
 }
 
-fn open_in_background(path: &str) {
-    let path = format!("http://{}", path);
+fn open_in_background(url: &str) {
+    let path = url.to_string();
     std::thread::spawn(move || match open::that(&path) {
         Ok(status) => {
             if !status.success() {
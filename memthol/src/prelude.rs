@@ -64,6 +64,9 @@ impl FromClient {
 
     /// Pushes a message.
     ///
+    /// A filter preview supersedes the previews not handled yet, which are dropped. Same for label
+    /// completion requests.
+    ///
     /// Fails if either
     ///
     /// - `self.close()` was called before, or
@@ -74,10 +77,32 @@ impl FromClient {
         if self.closed {
             bail!("receiving messages from a closed connection")
         }
+        if Self::is_preview(&msg) {
+            self.messages.retain(|msg| !Self::is_preview(msg))
+        }
+        if Self::is_label_completion(&msg) {
+            self.messages.retain(|msg| !Self::is_label_completion(msg))
+        }
         self.messages.push(msg);
         Ok(())
     }
 
+    /// True if a message is a filter preview.
+    fn is_preview(msg: &msg::from_client::Msg) -> bool {
+        match msg {
+            msg::from_client::Msg::Filters(msg) => msg.is_preview(),
+            _ => false,
+        }
+    }
+
+    /// True if a message is a label completion request.
+    fn is_label_completion(msg: &msg::from_client::Msg) -> bool {
+        match msg {
+            msg::from_client::Msg::Filters(msg) => msg.is_label_completion(),
+            _ => false,
+        }
+    }
+
     /// Drains all the messages.
     pub fn drain(&mut self) -> std::vec::Drain<msg::from_client::Msg> {
         self.messages.drain(0..)
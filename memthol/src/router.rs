@@ -33,7 +33,10 @@ pub mod handlers {
 
     /// Loads the index page.
     pub fn index_handler(state: State) -> (State, Response<Body>) {
-        (state, Response::new(Body::from(crate::assets::INDEX)))
+        (
+            state,
+            Response::new(Body::from(crate::assets::index_page())),
+        )
     }
     /// Loads the index page's favicon.
     pub fn favicon(state: State) -> (State, Response<Body>) {
@@ -62,15 +65,33 @@ pub mod handlers {
     }
 }
 
-/// Creates the router.
-pub fn new() -> Router {
+/// Creates the router, serving the assets under some base path.
+///
+/// The base path is empty or starts with a `/`, see [`ServerConfig::normalize_base_path`]. If
+/// `api`, the router also serves the [HTTP API] under `<base_path>/api`.
+///
+/// [HTTP API]: ../api/index.html (The api module)
+/// [`ServerConfig::normalize_base_path`]:
+/// ../../charts/msg/config/struct.ServerConfig.html#method.normalize_base_path
+/// (The normalize_base_path function of ServerConfig)
+pub fn new(base_path: &str, api: bool) -> Router {
     use gotham::router::builder::{build_simple_router, DefineSingleRoute, DrawRoutes};
     build_simple_router(|route| {
-        route.get("/").to(handlers::index_handler);
+        route.scope(base_path, |route| {
+            route.get("/").to(handlers::index_handler);
 
-        route.get("index.html").to(handlers::index_handler);
-        route.get("favicon.png").to(handlers::favicon);
-        route.get("client_bg.wasm").to(handlers::client_wasm);
-        route.get("client.js").to(handlers::client_js);
+            route.get("index.html").to(handlers::index_handler);
+            route.get("favicon.png").to(handlers::favicon);
+            route.get("client_bg.wasm").to(handlers::client_wasm);
+            route.get("client.js").to(handlers::client_js);
+
+            if api {
+                use crate::api::handlers;
+                route.get("api/charts").to(handlers::charts);
+                route.get("api/charts/:uid/points").to(handlers::points);
+                route.get("api/filters").to(handlers::filters);
+                route.get("api/stats").to(handlers::stats);
+            }
+        })
     })
 }
@@ -29,24 +29,48 @@ fn new_server(addr: &str, port: usize) -> Res<net::TcpListener> {
 }
 
 /// Spawns a `Handler` for each incoming connection request.
-fn handle_requests(log: bool, server: net::TcpListener) {
+///
+/// Each handler is created and runs in its own thread: creating a handler waits for the data to be
+/// loaded and generates filters, which must not delay the other connections.
+///
+/// If `read_only`, all sessions are read-only. Otherwise, sessions are read-only only if the client
+/// requests it, see [`Handler::new`].
+///
+/// [`Handler::new`]: struct.Handler.html#method.new (Handler::new)
+fn handle_requests(
+    log: bool,
+    read_only: bool,
+    config: charts::msg::config::ServerConfig,
+    server: net::TcpListener,
+) {
     for stream in server.incoming().filter_map(Result::ok) {
-        let mut handler = base::unwrap_or! {
-            Handler::new(log, stream).chain_err(|| "while creating request handler"),
-            {
-                log::error!("failed to start request handler");
-                return ()
-            }
-        };
-        std::thread::spawn(move || handler.run());
-        ()
+        let config = config.clone();
+        std::thread::spawn(move || {
+            let mut handler = base::unwrap_or! {
+                Handler::new(log, read_only, &config, stream)
+                    .chain_err(|| "while creating request handler"),
+                {
+                    log::error!("failed to start request handler");
+                    return ()
+                }
+            };
+            handler.run()
+        });
     }
 }
 
 /// Spawns the server that listens for connection requests.
-pub fn spawn_server(addr: &str, port: usize, log: bool) -> Res<()> {
-    let server = new_server(addr, port)?;
-    std::thread::spawn(move || handle_requests(log, server));
+///
+/// If `read_only`, all sessions are read-only.
+pub fn spawn_server(
+    addr: &str,
+    config: &charts::msg::config::ServerConfig,
+    log: bool,
+    read_only: bool,
+) -> Res<()> {
+    let server = new_server(addr, config.ws_port)?;
+    let config = config.clone();
+    std::thread::spawn(move || handle_requests(log, read_only, config, server));
     Ok(())
 }
 
@@ -73,13 +97,24 @@ pub struct Com {
     prof: Prof,
     /// Error context.
     err_cxt: err::ErrorCxt,
+    /// True if the client accepts compressed messages.
+    compress: bool,
 }
 impl Com {
     /// Constructor.
     ///
     /// The `log` flag, if `true`, makes the constructor create a log file in the current directory.
     /// It will contain a log of all the interactions with this client.
-    pub fn new(log: bool, ping_label: Vec<u8>, socket: net::WebSocket) -> Res<Self> {
+    ///
+    /// If `compress`, messages are sealed in [envelopes], which the client must accept.
+    ///
+    /// [envelopes]: ../../charts/msg/compress/struct.Envelope.html (The Envelope struct)
+    pub fn new(
+        log: bool,
+        ping_label: Vec<u8>,
+        socket: net::WebSocket,
+        compress: bool,
+    ) -> Res<Self> {
         let ping_msg = tungstenite::Message::Ping(ping_label);
 
         let ip = socket
@@ -108,6 +143,7 @@ impl Com {
             ping_msg,
             prof: Prof::new(),
             err_cxt: err::ErrorCxt::new(),
+            compress,
         })
     }
 
@@ -149,7 +185,11 @@ impl Com {
 
         let bytes = time! {
             > self.prof.bytes,
-            msg.to_bytes()
+            if self.compress {
+                msg.to_envelope_bytes()
+            } else {
+                msg.to_bytes()
+            }
         }?;
         log::trace!("sending binary message ({} bytes)", bytes.len());
         let msg = Message::Binary(bytes);
@@ -275,6 +315,22 @@ pub struct Handler {
     frame_span: time::Duration,
     /// Label for ping messages.
     ping_label: Vec<u8>,
+    /// True if the session is read-only, *i.e.* mutating messages are rejected.
+    read_only: bool,
+    /// Version of the notes the client knows about.
+    notes_version: u64,
+    /// Version of the tags the points of the client account for.
+    tags_version: u64,
+    /// Pruning epoch the points of the client account for.
+    prune_epoch: u64,
+    /// Status of the parse cache the client knows about.
+    cache_status: Option<charts::prelude::CacheStatus>,
+    /// Information about the run of the trace the client knows about.
+    trace_info: Option<charts::alloc_data::TraceInfo>,
+    /// Generation of the data the points of the client come from.
+    data_generation: u64,
+    /// Progress of the trace load the client knows about.
+    load_progress: Option<charts::prelude::LoadInfo>,
 
     instance_prof: HandlerProf,
     total_prof: HandlerProf,
@@ -283,8 +339,52 @@ pub struct Handler {
 
 impl Handler {
     /// Constructor from a request and a dump directory.
-    pub fn new(log: bool, stream: std::net::TcpStream) -> Res<Self> {
-        let socket = tungstenite::server::accept(stream).map_err(|e| e.to_string())?;
+    ///
+    /// The session is read-only if `read_only` is true, or if the URL of the websocket request has
+    /// a [`READ_ONLY_PARAM`] query parameter. This is decided once and for all here, clients
+    /// cannot change it afterwards.
+    ///
+    /// Messages to the client are compressed if the URL has a [`COMPRESS_PARAM`] query parameter.
+    /// Clients that do not ask for it receive plain messages.
+    ///
+    /// Requests for a path that is not a [websocket path] of the server are rejected.
+    ///
+    /// [`READ_ONLY_PARAM`]: ../../charts/msg/constant.READ_ONLY_PARAM.html
+    /// (READ_ONLY_PARAM constant)
+    /// [`COMPRESS_PARAM`]: ../../charts/msg/constant.COMPRESS_PARAM.html
+    /// (COMPRESS_PARAM constant)
+    /// [websocket path]: ../../charts/msg/config/struct.ServerConfig.html#method.is_ws_path
+    /// (The is_ws_path method of ServerConfig)
+    pub fn new(
+        log: bool,
+        read_only: bool,
+        config: &charts::msg::config::ServerConfig,
+        stream: std::net::TcpStream,
+    ) -> Res<Self> {
+        let mut read_only = read_only;
+        let mut compress = false;
+        let socket = tungstenite::server::accept_hdr(
+            stream,
+            |request: &tungstenite::handshake::server::Request, response| {
+                let path = request.uri().path();
+                if !config.is_ws_path(path) {
+                    let mut error =
+                        tungstenite::handshake::server::ErrorResponse::new(Some(format!(
+                            "no websocket at `{}`, expected `{}`",
+                            path,
+                            config.ws_path()
+                        )));
+                    *error.status_mut() = tungstenite::http::StatusCode::NOT_FOUND;
+                    return Err(error);
+                }
+                if let Some(query) = request.uri().query() {
+                    read_only = read_only || charts::msg::query_is_read_only(query);
+                    compress = charts::msg::query_accepts_compression(query)
+                }
+                Ok(response)
+            },
+        )
+        .map_err(|e| e.to_string())?;
 
         let instance_prof = HandlerProf::new();
         let total_prof = HandlerProf::new();
@@ -294,7 +394,7 @@ impl Handler {
         //     .chain_err(|| "while splitting the client into receive/send pair")?;
 
         let ping_label = vec![6u8, 6u8, 6u8];
-        let mut com = Com::new(log, ping_label.clone(), socket)
+        let mut com = Com::new(log, ping_label.clone(), socket, compress)
             .chain_err(|| "during communicator construction")?;
 
         com.send_errors()?;
@@ -319,15 +419,17 @@ impl Handler {
             }
         }
 
-        com.send(msg::to_client::Msg::DoneLoading)?;
-
-        let charts = time! {
-            Charts
-                ::auto_gen()
-                .chain_err(|| "during default filter generation")?,
-            |time| log::info!("done with filter generation in {}", time)
+        let charts = match Self::restore_charts()? {
+            Some(charts) => charts,
+            None => time! {
+                Self::gen_charts(&mut com, &ping_label)
+                    .chain_err(|| "during default filter generation")?,
+                |time| log::info!("done with filter generation in {}", time)
+            },
         };
 
+        com.send(msg::to_client::Msg::DoneLoading)?;
+
         let slf = Handler {
             com,
             charts,
@@ -335,17 +437,161 @@ impl Handler {
             last_frame: time::Instant::now(),
             frame_span: time::Duration::from_millis(500),
             ping_label,
+            read_only,
+            notes_version: 0,
+            tags_version: 0,
+            prune_epoch: charts::data::prune_epoch()?,
+            cache_status: None,
+            trace_info: None,
+            data_generation: charts::data::generation()?,
+            load_progress: None,
 
             instance_prof,
             total_prof,
             msgs: Vec::with_capacity(7),
         };
 
-        log::info!("successfully connected to {}", slf.ip());
+        log::info!(
+            "successfully connected to {}{}",
+            slf.ip(),
+            if slf.read_only { " (read-only)" } else { "" }
+        );
 
         Ok(slf)
     }
 
+    /// Runs filter generation, forwarding progress to the client.
+    ///
+    /// Generation runs in a separate thread, so that the client can cancel it with a
+    /// [`FiltersMsg::CancelGen`] message. Cancellation is atomic: the charts produced are then the
+    /// ones of the inactive generator, *i.e.* no filter is generated.
+    ///
+    /// Generation is instantaneous if the filters were already generated on the current data, see
+    /// [`spawn_filter_gen`].
+    ///
+    /// [`spawn_filter_gen`]: ../fn.spawn_filter_gen.html (The spawn_filter_gen function)
+    /// [`FiltersMsg::CancelGen`]: ../../charts/msg/to_server/enum.FiltersMsg.html#variant.CancelGen
+    /// (The CancelGen message)
+    fn gen_charts(com: &mut Com, ping_label: &[u8]) -> Res<Charts> {
+        use charts::filter::gen;
+
+        let token = gen::CancelToken::new();
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let worker = {
+            let token = token.clone();
+            std::thread::spawn(move || {
+                let mut cxt = gen::GenCxt::new(token, move |progress| {
+                    // Receiver is gone if the client disconnected, nothing to report then.
+                    let _ = sender.send(progress);
+                });
+                Charts::auto_gen_shared(&mut cxt)
+            })
+        };
+
+        loop {
+            // Only forward the latest progress information.
+            let (mut last, mut done) = (None, false);
+            loop {
+                match receiver.try_recv() {
+                    Ok(progress) => last = Some(progress),
+                    Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                        done = true;
+                        break;
+                    }
+                }
+            }
+            if let Some(progress) = last {
+                com.send(msg::to_client::Msg::gen_progress(progress))?
+            }
+            com.send_errors()?;
+            if done {
+                break;
+            }
+
+            // Check whether the client asked for cancellation.
+            com.send_ping()?;
+            match com.incoming_message()? {
+                net::Msg::Binary(data) => {
+                    let msg = msg::from_client::Msg::from_bytes(&data)
+                        .chain_err(|| "while parsing message from client")?;
+                    com.log_receive_msg(Either::Left(&msg))?;
+                    match msg {
+                        msg::from_client::Msg::Filters(msg::from_client::FiltersMsg::CancelGen) => {
+                            log::info!("client {} cancelled filter generation", com.ip());
+                            token.cancel()
+                        }
+                        msg => log::warn!("ignoring message during filter generation: {}", msg),
+                    }
+                }
+                net::Msg::Pong(label) => {
+                    if label != ping_label {
+                        bail!(
+                            "unexpected `Pong` label: expected {:?}, got {:?}",
+                            ping_label,
+                            label
+                        )
+                    }
+                }
+                net::Msg::Close(_) => {
+                    token.cancel();
+                    bail!(
+                        "client {} closed the connection during filter generation",
+                        com.ip()
+                    )
+                }
+                net::Msg::Text(_) => bail!(
+                    "trying to receive a message in text format, \
+                        only binary format is supported"
+                ),
+                net::Msg::Ping(label) => bail!(
+                    "unexpected `Ping({})` message",
+                    String::from_utf8_lossy(&label)
+                ),
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(200))
+        }
+
+        let charts = worker
+            .join()
+            .map_err(|_| err::Error::from("filter generation thread panicked"))??;
+
+        // The client may cancel after the generator is done, generation is atomic.
+        match charts {
+            Some(charts) if !token.is_cancelled() => Ok(charts),
+            _ => {
+                log::info!("filter generation was cancelled, falling back to no filters");
+                Ok(
+                    Charts::auto_gen_with(gen::FilterGen::Inactive, &mut gen::GenCxt::silent())?
+                        .expect("inactive filter generation cannot be cancelled"),
+                )
+            }
+        }
+    }
+
+    /// Charts of the session the server restores, if any, see the [`session`] module.
+    ///
+    /// A session that cannot be restored is ignored with a warning, the client then starts from
+    /// the filter generator.
+    ///
+    /// [`session`]: ../../charts/session/index.html (The session module)
+    fn restore_charts() -> Res<Option<Charts>> {
+        let session = match charts::session::restored()? {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+        match Charts::from_session(session) {
+            Ok(charts) => Ok(Some(charts)),
+            Err(e) => {
+                log::warn!("{}", e.to_pretty());
+                log::warn!("failed to restore the session, starting fresh");
+                Ok(None)
+            }
+        }
+    }
+
     /// The client's IP address.
     pub fn ip(&self) -> &net::IpAddr {
         self.com.ip()
@@ -427,8 +673,15 @@ impl Handler {
             debug_assert!(self.msgs.is_empty());
 
             // Handle the messages.
-            let mut send_stats = false;
+            let (mut send_stats, mut save_session) = (false, false);
             for msg in self.from_client.drain() {
+                if self.read_only && msg.is_mutating() {
+                    log::info!("rejecting message from read-only client: {}", msg);
+                    self.msgs.push(msg::to_client::Msg::read_only_reject(&msg));
+                    continue;
+                }
+                // Read-only clients do not get to overwrite the session of the others.
+                save_session = save_session || (!self.read_only && msg.changes_session());
                 log::debug!("handling message from client: {}", msg);
                 time! {
                     {
@@ -442,7 +695,18 @@ impl Handler {
                 }
             }
 
+            if save_session {
+                self.save_session()?
+            }
+
             self.send_all()?;
+            self.sync_notes()?;
+            self.sync_data()?;
+            self.sync_tags()?;
+            self.sync_pruned()?;
+            self.sync_cache()?;
+            self.sync_trace_info()?;
+            self.sync_progress()?;
 
             if send_stats {
                 self.send_stats()?
@@ -463,8 +727,33 @@ impl Handler {
 
     /// Sends chart-related statistics to the client.
     fn send_stats(&mut self) -> Res<()> {
+        self.cache_status = charts::prelude::AllocStats::get()?.and_then(|stats| stats.cache);
         self.com.send_stats(&self.charts)
     }
+    /// Sends the statistics to the client if the status of the parse cache changed.
+    ///
+    /// The cache is written in the background, usually after the client received the last points.
+    fn sync_cache(&mut self) -> Res<()> {
+        if charts::prelude::AllocStats::get()?.and_then(|stats| stats.cache) != self.cache_status {
+            self.send_stats()?
+        }
+        Ok(())
+    }
+
+    /// Writes the session of the client, and sends the new status of the session file.
+    ///
+    /// Failing to write the session is not fatal, the client is alerted.
+    fn save_session(&mut self) -> Res<()> {
+        let saved = charts::session::save(self.charts.session());
+        if let Err(e) = saved {
+            log::warn!("{}", e.to_pretty());
+            self.msgs
+                .push(msg::to_client::Msg::alert(e.to_pretty(), false))
+        }
+        self.msgs
+            .push(msg::to_client::Msg::session(charts::session::status()?));
+        Ok(())
+    }
 
     /// Sends all charts to the client.
     fn send_all_charts(&mut self) -> Res<()> {
@@ -515,6 +804,11 @@ impl Handler {
 
     /// Initializes a client.
     pub fn init(&mut self) -> Res<()> {
+        if self.read_only {
+            self.send(msg::to_client::Msg::ReadOnly)?
+        }
+        self.send(msg::to_client::Msg::limits(charts::limits::get()))?;
+        self.send(msg::to_client::Msg::session(charts::session::status()?))?;
         self.send_stats()?;
 
         self.send_filters()
@@ -523,6 +817,12 @@ impl Handler {
             .chain_err(|| "while sending charts for client init")?;
         self.send_points(true)
             .chain_err(|| "while sending points for client init")?;
+        self.send_notes(false)
+            .chain_err(|| "while sending notes for client init")?;
+        self.send_tags()
+            .chain_err(|| "while sending tags for client init")?;
+        self.send_trace_info()
+            .chain_err(|| "while sending trace info for client init")?;
 
         Ok(())
     }
@@ -541,11 +841,106 @@ impl Handler {
     /// Sends all its internal messages to the client.
     pub fn send_all(&mut self) -> Res<()> {
         for msg in self.msgs.drain(0..) {
+            if let msg::to_client::Msg::Notes { notes, .. } = &msg {
+                self.notes_version = notes.version
+            }
             self.com.send(msg)?
         }
         Ok(())
     }
 
+    /// Sends the notes of the server to the client.
+    fn send_notes(&mut self, conflict: bool) -> Res<()> {
+        let notes = charts::notes::get()?;
+        self.notes_version = notes.version;
+        self.send(msg::to_client::Msg::notes(notes, conflict))
+    }
+    /// Sends the notes of the server to the client if another client changed them.
+    fn sync_notes(&mut self) -> Res<()> {
+        if charts::notes::version()? != self.notes_version {
+            self.send_notes(false)?
+        }
+        Ok(())
+    }
+
+    /// Sends the tags of the server to the client.
+    fn send_tags(&mut self) -> Res<()> {
+        self.tags_version = charts::data::tags_version()?;
+        self.send(msg::to_client::Msg::tags(charts::data::tags()?))
+    }
+    /// Reloads the points and sends the tags to the client if the tags changed.
+    ///
+    /// Tags change the labels of allocations, so all the points must be recomputed.
+    fn sync_tags(&mut self) -> Res<()> {
+        if charts::data::tags_version()? != self.tags_version {
+            self.send_tags()?;
+            let points = self.charts.reload_points(None, true)?;
+            self.send(points)?;
+            self.send_stats()?
+        }
+        Ok(())
+    }
+
+    /// Prunes the old dead allocations, and forgets the filters that matched the ones pruned.
+    ///
+    /// The points of the client need not reload: the charts replay pruned allocations where their
+    /// events were, and refuse what cannot replay them.
+    fn sync_pruned(&mut self) -> Res<()> {
+        let _ = self.charts.prune()?;
+        let epoch = charts::data::prune_epoch()?;
+        if epoch != self.prune_epoch {
+            self.prune_epoch = epoch;
+            self.charts.forget_pruned()?
+        }
+        Ok(())
+    }
+
+    /// Wipes the points of the client if the server replaced its data, *e.g.* by switching to
+    /// another trace.
+    ///
+    /// Also sends the information about the run of the new trace. The points of the new data are
+    /// sent with the next frame, once the charts notice the new data.
+    fn sync_data(&mut self) -> Res<()> {
+        let generation = charts::data::generation()?;
+        if generation != self.data_generation {
+            self.data_generation = generation;
+            self.send(msg::to_client::ChartsMsg::new_points(
+                charts::point::ChartPoints::new(),
+                true,
+            ))?;
+            self.send_trace_info()?
+        }
+        Ok(())
+    }
+
+    /// Sends the information about the run of the trace to the client.
+    fn send_trace_info(&mut self) -> Res<()> {
+        self.trace_info = charts::data::trace_info()?;
+        self.send(msg::to_client::Msg::trace_info(self.trace_info.clone()))
+    }
+    /// Sends the information about the run of the trace if the server loaded a different trace.
+    fn sync_trace_info(&mut self) -> Res<()> {
+        if charts::data::trace_info()? != self.trace_info {
+            self.send_trace_info()?
+        }
+        Ok(())
+    }
+
+    /// Forwards the progress of a trace load to the client, see [`charts::data::load_trace`].
+    ///
+    /// The client is told the load is over once the progress goes away.
+    fn sync_progress(&mut self) -> Res<()> {
+        let progress = charts::data::progress::get()?;
+        if progress != self.load_progress {
+            match progress.clone() {
+                Some(info) => self.send(msg::to_client::Msg::load_progress(info))?,
+                None => self.send(msg::to_client::Msg::DoneLoading)?,
+            }
+            self.load_progress = progress
+        }
+        Ok(())
+    }
+
     /// Retrieves actions to perform from the client before rendering.
     ///
     /// Returns `None` if the client requested to close
@@ -553,11 +948,15 @@ impl Handler {
         for message in self.com.incoming_message() {
             // Let's do this.
             match message {
+                // JSON message from the client, for debugging.
+                net::Msg::Text(text) => {
+                    let msg = msg::from_client::Msg::from_json_bytes(text.as_bytes())
+                        .chain_err(|| "while parsing JSON message from client")?;
+                    self.com.log_receive_msg(Either::Left(&msg))?;
+                    log::info!("received JSON message from client: {}", msg);
+                    self.from_client.push(msg)?
+                }
                 // Normal message(s) from the client.
-                net::Msg::Text(_) => bail!(
-                    "trying to receive a message in text format, \
-                        only binary format is supported"
-                ),
                 net::Msg::Binary(data) => {
                     let msg = msg::from_client::Msg::from_bytes(&data)
                         .chain_err(|| "while parsing message from client")?;